@@ -1,6 +1,10 @@
 use crate::*;
+use near_sdk::json_types::Base64VecU8;
 use std::collections::HashSet;
 
+/// The maximum number of accounts returned by `get_accounts_detailed`.
+pub const MAX_NUM_DETAILED_ACCOUNTS: usize = 100;
+
 #[derive(BorshSerialize, BorshDeserialize, Serialize)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Account {
@@ -13,9 +17,15 @@ pub struct Account {
     pub collateral: HashMap<TokenId, Shares>,
     /// A list of borrowed assets.
     pub borrowed: HashMap<TokenId, Shares>,
-    /// Keeping track of data required for farms for this account.
+    /// The farms of this account. The data of every farm is stored in a separate entry, so it's
+    /// only loaded when the farm is affected.
+    #[serde(skip_serializing)]
+    pub farm_ids: HashSet<FarmId>,
+    /// The farms changed by the current operation that are not saved yet, `None` for the removed
+    /// farms.
+    #[borsh_skip]
     #[serde(skip_serializing)]
-    pub farms: HashMap<FarmId, AccountFarm>,
+    pub changed_farms: HashMap<FarmId, Option<AccountFarm>>,
     #[borsh_skip]
     #[serde(skip_serializing)]
     pub affected_farms: HashSet<FarmId>,
@@ -27,29 +37,29 @@ pub struct Account {
 
     /// Staking of booster token.
     pub booster_staking: Option<BoosterStaking>,
+
+    /// The shards of the account as they were loaded, if the account is sharded.
+    #[borsh_skip]
+    #[serde(skip)]
+    pub shards: Option<AccountShards>,
+    /// The principals of the positions as they were loaded.
+    #[borsh_skip]
+    #[serde(skip)]
+    pub principals: AccountPrincipals,
+    /// The timestamp of the last time the account was saved, `None` if it was stored by an older
+    /// version.
+    #[borsh_skip]
+    #[serde(skip)]
+    pub last_action_timestamp: Option<Timestamp>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum VAccount {
     V0(AccountV0),
     V1(AccountV1),
-    Current(Account),
-}
-
-impl VAccount {
-    pub fn into_account(self, is_view: bool) -> Account {
-        match self {
-            VAccount::V0(c) => c.into_account(is_view),
-            VAccount::V1(c) => c.into_account(is_view),
-            VAccount::Current(c) => c,
-        }
-    }
-}
-
-impl From<Account> for VAccount {
-    fn from(c: Account) -> Self {
-        VAccount::Current(c)
-    }
+    V2(AccountV2),
+    Current(AccountCompact),
+    Sharded(AccountCore),
 }
 
 impl Account {
@@ -59,10 +69,14 @@ impl Account {
             supplied: HashMap::new(),
             collateral: HashMap::new(),
             borrowed: HashMap::new(),
-            farms: HashMap::new(),
+            farm_ids: HashSet::new(),
+            changed_farms: HashMap::new(),
             affected_farms: HashSet::new(),
             storage_tracker: Default::default(),
             booster_staking: None,
+            shards: None,
+            principals: Default::default(),
+            last_action_timestamp: None,
         }
     }
 
@@ -122,6 +136,19 @@ impl Account {
             .expect("Borrowed asset not found")
     }
 
+    /// Sets the farm of the account to be saved with the account.
+    pub fn set_farm(&mut self, farm_id: FarmId, account_farm: AccountFarm) {
+        self.farm_ids.insert(farm_id.clone());
+        self.changed_farms.insert(farm_id, Some(account_farm));
+    }
+
+    /// Removes the farm of the account once the account is saved.
+    pub fn remove_farm(&mut self, farm_id: &FarmId) {
+        if self.farm_ids.remove(farm_id) || !self.is_farm_ids_loaded() {
+            self.changed_farms.insert(farm_id.clone(), None);
+        }
+    }
+
     pub fn add_affected_farm(&mut self, farm_id: FarmId) -> bool {
         self.affected_farms.insert(farm_id)
     }
@@ -171,7 +198,7 @@ impl Contract {
     pub fn internal_get_account(&self, account_id: &AccountId, is_view: bool) -> Option<Account> {
         self.accounts
             .get(account_id)
-            .map(|o| o.into_account(is_view))
+            .map(|o| self.internal_vaccount_into_account(account_id, o, is_view))
     }
 
     pub fn internal_unwrap_account(&self, account_id: &AccountId) -> Account {
@@ -179,15 +206,46 @@ impl Contract {
             .expect("Account is not registered")
     }
 
-    pub fn internal_set_account(&mut self, account_id: &AccountId, mut account: Account) {
+    /// Returns the account for a view by its index in the accounts map.
+    pub fn internal_get_account_by_index(&self, index: u64) -> Account {
+        let account_id = self.accounts.keys_as_vector().get(index).unwrap();
+        let v_account = self.accounts.values_as_vector().get(index).unwrap();
+        self.internal_vaccount_into_account(&account_id, v_account, true)
+    }
+
+    pub fn internal_set_account(&mut self, account_id: &AccountId, account: Account) {
+        self.internal_save_account(account_id, account, true);
+    }
+
+    /// Saves the account from the callback that releases the account lock. The added storage is
+    /// charged without asserting that the storage balance covers it, because a failed callback
+    /// would lose its changes and leave the account locked. The account has to top up the
+    /// storage balance before it can add more storage.
+    pub fn internal_set_account_in_callback(&mut self, account_id: &AccountId, account: Account) {
+        self.internal_save_account(account_id, account, false);
+    }
+
+    fn internal_save_account(
+        &mut self,
+        account_id: &AccountId,
+        mut account: Account,
+        is_storage_asserted: bool,
+    ) {
         let mut storage = self.internal_unwrap_storage(account_id);
         storage
             .storage_tracker
             .consume(&mut account.storage_tracker);
+        // The shared token index is not paid by the account.
+        self.internal_index_account_tokens(&account);
         storage.storage_tracker.start();
-        self.accounts.insert(account_id, &account.into());
+        self.internal_prepare_account_shards(&mut account);
+        self.internal_save_account_farms(&mut account);
+        self.internal_update_asset_accounts(&account);
+        self.internal_record_borrowed_duration(&account);
+        self.internal_notify_partner(&account);
+        self.internal_save_account_record(account);
         storage.storage_tracker.stop();
-        self.internal_set_storage(account_id, storage);
+        self.internal_save_storage(account_id, storage, is_storage_asserted);
     }
 }
 
@@ -196,23 +254,91 @@ impl Contract {
     /// Returns detailed information about an account for a given account_id.
     /// The information includes all supplied assets, collateral and borrowed.
     /// Each asset includes the current balance and the number of shares.
-    pub fn get_account(&self, account_id: AccountId) -> Option<AccountDetailedView> {
-        self.internal_get_account(&account_id, true)
-            .map(|account| self.account_into_detailed_view(account))
+    /// If `with_values` is true, the USD values of the assets and the totals are also returned
+    /// based on the last recorded prices.
+    pub fn get_account(
+        &self,
+        account_id: AccountId,
+        with_values: Option<bool>,
+    ) -> Option<AccountDetailedView> {
+        self.internal_get_account(&account_id, true).map(|account| {
+            let mut view = self.account_into_detailed_view(account);
+            if with_values.unwrap_or(false) {
+                self.internal_fill_account_values(&mut view);
+            }
+            view
+        })
+    }
+
+    /// Returns the detailed information of the given accounts in the same order, like
+    /// `get_account`. The unregistered accounts are returned as `null`.
+    /// - Panics if more than `MAX_NUM_DETAILED_ACCOUNTS` accounts are requested.
+    pub fn get_accounts_detailed(
+        &self,
+        account_ids: Vec<AccountId>,
+        with_values: Option<bool>,
+    ) -> Vec<Option<AccountDetailedView>> {
+        assert!(
+            account_ids.len() <= MAX_NUM_DETAILED_ACCOUNTS,
+            "Too many accounts requested"
+        );
+        account_ids
+            .into_iter()
+            .map(|account_id| self.get_account(account_id, with_values))
+            .collect()
     }
 
     /// Returns limited account information for accounts from a given index up to a given limit.
     /// The information includes number of shares for collateral and borrowed assets.
     /// This method can be used to iterate on the accounts for liquidation.
     pub fn get_accounts_paged(&self, from_index: Option<u64>, limit: Option<u64>) -> Vec<Account> {
-        let values = self.accounts.values_as_vector();
+        let num_accounts = self.accounts.len();
         let from_index = from_index.unwrap_or(0);
-        let limit = limit.unwrap_or(values.len());
-        (from_index..std::cmp::min(values.len(), from_index + limit))
-            .map(|index| values.get(index).unwrap().into_account(true))
+        let limit = limit.unwrap_or(num_accounts);
+        (from_index..std::cmp::min(num_accounts, from_index + limit))
+            .map(|index| self.internal_get_account_by_index(index))
             .collect()
     }
 
+    /// Returns a page of limited account information starting from the given continuation token.
+    /// The page includes the total number of accounts and the token for the next page.
+    /// The token may become invalid if accounts are removed between the calls.
+    pub fn get_accounts_page(&self, token: Option<String>, limit: Option<u64>) -> Page<Account> {
+        Page::new(self.accounts.len(), token, limit, |index| {
+            self.internal_get_account_by_index(index)
+        })
+    }
+
+    /// Returns a page of detailed account views, like `get_account`, starting from the given
+    /// continuation token. The page ends early once the gas for the page is used, so the size of
+    /// the page can be less than the `limit` even if it's not the last page.
+    pub fn get_accounts_page_detailed(
+        &self,
+        token: Option<String>,
+        limit: Option<u64>,
+        with_values: Option<bool>,
+    ) -> Page<AccountDetailedView> {
+        let account_ids = self.accounts.keys_as_vector();
+        Page::new(account_ids.len(), token, limit, |index| {
+            self.get_account(account_ids.get(index).unwrap(), with_values)
+                .unwrap()
+        })
+    }
+
+    /// Returns the Borsh-serialized list of accounts from a given index up to a given limit.
+    /// The accounts are serialized as `Vec<Account>`. This method can be used by indexers to sync
+    /// the full state without the JSON serialization overhead.
+    pub fn get_accounts_raw(&self, from_index: Option<u64>, limit: Option<u64>) -> Base64VecU8 {
+        let num_accounts = self.accounts.len();
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(num_accounts);
+        let accounts: Vec<Account> = (from_index
+            ..std::cmp::min(num_accounts, from_index.saturating_add(limit)))
+            .map(|index| self.internal_get_account_by_index(index))
+            .collect();
+        accounts.try_to_vec().unwrap().into()
+    }
+
     /// Returns the number of accounts
     pub fn get_num_accounts(&self) -> u32 {
         self.accounts.len() as _
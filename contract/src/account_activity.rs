@@ -0,0 +1,105 @@
+use crate::*;
+use near_sdk::json_types::U128;
+
+/// The activity metrics of the account, e.g. for the incentive programs of partners. The storage
+/// is paid by the account.
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+pub struct AccountActivity {
+    /// The total amounts deposited from the wallet by token ID, in the inner decimals.
+    pub supplied_volume: HashMap<TokenId, Balance>,
+    /// The total time in nanoseconds with borrowed assets until the last interaction with the
+    /// account.
+    pub borrowed_duration: u64,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountActivityView {
+    pub account_id: AccountId,
+    pub supplied_volume: HashMap<TokenId, U128>,
+    /// The total time in seconds with borrowed assets, including the current borrowing.
+    pub borrowed_duration_sec: u64,
+    /// The number of the whole days with borrowed assets.
+    pub borrowed_days: u32,
+}
+
+impl Contract {
+    /// Adds the amount deposited from the wallet to the supplied volume of the account, if the
+    /// activity tracking is enabled.
+    pub fn internal_record_supplied_volume(
+        &mut self,
+        account: &mut Account,
+        token_id: &TokenId,
+        amount: Balance,
+    ) {
+        if !self.internal_config().activity_tracking_enabled {
+            return;
+        }
+        account.storage_tracker.start();
+        let mut activity = self
+            .account_activities
+            .get(&account.account_id)
+            .unwrap_or_default();
+        *activity
+            .supplied_volume
+            .entry(token_id.clone())
+            .or_default() += amount;
+        self.account_activities
+            .insert(&account.account_id, &activity);
+        account.storage_tracker.stop();
+    }
+
+    /// Adds the time since the last interaction to the borrowed duration of the account, if the
+    /// account had borrowed assets at the last interaction. Should be called before the account
+    /// is saved.
+    pub fn internal_record_borrowed_duration(&mut self, account: &Account) {
+        if !self.internal_config().activity_tracking_enabled {
+            return;
+        }
+        let duration = current_borrowed_duration(account);
+        if duration == 0 {
+            return;
+        }
+        let account_id = &account.account_id;
+        let mut activity = self.account_activities.get(account_id).unwrap_or_default();
+        activity.borrowed_duration += duration;
+        self.account_activities.insert(account_id, &activity);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the activity metrics of the account, so the partners can verify the activity for
+    /// their incentive programs. The metrics are only recorded while `activity_tracking_enabled`
+    /// is set in the config. Returns `None` if nothing was recorded for the account.
+    pub fn get_account_activity(&self, account_id: AccountId) -> Option<AccountActivityView> {
+        let activity = self.account_activities.get(&account_id)?;
+        let current_duration = self
+            .internal_get_account(&account_id, true)
+            .map(|account| current_borrowed_duration(&account))
+            .unwrap_or(0);
+        let borrowed_duration_sec = (activity.borrowed_duration + current_duration) / 10u64.pow(9);
+        Some(AccountActivityView {
+            account_id,
+            supplied_volume: activity
+                .supplied_volume
+                .into_iter()
+                .map(|(token_id, amount)| (token_id, amount.into()))
+                .collect(),
+            borrowed_duration_sec,
+            borrowed_days: (borrowed_duration_sec / (24 * 60 * 60)) as u32,
+        })
+    }
+}
+
+/// Returns the time since the last interaction if the account had borrowed assets at the last
+/// interaction, using the principals of the positions as they were loaded.
+fn current_borrowed_duration(account: &Account) -> u64 {
+    match account.last_action_timestamp {
+        Some(timestamp) if !account.principals.borrowed.is_empty() => {
+            env::block_timestamp().saturating_sub(timestamp)
+        }
+        _ => 0,
+    }
+}
@@ -0,0 +1,155 @@
+use crate::*;
+
+/// The index of a token in the shared token index. The account positions are stored by the
+/// token index instead of the token ID.
+pub type TokenIndex = u32;
+
+/// The shares and the principal of a position keyed by the token index.
+pub(crate) type CompactPositions = Vec<(TokenIndex, Shares, Balance)>;
+
+/// The persistent form of the account. The positions are stored with the fixed-width token
+/// indices instead of the token ID strings, and the account ID is taken from the key. The data
+/// of the farms is stored in separate entries.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AccountCompact {
+    pub supplied: CompactPositions,
+    pub collateral: CompactPositions,
+    pub borrowed: CompactPositions,
+    pub farm_ids: Vec<FarmId>,
+    pub booster_staking: Option<BoosterStaking>,
+    pub last_action_timestamp: Timestamp,
+}
+
+/// Returns the size of the positions in the compact encoding.
+pub(crate) fn compact_positions_bytes(positions: &HashMap<TokenId, Shares>) -> StorageUsage {
+    // The length prefix and the token index with the shares and the principal for every position.
+    4 + positions.len() as StorageUsage * (4 + 16 + 16)
+}
+
+impl Contract {
+    /// Returns the index of the token, adding the token to the shared token index if needed.
+    pub fn internal_token_index(&mut self, token_id: &TokenId) -> TokenIndex {
+        if let Some(index) = self.token_indices.get(token_id) {
+            return index;
+        }
+        let index = self.token_ids.len() as TokenIndex;
+        self.token_ids.push(token_id);
+        self.token_indices.insert(token_id, &index);
+        index
+    }
+
+    /// Adds the tokens of the positions of the account to the token index.
+    pub fn internal_index_account_tokens(&mut self, account: &Account) {
+        for token_id in account
+            .supplied
+            .keys()
+            .chain(account.collateral.keys())
+            .chain(account.borrowed.keys())
+        {
+            self.internal_token_index(token_id);
+        }
+    }
+
+    fn internal_unwrap_token_index(&self, token_id: &TokenId) -> TokenIndex {
+        self.token_indices
+            .get(token_id)
+            .expect("Token index is missing")
+    }
+
+    fn internal_unwrap_token_id(&self, index: TokenIndex) -> TokenId {
+        self.token_ids
+            .get(u64::from(index))
+            .expect("Token index is missing")
+    }
+
+    /// Converts the positions into the persistent form with their principals, see
+    /// `internal_position_principal`.
+    pub(crate) fn positions_into_compact(
+        &self,
+        positions: HashMap<TokenId, Shares>,
+        principals: &HashMap<TokenId, (Shares, Balance)>,
+        is_borrowing: bool,
+    ) -> CompactPositions {
+        let mut compact: CompactPositions = positions
+            .into_iter()
+            .map(|(token_id, shares)| {
+                let principal =
+                    self.internal_position_principal(principals, &token_id, shares, is_borrowing);
+                (
+                    self.internal_unwrap_token_index(&token_id),
+                    shares,
+                    principal,
+                )
+            })
+            .collect();
+        compact.sort_by_key(|(index, _, _)| *index);
+        compact
+    }
+
+    /// Reads the positions from the persistent form, adding their principals to the given ones.
+    pub(crate) fn positions_from_compact(
+        &self,
+        compact: CompactPositions,
+        principals: &mut HashMap<TokenId, (Shares, Balance)>,
+    ) -> HashMap<TokenId, Shares> {
+        compact
+            .into_iter()
+            .map(|(index, shares, principal)| {
+                let token_id = self.internal_unwrap_token_id(index);
+                principals.insert(token_id.clone(), (shares, principal));
+                (token_id, shares)
+            })
+            .collect()
+    }
+
+    /// Converts the account into the persistent form. The tokens of the account have to be added
+    /// to the token index first.
+    pub fn account_into_compact(&self, account: Account) -> AccountCompact {
+        let principals = &account.principals;
+        AccountCompact {
+            supplied: self.positions_into_compact(account.supplied, &principals.supplied, false),
+            collateral: self.positions_into_compact(
+                account.collateral,
+                &principals.collateral,
+                false,
+            ),
+            borrowed: self.positions_into_compact(account.borrowed, &principals.borrowed, true),
+            farm_ids: {
+                let mut farm_ids: Vec<_> = account.farm_ids.into_iter().collect();
+                farm_ids.sort();
+                farm_ids
+            },
+            booster_staking: account.booster_staking,
+            last_action_timestamp: env::block_timestamp(),
+        }
+    }
+
+    /// Reads the account from any of the persistent versions. The legacy versions are migrated to
+    /// the compact form once the account is saved again.
+    pub fn internal_vaccount_into_account(
+        &self,
+        account_id: &AccountId,
+        v_account: VAccount,
+        is_view: bool,
+    ) -> Account {
+        let compact = match v_account {
+            VAccount::V0(c) => return c.into_account(is_view),
+            VAccount::V1(c) => return c.into_account(is_view),
+            VAccount::V2(c) => return c.into(),
+            VAccount::Current(c) => c,
+            VAccount::Sharded(core) => {
+                return self.internal_sharded_account_from_core(account_id, core, is_view)
+            }
+        };
+        let mut account = Account::new(account_id);
+        let principals = &mut account.principals;
+        account.supplied = self.positions_from_compact(compact.supplied, &mut principals.supplied);
+        account.collateral =
+            self.positions_from_compact(compact.collateral, &mut principals.collateral);
+        account.borrowed = self.positions_from_compact(compact.borrowed, &mut principals.borrowed);
+        account.farm_ids = compact.farm_ids.into_iter().collect();
+        account.booster_staking = compact.booster_staking;
+        account.last_action_timestamp = Some(compact.last_action_timestamp);
+        account
+    }
+}
@@ -1,4 +1,5 @@
 use crate::*;
+use std::collections::HashSet;
 
 pub const NET_TVL_SHARES_DIVISOR: u128 = 10u128.pow(18);
 
@@ -41,7 +42,7 @@ impl AccountFarm {
 #[derive(BorshSerialize, BorshDeserialize, Clone)]
 pub struct AccountFarmReward {
     pub boosted_shares: Balance,
-    pub last_reward_per_share: BigDecimal,
+    pub last_reward_per_share: RewardPerShare,
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
@@ -64,6 +65,39 @@ impl From<AccountFarm> for VAccountFarm {
 }
 
 impl Contract {
+    /// Returns the farm of the account, loading it from the storage unless it's changed by the
+    /// current operation.
+    pub fn internal_get_account_farm(
+        &self,
+        account: &Account,
+        farm_id: &FarmId,
+    ) -> Option<AccountFarm> {
+        if let Some(account_farm) = account.changed_farms.get(farm_id) {
+            return account_farm.clone();
+        }
+        if !self.internal_is_account_in_farm(account, farm_id) {
+            return None;
+        }
+        self.account_farms
+            .get(&(account.account_id.clone(), farm_id.clone()))
+            .map(|o| o.into())
+    }
+
+    /// Saves the farms changed by the current operation to their entries.
+    pub fn internal_save_account_farms(&mut self, account: &mut Account) {
+        for (farm_id, account_farm) in account.changed_farms.drain() {
+            let key = (account.account_id.clone(), farm_id);
+            match account_farm {
+                Some(account_farm) => {
+                    self.account_farms.insert(&key, &account_farm.into());
+                }
+                None => {
+                    self.account_farms.remove(&key);
+                }
+            }
+        }
+    }
+
     pub fn internal_account_farm_claim(
         &self,
         account: &Account,
@@ -77,10 +111,8 @@ impl Contract {
         let mut new_rewards = vec![];
         let mut inactive_rewards = vec![];
         let block_timestamp = env::block_timestamp();
-        let mut account_farm: AccountFarm = account
-            .farms
-            .get(farm_id)
-            .cloned()
+        let mut account_farm: AccountFarm = self
+            .internal_get_account_farm(account, farm_id)
             .unwrap_or_else(AccountFarm::new);
         if account_farm.block_timestamp != block_timestamp {
             account_farm.block_timestamp = block_timestamp;
@@ -97,8 +129,8 @@ impl Contract {
                     last_reward_per_share,
                 }) = old_rewards.remove(token_id)
                 {
-                    let diff = reward_per_share.clone() - last_reward_per_share;
-                    let amount = diff.round_mul_u128(boosted_shares);
+                    let amount =
+                        reward_per_share.rewards_since(&last_reward_per_share, boosted_shares);
                     if amount > 0 {
                         new_rewards.push((token_id.clone(), amount));
                     }
@@ -110,7 +142,7 @@ impl Contract {
                     token_id.clone(),
                     AccountFarmReward {
                         boosted_shares,
-                        last_reward_per_share: reward_per_share.clone(),
+                        last_reward_per_share: *reward_per_share,
                     },
                 );
             }
@@ -124,11 +156,10 @@ impl Contract {
             {
                 let AssetFarmReward {
                     reward_per_share, ..
-                } = asset_farm
-                    .internal_get_inactive_asset_farm_reward(&token_id)
+                } = self
+                    .internal_get_inactive_asset_farm_reward(farm_id, &token_id)
                     .unwrap();
-                let diff = reward_per_share - last_reward_per_share;
-                let amount = diff.round_mul_u128(boosted_shares);
+                let amount = reward_per_share.rewards_since(&last_reward_per_share, boosted_shares);
                 inactive_rewards.push((token_id.clone(), boosted_shares));
                 if amount > 0 {
                     new_rewards.push((token_id, amount));
@@ -138,25 +169,169 @@ impl Contract {
         (account_farm, new_rewards, inactive_rewards)
     }
 
+    /// Returns the shares of the account in the given farm. The account doesn't join new farms
+    /// once it has reached the limit of farms.
+    fn internal_account_farm_shares(
+        &self,
+        config: &Config,
+        account: &Account,
+        farm_id: &FarmId,
+    ) -> Balance {
+        let is_over_limit = config
+            .max_num_farms_per_account
+            .is_some_and(|max_num_farms| {
+                self.internal_account_num_farms(account) >= max_num_farms as usize
+            });
+        if is_over_limit && !self.internal_is_account_in_farm(account, farm_id) {
+            return 0;
+        }
+        match farm_id {
+            FarmId::Supplied(token_id) => account.get_supplied_shares(token_id).0,
+            FarmId::Borrowed(token_id) => account.get_borrowed_shares(token_id).0,
+            FarmId::NetTvl => {
+                let mut total_supplied: BigDecimal = BigDecimal::zero();
+                let mut total_borrowed: BigDecimal = BigDecimal::zero();
+                for (token_id, price) in self.last_prices.iter() {
+                    let supplied_shares = account.get_supplied_shares(token_id);
+                    let borrowed_shares = account.get_borrowed_shares(token_id);
+                    if supplied_shares.0 > 0 || borrowed_shares.0 > 0 {
+                        let asset = self.internal_unwrap_asset(&token_id);
+
+                        if supplied_shares.0 > 0 {
+                            let amount = asset
+                                .supplied
+                                .shares_to_amount(supplied_shares, Rounding::Down);
+
+                            total_supplied = total_supplied
+                                + BigDecimal::from_balance_price(
+                                    amount,
+                                    price,
+                                    asset.config.extra_decimals,
+                                )
+                                .mul_ratio(asset.config.net_tvl_multiplier)
+                        }
+
+                        if borrowed_shares.0 > 0 {
+                            let amount = asset
+                                .borrowed
+                                .shares_to_amount(borrowed_shares, Rounding::Up);
+
+                            total_borrowed = total_borrowed
+                                + BigDecimal::from_balance_price(
+                                    amount,
+                                    price,
+                                    asset.config.extra_decimals,
+                                )
+                                .mul_ratio(asset.config.net_tvl_multiplier)
+                        }
+                    }
+                }
+                if total_supplied > total_borrowed {
+                    let net_supplied = total_supplied - total_borrowed;
+                    net_supplied.round_mul_u128(NET_TVL_SHARES_DIVISOR)
+                } else {
+                    0
+                }
+            }
+        }
+    }
+
+    /// Whether the boosted shares of the account in the farm would change by settling the farm.
+    /// The rewards of the unchanged farms keep accruing and are claimed lazily.
+    fn internal_is_account_farm_changed(
+        &self,
+        account: &Account,
+        farm_id: &FarmId,
+        asset_farm: &AssetFarm,
+        shares: Balance,
+        booster: (Balance, Balance),
+    ) -> bool {
+        match self.internal_get_account_farm(account, farm_id) {
+            None => shares > 0,
+            Some(account_farm) => {
+                shares == 0
+                    || account_farm.rewards.len() != asset_farm.rewards.len()
+                    || asset_farm
+                        .rewards
+                        .iter()
+                        .any(|(token_id, asset_farm_reward)| {
+                            account_farm.rewards.get(token_id).is_none_or(|reward| {
+                                reward.boosted_shares
+                                    != boosted_shares(
+                                        shares,
+                                        asset_farm_reward.booster_log_base,
+                                        booster,
+                                    )
+                            })
+                        })
+            }
+        }
+    }
+
+    /// Settles the affected farms of the account whose shares have changed. The rewards of the
+    /// other affected farms are claimed lazily, e.g. with `account_farm_claim_all`.
     pub fn internal_account_apply_affected_farms(&mut self, account: &mut Account) {
+        self.internal_account_settle_farms(account, false);
+    }
+
+    /// Settles all affected farms of the account and claims their rewards.
+    pub fn internal_account_claim_affected_farms(&mut self, account: &mut Account) {
+        self.internal_account_settle_farms(account, true);
+    }
+
+    fn internal_account_settle_farms(&mut self, account: &mut Account, claim_all: bool) {
         let config = self.internal_config();
         if account.affected_farms.is_empty() {
             return;
         }
         account.add_affected_farm(FarmId::NetTvl);
+        let booster_balance = account
+            .booster_staking
+            .as_ref()
+            .map(|b| b.x_booster_amount)
+            .unwrap_or(0);
+        let booster = (booster_balance, 10u128.pow(config.booster_decimals as u32));
         let mut all_rewards: HashMap<TokenId, Balance> = HashMap::new();
         let mut farms = vec![];
-        let mut farms_ids: Vec<_> = account.affected_farms.iter().cloned().collect();
-        while let Some(farm_id) = farms_ids.pop() {
-            if let Some(asset_farm) = self.internal_get_asset_farm(&farm_id, false) {
+        let mut settled_farm_ids = HashSet::new();
+        let mut farms_ids: Vec<_> = account
+            .affected_farms
+            .iter()
+            .map(|farm_id| (farm_id.clone(), claim_all))
+            .collect();
+        while let Some((farm_id, is_forced)) = farms_ids.pop() {
+            if settled_farm_ids.contains(&farm_id) {
+                continue;
+            }
+            if let Some(asset_farm) = self.internal_get_asset_farm(&farm_id) {
+                if !is_forced {
+                    let shares = self.internal_account_farm_shares(&config, account, &farm_id);
+                    if !self.internal_is_account_farm_changed(
+                        account,
+                        &farm_id,
+                        &asset_farm,
+                        shares,
+                        booster,
+                    ) {
+                        continue;
+                    }
+                }
+                settled_farm_ids.insert(farm_id.clone());
                 let (account_farm, new_rewards, inactive_rewards) =
                     self.internal_account_farm_claim(account, &farm_id, &asset_farm);
                 for (token_id, amount) in new_rewards {
+                    events::emit::farm_reward_claimed(
+                        &account.account_id,
+                        &farm_id,
+                        amount,
+                        &token_id,
+                    );
+                    // The claimed rewards change the supplied shares of the account.
                     let new_farm_id = FarmId::Supplied(token_id.clone());
                     *all_rewards.entry(token_id).or_default() += amount;
-                    if account.add_affected_farm(new_farm_id.clone()) {
-                        farms_ids.push(new_farm_id);
-                    }
+                    account.add_affected_farm(new_farm_id.clone());
+                    farms_ids.push((new_farm_id, true));
+                    farms_ids.push((FarmId::NetTvl, true));
                 }
                 farms.push((farm_id, account_farm, asset_farm, inactive_rewards));
             }
@@ -164,96 +339,55 @@ impl Contract {
         for (token_id, &reward) in &all_rewards {
             self.internal_deposit(account, &token_id, reward);
         }
-        let booster_balance = account
-            .booster_staking
-            .as_ref()
-            .map(|b| b.x_booster_amount)
-            .unwrap_or(0);
-        let booster_base = 10u128.pow(config.booster_decimals as u32);
 
         for (farm_id, mut account_farm, mut asset_farm, inactive_rewards) in farms {
-            let shares = match &farm_id {
-                FarmId::Supplied(token_id) => account.get_supplied_shares(token_id).0,
-                FarmId::Borrowed(token_id) => account.get_borrowed_shares(token_id).0,
-                FarmId::NetTvl => {
-                    let mut total_supplied: BigDecimal = BigDecimal::zero();
-                    let mut total_borrowed: BigDecimal = BigDecimal::zero();
-                    for (token_id, price) in self.last_prices.iter() {
-                        let supplied_shares = account.get_supplied_shares(token_id);
-                        let borrowed_shares = account.get_borrowed_shares(token_id);
-                        if supplied_shares.0 > 0 || borrowed_shares.0 > 0 {
-                            let asset = self.internal_unwrap_asset(&token_id);
-
-                            if supplied_shares.0 > 0 {
-                                let amount =
-                                    asset.supplied.shares_to_amount(supplied_shares, false);
-
-                                total_supplied = total_supplied
-                                    + BigDecimal::from_balance_price(
-                                        amount,
-                                        price,
-                                        asset.config.extra_decimals,
-                                    )
-                                    .mul_ratio(asset.config.net_tvl_multiplier)
-                            }
-
-                            if borrowed_shares.0 > 0 {
-                                let amount = asset.borrowed.shares_to_amount(borrowed_shares, true);
-
-                                total_borrowed = total_borrowed
-                                    + BigDecimal::from_balance_price(
-                                        amount,
-                                        price,
-                                        asset.config.extra_decimals,
-                                    )
-                                    .mul_ratio(asset.config.net_tvl_multiplier)
-                            }
-                        }
-                    }
-                    if total_supplied > total_borrowed {
-                        let net_supplied = total_supplied - total_borrowed;
-                        net_supplied.round_mul_u128(NET_TVL_SHARES_DIVISOR)
-                    } else {
-                        0
-                    }
-                }
-            };
+            let shares = self.internal_account_farm_shares(&config, account, &farm_id);
             for (token_id, asset_farm_reward) in asset_farm.rewards.iter_mut() {
                 let account_farm_reward = account_farm.rewards.get_mut(token_id).unwrap();
                 asset_farm_reward.boosted_shares -= account_farm_reward.boosted_shares;
                 if shares > 0 {
-                    let extra_shares = if asset_farm_reward.booster_log_base > 0
-                        && booster_balance > booster_base
-                    {
-                        let log_base =
-                            (asset_farm_reward.booster_log_base as f64) / (booster_base as f64);
-                        ((shares as f64)
-                            * ((booster_balance as f64) / (booster_base as f64)).log(log_base))
-                            as u128
-                    } else {
-                        0
-                    };
-                    account_farm_reward.boosted_shares = shares + extra_shares;
+                    account_farm_reward.boosted_shares =
+                        boosted_shares(shares, asset_farm_reward.booster_log_base, booster);
                     asset_farm_reward.boosted_shares += account_farm_reward.boosted_shares;
                 }
             }
             for (token_id, boosted_shares) in inactive_rewards {
-                let mut asset_farm_reward = asset_farm
-                    .internal_get_inactive_asset_farm_reward(&token_id)
+                let mut asset_farm_reward = self
+                    .internal_get_inactive_asset_farm_reward(&farm_id, &token_id)
                     .unwrap();
                 asset_farm_reward.boosted_shares -= boosted_shares;
-                asset_farm.internal_set_inactive_asset_farm_reward(&token_id, asset_farm_reward);
+                self.internal_set_inactive_asset_farm_reward(
+                    &farm_id,
+                    &token_id,
+                    asset_farm_reward,
+                );
             }
             self.internal_set_asset_farm(&farm_id, asset_farm);
             if shares > 0 {
-                account.farms.insert(farm_id, account_farm);
+                account.set_farm(farm_id, account_farm);
             } else {
-                account.farms.remove(&farm_id);
+                account.remove_farm(&farm_id);
             }
         }
     }
 }
 
+/// Returns the shares boosted by the given booster balance and the booster base.
+fn boosted_shares(
+    shares: Balance,
+    booster_log_base: Balance,
+    booster: (Balance, Balance),
+) -> Balance {
+    let (booster_balance, booster_base) = booster;
+    let extra_shares = if booster_log_base > 0 && booster_balance > booster_base {
+        let log_base = (booster_log_base as f64) / (booster_base as f64);
+        ((shares as f64) * ((booster_balance as f64) / (booster_base as f64)).log(log_base)) as u128
+    } else {
+        0
+    };
+    shares + extra_shares
+}
+
 #[near_bindgen]
 impl Contract {
     /// Claims all unclaimed farm rewards and starts farming new farms.
@@ -265,7 +399,39 @@ impl Contract {
         account
             .affected_farms
             .extend(account.get_all_potential_farms());
-        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_account_claim_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Claims the rewards of the given farm of the predecessor account and removes the account
+    /// from the farm, so the account can join another farm within the limit of farms. The account
+    /// joins the farm again with the next operation that affects the farm if it's within the
+    /// limit.
+    /// - Panics if the account is not in the farm.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn exit_farm(&mut self, farm_id: FarmId) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        assert!(
+            self.internal_is_account_in_farm(&account, &farm_id),
+            "The account is not in the farm"
+        );
+        account.add_affected_farm(farm_id.clone());
+        self.internal_account_claim_affected_farms(&mut account);
+        let account_farm = self.internal_get_account_farm(&account, &farm_id);
+        let asset_farm = self.internal_get_asset_farm(&farm_id);
+        if let (Some(account_farm), Some(mut asset_farm)) = (account_farm, asset_farm) {
+            for (token_id, account_farm_reward) in account_farm.rewards {
+                if let Some(asset_farm_reward) = asset_farm.rewards.get_mut(&token_id) {
+                    asset_farm_reward.boosted_shares -= account_farm_reward.boosted_shares;
+                }
+            }
+            self.internal_set_asset_farm(&farm_id, asset_farm);
+        }
+        account.remove_farm(&farm_id);
+        events::emit::farm_exited(&account_id, &farm_id);
         self.internal_set_account(&account_id, account);
     }
 }
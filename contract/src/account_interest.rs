@@ -0,0 +1,95 @@
+use crate::*;
+
+/// The principals of the positions of the account as they were loaded, by token ID. The
+/// principal is the balance of the position at its last change and it's stored next to the
+/// shares of the position, so the interest accrued since then can be computed without another
+/// record. The loaded shares are kept to find the positions changed by the current operation.
+#[derive(Default, Clone)]
+pub struct AccountPrincipals {
+    pub supplied: HashMap<TokenId, (Shares, Balance)>,
+    pub collateral: HashMap<TokenId, (Shares, Balance)>,
+    pub borrowed: HashMap<TokenId, (Shares, Balance)>,
+}
+
+impl AccountPrincipals {
+    /// Removes the principals of the asset, so they are recorded again from the current balances
+    /// once the account is saved. Returns whether the account had a principal of the asset.
+    pub fn reset(&mut self, token_id: &TokenId) -> bool {
+        let mut is_removed = false;
+        for principals in [&mut self.supplied, &mut self.collateral, &mut self.borrowed] {
+            is_removed |= principals.remove(token_id).is_some();
+        }
+        is_removed
+    }
+}
+
+impl Contract {
+    pub fn compute_account_balances(
+        &self,
+        shares: &HashMap<TokenId, Shares>,
+        is_borrowing: bool,
+    ) -> HashMap<TokenId, Balance> {
+        shares
+            .iter()
+            .map(|(token_id, shares)| {
+                let balance = self.compute_position_balance(token_id, *shares, is_borrowing);
+                (token_id.clone(), balance)
+            })
+            .collect()
+    }
+
+    pub fn compute_position_balance(
+        &self,
+        token_id: &TokenId,
+        shares: Shares,
+        is_borrowing: bool,
+    ) -> Balance {
+        let asset = self.internal_unwrap_asset(token_id);
+        if is_borrowing {
+            asset.borrowed.shares_to_amount(shares, Rounding::Up)
+        } else {
+            asset.supplied.shares_to_amount(shares, Rounding::Down)
+        }
+    }
+
+    /// Returns the principal of the position to save. The principal is kept if the shares of the
+    /// position are not changed since it was loaded, otherwise it's the current balance.
+    pub(crate) fn internal_position_principal(
+        &self,
+        principals: &HashMap<TokenId, (Shares, Balance)>,
+        token_id: &TokenId,
+        shares: Shares,
+        is_borrowing: bool,
+    ) -> Balance {
+        match principals.get(token_id) {
+            Some((loaded_shares, principal)) if *loaded_shares == shares => *principal,
+            _ => self.compute_position_balance(token_id, shares, is_borrowing),
+        }
+    }
+
+    /// Fills the interest accrued on the assets of the account view since the last change of
+    /// every position. The interest is not filled for the positions of the accounts that were not
+    /// saved since they were stored by an older version.
+    pub fn internal_fill_accrued_interest(
+        &self,
+        view: &mut AccountDetailedView,
+        principals: &AccountPrincipals,
+        last_action_timestamp: Option<Timestamp>,
+    ) {
+        fill_accrued_interest(&mut view.supplied, &principals.supplied);
+        fill_accrued_interest(&mut view.collateral, &principals.collateral);
+        fill_accrued_interest(&mut view.borrowed, &principals.borrowed);
+        view.last_action_timestamp = last_action_timestamp.map(|timestamp| timestamp.into());
+    }
+}
+
+fn fill_accrued_interest(
+    assets: &mut [AssetView],
+    principals: &HashMap<TokenId, (Shares, Balance)>,
+) {
+    for asset_view in assets.iter_mut() {
+        asset_view.accrued_interest = principals
+            .get(&asset_view.token_id)
+            .map(|(_, principal)| asset_view.balance.saturating_sub(*principal).into());
+    }
+}
@@ -0,0 +1,39 @@
+use crate::*;
+
+impl Contract {
+    /// Locks the account until the callback of the pending multi-receipt flow, e.g. a withdraw
+    /// transfer, is resolved. The locks are counted, so the account stays locked until all
+    /// pending callbacks are resolved.
+    pub fn internal_lock_account(&mut self, account_id: &AccountId) {
+        let num_locks = self.account_locks.get(account_id).unwrap_or(0);
+        self.account_locks.insert(account_id, &(num_locks + 1));
+    }
+
+    /// Releases one lock of the account from the callback of the multi-receipt flow.
+    pub fn internal_unlock_account(&mut self, account_id: &AccountId) {
+        match self.account_locks.get(account_id).unwrap_or(0) {
+            0 => {}
+            1 => {
+                self.account_locks.remove(account_id);
+            }
+            num_locks => {
+                self.account_locks.insert(account_id, &(num_locks - 1));
+            }
+        }
+    }
+
+    pub fn internal_assert_account_not_locked(&self, account_id: &AccountId) {
+        assert!(
+            !self.account_locks.contains_key(account_id),
+            "The account is locked by the operations in progress"
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the number of pending callbacks that lock the given account.
+    pub fn get_account_lock(&self, account_id: AccountId) -> u32 {
+        self.account_locks.get(&account_id).unwrap_or(0)
+    }
+}
@@ -0,0 +1,236 @@
+use crate::*;
+use std::collections::HashSet;
+
+/// The accounts with more positions and farms in total are stored in shards, so the operations
+/// of the largest accounts don't read and rewrite all their records at once.
+pub const ACCOUNT_SHARDING_THRESHOLD: usize = 24;
+
+/// The sub-record of a sharded account.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Copy)]
+pub enum AccountShardId {
+    Supplied,
+    FarmIds,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum AccountShard {
+    Supplied(CompactPositions),
+    FarmIds(Vec<FarmId>),
+}
+
+/// The main record of a sharded account. The supplied positions and the farm IDs are stored in
+/// the shards, and each shard is only rewritten when it's changed.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AccountCore {
+    pub collateral: CompactPositions,
+    pub borrowed: CompactPositions,
+    /// The number of farms of the account, so the farm limit can be checked without loading the
+    /// farm IDs.
+    pub num_farms: u32,
+    pub booster_staking: Option<BoosterStaking>,
+    pub last_action_timestamp: Timestamp,
+}
+
+/// The shards of a loaded account, as they were loaded.
+#[derive(Clone)]
+pub struct AccountShards {
+    supplied: CompactPositions,
+    /// The sorted farm IDs, `None` until they are loaded on demand.
+    farm_ids: Option<Vec<FarmId>>,
+    num_farms: u32,
+}
+
+impl Account {
+    /// Whether the farm IDs of the account are loaded. The farm IDs of a sharded account are
+    /// loaded on demand, until then `farm_ids` only has the farms joined by the current operation.
+    pub fn is_farm_ids_loaded(&self) -> bool {
+        self.shards
+            .as_ref()
+            .is_none_or(|shards| shards.farm_ids.is_some())
+    }
+}
+
+impl Contract {
+    /// Reads the sharded account. The supplied positions are loaded with the main record, while
+    /// the farm IDs are only loaded for the views.
+    pub(crate) fn internal_sharded_account_from_core(
+        &self,
+        account_id: &AccountId,
+        core: AccountCore,
+        is_view: bool,
+    ) -> Account {
+        let supplied = match self
+            .account_shards
+            .get(&(account_id.clone(), AccountShardId::Supplied))
+        {
+            Some(AccountShard::Supplied(supplied)) => supplied,
+            _ => vec![],
+        };
+        let mut account = Account::new(account_id);
+        let principals = &mut account.principals;
+        account.supplied = self.positions_from_compact(supplied.clone(), &mut principals.supplied);
+        account.collateral =
+            self.positions_from_compact(core.collateral, &mut principals.collateral);
+        account.borrowed = self.positions_from_compact(core.borrowed, &mut principals.borrowed);
+        account.booster_staking = core.booster_staking;
+        account.last_action_timestamp = Some(core.last_action_timestamp);
+        account.shards = Some(AccountShards {
+            supplied,
+            farm_ids: None,
+            num_farms: core.num_farms,
+        });
+        if is_view {
+            self.internal_load_account_farm_ids(&mut account);
+        }
+        account
+    }
+
+    /// Loads the farm IDs of the sharded account, keeping the farms joined and left by the
+    /// current operation.
+    pub fn internal_load_account_farm_ids(&self, account: &mut Account) {
+        if account.is_farm_ids_loaded() {
+            return;
+        }
+        let stored_farm_ids = match self
+            .account_shards
+            .get(&(account.account_id.clone(), AccountShardId::FarmIds))
+        {
+            Some(AccountShard::FarmIds(farm_ids)) => farm_ids,
+            _ => vec![],
+        };
+        let mut farm_ids: HashSet<FarmId> = stored_farm_ids.iter().cloned().collect();
+        farm_ids.extend(account.farm_ids.drain());
+        for (farm_id, account_farm) in account.changed_farms.iter() {
+            if account_farm.is_none() {
+                farm_ids.remove(farm_id);
+            }
+        }
+        account.farm_ids = farm_ids;
+        account.shards.as_mut().unwrap().farm_ids = Some(stored_farm_ids);
+    }
+
+    fn internal_is_account_farm_stored(&self, account: &Account, farm_id: &FarmId) -> bool {
+        self.account_farms
+            .contains_key(&(account.account_id.clone(), farm_id.clone()))
+    }
+
+    /// Whether the account is in the farm, without loading the farm IDs of a sharded account.
+    pub fn internal_is_account_in_farm(&self, account: &Account, farm_id: &FarmId) -> bool {
+        if account.is_farm_ids_loaded() {
+            return account.farm_ids.contains(farm_id);
+        }
+        match account.changed_farms.get(farm_id) {
+            Some(account_farm) => account_farm.is_some(),
+            None => self.internal_is_account_farm_stored(account, farm_id),
+        }
+    }
+
+    /// Returns the number of farms of the account, without loading the farm IDs of a sharded
+    /// account.
+    pub fn internal_account_num_farms(&self, account: &Account) -> usize {
+        let shards = match account.shards.as_ref() {
+            Some(shards) if shards.farm_ids.is_none() => shards,
+            _ => return account.farm_ids.len(),
+        };
+        let mut num_farms = shards.num_farms as usize;
+        for (farm_id, account_farm) in account.changed_farms.iter() {
+            match (
+                account_farm.is_some(),
+                self.internal_is_account_farm_stored(account, farm_id),
+            ) {
+                (true, false) => num_farms += 1,
+                (false, true) => num_farms = num_farms.saturating_sub(1),
+                _ => {}
+            }
+        }
+        num_farms
+    }
+
+    /// Loads the farm IDs of the sharded account if the current operation joins or leaves a farm,
+    /// so the farm IDs shard is updated. Has to be called before the changed farms are saved.
+    pub(crate) fn internal_prepare_account_shards(&self, account: &mut Account) {
+        if account.is_farm_ids_loaded() {
+            return;
+        }
+        let is_farm_ids_changed = account.changed_farms.iter().any(|(farm_id, account_farm)| {
+            account_farm.is_some() != self.internal_is_account_farm_stored(account, farm_id)
+        });
+        if is_farm_ids_changed {
+            self.internal_load_account_farm_ids(account);
+        }
+    }
+
+    /// Saves the main record of the account. The account is sharded once it has more positions
+    /// and farms than the threshold, and stays sharded. The shards of a sharded account are only
+    /// rewritten if they are changed.
+    pub(crate) fn internal_save_account_record(&mut self, mut account: Account) {
+        let account_id = account.account_id.clone();
+        let num_records = account.supplied.len()
+            + account.collateral.len()
+            + account.borrowed.len()
+            + account.farm_ids.len();
+        let shards = match account.shards.take() {
+            Some(shards) => shards,
+            None if num_records <= ACCOUNT_SHARDING_THRESHOLD => {
+                let account = self.account_into_compact(account);
+                self.accounts
+                    .insert(&account_id, &VAccount::Current(account));
+                return;
+            }
+            None => AccountShards {
+                supplied: vec![],
+                farm_ids: Some(vec![]),
+                num_farms: 0,
+            },
+        };
+        let principals = &account.principals;
+        let supplied = self.positions_into_compact(account.supplied, &principals.supplied, false);
+        if supplied != shards.supplied {
+            self.internal_set_account_shard(&account_id, AccountShard::Supplied(supplied));
+        }
+        let num_farms = match shards.farm_ids {
+            Some(loaded_farm_ids) => {
+                let mut farm_ids: Vec<_> = account.farm_ids.into_iter().collect();
+                farm_ids.sort();
+                let num_farms = farm_ids.len() as u32;
+                if farm_ids != loaded_farm_ids {
+                    self.internal_set_account_shard(&account_id, AccountShard::FarmIds(farm_ids));
+                }
+                num_farms
+            }
+            None => shards.num_farms,
+        };
+        let core = AccountCore {
+            collateral: self.positions_into_compact(
+                account.collateral,
+                &principals.collateral,
+                false,
+            ),
+            borrowed: self.positions_into_compact(account.borrowed, &principals.borrowed, true),
+            num_farms,
+            booster_staking: account.booster_staking,
+            last_action_timestamp: env::block_timestamp(),
+        };
+        self.accounts.insert(&account_id, &VAccount::Sharded(core));
+    }
+
+    /// Saves the shard of the account, removing the empty shards.
+    fn internal_set_account_shard(&mut self, account_id: &AccountId, shard: AccountShard) {
+        let (shard_id, is_empty) = match &shard {
+            AccountShard::Supplied(supplied) => (AccountShardId::Supplied, supplied.is_empty()),
+            AccountShard::FarmIds(farm_ids) => (AccountShardId::FarmIds, farm_ids.is_empty()),
+        };
+        let key = (account_id.clone(), shard_id);
+        if is_empty {
+            self.account_shards.remove(&key);
+        } else {
+            self.account_shards.insert(&key, &shard);
+        }
+    }
+
+    pub(crate) fn internal_remove_account_shards(&mut self, account_id: &AccountId) {
+        for shard_id in [AccountShardId::Supplied, AccountShardId::FarmIds] {
+            self.account_shards.remove(&(account_id.clone(), shard_id));
+        }
+    }
+}
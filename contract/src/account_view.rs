@@ -11,6 +11,26 @@ pub struct AssetView {
     pub shares: Shares,
     /// The current APR for this asset (either supply or borrow APR).
     pub apr: BigDecimal,
+    /// The value of the balance in USD based on the last recorded price. Only returned when
+    /// the values are requested and the price is recorded.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub value: Option<BigDecimal>,
+    /// The interest accrued on the balance since the last interaction with the account. Only
+    /// returned when the asset had a balance at the last interaction.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub accrued_interest: Option<U128>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountValuesView {
+    /// The total value of the supplied assets (but not used a collateral).
+    pub supplied: BigDecimal,
+    /// The total value of the collateral assets.
+    pub collateral: BigDecimal,
+    /// The total value of the borrowed assets.
+    pub borrowed: BigDecimal,
 }
 
 #[derive(Serialize)]
@@ -30,6 +50,16 @@ pub struct AccountDetailedView {
     pub has_non_farmed_assets: bool,
     /// Staking of booster token.
     pub booster_staking: Option<BoosterStaking>,
+    /// Claimable amounts of failed withdrawals.
+    #[serde(default)]
+    pub withdraw_claims: Vec<WithdrawClaim>,
+    /// The total values of the assets in USD. Only returned when the values are requested and
+    /// all assets of the account have a recorded price.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub values: Option<AccountValuesView>,
+    /// The timestamp of the last interaction with the account.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_action_timestamp: Option<U64>,
 }
 
 #[derive(Serialize)]
@@ -53,20 +83,21 @@ pub struct AccountFarmRewardView {
 }
 
 impl Contract {
-    pub fn account_into_detailed_view(&self, account: Account) -> AccountDetailedView {
+    pub fn account_into_detailed_view(&self, mut account: Account) -> AccountDetailedView {
+        self.internal_load_account_farm_ids(&mut account);
         let mut potential_farms = account.get_all_potential_farms();
-        let farms = account
-            .farms
-            .keys()
-            .cloned()
+        let mut farm_ids: Vec<_> = account.farm_ids.iter().cloned().collect();
+        farm_ids.sort();
+        let farms = farm_ids
+            .into_iter()
             .map(|farm_id| {
                 // Remove already active farm.
                 potential_farms.remove(&farm_id);
-                let mut asset_farm = self.internal_unwrap_asset_farm(&farm_id, true);
+                let mut asset_farm = self.internal_unwrap_asset_farm(&farm_id);
                 let (account_farm, new_rewards, inactive_rewards) =
                     self.internal_account_farm_claim(&account, &farm_id, &asset_farm);
                 AccountFarmView {
-                    farm_id,
+                    farm_id: farm_id.clone(),
                     rewards: account_farm
                         .rewards
                         .into_iter()
@@ -79,8 +110,10 @@ impl Contract {
                                 .rewards
                                 .remove(&reward_token_id)
                                 .or_else(|| {
-                                    asset_farm
-                                        .internal_get_inactive_asset_farm_reward(&reward_token_id)
+                                    self.internal_get_inactive_asset_farm_reward(
+                                        &farm_id,
+                                        &reward_token_id,
+                                    )
                                 })
                                 .unwrap();
                             let unclaimed_amount = new_rewards
@@ -103,7 +136,8 @@ impl Contract {
         let has_non_farmed_assets = potential_farms
             .into_iter()
             .any(|farm_id| self.asset_farms.contains_key(&farm_id));
-        AccountDetailedView {
+        let withdraw_claims = self.internal_get_withdraw_claims(&account.account_id);
+        let mut view = AccountDetailedView {
             account_id: account.account_id,
             supplied: account
                 .supplied
@@ -123,10 +157,60 @@ impl Contract {
             farms,
             has_non_farmed_assets,
             booster_staking: account.booster_staking,
+            withdraw_claims,
+            values: None,
+            last_action_timestamp: None,
+        };
+        self.internal_fill_accrued_interest(
+            &mut view,
+            &account.principals,
+            account.last_action_timestamp,
+        );
+        view
+    }
+
+    /// Fills the USD values of the assets and the totals of the account view using the last
+    /// recorded prices.
+    pub fn internal_fill_account_values(&self, view: &mut AccountDetailedView) {
+        let supplied = self.internal_fill_asset_values(&mut view.supplied);
+        let collateral = self.internal_fill_asset_values(&mut view.collateral);
+        let borrowed = self.internal_fill_asset_values(&mut view.borrowed);
+        view.values = match (supplied, collateral, borrowed) {
+            (Some(supplied), Some(collateral), Some(borrowed)) => Some(AccountValuesView {
+                supplied,
+                collateral,
+                borrowed,
+            }),
+            _ => None,
+        };
+    }
+
+    /// Fills the values of the given assets and returns the total value. Returns `None` if some
+    /// asset doesn't have a recorded price.
+    fn internal_fill_asset_values(&self, assets: &mut [AssetView]) -> Option<BigDecimal> {
+        let mut total = Some(BigDecimal::zero());
+        for asset_view in assets.iter_mut() {
+            asset_view.value = self.last_prices.get(&asset_view.token_id).map(|price| {
+                let asset = self.internal_unwrap_asset(&asset_view.token_id);
+                BigDecimal::from_balance_price(
+                    asset_view.balance,
+                    price,
+                    asset.config.extra_decimals,
+                )
+            });
+            total = total
+                .zip(asset_view.value)
+                .map(|(total, value)| total + value);
         }
+        total
     }
 
-    fn get_asset_view(&self, token_id: TokenId, shares: Shares, is_borrowing: bool) -> AssetView {
+    pub fn get_asset_view(
+        &self,
+        token_id: TokenId,
+        shares: Shares,
+        is_borrowing: bool,
+    ) -> AssetView {
         let asset = self.internal_unwrap_asset(&token_id);
         let apr = if is_borrowing {
             asset.get_borrow_apr()
@@ -134,9 +218,9 @@ impl Contract {
             asset.get_supply_apr()
         };
         let balance = if is_borrowing {
-            asset.borrowed.shares_to_amount(shares, true)
+            asset.borrowed.shares_to_amount(shares, Rounding::Up)
         } else {
-            asset.supplied.shares_to_amount(shares, false)
+            asset.supplied.shares_to_amount(shares, Rounding::Down)
         };
 
         AssetView {
@@ -144,6 +228,8 @@ impl Contract {
             balance,
             shares,
             apr,
+            value: None,
+            accrued_interest: None,
         }
     }
 }
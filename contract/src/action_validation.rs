@@ -0,0 +1,318 @@
+use crate::*;
+use near_sdk::serde_json;
+use std::collections::HashSet;
+
+/// The estimated gas of the `execute` call itself, excluding the actions.
+const GAS_FOR_EXECUTE: Gas = Gas(Gas::ONE_TERA.0 * 20);
+/// The estimated gas of an action that only updates the state of the contract.
+const GAS_PER_ACTION: Gas = Gas(Gas::ONE_TERA.0 * 10);
+/// The estimated gas of an action that transfers the tokens, including the callback.
+const GAS_PER_TRANSFER_ACTION: Gas = Gas(Gas::ONE_TERA.0 * 50);
+/// The estimated gas of an action that swaps the tokens on the exchange, including the transfer
+/// of the output back and the callback.
+const GAS_PER_SWAP_ACTION: Gas = Gas(Gas::ONE_TERA.0 * 150);
+/// The estimated gas of the oracle call that precedes the actions that require prices.
+const GAS_FOR_ORACLE_CALL: Gas = Gas(Gas::ONE_TERA.0 * 30);
+
+/// The validated bundle of actions, e.g. from a deep-link payload of a wallet.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ActionsValidationView {
+    /// The actions re-serialized by the contract parser, e.g. with the omitted optional fields.
+    pub actions: Vec<Action>,
+    /// Whether the actions have to be executed through the oracle call.
+    pub requires_prices: bool,
+    /// The estimated gas to attach to the call that executes the actions.
+    pub gas: U64,
+    /// The estimated storage cost of the actions. Only returned if the account is given and
+    /// registered.
+    pub storage: Option<StorageCostEstimate>,
+}
+
+impl Action {
+    /// Returns the token IDs of the assets that the action uses.
+    fn token_ids(&self) -> Vec<&TokenId> {
+        match self {
+            Action::Withdraw(asset_amount)
+            | Action::IncreaseCollateral(asset_amount)
+            | Action::DecreaseCollateral(asset_amount)
+            | Action::Borrow(asset_amount)
+            | Action::BorrowAndWithdraw(asset_amount)
+            | Action::Repay(asset_amount)
+            | Action::Net(asset_amount)
+            | Action::WithdrawAndSwap { asset_amount, .. }
+            | Action::WithdrawToAurora { asset_amount, .. }
+            | Action::WithdrawNear(asset_amount) => vec![&asset_amount.token_id],
+            Action::Liquidate {
+                in_assets,
+                out_assets,
+                ..
+            } => in_assets
+                .iter()
+                .chain(out_assets.iter())
+                .map(|asset_amount| &asset_amount.token_id)
+                .collect(),
+            Action::LeverageUp {
+                collateral_token,
+                borrow_token,
+                ..
+            } => vec![collateral_token, borrow_token],
+            Action::RepayFromSupplied {
+                supplied_token,
+                borrowed_token,
+                ..
+            } => vec![supplied_token, borrowed_token],
+            Action::MigrateAsset { token_id } => vec![token_id],
+            Action::ForceClose { .. }
+            | Action::ExecuteAutoRepayOrder { .. }
+            | Action::WithdrawNft { .. }
+            | Action::StartNftAuction { .. } => vec![],
+        }
+    }
+
+    /// Returns the token IDs of the assets whose prices the action requires. The collateral taken
+    /// by a liquidation is not included, since the collateral without a price is valued at zero
+    /// and can't be seized.
+    fn priced_token_ids(&self) -> Vec<&TokenId> {
+        match self {
+            Action::Borrow(asset_amount) | Action::BorrowAndWithdraw(asset_amount) => {
+                vec![&asset_amount.token_id]
+            }
+            Action::Liquidate { in_assets, .. } => in_assets
+                .iter()
+                .map(|asset_amount| &asset_amount.token_id)
+                .collect(),
+            Action::LeverageUp {
+                collateral_token,
+                borrow_token,
+                ..
+            } => vec![collateral_token, borrow_token],
+            _ => vec![],
+        }
+    }
+
+    /// Whether the health of the account is checked after the action, so the prices of all the
+    /// borrowed assets of the account are required.
+    fn checks_health(&self) -> bool {
+        matches!(
+            self,
+            Action::Borrow(_)
+                | Action::BorrowAndWithdraw(_)
+                | Action::DecreaseCollateral(_)
+                | Action::LeverageUp { .. }
+                | Action::WithdrawNft { .. }
+                | Action::MigrateAsset { .. }
+        )
+    }
+
+    /// Returns the estimated gas of the action, including its cross-contract calls.
+    fn estimated_gas(&self) -> Gas {
+        match self {
+            Action::Withdraw(_)
+            | Action::BorrowAndWithdraw(_)
+            | Action::WithdrawToAurora { .. }
+            | Action::WithdrawNear(_)
+            | Action::WithdrawNft { .. } => GAS_PER_TRANSFER_ACTION,
+            Action::LeverageUp { .. }
+            | Action::WithdrawAndSwap { .. }
+            | Action::RepayFromSupplied { .. } => GAS_PER_SWAP_ACTION,
+            Action::IncreaseCollateral(_)
+            | Action::DecreaseCollateral(_)
+            | Action::Borrow(_)
+            | Action::Repay(_)
+            | Action::Liquidate { .. }
+            | Action::ForceClose { .. }
+            | Action::ExecuteAutoRepayOrder { .. }
+            | Action::StartNftAuction { .. }
+            | Action::MigrateAsset { .. }
+            | Action::Net(_) => GAS_PER_ACTION,
+        }
+    }
+
+    /// Returns the pausable actions that the action uses for each of its assets.
+    fn pausable_actions(&self) -> Vec<(&TokenId, PausableAction)> {
+        match self {
+            Action::Withdraw(asset_amount)
+            | Action::WithdrawAndSwap { asset_amount, .. }
+            | Action::WithdrawToAurora { asset_amount, .. }
+            | Action::WithdrawNear(asset_amount) => {
+                vec![(&asset_amount.token_id, PausableAction::Withdraw)]
+            }
+            Action::IncreaseCollateral(asset_amount) => {
+                vec![(&asset_amount.token_id, PausableAction::IncreaseCollateral)]
+            }
+            Action::DecreaseCollateral(asset_amount) => {
+                vec![(&asset_amount.token_id, PausableAction::DecreaseCollateral)]
+            }
+            Action::Borrow(asset_amount) => vec![(&asset_amount.token_id, PausableAction::Borrow)],
+            Action::BorrowAndWithdraw(asset_amount) => vec![
+                (&asset_amount.token_id, PausableAction::Borrow),
+                (&asset_amount.token_id, PausableAction::Withdraw),
+            ],
+            Action::Repay(asset_amount) | Action::Net(asset_amount) => {
+                vec![(&asset_amount.token_id, PausableAction::Repay)]
+            }
+            Action::Liquidate { .. } => self
+                .token_ids()
+                .into_iter()
+                .map(|token_id| (token_id, PausableAction::Liquidate))
+                .collect(),
+            Action::LeverageUp { .. }
+            | Action::RepayFromSupplied { .. }
+            | Action::MigrateAsset { .. }
+            | Action::ForceClose { .. }
+            | Action::ExecuteAutoRepayOrder { .. }
+            | Action::WithdrawNft { .. }
+            | Action::StartNftAuction { .. } => vec![],
+        }
+    }
+}
+
+impl Contract {
+    /// Asserts that the number of actions of the bundle doesn't exceed the maximum of the config.
+    pub fn internal_assert_num_actions(&self, num_actions: usize) {
+        if let Some(max_num_actions) = self.internal_config().max_num_actions {
+            assert!(
+                num_actions <= max_num_actions as usize,
+                "The number of actions {} exceeds the maximum of {} per call",
+                num_actions,
+                max_num_actions
+            );
+        }
+    }
+
+    /// Asserts that the prices cover the assets of the actions that require prices, and the
+    /// borrowed assets of the account if its health is checked, so the bundle doesn't fail in the
+    /// middle on a missing price. Not checked if none of the actions require prices.
+    pub fn internal_assert_prices_cover_actions(
+        &self,
+        account: &Account,
+        actions: &[Action],
+        prices: &Prices,
+    ) {
+        if !actions.iter().any(|action| action.requires_prices()) {
+            return;
+        }
+        let mut token_ids: Vec<&TokenId> = actions
+            .iter()
+            .flat_map(|action| action.priced_token_ids())
+            .collect();
+        if actions.iter().any(|action| action.checks_health()) {
+            token_ids.extend(account.borrowed.keys());
+        }
+        for token_id in token_ids {
+            assert!(
+                prices.get_valid(token_id).is_some(),
+                "The prices don't cover the asset {} required by the actions",
+                token_id
+            );
+        }
+    }
+
+    /// Validates the bundle of the account before the prices are requested, so the obviously
+    /// invalid bundles fail before the oracle round-trip. Checks that the bundle is not empty, the
+    /// assets exist and the actions are not paused. The balances are checked roughly, only for
+    /// the assets that are not touched by the earlier actions of the bundle.
+    pub fn internal_prevalidate_actions(&self, account: &Account, actions: &[Action]) {
+        assert!(!actions.is_empty(), "The actions can't be empty");
+        self.internal_assert_num_actions(actions.len());
+        let mut touched_token_ids = HashSet::new();
+        for action in actions {
+            for token_id in action.token_ids() {
+                self.internal_unwrap_asset(token_id);
+            }
+            for (token_id, pausable_action) in action.pausable_actions() {
+                self.internal_assert_not_paused(token_id, pausable_action);
+            }
+            match action {
+                Action::Withdraw(asset_amount)
+                | Action::WithdrawAndSwap { asset_amount, .. }
+                | Action::WithdrawToAurora { asset_amount, .. }
+                | Action::WithdrawNear(asset_amount)
+                    if !touched_token_ids.contains(&asset_amount.token_id) =>
+                {
+                    let shares = account.supplied.get(&asset_amount.token_id);
+                    self.internal_assert_rough_balance(shares, asset_amount, "supplied");
+                }
+                Action::DecreaseCollateral(asset_amount)
+                    if !touched_token_ids.contains(&asset_amount.token_id) =>
+                {
+                    let shares = account.collateral.get(&asset_amount.token_id);
+                    self.internal_assert_rough_balance(shares, asset_amount, "collateral");
+                }
+                Action::Repay(asset_amount) | Action::Net(asset_amount)
+                    if !touched_token_ids.contains(&asset_amount.token_id) =>
+                {
+                    assert!(
+                        account.borrowed.contains_key(&asset_amount.token_id),
+                        "Borrowed asset not found"
+                    );
+                }
+                _ => {}
+            }
+            touched_token_ids.extend(action.token_ids().into_iter().cloned());
+        }
+    }
+
+    /// Asserts that the account has the supplied or the collateral shares of the asset, and the
+    /// balance of the shares covers the exact amount if it's given.
+    fn internal_assert_rough_balance(
+        &self,
+        shares: Option<&Shares>,
+        asset_amount: &AssetAmount,
+        balance_name: &str,
+    ) {
+        let shares = match shares {
+            Some(shares) if shares.0 > 0 => *shares,
+            _ => env::panic_str(&format!("Not enough {} balance", balance_name)),
+        };
+        if let Some(amount) = &asset_amount.amount {
+            let asset = self.internal_unwrap_asset(&asset_amount.token_id);
+            assert!(
+                asset.supplied.shares_to_amount(shares, Rounding::Down) >= amount.0,
+                "Not enough {} balance",
+                balance_name
+            );
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Parses the JSON of the actions with the same parser as `execute`, and validates that the
+    /// bundle is not empty, doesn't exceed the maximum number of actions and the assets of the
+    /// actions exist. Returns the actions in the
+    /// canonical form, the estimated gas and, if the account is given, the estimated storage cost,
+    /// so the wallets can verify the deep-link payloads before signing them.
+    /// The checks that depend on the balances and prices are only done on the execution.
+    /// - Panics if the JSON can't be parsed or the actions are invalid.
+    pub fn validate_actions(
+        &self,
+        actions_json: String,
+        account_id: Option<AccountId>,
+    ) -> ActionsValidationView {
+        let actions: Vec<Action> = serde_json::from_str(&actions_json)
+            .unwrap_or_else(|e| env::panic_str(&format!("Can't parse the actions: {}", e)));
+        assert!(!actions.is_empty(), "The actions can't be empty");
+        self.internal_assert_num_actions(actions.len());
+        for token_id in actions.iter().flat_map(|action| action.token_ids()) {
+            self.internal_unwrap_asset(token_id);
+        }
+        let requires_prices = actions.iter().any(|action| action.requires_prices());
+        let mut gas = actions
+            .iter()
+            .fold(GAS_FOR_EXECUTE, |gas, action| gas + action.estimated_gas());
+        if requires_prices {
+            gas = gas + GAS_FOR_ORACLE_CALL;
+        }
+        let storage = account_id
+            .and_then(|account_id| self.internal_estimate_storage_cost(&account_id, &actions));
+        ActionsValidationView {
+            actions,
+            requires_prices,
+            gas: gas.0.into(),
+            storage,
+        }
+    }
+}
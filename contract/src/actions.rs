@@ -1,7 +1,7 @@
 use crate::*;
 
-#[derive(Deserialize)]
-#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Serialize))]
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
 #[serde(crate = "near_sdk::serde")]
 pub struct AssetAmount {
     pub token_id: TokenId,
@@ -13,14 +13,16 @@ pub struct AssetAmount {
     pub max_amount: Option<U128>,
 }
 
-#[derive(Deserialize)]
-#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Serialize))]
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
 #[serde(crate = "near_sdk::serde")]
 pub enum Action {
     Withdraw(AssetAmount),
     IncreaseCollateral(AssetAmount),
     DecreaseCollateral(AssetAmount),
     Borrow(AssetAmount),
+    /// Borrows the given amount and transfers it directly to the account's wallet.
+    BorrowAndWithdraw(AssetAmount),
     Repay(AssetAmount),
     Liquidate {
         account_id: AccountId,
@@ -32,58 +34,264 @@ pub enum Action {
     ForceClose {
         account_id: AccountId,
     },
+    /// Borrows `borrow_token` and swaps it for `collateral_token` on the exchange to
+    /// increase the collateral up to the target leverage (multiplied by 10000). E.g. 30000 means
+    /// the collateral will be 3 times the current collateral.
+    LeverageUp {
+        collateral_token: TokenId,
+        borrow_token: TokenId,
+        target_leverage: u32,
+        swap_route: Vec<SwapAction>,
+        /// The whitelisted exchange for the swap. The `ref_exchange_id` is used if not given.
+        dex_id: Option<AccountId>,
+    },
+    /// Withdraws the asset and swaps it on the exchange following the `swap_route`.
+    /// The output is transferred to the account only if it's at least `min_received` in the output
+    /// token decimals. Otherwise, or if the swap fails, it's returned to the supplied balance.
+    WithdrawAndSwap {
+        asset_amount: AssetAmount,
+        swap_route: Vec<SwapAction>,
+        min_received: U128,
+        /// The whitelisted exchange for the swap. The `ref_exchange_id` is used if not given.
+        dex_id: Option<AccountId>,
+    },
+    /// Sells the `amount` (or the entire balance if `None`) of the supplied `supplied_token` on the
+    /// exchange following the `route`, and uses the output to repay the borrowed
+    /// `borrowed_token`. The remaining output stays supplied. If the swap fails, the amount is
+    /// returned to the supplied balance.
+    RepayFromSupplied {
+        supplied_token: TokenId,
+        amount: Option<U128>,
+        borrowed_token: TokenId,
+        route: Vec<SwapAction>,
+        /// The whitelisted exchange for the swap. The `ref_exchange_id` is used if not given.
+        dex_id: Option<AccountId>,
+    },
+    /// Withdraws the asset and transfers it to the `recipient` address on Aurora through the
+    /// bridge. The amount refunded by the Aurora engine becomes claimable with `retry_withdraw`.
+    WithdrawToAurora {
+        asset_amount: AssetAmount,
+        recipient: String,
+    },
+    /// Withdraws the wNEAR, unwraps it and transfers the native NEAR to the account. The amount
+    /// becomes claimable as wNEAR if the unwrapping fails.
+    WithdrawNear(AssetAmount),
+    /// Executes the auto-repay order of the given account if its health factor is below the
+    /// trigger of the order. The tip of the order is supplied to the executing account.
+    ExecuteAutoRepayOrder {
+        account_id: AccountId,
+        order_id: u32,
+    },
+    /// Removes the NFT from the collateral and transfers it to the account. Requires prices if
+    /// the account has borrowed assets.
+    WithdrawNft {
+        collection_id: AccountId,
+        token_id: String,
+    },
+    /// Takes the NFT from the collateral of the liquidatable account and starts its auction.
+    StartNftAuction {
+        account_id: AccountId,
+        collection_id: AccountId,
+        token_id: String,
+    },
+    /// Converts the supplied, collateral and borrowed balances of the deprecated asset into its
+    /// replacement set by the owner, keeping the collateral and the borrowed status. Requires
+    /// prices if the account has borrowed assets.
+    MigrateAsset {
+        token_id: TokenId,
+    },
+    /// Offsets the borrowed balance of the asset with the supplied balance of the same asset,
+    /// using the collateral once the supplied balance is used. The amount is the repaid amount
+    /// like for `Repay`. The account doesn't become less healthy, so no prices are required.
+    Net(AssetAmount),
+}
+
+/// Optional constraints on the result of the actions. They're verified after all actions are
+/// executed, so the entire bundle is reverted if any of the constraints is violated.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ExecuteConstraints {
+    /// The minimum health factor of the account after the actions (multiplied by 10000).
+    /// E.g. 12000 means the adjusted collateral sum should be at least 120% of the adjusted
+    /// borrowed sum. Requires prices for all collateral and borrowed assets of the account.
+    pub min_health_after: Option<u32>,
+    /// The block timestamp in nanoseconds after which the actions can't be executed.
+    pub deadline_ts: Option<U64>,
+}
+
+impl Action {
+    /// Whether the action always requires prices, so it can only be executed through the oracle.
+    /// `DecreaseCollateral` requires prices only if the account has borrowed assets.
+    pub fn requires_prices(&self) -> bool {
+        match self {
+            Action::Withdraw(_)
+            | Action::IncreaseCollateral(_)
+            | Action::DecreaseCollateral(_)
+            | Action::Repay(_)
+            | Action::WithdrawAndSwap { .. }
+            | Action::RepayFromSupplied { .. }
+            | Action::WithdrawToAurora { .. }
+            | Action::WithdrawNear(_)
+            | Action::WithdrawNft { .. }
+            | Action::MigrateAsset { .. }
+            | Action::Net(_) => false,
+            Action::Borrow(_)
+            | Action::BorrowAndWithdraw(_)
+            | Action::Liquidate { .. }
+            | Action::ForceClose { .. }
+            | Action::LeverageUp { .. }
+            | Action::ExecuteAutoRepayOrder { .. }
+            | Action::StartNftAuction { .. } => true,
+        }
+    }
+}
+
+pub(crate) fn assert_actions_without_prices(actions: &[Action]) {
+    assert!(
+        actions.iter().all(|action| !action.requires_prices()),
+        "The actions require prices and have to be executed through the oracle call"
+    );
+}
+
+/// Asserts that the entry point that executes actions is not called by the contract itself, e.g.
+/// with a multisig request. The contract never calls these entry points on itself. The calls from
+/// the other receipts that could interleave with a multi-receipt flow are covered by the account
+/// locks instead.
+pub(crate) fn assert_not_self_call() {
+    assert_ne!(
+        env::predecessor_account_id(),
+        env::current_account_id(),
+        "The contract can't call its own entry points"
+    );
 }
 
 impl Contract {
+    /// Executes the actions that don't require prices directly, without the oracle call.
+    pub fn internal_execute_without_prices(
+        &mut self,
+        account_id: &AccountId,
+        account: &mut Account,
+        actions: Vec<Action>,
+    ) -> Prices {
+        assert_actions_without_prices(&actions);
+        let prices = Prices::new();
+        self.internal_execute(account_id, account, actions, &prices);
+        prices
+    }
+
     pub fn internal_execute(
         &mut self,
         account_id: &AccountId,
         account: &mut Account,
         actions: Vec<Action>,
-        prices: Prices,
+        prices: &Prices,
     ) {
-        self.internal_set_prices(&prices);
+        self.internal_assert_account_not_locked(account_id);
+        self.internal_execute_chunk(account_id, account, actions, prices, false);
+    }
+
+    /// Executes the actions in order. If `can_continue` is true, the execution stops once the
+    /// remaining gas is not enough to continue the bundle, and the actions that were not executed
+    /// are returned. The executed actions are verified as a complete bundle once the dust is
+    /// written off and the farms are settled, so the order of the actions can't bypass the checks.
+    pub fn internal_execute_chunk(
+        &mut self,
+        account_id: &AccountId,
+        account: &mut Account,
+        actions: Vec<Action>,
+        prices: &Prices,
+        can_continue: bool,
+    ) -> Vec<Action> {
+        self.internal_assert_no_execute_continuation(account_id);
+        self.internal_assert_num_actions(actions.len());
+        self.internal_assert_storage_for_actions(account, &actions);
+        self.internal_set_prices(prices);
+        let is_assets_batch = self.internal_start_assets_batch();
         let mut need_risk_check = false;
         let mut need_number_check = false;
-        for action in actions {
+        let mut need_health_buffer_check = false;
+        let initial_positions = InitialPositions::new(account);
+        let mut actions = actions.into_iter();
+        for action in actions.by_ref() {
+            #[cfg(feature = "gas-metrics")]
+            let gas_meter = ActionGasMeter::start(&action);
             match action {
                 Action::Withdraw(asset_amount) => {
                     account.add_affected_farm(FarmId::Supplied(asset_amount.token_id.clone()));
-                    let amount = self.internal_withdraw(account, &asset_amount);
+                    let (shares, amount) = self.internal_withdraw(account, &asset_amount);
+                    self.internal_use_withdraw_timelock(account, &asset_amount.token_id, amount);
+                    events::emit::withdraw(&account_id, amount, shares, &asset_amount.token_id);
                     self.internal_ft_transfer(account_id, &asset_amount.token_id, amount);
                     events::emit::withdraw_started(&account_id, amount, &asset_amount.token_id);
                 }
                 Action::IncreaseCollateral(asset_amount) => {
                     need_number_check = true;
-                    let amount = self.internal_increase_collateral(account, &asset_amount);
-                    events::emit::increase_collateral(&account_id, amount, &asset_amount.token_id);
+                    let (shares, amount) =
+                        self.internal_increase_collateral(account, &asset_amount);
+                    events::emit::increase_collateral(
+                        &account_id,
+                        amount,
+                        shares,
+                        &asset_amount.token_id,
+                    );
                 }
                 Action::DecreaseCollateral(asset_amount) => {
                     need_risk_check = true;
+                    need_health_buffer_check = true;
+                    self.internal_assert_not_paused(
+                        &asset_amount.token_id,
+                        PausableAction::DecreaseCollateral,
+                    );
+                    assert!(
+                        account.borrowed.is_empty()
+                            || prices.get_valid(&asset_amount.token_id).is_some(),
+                        "The collateral with a missing or zero price can't be decreased while borrowing"
+                    );
                     let mut account_asset =
                         account.internal_get_asset_or_default(&asset_amount.token_id);
-                    let amount = self.internal_decrease_collateral(
+                    let (shares, amount) = self.internal_decrease_collateral(
                         &mut account_asset,
                         account,
                         &asset_amount,
                     );
                     account.internal_set_asset(&asset_amount.token_id, account_asset);
-                    events::emit::decrease_collateral(&account_id, amount, &asset_amount.token_id);
+                    events::emit::decrease_collateral(
+                        &account_id,
+                        amount,
+                        shares,
+                        &asset_amount.token_id,
+                    );
                 }
                 Action::Borrow(asset_amount) => {
                     need_number_check = true;
                     need_risk_check = true;
+                    need_health_buffer_check = true;
                     account.add_affected_farm(FarmId::Supplied(asset_amount.token_id.clone()));
                     account.add_affected_farm(FarmId::Borrowed(asset_amount.token_id.clone()));
-                    let amount = self.internal_borrow(account, &asset_amount);
-                    events::emit::borrow(&account_id, amount, &asset_amount.token_id);
+                    let (shares, amount) = self.internal_borrow(account, &asset_amount);
+                    events::emit::borrow(&account_id, amount, shares, &asset_amount.token_id);
+                }
+                Action::BorrowAndWithdraw(asset_amount) => {
+                    need_number_check = true;
+                    need_risk_check = true;
+                    need_health_buffer_check = true;
+                    account.add_affected_farm(FarmId::Borrowed(asset_amount.token_id.clone()));
+                    let (shares, amount) =
+                        self.internal_borrow_and_withdraw(account, &asset_amount);
+                    self.internal_use_withdraw_timelock(account, &asset_amount.token_id, amount);
+                    events::emit::borrow(account_id, amount, shares, &asset_amount.token_id);
+                    self.internal_ft_transfer(account_id, &asset_amount.token_id, amount);
+                    events::emit::withdraw_started(account_id, amount, &asset_amount.token_id);
                 }
                 Action::Repay(asset_amount) => {
+                    self.internal_assert_not_paused(&asset_amount.token_id, PausableAction::Repay);
                     let mut account_asset = account.internal_unwrap_asset(&asset_amount.token_id);
                     account.add_affected_farm(FarmId::Supplied(asset_amount.token_id.clone()));
                     account.add_affected_farm(FarmId::Borrowed(asset_amount.token_id.clone()));
-                    let amount = self.internal_repay(&mut account_asset, account, &asset_amount);
-                    events::emit::repay(&account_id, amount, &asset_amount.token_id);
+                    let (shares, amount) =
+                        self.internal_repay(&mut account_asset, account, &asset_amount);
+                    events::emit::repay(&account_id, amount, shares, &asset_amount.token_id);
                     account.internal_set_asset(&asset_amount.token_id, account_asset);
                 }
                 Action::Liquidate {
@@ -99,7 +307,7 @@ impl Contract {
                     self.internal_liquidate(
                         account_id,
                         account,
-                        &prices,
+                        prices,
                         &liquidation_account_id,
                         in_assets,
                         out_assets,
@@ -112,21 +320,184 @@ impl Contract {
                         account_id, &liquidation_account_id,
                         "Can't liquidate yourself"
                     );
-                    self.internal_force_close(&prices, &liquidation_account_id);
+                    self.internal_force_close(prices, &liquidation_account_id);
+                }
+                Action::LeverageUp {
+                    collateral_token,
+                    borrow_token,
+                    target_leverage,
+                    swap_route,
+                    dex_id,
+                } => {
+                    need_number_check = true;
+                    need_risk_check = true;
+                    need_health_buffer_check = true;
+                    account.add_affected_farm(FarmId::Borrowed(borrow_token.clone()));
+                    let ft_amount = self.internal_leverage_borrow_amount(
+                        account,
+                        prices,
+                        &collateral_token,
+                        &borrow_token,
+                        target_leverage,
+                    );
+                    self.internal_leverage_up(
+                        account,
+                        collateral_token,
+                        borrow_token,
+                        ft_amount,
+                        swap_route,
+                        dex_id,
+                    );
+                }
+                Action::WithdrawAndSwap {
+                    asset_amount,
+                    swap_route,
+                    min_received,
+                    dex_id,
+                } => {
+                    account.add_affected_farm(FarmId::Supplied(asset_amount.token_id.clone()));
+                    self.internal_withdraw_and_swap(
+                        account,
+                        &asset_amount,
+                        swap_route,
+                        min_received.0,
+                        dex_id,
+                    );
+                }
+                Action::RepayFromSupplied {
+                    supplied_token,
+                    amount,
+                    borrowed_token,
+                    route,
+                    dex_id,
+                } => {
+                    account.add_affected_farm(FarmId::Supplied(supplied_token.clone()));
+                    self.internal_repay_from_supplied(
+                        account,
+                        supplied_token,
+                        amount,
+                        borrowed_token,
+                        route,
+                        dex_id,
+                    );
+                }
+                Action::WithdrawToAurora {
+                    asset_amount,
+                    recipient,
+                } => {
+                    let recipient = parse_aurora_address(&recipient);
+                    account.add_affected_farm(FarmId::Supplied(asset_amount.token_id.clone()));
+                    let (shares, amount) = self.internal_withdraw(account, &asset_amount);
+                    self.internal_use_withdraw_timelock(account, &asset_amount.token_id, amount);
+                    events::emit::withdraw(account_id, amount, shares, &asset_amount.token_id);
+                    self.internal_aurora_transfer(
+                        account_id,
+                        &asset_amount.token_id,
+                        amount,
+                        recipient,
+                    );
+                    events::emit::withdraw_started(account_id, amount, &asset_amount.token_id);
+                }
+                Action::WithdrawNear(asset_amount) => {
+                    account.add_affected_farm(FarmId::Supplied(asset_amount.token_id.clone()));
+                    let (shares, amount) = self.internal_withdraw(account, &asset_amount);
+                    self.internal_use_withdraw_timelock(account, &asset_amount.token_id, amount);
+                    events::emit::withdraw(account_id, amount, shares, &asset_amount.token_id);
+                    self.internal_near_withdraw(account_id, &asset_amount.token_id, amount);
+                    events::emit::withdraw_started(account_id, amount, &asset_amount.token_id);
+                }
+                Action::ExecuteAutoRepayOrder {
+                    account_id: order_account_id,
+                    order_id,
+                } => {
+                    assert_ne!(
+                        account_id, &order_account_id,
+                        "Can't execute your own auto-repay order"
+                    );
+                    self.internal_execute_auto_repay_order(
+                        account,
+                        prices,
+                        &order_account_id,
+                        order_id,
+                    );
+                }
+                Action::WithdrawNft {
+                    collection_id,
+                    token_id,
+                } => {
+                    need_risk_check = true;
+                    need_health_buffer_check = true;
+                    self.internal_withdraw_nft(
+                        account,
+                        NftId {
+                            collection_id,
+                            token_id,
+                        },
+                    );
+                }
+                Action::StartNftAuction {
+                    account_id: liquidation_account_id,
+                    collection_id,
+                    token_id,
+                } => {
+                    assert_ne!(
+                        account_id, &liquidation_account_id,
+                        "Can't liquidate yourself"
+                    );
+                    self.internal_start_nft_auction(
+                        prices,
+                        account_id,
+                        &liquidation_account_id,
+                        NftId {
+                            collection_id,
+                            token_id,
+                        },
+                    );
+                }
+                Action::MigrateAsset { token_id } => {
+                    need_risk_check = true;
+                    self.internal_migrate_asset(account, &token_id);
+                }
+                Action::Net(asset_amount) => {
+                    self.internal_assert_not_paused(&asset_amount.token_id, PausableAction::Repay);
+                    account.add_affected_farm(FarmId::Supplied(asset_amount.token_id.clone()));
+                    account.add_affected_farm(FarmId::Borrowed(asset_amount.token_id.clone()));
+                    let (shares, amount) = self.internal_net(account, &asset_amount);
+                    events::emit::repay(&account_id, amount, shares, &asset_amount.token_id);
                 }
             }
+            #[cfg(feature = "gas-metrics")]
+            gas_meter.finish();
+            if can_continue && !has_gas_for_next_action() {
+                break;
+            }
         }
+        let remaining_actions: Vec<Action> = actions.collect();
+        self.internal_write_off_dust(account, &initial_positions);
+        // The farms are settled before the checks, so the checks cover the final state of the
+        // account after the claimed rewards are deposited.
+        self.internal_account_apply_affected_farms(account);
         if need_number_check {
             assert!(
                 account.collateral.len() + account.borrowed.len()
                     <= self.internal_config().max_num_assets as _
             );
         }
+        if need_risk_check || need_health_buffer_check {
+            self.internal_emit_missing_collateral_prices(account, prices);
+        }
         if need_risk_check {
-            assert!(self.compute_max_discount(account, &prices) == BigDecimal::zero());
+            assert!(self.compute_max_discount(account, prices) == BigDecimal::zero());
+        }
+        if need_health_buffer_check {
+            self.internal_assert_min_borrow_health(account, prices);
         }
 
-        self.internal_account_apply_affected_farms(account);
+        if is_assets_batch {
+            self.internal_flush_assets();
+        }
+        self.internal_emit_account_health(account);
+        remaining_actions
     }
 
     pub fn internal_deposit(
@@ -138,7 +509,7 @@ impl Contract {
         let mut asset = self.internal_unwrap_asset(token_id);
         let mut account_asset = account.internal_get_asset_or_default(token_id);
 
-        let shares: Shares = asset.supplied.amount_to_shares(amount, false);
+        let shares: Shares = asset.supplied.amount_to_shares(amount, Rounding::Down);
 
         account_asset.deposit_shares(shares);
         account.internal_set_asset(&token_id, account_asset);
@@ -153,17 +524,22 @@ impl Contract {
         &mut self,
         account: &mut Account,
         asset_amount: &AssetAmount,
-    ) -> Balance {
+    ) -> (Shares, Balance) {
         let mut asset = self.internal_unwrap_asset(&asset_amount.token_id);
         assert!(
-            asset.config.can_withdraw,
+            !asset.config.is_paused(PausableAction::Withdraw),
             "Withdrawals for this asset are not enabled"
         );
+        self.internal_assert_no_flash_loan(&asset_amount.token_id);
 
         let mut account_asset = account.internal_unwrap_asset(&asset_amount.token_id);
 
-        let (shares, amount) =
-            asset_amount_to_shares(&asset.supplied, account_asset.shares, &asset_amount, false);
+        let (shares, amount) = asset_amount_to_shares(
+            &asset.supplied,
+            account_asset.shares,
+            &asset_amount,
+            Rounding::Up,
+        );
 
         let available_amount = asset.available_amount();
 
@@ -180,31 +556,45 @@ impl Contract {
         asset.supplied.withdraw(shares, amount);
         self.internal_set_asset(&asset_amount.token_id, asset);
 
-        amount
+        (shares, amount)
     }
 
     pub fn internal_increase_collateral(
         &mut self,
         account: &mut Account,
         asset_amount: &AssetAmount,
-    ) -> Balance {
-        let asset = self.internal_unwrap_asset(&asset_amount.token_id);
+    ) -> (Shares, Balance) {
+        let mut asset = self.internal_unwrap_asset(&asset_amount.token_id);
         assert!(
             asset.config.can_use_as_collateral,
             "Thi asset can't be used as a collateral"
         );
+        assert!(
+            !asset.config.is_paused(PausableAction::IncreaseCollateral),
+            "Increasing collateral for this asset is not enabled"
+        );
+        self.internal_assert_isolation_collateral(account, &asset_amount.token_id, &asset);
 
         let mut account_asset = account.internal_unwrap_asset(&asset_amount.token_id);
 
-        let (shares, amount) =
-            asset_amount_to_shares(&asset.supplied, account_asset.shares, &asset_amount, false);
+        let (shares, amount) = asset_amount_to_shares(
+            &asset.supplied,
+            account_asset.shares,
+            &asset_amount,
+            Rounding::Up,
+        );
 
         account_asset.withdraw_shares(shares);
         account.internal_set_asset(&asset_amount.token_id, account_asset);
 
         account.increase_collateral(&asset_amount.token_id, shares);
+        self.internal_assert_risk_tiers(account);
+
+        asset.collateral_shares.0 += shares.0;
+        asset.assert_collateral_cap(0);
+        self.internal_set_asset(&asset_amount.token_id, asset);
 
-        amount
+        (shares, amount)
     }
 
     pub fn internal_decrease_collateral(
@@ -212,35 +602,110 @@ impl Contract {
         account_asset: &mut AccountAsset,
         account: &mut Account,
         asset_amount: &AssetAmount,
-    ) -> Balance {
-        let asset = self.internal_unwrap_asset(&asset_amount.token_id);
+    ) -> (Shares, Balance) {
+        let mut asset = self.internal_unwrap_asset(&asset_amount.token_id);
         let collateral_shares = account.internal_unwrap_collateral(&asset_amount.token_id);
 
-        let (shares, amount) =
-            asset_amount_to_shares(&asset.supplied, collateral_shares, &asset_amount, false);
+        let (shares, amount) = asset_amount_to_shares(
+            &asset.supplied,
+            collateral_shares,
+            &asset_amount,
+            Rounding::Up,
+        );
 
         account.decrease_collateral(&asset_amount.token_id, shares);
+        asset.decrease_collateral_shares(shares);
+        self.internal_set_asset(&asset_amount.token_id, asset);
 
         account_asset.deposit_shares(shares);
 
-        amount
+        (shares, amount)
     }
 
     pub fn internal_borrow(
         &mut self,
         account: &mut Account,
         asset_amount: &AssetAmount,
-    ) -> Balance {
-        let mut asset = self.internal_unwrap_asset(&asset_amount.token_id);
-        assert!(asset.config.can_borrow, "Thi asset can't be used borrowed");
+    ) -> (Shares, Balance) {
+        let (borrowed_shares, amount) = self.internal_increase_borrowed(account, asset_amount);
 
+        let mut asset = self.internal_unwrap_asset(&asset_amount.token_id);
         let mut account_asset = account.internal_get_asset_or_default(&asset_amount.token_id);
 
+        let supplied_shares: Shares = asset.supplied.amount_to_shares(amount, Rounding::Down);
+
+        asset.supplied.deposit(supplied_shares, amount);
+        self.internal_set_asset(&asset_amount.token_id, asset);
+
+        account_asset.deposit_shares(supplied_shares);
+        account.internal_set_asset(&asset_amount.token_id, account_asset);
+
+        (borrowed_shares, amount)
+    }
+
+    /// Borrows the given amount without depositing it to the account supplied balance, so the
+    /// borrowed amount can be transferred out of the contract exactly. The amount is rounded down
+    /// to the token decimals, so the dust of the extra decimals that can't be transferred is not
+    /// borrowed.
+    pub fn internal_borrow_and_withdraw(
+        &mut self,
+        account: &mut Account,
+        asset_amount: &AssetAmount,
+    ) -> (Shares, Balance) {
+        let asset = self.internal_unwrap_asset(&asset_amount.token_id);
+        assert!(
+            !asset.config.is_paused(PausableAction::Withdraw),
+            "Withdrawals for this asset are not enabled"
+        );
         let available_amount = asset.available_amount();
-        let max_borrow_shares = asset.borrowed.amount_to_shares(available_amount, false);
+        let amount = match (asset_amount.amount, asset_amount.max_amount) {
+            (Some(amount), _) => amount.0,
+            (None, Some(max_amount)) => std::cmp::min(max_amount.0, available_amount),
+            (None, None) => available_amount,
+        };
+        let amount = amount - amount % 10u128.pow(asset.config.extra_decimals as u32);
+        self.internal_increase_borrowed(
+            account,
+            &AssetAmount {
+                token_id: asset_amount.token_id.clone(),
+                amount: Some(amount.into()),
+                max_amount: None,
+            },
+        )
+    }
 
-        let (borrowed_shares, amount) =
-            asset_amount_to_shares(&asset.borrowed, max_borrow_shares, &asset_amount, true);
+    fn internal_increase_borrowed(
+        &mut self,
+        account: &mut Account,
+        asset_amount: &AssetAmount,
+    ) -> (Shares, Balance) {
+        let mut asset = self.internal_unwrap_asset(&asset_amount.token_id);
+        assert!(
+            !asset.config.is_paused(PausableAction::Borrow),
+            "Thi asset can't be used borrowed"
+        );
+        assert!(
+            !asset.config.reserve_only,
+            "The reserve-only asset can't be borrowed"
+        );
+        assert!(
+            !asset.config.collateral_only,
+            "The collateral-only asset can't be borrowed"
+        );
+        self.internal_assert_no_flash_loan(&asset_amount.token_id);
+        self.internal_assert_allowlisted(&asset_amount.token_id, &account.account_id);
+
+        let available_amount = asset.available_amount();
+        let max_borrow_shares = asset
+            .borrowed
+            .amount_to_shares(available_amount, Rounding::Down);
+
+        let (borrowed_shares, amount) = asset_amount_to_shares(
+            &asset.borrowed,
+            max_borrow_shares,
+            &asset_amount,
+            Rounding::Up,
+        );
 
         assert!(
             amount <= available_amount,
@@ -249,18 +714,16 @@ impl Contract {
             &asset_amount.token_id
         );
 
-        let supplied_shares: Shares = asset.supplied.amount_to_shares(amount, false);
-
         asset.borrowed.deposit(borrowed_shares, amount);
-        asset.supplied.deposit(supplied_shares, amount);
-        self.internal_set_asset(&asset_amount.token_id, asset);
-
+        asset.assert_borrow_cap();
         account.increase_borrowed(&asset_amount.token_id, borrowed_shares);
+        asset.assert_min_borrow_amount(account.internal_unwrap_borrowed(&asset_amount.token_id));
+        self.internal_assert_risk_tiers(account);
+        self.internal_set_asset(&asset_amount.token_id, asset);
+        self.internal_increase_isolated_debt(account, &asset_amount.token_id, amount);
+        self.assert_max_total_borrowed_value();
 
-        account_asset.deposit_shares(supplied_shares);
-        account.internal_set_asset(&asset_amount.token_id, account_asset);
-
-        amount
+        (borrowed_shares, amount)
     }
 
     pub fn internal_repay(
@@ -268,40 +731,76 @@ impl Contract {
         account_asset: &mut AccountAsset,
         account: &mut Account,
         asset_amount: &AssetAmount,
-    ) -> Balance {
+    ) -> (Shares, Balance) {
         let mut asset = self.internal_unwrap_asset(&asset_amount.token_id);
         let available_borrowed_shares = account.internal_unwrap_borrowed(&asset_amount.token_id);
 
-        let (mut borrowed_shares, mut amount) = asset_amount_to_shares(
-            &asset.borrowed,
+        let (borrowed_shares, supplied_shares, amount) = compute_repayment(
+            &asset,
+            account_asset,
             available_borrowed_shares,
-            &asset_amount,
-            true,
+            asset_amount,
         );
 
-        let mut supplied_shares = asset.supplied.amount_to_shares(amount, true);
-        if supplied_shares.0 > account_asset.shares.0 {
-            supplied_shares = account_asset.shares;
-            amount = asset.supplied.shares_to_amount(supplied_shares, false);
-            if let Some(min_amount) = &asset_amount.amount {
-                assert!(amount >= min_amount.0, "Not enough supplied balance");
-            }
-            assert!(amount > 0, "Repayment amount can't be 0");
-
-            borrowed_shares = asset.borrowed.amount_to_shares(amount, false);
-            assert!(borrowed_shares.0 > 0, "Shares can't be 0");
-            assert!(borrowed_shares.0 <= available_borrowed_shares.0);
-        }
-
         asset.supplied.withdraw(supplied_shares, amount);
         asset.borrowed.withdraw(borrowed_shares, amount);
         self.internal_set_asset(&asset_amount.token_id, asset);
+        let isolated_token_id = self.internal_get_isolated_collateral(account);
+        self.internal_decrease_isolated_debt(&isolated_token_id, amount);
 
         account.decrease_borrowed(&asset_amount.token_id, borrowed_shares);
 
         account_asset.withdraw_shares(supplied_shares);
 
-        amount
+        (borrowed_shares, amount)
+    }
+
+    /// Repays the borrowed balance of the asset from the supplied balance and the collateral of
+    /// the same asset. The supplied balance is used first.
+    pub fn internal_net(
+        &mut self,
+        account: &mut Account,
+        asset_amount: &AssetAmount,
+    ) -> (Shares, Balance) {
+        let token_id = &asset_amount.token_id;
+        let mut account_asset = account.internal_get_asset_or_default(token_id);
+        let collateral_shares = account
+            .collateral
+            .get(token_id)
+            .cloned()
+            .unwrap_or_else(|| 0.into());
+        if collateral_shares.0 > 0 {
+            account.decrease_collateral(token_id, collateral_shares);
+            account_asset.deposit_shares(collateral_shares);
+        }
+
+        let (borrowed_shares, amount) =
+            self.internal_repay(&mut account_asset, account, asset_amount);
+
+        let restored_shares: Shares =
+            std::cmp::min(collateral_shares.0, account_asset.shares.0).into();
+        if restored_shares.0 > 0 {
+            account_asset.withdraw_shares(restored_shares);
+            account.increase_collateral(token_id, restored_shares);
+        }
+        let used_collateral_shares: Shares = (collateral_shares.0 - restored_shares.0).into();
+        if used_collateral_shares.0 > 0 {
+            let mut asset = self.internal_unwrap_asset(token_id);
+            let used_collateral_amount = asset
+                .supplied
+                .shares_to_amount(used_collateral_shares, Rounding::Down);
+            asset.decrease_collateral_shares(used_collateral_shares);
+            self.internal_set_asset(token_id, asset);
+            events::emit::decrease_collateral(
+                &account.account_id,
+                used_collateral_amount,
+                used_collateral_shares,
+                token_id,
+            );
+        }
+        account.internal_set_asset(token_id, account_asset);
+
+        (borrowed_shares, amount)
     }
 
     pub fn internal_liquidate(
@@ -313,6 +812,9 @@ impl Contract {
         in_assets: Vec<AssetAmount>,
         out_assets: Vec<AssetAmount>,
     ) {
+        for asset_amount in in_assets.iter().chain(out_assets.iter()) {
+            self.internal_assert_not_paused(&asset_amount.token_id, PausableAction::Liquidate);
+        }
         let mut liquidation_account = self.internal_unwrap_account(liquidation_account_id);
 
         let max_discount = self.compute_max_discount(&liquidation_account, &prices);
@@ -323,11 +825,13 @@ impl Contract {
 
         let mut borrowed_repaid_sum = BigDecimal::zero();
         let mut collateral_taken_sum = BigDecimal::zero();
+        let mut repaid_assets = vec![];
+        let mut collateral_assets = vec![];
 
         for asset_amount in in_assets {
             liquidation_account.add_affected_farm(FarmId::Borrowed(asset_amount.token_id.clone()));
             let mut account_asset = account.internal_unwrap_asset(&asset_amount.token_id);
-            let amount =
+            let (shares, amount) =
                 self.internal_repay(&mut account_asset, &mut liquidation_account, &asset_amount);
             account.internal_set_asset(&asset_amount.token_id, account_asset);
             let asset = self.internal_unwrap_asset(&asset_amount.token_id);
@@ -338,13 +842,15 @@ impl Contract {
                     prices.get_unwrap(&asset_amount.token_id),
                     asset.config.extra_decimals,
                 );
+            repaid_assets.push((asset_amount.token_id, amount, shares));
         }
 
         for asset_amount in out_assets {
+            prices.assert_seizable(&asset_amount.token_id);
             let asset = self.internal_unwrap_asset(&asset_amount.token_id);
             liquidation_account.add_affected_farm(FarmId::Supplied(asset_amount.token_id.clone()));
             let mut account_asset = account.internal_get_asset_or_default(&asset_amount.token_id);
-            let amount = self.internal_decrease_collateral(
+            let (shares, amount) = self.internal_decrease_collateral(
                 &mut account_asset,
                 &mut liquidation_account,
                 &asset_amount,
@@ -357,6 +863,7 @@ impl Contract {
                     prices.get_unwrap(&asset_amount.token_id),
                     asset.config.extra_decimals,
                 );
+            collateral_assets.push((asset_amount.token_id, amount, shares));
         }
 
         let discounted_collateral_taken = collateral_taken_sum * (BigDecimal::one() - max_discount);
@@ -379,6 +886,7 @@ impl Contract {
         );
 
         self.internal_account_apply_affected_farms(&mut liquidation_account);
+        self.internal_emit_account_health(&liquidation_account);
         self.internal_set_account(liquidation_account_id, liquidation_account);
 
         events::emit::liquidate(
@@ -386,7 +894,21 @@ impl Contract {
             &liquidation_account_id,
             &collateral_taken_sum,
             &borrowed_repaid_sum,
+            &repaid_assets,
+            &collateral_assets,
         );
+
+        for (token_id, amount, shares) in collateral_assets {
+            if parse_pool_id(&token_id).is_some() {
+                self.internal_unwind_lp_shares(
+                    account,
+                    liquidation_account_id,
+                    &token_id,
+                    shares,
+                    amount,
+                );
+            }
+        }
     }
 
     pub fn internal_force_close(&mut self, prices: &Prices, liquidation_account_id: &AccountId) {
@@ -397,17 +919,25 @@ impl Contract {
         );
 
         let mut liquidation_account = self.internal_unwrap_account(liquidation_account_id);
+        assert!(
+            self.internal_get_nft_collateral(liquidation_account_id)
+                .is_empty(),
+            "The NFT collateral has to be auctioned first"
+        );
 
         let mut borrowed_sum = BigDecimal::zero();
         let mut collateral_sum = BigDecimal::zero();
 
         let mut affected_farms = vec![];
+        let isolated_token_id = self.internal_get_isolated_collateral(&liquidation_account);
 
         for (token_id, shares) in liquidation_account.collateral.drain() {
+            prices.assert_seizable(&token_id);
             let mut asset = self.internal_unwrap_asset(&token_id);
-            let amount = asset.supplied.shares_to_amount(shares, false);
+            let amount = asset.supplied.shares_to_amount(shares, Rounding::Down);
             asset.reserved += amount;
             asset.supplied.withdraw(shares, amount);
+            asset.decrease_collateral_shares(shares);
 
             collateral_sum = collateral_sum
                 + BigDecimal::from_balance_price(
@@ -421,7 +951,7 @@ impl Contract {
 
         for (token_id, shares) in liquidation_account.borrowed.drain() {
             let mut asset = self.internal_unwrap_asset(&token_id);
-            let amount = asset.borrowed.shares_to_amount(shares, true);
+            let amount = asset.borrowed.shares_to_amount(shares, Rounding::Up);
             assert!(
                 asset.reserved >= amount,
                 "Not enough {} in reserve",
@@ -429,6 +959,7 @@ impl Contract {
             );
             asset.reserved -= amount;
             asset.borrowed.withdraw(shares, amount);
+            self.internal_decrease_isolated_debt(&isolated_token_id, amount);
 
             borrowed_sum = borrowed_sum
                 + BigDecimal::from_balance_price(
@@ -449,26 +980,106 @@ impl Contract {
         liquidation_account.affected_farms.extend(affected_farms);
 
         self.internal_account_apply_affected_farms(&mut liquidation_account);
+        self.internal_emit_account_health(&liquidation_account);
         self.internal_set_account(liquidation_account_id, liquidation_account);
 
         events::emit::force_close(&liquidation_account_id, &collateral_sum, &borrowed_sum);
     }
 
+    /// Verifies the given constraints against the account state after the actions are executed.
+    pub fn internal_assert_constraints(
+        &self,
+        account: &Account,
+        prices: &Prices,
+        constraints: &ExecuteConstraints,
+    ) {
+        if let Some(deadline_ts) = constraints.deadline_ts {
+            assert!(
+                env::block_timestamp() <= deadline_ts.0,
+                "The deadline for the actions has passed"
+            );
+        }
+        if let Some(min_health_after) = constraints.min_health_after {
+            if !account.borrowed.is_empty() {
+                let (collateral_sum, borrowed_sum) = self.compute_adjusted_sums(account, prices);
+                assert!(
+                    collateral_sum >= borrowed_sum.mul_ratio(min_health_after),
+                    "The health factor is below the minimum: adjusted collateral {} < adjusted borrowed {} * {}",
+                    collateral_sum,
+                    borrowed_sum,
+                    BigDecimal::from_ratio(min_health_after)
+                );
+            }
+        }
+    }
+
+    /// Asserts that the account stays above the minimum health factor of the borrows and the
+    /// withdrawals of the collateral, if it's set in the config.
+    pub fn internal_assert_min_borrow_health(&self, account: &Account, prices: &Prices) {
+        let min_borrow_health = match self.internal_config().min_borrow_health_bps {
+            Some(min_borrow_health) => min_borrow_health,
+            None => return,
+        };
+        if account.borrowed.is_empty() {
+            return;
+        }
+        let (collateral_sum, borrowed_sum) = self.compute_adjusted_sums(account, prices);
+        assert!(
+            collateral_sum >= borrowed_sum.mul_ratio(min_borrow_health),
+            "The health factor is below the minimum for the borrows: adjusted collateral {} < adjusted borrowed {} * {}",
+            collateral_sum,
+            borrowed_sum,
+            BigDecimal::from_ratio(min_borrow_health)
+        );
+    }
+
+    /// Panics if the given action is paused for the asset.
+    pub fn internal_assert_not_paused(&self, token_id: &TokenId, action: PausableAction) {
+        let asset = self.internal_unwrap_asset(token_id);
+        assert!(
+            !asset.config.is_paused(action),
+            "The action is paused for the asset {}",
+            token_id
+        );
+    }
+
     pub fn compute_max_discount(&self, account: &Account, prices: &Prices) -> BigDecimal {
         if account.borrowed.is_empty() {
             return BigDecimal::zero();
         }
 
-        let collateral_sum =
-            account
+        let (collateral_sum, borrowed_sum) = self.compute_adjusted_sums(account, prices);
+
+        if borrowed_sum <= collateral_sum {
+            BigDecimal::zero()
+        } else {
+            (borrowed_sum - collateral_sum) / borrowed_sum / BigDecimal::from(2u32)
+        }
+    }
+
+    /// Returns the sum of collateral adjusted by volatility ratios and the sum of borrowed assets
+    /// adjusted by volatility ratios. The collateral includes the expected collateral from the
+    /// leverage swap in progress. The collateral with a missing or zero price is valued at zero.
+    pub fn compute_adjusted_sums(
+        &self,
+        account: &Account,
+        prices: &Prices,
+    ) -> (BigDecimal, BigDecimal) {
+        let collateral_sum = self.compute_pending_collateral_sum(&account.account_id, prices)
+            + self.compute_nft_collateral_sum(&account.account_id, prices)
+            + account
                 .collateral
                 .iter()
                 .fold(BigDecimal::zero(), |sum, (token_id, shares)| {
+                    let price = match prices.get_valid(&token_id) {
+                        Some(price) => price,
+                        None => return sum,
+                    };
                     let asset = self.internal_unwrap_asset(&token_id);
-                    let balance = asset.supplied.shares_to_amount(*shares, false);
+                    let balance = asset.supplied.shares_to_amount(*shares, Rounding::Down);
                     sum + BigDecimal::from_balance_price(
                         balance,
-                        prices.get_unwrap(&token_id),
+                        price,
                         asset.config.extra_decimals,
                     )
                     .mul_ratio(asset.config.volatility_ratio)
@@ -480,7 +1091,7 @@ impl Contract {
                 .iter()
                 .fold(BigDecimal::zero(), |sum, (token_id, shares)| {
                     let asset = self.internal_unwrap_asset(&token_id);
-                    let balance = asset.borrowed.shares_to_amount(*shares, true);
+                    let balance = asset.borrowed.shares_to_amount(*shares, Rounding::Up);
                     sum + BigDecimal::from_balance_price(
                         balance,
                         prices.get_unwrap(&token_id),
@@ -489,60 +1100,58 @@ impl Contract {
                     .div_ratio(asset.config.volatility_ratio)
                 });
 
-        if borrowed_sum <= collateral_sum {
-            BigDecimal::zero()
-        } else {
-            (borrowed_sum - collateral_sum) / borrowed_sum / BigDecimal::from(2u32)
-        }
+        (collateral_sum, borrowed_sum)
     }
-}
 
-fn asset_amount_to_shares(
-    pool: &Pool,
-    available_shares: Shares,
-    asset_amount: &AssetAmount,
-    inverse_round_direction: bool,
-) -> (Shares, Balance) {
-    let (shares, amount) = if let Some(amount) = &asset_amount.amount {
-        (
-            pool.amount_to_shares(amount.0, !inverse_round_direction),
-            amount.0,
-        )
-    } else if let Some(max_amount) = &asset_amount.max_amount {
-        let shares = std::cmp::min(
-            available_shares.0,
-            pool.amount_to_shares(max_amount.0, !inverse_round_direction)
-                .0,
-        )
-        .into();
-        (
-            shares,
-            std::cmp::min(
-                pool.shares_to_amount(shares, inverse_round_direction),
-                max_amount.0,
-            ),
-        )
-    } else {
-        (
-            available_shares,
-            pool.shares_to_amount(available_shares, inverse_round_direction),
-        )
-    };
-    assert!(shares.0 > 0, "Shares can't be 0");
-    assert!(amount > 0, "Amount can't be 0");
-    (shares, amount)
+    /// Emits the adjusted collateral and borrowed sums of the account computed with the last
+    /// prices, so the health of the account can be tracked without the raw balances. Skipped if
+    /// the last price of any asset of the account is missing or the heavy events are disabled.
+    pub fn internal_emit_account_health(&mut self, account: &Account) {
+        self.internal_emit_heavy_events(|contract| {
+            let prices = contract.internal_last_prices();
+            let pending_collateral_token_id = contract
+                .internal_get_active_leverage_swap(&account.account_id)
+                .map(|leverage_swap| leverage_swap.collateral_token_id);
+            let all_priced = account
+                .collateral
+                .keys()
+                .chain(account.borrowed.keys())
+                .chain(pending_collateral_token_id.iter())
+                .all(|token_id| prices.contains(token_id));
+            if all_priced {
+                let (collateral_sum, borrowed_sum) =
+                    contract.compute_adjusted_sums(account, &prices);
+                events::emit::account_health(&account.account_id, &collateral_sum, &borrowed_sum);
+            }
+        });
+    }
 }
 
 #[near_bindgen]
 impl Contract {
-    /// Executes a given list actions on behalf of the predecessor account.
+    /// Executes a given list actions on behalf of the predecessor account without prices.
+    /// The actions that don't fit into the gas of the call are executed by a continuation.
+    /// - Optional constraints are verified after all actions are executed.
+    /// - Panics if any of the actions requires prices.
+    /// - Panics if the account is locked by the pending callbacks of the multi-receipt flows.
+    /// - Panics if the actions fail the validation before the execution, e.g. an asset doesn't
+    ///   exist, an action is paused or a balance is not enough.
     /// - Requires one yoctoNEAR.
     #[payable]
-    pub fn execute(&mut self, actions: Vec<Action>) {
+    pub fn execute(&mut self, actions: Vec<Action>, constraints: Option<ExecuteConstraints>) {
+        assert_not_self_call();
         assert_one_yocto();
         let account_id = env::predecessor_account_id();
-        let mut account = self.internal_unwrap_account(&account_id);
-        self.internal_execute(&account_id, &mut account, actions, Prices::new());
-        self.internal_set_account(&account_id, account);
+        let account = self.internal_unwrap_account(&account_id);
+        assert_actions_without_prices(&actions);
+        self.internal_assert_account_not_locked(&account_id);
+        self.internal_prevalidate_actions(&account, &actions);
+        self.internal_execute_with_continuation(
+            &account_id,
+            account,
+            actions,
+            Prices::new(),
+            constraints,
+        );
     }
 }
@@ -1,10 +1,24 @@
 use crate::*;
+use near_sdk::json_types::Base64VecU8;
+use std::collections::BTreeSet;
 
 pub const MS_PER_YEAR: u64 = 31536000000;
 
+/// The longest period compounded with a single exponentiation. The longer gaps between the
+/// updates of an asset are compounded in chunks, so the intermediate values stay bounded.
+pub const MAX_COMPOUND_CHUNK_MS: u64 = MS_PER_YEAR;
+
+/// The cap of the borrowed balance of an asset accrued by the interest, so the balances of the
+/// pools and their sums can't overflow after a long dormancy.
+pub const MAX_COMPOUNDED_BALANCE: Balance = u128::MAX / 4;
+
 static ASSETS: Lazy<Mutex<HashMap<TokenId, Option<Asset>>>> =
     Lazy::new(|| Mutex::new(HashMap::new()));
 
+/// The assets changed by the current batch, e.g. by the actions of an execute call. They are
+/// kept in the `ASSETS` cache and saved once when the batch is flushed.
+static PENDING_ASSETS: Lazy<Mutex<Option<BTreeSet<TokenId>>>> = Lazy::new(|| Mutex::new(None));
+
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Clone)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
 #[serde(crate = "near_sdk::serde")]
@@ -22,27 +36,89 @@ pub struct Asset {
     pub last_update_timestamp: Timestamp,
     /// The asset config.
     pub config: AssetConfig,
+    /// The total amount of the protocol fees collected to the reserve, e.g. the reserved part of
+    /// the interest and the flash loan fees.
+    #[serde(with = "u128_dec_format")]
+    pub collected_protocol_fees: Balance,
+    /// The total amount of the protocol fees withdrawn from the reserve by the owner.
+    #[serde(with = "u128_dec_format")]
+    pub withdrawn_protocol_fees: Balance,
+    /// The total borrowed balance of the accounts that use this isolated asset as collateral.
+    /// The accrued interest is not counted.
+    #[serde(with = "u128_dec_format")]
+    pub isolated_debt: Balance,
+    /// The total amount lent out with the flash loans of the asset.
+    #[serde(with = "u128_dec_format")]
+    pub flash_loan_volume: Balance,
+    /// The total amount of the flash loan fees of the asset. The fees go to the reserve.
+    #[serde(with = "u128_dec_format")]
+    pub flash_loan_fees: Balance,
+    /// The total supplied shares of the asset that are used as collateral by the accounts.
+    pub collateral_shares: Shares,
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum VAsset {
     V0(AssetV0),
-    Current(Asset),
+    V1(AssetV1),
+    Current(Box<Asset>),
 }
 
 impl From<VAsset> for Asset {
     fn from(v: VAsset) -> Self {
         match v {
             VAsset::V0(v) => v.into(),
-            VAsset::Current(c) => c,
+            VAsset::V1(v) => v.into(),
+            VAsset::Current(c) => *c,
         }
     }
 }
 
 impl From<Asset> for VAsset {
     fn from(c: Asset) -> Self {
-        VAsset::Current(c)
+        VAsset::Current(Box::new(c))
+    }
+}
+
+pub(crate) fn rate_to_apr(rate: BigDecimal) -> BigDecimal {
+    rate.pow(MS_PER_YEAR) - BigDecimal::one()
+}
+
+/// Compounds the balance with the rate per millisecond over the given time. The time is split into
+/// chunks of at most `MAX_COMPOUND_CHUNK_MS`, and the result is capped at
+/// `MAX_COMPOUNDED_BALANCE` instead of overflowing.
+pub(crate) fn compound_balance(
+    rate: BigDecimal,
+    mut balance: Balance,
+    time_diff_ms: Duration,
+) -> Balance {
+    let mut remaining_ms = time_diff_ms;
+    while remaining_ms > 0 && balance < MAX_COMPOUNDED_BALANCE {
+        let chunk_ms = std::cmp::min(remaining_ms, MAX_COMPOUND_CHUNK_MS);
+        remaining_ms -= chunk_ms;
+        balance = rate
+            .checked_pow(chunk_ms)
+            .and_then(|interest_rate| interest_rate.checked_round_mul_u128(balance))
+            .map_or(MAX_COMPOUNDED_BALANCE, |balance| {
+                std::cmp::min(balance, MAX_COMPOUNDED_BALANCE)
+            });
     }
+    balance
+}
+
+pub(crate) fn compute_supply_apr(
+    borrow_apr: BigDecimal,
+    supplied: Balance,
+    borrowed: Balance,
+    reserve_ratio: u32,
+) -> BigDecimal {
+    if supplied == 0 || borrow_apr == BigDecimal::zero() {
+        return BigDecimal::zero();
+    }
+
+    let interest = borrow_apr.round_mul_u128(borrowed);
+    let supply_interest = ratio(interest, MAX_RATIO - reserve_ratio);
+    BigDecimal::from(supply_interest).div_u128(supplied)
 }
 
 impl Asset {
@@ -53,6 +129,12 @@ impl Asset {
             reserved: 0,
             last_update_timestamp: timestamp,
             config,
+            collected_protocol_fees: 0,
+            withdrawn_protocol_fees: 0,
+            isolated_debt: 0,
+            flash_loan_volume: 0,
+            flash_loan_fees: 0,
+            collateral_shares: 0.into(),
         }
     }
 
@@ -62,8 +144,7 @@ impl Asset {
     }
 
     pub fn get_borrow_apr(&self) -> BigDecimal {
-        let rate = self.get_rate();
-        rate.pow(MS_PER_YEAR) - BigDecimal::one()
+        rate_to_apr(self.get_rate())
     }
 
     pub fn get_supply_apr(&self) -> BigDecimal {
@@ -71,14 +152,12 @@ impl Asset {
             return BigDecimal::zero();
         }
 
-        let borrow_apr = self.get_borrow_apr();
-        if borrow_apr == BigDecimal::zero() {
-            return borrow_apr;
-        }
-
-        let interest = borrow_apr.round_mul_u128(self.borrowed.balance);
-        let supply_interest = ratio(interest, MAX_RATIO - self.config.reserve_ratio);
-        BigDecimal::from(supply_interest).div_u128(self.supplied.balance)
+        compute_supply_apr(
+            self.get_borrow_apr(),
+            self.supplied.balance,
+            self.borrowed.balance,
+            self.config.get_protocol_ratio(),
+        )
     }
 
     // n = 31536000000 ms in a year (365 days)
@@ -95,15 +174,16 @@ impl Asset {
     fn compound(&mut self, time_diff_ms: Duration) {
         let rate = self.get_rate();
         let interest =
-            rate.pow(time_diff_ms).round_mul_u128(self.borrowed.balance) - self.borrowed.balance;
-        // TODO: Split interest based on ratio between reserved and supplied?
-        let reserved = ratio(interest, self.config.reserve_ratio);
+            compound_balance(rate, self.borrowed.balance, time_diff_ms) - self.borrowed.balance;
+        let protocol_fee = ratio(interest, self.config.protocol_fee_ratio);
         if self.supplied.shares.0 > 0 {
-            self.supplied.balance += interest - reserved;
-            self.reserved += reserved;
+            let reserved = ratio(interest, self.config.reserve_ratio);
+            self.supplied.balance += interest - reserved - protocol_fee;
+            self.reserved += reserved + protocol_fee;
         } else {
             self.reserved += interest;
         }
+        self.collected_protocol_fees += protocol_fee;
         self.borrowed.balance += interest;
     }
 
@@ -117,9 +197,117 @@ impl Asset {
         }
     }
 
+    /// Returns the ratio of the borrowed balance to the total supplied balance including reserved.
+    pub fn get_utilization(&self) -> BigDecimal {
+        let total_supplied_balance = self.supplied.balance + self.reserved;
+        if total_supplied_balance == 0 {
+            BigDecimal::zero()
+        } else {
+            BigDecimal::from(self.borrowed.balance).div_u128(total_supplied_balance)
+        }
+    }
+
     pub fn available_amount(&self) -> Balance {
         self.supplied.balance + self.reserved - self.borrowed.balance
     }
+
+    /// Returns the protocol fees that are still in the reserve. The reserve can also be used to
+    /// cover bad debt, so the pending fees can't exceed the reserved balance.
+    pub fn get_pending_protocol_fees(&self) -> Balance {
+        std::cmp::min(
+            self.collected_protocol_fees - self.withdrawn_protocol_fees,
+            self.reserved,
+        )
+    }
+
+    pub fn assert_supply_cap(&self) {
+        if let Some(supply_cap) = self.config.supply_cap {
+            assert!(
+                self.supplied.balance <= supply_cap.0,
+                "The supply cap of the asset is exceeded"
+            );
+        }
+    }
+
+    /// Asserts that the total collateral balance of the asset together with the pending
+    /// collateral amount doesn't exceed the collateral cap.
+    pub fn assert_collateral_cap(&self, pending_amount: Balance) {
+        if let Some(collateral_cap) = self.config.collateral_cap {
+            assert!(
+                self.supplied
+                    .shares_to_amount(self.collateral_shares, Rounding::Down)
+                    + pending_amount
+                    <= collateral_cap.0,
+                "The collateral cap of the asset is exceeded"
+            );
+        }
+    }
+
+    /// Decreases the total collateral shares of the asset. The total of the assets migrated from
+    /// the state before it was tracked is recounted with `backfill_collateral_shares`.
+    pub fn decrease_collateral_shares(&mut self, shares: Shares) {
+        self.collateral_shares.0 = self
+            .collateral_shares
+            .0
+            .checked_sub(shares.0)
+            .expect("The collateral shares of the asset are inconsistent");
+    }
+
+    pub fn assert_borrow_cap(&self) {
+        if let Some(borrow_cap) = self.config.borrow_cap {
+            assert!(
+                self.borrowed.balance <= borrow_cap.0,
+                "The borrow cap of the asset is exceeded"
+            );
+        }
+    }
+
+    /// Returns the amount that can still be supplied under the supply cap, if it's set.
+    pub fn remaining_supply_capacity(&self) -> Option<Balance> {
+        self.config
+            .supply_cap
+            .map(|supply_cap| supply_cap.0.saturating_sub(self.supplied.balance))
+    }
+
+    /// Returns the amount that can still be borrowed under the borrow cap, if it's set. The borrow
+    /// is also limited by the available amount of the asset.
+    pub fn remaining_borrow_capacity(&self) -> Option<Balance> {
+        self.config
+            .borrow_cap
+            .map(|borrow_cap| borrow_cap.0.saturating_sub(self.borrowed.balance))
+    }
+
+    /// Returns the amount that can still be added to the collateral under the collateral cap, if
+    /// it's set.
+    pub fn remaining_collateral_capacity(&self) -> Option<Balance> {
+        self.config.collateral_cap.map(|collateral_cap| {
+            collateral_cap.0.saturating_sub(
+                self.supplied
+                    .shares_to_amount(self.collateral_shares, Rounding::Down),
+            )
+        })
+    }
+
+    /// Asserts that the borrowed balance of the account in the asset is not below the minimum
+    /// borrow amount.
+    pub fn assert_min_borrow_amount(&self, borrowed_shares: Shares) {
+        if let Some(min_borrow_amount) = self.config.min_borrow_amount {
+            assert!(
+                self.borrowed
+                    .shares_to_amount(borrowed_shares, Rounding::Up)
+                    >= min_borrow_amount.0,
+                "The borrowed balance is below the minimum borrow amount {}",
+                min_borrow_amount.0
+            );
+        }
+    }
+
+    /// Takes the given amount from the reserve on behalf of the owner. The part of the amount
+    /// covered by the pending protocol fees is recorded as withdrawn fees.
+    pub fn withdraw_reserved(&mut self, amount: Balance) {
+        self.withdrawn_protocol_fees += std::cmp::min(amount, self.get_pending_protocol_fees());
+        self.reserved -= amount;
+    }
 }
 
 impl Contract {
@@ -155,8 +343,60 @@ impl Contract {
             .lock()
             .unwrap()
             .insert(token_id.clone(), Some(asset.clone()));
+        if let Some(pending_assets) = PENDING_ASSETS.lock().unwrap().as_mut() {
+            pending_assets.insert(token_id.clone());
+            return;
+        }
+        self.internal_save_asset(token_id, asset);
+    }
+
+    fn internal_save_asset(&mut self, token_id: &TokenId, asset: Asset) {
+        self.internal_record_asset_snapshot(token_id, &asset);
+        self.internal_emit_asset_rates(token_id, &asset);
+        self.internal_record_protocol_deltas(token_id, &asset);
         self.assets.insert(token_id, &asset.into());
     }
+
+    /// Replaces the cached asset without saving it, so the following reads of the asset within
+    /// the call see the given asset. Used by the simulation of the actions in the view calls.
+    pub fn internal_cache_asset(&self, token_id: &TokenId, asset: Asset) {
+        asset.supplied.assert_invariant();
+        asset.borrowed.assert_invariant();
+        ASSETS.lock().unwrap().insert(token_id.clone(), Some(asset));
+    }
+
+    /// Starts the batch of the asset changes unless a batch is already started. Returns whether
+    /// the batch was started, so only the outermost caller flushes it.
+    pub fn internal_start_assets_batch(&self) -> bool {
+        let mut pending_assets = PENDING_ASSETS.lock().unwrap();
+        if pending_assets.is_some() {
+            return false;
+        }
+        pending_assets.replace(BTreeSet::new());
+        true
+    }
+
+    /// Saves the assets changed by the batch and ends the batch.
+    pub fn internal_flush_assets(&mut self) {
+        let pending_assets = PENDING_ASSETS.lock().unwrap().take().unwrap_or_default();
+        for token_id in pending_assets {
+            if let Some(asset) = self.internal_get_asset(&token_id) {
+                self.internal_save_asset(&token_id, asset);
+            }
+        }
+    }
+
+    pub fn internal_remove_asset(&mut self, token_id: &TokenId) {
+        if let Some(pending_assets) = PENDING_ASSETS.lock().unwrap().as_mut() {
+            pending_assets.remove(token_id);
+        }
+        ASSETS.lock().unwrap().insert(token_id.clone(), None);
+        self.assets.remove(token_id);
+        self.asset_ids.remove(token_id);
+        if let Some(mt_token) = self.mt_tokens.remove(token_id) {
+            self.mt_asset_ids.remove(&mt_token);
+        }
+    }
 }
 
 #[near_bindgen]
@@ -167,6 +407,13 @@ impl Contract {
             .map(|asset| self.asset_into_detailed_view(token_id, asset))
     }
 
+    /// Returns the detailed view of the asset for a given token_id, including the utilization, the
+    /// available liquidity, the current APRs, the reserve, the farms and the config.
+    pub fn get_asset_detailed(&self, token_id: AccountId) -> AssetDetailedView {
+        let asset = self.internal_unwrap_asset(&token_id);
+        self.asset_into_detailed_view(token_id, asset)
+    }
+
     /// Returns an list of pairs (token_id, asset) for assets a given list of token_id.
     /// Only returns pais for existing assets.
     pub fn get_assets(&self, token_ids: Vec<AccountId>) -> Vec<AssetDetailedView> {
@@ -198,6 +445,8 @@ impl Contract {
             .collect()
     }
 
+    /// Returns the detailed asset views from a given index up to a given limit. Prefer
+    /// `get_assets_page_detailed`, which ends the page before the view runs out of gas.
     pub fn get_assets_paged_detailed(
         &self,
         from_index: Option<u64>,
@@ -215,4 +464,144 @@ impl Contract {
             })
             .collect()
     }
+
+    /// Returns a page of pairs (token_id, asset) starting from the given continuation token.
+    /// The page includes the total number of assets and the token for the next page.
+    pub fn get_assets_page(
+        &self,
+        token: Option<String>,
+        limit: Option<u64>,
+    ) -> Page<(TokenId, Asset)> {
+        let keys = self.asset_ids.as_vector();
+        Page::new(keys.len(), token, limit, |index| {
+            let key = keys.get(index).unwrap();
+            let mut asset: Asset = self.assets.get(&key).unwrap().into();
+            asset.update();
+            (key, asset)
+        })
+    }
+
+    /// Returns the Borsh-serialized list of all assets as `Vec<(TokenId, Asset)>`. This method can
+    /// be used by indexers to sync the full state without the JSON serialization overhead.
+    pub fn get_assets_raw(&self) -> Base64VecU8 {
+        let assets: Vec<(TokenId, Asset)> = self
+            .asset_ids
+            .iter()
+            .map(|token_id| {
+                let asset = self.internal_unwrap_asset(&token_id);
+                (token_id, asset)
+            })
+            .collect();
+        assets.try_to_vec().unwrap().into()
+    }
+
+    /// Returns a page of detailed asset views starting from the given continuation token.
+    /// The page includes the total number of assets and the token for the next page. The page
+    /// ends early once the gas for the page is used.
+    pub fn get_assets_page_detailed(
+        &self,
+        token: Option<String>,
+        limit: Option<u64>,
+    ) -> Page<AssetDetailedView> {
+        let keys = self.asset_ids.as_vector();
+        Page::new(keys.len(), token, limit, |index| {
+            let token_id = keys.get(index).unwrap();
+            let mut asset: Asset = self.assets.get(&token_id).unwrap().into();
+            asset.update();
+            self.asset_into_detailed_view(token_id, asset)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use near_sdk::json_types::U128;
+    use proptest::prelude::*;
+
+    // 100% APR.
+    const RATE: u128 = 1000000000021979552909930328;
+
+    #[test]
+    fn test_compound_balance_chunks() {
+        let rate = BigDecimal::from(U128(RATE));
+        let balance = 12345 * 10u128.pow(24);
+        assert_eq!(
+            compound_balance(rate, balance, MS_PER_YEAR),
+            rate.pow(MS_PER_YEAR).round_mul_u128(balance)
+        );
+        // The dormancy of 10 years and 1 ms is compounded in chunks of a year, doubling the balance
+        // every year.
+        let compounded = compound_balance(rate, balance, 10 * MS_PER_YEAR + 1);
+        let expected = rate.round_mul_u128(balance * 1024);
+        let precision = expected / 10u128.pow(12);
+        assert!(compounded > expected - precision && compounded < expected + precision);
+    }
+
+    #[test]
+    fn test_compound_balance_cap() {
+        let rate = BigDecimal::from(U128(RATE));
+        let balance = 10u128.pow(30);
+        // 2 ** 200 overflows the balance.
+        assert_eq!(
+            compound_balance(rate, balance, 200 * MS_PER_YEAR),
+            MAX_COMPOUNDED_BALANCE
+        );
+        assert_eq!(compound_balance(rate, balance, 0), balance);
+    }
+
+    // The rates of up to 100% APR.
+    fn any_rate() -> impl Strategy<Value = BigDecimal> {
+        (10u128.pow(27)..=RATE).prop_map(|raw| BigDecimal::from(U128(raw)))
+    }
+
+    fn any_balance() -> impl Strategy<Value = Balance> {
+        0..10u128.pow(30)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_compound_balance_doesnt_decrease(
+            rate in any_rate(),
+            balance in any_balance(),
+            time_diff_ms in 0..10 * MS_PER_YEAR
+        ) {
+            prop_assert!(compound_balance(rate, balance, time_diff_ms) >= balance);
+        }
+
+        #[test]
+        fn prop_compound_balance_monotonic(
+            rate_a in any_rate(),
+            rate_b in any_rate(),
+            balance_a in any_balance(),
+            balance_b in any_balance(),
+            time_diff_ms in 0..3 * MS_PER_YEAR
+        ) {
+            let (low_rate, high_rate) = if rate_a < rate_b {
+                (rate_a, rate_b)
+            } else {
+                (rate_b, rate_a)
+            };
+            let low_balance = std::cmp::min(balance_a, balance_b);
+            let high_balance = std::cmp::max(balance_a, balance_b);
+            let compounded = compound_balance(low_rate, low_balance, time_diff_ms);
+            prop_assert!(compounded <= compound_balance(low_rate, high_balance, time_diff_ms));
+            prop_assert!(compounded <= compound_balance(high_rate, low_balance, time_diff_ms));
+        }
+
+        #[test]
+        fn prop_compound_balance_associative(
+            rate in any_rate(),
+            balance in 10u128.pow(18)..10u128.pow(30),
+            first_ms in 0..MS_PER_YEAR,
+            second_ms in 0..MS_PER_YEAR
+        ) {
+            // Compounding in two steps only differs by the rounding of the intermediate balance.
+            let at_once = compound_balance(rate, balance, first_ms + second_ms);
+            let in_steps =
+                compound_balance(rate, compound_balance(rate, balance, first_ms), second_ms);
+            let precision = at_once / 10u128.pow(12) + 2;
+            prop_assert!(at_once.max(in_steps) - at_once.min(in_steps) <= precision);
+        }
+    }
 }
@@ -0,0 +1,129 @@
+use crate::*;
+
+/// Adds or removes the account from the index of the given asset. Only saves the index if it
+/// has changed.
+fn update_asset_accounts_index<F: FnOnce() -> StorageKey>(
+    index: &mut LookupMap<TokenId, UnorderedSet<AccountId>>,
+    token_id: &TokenId,
+    account_id: &AccountId,
+    is_present: bool,
+    prefix: F,
+) {
+    let mut account_ids = index
+        .get(token_id)
+        .unwrap_or_else(|| UnorderedSet::new(prefix()));
+    let changed = if is_present {
+        account_ids.insert(account_id)
+    } else {
+        account_ids.remove(account_id)
+    };
+    if changed {
+        index.insert(token_id, &account_ids);
+    }
+}
+
+impl Contract {
+    /// Updates the indexes of the suppliers and the borrowers for the assets that were affected
+    /// by the account changes.
+    pub fn internal_update_asset_accounts(&mut self, account: &Account) {
+        for farm_id in account.affected_farms.iter() {
+            self.internal_update_asset_accounts_index(account, farm_id);
+        }
+    }
+
+    fn internal_update_asset_accounts_index(&mut self, account: &Account, farm_id: &FarmId) {
+        match farm_id {
+            FarmId::Supplied(token_id) => {
+                let token_index = self.internal_token_index(token_id);
+                update_asset_accounts_index(
+                    &mut self.asset_suppliers,
+                    token_id,
+                    &account.account_id,
+                    account.get_supplied_shares(token_id).0 > 0,
+                    || StorageKey::AssetSuppliersByIndex { token_index },
+                )
+            }
+            FarmId::Borrowed(token_id) => {
+                let token_index = self.internal_token_index(token_id);
+                update_asset_accounts_index(
+                    &mut self.asset_borrowers,
+                    token_id,
+                    &account.account_id,
+                    account.get_borrowed_shares(token_id).0 > 0,
+                    || StorageKey::AssetBorrowersByIndex { token_index },
+                )
+            }
+            FarmId::NetTvl => {}
+        }
+    }
+}
+
+pub(crate) fn asset_accounts_page(
+    index: &LookupMap<TokenId, UnorderedSet<AccountId>>,
+    token_id: &TokenId,
+    token: Option<String>,
+    limit: Option<u64>,
+) -> Page<AccountId> {
+    match index.get(token_id) {
+        Some(account_ids) => {
+            let account_ids = account_ids.as_vector();
+            Page::new(account_ids.len(), token, limit, |index| {
+                account_ids.get(index).unwrap()
+            })
+        }
+        None => Page::new(0, token, limit, |_| unreachable!()),
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Adds the positions of the next `limit` accounts starting from `from_index` to the indexes
+    /// of the suppliers and the borrowers, e.g. for the accounts that haven't changed since the
+    /// indexes were introduced. Anyone can call it, since it only indexes the existing positions.
+    /// Returns the index of the next account to backfill, which is the number of accounts once
+    /// all accounts are indexed.
+    pub fn backfill_asset_accounts(&mut self, from_index: u64, limit: u64) -> u64 {
+        let end_index = std::cmp::min(self.accounts.len(), from_index.saturating_add(limit));
+        for index in from_index..end_index {
+            let account = self.internal_get_account_by_index(index);
+            let farm_ids: Vec<FarmId> = account
+                .supplied
+                .keys()
+                .chain(account.collateral.keys())
+                .map(|token_id| FarmId::Supplied(token_id.clone()))
+                .chain(
+                    account
+                        .borrowed
+                        .keys()
+                        .map(|token_id| FarmId::Borrowed(token_id.clone())),
+                )
+                .collect();
+            for farm_id in farm_ids.iter() {
+                self.internal_update_asset_accounts_index(&account, farm_id);
+            }
+        }
+        end_index
+    }
+
+    /// Returns a page of accounts that have supplied the given asset (including collateral).
+    /// The accounts are indexed when their balances change or by `backfill_asset_accounts`.
+    pub fn get_asset_suppliers(
+        &self,
+        token_id: AccountId,
+        token: Option<String>,
+        limit: Option<u64>,
+    ) -> Page<AccountId> {
+        asset_accounts_page(&self.asset_suppliers, &token_id, token, limit)
+    }
+
+    /// Returns a page of accounts that have borrowed the given asset.
+    /// The accounts are indexed when their balances change or by `backfill_asset_accounts`.
+    pub fn get_asset_borrowers(
+        &self,
+        token_id: AccountId,
+        token: Option<String>,
+        limit: Option<u64>,
+    ) -> Page<AccountId> {
+        asset_accounts_page(&self.asset_borrowers, &token_id, token, limit)
+    }
+}
@@ -0,0 +1,86 @@
+use crate::asset_accounts::asset_accounts_page;
+use crate::*;
+
+impl Contract {
+    /// Asserts that the account can deposit and borrow the asset, i.e. the asset is not
+    /// permissioned or the account is in the allowlist of the asset.
+    pub fn internal_assert_allowlisted(&self, token_id: &TokenId, account_id: &AccountId) {
+        if self.internal_unwrap_asset(token_id).config.permissioned {
+            assert!(
+                self.internal_is_allowlisted(token_id, account_id),
+                "The account is not in the allowlist of the asset"
+            );
+        }
+    }
+
+    pub fn internal_is_allowlisted(&self, token_id: &TokenId, account_id: &AccountId) -> bool {
+        self.asset_allowlists
+            .get(token_id)
+            .map(|account_ids| account_ids.contains(account_id))
+            .unwrap_or(false)
+    }
+
+    pub fn internal_remove_asset_allowlist(&mut self, token_id: &TokenId) {
+        if let Some(mut account_ids) = self.asset_allowlists.remove(token_id) {
+            account_ids.clear();
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Adds the accounts to the allowlist of the asset. The allowlist is only enforced for the
+    /// deposits and borrows of the permissioned assets.
+    /// - Panics if an asset with the given token_id doesn't exist.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or a compliance manager.
+    #[payable]
+    pub fn add_to_asset_allowlist(&mut self, token_id: AccountId, account_ids: Vec<AccountId>) {
+        assert_one_yocto();
+        self.assert_owner_or_role(Role::ComplianceManager);
+        self.internal_unwrap_asset(&token_id);
+        let token_index = self.internal_token_index(&token_id);
+        let mut allowlist = self.asset_allowlists.get(&token_id).unwrap_or_else(|| {
+            UnorderedSet::new(StorageKey::AssetAllowlistByIndex { token_index })
+        });
+        for account_id in &account_ids {
+            allowlist.insert(account_id);
+        }
+        self.asset_allowlists.insert(&token_id, &allowlist);
+    }
+
+    /// Removes the accounts from the allowlist of the asset. The existing positions of the
+    /// removed accounts are not affected, but they can't deposit or borrow the permissioned asset.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or a compliance manager.
+    #[payable]
+    pub fn remove_from_asset_allowlist(
+        &mut self,
+        token_id: AccountId,
+        account_ids: Vec<AccountId>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner_or_role(Role::ComplianceManager);
+        if let Some(mut allowlist) = self.asset_allowlists.get(&token_id) {
+            for account_id in &account_ids {
+                allowlist.remove(account_id);
+            }
+            self.asset_allowlists.insert(&token_id, &allowlist);
+        }
+    }
+
+    /// Returns whether the account is in the allowlist of the asset.
+    pub fn is_allowlisted(&self, token_id: AccountId, account_id: AccountId) -> bool {
+        self.internal_is_allowlisted(&token_id, &account_id)
+    }
+
+    /// Returns a page of the accounts from the allowlist of the asset.
+    pub fn get_asset_allowlist(
+        &self,
+        token_id: AccountId,
+        token: Option<String>,
+        limit: Option<u64>,
+    ) -> Page<AccountId> {
+        asset_accounts_page(&self.asset_allowlists, &token_id, token, limit)
+    }
+}
@@ -0,0 +1,111 @@
+use crate::*;
+
+const DAYS_PER_YEAR: u128 = 365;
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetApyView {
+    pub token_id: TokenId,
+    /// Current APR excluding farms for supplying the asset.
+    pub supply_apr: BigDecimal,
+    /// Current APR excluding farms for borrowing the asset.
+    pub borrow_apr: BigDecimal,
+    /// The total APR of the active rewards of the supplied farm of the asset.
+    pub supply_farm_apr: BigDecimal,
+    /// The total APR of the active rewards of the borrowed farm of the asset.
+    pub borrow_farm_apr: BigDecimal,
+    /// The supply APR including the farm rewards.
+    pub net_supply_apy: BigDecimal,
+    /// The borrow APR reduced by the farm rewards. It's `0` if the rewards exceed the interest.
+    pub net_borrow_apy: BigDecimal,
+}
+
+impl Contract {
+    /// Computes the yearly value of the active farm rewards relative to the value of the given
+    /// pool balance. The rewards without the recorded price are not included.
+    fn internal_compute_farm_apr(
+        &self,
+        farm_id: &FarmId,
+        balance: Balance,
+        price: Option<&Price>,
+        extra_decimals: u8,
+    ) -> BigDecimal {
+        let (price, asset_farm) = match (price, self.internal_get_asset_farm(farm_id)) {
+            (Some(price), Some(asset_farm)) if balance > 0 => (price, asset_farm),
+            _ => return BigDecimal::zero(),
+        };
+        let total_value = BigDecimal::from_balance_price(balance, price, extra_decimals);
+        if total_value == BigDecimal::zero() {
+            return BigDecimal::zero();
+        }
+        let mut rewards_value = BigDecimal::zero();
+        for (reward_token_id, reward) in asset_farm.rewards.iter() {
+            if reward.remaining_rewards == 0 {
+                continue;
+            }
+            if let Some(reward_price) = self.last_prices.get(reward_token_id) {
+                let reward_asset = self.internal_unwrap_asset(reward_token_id);
+                rewards_value = rewards_value
+                    + BigDecimal::from_balance_price(
+                        reward.reward_per_day * DAYS_PER_YEAR,
+                        reward_price,
+                        reward_asset.config.extra_decimals,
+                    );
+            }
+        }
+        rewards_value / total_value
+    }
+
+    pub fn internal_compute_asset_apy(&self, token_id: TokenId) -> AssetApyView {
+        let asset = self.internal_unwrap_asset(&token_id);
+        let price = self.last_prices.get(&token_id);
+        let extra_decimals = asset.config.extra_decimals;
+        let supply_apr = asset.get_supply_apr();
+        let borrow_apr = asset.get_borrow_apr();
+        let supply_farm_apr = self.internal_compute_farm_apr(
+            &FarmId::Supplied(token_id.clone()),
+            asset.supplied.balance,
+            price,
+            extra_decimals,
+        );
+        let borrow_farm_apr = self.internal_compute_farm_apr(
+            &FarmId::Borrowed(token_id.clone()),
+            asset.borrowed.balance,
+            price,
+            extra_decimals,
+        );
+        let net_borrow_apy = if borrow_apr > borrow_farm_apr {
+            borrow_apr - borrow_farm_apr
+        } else {
+            BigDecimal::zero()
+        };
+        AssetApyView {
+            token_id,
+            supply_apr,
+            borrow_apr,
+            supply_farm_apr,
+            borrow_farm_apr,
+            net_supply_apy: supply_apr + supply_farm_apr,
+            net_borrow_apy,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the APRs of the asset combined with the APRs of the active farm rewards. The farm
+    /// rewards are valued using the last recorded prices from the oracle. The net values are
+    /// simple sums of the APRs, the same way the official UI displays them.
+    pub fn get_asset_apy(&self, token_id: AccountId) -> AssetApyView {
+        self.internal_compute_asset_apy(token_id)
+    }
+
+    /// Returns the combined APRs for all assets. See `get_asset_apy()`.
+    pub fn get_assets_apy(&self) -> Vec<AssetApyView> {
+        self.asset_ids
+            .iter()
+            .map(|token_id| self.internal_compute_asset_apy(token_id))
+            .collect()
+    }
+}
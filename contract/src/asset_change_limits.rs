@@ -0,0 +1,63 @@
+use crate::*;
+
+const NANOS_PER_DAY: Duration = 24 * 60 * 60 * 10u64.pow(9);
+
+/// The 24 hour window of the changes of the volatility ratio of an asset.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct VolatilityRatioChangeWindow {
+    /// When the window has started in nanoseconds.
+    #[serde(with = "u64_dec_format")]
+    pub start_timestamp: Timestamp,
+    /// The volatility ratio at the start of the window.
+    pub start_volatility_ratio: u32,
+}
+
+impl Contract {
+    /// Records the change of the volatility ratio of the asset and asserts that the total change
+    /// within the current 24 hour window doesn't exceed the daily limit from the config.
+    pub fn internal_record_volatility_ratio_change(
+        &mut self,
+        token_id: &TokenId,
+        old_volatility_ratio: u32,
+        new_volatility_ratio: u32,
+    ) {
+        if old_volatility_ratio == new_volatility_ratio {
+            return;
+        }
+        let max_change = match self.internal_config().max_volatility_ratio_change_per_day {
+            Some(max_change) => max_change,
+            None => return,
+        };
+        let timestamp = env::block_timestamp();
+        let window = self
+            .volatility_ratio_change_windows
+            .get(token_id)
+            .filter(|window| timestamp < window.start_timestamp + NANOS_PER_DAY)
+            .unwrap_or(VolatilityRatioChangeWindow {
+                start_timestamp: timestamp,
+                start_volatility_ratio: old_volatility_ratio,
+            });
+        let change = std::cmp::max(new_volatility_ratio, window.start_volatility_ratio)
+            - std::cmp::min(new_volatility_ratio, window.start_volatility_ratio);
+        assert!(
+            change <= max_change,
+            "The volatility ratio can change by at most {} within 24 hours",
+            max_change
+        );
+        self.volatility_ratio_change_windows
+            .insert(token_id, &window);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the current 24 hour window of the volatility ratio changes of the asset.
+    pub fn get_volatility_ratio_change_window(
+        &self,
+        token_id: AccountId,
+    ) -> Option<VolatilityRatioChangeWindow> {
+        self.volatility_ratio_change_windows.get(&token_id)
+    }
+}
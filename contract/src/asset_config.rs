@@ -3,10 +3,11 @@ use crate::*;
 const MAX_POS: u32 = 10000;
 const MAX_RATIO: u32 = 10000;
 
-/// Represents an asset config.
+/// Represents an asset config. The fields added after the first version can be omitted in JSON
+/// and default to `0`, `false` or `null`, so the configs of the older tooling are still accepted.
 /// Example:
 /// 25% reserve, 80% target utilization, 12% target APR, 250% max APR, 60% vol
-/// no extra decimals, can be deposited, withdrawn, used as a collateral, borrowed
+/// no extra decimals, no paused actions, can be used as a collateral
 /// JSON:
 /// ```json
 /// {
@@ -16,18 +17,34 @@ const MAX_RATIO: u32 = 10000;
 ///   "max_utilization_rate": "1000000000039724853136740579",
 ///   "volatility_ratio": 6000,
 ///   "extra_decimals": 0,
-///   "can_deposit": true,
-///   "can_withdraw": true,
+///   "paused_actions": 0,
 ///   "can_use_as_collateral": true,
-///   "can_borrow": true
+///   "net_tvl_multiplier": 10000,
+///   "supply_cap": null,
+///   "borrow_cap": null,
+///   "isolation_debt_ceiling": null,
+///   "borrowable_in_isolation": false,
+///   "permissioned": false,
+///   "frozen_rate": null,
+///   "fee_on_transfer": false,
+///   "min_borrow_amount": null,
+///   "risk_tier": null,
+///   "can_flash_loan": true,
+///   "flash_loan_fee": null,
+///   "protocol_fee_ratio": 0,
+///   "collateral_cap": null,
+///   "reserve_only": false,
+///   "oracle_price_decimals": null,
+///   "collateral_only": false,
+///   "dust_threshold": null
 /// }
 /// ```
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
 #[serde(crate = "near_sdk::serde")]
 pub struct AssetConfig {
-    /// The ratio of interest that is reserved by the protocol (multiplied by 10000).
-    /// E.g. 2500 means 25% from borrowed interests goes to the reserve.
+    /// The ratio of interest that stays in the reserve as a liquidity backstop (multiplied by
+    /// 10000). E.g. 2500 means 25% from borrowed interests goes to the reserve.
     pub reserve_ratio: u32,
     /// Target utilization ratio (multiplied by 10000).
     /// E.g. 8000 means the protocol targets 80% of assets are borrowed.
@@ -52,28 +69,215 @@ pub struct AssetConfig {
     /// USDT has `6` decimals in the metadata, the `extra_decimals` can be set to `12`, to make the
     /// inner balance of USDT at `18` decimals.
     pub extra_decimals: u8,
-    /// Whether the deposits of this assets are enabled.
-    pub can_deposit: bool,
-    /// Whether the withdrawals of this assets are enabled.
-    pub can_withdraw: bool,
+    /// The bitmask of the paused actions of this asset. The bit of every action is given by
+    /// `PausableAction::mask`. E.g. `0` means all actions are enabled and `16` means borrowing
+    /// is paused.
+    #[serde(default)]
+    pub paused_actions: u32,
     /// Whether this assets can be used as collateral.
     pub can_use_as_collateral: bool,
-    /// Whether this assets can be borrowed.
-    pub can_borrow: bool,
     /// NetTvl asset multiplier (multiplied by 10000).
     /// Default multiplier is 10000, means the asset weight shouldn't be changed.
     /// Example: a multiplier of 5000 means the asset in TVL should only counted as 50%, e.g. if an
     /// asset is not useful for borrowing, but only useful as a collateral.
     pub net_tvl_multiplier: u32,
+    /// The maximum total supplied balance of the asset including the accrued interest, e.g. in
+    /// the inner balance with extra decimals. Deposits above the cap are rejected.
+    /// No cap if not set.
+    #[serde(default)]
+    pub supply_cap: Option<U128>,
+    /// The maximum total borrowed balance of the asset including the accrued interest, e.g. in
+    /// the inner balance with extra decimals. Borrows above the cap are rejected.
+    /// No cap if not set.
+    #[serde(default)]
+    pub borrow_cap: Option<U128>,
+    /// If set, the asset is isolated. An account that uses the isolated asset as collateral can't
+    /// have other collateral assets and can only borrow the assets that are borrowable in
+    /// isolation. The total debt of such accounts is limited by this ceiling. The debt is counted
+    /// as the sum of the borrowed inner balances, so the assets borrowable in isolation should be
+    /// stablecoins with the same number of decimals including the extra decimals.
+    #[serde(default)]
+    pub isolation_debt_ceiling: Option<U128>,
+    /// Whether the asset can be borrowed by the accounts that use an isolated collateral.
+    #[serde(default)]
+    pub borrowable_in_isolation: bool,
+    /// Whether only the accounts from the allowlist of the asset can deposit and borrow it.
+    /// The allowlist is managed by the compliance managers.
+    #[serde(default)]
+    pub permissioned: bool,
+    /// If set, the borrow rate is fixed at this compounding rate regardless of the utilization,
+    /// e.g. for the deprecated assets. Given as a decimal string.
+    #[serde(default)]
+    pub frozen_rate: Option<LowU128>,
+    /// Whether the token can transfer fewer tokens than requested, e.g. a fee-on-transfer or a
+    /// deflationary token. Only plain deposits of such an asset are accepted, and the deposited
+    /// amount is measured by the balance difference of the contract.
+    #[serde(default)]
+    pub fee_on_transfer: bool,
+    /// The minimum borrowed balance of the account in the asset after a borrow, in the inner
+    /// balance with extra decimals. The dust loans are uneconomical to liquidate.
+    /// No minimum if not set.
+    #[serde(default)]
+    pub min_borrow_amount: Option<U128>,
+    /// The risk tier of the asset for the risk tier policy of the config.
+    /// No restrictions if not set.
+    #[serde(default)]
+    pub risk_tier: Option<AssetTier>,
+    /// Whether the asset can be lent out with the flash loans. Disabled e.g. for the bridged
+    /// tokens that can't be lent out within a transaction chain.
+    #[serde(default)]
+    pub can_flash_loan: bool,
+    /// The flash loan fee of the asset (multiplied by 10000). E.g. `9` means 0.09% fee.
+    /// The `flash_loan_fee` of the config is used if not set.
+    #[serde(default)]
+    pub flash_loan_fee: Option<u32>,
+    /// The ratio of interest that accrues to the protocol fees (multiplied by 10000). The fees are
+    /// kept in the reserve until they are claimed with `claim_protocol_fees`.
+    /// E.g. 1000 means 10% from borrowed interests goes to the protocol fees.
+    #[serde(default)]
+    pub protocol_fee_ratio: u32,
+    /// The maximum total balance of the asset used as collateral across the protocol including
+    /// the accrued interest, in the inner balance with extra decimals. The asset can still be
+    /// supplied above the cap for yield, but increasing the collateral above the cap is rejected.
+    /// No cap if not set.
+    #[serde(default)]
+    pub collateral_cap: Option<U128>,
+    /// Whether the asset is reserve-only, i.e. the accounts can't deposit it, e.g. an insurance
+    /// backstop token that can only be deposited to the reserve and withdrawn from it. Such an
+    /// asset can't be borrowed and is excluded from the rate math.
+    #[serde(default)]
+    pub reserve_only: bool,
+    /// The decimals of the oracle prices of the asset, for the feeds whose decimals differ from
+    /// the oracle default. The decimals include the token decimals, e.g. `22` for a token with 18
+    /// decimals and a USD price with 4 decimals. The decimals from the oracle are used if not set.
+    #[serde(default)]
+    pub oracle_price_decimals: Option<u8>,
+    /// Whether the asset can only be used as collateral, e.g. the pool shares or the staked
+    /// tokens. Such an asset can't be borrowed, earns no supply interest and is excluded from the
+    /// rate math.
+    #[serde(default)]
+    pub collateral_only: bool,
+    /// The dust threshold of the asset, in the inner balance with extra decimals. The supplied or
+    /// the borrowed balance of an account that is decreased by the actions below the threshold
+    /// is written off against the reserve and removed. No write-off if not set.
+    #[serde(default)]
+    pub dust_threshold: Option<U128>,
+}
+
+/// The actions of an asset that can be paused.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum PausableAction {
+    Deposit,
+    Withdraw,
+    IncreaseCollateral,
+    DecreaseCollateral,
+    Borrow,
+    Repay,
+    Liquidate,
+}
+
+impl PausableAction {
+    pub fn all() -> Vec<PausableAction> {
+        vec![
+            PausableAction::Deposit,
+            PausableAction::Withdraw,
+            PausableAction::IncreaseCollateral,
+            PausableAction::DecreaseCollateral,
+            PausableAction::Borrow,
+            PausableAction::Repay,
+            PausableAction::Liquidate,
+        ]
+    }
+
+    /// Returns the bit of the action in the `paused_actions` bitmask.
+    pub fn mask(self) -> u32 {
+        1 << (self as u32)
+    }
 }
 
 impl AssetConfig {
+    /// The ratio of interest that doesn't go to the suppliers, i.e. the reserve and the protocol
+    /// fees (multiplied by 10000).
+    pub fn get_protocol_ratio(&self) -> u32 {
+        self.reserve_ratio + self.protocol_fee_ratio
+    }
+
+    pub fn is_paused(&self, action: PausableAction) -> bool {
+        self.paused_actions & action.mask() != 0
+    }
+
+    pub fn pause(&mut self, action: PausableAction) {
+        self.paused_actions |= action.mask();
+    }
+
+    /// Returns the list of the paused actions.
+    pub fn get_paused_actions(&self) -> Vec<PausableAction> {
+        PausableAction::all()
+            .into_iter()
+            .filter(|action| self.is_paused(*action))
+            .collect()
+    }
+
     pub fn assert_valid(&self) {
-        assert!(self.reserve_ratio <= MAX_RATIO);
-        assert!(self.target_utilization < MAX_POS);
-        assert!(self.target_utilization_rate.0 <= self.max_utilization_rate.0);
+        assert!(
+            self.reserve_ratio <= MAX_RATIO,
+            "The reserve ratio should be no more than 100%"
+        );
+        assert!(
+            self.protocol_fee_ratio <= MAX_RATIO,
+            "The protocol fee ratio should be no more than 100%"
+        );
+        assert!(
+            self.reserve_ratio + self.protocol_fee_ratio <= MAX_RATIO,
+            "The sum of the reserve ratio and the protocol fee ratio should be no more than 100%"
+        );
+        assert!(
+            self.target_utilization < MAX_POS,
+            "The target utilization should be less than 100%"
+        );
+        assert!(
+            self.target_utilization_rate.0 <= self.max_utilization_rate.0,
+            "The max utilization rate should be no less than the target utilization rate"
+        );
         // The volatility ratio can't be 100% to avoid free liquidations of such assets.
-        assert!(self.volatility_ratio < MAX_RATIO);
+        assert!(
+            self.volatility_ratio < MAX_RATIO,
+            "The volatility ratio should be less than 100%"
+        );
+        assert!(
+            self.net_tvl_multiplier <= MAX_RATIO,
+            "The net TVL multiplier should be no more than 100%"
+        );
+        assert!(
+            self.paused_actions >> PausableAction::all().len() == 0,
+            "Unknown paused actions"
+        );
+        assert!(
+            self.frozen_rate
+                .map(|rate| BigDecimal::from(rate) >= BigDecimal::one())
+                .unwrap_or(true),
+            "The frozen rate should be no less than 1"
+        );
+        assert!(
+            self.flash_loan_fee.unwrap_or(0) <= MAX_RATIO,
+            "The flash loan fee should be no more than 100%"
+        );
+        assert!(
+            !self.reserve_only || !self.can_use_as_collateral,
+            "The reserve-only asset can't be used as collateral"
+        );
+        assert!(
+            !self.collateral_only || self.can_use_as_collateral,
+            "The collateral-only asset has to be usable as collateral"
+        );
+    }
+
+    /// Whether the asset can be borrowed at all, i.e. it's neither reserve-only nor
+    /// collateral-only. The assets that can't be borrowed are excluded from the rate math.
+    pub fn is_borrowable(&self) -> bool {
+        !self.reserve_only && !self.collateral_only
     }
 
     pub fn get_rate(
@@ -81,7 +285,12 @@ impl AssetConfig {
         borrowed_balance: Balance,
         total_supplied_balance: Balance,
     ) -> BigDecimal {
-        if total_supplied_balance == 0 {
+        if !self.is_borrowable() {
+            // The reserve-only and the collateral-only assets are excluded from the rate math.
+            BigDecimal::one()
+        } else if let Some(frozen_rate) = self.frozen_rate {
+            BigDecimal::from(frozen_rate)
+        } else if total_supplied_balance == 0 {
             BigDecimal::one()
         } else {
             let pos = BigDecimal::from(borrowed_balance).div_u128(total_supplied_balance);
@@ -115,11 +324,26 @@ mod tests {
             max_utilization_rate: 1000000000039724853136740579u128.into(),
             volatility_ratio: 6000,
             extra_decimals: 0,
-            can_deposit: true,
-            can_withdraw: true,
+            paused_actions: 0,
             can_use_as_collateral: true,
-            can_borrow: true,
             net_tvl_multiplier: 10000,
+            supply_cap: None,
+            borrow_cap: None,
+            isolation_debt_ceiling: None,
+            borrowable_in_isolation: false,
+            permissioned: false,
+            frozen_rate: None,
+            fee_on_transfer: false,
+            min_borrow_amount: None,
+            risk_tier: None,
+            can_flash_loan: true,
+            flash_loan_fee: None,
+            protocol_fee_ratio: 0,
+            collateral_cap: None,
+            reserve_only: false,
+            oracle_price_decimals: None,
+            collateral_only: false,
+            dust_threshold: None,
         }
     }
 
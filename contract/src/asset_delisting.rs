@@ -0,0 +1,156 @@
+use crate::*;
+
+const GAS_FOR_RESERVE_TRANSFER: Gas = Gas(Gas::ONE_TERA.0 * 10);
+
+/// The actions that are paused for the delisting assets and can't be resumed until the asset is
+/// removed. Withdrawals, repayments and liquidations stay enabled to wind down the positions.
+const DELISTING_PAUSED_ACTIONS: [PausableAction; 3] = [
+    PausableAction::Deposit,
+    PausableAction::IncreaseCollateral,
+    PausableAction::Borrow,
+];
+
+impl Contract {
+    /// Pauses the actions that are disabled for the asset if the asset is delisting.
+    pub fn internal_apply_delisting(&self, token_id: &TokenId, asset_config: &mut AssetConfig) {
+        if self.delisting_assets.contains(token_id) {
+            for action in DELISTING_PAUSED_ACTIONS {
+                asset_config.pause(action);
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Starts the delisting of the asset with the given token_id. Deposits, new collateral and
+    /// borrowing of the asset are paused, while withdrawals, repayments and liquidations are still
+    /// allowed. The interest rates can be optionally raised to push the borrowers to repay.
+    /// - Panics if an asset with the given token_id doesn't exist or is already delisting.
+    /// - Panics if the rates are given and the proposal timelock is not 0.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn start_asset_delisting(
+        &mut self,
+        token_id: AccountId,
+        target_utilization_rate: Option<LowU128>,
+        max_utilization_rate: Option<LowU128>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        let old_asset_config = asset.config.clone();
+        assert!(
+            self.delisting_assets.insert(&token_id),
+            "The asset is already delisting"
+        );
+        if target_utilization_rate.is_some() || max_utilization_rate.is_some() {
+            self.assert_no_proposal_timelock();
+        }
+        if let Some(target_utilization_rate) = target_utilization_rate {
+            asset.config.target_utilization_rate = target_utilization_rate;
+        }
+        if let Some(max_utilization_rate) = max_utilization_rate {
+            asset.config.max_utilization_rate = max_utilization_rate;
+        }
+        asset.config.assert_valid();
+        self.internal_apply_delisting(&token_id, &mut asset.config);
+        events::emit::asset_config_updated(&token_id, &old_asset_config, &asset.config);
+        self.internal_set_asset(&token_id, asset);
+    }
+
+    /// Removes the delisting asset with the given token_id once nothing is supplied or borrowed,
+    /// and reclaims its storage, including the history and the farms of the asset.
+    /// The remaining reserve is transferred to the owner.
+    /// - Panics if the asset is not delisting.
+    /// - Panics if the asset has suppliers or borrowers.
+    /// - Panics if the asset is the booster token or a reward of an active farm.
+    /// - Panics if a farm of the asset has remaining rewards.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn remove_asset(&mut self, token_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        assert!(
+            self.delisting_assets.contains(&token_id),
+            "The asset is not delisting"
+        );
+        let config = self.internal_config();
+        assert_ne!(
+            token_id, config.booster_token_id,
+            "Can't remove the booster token"
+        );
+        let asset = self.internal_unwrap_asset(&token_id);
+        assert!(
+            asset.supplied.shares.0 == 0 && asset.borrowed.shares.0 == 0,
+            "The asset still has suppliers or borrowers"
+        );
+
+        let asset_farm_ids = [
+            FarmId::Supplied(token_id.clone()),
+            FarmId::Borrowed(token_id.clone()),
+        ];
+        for farm_id in self.internal_get_all_farm_ids() {
+            let asset_farm = self.internal_get_asset_farm(&farm_id).unwrap();
+            assert!(
+                asset_farm
+                    .rewards
+                    .get(&token_id)
+                    .is_none_or(|reward| reward.is_exhausted()),
+                "The asset is a reward of an active farm"
+            );
+            if asset_farm_ids.contains(&farm_id) {
+                assert!(
+                    asset_farm
+                        .rewards
+                        .values()
+                        .all(|reward| reward.remaining_rewards == 0),
+                    "The asset farm has remaining rewards"
+                );
+                self.internal_remove_asset_farm(&farm_id);
+            }
+        }
+
+        if let Some(mut account_ids) = self.asset_suppliers.remove(&token_id) {
+            account_ids.clear();
+        }
+        if let Some(mut account_ids) = self.asset_borrowers.remove(&token_id) {
+            account_ids.clear();
+        }
+        // The transfer is dispatched before the asset is removed with its multi token mapping.
+        let ft_amount = (asset.reserved + asset.supplied.balance)
+            / 10u128.pow(asset.config.extra_decimals as u32);
+        if ft_amount > 0 {
+            self.internal_token_transfer(
+                &config.owner_id,
+                &token_id,
+                ft_amount,
+                GAS_FOR_RESERVE_TRANSFER,
+            );
+        }
+
+        self.internal_remove_asset_history(&token_id);
+        self.last_prices.remove(&token_id);
+        self.delisting_assets.remove(&token_id);
+        self.deprecated_assets.remove(&token_id);
+        self.asset_migrations.remove(&token_id);
+        self.pyth_feeds.remove(&token_id);
+        self.extra_decimals_migrations.remove(&token_id);
+        self.collateral_shares_backfills.remove(&token_id);
+        self.token_metadata.remove(&token_id);
+        self.volatility_ratio_change_windows.remove(&token_id);
+        self.reserve_withdrawals.remove(&token_id);
+        self.internal_remove_asset_allowlist(&token_id);
+        self.risk_oracle_bounds.remove(&token_id);
+        self.twap_pairs.remove(&token_id);
+        self.internal_remove_asset(&token_id);
+        events::emit::asset_removed(&token_id);
+    }
+
+    /// Returns the token IDs of the assets that are being delisted.
+    pub fn get_delisting_assets(&self) -> Vec<TokenId> {
+        self.delisting_assets.to_vec()
+    }
+}
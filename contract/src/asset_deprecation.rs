@@ -0,0 +1,81 @@
+use crate::*;
+
+/// The actions that are paused for the deprecated assets while they are deprecated. Only
+/// repayments, collateral withdrawals and liquidations stay enabled.
+const DEPRECATION_PAUSED_ACTIONS: [PausableAction; 3] = [
+    PausableAction::Deposit,
+    PausableAction::IncreaseCollateral,
+    PausableAction::Borrow,
+];
+
+impl Contract {
+    /// Pauses the actions that are disabled for the asset if the asset is deprecated.
+    pub fn internal_apply_deprecation(&self, token_id: &TokenId, asset_config: &mut AssetConfig) {
+        if self.deprecated_assets.contains(token_id) {
+            for action in DEPRECATION_PAUSED_ACTIONS {
+                asset_config.pause(action);
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Deprecates the asset with the given token_id, so the asset is in the repay-only mode.
+    /// Deposits, new collateral and borrowing of the asset are paused, while repayments,
+    /// withdrawals and liquidations are still allowed. If `freeze_rate` is true, the borrow rate is
+    /// frozen at the current rate regardless of the utilization. Unlike the delisting, the
+    /// deprecation can be reverted with `undeprecate_asset`.
+    /// - Panics if an asset with the given token_id doesn't exist or is already deprecated.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn deprecate_asset(&mut self, token_id: AccountId, freeze_rate: bool) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        let old_asset_config = asset.config.clone();
+        assert!(
+            self.deprecated_assets.insert(&token_id),
+            "The asset is already deprecated"
+        );
+        if freeze_rate {
+            asset.config.frozen_rate = Some(asset.get_rate().into());
+        }
+        self.internal_apply_deprecation(&token_id, &mut asset.config);
+        events::emit::asset_config_updated(&token_id, &old_asset_config, &asset.config);
+        self.internal_set_asset(&token_id, asset);
+    }
+
+    /// Reverts the deprecation of the asset with the given token_id. The actions paused by the
+    /// deprecation are resumed, unless the asset is delisting or the contract is sunset, and the
+    /// borrow rate is unfrozen. The migration of the asset to its replacement is removed.
+    /// - Panics if the asset is not deprecated.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn undeprecate_asset(&mut self, token_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        assert!(
+            self.deprecated_assets.remove(&token_id),
+            "The asset is not deprecated"
+        );
+        self.asset_migrations.remove(&token_id);
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        let old_asset_config = asset.config.clone();
+        for action in DEPRECATION_PAUSED_ACTIONS {
+            asset.config.paused_actions &= !action.mask();
+        }
+        asset.config.frozen_rate = None;
+        self.internal_apply_delisting(&token_id, &mut asset.config);
+        self.internal_apply_sunset(&mut asset.config);
+        events::emit::asset_config_updated(&token_id, &old_asset_config, &asset.config);
+        self.internal_set_asset(&token_id, asset);
+    }
+
+    /// Returns the token IDs of the assets that are deprecated.
+    pub fn get_deprecated_assets(&self) -> Vec<TokenId> {
+        self.deprecated_assets.to_vec()
+    }
+}
@@ -6,29 +6,14 @@ static ASSET_FARMS: Lazy<Mutex<HashMap<FarmId, Option<AssetFarm>>>> =
 const NANOS_PER_DAY: Duration = 24 * 60 * 60 * 10u64.pow(9);
 
 /// A data required to keep track of a farm for an account.
-#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+/// The inactive rewards of the farm are kept in the contract by farm ID and token ID.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct AssetFarm {
     #[serde(with = "u64_dec_format")]
     pub block_timestamp: Timestamp,
     /// Active rewards for the farm
     pub rewards: HashMap<TokenId, AssetFarmReward>,
-    /// Inactive rewards
-    #[serde(skip_serializing)]
-    pub inactive_rewards: LookupMap<TokenId, VAssetFarmReward>,
-}
-
-impl Clone for AssetFarm {
-    fn clone(&self) -> Self {
-        Self {
-            block_timestamp: self.block_timestamp,
-            rewards: self.rewards.clone(),
-            inactive_rewards: BorshDeserialize::try_from_slice(
-                &self.inactive_rewards.try_to_vec().unwrap(),
-            )
-            .unwrap(),
-        }
-    }
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Clone)]
@@ -70,19 +55,27 @@ pub struct AssetFarmReward {
     #[serde(with = "u128_dec_format")]
     pub boosted_shares: Balance,
     #[serde(skip)]
-    pub reward_per_share: BigDecimal,
+    pub reward_per_share: RewardPerShare,
+}
+
+impl AssetFarmReward {
+    /// Whether the reward has run out of the remaining rewards after distributing them.
+    pub fn is_exhausted(&self) -> bool {
+        self.remaining_rewards == 0 && self.boosted_shares > 0
+    }
 }
 
 impl AssetFarm {
-    pub fn update(&mut self, is_view: bool) {
+    /// Distributes the rewards since the last update. The rewards that have run out of the
+    /// remaining rewards become inactive once the farm is saved.
+    pub fn update(&mut self) {
         let block_timestamp = env::block_timestamp();
         if block_timestamp == self.block_timestamp {
             return;
         }
         let time_diff = block_timestamp - self.block_timestamp;
         self.block_timestamp = block_timestamp;
-        let mut new_inactive_reward = vec![];
-        for (token_id, reward) in self.rewards.iter_mut() {
+        for reward in self.rewards.values_mut() {
             if reward.boosted_shares == 0 {
                 continue;
             }
@@ -95,52 +88,23 @@ impl AssetFarm {
                 ),
             );
             reward.remaining_rewards -= acquired_rewards;
-            reward.reward_per_share = reward.reward_per_share
-                + BigDecimal::from(acquired_rewards) / BigDecimal::from(reward.boosted_shares);
-            if reward.remaining_rewards == 0 {
-                new_inactive_reward.push(token_id.clone());
-            }
-        }
-        if !is_view {
-            for token_id in new_inactive_reward {
-                let reward = self.rewards.remove(&token_id).unwrap();
-                self.internal_set_inactive_asset_farm_reward(&token_id, reward);
-            }
+            reward
+                .reward_per_share
+                .add_rewards(acquired_rewards, reward.boosted_shares);
         }
     }
-
-    pub fn internal_get_inactive_asset_farm_reward(
-        &self,
-        token_id: &TokenId,
-    ) -> Option<AssetFarmReward> {
-        self.inactive_rewards.get(token_id).map(|o| o.into())
-    }
-
-    pub fn internal_remove_inactive_asset_farm_reward(
-        &mut self,
-        token_id: &TokenId,
-    ) -> Option<AssetFarmReward> {
-        self.inactive_rewards.remove(token_id).map(|o| o.into())
-    }
-
-    pub fn internal_set_inactive_asset_farm_reward(
-        &mut self,
-        token_id: &TokenId,
-        asset_farm_reward: AssetFarmReward,
-    ) {
-        self.inactive_rewards
-            .insert(token_id, &asset_farm_reward.into());
-    }
 }
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum VAssetFarm {
+    V0(AssetFarmV0),
     Current(AssetFarm),
 }
 
 impl From<VAssetFarm> for AssetFarm {
     fn from(v: VAssetFarm) -> Self {
         match v {
+            VAssetFarm::V0(v) => v.into(),
             VAssetFarm::Current(c) => c,
         }
     }
@@ -153,17 +117,17 @@ impl From<AssetFarm> for VAssetFarm {
 }
 
 impl Contract {
-    pub fn internal_unwrap_asset_farm(&self, farm_id: &FarmId, is_view: bool) -> AssetFarm {
-        self.internal_get_asset_farm(farm_id, is_view)
+    pub fn internal_unwrap_asset_farm(&self, farm_id: &FarmId) -> AssetFarm {
+        self.internal_get_asset_farm(farm_id)
             .expect("Asset farm not found")
     }
 
-    pub fn internal_get_asset_farm(&self, farm_id: &FarmId, is_view: bool) -> Option<AssetFarm> {
+    pub fn internal_get_asset_farm(&self, farm_id: &FarmId) -> Option<AssetFarm> {
         let mut cache = ASSET_FARMS.lock().unwrap();
         cache.get(farm_id).cloned().unwrap_or_else(|| {
             let asset_farm = self.asset_farms.get(farm_id).map(|v| {
                 let mut asset_farm: AssetFarm = v.into();
-                asset_farm.update(is_view);
+                asset_farm.update();
                 asset_farm
             });
             cache.insert(farm_id.clone(), asset_farm.clone());
@@ -171,20 +135,97 @@ impl Contract {
         })
     }
 
-    pub fn internal_set_asset_farm(&mut self, farm_id: &FarmId, asset_farm: AssetFarm) {
+    /// Returns the IDs of all existing farms. The supplied and the borrowed farms of every asset
+    /// go first followed by the Net TVL farm.
+    pub fn internal_get_all_farm_ids(&self) -> Vec<FarmId> {
+        let mut farm_ids = vec![];
+        for token_id in self.asset_ids.iter() {
+            farm_ids.push(FarmId::Supplied(token_id.clone()));
+            farm_ids.push(FarmId::Borrowed(token_id));
+        }
+        farm_ids.push(FarmId::NetTvl);
+        farm_ids.retain(|farm_id| self.asset_farms.contains_key(farm_id));
+        farm_ids
+    }
+
+    /// Saves the asset farm. The rewards that have run out of the remaining rewards are moved to
+    /// the inactive rewards.
+    pub fn internal_set_asset_farm(&mut self, farm_id: &FarmId, mut asset_farm: AssetFarm) {
+        let mut exhausted_token_ids: Vec<_> = asset_farm
+            .rewards
+            .iter()
+            .filter(|(_, reward)| reward.is_exhausted())
+            .map(|(token_id, _)| token_id.clone())
+            .collect();
+        exhausted_token_ids.sort();
+        for token_id in exhausted_token_ids {
+            let reward = asset_farm.rewards.remove(&token_id).unwrap();
+            self.internal_set_inactive_asset_farm_reward(farm_id, &token_id, reward);
+            events::emit::asset_farm_reward_exhausted(farm_id, &token_id);
+        }
         ASSET_FARMS
             .lock()
             .unwrap()
             .insert(farm_id.clone(), Some(asset_farm.clone()));
         self.asset_farms.insert(farm_id, &asset_farm.into());
     }
+
+    /// The inactive rewards of the farms saved before they were kept by farm ID and token ID.
+    fn legacy_inactive_asset_farm_rewards(
+        farm_id: &FarmId,
+    ) -> LookupMap<TokenId, VAssetFarmReward> {
+        LookupMap::new(StorageKey::InactiveAssetFarmRewards {
+            farm_id: farm_id.clone(),
+        })
+    }
+
+    pub fn internal_get_inactive_asset_farm_reward(
+        &self,
+        farm_id: &FarmId,
+        token_id: &TokenId,
+    ) -> Option<AssetFarmReward> {
+        self.inactive_asset_farm_rewards
+            .get(&(farm_id.clone(), token_id.clone()))
+            .or_else(|| Self::legacy_inactive_asset_farm_rewards(farm_id).get(token_id))
+            .map(|o| o.into())
+    }
+
+    pub fn internal_remove_inactive_asset_farm_reward(
+        &mut self,
+        farm_id: &FarmId,
+        token_id: &TokenId,
+    ) -> Option<AssetFarmReward> {
+        let legacy_reward = Self::legacy_inactive_asset_farm_rewards(farm_id).remove(token_id);
+        self.inactive_asset_farm_rewards
+            .remove(&(farm_id.clone(), token_id.clone()))
+            .or(legacy_reward)
+            .map(|o| o.into())
+    }
+
+    pub fn internal_set_inactive_asset_farm_reward(
+        &mut self,
+        farm_id: &FarmId,
+        token_id: &TokenId,
+        asset_farm_reward: AssetFarmReward,
+    ) {
+        Self::legacy_inactive_asset_farm_rewards(farm_id).remove(token_id);
+        self.inactive_asset_farm_rewards.insert(
+            &(farm_id.clone(), token_id.clone()),
+            &asset_farm_reward.into(),
+        );
+    }
+
+    pub fn internal_remove_asset_farm(&mut self, farm_id: &FarmId) {
+        ASSET_FARMS.lock().unwrap().insert(farm_id.clone(), None);
+        self.asset_farms.remove(farm_id);
+    }
 }
 
 #[near_bindgen]
 impl Contract {
     /// Returns an asset farm for a given farm ID.
     pub fn get_asset_farm(&self, farm_id: FarmId) -> Option<AssetFarm> {
-        self.internal_get_asset_farm(&farm_id, true)
+        self.internal_get_asset_farm(&farm_id)
     }
 
     /// Returns a list of pairs (farm ID, asset farm) for a given list of farm IDs.
@@ -192,30 +233,32 @@ impl Contract {
         farm_ids
             .into_iter()
             .filter_map(|farm_id| {
-                self.internal_get_asset_farm(&farm_id, true)
+                self.internal_get_asset_farm(&farm_id)
                     .map(|asset_farm| (farm_id, asset_farm))
             })
             .collect()
     }
 
-    /// Deprecated. Returns all farms using `get_asset_farms_all()`.
-    #[allow(unused)]
+    /// Returns a list of pairs (farm ID, asset farm) for existing farms from a given index up to a
+    /// given limit. The farms are ordered the same way as in `get_all_farm_ids()`.
     pub fn get_asset_farms_paged(
         &self,
         from_index: Option<u64>,
         limit: Option<u64>,
     ) -> Vec<(FarmId, AssetFarm)> {
-        self.get_asset_farms_all()
+        let farm_ids = self.internal_get_all_farm_ids();
+        let from_index = from_index.unwrap_or(0) as usize;
+        let limit = limit.map(|limit| limit as usize).unwrap_or(farm_ids.len());
+        self.get_asset_farms(farm_ids.into_iter().skip(from_index).take(limit).collect())
     }
 
     /// Returns full list of pairs (farm ID, asset farm).
     pub fn get_asset_farms_all(&self) -> Vec<(FarmId, AssetFarm)> {
-        let mut farm_ids = vec![];
-        for token_id in self.asset_ids.iter() {
-            farm_ids.push(FarmId::Supplied(token_id.clone()));
-            farm_ids.push(FarmId::Borrowed(token_id));
-        }
-        farm_ids.push(FarmId::NetTvl);
-        self.get_asset_farms(farm_ids)
+        self.get_asset_farms(self.internal_get_all_farm_ids())
+    }
+
+    /// Returns the IDs of all existing farms.
+    pub fn get_all_farm_ids(&self) -> Vec<FarmId> {
+        self.internal_get_all_farm_ids()
     }
 }
@@ -0,0 +1,143 @@
+use crate::*;
+
+const NANOS_PER_DAY: Duration = 24 * 60 * 60 * 10u64.pow(9);
+
+/// The number of days the asset snapshots are kept for.
+pub const MAX_ASSET_HISTORY_DAYS: u32 = 365;
+
+/// A daily snapshot of the asset balances and the borrow rate.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AssetSnapshot {
+    pub timestamp: Timestamp,
+    pub supplied: Balance,
+    pub borrowed: Balance,
+    pub reserved: Balance,
+    /// The borrow rate per millisecond.
+    pub borrow_rate: BigDecimal,
+    /// The ratio of the interest that doesn't go to the suppliers, including the protocol fees.
+    pub reserve_ratio: u32,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetSnapshotView {
+    /// The timestamp when the snapshot was taken.
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    #[serde(with = "u128_dec_format")]
+    pub supplied: Balance,
+    #[serde(with = "u128_dec_format")]
+    pub borrowed: Balance,
+    #[serde(with = "u128_dec_format")]
+    pub reserved: Balance,
+    pub supply_apr: BigDecimal,
+    pub borrow_apr: BigDecimal,
+}
+
+impl From<AssetSnapshot> for AssetSnapshotView {
+    fn from(snapshot: AssetSnapshot) -> Self {
+        let borrow_apr = rate_to_apr(snapshot.borrow_rate);
+        Self {
+            timestamp: snapshot.timestamp,
+            supplied: snapshot.supplied,
+            borrowed: snapshot.borrowed,
+            reserved: snapshot.reserved,
+            supply_apr: compute_supply_apr(
+                borrow_apr,
+                snapshot.supplied,
+                snapshot.borrowed,
+                snapshot.reserve_ratio,
+            ),
+            borrow_apr,
+        }
+    }
+}
+
+fn timestamp_to_day(timestamp: Timestamp) -> u32 {
+    (timestamp / NANOS_PER_DAY) as u32
+}
+
+impl Contract {
+    /// Records the snapshot of the asset if it's the first time the asset is saved today.
+    /// The snapshots are stored in `MAX_ASSET_HISTORY_DAYS` slots per asset, so the new snapshot
+    /// replaces the expired one.
+    pub fn internal_record_asset_snapshot(&mut self, token_id: &TokenId, asset: &Asset) {
+        let day = timestamp_to_day(asset.last_update_timestamp);
+        if self
+            .last_asset_snapshot_days
+            .get(token_id)
+            .map(|last_day| *last_day >= day)
+            .unwrap_or(false)
+        {
+            return;
+        }
+        self.asset_history.insert(
+            &(token_id.clone(), day % MAX_ASSET_HISTORY_DAYS),
+            &AssetSnapshot {
+                timestamp: asset.last_update_timestamp,
+                supplied: asset.supplied.balance,
+                borrowed: asset.borrowed.balance,
+                reserved: asset.reserved,
+                borrow_rate: asset.get_rate(),
+                reserve_ratio: asset.config.get_protocol_ratio(),
+            },
+        );
+        self.last_asset_snapshot_days.insert(token_id.clone(), day);
+    }
+
+    /// Emits the current rates and the utilization of the asset, if the rates events are enabled
+    /// and the interval from the config has passed since the last rates event of the asset.
+    pub fn internal_emit_asset_rates(&mut self, token_id: &TokenId, asset: &Asset) {
+        let interval_sec = match self.internal_config().asset_rates_event_interval_sec {
+            Some(interval_sec) => interval_sec,
+            None => return,
+        };
+        let timestamp = env::block_timestamp();
+        if self
+            .last_asset_rates_event_timestamps
+            .get(token_id)
+            .map(|last_timestamp| timestamp < last_timestamp + sec_to_nano(interval_sec))
+            .unwrap_or(false)
+        {
+            return;
+        }
+        events::emit::asset_rates_updated(
+            token_id,
+            &asset.get_borrow_apr(),
+            &asset.get_supply_apr(),
+            &asset.get_utilization(),
+        );
+        self.last_asset_rates_event_timestamps
+            .insert(token_id.clone(), timestamp);
+    }
+
+    /// Removes all snapshots of the asset.
+    pub fn internal_remove_asset_history(&mut self, token_id: &TokenId) {
+        self.last_asset_rates_event_timestamps.remove(token_id);
+        if self.last_asset_snapshot_days.remove(token_id).is_none() {
+            return;
+        }
+        for slot in 0..MAX_ASSET_HISTORY_DAYS {
+            self.asset_history.remove(&(token_id.clone(), slot));
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the daily snapshots of the asset for the last given number of days, including today.
+    /// The days without a recorded snapshot are skipped.
+    pub fn get_asset_history(&self, token_id: TokenId, days: u32) -> Vec<AssetSnapshotView> {
+        let today = timestamp_to_day(env::block_timestamp());
+        let days = std::cmp::min(days, MAX_ASSET_HISTORY_DAYS);
+        (today.saturating_sub(days) + 1..=today)
+            .filter_map(|day| {
+                self.asset_history
+                    .get(&(token_id.clone(), day % MAX_ASSET_HISTORY_DAYS))
+                    .filter(|snapshot| timestamp_to_day(snapshot.timestamp) == day)
+                    .map(|snapshot| snapshot.into())
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,141 @@
+use crate::*;
+use near_sdk::PromiseOrValue;
+
+const GAS_FOR_AFTER_FT_METADATA: Gas = Gas(Gas::ONE_TERA.0 * 10);
+
+/// The maximum reserve ratio of the assets listed through `apply_asset_listing`, e.g. 50%.
+const MAX_LISTING_RESERVE_RATIO: u32 = 5000;
+/// The maximum protocol fee ratio of the assets listed through `apply_asset_listing`, e.g. 50%.
+const MAX_LISTING_PROTOCOL_FEE_RATIO: u32 = 5000;
+/// The bounds of the target utilization of the assets listed through `apply_asset_listing`.
+const MIN_LISTING_TARGET_UTILIZATION: u32 = 1000;
+const MAX_LISTING_TARGET_UTILIZATION: u32 = 9500;
+/// The maximum volatility ratio of the assets listed through `apply_asset_listing`, e.g. 95%.
+const MAX_LISTING_VOLATILITY_RATIO: u32 = 9500;
+/// The maximum APR at 100% utilization of the assets listed through `apply_asset_listing`,
+/// e.g. 1000%.
+const MAX_LISTING_APR_MULTIPLIER: u32 = 11;
+/// The maximum number of extra decimals, so the inner balance doesn't exceed 24 decimals for the
+/// tokens with at least 6 decimals.
+const MAX_LISTING_EXTRA_DECIMALS: u8 = 18;
+
+impl AssetConfig {
+    /// Asserts that the config parameters are within the sane bounds for the governance listings.
+    pub fn assert_within_listing_bounds(&self) {
+        self.assert_valid();
+        assert!(
+            self.reserve_ratio <= MAX_LISTING_RESERVE_RATIO,
+            "The reserve ratio is out of bounds"
+        );
+        assert!(
+            self.protocol_fee_ratio <= MAX_LISTING_PROTOCOL_FEE_RATIO,
+            "The protocol fee ratio is out of bounds"
+        );
+        assert!(
+            (MIN_LISTING_TARGET_UTILIZATION..=MAX_LISTING_TARGET_UTILIZATION)
+                .contains(&self.target_utilization),
+            "The target utilization is out of bounds"
+        );
+        assert!(
+            self.volatility_ratio <= MAX_LISTING_VOLATILITY_RATIO,
+            "The volatility ratio is out of bounds"
+        );
+        assert!(
+            self.extra_decimals <= MAX_LISTING_EXTRA_DECIMALS,
+            "The extra decimals are out of bounds"
+        );
+        assert!(
+            BigDecimal::from(self.target_utilization_rate) >= BigDecimal::one(),
+            "The target utilization rate is out of bounds"
+        );
+        assert!(
+            BigDecimal::from(self.max_utilization_rate).pow(MS_PER_YEAR)
+                <= BigDecimal::from(MAX_LISTING_APR_MULTIPLIER),
+            "The max utilization rate is out of bounds"
+        );
+    }
+}
+
+impl Contract {
+    /// Validates the asset config and adds the asset once the extra decimals are checked against
+    /// the decimals from the token metadata. The metadata of the pool shares is requested from
+    /// the exchange.
+    pub fn internal_add_asset(&mut self, token_id: &TokenId, asset_config: AssetConfig) -> Promise {
+        self.assert_not_sunset();
+        asset_config.assert_valid();
+        self.internal_assert_lp_asset_config(token_id, &asset_config);
+        assert!(
+            !self.asset_ids.contains(token_id),
+            "The asset already exists"
+        );
+        self.internal_token_metadata(token_id)
+            .then(ext_asset_listing::after_ft_metadata(
+                token_id.clone(),
+                asset_config,
+                env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_AFTER_FT_METADATA,
+            ))
+    }
+
+    /// Adds the asset if the extra decimals match the decimals from the token metadata, and caches
+    /// the metadata.
+    pub fn internal_list_asset(
+        &mut self,
+        token_id: &TokenId,
+        asset_config: AssetConfig,
+        metadata: TokenMetadata,
+    ) {
+        self.internal_set_token_metadata(token_id, &metadata, &asset_config);
+        assert!(self.asset_ids.insert(token_id), "The asset already exists");
+        events::emit::asset_added(token_id, &asset_config);
+        self.internal_set_asset(token_id, Asset::new(env::block_timestamp(), asset_config));
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Lists a new asset or updates the config of the existing asset after validating that the
+    /// parameters are within the sane bounds. It's the entry point for the Sputnik DAO
+    /// `FunctionCall` proposals, when the DAO is the owner of the contract.
+    /// - Panics if the asset config is out of the listing bounds.
+    /// - Panics if the asset exists and the proposal timelock is not 0.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn apply_asset_listing(
+        &mut self,
+        token_id: AccountId,
+        asset_config: AssetConfig,
+    ) -> PromiseOrValue<()> {
+        assert_one_yocto();
+        self.assert_owner();
+        asset_config.assert_within_listing_bounds();
+        if self.asset_ids.contains(&token_id) {
+            self.assert_no_proposal_timelock();
+            self.internal_update_asset(&token_id, asset_config);
+            PromiseOrValue::Value(())
+        } else {
+            PromiseOrValue::Promise(self.internal_add_asset(&token_id, asset_config))
+        }
+    }
+}
+
+#[ext_contract(ext_asset_listing)]
+trait ExtAssetListing {
+    fn after_ft_metadata(&mut self, token_id: TokenId, asset_config: AssetConfig);
+}
+
+trait ExtAssetListing {
+    fn after_ft_metadata(&mut self, token_id: TokenId, asset_config: AssetConfig);
+}
+
+#[near_bindgen]
+impl ExtAssetListing for Contract {
+    /// Adds the asset if the extra decimals match the decimals from the token metadata.
+    #[private]
+    fn after_ft_metadata(&mut self, token_id: TokenId, asset_config: AssetConfig) {
+        let metadata = parse_ft_metadata_result().expect("Failed to get the token metadata");
+        self.internal_list_asset(&token_id, asset_config, metadata);
+    }
+}
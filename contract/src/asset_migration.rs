@@ -0,0 +1,138 @@
+use crate::*;
+
+impl Contract {
+    /// Converts the supplied, collateral and borrowed balances of the account in the deprecated
+    /// asset into its replacement at 1:1 of the inner balances. The conversion is handled against
+    /// the paired reserves, so the token balances of both assets don't change:
+    /// - the supplied and the collateral balances are added to the reserve of the deprecated asset
+    ///   and taken from the reserve of the replacement;
+    /// - the borrowed balance is repaid from the reserve of the deprecated asset and lent from the
+    ///   reserve of the replacement.
+    pub fn internal_migrate_asset(&mut self, account: &mut Account, token_id: &TokenId) {
+        let new_token_id = self
+            .asset_migrations
+            .get(token_id)
+            .expect("The asset migration is not set");
+        let mut asset = self.internal_unwrap_asset(token_id);
+        let mut new_asset = self.internal_unwrap_asset(&new_token_id);
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        account.add_affected_farm(FarmId::Borrowed(token_id.clone()));
+        account.add_affected_farm(FarmId::Supplied(new_token_id.clone()));
+        account.add_affected_farm(FarmId::Borrowed(new_token_id.clone()));
+
+        let mut supplied_amount = 0;
+        if let Some(mut account_asset) = account.internal_get_asset(token_id) {
+            let shares = account_asset.shares;
+            supplied_amount = asset.supplied.shares_to_amount(shares, Rounding::Down);
+            account_asset.withdraw_shares(shares);
+            account.internal_set_asset(token_id, account_asset);
+            asset.supplied.withdraw(shares, supplied_amount);
+        }
+        let mut collateral_amount = 0;
+        if let Some(shares) = account.collateral.get(token_id).cloned() {
+            collateral_amount = asset.supplied.shares_to_amount(shares, Rounding::Down);
+            account.decrease_collateral(token_id, shares);
+            asset.supplied.withdraw(shares, collateral_amount);
+            asset.decrease_collateral_shares(shares);
+        }
+        asset.reserved += supplied_amount + collateral_amount;
+        let mut borrowed_amount = 0;
+        if let Some(shares) = account.borrowed.get(token_id).cloned() {
+            borrowed_amount = asset.borrowed.shares_to_amount(shares, Rounding::Up);
+            account.decrease_borrowed(token_id, shares);
+            asset.borrowed.withdraw(shares, borrowed_amount);
+            assert!(
+                asset.reserved >= borrowed_amount,
+                "Not enough reserve of the deprecated asset"
+            );
+            asset.reserved -= borrowed_amount;
+        }
+        assert!(
+            supplied_amount + collateral_amount + borrowed_amount > 0,
+            "The account has no balance of the asset"
+        );
+
+        assert!(
+            new_asset.reserved + borrowed_amount >= supplied_amount + collateral_amount,
+            "Not enough reserve of the replacement asset"
+        );
+        new_asset.reserved =
+            new_asset.reserved + borrowed_amount - supplied_amount - collateral_amount;
+        if supplied_amount > 0 {
+            let shares = new_asset
+                .supplied
+                .amount_to_shares(supplied_amount, Rounding::Down);
+            new_asset.supplied.deposit(shares, supplied_amount);
+            let mut account_asset = account.internal_get_asset_or_default(&new_token_id);
+            account_asset.deposit_shares(shares);
+            account.internal_set_asset(&new_token_id, account_asset);
+        }
+        if collateral_amount > 0 {
+            let shares = new_asset
+                .supplied
+                .amount_to_shares(collateral_amount, Rounding::Down);
+            new_asset.supplied.deposit(shares, collateral_amount);
+            account.increase_collateral(&new_token_id, shares);
+            new_asset.collateral_shares.0 += shares.0;
+        }
+        if borrowed_amount > 0 {
+            let shares = new_asset
+                .borrowed
+                .amount_to_shares(borrowed_amount, Rounding::Up);
+            new_asset.borrowed.deposit(shares, borrowed_amount);
+            account.increase_borrowed(&new_token_id, shares);
+        }
+        self.internal_set_asset(token_id, asset);
+        self.internal_set_asset(&new_token_id, new_asset);
+        events::emit::asset_migrated(
+            &account.account_id,
+            token_id,
+            &new_token_id,
+            supplied_amount,
+            collateral_amount,
+            borrowed_amount,
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the replacement of the deprecated asset, e.g. of a bridged token that is wound down.
+    /// The accounts can convert their balances of the deprecated asset into the replacement at
+    /// 1:1 of the inner balances with the `MigrateAsset` action.
+    /// - Panics if any of the assets doesn't exist, the assets are the same, or the asset is not
+    ///   deprecated.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn set_asset_migration(&mut self, token_id: TokenId, new_token_id: TokenId) {
+        assert_one_yocto();
+        self.assert_owner();
+        assert_ne!(token_id, new_token_id, "The assets have to be different");
+        self.internal_unwrap_asset(&token_id);
+        self.internal_unwrap_asset(&new_token_id);
+        assert!(
+            self.deprecated_assets.contains(&token_id),
+            "The asset has to be deprecated"
+        );
+        self.asset_migrations.insert(&token_id, &new_token_id);
+    }
+
+    /// Removes the replacement of the deprecated asset.
+    /// - Panics if the asset migration is not set.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn remove_asset_migration(&mut self, token_id: TokenId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.asset_migrations
+            .remove(&token_id)
+            .expect("The asset migration is not set");
+    }
+
+    /// Returns the replacements of the deprecated assets by the deprecated token ID.
+    pub fn get_asset_migrations(&self) -> HashMap<TokenId, TokenId> {
+        self.asset_migrations.iter().collect()
+    }
+}
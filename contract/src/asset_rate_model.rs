@@ -0,0 +1,63 @@
+use crate::*;
+
+/// The interest rate model of an asset, i.e. the parameters of the asset config that define the
+/// borrow rate based on the utilization.
+#[derive(Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetRateModel {
+    /// The utilization at which the rate is `target_utilization_rate` (multiplied by 10000).
+    pub target_utilization: u32,
+    /// The compounding rate at the target utilization. Given as a decimal string.
+    pub target_utilization_rate: LowU128,
+    /// The compounding rate at the 100% utilization. Given as a decimal string.
+    pub max_utilization_rate: LowU128,
+}
+
+impl AssetConfig {
+    pub fn rate_model(&self) -> AssetRateModel {
+        AssetRateModel {
+            target_utilization: self.target_utilization,
+            target_utilization_rate: self.target_utilization_rate,
+            max_utilization_rate: self.max_utilization_rate,
+        }
+    }
+
+    pub fn set_rate_model(&mut self, rate_model: AssetRateModel) {
+        self.target_utilization = rate_model.target_utilization;
+        self.target_utilization_rate = rate_model.target_utilization_rate;
+        self.max_utilization_rate = rate_model.max_utilization_rate;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Swaps the interest rate model of the asset with the given token_id. The interest is
+    /// compounded with the old model up to the current block timestamp, so the new model only
+    /// applies from the switch. The rest of the asset config is not changed, and the frozen rate
+    /// of the asset still overrides the model.
+    /// - Panics if the resulting asset config is invalid.
+    /// - Panics if an asset with the given token_id doesn't exist.
+    /// - Panics if the proposal timelock is not 0.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn set_asset_rate_model(&mut self, token_id: AccountId, rate_model: AssetRateModel) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.assert_no_proposal_timelock();
+        // The asset is compounded to the current block timestamp when it's read.
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        let old_asset_config = asset.config.clone();
+        asset.config.set_rate_model(rate_model);
+        asset.config.assert_valid();
+        events::emit::asset_config_updated(&token_id, &old_asset_config, &asset.config);
+        self.internal_set_asset(&token_id, asset);
+    }
+
+    /// Returns the interest rate model of the asset with the given token_id.
+    /// - Panics if an asset with the given token_id doesn't exist.
+    pub fn get_asset_rate_model(&self, token_id: AccountId) -> AssetRateModel {
+        self.internal_unwrap_asset(&token_id).config.rate_model()
+    }
+}
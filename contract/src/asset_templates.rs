@@ -0,0 +1,148 @@
+use crate::*;
+use near_sdk::serde_json::{self, Value};
+
+/// The compounding rate for 5% APR.
+const RATE_5_PERCENT_APR: u128 = 1000000000001547125956667610;
+/// The compounding rate for 12% APR.
+const RATE_12_PERCENT_APR: u128 = 1000000000003593629036885046;
+/// The compounding rate for 30% APR.
+const RATE_30_PERCENT_APR: u128 = 1000000000008319516250272147;
+/// The compounding rate for 250% APR.
+const RATE_250_PERCENT_APR: u128 = 1000000000039724853136740579;
+
+/// The names of the built-in asset listing templates.
+pub const ASSET_TEMPLATE_NAMES: [&str; 3] = ["stablecoin", "major", "long_tail"];
+
+/// Returns the asset config of the built-in listing template with the given name, or `None` if
+/// the template doesn't exist. The templates don't set the extra decimals and the caps, since they
+/// depend on the token decimals.
+pub fn asset_template(template_name: &str) -> Option<AssetConfig> {
+    let (
+        reserve_ratio,
+        target_utilization,
+        target_utilization_rate,
+        volatility_ratio,
+        risk_tier,
+        borrowable_in_isolation,
+        can_flash_loan,
+    ) = match template_name {
+        "stablecoin" => (
+            2000,
+            8000,
+            RATE_5_PERCENT_APR,
+            9500,
+            AssetTier::BlueChip,
+            true,
+            true,
+        ),
+        "major" => (
+            2000,
+            8000,
+            RATE_12_PERCENT_APR,
+            6000,
+            AssetTier::BlueChip,
+            false,
+            true,
+        ),
+        // The long-tail assets have thin liquidity, so they are more conservative.
+        "long_tail" => (
+            3000,
+            6000,
+            RATE_30_PERCENT_APR,
+            4000,
+            AssetTier::LongTail,
+            false,
+            false,
+        ),
+        _ => return None,
+    };
+    Some(AssetConfig {
+        reserve_ratio,
+        target_utilization,
+        target_utilization_rate: target_utilization_rate.into(),
+        max_utilization_rate: RATE_250_PERCENT_APR.into(),
+        volatility_ratio,
+        extra_decimals: 0,
+        paused_actions: 0,
+        can_use_as_collateral: true,
+        net_tvl_multiplier: 10000,
+        supply_cap: None,
+        borrow_cap: None,
+        isolation_debt_ceiling: None,
+        borrowable_in_isolation,
+        permissioned: false,
+        frozen_rate: None,
+        fee_on_transfer: false,
+        min_borrow_amount: None,
+        risk_tier: Some(risk_tier),
+        can_flash_loan,
+        flash_loan_fee: None,
+        protocol_fee_ratio: 0,
+        collateral_cap: None,
+        reserve_only: false,
+        oracle_price_decimals: None,
+        collateral_only: false,
+        dust_threshold: None,
+    })
+}
+
+/// Applies the overrides to the asset config. The overrides are the asset config parameters in
+/// the JSON format, e.g. `{"extra_decimals": 12}`.
+/// - Panics if an override is not a parameter of the asset config or has an invalid value.
+pub fn apply_asset_config_overrides(
+    asset_config: AssetConfig,
+    overrides: HashMap<String, Value>,
+) -> AssetConfig {
+    let mut value = serde_json::to_value(asset_config).unwrap();
+    let params = value.as_object_mut().unwrap();
+    for (key, override_value) in overrides {
+        assert!(
+            params.contains_key(&key),
+            "Unknown asset config parameter {}",
+            key
+        );
+        params.insert(key, override_value);
+    }
+    serde_json::from_value(value).expect("Invalid asset config overrides")
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Lists a new asset with the config of the built-in template with the given name, i.e.
+    /// `stablecoin`, `major` or `long_tail`, and the given overrides of the template parameters,
+    /// so the listings reuse the vetted parameters and only override what's intentionally
+    /// different, e.g. the extra decimals.
+    /// - Panics if the template doesn't exist or an override is unknown or invalid.
+    /// - Panics if the resulting asset config is out of the listing bounds.
+    /// - Panics if the asset already exists.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn add_asset_from_template(
+        &mut self,
+        token_id: AccountId,
+        template_name: String,
+        overrides: Option<HashMap<String, Value>>,
+    ) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        let asset_config = asset_template(&template_name).expect("Unknown asset template");
+        let asset_config =
+            apply_asset_config_overrides(asset_config, overrides.unwrap_or_default());
+        asset_config.assert_within_listing_bounds();
+        self.internal_add_asset(&token_id, asset_config)
+    }
+
+    /// Returns the asset configs of the built-in listing templates by the template name.
+    pub fn get_asset_templates(&self) -> HashMap<String, AssetConfig> {
+        ASSET_TEMPLATE_NAMES
+            .iter()
+            .map(|template_name| {
+                (
+                    template_name.to_string(),
+                    asset_template(template_name).unwrap(),
+                )
+            })
+            .collect()
+    }
+}
@@ -22,6 +22,29 @@ pub struct AssetDetailedView {
     pub supply_apr: BigDecimal,
     /// Current APR excluding farms for borrowing the asset.
     pub borrow_apr: BigDecimal,
+    /// The ratio of the borrowed balance to the total supplied balance including reserved.
+    pub utilization: BigDecimal,
+    /// The amount that is available to borrow or withdraw.
+    #[serde(with = "u128_dec_format")]
+    pub available_amount: Balance,
+    /// The total borrowed balance of the accounts that use this isolated asset as collateral.
+    #[serde(with = "u128_dec_format")]
+    pub isolated_debt: Balance,
+    /// The total amount lent out with the flash loans of the asset.
+    #[serde(with = "u128_dec_format")]
+    pub flash_loan_volume: Balance,
+    /// The total amount of the flash loan fees of the asset collected to the reserve.
+    #[serde(with = "u128_dec_format")]
+    pub flash_loan_fees: Balance,
+    /// The total supplied shares of the asset that are used as collateral by the accounts.
+    pub collateral_shares: Shares,
+    /// The amount that can still be supplied under the supply cap. `None` if there is no cap.
+    pub remaining_supply_capacity: Option<U128>,
+    /// The amount that can still be borrowed under the borrow cap. `None` if there is no cap.
+    pub remaining_borrow_capacity: Option<U128>,
+    /// The amount that can still be added to the collateral under the collateral cap. `None` if
+    /// there is no cap.
+    pub remaining_collateral_capacity: Option<U128>,
     /// Asset farms
     pub farms: Vec<AssetFarmView>,
 }
@@ -50,12 +73,22 @@ impl Contract {
             .collect();
         let supply_apr = asset.get_supply_apr();
         let borrow_apr = asset.get_borrow_apr();
+        let utilization = asset.get_utilization();
+        let available_amount = asset.available_amount();
+        let remaining_supply_capacity = asset.remaining_supply_capacity().map(U128);
+        let remaining_borrow_capacity = asset.remaining_borrow_capacity().map(U128);
+        let remaining_collateral_capacity = asset.remaining_collateral_capacity().map(U128);
         let Asset {
             supplied,
             borrowed,
             reserved,
             last_update_timestamp,
             config,
+            isolated_debt,
+            flash_loan_volume,
+            flash_loan_fees,
+            collateral_shares,
+            ..
         } = asset;
         AssetDetailedView {
             token_id,
@@ -66,6 +99,15 @@ impl Contract {
             config,
             supply_apr,
             borrow_apr,
+            utilization,
+            available_amount,
+            isolated_debt,
+            flash_loan_volume,
+            flash_loan_fees,
+            collateral_shares,
+            remaining_supply_capacity,
+            remaining_borrow_capacity,
+            remaining_collateral_capacity,
             farms,
         }
     }
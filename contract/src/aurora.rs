@@ -0,0 +1,121 @@
+use crate::*;
+use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
+use near_sdk::json_types::U128;
+
+const GAS_FOR_AURORA_TRANSFER: Gas = Gas(Gas::ONE_TERA.0 * 50);
+const GAS_FOR_AFTER_AURORA_TRANSFER: Gas = Gas(Gas::ONE_TERA.0 * 20);
+
+/// Returns the Aurora address in the lowercase hex without the `0x` prefix, which is the message
+/// format of the deposits to Aurora.
+pub fn parse_aurora_address(address: &str) -> String {
+    let address = address.strip_prefix("0x").unwrap_or(address);
+    assert!(
+        address.len() == 40 && address.chars().all(|c| c.is_ascii_hexdigit()),
+        "Invalid Aurora address"
+    );
+    address.to_ascii_lowercase()
+}
+
+impl Contract {
+    fn internal_unwrap_aurora_engine_id(&self) -> AccountId {
+        self.internal_config()
+            .aurora_engine_id
+            .expect("The Aurora bridge is not enabled")
+    }
+
+    /// Deposits the tokens bridged from Aurora to the supplied balance of the account given in the
+    /// transfer message.
+    pub fn internal_aurora_deposit(
+        &mut self,
+        sender_id: &AccountId,
+        token_id: &TokenId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) {
+        assert_eq!(
+            sender_id,
+            &self.internal_unwrap_aurora_engine_id(),
+            "Only the Aurora engine can deposit on behalf of an account"
+        );
+        self.internal_assert_allowlisted(token_id, account_id);
+        let mut account = self.internal_unwrap_account(account_id);
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        let shares = self.internal_deposit(&mut account, token_id, amount);
+        self.internal_record_supplied_volume(&mut account, token_id, amount);
+        self.internal_unwrap_asset(token_id).assert_supply_cap();
+        self.internal_assert_canary_caps(&account);
+        events::emit::deposit(account_id, amount, shares, token_id);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(account_id, account);
+    }
+
+    /// Transfers the withdrawn amount to the Aurora address through the bridge, i.e. with
+    /// `ft_transfer_call` to the Aurora engine with the address as the message. The refunded
+    /// amount becomes claimable by the account.
+    pub fn internal_aurora_transfer(
+        &mut self,
+        account_id: &AccountId,
+        token_id: &TokenId,
+        amount: Balance,
+        recipient: String,
+    ) -> Promise {
+        let aurora_engine_id = self.internal_unwrap_aurora_engine_id();
+        assert!(
+            parse_pool_id(token_id).is_none() && !self.mt_tokens.contains_key(token_id),
+            "The asset can't be bridged to Aurora"
+        );
+        let asset = self.internal_unwrap_asset(token_id);
+        let ft_amount = amount / 10u128.pow(asset.config.extra_decimals as u32);
+        self.internal_lock_account(account_id);
+        ext_fungible_token::ft_transfer_call(
+            aurora_engine_id,
+            ft_amount.into(),
+            None,
+            recipient,
+            token_id.clone(),
+            ONE_YOCTO,
+            GAS_FOR_AURORA_TRANSFER,
+        )
+        .then(ext_aurora::after_aurora_transfer(
+            account_id.clone(),
+            token_id.clone(),
+            amount.into(),
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_AURORA_TRANSFER,
+        ))
+    }
+}
+
+#[ext_contract(ext_aurora)]
+trait ExtAurora {
+    fn after_aurora_transfer(&mut self, account_id: AccountId, token_id: TokenId, amount: U128);
+}
+
+trait ExtAurora {
+    fn after_aurora_transfer(&mut self, account_id: AccountId, token_id: TokenId, amount: U128);
+}
+
+#[near_bindgen]
+impl ExtAurora for Contract {
+    /// Records the amount refunded by the Aurora engine as claimable by the account.
+    #[private]
+    fn after_aurora_transfer(&mut self, account_id: AccountId, token_id: TokenId, amount: U128) {
+        self.internal_unlock_account(&account_id);
+        let asset = self.internal_unwrap_asset(&token_id);
+        let refunded = transfer_call_refunded_amount(amount.0, asset.config.extra_decimals);
+        if refunded < amount.0 {
+            events::emit::withdraw_succeeded(&account_id, amount.0 - refunded, &token_id);
+        }
+        if refunded == 0 {
+            return;
+        }
+        if let Some(mut account) = self.internal_get_account(&account_id, false) {
+            self.internal_add_withdraw_claim(&mut account, &token_id, refunded);
+            self.internal_set_account_in_callback(&account_id, account);
+        } else {
+            self.internal_add_unregistered_withdraw_claim(&account_id, &token_id, refunded);
+        }
+        events::emit::withdraw_failed(&account_id, refunded, &token_id);
+    }
+}
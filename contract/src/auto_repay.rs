@@ -0,0 +1,175 @@
+use crate::*;
+
+/// The maximum number of auto-repay orders per account.
+const MAX_NUM_AUTO_REPAY_ORDERS: usize = 10;
+
+/// A conditional order to repay the borrowed asset from the supplied balance of the account.
+/// Anyone can execute the order through the oracle call once the health factor of the account
+/// drops below the trigger and receive the tip.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AutoRepayOrder {
+    pub order_id: u32,
+    /// The health factor (multiplied by 10000) below which the order can be executed.
+    /// E.g. 10500 means the adjusted collateral sum is less than 105% of the adjusted borrowed sum.
+    pub trigger_health: u32,
+    pub token_id: TokenId,
+    /// The maximum amount of the borrowed asset to repay.
+    #[serde(with = "u128_dec_format")]
+    pub max_amount: Balance,
+    /// The amount of the asset paid from the supplied balance to the account executing the order.
+    #[serde(with = "u128_dec_format")]
+    pub tip: Balance,
+}
+
+impl Contract {
+    pub fn internal_get_auto_repay_orders(&self, account_id: &AccountId) -> Vec<AutoRepayOrder> {
+        self.auto_repay_orders.get(account_id).unwrap_or_default()
+    }
+
+    /// Saves the orders and records the storage change under the account storage tracker.
+    pub fn internal_set_auto_repay_orders(
+        &mut self,
+        account: &mut Account,
+        orders: Vec<AutoRepayOrder>,
+    ) {
+        account.storage_tracker.start();
+        if orders.is_empty() {
+            self.auto_repay_orders.remove(&account.account_id);
+        } else {
+            self.auto_repay_orders.insert(&account.account_id, &orders);
+        }
+        account.storage_tracker.stop();
+    }
+
+    /// Executes the auto-repay order of the given account. The tip is paid first, and the rest of
+    /// the supplied balance is used to repay up to the maximum amount of the order.
+    pub fn internal_execute_auto_repay_order(
+        &mut self,
+        account: &mut Account,
+        prices: &Prices,
+        order_account_id: &AccountId,
+        order_id: u32,
+    ) {
+        let mut order_account = self.internal_unwrap_account(order_account_id);
+        let mut orders = self.internal_get_auto_repay_orders(order_account_id);
+        let index = orders
+            .iter()
+            .position(|order| order.order_id == order_id)
+            .expect("Auto-repay order not found");
+        let order = orders.remove(index);
+
+        let (collateral_sum, borrowed_sum) = self.compute_adjusted_sums(&order_account, prices);
+        assert!(
+            collateral_sum < borrowed_sum.mul_ratio(order.trigger_health),
+            "The health factor is not below the trigger of the order"
+        );
+
+        let token_id = &order.token_id;
+        order_account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        order_account.add_affected_farm(FarmId::Borrowed(token_id.clone()));
+        let mut order_account_asset = order_account.internal_unwrap_asset(token_id);
+
+        if order.tip > 0 {
+            let asset = self.internal_unwrap_asset(token_id);
+            let tip_shares = asset.supplied.amount_to_shares(order.tip, Rounding::Up);
+            order_account_asset.withdraw_shares(tip_shares);
+            account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+            let mut account_asset = account.internal_get_asset_or_default(token_id);
+            account_asset.deposit_shares(tip_shares);
+            account.internal_set_asset(token_id, account_asset);
+        }
+
+        let (shares, amount) = self.internal_repay(
+            &mut order_account_asset,
+            &mut order_account,
+            &AssetAmount {
+                token_id: token_id.clone(),
+                amount: None,
+                max_amount: Some(order.max_amount.into()),
+            },
+        );
+        order_account.internal_set_asset(token_id, order_account_asset);
+        events::emit::repay(order_account_id, amount, shares, token_id);
+        events::emit::auto_repay_order_executed(
+            &account.account_id,
+            order_account_id,
+            order_id,
+            amount,
+            token_id,
+            order.tip,
+        );
+
+        self.internal_set_auto_repay_orders(&mut order_account, orders);
+        self.internal_account_apply_affected_farms(&mut order_account);
+        self.internal_set_account(order_account_id, order_account);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Adds an auto-repay order for the predecessor account and returns the order ID.
+    /// - `trigger_health` - the health factor (multiplied by 10000) below which the order can be
+    ///   executed. Should be greater than 10000.
+    /// - `max_amount` - the maximum amount of the borrowed asset to repay.
+    /// - `tip` - the amount of the asset paid to the account executing the order.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn add_auto_repay_order(
+        &mut self,
+        token_id: TokenId,
+        trigger_health: u32,
+        max_amount: U128,
+        tip: U128,
+    ) -> u32 {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        self.internal_unwrap_asset(&token_id);
+        assert!(
+            trigger_health > MAX_RATIO,
+            "The trigger health factor should be greater than 1"
+        );
+        assert!(max_amount.0 > 0, "The maximum amount should be positive");
+
+        let mut orders = self.internal_get_auto_repay_orders(&account_id);
+        assert!(
+            orders.len() < MAX_NUM_AUTO_REPAY_ORDERS,
+            "Too many auto-repay orders"
+        );
+        let order_id = orders.last().map(|order| order.order_id + 1).unwrap_or(0);
+        orders.push(AutoRepayOrder {
+            order_id,
+            trigger_health,
+            token_id,
+            max_amount: max_amount.0,
+            tip: tip.0,
+        });
+        self.internal_set_auto_repay_orders(&mut account, orders);
+        self.internal_set_account(&account_id, account);
+        order_id
+    }
+
+    /// Removes the auto-repay order of the predecessor account.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn remove_auto_repay_order(&mut self, order_id: u32) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        let mut orders = self.internal_get_auto_repay_orders(&account_id);
+        let index = orders
+            .iter()
+            .position(|order| order.order_id == order_id)
+            .expect("Auto-repay order not found");
+        orders.remove(index);
+        self.internal_set_auto_repay_orders(&mut account, orders);
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Returns the auto-repay orders of the given account.
+    pub fn get_auto_repay_orders(&self, account_id: AccountId) -> Vec<AutoRepayOrder> {
+        self.internal_get_auto_repay_orders(&account_id)
+    }
+}
@@ -24,6 +24,22 @@ const HALF_DIVISOR: u128 = BIG_DIVISOR / 2;
 
 pub type LowU128 = U128;
 
+/// Returns the integer square root, i.e. the largest `x` such that `x * x <= n`, with the Newton
+/// iteration starting above the root.
+fn u384_isqrt(n: U384) -> U384 {
+    if n.is_zero() {
+        return n;
+    }
+    let mut x = U384::one() << n.bits().div_ceil(2);
+    loop {
+        let y = (x + n / x) >> 1;
+        if y >= x {
+            return x;
+        }
+        x = y;
+    }
+}
+
 #[derive(Copy, Clone)]
 pub struct BigDecimal(U384);
 
@@ -71,7 +87,7 @@ impl FromStr for BigDecimal {
         } else {
             (s, 0u128)
         };
-        let int = U384::from_str(&int).map_err(|_| PARSE_INT_ERROR)?;
+        let int = U384::from_dec_str(int).map_err(|_| PARSE_INT_ERROR)?;
         if dec >= BIG_DIVISOR {
             return Err(String::from("The decimal part is too large"));
         }
@@ -121,6 +137,9 @@ impl From<u32> for BigDecimal {
 
 impl From<f64> for BigDecimal {
     fn from(a: f64) -> Self {
+        audit_assert(a.is_finite() && a >= 0f64 && a < u128::MAX as f64, || {
+            format!("f64 {} is saturated by the conversion to BigDecimal", a)
+        });
         let base = a as u128;
         Self(
             U384::from(base) * U384::from(BIG_DIVISOR)
@@ -141,6 +160,9 @@ impl Sub for BigDecimal {
     type Output = Self;
 
     fn sub(self, rhs: BigDecimal) -> Self::Output {
+        audit_assert(self.0 >= rhs.0, || {
+            format!("BigDecimal subtraction {} - {} underflows", self, rhs)
+        });
         Self(self.0 - rhs.0)
     }
 }
@@ -157,6 +179,9 @@ impl Div for BigDecimal {
     type Output = Self;
 
     fn div(self, rhs: Self) -> Self::Output {
+        audit_assert(!rhs.0.is_zero(), || {
+            format!("BigDecimal division {} / 0", self)
+        });
         Self((self.0 * U384::from(BIG_DIVISOR) + U384::from(HALF_DIVISOR)) / rhs.0)
     }
 }
@@ -169,10 +194,34 @@ impl From<LowU128> for BigDecimal {
 
 impl From<BigDecimal> for LowU128 {
     fn from(bd: BigDecimal) -> Self {
+        audit_assert(bd.0.bits() <= 128, || {
+            format!("BigDecimal to LowU128 conversion truncates {}", bd)
+        });
         Self(bd.0.low_u128())
     }
 }
 
+impl From<RewardPerShare> for BigDecimal {
+    fn from(value: RewardPerShare) -> Self {
+        let mut words = [0u64; 6];
+        words[..4].copy_from_slice(&(value.0).0);
+        Self(U384(words))
+    }
+}
+
+impl From<BigDecimal> for RewardPerShare {
+    fn from(value: BigDecimal) -> Self {
+        let words = (value.0).0;
+        assert!(
+            words[4..].iter().all(|&word| word == 0),
+            "The reward per share doesn't fit into u256"
+        );
+        let mut low_words = [0u64; 4];
+        low_words.copy_from_slice(&words[..4]);
+        Self(U256(low_words))
+    }
+}
+
 impl BigDecimal {
     pub fn from_ratio(ratio: u32) -> Self {
         Self(U384::from(ratio) * U384::from(BIG_DIVISOR / (MAX_RATIO as u128)))
@@ -197,24 +246,53 @@ impl BigDecimal {
     }
 
     pub fn round_u128(&self) -> u128 {
-        ((self.0 + U384::from(HALF_DIVISOR)) / U384::from(BIG_DIVISOR)).as_u128()
+        u384_to_u128(
+            (self.0 + U384::from(HALF_DIVISOR)) / U384::from(BIG_DIVISOR),
+            "BigDecimal::round_u128",
+        )
+    }
+
+    pub fn floor_u128(&self) -> u128 {
+        u384_to_u128(self.0 / U384::from(BIG_DIVISOR), "BigDecimal::floor_u128")
     }
 
     #[cfg(not(target_arch = "wasm32"))]
     pub fn f64(&self) -> f64 {
         let base = (self.0 / U384::from(BIG_DIVISOR)).as_u128();
-        let fract = (self.0 - U384::from(base)).as_u128() as f64;
+        let fract = (self.0 - U384::from(base) * U384::from(BIG_DIVISOR)).as_u128() as f64;
         base as f64 + fract / (BIG_DIVISOR as f64)
     }
 
     pub fn round_mul_u128(&self, rhs: u128) -> u128 {
-        ((self.0 * U384::from(rhs) + U384::from(HALF_DIVISOR)) / U384::from(BIG_DIVISOR)).as_u128()
+        u384_to_u128(
+            (self.0 * U384::from(rhs) + U384::from(HALF_DIVISOR)) / U384::from(BIG_DIVISOR),
+            "BigDecimal::round_mul_u128",
+        )
+    }
+
+    /// Same as `round_mul_u128`, but returns `None` if the result doesn't fit into `u128`.
+    pub fn checked_round_mul_u128(&self, rhs: u128) -> Option<u128> {
+        let res = self
+            .0
+            .checked_mul(U384::from(rhs))?
+            .checked_add(U384::from(HALF_DIVISOR))?
+            / U384::from(BIG_DIVISOR);
+        if res > U384::from(u128::MAX) {
+            None
+        } else {
+            Some(res.as_u128())
+        }
     }
 
     pub fn div_u128(&self, rhs: u128) -> BigDecimal {
+        audit_assert(rhs != 0, || format!("BigDecimal division {} / 0", self));
         Self(self.0 / U384::from(rhs))
     }
 
+    pub fn sqrt(&self) -> Self {
+        Self(u384_isqrt(self.0 * U384::from(BIG_DIVISOR)))
+    }
+
     pub fn zero() -> Self {
         Self(U384::zero())
     }
@@ -223,21 +301,37 @@ impl BigDecimal {
         Self(U384::from(BIG_DIVISOR))
     }
 
-    pub fn pow(&self, mut exponent: u64) -> Self {
+    pub fn pow(&self, exponent: u64) -> Self {
+        self.checked_pow(exponent)
+            .expect("BigDecimal power overflow")
+    }
+
+    /// Returns the power of the number, or `None` if the result or the intermediate values don't
+    /// fit into 384 bits.
+    pub fn checked_pow(&self, mut exponent: u64) -> Option<Self> {
         let mut res = BigDecimal::one();
         let mut x = *self;
 
         while exponent != 0 {
             if (exponent & 1) != 0 {
-                res = res * x;
+                res = res.checked_mul(x)?;
             }
             exponent >>= 1;
             if exponent != 0 {
-                x = x * x;
+                x = x.checked_mul(x)?;
             }
         }
 
-        res
+        Some(res)
+    }
+
+    fn checked_mul(self, rhs: Self) -> Option<Self> {
+        Some(Self(
+            self.0
+                .checked_mul(rhs.0)?
+                .checked_add(U384::from(HALF_DIVISOR))?
+                / U384::from(BIG_DIVISOR),
+        ))
     }
 }
 
@@ -268,6 +362,7 @@ impl BorshDeserialize for BigDecimal {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
     use rand::RngCore;
 
     // Number of milliseconds in a regular year.
@@ -309,6 +404,31 @@ mod tests {
         assert_eq!((b(3) / b(5)).round_u128(), 1);
     }
 
+    #[test]
+    fn test_u384_isqrt() {
+        assert_eq!(u384_isqrt(U384::zero()), U384::zero());
+        assert_eq!(u384_isqrt(U384::one()), U384::one());
+        assert_eq!(u384_isqrt(U384::from(15)), U384::from(3));
+        assert_eq!(u384_isqrt(U384::from(16)), U384::from(4));
+        assert_eq!(u384_isqrt(U384::from(17)), U384::from(4));
+        let x = U384::from(u128::MAX) * U384::from(u64::MAX);
+        assert_eq!(u384_isqrt(x * x), x);
+        assert_eq!(u384_isqrt(x * x - U384::one()), x - U384::one());
+        assert_eq!(u384_isqrt(U384::MAX), (U384::one() << 192) - U384::one());
+    }
+
+    #[test]
+    fn test_sqrt() {
+        assert_eq!(b(0).sqrt().round_u128(), 0);
+        assert_eq!(b(16).sqrt().round_u128(), 4);
+        assert_eq!((b(2).sqrt() * b(2).sqrt()).round_u128(), 2);
+        almost_eq(
+            LowU128::from(b(2).sqrt()).0,
+            1414213562373095048801688724,
+            26,
+        );
+    }
+
     #[test]
     fn test_pow() {
         let r = BigDecimal::from(LOW_R);
@@ -370,6 +490,16 @@ mod tests {
         almost_eq(val, initial_val * 2, 15);
     }
 
+    #[test]
+    fn test_checked_pow_overflow() {
+        let r = BigDecimal::from(LOW_R);
+        assert!(r.checked_pow(N) == Some(r.pow(N)));
+        // 2 ** 300 doesn't fit into 384 bits with 27 decimals.
+        assert!(b(2).checked_pow(300).is_none());
+        assert_eq!(b(2).checked_round_mul_u128(u128::MAX), None);
+        assert_eq!(b(2).checked_round_mul_u128(3), Some(6));
+    }
+
     #[test]
     fn test_display() {
         assert_eq!("1.0", BigDecimal::one().to_string());
@@ -378,4 +508,70 @@ mod tests {
         assert!(BigDecimal::from(1.5f64).to_string().starts_with("1.500000"));
         assert!(BigDecimal::from(0.5f64).to_string().starts_with("0.500000"));
     }
+
+    #[test]
+    fn test_f64() {
+        assert_eq!(BigDecimal::one().f64(), 1.0);
+        assert_eq!(BigDecimal::from(2u32).f64(), 2.0);
+        assert!((BigDecimal::from(1.5f64).f64() - 1.5).abs() < 1e-9);
+    }
+
+    fn any_decimal() -> impl Strategy<Value = BigDecimal> {
+        any::<u128>().prop_map(|raw| BigDecimal::from(U128(raw)))
+    }
+
+    // The values of up to a million, with the full precision of the fraction.
+    fn small() -> impl Strategy<Value = BigDecimal> {
+        (0..10u128.pow(33)).prop_map(|raw| BigDecimal::from(U128(raw)))
+    }
+
+    // The rates per millisecond of up to 100% APR.
+    fn rate() -> impl Strategy<Value = BigDecimal> {
+        (BIG_DIVISOR..=LOW_R.0).prop_map(|raw| BigDecimal::from(U128(raw)))
+    }
+
+    proptest! {
+        #[test]
+        fn prop_add_sub_round_trip(a in any_decimal(), b in any_decimal()) {
+            prop_assert!((a + b) - b == a);
+        }
+
+        #[test]
+        fn prop_add_associative(a in any_decimal(), b in any_decimal(), c in any_decimal()) {
+            prop_assert!((a + b) + c == a + (b + c));
+        }
+
+        #[test]
+        fn prop_mul_commutative(a in small(), b in small()) {
+            prop_assert!(a * b == b * a);
+        }
+
+        #[test]
+        fn prop_mul_associative_within_rounding(a in small(), b in small(), c in small()) {
+            // Every product rounds by at most half of the last digit, which the outer product
+            // scales by the other factor.
+            let (left, right) = ((a * b) * c, a * (b * c));
+            let diff = if left > right {
+                left.0 - right.0
+            } else {
+                right.0 - left.0
+            };
+            prop_assert!(diff <= (a.0 + c.0) / U384::from(BIG_DIVISOR) + U384::from(1));
+        }
+
+        #[test]
+        fn prop_mul_monotonic(a in small(), b in small(), c in small()) {
+            let (low, high) = if a < b { (a, b) } else { (b, a) };
+            prop_assert!(low * c <= high * c);
+            prop_assert!(
+                c.round_mul_u128(low.round_u128()) <= c.round_mul_u128(high.round_u128())
+            );
+        }
+
+        #[test]
+        fn prop_pow_monotonic(a in rate(), b in rate(), exponent in 0..N) {
+            let (low, high) = if a < b { (a, b) } else { (b, a) };
+            prop_assert!(low.pow(exponent) <= high.pow(exponent));
+        }
+    }
 }
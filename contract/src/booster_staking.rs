@@ -41,11 +41,16 @@ impl Contract {
         let mut account_asset = account.internal_unwrap_asset(&booster_token_id);
 
         let (shares, amount) = if let Some(amount) = amount.map(|a| a.0) {
-            (asset.supplied.amount_to_shares(amount, true), amount)
+            (
+                asset.supplied.amount_to_shares(amount, Rounding::Up),
+                amount,
+            )
         } else {
             (
                 account_asset.shares,
-                asset.supplied.shares_to_amount(account_asset.shares, false),
+                asset
+                    .supplied
+                    .shares_to_amount(account_asset.shares, Rounding::Down),
             )
         };
         assert!(
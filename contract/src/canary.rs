@@ -0,0 +1,171 @@
+use crate::*;
+
+/// The caps of a canary deployment, e.g. of a new version on the mainnet. The deposits are
+/// rejected once the total supplied value or the supplied value of the account exceeds the caps.
+/// The owner raises the caps stage by stage and removes them once the deployment is trusted.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct CanaryConfig {
+    /// The stage of the canary deployment, increased every time the caps are raised.
+    pub stage: u32,
+    /// The maximum total value of the supplied assets in USD using the last recorded prices.
+    pub max_total_supplied_value: U128,
+    /// The maximum value of the supplied assets, including the collateral, of one account in USD
+    /// using the last recorded prices.
+    pub max_account_supplied_value: U128,
+}
+
+/// The current stage of the canary deployment.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct CanaryStage {
+    pub stage: u32,
+    pub max_total_supplied_value: U128,
+    pub max_account_supplied_value: U128,
+    /// The total value of the supplied assets using the last recorded prices.
+    pub total_supplied_value: BigDecimal,
+}
+
+impl Contract {
+    /// Returns the total value of the supplied assets using the last recorded prices. The assets
+    /// without a recorded price are not counted.
+    pub fn internal_get_total_supplied_value(&self) -> BigDecimal {
+        self.asset_ids
+            .iter()
+            .filter_map(|token_id| {
+                let price = self.last_prices.get(&token_id)?;
+                let asset = self.internal_unwrap_asset(&token_id);
+                Some(BigDecimal::from_balance_price(
+                    asset.supplied.balance,
+                    price,
+                    asset.config.extra_decimals,
+                ))
+            })
+            .fold(BigDecimal::zero(), |total, value| total + value)
+    }
+
+    /// Returns the value of the supplied assets and the collateral of the account using the last
+    /// recorded prices. The assets without a recorded price are not counted.
+    fn internal_get_account_supplied_value(&self, account: &Account) -> BigDecimal {
+        account
+            .supplied
+            .iter()
+            .chain(account.collateral.iter())
+            .filter_map(|(token_id, shares)| {
+                let price = self.last_prices.get(token_id)?;
+                let asset = self.internal_unwrap_asset(token_id);
+                Some(BigDecimal::from_balance_price(
+                    asset.supplied.shares_to_amount(*shares, Rounding::Down),
+                    price,
+                    asset.config.extra_decimals,
+                ))
+            })
+            .fold(BigDecimal::zero(), |total, value| total + value)
+    }
+
+    /// Asserts that the deposit to the account doesn't exceed the caps of the canary deployment.
+    pub fn internal_assert_canary_caps(&self, account: &Account) {
+        self.internal_assert_canary_caps_with_pending(account, None);
+    }
+
+    /// Asserts that the caps of the canary deployment are not exceeded once the pending deposit
+    /// of the asset, which is not credited yet, is added to the account.
+    pub fn internal_assert_canary_caps_with_pending(
+        &self,
+        account: &Account,
+        pending_deposit: Option<(&TokenId, Balance)>,
+    ) {
+        let canary = match self.internal_config().canary {
+            Some(canary) => canary,
+            None => return,
+        };
+        let pending_value = pending_deposit
+            .and_then(|(token_id, amount)| {
+                let price = self.last_prices.get(token_id)?;
+                let asset = self.internal_unwrap_asset(token_id);
+                Some(BigDecimal::from_balance_price(
+                    amount,
+                    price,
+                    asset.config.extra_decimals,
+                ))
+            })
+            .unwrap_or_else(BigDecimal::zero);
+        assert!(
+            self.internal_get_total_supplied_value() + pending_value
+                <= BigDecimal::from(canary.max_total_supplied_value.0),
+            "The total supplied value exceeds the canary cap of {} USD",
+            canary.max_total_supplied_value.0
+        );
+        assert!(
+            self.internal_get_account_supplied_value(account) + pending_value
+                <= BigDecimal::from(canary.max_account_supplied_value.0),
+            "The supplied value of the account exceeds the canary cap of {} USD",
+            canary.max_account_supplied_value.0
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the current stage of the canary deployment, or `None` if the deposits are not
+    /// capped.
+    pub fn get_canary_stage(&self) -> Option<CanaryStage> {
+        self.internal_config().canary.map(|canary| CanaryStage {
+            stage: canary.stage,
+            max_total_supplied_value: canary.max_total_supplied_value,
+            max_account_supplied_value: canary.max_account_supplied_value,
+            total_supplied_value: self.internal_get_total_supplied_value(),
+        })
+    }
+
+    /// Raises the caps of the canary deployment and moves it to the next stage.
+    /// - Panics if the contract is not in the canary deployment.
+    /// - Panics if any of the caps is lowered.
+    /// - Panics if the proposal timelock is not 0.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn raise_canary_caps(
+        &mut self,
+        max_total_supplied_value: U128,
+        max_account_supplied_value: U128,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.assert_no_proposal_timelock();
+        let mut config = self.internal_config();
+        let canary = config
+            .canary
+            .as_mut()
+            .expect("The contract is not in the canary deployment");
+        assert!(
+            max_total_supplied_value.0 >= canary.max_total_supplied_value.0
+                && max_account_supplied_value.0 >= canary.max_account_supplied_value.0,
+            "The canary caps can only be raised"
+        );
+        canary.stage += 1;
+        canary.max_total_supplied_value = max_total_supplied_value;
+        canary.max_account_supplied_value = max_account_supplied_value;
+        self.internal_update_config(config);
+    }
+
+    /// Ends the canary deployment, removing the caps.
+    /// - Panics if the contract is not in the canary deployment.
+    /// - Panics if the proposal timelock is not 0.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn end_canary_deployment(&mut self) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.assert_no_proposal_timelock();
+        let mut config = self.internal_config();
+        assert!(
+            config.canary.take().is_some(),
+            "The contract is not in the canary deployment"
+        );
+        self.internal_update_config(config);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::*;
+
+/// The recount of the total collateral shares of the asset from the collateral of the accounts.
+/// The assets migrated from the state before the total was tracked start with 0 collateral
+/// shares, so the total is recounted by the paginated `backfill_collateral_shares` calls. All
+/// actions of the asset are paused until the recount is completed, so the collateral of the
+/// accounts can't change while it's counted.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct CollateralSharesBackfill {
+    /// The collateral shares of the accounts counted so far.
+    pub collateral_shares: Shares,
+    /// The paused actions of the asset that are restored when the backfill is completed.
+    pub paused_actions: u32,
+    /// The index of the next account to count.
+    pub next_account_index: u64,
+}
+
+impl Contract {
+    /// Pauses all actions of the asset if its collateral shares are being backfilled. The paused
+    /// actions of the given config are restored when the backfill is completed.
+    pub fn internal_apply_collateral_shares_backfill(
+        &mut self,
+        token_id: &TokenId,
+        asset_config: &mut AssetConfig,
+    ) {
+        if let Some(mut backfill) = self.collateral_shares_backfills.get(token_id) {
+            backfill.paused_actions = asset_config.paused_actions;
+            self.collateral_shares_backfills.insert(token_id, &backfill);
+            for action in PausableAction::all() {
+                asset_config.pause(action);
+            }
+        }
+    }
+
+    /// Starts the backfill of the asset and pauses all its actions until it's completed.
+    pub fn internal_start_collateral_shares_backfill(&mut self, token_id: &TokenId) {
+        let mut asset = self.internal_unwrap_asset(token_id);
+        let old_asset_config = asset.config.clone();
+        self.collateral_shares_backfills.insert(
+            token_id,
+            &CollateralSharesBackfill {
+                collateral_shares: 0.into(),
+                paused_actions: asset.config.paused_actions,
+                next_account_index: 0,
+            },
+        );
+        self.internal_apply_collateral_shares_backfill(token_id, &mut asset.config);
+        events::emit::asset_config_updated(token_id, &old_asset_config, &asset.config);
+        self.internal_set_asset(token_id, asset);
+    }
+
+    pub fn internal_assert_no_collateral_shares_backfill(&self, token_id: &TokenId) {
+        assert!(
+            self.collateral_shares_backfills.get(token_id).is_none(),
+            "The collateral shares backfill of the asset is in progress"
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Starts the recount of the total collateral shares of the asset. All actions of the asset
+    /// are paused until the collateral of all accounts is counted with
+    /// `backfill_collateral_shares`. The backfill of every asset is started by the migration from
+    /// the state before the total was tracked, this method recounts it again if needed.
+    /// - Panics if the asset doesn't exist.
+    /// - Panics if the backfill or the extra decimals migration of the asset is in progress.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn start_collateral_shares_backfill(&mut self, token_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_assert_no_collateral_shares_backfill(&token_id);
+        self.internal_assert_no_extra_decimals_migration(&token_id);
+        self.internal_start_collateral_shares_backfill(&token_id);
+    }
+
+    /// Counts the collateral shares of the asset of the next `limit` accounts. Once all accounts
+    /// are counted, the total collateral shares of the asset are replaced with the count and the
+    /// actions of the asset that were not paused before the backfill are resumed. Returns whether
+    /// the backfill is completed.
+    /// - Panics if the backfill of the asset is not in progress.
+    pub fn backfill_collateral_shares(&mut self, token_id: AccountId, limit: u64) -> bool {
+        let mut backfill = self
+            .collateral_shares_backfills
+            .get(&token_id)
+            .expect("The collateral shares backfill of the asset is not in progress");
+        let num_accounts = self.accounts.len();
+        let end_index = std::cmp::min(
+            backfill.next_account_index.saturating_add(limit),
+            num_accounts,
+        );
+        let account_ids: Vec<AccountId> = {
+            let keys = self.accounts.keys_as_vector();
+            (backfill.next_account_index..end_index)
+                .map(|index| keys.get(index).unwrap())
+                .collect()
+        };
+        for account_id in account_ids {
+            let account = self.internal_get_account(&account_id, true).unwrap();
+            if let Some(shares) = account.collateral.get(&token_id) {
+                backfill.collateral_shares.0 += shares.0;
+            }
+        }
+        backfill.next_account_index = end_index;
+        if end_index < num_accounts {
+            self.collateral_shares_backfills
+                .insert(&token_id, &backfill);
+            return false;
+        }
+
+        self.collateral_shares_backfills.remove(&token_id);
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        let old_asset_config = asset.config.clone();
+        asset.collateral_shares = backfill.collateral_shares;
+        asset.config.paused_actions = backfill.paused_actions;
+        self.internal_apply_delisting(&token_id, &mut asset.config);
+        self.internal_apply_deprecation(&token_id, &mut asset.config);
+        self.internal_apply_sunset(&mut asset.config);
+        events::emit::asset_config_updated(&token_id, &old_asset_config, &asset.config);
+        self.internal_set_asset(&token_id, asset);
+        true
+    }
+
+    /// Returns the collateral shares backfills in progress by token ID.
+    pub fn get_collateral_shares_backfills(&self) -> HashMap<TokenId, CollateralSharesBackfill> {
+        self.collateral_shares_backfills.iter().collect()
+    }
+}
@@ -2,8 +2,11 @@ use crate::*;
 
 pub const MIN_BOOSTER_MULTIPLIER: u32 = 10000;
 
+/// The version of the config layout, i.e. the index of the current variant of `VConfig`.
+pub const CONFIG_VERSION: u32 = 1;
+
 /// Contract config
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
 #[serde(crate = "near_sdk::serde")]
 pub struct Config {
     /// The account ID of the oracle contract
@@ -44,6 +47,235 @@ pub struct Config {
     /// Whether an account with bad debt can be liquidated using reserves.
     /// The account should have borrowed sum larger than the collateral sum.
     pub force_closing_enabled: bool,
+
+    /// The fee for flash loans as a ratio of the loan amount. E.g. 9 means 0.09% fee.
+    /// The fee is split between the reserve and the suppliers according to the asset reserve ratio.
+    pub flash_loan_fee: u32,
+
+    /// The account ID of the Ref Finance exchange contract that is used for leverage swaps.
+    /// Leverage is disabled if not set.
+    pub ref_exchange_id: Option<AccountId>,
+
+    /// The account IDs of the router contracts that can execute actions of multiple accounts in
+    /// one call. Every account has to approve the router as well.
+    pub router_ids: Vec<AccountId>,
+
+    /// The accounts of every role that the owner has delegated. Managed by the owner.
+    pub roles: HashMap<Role, Vec<AccountId>>,
+
+    /// The delay in seconds between proposing a change of the config or an asset config and
+    /// executing it. If not 0, the changes can only be made through the proposals.
+    pub proposal_timelock_sec: DurationSec,
+
+    /// The maximum total value of the borrowed assets in USD using the last recorded prices.
+    /// New borrows are rejected once the total borrowed value exceeds it. Not limited if not set.
+    pub max_total_borrowed_value: Option<U128>,
+
+    /// The delay in seconds between announcing a reserve withdrawal and executing it.
+    /// The reserve withdrawals don't have to be announced if it's 0.
+    pub reserve_withdrawal_delay_sec: DurationSec,
+
+    /// The maximum part of the reserve of an asset that can be withdrawn in one epoch.
+    /// E.g. 1000 means 10% of the reserve.
+    pub max_reserve_withdrawal_ratio_per_epoch: u32,
+
+    /// The accounts that can request and confirm the calls of the owner methods on behalf of the
+    /// contract. Requires the owner to be the contract account itself.
+    pub multisig_members: Vec<AccountId>,
+
+    /// The number of confirmations of the multisig members required to execute a request.
+    pub multisig_threshold: u32,
+
+    /// The owner methods that the multisig members can request, e.g. `update_asset`. The other
+    /// methods can't be called with the multisig, so `update_config` has to be listed for the
+    /// multisig to change the config.
+    pub multisig_methods: Vec<String>,
+
+    /// The account ID of the risk oracle that can update the risk parameters of the assets
+    /// within the bounds set by the owner. Disabled if not set.
+    pub risk_oracle_account_id: Option<AccountId>,
+
+    /// The maximum change of the volatility ratio of an asset within 24 hours, e.g. 500 means
+    /// the volatility ratio can move by at most 5% per day. Not limited if not set.
+    pub max_volatility_ratio_change_per_day: Option<u32>,
+
+    /// The minimum interval in seconds between the `asset_rates_updated` events of an asset.
+    /// The events are not emitted if not set.
+    pub asset_rates_event_interval_sec: Option<DurationSec>,
+
+    /// The minimum interval in seconds between the `prices_used` events of the executions without
+    /// liquidations. The executions with liquidations always emit the event. The events are not
+    /// emitted if not set.
+    pub prices_used_event_interval_sec: Option<DurationSec>,
+
+    /// Whether the heavy events are emitted, i.e. `account_health`, `protocol_summary` and
+    /// `transfer_failed`. They require extra computation, storage reads or calls, so the gas used
+    /// by them is measured in `get_heavy_events_stats`.
+    pub heavy_events_enabled: bool,
+
+    /// The number of bytes sponsored by the storage sponsorship pool to register a new account
+    /// with `sponsored_storage_register`. Should be no less than the minimum storage of 10000
+    /// bytes. The storage sponsorship is disabled if not set.
+    pub max_sponsored_storage_bytes: Option<StorageUsage>,
+
+    /// The maximum number of farms an account can participate in at the same time. Once the limit
+    /// is reached, the account doesn't join new farms until it exits one with `exit_farm`. Not
+    /// limited if not set.
+    pub max_num_farms_per_account: Option<u32>,
+
+    /// The account ID of the Aurora engine. It can deposit the tokens bridged from Aurora on behalf
+    /// of the accounts, and the accounts can withdraw to the Aurora addresses through it. The
+    /// Aurora bridge is disabled if not set.
+    pub aurora_engine_id: Option<AccountId>,
+
+    /// The tip in yoctoNEAR paid from the maintenance pool for every keeper call that did the
+    /// work, e.g. accrued an asset. The tips are not paid if not set.
+    pub keeper_tip: Option<U128>,
+
+    /// The account ID of the Croncat manager that schedules the keeper tasks registered with
+    /// `register_keeper_task`.
+    pub croncat_manager_id: Option<AccountId>,
+
+    /// The account ID of the Ref boost farm. It can deposit the farm rewards of the pool shares
+    /// held via the shadow records on behalf of the accounts. The claiming is disabled if not set.
+    pub ref_boost_farm_id: Option<AccountId>,
+
+    /// The staking of a part of the reserve of an asset, e.g. wNEAR, into a liquid staking token
+    /// with `stake_reserve`. Disabled if not set.
+    pub reserve_staking: Option<ReserveStakingConfig>,
+
+    /// The account IDs of the intent settlement contracts, e.g. `intents.near`. They can deposit
+    /// the settled tokens on behalf of the accounts with the follow-up actions.
+    pub intents_contract_ids: Vec<AccountId>,
+
+    /// The account ID of the Pyth contract, e.g. `pyth-oracle.near`. The actions can be executed
+    /// with the prices pushed to Pyth with `execute_with_pyth`. Disabled if not set.
+    pub pyth_oracle_id: Option<AccountId>,
+
+    /// Whether the activity metrics of the accounts are recorded for `get_account_activity`, e.g.
+    /// for the incentive programs of partners. The storage of the metrics is paid by the account.
+    pub activity_tracking_enabled: bool,
+
+    /// The rules that limit which risk tiers of the borrowed assets can be backed by the
+    /// collateral of each risk tier. The tiers without a rule are not restricted.
+    pub risk_tier_policy: Vec<RiskTierRule>,
+
+    /// The maximum number of actions in a bundle of one call, e.g. `execute` or the message of
+    /// `ft_transfer_call`. The larger bundles are rejected before any action is executed. Not
+    /// limited if not set.
+    pub max_num_actions: Option<u32>,
+
+    /// The minimum health factor (multiplied by 10000) that the borrows and the withdrawals of the
+    /// collateral have to leave, e.g. 10500 for 105%, so the new positions don't start right at
+    /// the liquidation line. Only the solvency is required if not set.
+    pub min_borrow_health_bps: Option<u32>,
+
+    /// The caps of the canary deployment of a new version, e.g. on the mainnet. The deposits are
+    /// not capped if not set.
+    pub canary: Option<CanaryConfig>,
+
+    /// The account ID of the wNEAR token, e.g. `wrap.near`. The native NEAR can be deposited with
+    /// `deposit_near`, which wraps it, and withdrawn with the `WithdrawNear` action, which unwraps
+    /// it. Disabled if not set.
+    pub wnear_token_id: Option<AccountId>,
+}
+
+/// The config of any known version. The older variants stay deserializable, so the config
+/// written by an older version of the contract can always be read.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum VConfig {
+    V0(ConfigV0),
+    Current(Box<Config>),
+}
+
+impl VConfig {
+    /// Returns the version of the config layout.
+    pub fn version(&self) -> u32 {
+        match self {
+            VConfig::V0(_) => 0,
+            VConfig::Current(_) => CONFIG_VERSION,
+        }
+    }
+}
+
+impl From<VConfig> for Config {
+    fn from(v: VConfig) -> Self {
+        match v {
+            VConfig::V0(v) => v.into(),
+            VConfig::Current(c) => *c,
+        }
+    }
+}
+
+impl From<Config> for VConfig {
+    fn from(c: Config) -> Self {
+        VConfig::Current(Box::new(c))
+    }
+}
+
+/// The operational state of an asset, i.e. which actions are currently enabled for the asset.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetOperationalState {
+    pub token_id: TokenId,
+    pub paused_actions: Vec<PausableAction>,
+    pub can_use_as_collateral: bool,
+    /// Whether the asset is reserve-only, i.e. the accounts can't deposit it.
+    pub reserve_only: bool,
+    /// Whether the asset is being delisted.
+    pub is_delisting: bool,
+    /// Whether the asset is deprecated, i.e. in the repay-only mode.
+    pub is_deprecated: bool,
+    /// Whether a flash loan of the asset is in progress, which blocks withdrawals and borrows.
+    pub flash_loan_in_progress: bool,
+}
+
+/// The operational state of the contract that integrators can use to react to the mode changes.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct OperationalState {
+    pub owner_id: AccountId,
+    pub oracle_account_id: AccountId,
+    pub force_closing_enabled: bool,
+    /// Whether the swaps through the Ref Finance exchange are enabled.
+    pub swaps_enabled: bool,
+    /// Whether the price feeds are paused by a guardian, so the actions that need the prices are
+    /// rejected.
+    pub price_feeds_paused: bool,
+    /// The guardians that can pause the assets and the price feeds.
+    pub guardians: Vec<AccountId>,
+    /// The IDs of the pending proposals, see `get_pending_proposals`.
+    pub pending_proposal_ids: Vec<u32>,
+    pub assets: Vec<AssetOperationalState>,
+}
+
+/// The liquidation parameters of an asset.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetLiquidationConfig {
+    pub token_id: TokenId,
+    /// The ratio that adjusts the collateral and the borrowed values for the health factor.
+    pub volatility_ratio: u32,
+    /// Whether the asset can be used as a collateral, i.e. taken by liquidators.
+    pub can_use_as_collateral: bool,
+    /// Whether the liquidations involving the asset are paused.
+    pub liquidation_paused: bool,
+    pub extra_decimals: u8,
+}
+
+/// The liquidation parameters for liquidators. The discount of the liquidation is not fixed and
+/// is computed from the health factor of the liquidated account as `(1 - health_factor) / 2`.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct LiquidationConfig {
+    /// Whether the accounts with the borrowed sum larger than the collateral sum can be closed
+    /// using reserves.
+    pub force_closing_enabled: bool,
+    pub assets: Vec<AssetLiquidationConfig>,
 }
 
 impl Config {
@@ -56,12 +288,55 @@ impl Config {
             self.x_booster_multiplier_at_maximum_staking_duration >= MIN_BOOSTER_MULTIPLIER,
             "xBooster multiplier should be no less than 100%"
         );
+        assert!(
+            self.flash_loan_fee <= MAX_RATIO,
+            "Flash loan fee should be no more than 100%"
+        );
+        assert!(
+            self.max_reserve_withdrawal_ratio_per_epoch <= MAX_RATIO,
+            "Reserve withdrawal ratio per epoch should be no more than 100%"
+        );
+        assert!(
+            self.multisig_threshold as usize <= self.multisig_members.len(),
+            "Multisig threshold should be no more than the number of members"
+        );
+        assert!(
+            self.max_volatility_ratio_change_per_day.unwrap_or(0) <= MAX_RATIO,
+            "Volatility ratio change per day should be no more than 100%"
+        );
+        assert!(
+            self.max_sponsored_storage_bytes
+                .unwrap_or(MIN_STORAGE_BYTES)
+                >= MIN_STORAGE_BYTES,
+            "Sponsored storage bytes should be no less than the minimum storage bytes"
+        );
+        for (i, rule) in self.risk_tier_policy.iter().enumerate() {
+            assert!(
+                self.risk_tier_policy[..i]
+                    .iter()
+                    .all(|other| other.collateral_tier != rule.collateral_tier),
+                "Duplicate risk tier policy rule"
+            );
+        }
+        assert_ne!(
+            self.max_num_actions,
+            Some(0),
+            "The maximum number of actions can't be 0"
+        );
+        assert!(
+            self.min_borrow_health_bps.unwrap_or(MAX_RATIO) >= MAX_RATIO,
+            "Minimum borrow health should be no less than 100%"
+        );
     }
 }
 
 impl Contract {
     pub fn internal_config(&self) -> Config {
-        self.config.get().unwrap()
+        self.config.get().unwrap().into()
+    }
+
+    pub(crate) fn internal_set_config(&mut self, config: Config) {
+        self.config.set(&config.into());
     }
 
     pub fn get_oracle_account_id(&self) -> AccountId {
@@ -75,6 +350,17 @@ impl Contract {
             "Not an owner"
         );
     }
+
+    /// Updates the asset config in place and applies it the same way as `update_asset`.
+    fn internal_modify_asset_config<F>(&mut self, token_id: &TokenId, f: F)
+    where
+        F: FnOnce(&mut AssetConfig),
+    {
+        self.assert_no_proposal_timelock();
+        let mut asset_config = self.internal_unwrap_asset(token_id).config;
+        f(&mut asset_config);
+        self.internal_update_asset(token_id, asset_config);
+    }
 }
 
 #[near_bindgen]
@@ -84,62 +370,229 @@ impl Contract {
         self.internal_config()
     }
 
+    /// Returns the operational state of the contract and every asset.
+    pub fn get_operational_state(&self) -> OperationalState {
+        let config = self.internal_config();
+        OperationalState {
+            owner_id: config.owner_id,
+            oracle_account_id: config.oracle_account_id,
+            force_closing_enabled: config.force_closing_enabled,
+            swaps_enabled: config.ref_exchange_id.is_some(),
+            price_feeds_paused: self.internal_is_price_feeds_paused(),
+            guardians: self.get_guardians(),
+            pending_proposal_ids: self.proposals.keys().collect(),
+            assets: self
+                .asset_ids
+                .iter()
+                .map(|token_id| {
+                    let asset = self.internal_unwrap_asset(&token_id);
+                    AssetOperationalState {
+                        flash_loan_in_progress: self.flash_loans.contains_key(&token_id),
+                        is_delisting: self.delisting_assets.contains(&token_id),
+                        is_deprecated: self.deprecated_assets.contains(&token_id),
+                        token_id,
+                        paused_actions: asset.config.get_paused_actions(),
+                        can_use_as_collateral: asset.config.can_use_as_collateral,
+                        reserve_only: asset.config.reserve_only,
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the liquidation parameters of the contract and every asset.
+    pub fn get_liquidation_config(&self) -> LiquidationConfig {
+        LiquidationConfig {
+            force_closing_enabled: self.internal_config().force_closing_enabled,
+            assets: self
+                .asset_ids
+                .iter()
+                .map(|token_id| {
+                    let asset = self.internal_unwrap_asset(&token_id);
+                    AssetLiquidationConfig {
+                        token_id,
+                        volatility_ratio: asset.config.volatility_ratio,
+                        can_use_as_collateral: asset.config.can_use_as_collateral,
+                        liquidation_paused: asset.config.is_paused(PausableAction::Liquidate),
+                        extra_decimals: asset.config.extra_decimals,
+                    }
+                })
+                .collect(),
+        }
+    }
+
     /// Updates the current config.
+    /// - Panics if the proposal timelock is not 0.
     /// - Requires one yoctoNEAR.
     /// - Requires to be called by the contract owner.
     #[payable]
     pub fn update_config(&mut self, config: Config) {
         assert_one_yocto();
         self.assert_owner();
-        config.assert_valid();
-        self.config.set(&config);
+        self.assert_no_proposal_timelock();
+        self.internal_update_config(config);
     }
 
-    /// Adds an asset with a given token_id and a given asset_config.
+    /// Adds an asset with a given token_id and a given asset_config. The asset is added after
+    /// the extra decimals are checked against the decimals from the token metadata, so the token
+    /// decimals plus the extra decimals are between 18 and 24.
     /// - Panics if the asset config is invalid.
     /// - Panics if an asset with the given token_id already exists.
+    /// - Panics if the token metadata can't be fetched or the extra decimals don't match.
     /// - Requires one yoctoNEAR.
     /// - Requires to be called by the contract owner.
     #[payable]
-    pub fn add_asset(&mut self, token_id: AccountId, asset_config: AssetConfig) {
+    pub fn add_asset(&mut self, token_id: AccountId, asset_config: AssetConfig) -> Promise {
         assert_one_yocto();
-        asset_config.assert_valid();
         self.assert_owner();
-        assert!(self.asset_ids.insert(&token_id));
-        self.internal_set_asset(&token_id, Asset::new(env::block_timestamp(), asset_config))
+        self.internal_add_asset(&token_id, asset_config)
     }
 
     /// Updates the asset config for the asset with the a given token_id.
     /// - Panics if the asset config is invalid.
     /// - Panics if an asset with the given token_id doesn't exist.
+    /// - Panics if the proposal timelock is not 0.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner.
+    /// - Requires to be called by the contract owner or a risk admin.
     #[payable]
     pub fn update_asset(&mut self, token_id: AccountId, asset_config: AssetConfig) {
         assert_one_yocto();
-        asset_config.assert_valid();
-        self.assert_owner();
+        self.assert_owner_or_role(Role::RiskAdmin);
+        self.assert_no_proposal_timelock();
+        self.internal_update_asset(&token_id, asset_config);
+    }
+
+    /// Updates the interest rate parameters of the asset with the given token_id. The parameters
+    /// that are not given are not changed.
+    /// - Panics if the resulting asset config is invalid.
+    /// - Panics if an asset with the given token_id doesn't exist.
+    /// - Panics if the proposal timelock is not 0.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or a risk admin.
+    #[payable]
+    pub fn update_asset_rates(
+        &mut self,
+        token_id: AccountId,
+        reserve_ratio: Option<u32>,
+        target_utilization: Option<u32>,
+        target_utilization_rate: Option<LowU128>,
+        max_utilization_rate: Option<LowU128>,
+        protocol_fee_ratio: Option<u32>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner_or_role(Role::RiskAdmin);
+        self.internal_modify_asset_config(&token_id, |asset_config| {
+            if let Some(reserve_ratio) = reserve_ratio {
+                asset_config.reserve_ratio = reserve_ratio;
+            }
+            if let Some(target_utilization) = target_utilization {
+                asset_config.target_utilization = target_utilization;
+            }
+            if let Some(target_utilization_rate) = target_utilization_rate {
+                asset_config.target_utilization_rate = target_utilization_rate;
+            }
+            if let Some(max_utilization_rate) = max_utilization_rate {
+                asset_config.max_utilization_rate = max_utilization_rate;
+            }
+            if let Some(protocol_fee_ratio) = protocol_fee_ratio {
+                asset_config.protocol_fee_ratio = protocol_fee_ratio;
+            }
+        });
+    }
+
+    /// Updates the risk limits of the asset with the given token_id. The parameters that are not
+    /// given are not changed. The caps can only be removed with `update_asset`.
+    /// - Panics if the resulting asset config is invalid.
+    /// - Panics if an asset with the given token_id doesn't exist.
+    /// - Panics if the proposal timelock is not 0.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or a risk admin.
+    #[payable]
+    pub fn update_asset_limits(
+        &mut self,
+        token_id: AccountId,
+        volatility_ratio: Option<u32>,
+        net_tvl_multiplier: Option<u32>,
+        supply_cap: Option<U128>,
+        borrow_cap: Option<U128>,
+        collateral_cap: Option<U128>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner_or_role(Role::RiskAdmin);
+        self.internal_modify_asset_config(&token_id, |asset_config| {
+            if let Some(volatility_ratio) = volatility_ratio {
+                asset_config.volatility_ratio = volatility_ratio;
+            }
+            if let Some(net_tvl_multiplier) = net_tvl_multiplier {
+                asset_config.net_tvl_multiplier = net_tvl_multiplier;
+            }
+            if supply_cap.is_some() {
+                asset_config.supply_cap = supply_cap;
+            }
+            if borrow_cap.is_some() {
+                asset_config.borrow_cap = borrow_cap;
+            }
+            if collateral_cap.is_some() {
+                asset_config.collateral_cap = collateral_cap;
+            }
+        });
+    }
+
+    /// Updates the flags of the asset with the given token_id. The paused actions are updated
+    /// with `update_asset_paused_actions` instead.
+    /// - Panics if an asset with the given token_id doesn't exist.
+    /// - Panics if the proposal timelock is not 0.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or a risk admin.
+    #[payable]
+    pub fn update_asset_flags(&mut self, token_id: AccountId, can_use_as_collateral: bool) {
+        assert_one_yocto();
+        self.assert_owner_or_role(Role::RiskAdmin);
+        self.internal_modify_asset_config(&token_id, |asset_config| {
+            asset_config.can_use_as_collateral = can_use_as_collateral;
+        });
+    }
+
+    /// Sets the paused actions of the asset with the given token_id without changing the rest of
+    /// the asset config. The actions that are not in the list are resumed, unless they are paused
+    /// for the delisting or deprecated asset or by the sunset.
+    /// - Panics if an asset with the given token_id doesn't exist.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or a risk admin.
+    #[payable]
+    pub fn update_asset_paused_actions(
+        &mut self,
+        token_id: AccountId,
+        paused_actions: Vec<PausableAction>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner_or_role(Role::RiskAdmin);
         let mut asset = self.internal_unwrap_asset(&token_id);
-        if asset.config.extra_decimals != asset_config.extra_decimals {
-            assert!(
-                asset.borrowed.balance == 0 && asset.supplied.balance == 0 && asset.reserved == 0,
-                "Can't change extra decimals if any of the balances are not 0"
-            );
+        let old_asset_config = asset.config.clone();
+        asset.config.paused_actions = 0;
+        for action in paused_actions {
+            asset.config.pause(action);
         }
-        asset.config = asset_config;
+        self.internal_apply_delisting(&token_id, &mut asset.config);
+        self.internal_apply_deprecation(&token_id, &mut asset.config);
+        self.internal_apply_sunset(&mut asset.config);
+        self.internal_apply_extra_decimals_migration(&token_id, &mut asset.config);
+        self.internal_apply_collateral_shares_backfill(&token_id, &mut asset.config);
+        events::emit::asset_config_updated(&token_id, &old_asset_config, &asset.config);
         self.internal_set_asset(&token_id, asset);
     }
 
     /// Adds an asset farm reward for the farm with a given farm_id. The reward is of token_id with
     /// the new reward per day amount and a new booster log base. The extra amount of reward is
     /// taken from the asset reserved balance.
+    /// - Panics if the contract is sunset.
     /// - The booster log base should include decimals of the token for better precision of the log
     ///    base. For example, if token decimals is `6` the log base of `10_500_000` will be `10.5`.
     /// - Panics if the farm asset token_id doesn't exists.
     /// - Panics if an asset with the given token_id doesn't exists.
     /// - Panics if an asset with the given token_id doesn't have enough reserved balance.
     /// - Requires one yoctoNEAR.
-    /// - Requires to be called by the contract owner.
+    /// - Requires to be called by the contract owner or a farm manager.
     #[payable]
     pub fn add_asset_farm_reward(
         &mut self,
@@ -150,7 +603,8 @@ impl Contract {
         reward_amount: U128,
     ) {
         assert_one_yocto();
-        self.assert_owner();
+        self.assert_owner_or_role(Role::FarmManager);
+        self.assert_not_sunset();
         match &farm_id {
             FarmId::Supplied(token_id) | FarmId::Borrowed(token_id) => {
                 assert!(self.assets.contains_key(token_id));
@@ -164,29 +618,63 @@ impl Contract {
                 && reward_asset.available_amount() >= reward_amount.0,
             "Not enough reserved reward balance"
         );
-        reward_asset.reserved -= reward_amount.0;
+        reward_asset.withdraw_reserved(reward_amount.0);
         self.internal_set_asset(&reward_token_id, reward_asset);
         let mut asset_farm = self
-            .internal_get_asset_farm(&farm_id, false)
+            .internal_get_asset_farm(&farm_id)
             .unwrap_or_else(|| AssetFarm {
                 block_timestamp: env::block_timestamp(),
                 rewards: HashMap::new(),
-                inactive_rewards: LookupMap::new(StorageKey::InactiveAssetFarmRewards {
-                    farm_id: farm_id.clone(),
-                }),
             });
 
         let mut asset_farm_reward = asset_farm
             .rewards
             .remove(&reward_token_id)
-            .or_else(|| asset_farm.internal_remove_inactive_asset_farm_reward(&reward_token_id))
+            .or_else(|| self.internal_remove_inactive_asset_farm_reward(&farm_id, &reward_token_id))
             .unwrap_or_default();
+        let old_asset_farm_reward = asset_farm_reward.clone();
         asset_farm_reward.reward_per_day = new_reward_per_day.into();
         asset_farm_reward.booster_log_base = new_booster_log_base.into();
         asset_farm_reward.remaining_rewards += reward_amount.0;
+        events::emit::asset_farm_reward_updated(
+            &farm_id,
+            &reward_token_id,
+            &old_asset_farm_reward,
+            &asset_farm_reward,
+        );
         asset_farm
             .rewards
             .insert(reward_token_id, asset_farm_reward);
         self.internal_set_asset_farm(&farm_id, asset_farm);
     }
+
+    /// Claims the given amount of the pending protocol fees of the asset from the reserve to the
+    /// supplied balance of the predecessor account. If the amount is not given, claims all pending
+    /// protocol fees. The claimed fees can be withdrawn as a regular supplied balance.
+    /// - Panics if the amount exceeds the pending protocol fees or the available amount.
+    /// - Requires the predecessor account to be registered.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or a fee collector.
+    #[payable]
+    pub fn claim_protocol_fees(&mut self, token_id: AccountId, amount: Option<U128>) {
+        assert_one_yocto();
+        self.assert_owner_or_role(Role::FeeCollector);
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        let pending_protocol_fees = asset.get_pending_protocol_fees();
+        let amount = amount.map(|a| a.0).unwrap_or(pending_protocol_fees);
+        assert!(
+            amount > 0 && amount <= pending_protocol_fees && amount <= asset.available_amount(),
+            "Not enough pending protocol fees"
+        );
+        asset.withdraw_reserved(amount);
+        self.internal_set_asset(&token_id, asset);
+
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        self.internal_deposit(&mut account, &token_id, amount);
+        events::emit::protocol_fees_claimed(&account_id, amount, &token_id);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+    }
 }
@@ -0,0 +1,82 @@
+use crate::*;
+use near_sdk::json_types::U128;
+
+impl Contract {
+    /// Returns the actions of the `DepositToCollateral` transfer message, i.e. adds the entire
+    /// deposit to the collateral.
+    pub fn internal_deposit_to_collateral_actions(
+        &self,
+        token_id: &TokenId,
+        amount: Balance,
+    ) -> Vec<Action> {
+        assert!(
+            self.internal_unwrap_asset(token_id)
+                .config
+                .can_use_as_collateral,
+            "The asset can't be used as collateral"
+        );
+        vec![Action::IncreaseCollateral(AssetAmount {
+            token_id: token_id.clone(),
+            amount: Some(amount.into()),
+            max_amount: None,
+        })]
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Repays the entire borrowed `repay_token_id` from the supplied balance and withdraws the
+    /// `amount` of `withdraw_token_id` (or the entire balance if not given) to the wallet of the
+    /// predecessor account. The collateral of the withdrawn asset is decreased first, up to the
+    /// amount. The arguments are plain, so the method can be called from a function call proposal
+    /// of a Sputnik DAO. The actions are executed without prices, so the collateral can only be
+    /// withdrawn once the account has no borrowed assets left.
+    /// - Panics if the assets are not listed, the account doesn't borrow `repay_token_id` or the
+    ///   amount is 0.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn repay_and_withdraw(
+        &mut self,
+        repay_token_id: TokenId,
+        withdraw_token_id: TokenId,
+        amount: Option<U128>,
+    ) {
+        assert_one_yocto();
+        self.internal_unwrap_asset(&repay_token_id);
+        self.internal_unwrap_asset(&withdraw_token_id);
+        assert!(
+            amount.map(|amount| amount.0 > 0).unwrap_or(true),
+            "The amount can't be 0"
+        );
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        assert!(
+            account.borrowed.contains_key(&repay_token_id),
+            "The account doesn't borrow the repay asset"
+        );
+        let mut actions = vec![Action::Repay(AssetAmount {
+            token_id: repay_token_id,
+            amount: None,
+            max_amount: None,
+        })];
+        if let Some(collateral_shares) = account.collateral.get(&withdraw_token_id) {
+            // The repayment only uses the supplied balance, so the collateral is not affected.
+            let collateral_amount = self
+                .internal_unwrap_asset(&withdraw_token_id)
+                .supplied
+                .shares_to_amount(*collateral_shares, Rounding::Down);
+            actions.push(Action::DecreaseCollateral(AssetAmount {
+                token_id: withdraw_token_id.clone(),
+                amount: amount.map(|amount| std::cmp::min(amount.0, collateral_amount).into()),
+                max_amount: None,
+            }));
+        }
+        actions.push(Action::Withdraw(AssetAmount {
+            token_id: withdraw_token_id,
+            amount,
+            max_amount: None,
+        }));
+        self.internal_execute_without_prices(&account_id, &mut account, actions);
+        self.internal_set_account(&account_id, account);
+    }
+}
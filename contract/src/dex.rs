@@ -0,0 +1,208 @@
+use crate::*;
+use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
+use near_sdk::json_types::U128;
+use near_sdk::{serde_json, PromiseResult};
+
+const GAS_FOR_DEX_SWAP: Gas = Gas(Gas::ONE_TERA.0 * 80);
+
+/// The time after which a swap in progress expires. The swap output arrives within a few blocks,
+/// so an expired swap is stuck, e.g. because the output couldn't be received.
+pub const SWAP_EXPIRY: Duration = 60 * 60 * 10u64.pow(9);
+
+/// A single swap action of the swap route.
+#[derive(Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct SwapAction {
+    /// The ID of the pool on the exchange.
+    pub pool_id: u64,
+    pub token_in: TokenId,
+    /// The amount of the input token in the token decimals. It's set by the contract for the
+    /// first action of the route and should be `None` for the following actions.
+    pub amount_in: Option<U128>,
+    pub token_out: TokenId,
+    /// The minimum amount of the output token in the token decimals.
+    pub min_amount_out: U128,
+}
+
+/// The message for the `ft_transfer_call` to the Ref Finance exchange.
+/// The output of the swap is transferred back with `ft_transfer_call` using `client_echo` message.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct RefSwapMsg {
+    actions: Vec<SwapAction>,
+    client_echo: Option<String>,
+}
+
+/// The kind of the exchange. It defines how the swap route is passed to the exchange.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum DexKind {
+    /// The Ref Finance exchange, or a fork with the same swap interface.
+    RefFinance,
+}
+
+impl DexKind {
+    /// Returns the message for the `ft_transfer_call` of the swap input to the exchange. The
+    /// exchange has to transfer the output back with `ft_transfer_call` using the `client_echo`
+    /// as the message.
+    fn swap_msg(&self, swap_route: Vec<SwapAction>, client_echo: String) -> String {
+        match self {
+            DexKind::RefFinance => serde_json::to_string(&RefSwapMsg {
+                actions: swap_route,
+                client_echo: Some(client_echo),
+            }),
+        }
+        .unwrap()
+    }
+}
+
+/// A whitelisted exchange that is used for the swaps.
+#[derive(Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Dex {
+    pub dex_id: AccountId,
+    pub kind: DexKind,
+}
+
+impl Dex {
+    /// Transfers the input of the prepared swap route to the exchange. The output of the swap is
+    /// transferred back with `ft_transfer_call` using the `client_echo` message.
+    pub fn swap(self, swap_route: Vec<SwapAction>, client_echo: String) -> Promise {
+        let token_in = swap_route[0].token_in.clone();
+        let ft_amount_in = swap_route[0].amount_in.expect("Missing swap input amount");
+        ext_fungible_token::ft_transfer_call(
+            self.dex_id,
+            ft_amount_in,
+            None,
+            self.kind.swap_msg(swap_route, client_echo),
+            token_in,
+            ONE_YOCTO,
+            GAS_FOR_DEX_SWAP,
+        )
+    }
+}
+
+/// Validates the swap route from `token_in` to `token_out` and sets the input amount of the first
+/// action. Returns the minimum amount out of the route in the token decimals.
+pub fn prepare_swap_route(
+    swap_route: &mut [SwapAction],
+    token_in: &TokenId,
+    token_out: &TokenId,
+    ft_amount_in: Balance,
+) -> Balance {
+    assert!(!swap_route.is_empty(), "Swap route can't be empty");
+    assert_eq!(
+        &swap_route[0].token_in, token_in,
+        "Swap route should start with the input token"
+    );
+    assert_eq!(
+        &swap_route.last().unwrap().token_out,
+        token_out,
+        "Swap route should end with the output token"
+    );
+    for i in 1..swap_route.len() {
+        assert_eq!(
+            swap_route[i - 1].token_out,
+            swap_route[i].token_in,
+            "Swap route is not continuous"
+        );
+        swap_route[i].amount_in = None;
+    }
+    swap_route[0].amount_in = Some(ft_amount_in.into());
+    let min_amount_out = swap_route.last().unwrap().min_amount_out.0;
+    assert!(
+        min_amount_out > 0,
+        "Minimum amount out of the swap route can't be 0"
+    );
+    min_amount_out
+}
+
+/// Returns the amount refunded by the receiver of the `ft_transfer_call` of the given amount, e.g.
+/// the swap input. Should be called from the callback of the transfer call.
+pub fn transfer_call_refunded_amount(amount: Balance, extra_decimals: u8) -> Balance {
+    let multiplier = 10u128.pow(extra_decimals as u32);
+    let ft_amount = amount / multiplier;
+    let used_ft_amount = match env::promise_result(0) {
+        PromiseResult::Successful(value) => serde_json::from_slice::<U128>(&value)
+            .map(|used_ft_amount| std::cmp::min(used_ft_amount.0, ft_amount))
+            .unwrap_or(ft_amount),
+        _ => 0,
+    };
+    if used_ft_amount == ft_amount {
+        return 0;
+    }
+    // The remainder below the token decimals wasn't transferred, so it's refunded as well.
+    amount - used_ft_amount * multiplier
+}
+
+impl Contract {
+    /// Returns the exchange for the swap. The `ref_exchange_id` from the config is used if the
+    /// exchange is not given. Returns `None` if the swaps are not enabled.
+    /// - Panics if the given exchange is not whitelisted.
+    pub fn internal_get_dex(&self, dex_id: Option<AccountId>) -> Option<Dex> {
+        let ref_exchange_id = self.internal_config().ref_exchange_id;
+        match dex_id {
+            Some(dex_id) if Some(&dex_id) != ref_exchange_id.as_ref() => {
+                let kind = self
+                    .dexes
+                    .get(&dex_id)
+                    .expect("The exchange is not whitelisted");
+                Some(Dex { dex_id, kind })
+            }
+            _ => ref_exchange_id.map(|dex_id| Dex {
+                dex_id,
+                kind: DexKind::RefFinance,
+            }),
+        }
+    }
+
+    /// Whether the account is a whitelisted exchange that can transfer the swap outputs.
+    pub fn internal_is_dex(&self, account_id: &AccountId) -> bool {
+        self.internal_config().ref_exchange_id.as_ref() == Some(account_id)
+            || self.dexes.get(account_id).is_some()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Whitelists the exchange of the given kind for the swaps. The actions with the swaps can
+    /// select the exchange with `dex_id`.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn add_dex(&mut self, dex_id: AccountId, kind: DexKind) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.dexes.insert(&dex_id, &kind);
+    }
+
+    /// Removes the exchange from the whitelist. The outputs of the swaps in progress on the
+    /// exchange can't be received after that.
+    /// - Panics if the exchange is not whitelisted.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn remove_dex(&mut self, dex_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.dexes
+            .remove(&dex_id)
+            .expect("The exchange is not whitelisted");
+    }
+
+    /// Returns the whitelisted exchanges, including the `ref_exchange_id` from the config.
+    pub fn get_dexes(&self) -> Vec<Dex> {
+        self.internal_config()
+            .ref_exchange_id
+            .map(|dex_id| Dex {
+                dex_id,
+                kind: DexKind::RefFinance,
+            })
+            .into_iter()
+            .chain(self.dexes.iter().map(|(dex_id, kind)| Dex { dex_id, kind }))
+            .collect()
+    }
+}
@@ -0,0 +1,103 @@
+use crate::*;
+
+/// The supplied and the borrowed shares of an account before the actions, to find the positions
+/// that were decreased by the actions.
+pub struct InitialPositions {
+    supplied: HashMap<TokenId, Shares>,
+    borrowed: HashMap<TokenId, Shares>,
+}
+
+impl InitialPositions {
+    pub fn new(account: &Account) -> Self {
+        Self {
+            supplied: account.supplied.clone(),
+            borrowed: account.borrowed.clone(),
+        }
+    }
+}
+
+/// Whether the position was decreased to the given shares by the actions.
+fn is_decreased(initial: &HashMap<TokenId, Shares>, token_id: &TokenId, shares: Shares) -> bool {
+    initial
+        .get(token_id)
+        .is_some_and(|initial_shares| shares.0 < initial_shares.0)
+}
+
+impl Contract {
+    /// Writes off the residual supplied and borrowed positions of the account that were decreased
+    /// by the actions below the dust threshold of the asset. The supplied dust goes to the
+    /// reserve, while the borrowed dust is covered by the reserve if it's enough.
+    /// The collateral is not written off, so the health of the account can't decrease.
+    pub fn internal_write_off_dust(&mut self, account: &mut Account, initial: &InitialPositions) {
+        let farm_ids: Vec<FarmId> = account.affected_farms.iter().cloned().collect();
+        for farm_id in farm_ids {
+            match farm_id {
+                FarmId::Supplied(token_id) => {
+                    let shares = match account.supplied.get(&token_id) {
+                        Some(shares) => *shares,
+                        None => continue,
+                    };
+                    if is_decreased(&initial.supplied, &token_id, shares) {
+                        self.internal_write_off_supplied_dust(account, &token_id, shares);
+                    }
+                }
+                FarmId::Borrowed(token_id) => {
+                    let shares = match account.borrowed.get(&token_id) {
+                        Some(shares) => *shares,
+                        None => continue,
+                    };
+                    if is_decreased(&initial.borrowed, &token_id, shares) {
+                        self.internal_write_off_borrowed_dust(account, &token_id, shares);
+                    }
+                }
+                FarmId::NetTvl => {}
+            }
+        }
+    }
+
+    fn internal_write_off_supplied_dust(
+        &mut self,
+        account: &mut Account,
+        token_id: &TokenId,
+        shares: Shares,
+    ) {
+        let mut asset = self.internal_unwrap_asset(token_id);
+        let dust_threshold = match asset.config.dust_threshold {
+            Some(dust_threshold) => dust_threshold.0,
+            None => return,
+        };
+        let amount = asset.supplied.shares_to_amount(shares, Rounding::Down);
+        if amount >= dust_threshold {
+            return;
+        }
+        asset.supplied.withdraw(shares, amount);
+        asset.reserved += amount;
+        self.internal_set_asset(token_id, asset);
+        account.internal_set_asset(token_id, AccountAsset::new());
+        events::emit::dust_written_off(&account.account_id, amount, shares, token_id, false);
+    }
+
+    fn internal_write_off_borrowed_dust(
+        &mut self,
+        account: &mut Account,
+        token_id: &TokenId,
+        shares: Shares,
+    ) {
+        let mut asset = self.internal_unwrap_asset(token_id);
+        let dust_threshold = match asset.config.dust_threshold {
+            Some(dust_threshold) => dust_threshold.0,
+            None => return,
+        };
+        let amount = asset.borrowed.shares_to_amount(shares, Rounding::Up);
+        if amount >= dust_threshold || amount > asset.reserved {
+            return;
+        }
+        asset.borrowed.withdraw(shares, amount);
+        asset.reserved -= amount;
+        self.internal_set_asset(token_id, asset);
+        let isolated_token_id = self.internal_get_isolated_collateral(account);
+        self.internal_decrease_isolated_debt(&isolated_token_id, amount);
+        account.decrease_borrowed(token_id, shares);
+        events::emit::dust_written_off(&account.account_id, amount, shares, token_id, true);
+    }
+}
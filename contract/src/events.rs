@@ -1,4 +1,94 @@
 use crate::*;
+use near_sdk::{serde_json, BlockHeight};
+
+/// The name of the event standard in the NEP-297 header of every event.
+pub const EVENT_STANDARD: &str = "burrow";
+
+/// The version of the event schema in the NEP-297 header of every event. The minor version is
+/// bumped when events or fields are added, so the indexers can ignore the unknown fields. The major
+/// version is bumped when fields are removed, renamed or change their meaning.
+/// - 1.1.0: added the `shares` to the account action events, the `withdraw` event and the
+///   assets of the `liquidate` event.
+/// - 1.2.0: added the `farm_reward_claimed` and `asset_farm_reward_exhausted` events.
+/// - 1.3.0: added the `asset_rates_updated` event.
+/// - 1.4.0: added the `transfer_failed` event.
+/// - 1.5.0: added the `account_health` event.
+/// - 1.6.0: added the `prices_used` event.
+/// - 1.7.0: added the `protocol_summary` event.
+/// - 1.8.0: added the `account_unregistered` event.
+/// - 1.9.0: added the `farm_exited` event.
+/// - 1.10.0: added the `lp_shares_unwound` and `lp_shares_unwind_failed` events.
+/// - 1.11.0: added the `asset_migrated` event.
+/// - 1.12.0: added the `deposit_refunded` event.
+/// - 1.13.0: added the `execute_continued` and `execute_continuation_cancelled` events.
+/// - 1.14.0: added the `dust_written_off` event.
+/// - 1.15.0: added the `collateral_price_missing` event.
+/// - 1.16.0: added the `sunset_announced`, `sunset_cancelled` and `sunset_executed` events.
+/// - 1.17.0: added the `repay_on_behalf` and `deposit_collateral_for` events.
+/// - 1.18.0: added the `leverage_swap_expired`, `withdraw_swap_expired`, `repay_swap_expired` and
+///   `flash_loan_shortfall` events.
+pub const EVENT_STANDARD_VERSION: &str = "1.18.0";
+
+/// The gas used by the emission of the heavy events, so the deployments can weigh the indexing
+/// richness against the gas.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Default, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct HeavyEventsStats {
+    /// The number of the runs of the heavy event emissions.
+    #[serde(with = "u64_dec_format")]
+    pub num_emissions: u64,
+    /// The total gas used by the emissions.
+    #[serde(with = "u64_dec_format")]
+    pub gas_used: u64,
+}
+
+impl Contract {
+    /// Runs the emission of the heavy events, i.e. the events that require extra computation,
+    /// storage reads or calls, if they are enabled in the config. The gas used by the emission is
+    /// added to the stats.
+    pub fn internal_emit_heavy_events<F: FnOnce(&mut Self)>(&mut self, emit: F) {
+        if !self.internal_config().heavy_events_enabled {
+            return;
+        }
+        let used_gas = env::used_gas();
+        emit(self);
+        self.heavy_events_stats.num_emissions += 1;
+        self.heavy_events_stats.gas_used += (env::used_gas() - used_gas).0;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the number of the emissions of the heavy events and the total gas used by them.
+    pub fn get_heavy_events_stats(&self) -> HeavyEventsStats {
+        self.heavy_events_stats.clone()
+    }
+}
+
+/// The NEP-297 event log. The data of every event is a list with a single item.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+struct EventLog<'a, T: Serialize> {
+    standard: &'static str,
+    version: &'static str,
+    event: &'a str,
+    data: [T; 1],
+}
+
+/// Logs the event with the NEP-297 header. It's the single path to emit the events.
+fn log_event<T: Serialize>(event: &str, data: T) {
+    let event = EventLog {
+        standard: EVENT_STANDARD,
+        version: EVENT_STANDARD_VERSION,
+        event,
+        data: [data],
+    };
+    log!(
+        "EVENT_JSON:{}",
+        serde_json::to_string(&event).expect("Failed to serialize the event")
+    );
+}
 
 pub mod emit {
     use super::*;
@@ -13,15 +103,23 @@ pub mod emit {
         pub token_id: &'a TokenId,
     }
 
-    fn log_event<T: Serialize>(event: &str, data: T) {
-        let event = json!({
-            "standard": "burrow",
-            "version": "1.0.0",
-            "event": event,
-            "data": [data]
-        });
+    #[derive(Serialize)]
+    #[serde(crate = "near_sdk::serde")]
+    struct AccountAmountSharesToken<'a> {
+        pub account_id: &'a AccountId,
+        #[serde(with = "u128_dec_format")]
+        pub amount: Balance,
+        pub shares: Shares,
+        pub token_id: &'a TokenId,
+    }
 
-        log!("EVENT_JSON:{}", event.to_string());
+    #[derive(Serialize)]
+    #[serde(crate = "near_sdk::serde")]
+    struct AmountSharesToken<'a> {
+        pub token_id: &'a TokenId,
+        #[serde(with = "u128_dec_format")]
+        pub amount: Balance,
+        pub shares: Shares,
     }
 
     pub fn deposit_to_reserve(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
@@ -35,9 +133,9 @@ pub mod emit {
         );
     }
 
-    pub fn deposit(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn deposit_refunded(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
         log_event(
-            "deposit",
+            "deposit_refunded",
             AccountAmountToken {
                 account_id: &account_id,
                 amount,
@@ -46,6 +144,30 @@ pub mod emit {
         );
     }
 
+    pub fn deposit(account_id: &AccountId, amount: Balance, shares: Shares, token_id: &TokenId) {
+        log_event(
+            "deposit",
+            AccountAmountSharesToken {
+                account_id: &account_id,
+                amount,
+                shares,
+                token_id: &token_id,
+            },
+        );
+    }
+
+    pub fn withdraw(account_id: &AccountId, amount: Balance, shares: Shares, token_id: &TokenId) {
+        log_event(
+            "withdraw",
+            AccountAmountSharesToken {
+                account_id: &account_id,
+                amount,
+                shares,
+                token_id: &token_id,
+            },
+        );
+    }
+
     pub fn withdraw_started(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
         log_event(
             "withdraw_started",
@@ -79,55 +201,126 @@ pub mod emit {
         );
     }
 
-    pub fn increase_collateral(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn repay_on_behalf(
+        sender_id: &AccountId,
+        account_id: &AccountId,
+        amount: Balance,
+        token_id: &TokenId,
+    ) {
+        log_event(
+            "repay_on_behalf",
+            json!({
+                "sender_id": sender_id,
+                "account_id": account_id,
+                "amount": U128(amount),
+                "token_id": token_id,
+            }),
+        );
+    }
+
+    pub fn deposit_collateral_for(
+        sender_id: &AccountId,
+        account_id: &AccountId,
+        amount: Balance,
+        token_id: &TokenId,
+    ) {
+        log_event(
+            "deposit_collateral_for",
+            json!({
+                "sender_id": sender_id,
+                "account_id": account_id,
+                "amount": U128(amount),
+                "token_id": token_id,
+            }),
+        );
+    }
+
+    pub fn account_unregistered(account_id: &AccountId, force: bool) {
+        log_event(
+            "account_unregistered",
+            json!({
+                "account_id": account_id,
+                "force": force,
+            }),
+        );
+    }
+
+    pub fn increase_collateral(
+        account_id: &AccountId,
+        amount: Balance,
+        shares: Shares,
+        token_id: &TokenId,
+    ) {
         log_event(
             "increase_collateral",
-            AccountAmountToken {
+            AccountAmountSharesToken {
                 account_id: &account_id,
                 amount,
+                shares,
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn decrease_collateral(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn decrease_collateral(
+        account_id: &AccountId,
+        amount: Balance,
+        shares: Shares,
+        token_id: &TokenId,
+    ) {
         log_event(
             "decrease_collateral",
-            AccountAmountToken {
+            AccountAmountSharesToken {
                 account_id: &account_id,
                 amount,
+                shares,
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn borrow(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn borrow(account_id: &AccountId, amount: Balance, shares: Shares, token_id: &TokenId) {
         log_event(
             "borrow",
-            AccountAmountToken {
+            AccountAmountSharesToken {
                 account_id: &account_id,
                 amount,
+                shares,
                 token_id: &token_id,
             },
         );
     }
 
-    pub fn repay(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+    pub fn repay(account_id: &AccountId, amount: Balance, shares: Shares, token_id: &TokenId) {
         log_event(
             "repay",
-            AccountAmountToken {
+            AccountAmountSharesToken {
                 account_id: &account_id,
                 amount,
+                shares,
                 token_id: &token_id,
             },
         );
     }
 
+    fn to_amounts(assets: &[(TokenId, Balance, Shares)]) -> Vec<AmountSharesToken<'_>> {
+        assets
+            .iter()
+            .map(|(token_id, amount, shares)| AmountSharesToken {
+                token_id,
+                amount: *amount,
+                shares: *shares,
+            })
+            .collect()
+    }
+
     pub fn liquidate(
         account_id: &AccountId,
         liquidation_account_id: &AccountId,
         collateral_sum: &BigDecimal,
         repaid_sum: &BigDecimal,
+        repaid_assets: &[(TokenId, Balance, Shares)],
+        collateral_assets: &[(TokenId, Balance, Shares)],
     ) {
         log_event(
             "liquidate",
@@ -136,6 +329,8 @@ pub mod emit {
                 "liquidation_account_id": liquidation_account_id,
                 "collateral_sum": collateral_sum,
                 "repaid_sum": repaid_sum,
+                "repaid_assets": to_amounts(repaid_assets),
+                "collateral_assets": to_amounts(collateral_assets),
             }),
         );
     }
@@ -155,6 +350,42 @@ pub mod emit {
         );
     }
 
+    pub fn prices_used(account_id: &AccountId, oracle_id: &AccountId, price_data: &PriceData) {
+        log_event(
+            "prices_used",
+            json!({
+                "account_id": account_id,
+                "oracle_id": oracle_id,
+                "price_data": price_data,
+            }),
+        );
+    }
+
+    pub fn protocol_summary(block_height: BlockHeight, asset_deltas: &[AssetDeltas]) {
+        log_event(
+            "protocol_summary",
+            json!({
+                "block_height": block_height,
+                "asset_deltas": asset_deltas,
+            }),
+        );
+    }
+
+    pub fn account_health(
+        account_id: &AccountId,
+        collateral_sum: &BigDecimal,
+        borrowed_sum: &BigDecimal,
+    ) {
+        log_event(
+            "account_health",
+            json!({
+                "account_id": account_id,
+                "collateral_sum": collateral_sum,
+                "borrowed_sum": borrowed_sum,
+            }),
+        );
+    }
+
     pub fn booster_stake(
         account_id: &AccountId,
         amount: Balance,
@@ -185,4 +416,532 @@ pub mod emit {
             }),
         );
     }
+
+    pub fn flash_loan_started(
+        account_id: &AccountId,
+        amount: Balance,
+        token_id: &TokenId,
+        fee: Balance,
+    ) {
+        log_event(
+            "flash_loan_started",
+            json!({
+                "account_id": account_id,
+                "amount": U128(amount),
+                "token_id": token_id,
+                "fee": U128(fee),
+            }),
+        );
+    }
+
+    pub fn flash_loan_resolved(
+        account_id: &AccountId,
+        amount: Balance,
+        token_id: &TokenId,
+        fee: Balance,
+        returned_amount: Balance,
+    ) {
+        log_event(
+            "flash_loan_resolved",
+            json!({
+                "account_id": account_id,
+                "amount": U128(amount),
+                "token_id": token_id,
+                "fee": U128(fee),
+                "returned_amount": U128(returned_amount),
+            }),
+        );
+    }
+
+    pub fn flash_loan_shortfall(
+        account_id: &AccountId,
+        amount: Balance,
+        shares: Shares,
+        token_id: &TokenId,
+    ) {
+        log_event(
+            "flash_loan_shortfall",
+            AccountAmountSharesToken {
+                account_id: &account_id,
+                amount,
+                shares,
+                token_id: &token_id,
+            },
+        );
+    }
+
+    pub fn leverage_swap_started(
+        account_id: &AccountId,
+        amount: Balance,
+        token_id: &TokenId,
+        min_collateral_amount: Balance,
+        collateral_token_id: &TokenId,
+    ) {
+        log_event(
+            "leverage_swap_started",
+            json!({
+                "account_id": account_id,
+                "amount": U128(amount),
+                "token_id": token_id,
+                "min_collateral_amount": U128(min_collateral_amount),
+                "collateral_token_id": collateral_token_id,
+            }),
+        );
+    }
+
+    pub fn auto_repay_order_executed(
+        account_id: &AccountId,
+        order_account_id: &AccountId,
+        order_id: u32,
+        amount: Balance,
+        token_id: &TokenId,
+        tip: Balance,
+    ) {
+        log_event(
+            "auto_repay_order_executed",
+            json!({
+                "account_id": account_id,
+                "order_account_id": order_account_id,
+                "order_id": order_id,
+                "amount": U128(amount),
+                "token_id": token_id,
+                "tip": U128(tip),
+            }),
+        );
+    }
+
+    pub fn leverage_swap_failed(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+        log_event(
+            "leverage_swap_failed",
+            AccountAmountToken {
+                account_id,
+                amount,
+                token_id,
+            },
+        );
+    }
+
+    pub fn leverage_swap_expired(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+        log_event(
+            "leverage_swap_expired",
+            AccountAmountToken {
+                account_id,
+                amount,
+                token_id,
+            },
+        );
+    }
+
+    pub fn withdraw_swap_started(
+        account_id: &AccountId,
+        amount: Balance,
+        token_id: &TokenId,
+        min_received: Balance,
+        output_token_id: &TokenId,
+    ) {
+        log_event(
+            "withdraw_swap_started",
+            json!({
+                "account_id": account_id,
+                "amount": U128(amount),
+                "token_id": token_id,
+                "min_received": U128(min_received),
+                "output_token_id": output_token_id,
+            }),
+        );
+    }
+
+    pub fn withdraw_swap_failed(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+        log_event(
+            "withdraw_swap_failed",
+            AccountAmountToken {
+                account_id,
+                amount,
+                token_id,
+            },
+        );
+    }
+
+    pub fn withdraw_swap_expired(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+        log_event(
+            "withdraw_swap_expired",
+            AccountAmountToken {
+                account_id,
+                amount,
+                token_id,
+            },
+        );
+    }
+
+    pub fn repay_swap_started(
+        account_id: &AccountId,
+        amount: Balance,
+        token_id: &TokenId,
+        min_amount_out: Balance,
+        borrowed_token_id: &TokenId,
+    ) {
+        log_event(
+            "repay_swap_started",
+            json!({
+                "account_id": account_id,
+                "amount": U128(amount),
+                "token_id": token_id,
+                "min_amount_out": U128(min_amount_out),
+                "borrowed_token_id": borrowed_token_id,
+            }),
+        );
+    }
+
+    pub fn repay_swap_failed(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+        log_event(
+            "repay_swap_failed",
+            AccountAmountToken {
+                account_id,
+                amount,
+                token_id,
+            },
+        );
+    }
+
+    pub fn repay_swap_expired(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+        log_event(
+            "repay_swap_expired",
+            AccountAmountToken {
+                account_id,
+                amount,
+                token_id,
+            },
+        );
+    }
+
+    pub fn reserve_withdrawal_announced(
+        account_id: &AccountId,
+        amount: Balance,
+        token_id: &TokenId,
+    ) {
+        log_event(
+            "reserve_withdrawal_announced",
+            AccountAmountToken {
+                account_id,
+                amount,
+                token_id,
+            },
+        );
+    }
+
+    pub fn reserve_withdrawal_started(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+        log_event(
+            "reserve_withdrawal_started",
+            AccountAmountToken {
+                account_id,
+                amount,
+                token_id,
+            },
+        );
+    }
+
+    pub fn reserve_withdrawal_failed(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+        log_event(
+            "reserve_withdrawal_failed",
+            AccountAmountToken {
+                account_id,
+                amount,
+                token_id,
+            },
+        );
+    }
+
+    pub fn transfer_failed(
+        account_id: &AccountId,
+        amount: Balance,
+        token_id: &TokenId,
+        reason: &TransferFailureReason,
+        recovery: &TransferRecovery,
+    ) {
+        log_event(
+            "transfer_failed",
+            json!({
+                "account_id": account_id,
+                "amount": U128(amount),
+                "token_id": token_id,
+                "reason": reason,
+                "recovery": recovery,
+            }),
+        );
+    }
+
+    pub fn config_updated(old_config: &Config, new_config: &Config) {
+        log_event(
+            "config_updated",
+            json!({
+                "old_config": old_config,
+                "new_config": new_config,
+            }),
+        );
+    }
+
+    pub fn role_granted(role: Role, account_id: &AccountId) {
+        log_event(
+            "role_granted",
+            json!({
+                "role": role,
+                "account_id": account_id,
+            }),
+        );
+    }
+
+    pub fn role_revoked(role: Role, account_id: &AccountId) {
+        log_event(
+            "role_revoked",
+            json!({
+                "role": role,
+                "account_id": account_id,
+            }),
+        );
+    }
+
+    pub fn asset_added(token_id: &TokenId, asset_config: &AssetConfig) {
+        log_event(
+            "asset_added",
+            json!({
+                "token_id": token_id,
+                "asset_config": asset_config,
+            }),
+        );
+    }
+
+    pub fn asset_config_updated(
+        token_id: &TokenId,
+        old_asset_config: &AssetConfig,
+        new_asset_config: &AssetConfig,
+    ) {
+        log_event(
+            "asset_config_updated",
+            json!({
+                "token_id": token_id,
+                "old_asset_config": old_asset_config,
+                "new_asset_config": new_asset_config,
+            }),
+        );
+    }
+
+    pub fn sunset_announced(effective_timestamp: Timestamp) {
+        log_event(
+            "sunset_announced",
+            json!({
+                "effective_timestamp": U64(effective_timestamp),
+            }),
+        );
+    }
+
+    pub fn sunset_cancelled() {
+        log_event("sunset_cancelled", json!({}));
+    }
+
+    pub fn sunset_executed() {
+        log_event("sunset_executed", json!({}));
+    }
+
+    pub fn price_feeds_paused() {
+        log_event("price_feeds_paused", json!({}));
+    }
+
+    pub fn price_feeds_resumed() {
+        log_event("price_feeds_resumed", json!({}));
+    }
+
+    pub fn asset_removed(token_id: &TokenId) {
+        log_event(
+            "asset_removed",
+            json!({
+                "token_id": token_id,
+            }),
+        );
+    }
+
+    pub fn asset_farm_reward_updated(
+        farm_id: &FarmId,
+        reward_token_id: &TokenId,
+        old_reward: &AssetFarmReward,
+        new_reward: &AssetFarmReward,
+    ) {
+        log_event(
+            "asset_farm_reward_updated",
+            json!({
+                "farm_id": farm_id,
+                "reward_token_id": reward_token_id,
+                "old_reward": old_reward,
+                "new_reward": new_reward,
+            }),
+        );
+    }
+
+    pub fn farm_reward_claimed(
+        account_id: &AccountId,
+        farm_id: &FarmId,
+        amount: Balance,
+        token_id: &TokenId,
+    ) {
+        log_event(
+            "farm_reward_claimed",
+            json!({
+                "account_id": account_id,
+                "farm_id": farm_id,
+                "amount": U128(amount),
+                "token_id": token_id,
+            }),
+        );
+    }
+
+    pub fn farm_exited(account_id: &AccountId, farm_id: &FarmId) {
+        log_event(
+            "farm_exited",
+            json!({
+                "account_id": account_id,
+                "farm_id": farm_id,
+            }),
+        );
+    }
+
+    pub fn execute_continued(account_id: &AccountId, num_remaining_actions: u32) {
+        log_event(
+            "execute_continued",
+            json!({
+                "account_id": account_id,
+                "num_remaining_actions": num_remaining_actions,
+            }),
+        );
+    }
+
+    pub fn execute_continuation_cancelled(account_id: &AccountId, num_remaining_actions: u32) {
+        log_event(
+            "execute_continuation_cancelled",
+            json!({
+                "account_id": account_id,
+                "num_remaining_actions": num_remaining_actions,
+            }),
+        );
+    }
+
+    pub fn lp_shares_unwound(
+        liquidator_account_id: &AccountId,
+        liquidation_account_id: &AccountId,
+        amount: Balance,
+        token_id: &TokenId,
+    ) {
+        log_event(
+            "lp_shares_unwound",
+            json!({
+                "liquidator_account_id": liquidator_account_id,
+                "liquidation_account_id": liquidation_account_id,
+                "amount": U128(amount),
+                "token_id": token_id,
+            }),
+        );
+    }
+
+    pub fn lp_shares_unwind_failed(
+        liquidator_account_id: &AccountId,
+        liquidation_account_id: &AccountId,
+        amount: Balance,
+        token_id: &TokenId,
+    ) {
+        log_event(
+            "lp_shares_unwind_failed",
+            json!({
+                "liquidator_account_id": liquidator_account_id,
+                "liquidation_account_id": liquidation_account_id,
+                "amount": U128(amount),
+                "token_id": token_id,
+            }),
+        );
+    }
+
+    pub fn dust_written_off(
+        account_id: &AccountId,
+        amount: Balance,
+        shares: Shares,
+        token_id: &TokenId,
+        is_borrowed: bool,
+    ) {
+        log_event(
+            "dust_written_off",
+            json!({
+                "account_id": account_id,
+                "amount": U128(amount),
+                "shares": shares,
+                "token_id": token_id,
+                "is_borrowed": is_borrowed,
+            }),
+        );
+    }
+
+    pub fn collateral_price_missing(account_id: &AccountId, token_id: &TokenId) {
+        log_event(
+            "collateral_price_missing",
+            json!({
+                "account_id": account_id,
+                "token_id": token_id,
+            }),
+        );
+    }
+
+    pub fn asset_migrated(
+        account_id: &AccountId,
+        token_id: &TokenId,
+        new_token_id: &TokenId,
+        supplied_amount: Balance,
+        collateral_amount: Balance,
+        borrowed_amount: Balance,
+    ) {
+        log_event(
+            "asset_migrated",
+            json!({
+                "account_id": account_id,
+                "token_id": token_id,
+                "new_token_id": new_token_id,
+                "supplied_amount": U128(supplied_amount),
+                "collateral_amount": U128(collateral_amount),
+                "borrowed_amount": U128(borrowed_amount),
+            }),
+        );
+    }
+
+    pub fn asset_farm_reward_exhausted(farm_id: &FarmId, reward_token_id: &TokenId) {
+        log_event(
+            "asset_farm_reward_exhausted",
+            json!({
+                "farm_id": farm_id,
+                "reward_token_id": reward_token_id,
+            }),
+        );
+    }
+
+    pub fn asset_rates_updated(
+        token_id: &TokenId,
+        borrow_apr: &BigDecimal,
+        supply_apr: &BigDecimal,
+        utilization: &BigDecimal,
+    ) {
+        log_event(
+            "asset_rates_updated",
+            json!({
+                "token_id": token_id,
+                "borrow_apr": borrow_apr,
+                "supply_apr": supply_apr,
+                "utilization": utilization,
+            }),
+        );
+    }
+
+    pub fn protocol_fees_claimed(account_id: &AccountId, amount: Balance, token_id: &TokenId) {
+        log_event(
+            "protocol_fees_claimed",
+            AccountAmountToken {
+                account_id,
+                amount,
+                token_id,
+            },
+        );
+    }
 }
@@ -0,0 +1,175 @@
+use crate::*;
+use near_sdk::json_types::U64;
+
+/// The gas that has to remain to execute the next action of a bundle that can be continued. Once
+/// less gas remains, the remaining actions are executed by a continuation.
+const GAS_RESERVE_FOR_EXECUTE_CONTINUATION: Gas = Gas(Gas::ONE_TERA.0 * 100);
+/// The gas kept to complete the current call after the continuation is scheduled.
+const GAS_TO_SCHEDULE_EXECUTE_CONTINUATION: Gas = Gas(Gas::ONE_TERA.0 * 10);
+
+/// The continuation of an execute bundle that is in progress for an account.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ExecuteContinuation {
+    /// When the continuation was scheduled in nanoseconds. Identifies the continuation callback.
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+    /// The number of actions that are left to execute.
+    pub num_remaining_actions: u32,
+}
+
+/// The remaining actions of the execute bundle that are passed to the continuation callback.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RemainingBundle {
+    pub actions: Vec<Action>,
+    pub prices: Prices,
+    pub constraints: Option<ExecuteConstraints>,
+}
+
+/// Whether there is enough gas left to execute the next action of the bundle that can be
+/// continued.
+pub(crate) fn has_gas_for_next_action() -> bool {
+    env::used_gas() + GAS_RESERVE_FOR_EXECUTE_CONTINUATION <= env::prepaid_gas()
+}
+
+impl Contract {
+    pub fn internal_assert_no_execute_continuation(&self, account_id: &AccountId) {
+        assert!(
+            !self.execute_continuations.contains_key(account_id),
+            "The execute continuation of the account is in progress"
+        );
+    }
+
+    /// Executes the actions of the bundle while there is enough gas left, and saves the account.
+    /// The actions that don't fit into the call are executed by a continuation callback with the
+    /// same prices, while the account is locked. The executed part of the bundle has to leave the
+    /// account healthy. The constraints are verified once all actions are executed.
+    pub fn internal_execute_with_continuation(
+        &mut self,
+        account_id: &AccountId,
+        mut account: Account,
+        actions: Vec<Action>,
+        prices: Prices,
+        constraints: Option<ExecuteConstraints>,
+    ) {
+        let remaining_actions =
+            self.internal_execute_chunk(account_id, &mut account, actions, &prices, true);
+        if remaining_actions.is_empty() {
+            if let Some(constraints) = constraints {
+                self.internal_assert_constraints(&account, &prices, &constraints);
+            }
+            self.internal_set_account(account_id, account);
+            return;
+        }
+
+        let continuation = ExecuteContinuation {
+            timestamp: env::block_timestamp(),
+            num_remaining_actions: remaining_actions.len() as u32,
+        };
+        events::emit::execute_continued(account_id, continuation.num_remaining_actions);
+        self.internal_track_account_storage(account_id, |contract| {
+            contract
+                .execute_continuations
+                .insert(account_id, &continuation)
+        });
+        self.internal_set_account(account_id, account);
+
+        let gas = env::prepaid_gas() - env::used_gas() - GAS_TO_SCHEDULE_EXECUTE_CONTINUATION;
+        ext_execute_continuation::execute_continuation(
+            account_id.clone(),
+            continuation.timestamp.into(),
+            RemainingBundle {
+                actions: remaining_actions,
+                prices,
+                constraints,
+            },
+            env::current_account_id(),
+            NO_DEPOSIT,
+            gas,
+        );
+    }
+
+    /// Removes the continuation of the account and releases the storage to the account.
+    fn internal_remove_execute_continuation(
+        &mut self,
+        account_id: &AccountId,
+    ) -> Option<ExecuteContinuation> {
+        self.internal_track_account_storage(account_id, |contract| {
+            contract.execute_continuations.remove(account_id)
+        })
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Cancels the continuation of the execute bundle of the predecessor account, e.g. if the
+    /// continuation callback has failed. The actions of the bundle that were already executed are
+    /// kept, while the remaining actions are dropped.
+    /// - Panics if the continuation of the account is not in progress.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn cancel_execute_continuation(&mut self) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let continuation = self
+            .internal_remove_execute_continuation(&account_id)
+            .expect("The execute continuation of the account is not in progress");
+        events::emit::execute_continuation_cancelled(
+            &account_id,
+            continuation.num_remaining_actions,
+        );
+    }
+
+    /// Returns the continuation of the execute bundle in progress for the given account, if any.
+    pub fn get_execute_continuation(&self, account_id: AccountId) -> Option<ExecuteContinuation> {
+        self.execute_continuations.get(&account_id)
+    }
+}
+
+#[ext_contract(ext_execute_continuation)]
+trait ExtExecuteContinuation {
+    fn execute_continuation(
+        &mut self,
+        account_id: AccountId,
+        timestamp: U64,
+        bundle: RemainingBundle,
+    );
+}
+
+trait ExtExecuteContinuation {
+    fn execute_continuation(
+        &mut self,
+        account_id: AccountId,
+        timestamp: U64,
+        bundle: RemainingBundle,
+    );
+}
+
+#[near_bindgen]
+impl ExtExecuteContinuation for Contract {
+    /// Executes the remaining actions of the bundle of the account. Skipped if the continuation
+    /// was cancelled.
+    #[private]
+    fn execute_continuation(
+        &mut self,
+        account_id: AccountId,
+        timestamp: U64,
+        bundle: RemainingBundle,
+    ) {
+        match self.execute_continuations.get(&account_id) {
+            Some(continuation) if continuation.timestamp == timestamp.0 => {}
+            _ => return,
+        }
+        self.internal_remove_execute_continuation(&account_id);
+        let account = self.internal_unwrap_account(&account_id);
+        self.internal_execute_with_continuation(
+            &account_id,
+            account,
+            bundle.actions,
+            bundle.prices,
+            bundle.constraints,
+        );
+    }
+}
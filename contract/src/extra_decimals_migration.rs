@@ -0,0 +1,210 @@
+use crate::*;
+
+/// The migration of the asset to new extra decimals, e.g. when the token redenominates. The pool
+/// balances of the asset are rescaled when the migration starts, while the shares are kept, so
+/// the positions of the accounts stay exact. The amounts recorded per account, e.g. the withdraw
+/// claims and the timelocks, are rescaled by the paginated `migrate_extra_decimals` calls.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ExtraDecimalsMigration {
+    pub old_extra_decimals: u8,
+    pub extra_decimals: u8,
+    /// The paused actions of the asset that are restored when the migration is completed.
+    pub paused_actions: u32,
+    /// When the migration was started in nanoseconds.
+    #[serde(with = "u64_dec_format")]
+    pub start_timestamp: Timestamp,
+    /// The index of the next account to migrate.
+    pub next_account_index: u64,
+}
+
+impl ExtraDecimalsMigration {
+    /// Rescales the inner amount from the old extra decimals to the new extra decimals.
+    pub fn rescale(&self, amount: Balance) -> Balance {
+        if self.extra_decimals >= self.old_extra_decimals {
+            amount * 10u128.pow((self.extra_decimals - self.old_extra_decimals) as u32)
+        } else {
+            amount / 10u128.pow((self.old_extra_decimals - self.extra_decimals) as u32)
+        }
+    }
+}
+
+impl Contract {
+    /// Pauses all actions of the asset if the asset is migrating to new extra decimals. The
+    /// paused actions of the given config are restored when the migration is completed.
+    pub fn internal_apply_extra_decimals_migration(
+        &mut self,
+        token_id: &TokenId,
+        asset_config: &mut AssetConfig,
+    ) {
+        if let Some(mut migration) = self.extra_decimals_migrations.get(token_id) {
+            migration.paused_actions = asset_config.paused_actions;
+            self.extra_decimals_migrations.insert(token_id, &migration);
+            for action in PausableAction::all() {
+                asset_config.pause(action);
+            }
+        }
+    }
+
+    pub fn internal_assert_no_extra_decimals_migration(&self, token_id: &TokenId) {
+        assert!(
+            self.extra_decimals_migrations.get(token_id).is_none(),
+            "The extra decimals migration of the asset is in progress"
+        );
+    }
+
+    /// Rescales the amounts of the asset recorded for the account.
+    fn internal_migrate_account_extra_decimals(
+        &mut self,
+        account_id: &AccountId,
+        token_id: &TokenId,
+        migration: &ExtraDecimalsMigration,
+    ) {
+        if let Some(mut claims) = self.withdraw_claims.get(account_id) {
+            for claim in claims
+                .iter_mut()
+                .filter(|claim| &claim.token_id == token_id)
+            {
+                claim.amount = migration.rescale(claim.amount);
+            }
+            self.withdraw_claims.insert(account_id, &claims);
+        }
+        if let Some(mut timelocks) = self.withdraw_timelocks.get(account_id) {
+            for timelock in timelocks
+                .iter_mut()
+                .filter(|timelock| &timelock.token_id == token_id)
+            {
+                timelock.threshold = migration.rescale(timelock.threshold);
+                timelock.window_withdrawn = migration.rescale(timelock.window_withdrawn);
+                if let Some(announced_withdraw) = timelock.announced_withdraw.as_mut() {
+                    announced_withdraw.amount = migration.rescale(announced_withdraw.amount);
+                }
+            }
+            self.withdraw_timelocks.insert(account_id, &timelocks);
+        }
+        // The principals of the positions are recorded again from the current balances. The
+        // accounts stored by an older version don't have the principals yet.
+        if let Some(v_account @ VAccount::Current(_) | v_account @ VAccount::Sharded(_)) =
+            self.accounts.get(account_id)
+        {
+            let mut account = self.internal_vaccount_into_account(account_id, v_account, false);
+            if account.principals.reset(token_id) {
+                self.internal_save_account_record(account);
+            }
+        }
+        if let Some(mut activity) = self.account_activities.get(account_id) {
+            if let Some(volume) = activity.supplied_volume.get_mut(token_id) {
+                *volume = migration.rescale(*volume);
+                self.account_activities.insert(account_id, &activity);
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Starts the migration of the asset to the new extra decimals. The pool balances, the reserve,
+    /// the caps and the fee totals of the asset are rescaled immediately, while the shares are
+    /// kept. All actions of the asset are paused until the amounts recorded per account are
+    /// rescaled with `migrate_extra_decimals`.
+    /// - Panics if the asset doesn't exist or the extra decimals are not changed.
+    /// - Panics if the new extra decimals don't match the cached token metadata.
+    /// - Panics if the migration, the collateral shares backfill or a flash loan of the asset is
+    ///   in progress.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn start_extra_decimals_migration(&mut self, token_id: AccountId, extra_decimals: u8) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_assert_no_extra_decimals_migration(&token_id);
+        self.internal_assert_no_collateral_shares_backfill(&token_id);
+        self.internal_assert_no_flash_loan(&token_id);
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        assert_ne!(
+            asset.config.extra_decimals, extra_decimals,
+            "The extra decimals are not changed"
+        );
+        if let Some(metadata) = self.token_metadata.get(&token_id) {
+            assert_extra_decimals(metadata.decimals, extra_decimals);
+        }
+        let old_asset_config = asset.config.clone();
+        let migration = ExtraDecimalsMigration {
+            old_extra_decimals: asset.config.extra_decimals,
+            extra_decimals,
+            paused_actions: asset.config.paused_actions,
+            start_timestamp: env::block_timestamp(),
+            next_account_index: 0,
+        };
+        asset.supplied.balance = migration.rescale(asset.supplied.balance);
+        asset.borrowed.balance = migration.rescale(asset.borrowed.balance);
+        asset.reserved = migration.rescale(asset.reserved);
+        asset.collected_protocol_fees = migration.rescale(asset.collected_protocol_fees);
+        asset.withdrawn_protocol_fees = migration.rescale(asset.withdrawn_protocol_fees);
+        asset.flash_loan_volume = migration.rescale(asset.flash_loan_volume);
+        asset.flash_loan_fees = migration.rescale(asset.flash_loan_fees);
+        for cap in [
+            &mut asset.config.supply_cap,
+            &mut asset.config.borrow_cap,
+            &mut asset.config.collateral_cap,
+            &mut asset.config.min_borrow_amount,
+        ] {
+            if let Some(amount) = cap.as_mut() {
+                amount.0 = migration.rescale(amount.0);
+            }
+        }
+        asset.config.extra_decimals = extra_decimals;
+        self.extra_decimals_migrations.insert(&token_id, &migration);
+        self.internal_apply_extra_decimals_migration(&token_id, &mut asset.config);
+        events::emit::asset_config_updated(&token_id, &old_asset_config, &asset.config);
+        self.internal_set_asset(&token_id, asset);
+    }
+
+    /// Rescales the amounts of the migrating asset recorded for the next `limit` accounts.
+    /// Once all accounts are migrated, the migration is completed and the actions of the asset
+    /// that were not paused before the migration are resumed. Returns whether the migration is
+    /// completed.
+    /// - Panics if the migration of the asset is not in progress.
+    pub fn migrate_extra_decimals(&mut self, token_id: AccountId, limit: u64) -> bool {
+        let mut migration = self
+            .extra_decimals_migrations
+            .get(&token_id)
+            .expect("The extra decimals migration of the asset is not in progress");
+        let num_accounts = self.accounts.len();
+        let end_index = std::cmp::min(
+            migration.next_account_index.saturating_add(limit),
+            num_accounts,
+        );
+        let account_ids: Vec<AccountId> = {
+            let keys = self.accounts.keys_as_vector();
+            (migration.next_account_index..end_index)
+                .map(|index| keys.get(index).unwrap())
+                .collect()
+        };
+        for account_id in account_ids {
+            self.internal_migrate_account_extra_decimals(&account_id, &token_id, &migration);
+        }
+        migration.next_account_index = end_index;
+        if end_index < num_accounts {
+            self.extra_decimals_migrations.insert(&token_id, &migration);
+            return false;
+        }
+
+        self.extra_decimals_migrations.remove(&token_id);
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        let old_asset_config = asset.config.clone();
+        asset.config.paused_actions = migration.paused_actions;
+        self.internal_apply_delisting(&token_id, &mut asset.config);
+        self.internal_apply_deprecation(&token_id, &mut asset.config);
+        self.internal_apply_sunset(&mut asset.config);
+        events::emit::asset_config_updated(&token_id, &old_asset_config, &asset.config);
+        self.internal_set_asset(&token_id, asset);
+        true
+    }
+
+    /// Returns the extra decimals migrations in progress by token ID.
+    pub fn get_extra_decimals_migrations(&self) -> HashMap<TokenId, ExtraDecimalsMigration> {
+        self.extra_decimals_migrations.iter().collect()
+    }
+}
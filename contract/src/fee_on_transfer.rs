@@ -0,0 +1,116 @@
+use crate::*;
+use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
+use near_sdk::json_types::U128;
+use near_sdk::{serde_json, PromiseResult};
+
+const GAS_FOR_FT_BALANCE_OF: Gas = Gas(Gas::ONE_TERA.0 * 5);
+const GAS_FOR_AFTER_FEE_ON_TRANSFER_BALANCE_OF: Gas = Gas(Gas::ONE_TERA.0 * 30);
+
+impl Asset {
+    /// Returns the balance of the token that the contract should hold for the asset in the token
+    /// decimals, i.e. the supplied and the reserved balance without the borrowed balance.
+    fn accounted_ft_balance(&self) -> Balance {
+        (self.supplied.balance + self.reserved).saturating_sub(self.borrowed.balance)
+            / 10u128.pow(self.config.extra_decimals as u32)
+    }
+}
+
+impl Contract {
+    /// Requests the balance of the contract to measure the received amount of the deposit of the
+    /// fee-on-transfer asset. The received amount is deposited in the callback.
+    /// - Panics if the message is not empty, the account is not registered or not allowed to
+    ///   deposit the asset.
+    pub fn internal_fee_on_transfer_deposit(
+        &self,
+        sender_id: AccountId,
+        token_id: TokenId,
+        ft_amount: Balance,
+        msg: &str,
+    ) -> Promise {
+        assert!(
+            msg.is_empty(),
+            "Only plain deposits are supported for the fee-on-transfer asset"
+        );
+        self.internal_assert_allowlisted(&token_id, &sender_id);
+        self.internal_unwrap_account(&sender_id);
+        ext_fungible_token::ft_balance_of(
+            env::current_account_id(),
+            token_id.clone(),
+            NO_DEPOSIT,
+            GAS_FOR_FT_BALANCE_OF,
+        )
+        .then(ext_fee_on_transfer::after_fee_on_transfer_balance_of(
+            sender_id,
+            token_id,
+            ft_amount.into(),
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_FEE_ON_TRANSFER_BALANCE_OF,
+        ))
+    }
+}
+
+#[ext_contract(ext_fee_on_transfer)]
+trait ExtFeeOnTransfer {
+    fn after_fee_on_transfer_balance_of(
+        &mut self,
+        account_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+    ) -> U128;
+}
+
+pub trait ExtFeeOnTransfer {
+    fn after_fee_on_transfer_balance_of(
+        &mut self,
+        account_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+    ) -> U128;
+}
+
+#[near_bindgen]
+impl ExtFeeOnTransfer for Contract {
+    /// Deposits the received amount of the fee-on-transfer asset, i.e. the balance of the contract
+    /// above the accounted balance of the asset, but no more than the transferred amount. So the
+    /// accounting never credits more than the contract actually holds. Returns the unused amount,
+    /// which is always 0.
+    #[private]
+    fn after_fee_on_transfer_balance_of(
+        &mut self,
+        account_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+    ) -> U128 {
+        let ft_balance = match env::promise_result(0) {
+            PromiseResult::Successful(value) => serde_json::from_slice::<U128>(&value).ok(),
+            _ => None,
+        }
+        .expect("Failed to get the balance of the fee-on-transfer asset");
+        let asset = self.internal_unwrap_asset(&token_id);
+        let received_ft_amount = std::cmp::min(
+            ft_balance.0.saturating_sub(asset.accounted_ft_balance()),
+            amount.0,
+        );
+        if received_ft_amount == 0 {
+            return U128(0);
+        }
+        let amount = received_ft_amount * 10u128.pow(asset.config.extra_decimals as u32);
+        let mut account = match self.internal_get_account(&account_id, false) {
+            Some(account) => account,
+            None => {
+                self.internal_add_unregistered_withdraw_claim(&account_id, &token_id, amount);
+                return U128(0);
+            }
+        };
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        let shares = self.internal_deposit(&mut account, &token_id, amount);
+        self.internal_record_supplied_volume(&mut account, &token_id, amount);
+        self.internal_unwrap_asset(&token_id).assert_supply_cap();
+        self.internal_assert_canary_caps(&account);
+        events::emit::deposit(&account_id, amount, shares, &token_id);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+        U128(0)
+    }
+}
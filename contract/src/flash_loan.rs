@@ -0,0 +1,383 @@
+use crate::*;
+use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
+use near_sdk::{is_promise_success, PromiseOrValue};
+
+const GAS_FOR_FLASH_LOAN_TRANSFER: Gas = Gas(Gas::ONE_TERA.0 * 10);
+const GAS_FOR_AFTER_FLASH_LOAN: Gas = Gas(Gas::ONE_TERA.0 * 40);
+const GAS_TO_COMPLETE_FLASH_LOAN_CALL: Gas = Gas(Gas::ONE_TERA.0 * 25);
+const MIN_GAS_FOR_FLASH_LOAN_RECEIVER: Gas = Gas(Gas::ONE_TERA.0 * 50);
+
+/// A flash loan that is in progress.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct FlashLoan {
+    /// The account that took the flash loan and is responsible for the repayment.
+    pub account_id: AccountId,
+    /// The amount of the loan.
+    #[serde(with = "u128_dec_format")]
+    pub amount: Balance,
+    /// The fee that has to be paid on top of the loan amount.
+    #[serde(with = "u128_dec_format")]
+    pub fee: Balance,
+    /// The amount that was repaid so far using `FlashLoanRepay` message.
+    #[serde(with = "u128_dec_format")]
+    pub repaid: Balance,
+    /// The supplied shares of the account that are held until the loan is resolved. They cover
+    /// the loan amount and the fee in case the loan is not repaid.
+    pub escrow_shares: Shares,
+}
+
+/// The interface of the contract that receives the flash loan.
+pub trait FlashLoanReceiver {
+    /// Called once the loan `amount` is transferred to the receiver. The `amount` and the `fee`
+    /// are in the token decimals. The receiver has to transfer the amount and the fee back to the
+    /// contract using `ft_transfer_call` with the `FlashLoanRepay` message before the returned
+    /// promise is resolved. The `account_id` is the account that took the loan.
+    fn on_flash_loan(
+        &mut self,
+        account_id: AccountId,
+        token_id: AccountId,
+        amount: U128,
+        fee: U128,
+        msg: String,
+    ) -> PromiseOrValue<()>;
+}
+
+/// Calls `on_flash_loan` of the `FlashLoanReceiver` interface on the receiver with the `amount`
+/// and the `fee` in the token decimals.
+fn on_flash_loan(
+    receiver_id: AccountId,
+    account_id: &AccountId,
+    token_id: &TokenId,
+    amount: Balance,
+    fee: Balance,
+    msg: String,
+    gas: Gas,
+) -> Promise {
+    Promise::new(receiver_id).function_call(
+        "on_flash_loan".to_string(),
+        json!({
+            "account_id": account_id,
+            "token_id": token_id,
+            "amount": U128(amount),
+            "fee": U128(fee),
+            "msg": msg,
+        })
+        .to_string()
+        .into_bytes(),
+        NO_DEPOSIT,
+        gas,
+    )
+}
+
+impl Contract {
+    pub fn internal_assert_no_flash_loan(&self, token_id: &TokenId) {
+        assert!(
+            !self.flash_loans.contains_key(token_id),
+            "Flash loan for this asset is in progress"
+        );
+    }
+
+    /// Records the repayment of the flash loan in progress for the given token.
+    pub fn internal_flash_loan_repay(&mut self, token_id: &TokenId, amount: Balance) {
+        let mut flash_loan = self
+            .flash_loans
+            .get(token_id)
+            .expect("Flash loan for this asset is not in progress");
+        flash_loan.repaid += amount;
+        self.flash_loans.insert(token_id, &flash_loan);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Lends a given amount of the asset to the given receiver within a single transaction chain.
+    /// The tokens are transferred to the receiver, and then its `on_flash_loan` of the
+    /// `FlashLoanReceiver` interface is called with the given `msg`. The loan and the fee should
+    /// be returned before the call is resolved by transferring tokens to this contract with the
+    /// `FlashLoanRepay` message. The supplied shares of the predecessor account that cover the loan
+    /// and the fee are held in escrow until the loan is resolved, so the loan is always repaid
+    /// within the call chain and never becomes a debt. The repayment is checked when the loan is
+    /// resolved:
+    /// - If the transfer to the receiver fails, the loan isn't taken and the fee is not charged.
+    /// - Any excess of the returned amount is deposited to the predecessor account.
+    /// - Any shortfall of the returned amount is repaid from the escrowed shares, and the
+    ///   `flash_loan_shortfall` event reports the amount and the shares taken.
+    /// - The rest of the escrowed shares is returned to the supplied balance of the account.
+    /// - The fee is computed with the `flash_loan_fee` of the asset config if set, or of the
+    ///   config otherwise, and is split between the reserve and the suppliers of the asset
+    ///   according to the `reserve_ratio` of the asset.
+    /// - Panics if a flash loan for the asset is already in progress.
+    /// - Panics if the flash loans of the asset are disabled.
+    /// - Panics if the asset is a multi token.
+    /// - Panics if the amount exceeds the available amount of the asset.
+    /// - Panics if the supplied balance of the asset (not used as collateral) of the account
+    ///   doesn't cover the loan and the fee.
+    /// - Panics if the amount is above the withdraw timelock threshold of the account.
+    /// - Panics if the account is locked by the pending callbacks of the multi-receipt flows.
+    /// - The account is locked until the loan is resolved, so the receiver can't change the
+    ///   account that backs the loan.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn flash_loan(
+        &mut self,
+        token_id: AccountId,
+        amount: U128,
+        receiver_id: AccountId,
+        msg: String,
+    ) -> Promise {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        self.internal_assert_account_not_locked(&account_id);
+        self.internal_assert_no_flash_loan(&token_id);
+
+        let asset = self.internal_unwrap_asset(&token_id);
+        assert!(
+            !asset.config.is_paused(PausableAction::Borrow)
+                && asset.config.can_flash_loan
+                && asset.config.is_borrowable(),
+            "This asset can't be used for flash loans"
+        );
+        assert!(
+            !self.mt_tokens.contains_key(&token_id),
+            "The multi token assets can't be used for flash loans"
+        );
+        let multiplier = 10u128.pow(asset.config.extra_decimals as u32);
+        let ft_amount = amount.0 / multiplier;
+        assert!(ft_amount > 0, "Flash loan amount can't be 0");
+        let amount = ft_amount * multiplier;
+
+        let available_amount = asset.available_amount();
+        assert!(
+            amount <= available_amount,
+            "Flash loan error: Exceeded available amount {} of {}",
+            available_amount,
+            &token_id
+        );
+
+        self.internal_assert_below_withdraw_timelock(&account_id, &token_id, amount);
+
+        let fee = ratio(
+            amount,
+            asset
+                .config
+                .flash_loan_fee
+                .unwrap_or(self.internal_config().flash_loan_fee),
+        );
+        let escrow_shares = asset.supplied.amount_to_shares(amount + fee, Rounding::Up);
+        let mut account_asset = account.internal_get_asset_or_default(&token_id);
+        assert!(
+            account_asset.shares.0 >= escrow_shares.0,
+            "Not enough supplied balance to cover the flash loan"
+        );
+        account_asset.withdraw_shares(escrow_shares);
+        account.internal_set_asset(&token_id, account_asset);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+
+        self.flash_loans.insert(
+            &token_id,
+            &FlashLoan {
+                account_id: account_id.clone(),
+                amount,
+                fee,
+                repaid: 0,
+                escrow_shares,
+            },
+        );
+        self.internal_lock_account(&account_id);
+        events::emit::flash_loan_started(&account_id, amount, &token_id, fee);
+
+        // The call itself and the callback that calls the receiver need the gas to complete, and
+        // the callback needs the gas to start.
+        let required_gas = env::used_gas()
+            + GAS_FOR_FLASH_LOAN_TRANSFER
+            + GAS_TO_COMPLETE_FLASH_LOAN_CALL * 3
+            + GAS_FOR_AFTER_FLASH_LOAN;
+        assert!(
+            env::prepaid_gas() >= required_gas + MIN_GAS_FOR_FLASH_LOAN_RECEIVER,
+            "Not enough gas for the flash loan"
+        );
+        ext_fungible_token::ft_transfer(
+            receiver_id.clone(),
+            ft_amount.into(),
+            None,
+            token_id.clone(),
+            ONE_YOCTO,
+            GAS_FOR_FLASH_LOAN_TRANSFER,
+        )
+        .then(ext_flash_loan::after_flash_loan_transfer(
+            token_id,
+            receiver_id,
+            msg,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            env::prepaid_gas()
+                - env::used_gas()
+                - GAS_FOR_FLASH_LOAN_TRANSFER
+                - GAS_TO_COMPLETE_FLASH_LOAN_CALL,
+        ))
+    }
+
+    /// Returns the flash loan in progress for the given token, if any.
+    pub fn get_flash_loan(&self, token_id: AccountId) -> Option<FlashLoan> {
+        self.flash_loans.get(&token_id)
+    }
+}
+
+#[ext_contract(ext_flash_loan)]
+trait ExtFlashLoan {
+    fn after_flash_loan_transfer(
+        &mut self,
+        token_id: TokenId,
+        receiver_id: AccountId,
+        msg: String,
+    ) -> PromiseOrValue<U128>;
+
+    fn after_flash_loan(&mut self, token_id: TokenId) -> U128;
+}
+
+pub trait ExtFlashLoan {
+    fn after_flash_loan_transfer(
+        &mut self,
+        token_id: TokenId,
+        receiver_id: AccountId,
+        msg: String,
+    ) -> PromiseOrValue<U128>;
+
+    fn after_flash_loan(&mut self, token_id: TokenId) -> U128;
+}
+
+#[near_bindgen]
+impl ExtFlashLoan for Contract {
+    /// Calls the receiver once the loan is transferred to it. If the transfer has failed, the loan
+    /// is resolved right away without the fee.
+    #[private]
+    fn after_flash_loan_transfer(
+        &mut self,
+        token_id: TokenId,
+        receiver_id: AccountId,
+        msg: String,
+    ) -> PromiseOrValue<U128> {
+        let flash_loan = match self.flash_loans.get(&token_id) {
+            Some(flash_loan) if is_promise_success() => flash_loan,
+            _ => {
+                return PromiseOrValue::Value(
+                    self.internal_resolve_flash_loan(&token_id, false).into(),
+                )
+            }
+        };
+        let multiplier =
+            10u128.pow(self.internal_unwrap_asset(&token_id).config.extra_decimals as u32);
+        let ft_amount = flash_loan.amount / multiplier;
+        let ft_fee = flash_loan.fee.div_ceil(multiplier);
+        let receiver_gas = env::prepaid_gas()
+            - env::used_gas()
+            - GAS_TO_COMPLETE_FLASH_LOAN_CALL
+            - GAS_FOR_AFTER_FLASH_LOAN;
+        on_flash_loan(
+            receiver_id,
+            &flash_loan.account_id,
+            &token_id,
+            ft_amount,
+            ft_fee,
+            msg,
+            receiver_gas,
+        )
+        .then(ext_flash_loan::after_flash_loan(
+            token_id,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_FLASH_LOAN,
+        ))
+        .into()
+    }
+
+    /// Resolves the flash loan once the receiver is called, regardless of its result. Returns the
+    /// total amount returned to the contract.
+    #[private]
+    fn after_flash_loan(&mut self, token_id: TokenId) -> U128 {
+        self.internal_resolve_flash_loan(&token_id, true).into()
+    }
+}
+
+impl Contract {
+    /// Resolves the flash loan and checks its repayment. If the loan wasn't transferred, it's
+    /// returned in full and the fee is not charged. Otherwise the amount repaid with the
+    /// `FlashLoanRepay` message is compared to the loan and the fee, and the shortfall is taken
+    /// from the escrowed shares. Returns the total amount returned to the contract.
+    ///
+    /// The method is called from the callbacks, so it doesn't panic: every check of the loan is
+    /// done before the loan is transferred, and the escrowed shares always cover the shortfall.
+    fn internal_resolve_flash_loan(&mut self, token_id: &TokenId, is_transferred: bool) -> Balance {
+        let FlashLoan {
+            account_id,
+            amount,
+            mut fee,
+            repaid,
+            escrow_shares,
+        } = match self.flash_loans.remove(token_id) {
+            Some(flash_loan) => flash_loan,
+            // The loan has been resolved already.
+            None => return 0,
+        };
+        self.internal_unlock_account(&account_id);
+
+        let mut asset = self.internal_unwrap_asset(token_id);
+        let returned = if is_transferred {
+            asset.flash_loan_volume += amount;
+            repaid
+        } else {
+            // The transfer has failed, so the loan wasn't taken and the fee is not charged.
+            fee = 0;
+            amount + repaid
+        };
+
+        // The fee is split like the interest: the reserve ratio of it goes to the reserve and the
+        // rest to the suppliers. The whole fee goes to the reserve if there are no suppliers.
+        let reserved_fee = if asset.supplied.shares.0 > 0 {
+            ratio(fee, asset.config.reserve_ratio)
+        } else {
+            fee
+        };
+        asset.supplied.balance += fee - reserved_fee;
+        asset.reserved += reserved_fee;
+        asset.collected_protocol_fees += reserved_fee;
+        asset.flash_loan_fees += fee;
+
+        let owed = amount + fee;
+        let mut returned_shares = escrow_shares;
+        if returned < owed {
+            // The escrow covered the loan and the fee at the share price of the loan start, and
+            // the share price only grows, so the shortfall is always covered.
+            let shortfall = owed - returned;
+            let shares = U128(std::cmp::min(
+                asset.supplied.amount_to_shares(shortfall, Rounding::Up).0,
+                escrow_shares.0,
+            ));
+            asset.supplied.withdraw(shares, shortfall);
+            returned_shares.0 -= shares.0;
+            events::emit::flash_loan_shortfall(&account_id, shortfall, shares, token_id);
+        }
+        self.internal_set_asset(token_id, asset);
+
+        let mut account = self.internal_unwrap_account(&account_id);
+        let mut account_asset = account.internal_get_asset_or_default(token_id);
+        account_asset.deposit_shares(returned_shares);
+        account.internal_set_asset(token_id, account_asset);
+        if returned > owed {
+            let excess = returned - owed;
+            let shares = self.internal_deposit(&mut account, token_id, excess);
+            events::emit::deposit(&account_id, excess, shares, token_id);
+        }
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account_in_callback(&account_id, account);
+
+        events::emit::flash_loan_resolved(&account_id, amount, token_id, fee, returned);
+        returned
+    }
+}
@@ -1,18 +1,135 @@
 use crate::*;
-use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
 use near_contract_standards::fungible_token::receiver::FungibleTokenReceiver;
 use near_sdk::json_types::U128;
 use near_sdk::{is_promise_success, serde_json, PromiseOrValue};
 
 const GAS_FOR_FT_TRANSFER: Gas = Gas(Gas::ONE_TERA.0 * 10);
-const GAS_FOR_AFTER_FT_TRANSFER: Gas = Gas(Gas::ONE_TERA.0 * 20);
+const GAS_FOR_AFTER_FT_TRANSFER: Gas = Gas(Gas::ONE_TERA.0 * 30);
+const GAS_FOR_AFTER_TRANSFER_ACTIONS: Gas = Gas(Gas::ONE_TERA.0 * 20);
+const GAS_TO_COMPLETE_FT_ON_TRANSFER: Gas = Gas(Gas::ONE_TERA.0 * 25);
+const MIN_GAS_FOR_TRANSFER_ACTIONS: Gas = Gas(Gas::ONE_TERA.0 * 20);
+
+/// What to do with the deposit if the actions of the transfer message fail.
+#[derive(Serialize, Deserialize, Clone, Copy)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum OnActionsFailure {
+    /// The deposit stays in the supplied balance of the account.
+    KeepDeposit,
+    /// The deposit is withdrawn from the supplied balance and returned as the unused amount.
+    RefundDeposit,
+}
 
 #[derive(Deserialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Serialize))]
 #[serde(crate = "near_sdk::serde")]
 pub enum TokenReceiverMsg {
-    Execute { actions: Vec<Action> },
+    /// Deposits the tokens and executes the actions.
+    /// If `on_failure` is not given, the entire transfer is reverted if the actions fail.
+    /// Otherwise the actions are executed after the deposit in a separate call.
+    Execute {
+        actions: Vec<Action>,
+        on_failure: Option<OnActionsFailure>,
+    },
     DepositToReserve,
+    FlashLoanRepay,
+    /// Deposits the tokens and adds the entire deposit to the collateral. The message has no
+    /// arguments, e.g. for the function call proposals of a Sputnik DAO.
+    DepositToCollateral,
+    /// The output of the leverage swap from the exchange for the given account.
+    LeverageCollateral {
+        account_id: AccountId,
+    },
+    /// The output of the withdraw swap from the exchange for the given account.
+    WithdrawSwapOutput {
+        account_id: AccountId,
+    },
+    /// The output of the repay swap from the exchange for the given account.
+    RepaySwapOutput {
+        account_id: AccountId,
+    },
+    /// The tokens bridged from Aurora by the Aurora engine for the given account.
+    AuroraDeposit {
+        account_id: AccountId,
+    },
+    /// The tokens settled by the intent settlement contract for the given account, followed by
+    /// the actions of the account, e.g. to increase the collateral.
+    IntentDeposit {
+        account_id: AccountId,
+        #[serde(default)]
+        actions: Vec<Action>,
+    },
+    /// The rewards of the Ref boost farm for the pool shares of the given account.
+    LpFarmRewards {
+        account_id: AccountId,
+    },
+    /// The output of the reserve staking swap from the exchange.
+    ReserveStakingOutput,
+    /// The bid on the auction of the given NFT.
+    NftBid {
+        collection_id: AccountId,
+        token_id: String,
+    },
+    /// Repays the borrowed balance of the transferred asset of the given account. The amount that
+    /// exceeds the debt is refunded to the sender.
+    RepayOnBehalf {
+        account_id: AccountId,
+    },
+    /// Deposits the tokens and adds the entire deposit to the collateral of the given account.
+    DepositCollateralFor {
+        account_id: AccountId,
+    },
+}
+
+/// The version of the versioned message envelope, e.g. `{"v": 2, "Execute": {"actions": []}}`.
+pub const TOKEN_RECEIVER_MSG_VERSION: u64 = 2;
+
+/// The versioned message envelope. Unknown fields are ignored, so the payloads stay valid when new
+/// optional fields are added. Unit variants are given with `null`, e.g. `"DepositToReserve": null`.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+struct VersionedTokenReceiverMsg {
+    #[serde(flatten)]
+    msg: TokenReceiverMsg,
+}
+
+impl TokenReceiverMsg {
+    /// Parses either the versioned envelope or the legacy message without the version.
+    pub fn parse(msg: &str) -> Self {
+        let value: serde_json::Value =
+            serde_json::from_str(msg).expect("Can't parse TokenReceiverMsg");
+        match value.get("v") {
+            Some(version) => {
+                assert_eq!(
+                    version.as_u64(),
+                    Some(TOKEN_RECEIVER_MSG_VERSION),
+                    "Unsupported TokenReceiverMsg version"
+                );
+                serde_json::from_value::<VersionedTokenReceiverMsg>(value)
+                    .map(|versioned| versioned.msg)
+            }
+            None => serde_json::from_value(value),
+        }
+        .expect("Can't parse TokenReceiverMsg")
+    }
+
+    /// Whether the message only repays the borrowed balance or the flash loan of the transferred
+    /// asset. Such transfers are accepted even if deposits of the asset are paused.
+    pub(crate) fn is_repayment(&self, token_id: &TokenId) -> bool {
+        match self {
+            TokenReceiverMsg::Execute { actions, .. } => {
+                !actions.is_empty()
+                    && actions.iter().all(|action| match action {
+                        Action::Repay(asset_amount) => &asset_amount.token_id == token_id,
+                        _ => false,
+                    })
+            }
+            TokenReceiverMsg::RepaySwapOutput { .. }
+            | TokenReceiverMsg::RepayOnBehalf { .. }
+            | TokenReceiverMsg::FlashLoanRepay => true,
+            _ => false,
+        }
+    }
 }
 
 #[near_bindgen]
@@ -20,6 +137,9 @@ impl FungibleTokenReceiver for Contract {
     /// Receives the transfer from the fungible token and executes a list of actions given in the
     /// message on behalf of the sender. The actions that can be executed should be limited to a set
     /// that doesn't require pricing.
+    /// - Returns the full amount as unused if the deposits of the asset are paused, unless the
+    ///   transfer repays the asset, or if the asset is reserve-only, unless the transfer is
+    ///   deposited to the reserve.
     /// - Requires to be called by the fungible token account.
     fn ft_on_transfer(
         &mut self,
@@ -27,41 +147,181 @@ impl FungibleTokenReceiver for Contract {
         amount: U128,
         msg: String,
     ) -> PromiseOrValue<U128> {
+        assert_not_self_call();
         let token_id = env::predecessor_account_id();
-        let mut asset = self.internal_unwrap_asset(&token_id);
         assert!(
-            asset.config.can_deposit,
-            "Deposits for this asset are not enabled"
+            !self.mt_tokens.contains_key(&token_id),
+            "The multi token assets have to be deposited with mt_transfer_call"
         );
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        let is_repayment = !msg.is_empty() && TokenReceiverMsg::parse(&msg).is_repayment(&token_id);
+        let is_reserve_deposit = !msg.is_empty()
+            && matches!(
+                TokenReceiverMsg::parse(&msg),
+                TokenReceiverMsg::DepositToReserve
+            );
+        if (asset.config.is_paused(PausableAction::Deposit) && !is_repayment)
+            || (asset.config.reserve_only && !is_reserve_deposit)
+        {
+            // The full amount is returned as unused instead of failing the receipt, so the sender
+            // contracts and the bridges get the refund through the regular resolve path.
+            events::emit::deposit_refunded(
+                &sender_id,
+                amount.0 * 10u128.pow(asset.config.extra_decimals as u32),
+                &token_id,
+            );
+            return PromiseOrValue::Value(amount);
+        }
+
+        if asset.config.fee_on_transfer {
+            return PromiseOrValue::Promise(
+                self.internal_fee_on_transfer_deposit(sender_id, token_id, amount.0, &msg),
+            );
+        }
 
         let amount = amount.0 * 10u128.pow(asset.config.extra_decimals as u32);
 
         // TODO: We need to be careful that only whitelisted tokens can call this method with a
         //     given set of actions. Or verify which actions are possible to do.
-        let actions: Vec<Action> = if msg.is_empty() {
-            vec![]
+        let (actions, on_failure) = if msg.is_empty() {
+            (vec![], None)
         } else {
-            let token_receiver_msg: TokenReceiverMsg =
-                serde_json::from_str(&msg).expect("Can't parse TokenReceiverMsg");
-            match token_receiver_msg {
-                TokenReceiverMsg::Execute { actions } => actions,
+            match TokenReceiverMsg::parse(&msg) {
+                TokenReceiverMsg::Execute {
+                    actions,
+                    on_failure,
+                } => (actions, on_failure),
                 TokenReceiverMsg::DepositToReserve => {
                     asset.reserved += amount;
                     self.internal_set_asset(&token_id, asset);
                     events::emit::deposit_to_reserve(&sender_id, amount, &token_id);
                     return PromiseOrValue::Value(U128(0));
                 }
+                TokenReceiverMsg::FlashLoanRepay => {
+                    self.internal_flash_loan_repay(&token_id, amount);
+                    return PromiseOrValue::Value(U128(0));
+                }
+                TokenReceiverMsg::DepositToCollateral => (
+                    self.internal_deposit_to_collateral_actions(&token_id, amount),
+                    None,
+                ),
+                TokenReceiverMsg::LeverageCollateral { account_id } => {
+                    self.internal_leverage_collateral(&sender_id, &token_id, &account_id, amount);
+                    return PromiseOrValue::Value(U128(0));
+                }
+                TokenReceiverMsg::WithdrawSwapOutput { account_id } => {
+                    self.internal_withdraw_swap_output(&sender_id, &token_id, &account_id, amount);
+                    return PromiseOrValue::Value(U128(0));
+                }
+                TokenReceiverMsg::RepaySwapOutput { account_id } => {
+                    self.internal_repay_swap_output(&sender_id, &token_id, &account_id, amount);
+                    return PromiseOrValue::Value(U128(0));
+                }
+                TokenReceiverMsg::AuroraDeposit { account_id } => {
+                    self.internal_aurora_deposit(&sender_id, &token_id, &account_id, amount);
+                    return PromiseOrValue::Value(U128(0));
+                }
+                TokenReceiverMsg::IntentDeposit {
+                    account_id,
+                    actions,
+                } => {
+                    self.internal_intent_deposit(
+                        &sender_id,
+                        &token_id,
+                        &account_id,
+                        actions,
+                        amount,
+                    );
+                    return PromiseOrValue::Value(U128(0));
+                }
+                TokenReceiverMsg::LpFarmRewards { account_id } => {
+                    self.internal_lp_farm_rewards_deposit(
+                        &sender_id,
+                        &token_id,
+                        &account_id,
+                        amount,
+                    );
+                    return PromiseOrValue::Value(U128(0));
+                }
+                TokenReceiverMsg::ReserveStakingOutput => {
+                    self.internal_reserve_staking_output(&sender_id, &token_id, amount);
+                    return PromiseOrValue::Value(U128(0));
+                }
+                TokenReceiverMsg::NftBid {
+                    collection_id,
+                    token_id: nft_token_id,
+                } => {
+                    let nft = NftId {
+                        collection_id,
+                        token_id: nft_token_id,
+                    };
+                    self.internal_nft_bid(&sender_id, &token_id, nft, amount);
+                    return PromiseOrValue::Value(U128(0));
+                }
+                TokenReceiverMsg::RepayOnBehalf { account_id } => {
+                    let unused_amount =
+                        self.internal_repay_on_behalf(&sender_id, &token_id, &account_id, amount);
+                    return PromiseOrValue::Value(U128(unused_amount));
+                }
+                TokenReceiverMsg::DepositCollateralFor { account_id } => {
+                    self.internal_deposit_collateral_for(
+                        &sender_id,
+                        &token_id,
+                        &account_id,
+                        amount,
+                    );
+                    return PromiseOrValue::Value(U128(0));
+                }
             }
         };
 
+        if !is_repayment {
+            self.internal_assert_allowlisted(&token_id, &sender_id);
+        }
         let mut account = self.internal_unwrap_account(&sender_id);
         account.add_affected_farm(FarmId::Supplied(token_id.clone()));
-        self.internal_deposit(&mut account, &token_id, amount);
-        events::emit::deposit(&sender_id, amount, &token_id);
-        self.internal_execute(&sender_id, &mut account, actions, Prices::new());
+        let shares = self.internal_deposit(&mut account, &token_id, amount);
+        self.internal_record_supplied_volume(&mut account, &token_id, amount);
+        if !is_repayment {
+            self.internal_unwrap_asset(&token_id).assert_supply_cap();
+            self.internal_assert_canary_caps(&account);
+        }
+        events::emit::deposit(&sender_id, amount, shares, &token_id);
+
+        let on_failure = match on_failure {
+            Some(on_failure) if !actions.is_empty() => on_failure,
+            _ => {
+                self.internal_execute_without_prices(&sender_id, &mut account, actions);
+                self.internal_set_account(&sender_id, account);
+                return PromiseOrValue::Value(U128(0));
+            }
+        };
         self.internal_set_account(&sender_id, account);
 
-        PromiseOrValue::Value(U128(0))
+        let required_gas =
+            env::used_gas() + GAS_TO_COMPLETE_FT_ON_TRANSFER + GAS_FOR_AFTER_TRANSFER_ACTIONS;
+        assert!(
+            env::prepaid_gas() >= required_gas + MIN_GAS_FOR_TRANSFER_ACTIONS,
+            "Not enough gas for the transfer actions"
+        );
+        PromiseOrValue::Promise(
+            ext_self::execute_transfer_actions(
+                sender_id.clone(),
+                msg,
+                env::current_account_id(),
+                NO_DEPOSIT,
+                env::prepaid_gas() - required_gas,
+            )
+            .then(ext_self::after_transfer_actions(
+                sender_id,
+                token_id,
+                amount.into(),
+                on_failure,
+                env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_AFTER_TRANSFER_ACTIONS,
+            )),
+        )
     }
 }
 
@@ -72,24 +332,65 @@ impl Contract {
         token_id: &TokenId,
         amount: Balance,
     ) -> Promise {
+        assert!(
+            parse_pool_id(token_id).is_none(),
+            "The pool shares can only be withdrawn through the exchange"
+        );
         let asset = self.internal_unwrap_asset(token_id);
         let ft_amount = amount / 10u128.pow(asset.config.extra_decimals as u32);
-        ext_fungible_token::ft_transfer(
-            account_id.clone(),
-            ft_amount.into(),
-            None,
-            token_id.clone(),
-            ONE_YOCTO,
-            GAS_FOR_FT_TRANSFER,
+        self.internal_lock_account(account_id);
+        self.internal_token_transfer(account_id, token_id, ft_amount, GAS_FOR_FT_TRANSFER)
+            .then(ext_self::after_ft_transfer(
+                account_id.clone(),
+                token_id.clone(),
+                amount.into(),
+                env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_AFTER_FT_TRANSFER,
+            ))
+    }
+
+    /// Withdraws up to the given amount from the supplied balance of the account to refund it to
+    /// the token. Returns the refunded amount in the token decimals.
+    /// Doesn't panic, because the token would refund the full amount on failure.
+    pub fn internal_refund_deposit(
+        &mut self,
+        account_id: &AccountId,
+        token_id: &TokenId,
+        amount: Balance,
+    ) -> Balance {
+        let mut account = match self.internal_get_account(account_id, false) {
+            Some(account) => account,
+            None => return 0,
+        };
+        let mut asset = self.internal_unwrap_asset(token_id);
+        let mut account_asset = account.internal_get_asset_or_default(token_id);
+        let multiplier = 10u128.pow(asset.config.extra_decimals as u32);
+
+        let available_amount = asset
+            .supplied
+            .shares_to_amount(account_asset.shares, Rounding::Down);
+        let ft_amount = std::cmp::min(available_amount, amount) / multiplier;
+        if ft_amount == 0 {
+            return 0;
+        }
+        let amount = ft_amount * multiplier;
+        let shares: Shares = std::cmp::min(
+            asset.supplied.amount_to_shares(amount, Rounding::Up).0,
+            account_asset.shares.0,
         )
-        .then(ext_self::after_ft_transfer(
-            account_id.clone(),
-            token_id.clone(),
-            amount.into(),
-            env::current_account_id(),
-            NO_DEPOSIT,
-            GAS_FOR_AFTER_FT_TRANSFER,
-        ))
+        .into();
+
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        account_asset.withdraw_shares(shares);
+        account.internal_set_asset(token_id, account_asset);
+        asset.supplied.withdraw(shares, amount);
+        self.internal_set_asset(token_id, asset);
+        events::emit::withdraw_succeeded(account_id, amount, token_id);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(account_id, account);
+
+        ft_amount
     }
 }
 
@@ -97,15 +398,37 @@ impl Contract {
 trait ExtSelf {
     fn after_ft_transfer(&mut self, account_id: AccountId, token_id: TokenId, amount: U128)
         -> bool;
+
+    fn execute_transfer_actions(&mut self, account_id: AccountId, msg: String);
+
+    fn after_transfer_actions(
+        &mut self,
+        account_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        on_failure: OnActionsFailure,
+    ) -> U128;
 }
 
 trait ExtSelf {
     fn after_ft_transfer(&mut self, account_id: AccountId, token_id: TokenId, amount: U128)
         -> bool;
+
+    fn execute_transfer_actions(&mut self, account_id: AccountId, msg: String);
+
+    fn after_transfer_actions(
+        &mut self,
+        account_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        on_failure: OnActionsFailure,
+    ) -> U128;
 }
 
 #[near_bindgen]
 impl ExtSelf for Contract {
+    /// Records the amount as claimable by the account and diagnoses the failure if the transfer
+    /// has failed.
     #[private]
     fn after_ft_transfer(
         &mut self,
@@ -113,16 +436,58 @@ impl ExtSelf for Contract {
         token_id: TokenId,
         amount: U128,
     ) -> bool {
+        self.internal_unlock_account(&account_id);
         let promise_success = is_promise_success();
         if !promise_success {
-            let mut account = self.internal_unwrap_account(&account_id);
-            account.add_affected_farm(FarmId::Supplied(token_id.clone()));
-            self.internal_deposit(&mut account, &token_id, amount.0);
+            if let Some(mut account) = self.internal_get_account(&account_id, false) {
+                self.internal_add_withdraw_claim(&mut account, &token_id, amount.0);
+                self.internal_set_account_in_callback(&account_id, account);
+            } else {
+                self.internal_add_unregistered_withdraw_claim(&account_id, &token_id, amount.0);
+            }
             events::emit::withdraw_failed(&account_id, amount.0, &token_id);
-            self.internal_set_account(&account_id, account);
+            self.internal_diagnose_transfer_failure(
+                &account_id,
+                &token_id,
+                amount.0,
+                TransferRecovery::WithdrawClaim,
+            );
         } else {
             events::emit::withdraw_succeeded(&account_id, amount.0, &token_id);
         }
         promise_success
     }
+
+    /// Executes the actions of the transfer message after the deposit.
+    #[private]
+    fn execute_transfer_actions(&mut self, account_id: AccountId, msg: String) {
+        let actions = match TokenReceiverMsg::parse(&msg) {
+            TokenReceiverMsg::Execute { actions, .. } => actions,
+            _ => env::panic_str("Unexpected TokenReceiverMsg"),
+        };
+        let mut account = self.internal_unwrap_account(&account_id);
+        self.internal_execute_without_prices(&account_id, &mut account, actions);
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Returns the unused amount of the transfer in the token decimals. It's the refunded
+    /// deposit if the actions have failed and the deposit has to be refunded.
+    #[private]
+    fn after_transfer_actions(
+        &mut self,
+        account_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        on_failure: OnActionsFailure,
+    ) -> U128 {
+        if is_promise_success() {
+            return U128(0);
+        }
+        match on_failure {
+            OnActionsFailure::KeepDeposit => U128(0),
+            OnActionsFailure::RefundDeposit => self
+                .internal_refund_deposit(&account_id, &token_id, amount.0)
+                .into(),
+        }
+    }
 }
@@ -0,0 +1,43 @@
+use crate::*;
+use near_sdk::serde_json::{self, json};
+
+/// The prefix of the gas metrics logs. The logs are only emitted by the builds with the
+/// `gas-metrics` feature, so they are not a part of the events standard.
+pub const GAS_METRICS_LOG_PREFIX: &str = "GAS_METRICS:";
+
+/// Measures the gas used by a single action of the bundle.
+pub(crate) struct ActionGasMeter {
+    action: String,
+    start_gas: Gas,
+}
+
+impl ActionGasMeter {
+    pub fn start(action: &Action) -> Self {
+        Self {
+            action: action_name(action),
+            start_gas: env::used_gas(),
+        }
+    }
+
+    /// Logs the gas used by the action since the meter was started.
+    pub fn finish(self) {
+        let gas_used = env::used_gas() - self.start_gas;
+        log!(
+            "{}{}",
+            GAS_METRICS_LOG_PREFIX,
+            json!({
+                "action": self.action,
+                "gas_used": U64(gas_used.0),
+            })
+        );
+    }
+}
+
+/// The name of the action variant as it's serialized in the JSON of the actions.
+fn action_name(action: &Action) -> String {
+    match serde_json::to_value(action).expect("Failed to serialize the action") {
+        serde_json::Value::String(name) => name,
+        serde_json::Value::Object(object) => object.keys().next().cloned().unwrap_or_default(),
+        _ => unreachable!(),
+    }
+}
@@ -0,0 +1,100 @@
+use crate::*;
+
+impl Contract {
+    pub fn internal_is_price_feeds_paused(&self) -> bool {
+        self.price_feeds_paused
+    }
+
+    fn internal_set_price_feeds_paused(&mut self, paused: bool) {
+        if self.internal_is_price_feeds_paused() == paused {
+            return;
+        }
+        self.price_feeds_paused = paused;
+        if paused {
+            events::emit::price_feeds_paused();
+        } else {
+            events::emit::price_feeds_resumed();
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Adds the account to the guardians that can pause the assets. The guardians are the
+    /// accounts with the `EmergencyAdmin` role.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn add_guardian(&mut self, guardian_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_grant_role(Role::EmergencyAdmin, guardian_id);
+    }
+
+    /// Removes the account from the guardians.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn remove_guardian(&mut self, guardian_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_revoke_role(Role::EmergencyAdmin, &guardian_id);
+    }
+
+    /// Pauses the given actions of the given assets. If `token_ids` are not given, the actions
+    /// are paused for all assets. If `actions` are not given, all actions are paused.
+    /// The actions can only be resumed by the owner or a risk admin.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or a guardian.
+    #[payable]
+    pub fn pause(
+        &mut self,
+        token_ids: Option<Vec<AccountId>>,
+        actions: Option<Vec<PausableAction>>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner_or_role(Role::EmergencyAdmin);
+        let token_ids = token_ids.unwrap_or_else(|| self.asset_ids.to_vec());
+        let actions = actions.unwrap_or_else(PausableAction::all);
+        for token_id in token_ids {
+            let mut asset = self.internal_unwrap_asset(&token_id);
+            let old_asset_config = asset.config.clone();
+            for action in &actions {
+                asset.config.pause(*action);
+            }
+            if asset.config.paused_actions != old_asset_config.paused_actions {
+                events::emit::asset_config_updated(&token_id, &old_asset_config, &asset.config);
+            }
+            self.internal_set_asset(&token_id, asset);
+        }
+    }
+
+    /// Pauses the price feeds, i.e. the oracle calls, the Pyth executions and the keeper price
+    /// updates are rejected, e.g. when the oracle is compromised. The actions that don't need
+    /// the prices are still executed. The liquidations need the prices, so they are halted until
+    /// the price feeds are resumed. The price feeds can only be resumed by the owner or a risk
+    /// admin.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or a guardian.
+    #[payable]
+    pub fn pause_price_feeds(&mut self) {
+        assert_one_yocto();
+        self.assert_owner_or_role(Role::EmergencyAdmin);
+        self.internal_set_price_feeds_paused(true);
+    }
+
+    /// Resumes the price feeds paused with `pause_price_feeds`.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or a risk admin.
+    #[payable]
+    pub fn resume_price_feeds(&mut self) {
+        assert_one_yocto();
+        self.assert_owner_or_role(Role::RiskAdmin);
+        self.internal_set_price_feeds_paused(false);
+    }
+
+    /// Returns the guardians that can pause the assets.
+    pub fn get_guardians(&self) -> Vec<AccountId> {
+        self.get_role_members(Role::EmergencyAdmin)
+    }
+}
@@ -0,0 +1,90 @@
+use crate::*;
+
+/// The upper bounds of the health factor buckets as ratios. The health factor below `MAX_RATIO`
+/// means the account can be liquidated. The last bucket has no upper bound.
+const HEALTH_BUCKET_BOUNDS: [u32; 6] = [MAX_RATIO, 10500, 11000, 12500, 15000, 20000];
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct HealthBucket {
+    /// The inclusive lower bound of the health factor as a ratio, `None` for the first bucket.
+    pub min_health: Option<u32>,
+    /// The exclusive upper bound of the health factor as a ratio, `None` for the last bucket.
+    pub max_health: Option<u32>,
+    /// The number of accounts with borrowed assets in this bucket.
+    pub num_accounts: u32,
+    /// The total value of the borrowed assets of the accounts in this bucket.
+    pub total_debt: BigDecimal,
+}
+
+impl Contract {
+    /// Returns the adjusted collateral sum and borrowed sum, and the unadjusted value of the
+    /// borrowed assets of the account.
+    fn compute_health_sums(
+        &self,
+        account: &Account,
+        prices: &Prices,
+    ) -> (BigDecimal, BigDecimal, BigDecimal) {
+        let (collateral_sum, borrowed_sum) = self.compute_adjusted_sums(account, prices);
+        let debt = account
+            .borrowed
+            .iter()
+            .fold(BigDecimal::zero(), |sum, (token_id, shares)| {
+                let asset = self.internal_unwrap_asset(&token_id);
+                let balance = asset.borrowed.shares_to_amount(*shares, Rounding::Up);
+                sum + BigDecimal::from_balance_price(
+                    balance,
+                    prices.get_unwrap(&token_id),
+                    asset.config.extra_decimals,
+                )
+            });
+        (collateral_sum, borrowed_sum, debt)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the number of accounts with borrowed assets and the total value of their debt
+    /// bucketed by the health factor. The health factor is the ratio of the adjusted collateral
+    /// sum to the adjusted borrowed sum, the accounts below 1 can be liquidated.
+    /// Only the accounts from the given index up to the given limit are counted, so the callers
+    /// iterate the pages and add up the buckets of the same index.
+    /// If `prices` are not given, the last recorded prices from the oracle are used.
+    pub fn get_health_distribution(
+        &self,
+        prices: Option<PriceData>,
+        from_index: Option<u64>,
+        limit: Option<u64>,
+    ) -> Vec<HealthBucket> {
+        let prices = prices
+            .map(|data| self.internal_prices_from_data(data))
+            .unwrap_or_else(|| self.internal_last_prices());
+        let mut buckets: Vec<HealthBucket> = (0..=HEALTH_BUCKET_BOUNDS.len())
+            .map(|index| HealthBucket {
+                min_health: index.checked_sub(1).map(|i| HEALTH_BUCKET_BOUNDS[i]),
+                max_health: HEALTH_BUCKET_BOUNDS.get(index).cloned(),
+                num_accounts: 0,
+                total_debt: BigDecimal::zero(),
+            })
+            .collect();
+        let num_accounts = self.accounts.len();
+        let from_index = from_index.unwrap_or(0);
+        let limit = limit.unwrap_or(num_accounts);
+        for index in from_index..std::cmp::min(num_accounts, from_index.saturating_add(limit)) {
+            let account = self.internal_get_account_by_index(index);
+            if account.borrowed.is_empty() {
+                continue;
+            }
+            let (collateral_sum, borrowed_sum, debt) = self.compute_health_sums(&account, &prices);
+            let index = HEALTH_BUCKET_BOUNDS
+                .iter()
+                .position(|&bound| collateral_sum < borrowed_sum.mul_ratio(bound))
+                .unwrap_or(HEALTH_BUCKET_BOUNDS.len());
+            let bucket = &mut buckets[index];
+            bucket.num_accounts += 1;
+            bucket.total_debt = bucket.total_debt + debt;
+        }
+        buckets
+    }
+}
@@ -0,0 +1,38 @@
+use crate::*;
+
+impl Contract {
+    /// Deposits the tokens settled by the intent settlement contract to the supplied balance of the
+    /// account given in the transfer message and executes the follow-up actions on behalf of the
+    /// account. Only `IncreaseCollateral` and `Repay` actions can follow the deposit.
+    pub fn internal_intent_deposit(
+        &mut self,
+        sender_id: &AccountId,
+        token_id: &TokenId,
+        account_id: &AccountId,
+        actions: Vec<Action>,
+        amount: Balance,
+    ) {
+        assert!(
+            self.internal_config()
+                .intents_contract_ids
+                .contains(sender_id),
+            "Only the intent settlement contracts can deposit on behalf of an account"
+        );
+        assert!(
+            actions
+                .iter()
+                .all(|action| matches!(action, Action::IncreaseCollateral(_) | Action::Repay(_))),
+            "The action can't follow the intent deposit"
+        );
+        self.internal_assert_allowlisted(token_id, account_id);
+        let mut account = self.internal_unwrap_account(account_id);
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        let shares = self.internal_deposit(&mut account, token_id, amount);
+        self.internal_record_supplied_volume(&mut account, token_id, amount);
+        self.internal_unwrap_asset(token_id).assert_supply_cap();
+        self.internal_assert_canary_caps(&account);
+        events::emit::deposit(account_id, amount, shares, token_id);
+        self.internal_execute_without_prices(account_id, &mut account, actions);
+        self.internal_set_account(account_id, account);
+    }
+}
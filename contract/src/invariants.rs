@@ -0,0 +1,114 @@
+use crate::*;
+
+/// The maximum number of the suppliers and the borrowers of an asset whose shares are summed up
+/// by the invariant check.
+pub const MAX_INVARIANT_SAMPLE_ACCOUNTS: u64 = 50;
+
+/// The result of the invariant check of an asset.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetInvariants {
+    pub token_id: TokenId,
+    /// Whether the supplied balance and the reserve cover the borrowed balance.
+    pub is_borrowed_covered: bool,
+    /// Whether the balance of the supplied pool is not less than its shares. The pools start
+    /// with one share per unit of the balance and the ratio only grows with the interest.
+    pub is_supplied_ratio_valid: bool,
+    /// Whether the balance of the borrowed pool is not less than its shares.
+    pub is_borrowed_ratio_valid: bool,
+    /// The total supplied shares, including the collateral, of the sampled suppliers.
+    #[serde(with = "u128_dec_format")]
+    pub sampled_supplied_shares: Balance,
+    /// The total borrowed shares of the sampled borrowers.
+    #[serde(with = "u128_dec_format")]
+    pub sampled_borrowed_shares: Balance,
+    /// Whether all the indexed suppliers and borrowers of the asset were sampled.
+    pub is_fully_sampled: bool,
+    /// Whether the sampled shares don't exceed the shares of the pools, and match them exactly
+    /// if all the accounts were sampled.
+    pub is_shares_sum_valid: bool,
+    /// Whether all the invariants of the asset hold.
+    pub is_valid: bool,
+}
+
+impl Contract {
+    /// Sums up the shares of the sampled accounts of the asset index. Returns the sum and whether
+    /// all the accounts of the index were sampled.
+    fn internal_sample_asset_shares<F: Fn(&Account) -> Shares>(
+        &self,
+        index: &LookupMap<TokenId, UnorderedSet<AccountId>>,
+        token_id: &TokenId,
+        account_shares: F,
+    ) -> (Balance, bool) {
+        let account_ids = match index.get(token_id) {
+            Some(account_ids) => account_ids,
+            None => return (0, true),
+        };
+        let account_ids = account_ids.as_vector();
+        let num_sampled = std::cmp::min(account_ids.len(), MAX_INVARIANT_SAMPLE_ACCOUNTS);
+        let shares = (0..num_sampled)
+            .filter_map(|index| self.internal_get_account(&account_ids.get(index).unwrap(), true))
+            .map(|account| account_shares(&account).0)
+            .sum();
+        (shares, num_sampled == account_ids.len())
+    }
+
+    pub fn internal_check_asset_invariants(&self, token_id: TokenId) -> AssetInvariants {
+        let asset = self.internal_unwrap_asset(&token_id);
+        let (sampled_supplied_shares, is_suppliers_sampled) =
+            self.internal_sample_asset_shares(&self.asset_suppliers, &token_id, |account| {
+                account.get_supplied_shares(&token_id)
+            });
+        let (sampled_borrowed_shares, is_borrowers_sampled) =
+            self.internal_sample_asset_shares(&self.asset_borrowers, &token_id, |account| {
+                account.get_borrowed_shares(&token_id)
+            });
+        let is_fully_sampled = is_suppliers_sampled && is_borrowers_sampled;
+        let is_sum_valid = |sampled_shares: Balance, pool: &Pool| {
+            if is_fully_sampled {
+                sampled_shares == pool.shares.0
+            } else {
+                sampled_shares <= pool.shares.0
+            }
+        };
+        let is_borrowed_covered = asset.supplied.balance + asset.reserved >= asset.borrowed.balance;
+        let is_supplied_ratio_valid = asset.supplied.balance >= asset.supplied.shares.0;
+        let is_borrowed_ratio_valid = asset.borrowed.balance >= asset.borrowed.shares.0;
+        let is_shares_sum_valid = is_sum_valid(sampled_supplied_shares, &asset.supplied)
+            && is_sum_valid(sampled_borrowed_shares, &asset.borrowed);
+        AssetInvariants {
+            token_id,
+            is_borrowed_covered,
+            is_supplied_ratio_valid,
+            is_borrowed_ratio_valid,
+            sampled_supplied_shares,
+            sampled_borrowed_shares,
+            is_fully_sampled,
+            is_shares_sum_valid,
+            is_valid: is_borrowed_covered
+                && is_supplied_ratio_valid
+                && is_borrowed_ratio_valid
+                && is_shares_sum_valid,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Checks the invariants of the given assets and returns the report for each asset:
+    /// - The supplied balance and the reserve cover the borrowed balance.
+    /// - The balances of the supplied and the borrowed pools are not less than their shares.
+    /// - The shares of the suppliers and the borrowers of the asset match the shares of the pools.
+    ///   Up to `MAX_INVARIANT_SAMPLE_ACCOUNTS` accounts of each kind are sampled, so the sampled
+    ///   shares only have to match exactly if all the accounts were sampled.
+    ///
+    /// The violations are reported instead of failing the call.
+    /// - Panics if any of the assets doesn't exist.
+    pub fn assert_invariants(&self, token_ids: Vec<AccountId>) -> Vec<AssetInvariants> {
+        token_ids
+            .into_iter()
+            .map(|token_id| self.internal_check_asset_invariants(token_id))
+            .collect()
+    }
+}
@@ -0,0 +1,84 @@
+use crate::*;
+
+impl Contract {
+    /// Returns the token ID of the isolated collateral asset of the account if the account is in
+    /// isolation.
+    pub fn internal_get_isolated_collateral(&self, account: &Account) -> Option<TokenId> {
+        account
+            .collateral
+            .keys()
+            .find(|token_id| {
+                self.internal_unwrap_asset(token_id)
+                    .config
+                    .isolation_debt_ceiling
+                    .is_some()
+            })
+            .cloned()
+    }
+
+    /// Asserts that the asset can be added to the collateral of the account. The isolated asset
+    /// can't be combined with other collateral assets, and the account can't enter the isolation
+    /// with the existing borrowed assets.
+    pub fn internal_assert_isolation_collateral(
+        &self,
+        account: &Account,
+        token_id: &TokenId,
+        asset: &Asset,
+    ) {
+        if asset.config.isolation_debt_ceiling.is_some() {
+            assert!(
+                account.collateral.keys().all(|id| id == token_id),
+                "The isolated asset can't be combined with other collateral assets"
+            );
+            assert!(
+                account.collateral.contains_key(token_id) || account.borrowed.is_empty(),
+                "Can't use the isolated asset as collateral with the existing borrowed assets"
+            );
+        } else {
+            assert!(
+                self.internal_get_isolated_collateral(account).is_none(),
+                "The isolated asset can't be combined with other collateral assets"
+            );
+        }
+    }
+
+    /// Records the new borrowed amount of the account in the debt of the isolated collateral asset.
+    pub fn internal_increase_isolated_debt(
+        &mut self,
+        account: &Account,
+        token_id: &TokenId,
+        amount: Balance,
+    ) {
+        let isolated_token_id = match self.internal_get_isolated_collateral(account) {
+            Some(isolated_token_id) => isolated_token_id,
+            None => return,
+        };
+        assert!(
+            self.internal_unwrap_asset(token_id)
+                .config
+                .borrowable_in_isolation,
+            "The asset can't be borrowed in isolation"
+        );
+        let mut isolated_asset = self.internal_unwrap_asset(&isolated_token_id);
+        isolated_asset.isolated_debt += amount;
+        assert!(
+            isolated_asset.isolated_debt <= isolated_asset.config.isolation_debt_ceiling.unwrap().0,
+            "The isolation debt ceiling of {} is exceeded",
+            isolated_token_id
+        );
+        self.internal_set_asset(&isolated_token_id, isolated_asset);
+    }
+
+    /// Removes the repaid amount from the debt of the isolated collateral asset.
+    pub fn internal_decrease_isolated_debt(
+        &mut self,
+        isolated_token_id: &Option<TokenId>,
+        amount: Balance,
+    ) {
+        if let Some(isolated_token_id) = isolated_token_id {
+            let mut isolated_asset = self.internal_unwrap_asset(isolated_token_id);
+            isolated_asset.isolated_debt = isolated_asset.isolated_debt.saturating_sub(amount);
+            self.internal_set_asset(isolated_token_id, isolated_asset);
+        }
+    }
+}
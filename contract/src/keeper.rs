@@ -0,0 +1,425 @@
+use crate::*;
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::{serde_json, PromiseResult};
+
+const GAS_FOR_GET_PRICE_DATA: Gas = Gas(Gas::ONE_TERA.0 * 10);
+const GAS_FOR_AFTER_GET_PRICE_DATA: Gas = Gas(Gas::ONE_TERA.0 * 30);
+const GAS_FOR_CREATE_TASK: Gas = Gas(Gas::ONE_TERA.0 * 20);
+const GAS_FOR_AFTER_CREATE_TASK: Gas = Gas(Gas::ONE_TERA.0 * 10);
+const GAS_FOR_REMOVE_TASK: Gas = Gas(Gas::ONE_TERA.0 * 20);
+
+/// The maximum gas a single keeper call spends on its work. The keeper calls stop at this bound
+/// or at the prepaid gas, whichever is lower, and the next call continues from the cursor.
+const KEEPER_MAX_GAS_PER_CALL: Gas = Gas(Gas::ONE_TERA.0 * 150);
+/// The gas kept after the work of a keeper call for the tip payment and the state write.
+const KEEPER_GAS_RESERVE: Gas = Gas(Gas::ONE_TERA.0 * 15);
+/// The gas reserved for the accrual of a single asset.
+const GAS_PER_ACCRUED_ASSET: Gas = Gas(Gas::ONE_TERA.0 * 5);
+/// The gas reserved for the health check of a single account.
+const GAS_PER_SCANNED_ACCOUNT: Gas = Gas(Gas::ONE_TERA.0 * 3);
+/// The gas reserved for the force closing of the worst account.
+const GAS_FOR_KEEPER_FORCE_CLOSE: Gas = Gas(Gas::ONE_TERA.0 * 40);
+
+/// The assets accrued by the keeper sooner than this after their last update are skipped.
+const KEEPER_ACCRUE_INTERVAL_SEC: u32 = 3600;
+
+/// The keeper entry points and the gas of their scheduled Croncat tasks.
+const KEEPER_METHODS: [(&str, Gas); 3] = [
+    ("keeper_accrue", Gas(Gas::ONE_TERA.0 * 200)),
+    ("keeper_liquidate_worst", Gas(Gas::ONE_TERA.0 * 200)),
+    ("keeper_update_prices", Gas(Gas::ONE_TERA.0 * 60)),
+];
+
+/// The state of the keeper entry points and the maintenance pool that pays their tips.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct KeeperStateView {
+    /// The balance of the maintenance pool.
+    #[serde(with = "u128_dec_format")]
+    pub pool_balance: Balance,
+    /// The tip paid from the pool for every keeper call that did the work.
+    pub tip: Option<U128>,
+    /// The timestamp of the price data recorded by `keeper_update_prices`.
+    #[serde(with = "u64_dec_format")]
+    pub prices_timestamp: Timestamp,
+    /// The index of the next asset to accrue.
+    pub accrue_index: u64,
+    /// The index of the next account to check for the force closing.
+    pub liquidation_index: u64,
+    /// The hashes of the registered Croncat tasks by keeper method.
+    pub tasks: Vec<(String, Base64VecU8)>,
+}
+
+/// Returns the gas of the scheduled task of the keeper method.
+/// - Panics if the method is not a keeper entry point.
+fn keeper_method_gas(method_name: &str) -> Gas {
+    KEEPER_METHODS
+        .iter()
+        .find(|(name, _)| *name == method_name)
+        .map(|(_, gas)| *gas)
+        .expect("Not a keeper method")
+}
+
+/// Whether the keeper call can do the work that needs the given gas within its bound.
+fn keeper_has_gas_for(gas: Gas) -> bool {
+    let limit = std::cmp::min(env::prepaid_gas(), KEEPER_MAX_GAS_PER_CALL);
+    env::used_gas() + gas + KEEPER_GAS_RESERVE <= limit
+}
+
+impl Contract {
+    /// Pays the tip from the maintenance pool to the keeper. Skipped if the tip is not set or the
+    /// pool doesn't have enough balance.
+    fn internal_pay_keeper_tip(&mut self, keeper_id: &AccountId) {
+        let tip = match self.internal_config().keeper_tip {
+            Some(tip) => tip.0,
+            None => return,
+        };
+        if tip > self.keeper_pool_balance {
+            log!("Not enough balance in the maintenance pool to pay the keeper tip");
+            return;
+        }
+        self.keeper_pool_balance -= tip;
+        Promise::new(keeper_id.clone()).transfer(tip);
+    }
+
+    /// Returns the last recorded prices if they were recorded by `keeper_update_prices` within
+    /// the maximum staleness duration.
+    /// - Panics if the prices are stale.
+    fn internal_keeper_prices(&self) -> Prices {
+        let staleness_sec = self.internal_config().maximum_staleness_duration_sec;
+        assert!(
+            self.keeper_prices_timestamp > 0
+                && env::block_timestamp() <= self.keeper_prices_timestamp + to_nano(staleness_sec),
+            "The keeper prices are stale"
+        );
+        self.internal_last_prices()
+    }
+
+    /// Returns the unadjusted collateral sum and borrowed sum of the account, or `None` if the
+    /// account has a leverage swap in progress, NFT collateral, or any of its assets has no price.
+    fn internal_keeper_account_sums(
+        &self,
+        account: &Account,
+        prices: &Prices,
+    ) -> Option<(BigDecimal, BigDecimal)> {
+        if self.leverage_swaps.contains_key(&account.account_id)
+            || self.nft_collateral.contains_key(&account.account_id)
+        {
+            return None;
+        }
+        let sum = |positions: &HashMap<TokenId, Shares>, is_borrowed: bool| {
+            positions
+                .iter()
+                .try_fold(BigDecimal::zero(), |sum, (token_id, shares)| {
+                    let asset = self.internal_unwrap_asset(token_id);
+                    let pool = if is_borrowed {
+                        &asset.borrowed
+                    } else {
+                        &asset.supplied
+                    };
+                    let balance =
+                        pool.shares_to_amount(*shares, Rounding::for_balance(is_borrowed));
+                    Some(
+                        sum + BigDecimal::from_balance_price(
+                            balance,
+                            prices.get(token_id)?,
+                            asset.config.extra_decimals,
+                        ),
+                    )
+                })
+        };
+        Some((
+            sum(&account.collateral, false)?,
+            sum(&account.borrowed, true)?,
+        ))
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Accrues the interest of the assets that were not updated within the last hour, starting
+    /// from the asset after the one accrued by the previous call. Stops at the gas bound of the
+    /// call. Pays the keeper tip if any asset is accrued. Anyone can call it, it's expected to be
+    /// scheduled with Croncat. Returns the number of the accrued assets.
+    pub fn keeper_accrue(&mut self) -> u32 {
+        let num_assets = self.asset_ids.len();
+        let min_update_timestamp =
+            env::block_timestamp().saturating_sub(sec_to_nano(KEEPER_ACCRUE_INTERVAL_SEC));
+        let mut num_accrued = 0;
+        for _ in 0..num_assets {
+            if !keeper_has_gas_for(GAS_PER_ACCRUED_ASSET) {
+                break;
+            }
+            let index = self.keeper_accrue_index % num_assets;
+            self.keeper_accrue_index = (index + 1) % num_assets;
+            let token_id = self.asset_ids.as_vector().get(index).unwrap();
+            let stored_asset: Asset = self.assets.get(&token_id).unwrap().into();
+            if stored_asset.last_update_timestamp > min_update_timestamp {
+                continue;
+            }
+            let asset = self.internal_unwrap_asset(&token_id);
+            self.internal_set_asset(&token_id, asset);
+            num_accrued += 1;
+        }
+        if num_accrued > 0 {
+            self.internal_pay_keeper_tip(&env::predecessor_account_id());
+        }
+        num_accrued
+    }
+
+    /// Checks the accounts for the force closing with the prices recorded by
+    /// `keeper_update_prices`, starting from the account after the ones checked by the previous
+    /// call, and force closes the account with the largest excess of the borrowed sum over the
+    /// collateral sum. Stops at the gas bound of the call. Pays the keeper tip if an account is
+    /// force closed. Anyone can call it, it's expected to be scheduled with Croncat. Returns the
+    /// force closed account.
+    /// - Panics if the force closing is not enabled.
+    /// - Panics if the keeper prices are stale.
+    pub fn keeper_liquidate_worst(&mut self) -> Option<AccountId> {
+        assert!(
+            self.internal_config().force_closing_enabled,
+            "The force closing is not enabled"
+        );
+        let prices = self.internal_keeper_prices();
+        let num_accounts = self.accounts.len();
+        let mut worst: Option<(AccountId, BigDecimal)> = None;
+        for _ in 0..num_accounts {
+            if !keeper_has_gas_for(GAS_PER_SCANNED_ACCOUNT + GAS_FOR_KEEPER_FORCE_CLOSE) {
+                break;
+            }
+            let index = self.keeper_liquidation_index % num_accounts;
+            self.keeper_liquidation_index = (index + 1) % num_accounts;
+            let account_id = self.accounts.keys_as_vector().get(index).unwrap();
+            let v_account = self.accounts.values_as_vector().get(index).unwrap();
+            let account = self.internal_vaccount_into_account(&account_id, v_account, true);
+            if account.borrowed.is_empty() {
+                continue;
+            }
+            if let Some((collateral_sum, borrowed_sum)) =
+                self.internal_keeper_account_sums(&account, &prices)
+            {
+                if borrowed_sum > collateral_sum {
+                    let bad_debt = borrowed_sum - collateral_sum;
+                    let is_worse = match &worst {
+                        Some((_, worst_bad_debt)) => &bad_debt > worst_bad_debt,
+                        None => true,
+                    };
+                    if is_worse {
+                        worst = Some((account_id, bad_debt));
+                    }
+                }
+            }
+        }
+        let (account_id, _) = worst?;
+        self.internal_force_close(&prices, &account_id);
+        self.internal_pay_keeper_tip(&env::predecessor_account_id());
+        Some(account_id)
+    }
+
+    /// Fetches the price data of the listed assets from the oracle and records it as the last
+    /// prices, after the same checks as the prices of the oracle calls. The price data that is
+    /// not newer than the recorded one is skipped. Pays the keeper tip if the prices are
+    /// recorded. Anyone can call it, it's expected to be scheduled with Croncat.
+    pub fn keeper_update_prices(&mut self) -> Promise {
+        let asset_ids = self
+            .asset_ids
+            .iter()
+            .filter(|token_id| parse_pool_id(token_id).is_none())
+            .map(|token_id| token_id.to_string())
+            .collect();
+        ext_price_oracle::get_price_data(
+            Some(asset_ids),
+            self.get_oracle_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_GET_PRICE_DATA,
+        )
+        .then(ext_keeper::after_keeper_get_price_data(
+            env::predecessor_account_id(),
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_GET_PRICE_DATA,
+        ))
+    }
+
+    /// Adds the attached deposit to the maintenance pool that pays the keeper tips.
+    /// - Panics if the attached deposit is 0.
+    #[payable]
+    pub fn fund_keeper_pool(&mut self) {
+        let amount = env::attached_deposit();
+        assert!(amount > 0, "The attached deposit can't be 0");
+        self.keeper_pool_balance += amount;
+    }
+
+    /// Withdraws the given amount, or the whole balance if not given, from the maintenance pool
+    /// to the owner.
+    /// - Panics if the amount exceeds the balance of the pool.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn withdraw_keeper_pool(&mut self, amount: Option<U128>) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        let amount = amount.map(|a| a.0).unwrap_or(self.keeper_pool_balance);
+        assert!(
+            amount <= self.keeper_pool_balance,
+            "The amount exceeds the maintenance pool balance"
+        );
+        self.keeper_pool_balance -= amount;
+        Promise::new(env::predecessor_account_id()).transfer(amount)
+    }
+
+    /// Schedules the recurring Croncat task that calls the keeper method with the given cron
+    /// cadence. The attached deposit is forwarded to the Croncat manager to pay for the task.
+    /// The hash of the created task is recorded for `unregister_keeper_task`.
+    /// - Panics if the Croncat manager is not set in the config.
+    /// - Panics if the method is not a keeper entry point or its task is already registered.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn register_keeper_task(&mut self, method_name: String, cadence: String) -> Promise {
+        self.assert_owner();
+        let croncat_manager_id = self
+            .internal_config()
+            .croncat_manager_id
+            .expect("The Croncat manager is not set");
+        let gas = keeper_method_gas(&method_name);
+        assert!(
+            self.keeper_tasks.get(&method_name).is_none(),
+            "The keeper task is already registered"
+        );
+        create_croncat_task(
+            croncat_manager_id,
+            method_name.clone(),
+            cadence,
+            gas,
+            env::attached_deposit(),
+        )
+        .then(ext_keeper::after_create_task(
+            method_name,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_CREATE_TASK,
+        ))
+    }
+
+    /// Removes the Croncat task of the keeper method. The unused deposit of the task is refunded
+    /// by Croncat to the contract.
+    /// - Panics if the task of the method is not registered.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn unregister_keeper_task(&mut self, method_name: String) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        let croncat_manager_id = self
+            .internal_config()
+            .croncat_manager_id
+            .expect("The Croncat manager is not set");
+        let task_hash = self
+            .keeper_tasks
+            .remove(&method_name)
+            .expect("The keeper task is not registered");
+        ext_croncat::remove_task(
+            task_hash,
+            croncat_manager_id,
+            NO_DEPOSIT,
+            GAS_FOR_REMOVE_TASK,
+        )
+    }
+
+    /// Returns the state of the keeper entry points and the maintenance pool.
+    pub fn get_keeper_state(&self) -> KeeperStateView {
+        KeeperStateView {
+            pool_balance: self.keeper_pool_balance,
+            tip: self.internal_config().keeper_tip,
+            prices_timestamp: self.keeper_prices_timestamp,
+            accrue_index: self.keeper_accrue_index,
+            liquidation_index: self.keeper_liquidation_index,
+            tasks: self.keeper_tasks.to_vec(),
+        }
+    }
+}
+
+#[ext_contract(ext_price_oracle)]
+trait ExtPriceOracle {
+    fn get_price_data(&self, asset_ids: Option<Vec<AssetId>>) -> PriceData;
+}
+
+/// Calls `create_task` of the Croncat manager to schedule the recurring task that calls the
+/// `function_id` of the contract with the given gas.
+fn create_croncat_task(
+    croncat_manager_id: AccountId,
+    function_id: String,
+    cadence: String,
+    gas: Gas,
+    deposit: Balance,
+) -> Promise {
+    Promise::new(croncat_manager_id).function_call(
+        "create_task".to_string(),
+        serde_json::json!({
+            "contract_id": env::current_account_id(),
+            "function_id": function_id,
+            "cadence": cadence,
+            "recurring": true,
+            "gas": gas.0,
+        })
+        .to_string()
+        .into_bytes(),
+        deposit,
+        GAS_FOR_CREATE_TASK,
+    )
+}
+
+#[ext_contract(ext_croncat)]
+trait ExtCroncat {
+    fn remove_task(&mut self, task_hash: Base64VecU8);
+}
+
+#[ext_contract(ext_keeper)]
+trait ExtKeeper {
+    fn after_keeper_get_price_data(&mut self, keeper_id: AccountId);
+
+    fn after_create_task(&mut self, method_name: String);
+}
+
+trait ExtKeeper {
+    fn after_keeper_get_price_data(&mut self, keeper_id: AccountId);
+
+    fn after_create_task(&mut self, method_name: String);
+}
+
+#[near_bindgen]
+impl ExtKeeper for Contract {
+    /// Records the prices from the oracle and pays the keeper tip, unless the price data is not
+    /// newer than the recorded one.
+    #[private]
+    fn after_keeper_get_price_data(&mut self, keeper_id: AccountId) {
+        let data = match env::promise_result(0) {
+            PromiseResult::Successful(value) => serde_json::from_slice::<PriceData>(&value).ok(),
+            _ => None,
+        }
+        .expect("Failed to get the price data");
+        if data.timestamp <= self.keeper_prices_timestamp {
+            return;
+        }
+        self.validate_price_data(&data);
+        let timestamp = data.timestamp;
+        let mut prices = self.internal_prices_from_data(data);
+        self.internal_apply_oracle_sources(PriceSource::Oracle, timestamp, &mut prices, false);
+        self.internal_assert_twap_deviation(&prices);
+        self.internal_set_prices(&prices);
+        self.keeper_prices_timestamp = timestamp;
+        self.internal_pay_keeper_tip(&keeper_id);
+    }
+
+    /// Records the hash of the created Croncat task of the keeper method.
+    #[private]
+    fn after_create_task(&mut self, method_name: String) {
+        let task_hash = match env::promise_result(0) {
+            PromiseResult::Successful(value) => serde_json::from_slice::<Base64VecU8>(&value).ok(),
+            _ => None,
+        }
+        .expect("Failed to create the keeper task");
+        self.keeper_tasks.insert(&method_name, &task_hash);
+    }
+}
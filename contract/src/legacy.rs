@@ -92,7 +92,7 @@ impl AccountV1 {
             .into_iter()
             .map(|b| (b.token_id, b.shares))
             .collect();
-        let farms = farms_unordered_map
+        let farms: Vec<(FarmId, AccountFarm)> = farms_unordered_map
             .iter()
             .map(|(key, value)| (key, value.into()))
             .collect();
@@ -103,16 +103,75 @@ impl AccountV1 {
             farms_unordered_map.clear();
             storage_tracker.stop();
         }
-        Account {
+        let mut account = Account {
             account_id,
             supplied,
             collateral,
             borrowed,
-            farms,
+            farm_ids: Default::default(),
+            changed_farms: Default::default(),
             affected_farms,
             storage_tracker,
             booster_staking,
+            shards: None,
+            principals: Default::default(),
+            last_action_timestamp: None,
+        };
+        // The farms are moved to their entries once the account is saved.
+        for (farm_id, account_farm) in farms {
+            account.set_farm(farm_id, account_farm);
+        }
+        account
+    }
+}
+
+/// V2 legacy version of Account structure, before the positions were stored with the token
+/// indices.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AccountV2 {
+    /// A copy of an account ID. Saves one storage_read when iterating on accounts.
+    pub account_id: AccountId,
+    /// A list of assets that are supplied by the account (but not used a collateral).
+    pub supplied: HashMap<TokenId, Shares>,
+    /// A list of collateral assets.
+    pub collateral: HashMap<TokenId, Shares>,
+    /// A list of borrowed assets.
+    pub borrowed: HashMap<TokenId, Shares>,
+    /// Keeping track of data required for farms for this account.
+    pub farms: HashMap<FarmId, AccountFarm>,
+    /// Staking of booster token.
+    pub booster_staking: Option<BoosterStaking>,
+}
+
+impl From<AccountV2> for Account {
+    fn from(a: AccountV2) -> Self {
+        let AccountV2 {
+            account_id,
+            supplied,
+            collateral,
+            borrowed,
+            farms,
+            booster_staking,
+        } = a;
+        let mut account = Self {
+            account_id,
+            supplied,
+            collateral,
+            borrowed,
+            farm_ids: Default::default(),
+            changed_farms: Default::default(),
+            affected_farms: Default::default(),
+            storage_tracker: Default::default(),
+            booster_staking,
+            shards: None,
+            principals: Default::default(),
+            last_action_timestamp: None,
+        };
+        // The farms are moved to their entries once the account is saved.
+        for (farm_id, account_farm) in farms {
+            account.set_farm(farm_id, account_farm);
         }
+        account
     }
 }
 
@@ -154,7 +213,7 @@ pub struct AssetConfigV0 {
     pub can_borrow: bool,
 }
 
-impl From<AssetConfigV0> for AssetConfig {
+impl From<AssetConfigV0> for AssetConfigV1 {
     fn from(a: AssetConfigV0) -> Self {
         let AssetConfigV0 {
             reserve_ratio,
@@ -184,6 +243,85 @@ impl From<AssetConfigV0> for AssetConfig {
     }
 }
 
+impl From<AssetConfigV0> for AssetConfig {
+    fn from(a: AssetConfigV0) -> Self {
+        let v1: AssetConfigV1 = a.into();
+        v1.into()
+    }
+}
+
+/// V1 legacy version of AssetConfig structure, before the paused actions bitmask was introduced.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AssetConfigV1 {
+    pub reserve_ratio: u32,
+    pub target_utilization: u32,
+    pub target_utilization_rate: LowU128,
+    pub max_utilization_rate: LowU128,
+    pub volatility_ratio: u32,
+    pub extra_decimals: u8,
+    pub can_deposit: bool,
+    pub can_withdraw: bool,
+    pub can_use_as_collateral: bool,
+    pub can_borrow: bool,
+    pub net_tvl_multiplier: u32,
+}
+
+impl From<AssetConfigV1> for AssetConfig {
+    fn from(a: AssetConfigV1) -> Self {
+        let AssetConfigV1 {
+            reserve_ratio,
+            target_utilization,
+            target_utilization_rate,
+            max_utilization_rate,
+            volatility_ratio,
+            extra_decimals,
+            can_deposit,
+            can_withdraw,
+            can_use_as_collateral,
+            can_borrow,
+            net_tvl_multiplier,
+        } = a;
+        let mut config = Self {
+            reserve_ratio,
+            target_utilization,
+            target_utilization_rate,
+            max_utilization_rate,
+            volatility_ratio,
+            extra_decimals,
+            paused_actions: 0,
+            can_use_as_collateral,
+            net_tvl_multiplier,
+            supply_cap: None,
+            borrow_cap: None,
+            isolation_debt_ceiling: None,
+            borrowable_in_isolation: false,
+            permissioned: false,
+            frozen_rate: None,
+            fee_on_transfer: false,
+            min_borrow_amount: None,
+            risk_tier: None,
+            can_flash_loan: false,
+            flash_loan_fee: None,
+            protocol_fee_ratio: 0,
+            collateral_cap: None,
+            reserve_only: false,
+            oracle_price_decimals: None,
+            collateral_only: false,
+            dust_threshold: None,
+        };
+        if !can_deposit {
+            config.pause(PausableAction::Deposit);
+        }
+        if !can_withdraw {
+            config.pause(PausableAction::Withdraw);
+        }
+        if !can_borrow {
+            config.pause(PausableAction::Borrow);
+        }
+        config
+    }
+}
+
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct AssetV0 {
     /// Total supplied including collateral, but excluding reserved.
@@ -214,6 +352,186 @@ impl From<AssetV0> for Asset {
             reserved,
             last_update_timestamp,
             config: config.into(),
+            collected_protocol_fees: 0,
+            withdrawn_protocol_fees: 0,
+            isolated_debt: 0,
+            flash_loan_volume: 0,
+            flash_loan_fees: 0,
+            collateral_shares: 0.into(),
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AssetV1 {
+    /// Total supplied including collateral, but excluding reserved.
+    pub supplied: Pool,
+    /// Total borrowed.
+    pub borrowed: Pool,
+    /// The amount reserved for the stability. This amount can also be borrowed and affects
+    /// borrowing rate.
+    pub reserved: Balance,
+    /// When the asset was last updated. It's always going to be the current block timestamp.
+    pub last_update_timestamp: Timestamp,
+    /// The asset config.
+    pub config: AssetConfigV1,
+}
+
+impl From<AssetV1> for Asset {
+    fn from(a: AssetV1) -> Self {
+        let AssetV1 {
+            supplied,
+            borrowed,
+            reserved,
+            last_update_timestamp,
+            config,
+        } = a;
+        Self {
+            supplied,
+            borrowed,
+            reserved,
+            last_update_timestamp,
+            config: config.into(),
+            collected_protocol_fees: 0,
+            withdrawn_protocol_fees: 0,
+            isolated_debt: 0,
+            flash_loan_volume: 0,
+            flash_loan_fees: 0,
+            collateral_shares: 0.into(),
+        }
+    }
+}
+
+/// V0 legacy version of Config structure, before flash loans were introduced.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct ConfigV0 {
+    pub oracle_account_id: AccountId,
+    pub owner_id: AccountId,
+    pub booster_token_id: TokenId,
+    pub booster_decimals: u8,
+    pub max_num_assets: u32,
+    pub maximum_recency_duration_sec: DurationSec,
+    pub maximum_staleness_duration_sec: DurationSec,
+    pub minimum_staking_duration_sec: DurationSec,
+    pub maximum_staking_duration_sec: DurationSec,
+    pub x_booster_multiplier_at_maximum_staking_duration: u32,
+    pub force_closing_enabled: bool,
+}
+
+impl From<ConfigV0> for Config {
+    fn from(a: ConfigV0) -> Self {
+        let ConfigV0 {
+            oracle_account_id,
+            owner_id,
+            booster_token_id,
+            booster_decimals,
+            max_num_assets,
+            maximum_recency_duration_sec,
+            maximum_staleness_duration_sec,
+            minimum_staking_duration_sec,
+            maximum_staking_duration_sec,
+            x_booster_multiplier_at_maximum_staking_duration,
+            force_closing_enabled,
+        } = a;
+        Self {
+            oracle_account_id,
+            owner_id,
+            booster_token_id,
+            booster_decimals,
+            max_num_assets,
+            maximum_recency_duration_sec,
+            maximum_staleness_duration_sec,
+            minimum_staking_duration_sec,
+            maximum_staking_duration_sec,
+            x_booster_multiplier_at_maximum_staking_duration,
+            force_closing_enabled,
+            flash_loan_fee: 0,
+            ref_exchange_id: None,
+            router_ids: vec![],
+            roles: HashMap::new(),
+            proposal_timelock_sec: 0,
+            max_total_borrowed_value: None,
+            reserve_withdrawal_delay_sec: 0,
+            max_reserve_withdrawal_ratio_per_epoch: 10000,
+            multisig_members: vec![],
+            multisig_threshold: 0,
+            multisig_methods: vec![],
+            risk_oracle_account_id: None,
+            max_volatility_ratio_change_per_day: None,
+            asset_rates_event_interval_sec: None,
+            prices_used_event_interval_sec: None,
+            heavy_events_enabled: true,
+            max_sponsored_storage_bytes: None,
+            max_num_farms_per_account: None,
+            aurora_engine_id: None,
+            keeper_tip: None,
+            croncat_manager_id: None,
+            ref_boost_farm_id: None,
+            reserve_staking: None,
+            intents_contract_ids: vec![],
+            pyth_oracle_id: None,
+            activity_tracking_enabled: false,
+            risk_tier_policy: vec![],
+            max_num_actions: None,
+            min_borrow_health_bps: None,
+            canary: None,
+            wnear_token_id: None,
         }
     }
 }
+
+/// V0 legacy version of AssetFarm structure, before the inactive rewards were kept by the contract.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AssetFarmV0 {
+    pub block_timestamp: Timestamp,
+    pub rewards: HashMap<TokenId, AssetFarmReward>,
+    /// The inactive rewards are still read from the map by its prefix.
+    pub inactive_rewards: LookupMap<TokenId, VAssetFarmReward>,
+}
+
+impl From<AssetFarmV0> for AssetFarm {
+    fn from(a: AssetFarmV0) -> Self {
+        let AssetFarmV0 {
+            block_timestamp,
+            rewards,
+            inactive_rewards: _,
+        } = a;
+        Self {
+            block_timestamp,
+            rewards,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn a(account_id: &str) -> AccountId {
+        account_id.parse().unwrap()
+    }
+
+    #[test]
+    fn test_read_account_v2() {
+        let account_v2 = VAccount::V2(AccountV2 {
+            account_id: a("alice.near"),
+            supplied: vec![(a("dai.near"), U128(100))].into_iter().collect(),
+            collateral: vec![(a("usdc.near"), U128(200))].into_iter().collect(),
+            borrowed: vec![(a("wrap.near"), U128(50))].into_iter().collect(),
+            farms: HashMap::new(),
+            booster_staking: None,
+        });
+        let bytes = account_v2.try_to_vec().unwrap();
+        let account: Account = match VAccount::try_from_slice(&bytes).unwrap() {
+            VAccount::V2(account) => account.into(),
+            _ => panic!("Expected the V2 account"),
+        };
+        assert_eq!(account.account_id, a("alice.near"));
+        assert_eq!(account.supplied[&a("dai.near")].0, 100);
+        assert_eq!(account.collateral[&a("usdc.near")].0, 200);
+        assert_eq!(account.borrowed[&a("wrap.near")].0, 50);
+        assert!(account.farm_ids.is_empty());
+        assert!(account.booster_staking.is_none());
+        assert!(account.shards.is_none());
+    }
+}
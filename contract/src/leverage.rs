@@ -0,0 +1,325 @@
+use crate::*;
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
+
+const GAS_FOR_AFTER_LEVERAGE_SWAP: Gas = Gas(Gas::ONE_TERA.0 * 30);
+
+/// A leverage swap that is in progress for an account.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct LeverageSwap {
+    pub collateral_token_id: TokenId,
+    pub borrow_token_id: TokenId,
+    /// The borrowed amount that was sent to the exchange.
+    #[serde(with = "u128_dec_format")]
+    pub borrowed_amount: Balance,
+    /// The minimum amount of the collateral token expected from the exchange. It's counted as
+    /// collateral of the account until the swap output is received.
+    #[serde(with = "u128_dec_format")]
+    pub min_collateral_amount: Balance,
+    /// The borrowed shares of the swap.
+    pub borrowed_shares: Shares,
+    /// When the swap expires in nanoseconds. The expected collateral is not counted once the swap
+    /// expires, and anyone can roll back the swap with `clear_expired_leverage_swap`.
+    #[serde(with = "u64_dec_format")]
+    pub expiry_timestamp: Timestamp,
+}
+
+impl LeverageSwap {
+    pub fn is_expired(&self) -> bool {
+        env::block_timestamp() >= self.expiry_timestamp
+    }
+}
+
+impl Contract {
+    /// Returns the amount of `borrow_token` in the token decimals that has to be borrowed to reach
+    /// the target leverage of the `collateral_token` collateral of the account.
+    pub fn internal_leverage_borrow_amount(
+        &self,
+        account: &mut Account,
+        prices: &Prices,
+        collateral_token: &TokenId,
+        borrow_token: &TokenId,
+        target_leverage: u32,
+    ) -> Balance {
+        assert_ne!(
+            collateral_token, borrow_token,
+            "Collateral and borrow tokens should be different"
+        );
+        assert!(
+            target_leverage > MAX_RATIO,
+            "Target leverage should be greater than 1x"
+        );
+
+        let collateral_asset = self.internal_unwrap_asset(collateral_token);
+        let collateral_shares = account.internal_unwrap_collateral(collateral_token);
+        let collateral_amount = collateral_asset
+            .supplied
+            .shares_to_amount(collateral_shares, Rounding::Down);
+        let borrow_sum = BigDecimal::from_balance_price(
+            collateral_amount,
+            prices.get_unwrap(collateral_token),
+            collateral_asset.config.extra_decimals,
+        )
+        .mul_ratio(target_leverage - MAX_RATIO);
+
+        let borrow_asset = self.internal_unwrap_asset(borrow_token);
+        let unit_sum = BigDecimal::from_balance_price(
+            10u128.pow(borrow_asset.config.extra_decimals as u32),
+            prices.get_unwrap(borrow_token),
+            borrow_asset.config.extra_decimals,
+        );
+        (borrow_sum / unit_sum).round_u128()
+    }
+
+    /// Borrows the `ft_amount` of `borrow_token` and swaps it for the `collateral_token` on the
+    /// exchange. The output of the swap is added to the account collateral once received.
+    pub fn internal_leverage_up(
+        &mut self,
+        account: &mut Account,
+        collateral_token: TokenId,
+        borrow_token: TokenId,
+        ft_amount: Balance,
+        mut swap_route: Vec<SwapAction>,
+        dex_id: Option<AccountId>,
+    ) {
+        let account_id = &account.account_id.clone();
+        let dex = self
+            .internal_get_dex(dex_id)
+            .expect("Leverage is not enabled");
+        assert!(
+            !self.leverage_swaps.contains_key(account_id),
+            "Leverage swap is in progress"
+        );
+        assert!(ft_amount > 0, "Leverage borrow amount can't be 0");
+        let collateral_asset = self.internal_unwrap_asset(&collateral_token);
+        let borrow_asset = self.internal_unwrap_asset(&borrow_token);
+        let amount = ft_amount * 10u128.pow(borrow_asset.config.extra_decimals as u32);
+
+        let min_collateral_amount =
+            prepare_swap_route(&mut swap_route, &borrow_token, &collateral_token, ft_amount)
+                * 10u128.pow(collateral_asset.config.extra_decimals as u32);
+        collateral_asset.assert_collateral_cap(min_collateral_amount);
+
+        let (shares, _) = self.internal_borrow_and_withdraw(
+            account,
+            &AssetAmount {
+                token_id: borrow_token.clone(),
+                amount: Some(amount.into()),
+                max_amount: None,
+            },
+        );
+        events::emit::borrow(account_id, amount, shares, &borrow_token);
+
+        account.storage_tracker.start();
+        self.leverage_swaps.insert(
+            account_id,
+            &LeverageSwap {
+                collateral_token_id: collateral_token.clone(),
+                borrow_token_id: borrow_token.clone(),
+                borrowed_amount: amount,
+                min_collateral_amount,
+                borrowed_shares: shares,
+                expiry_timestamp: env::block_timestamp() + SWAP_EXPIRY,
+            },
+        );
+        account.storage_tracker.stop();
+        events::emit::leverage_swap_started(
+            account_id,
+            amount,
+            &borrow_token,
+            min_collateral_amount,
+            &collateral_token,
+        );
+
+        let client_echo = json!({
+            "LeverageCollateral": {
+                "account_id": account_id,
+            }
+        })
+        .to_string();
+        dex.swap(swap_route, client_echo)
+            .then(ext_leverage::after_leverage_swap(
+                account_id.clone(),
+                amount.into(),
+                env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_AFTER_LEVERAGE_SWAP,
+            ));
+    }
+
+    /// Adds the received output of the leverage swap to the account collateral.
+    pub fn internal_leverage_collateral(
+        &mut self,
+        sender_id: &AccountId,
+        token_id: &TokenId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) {
+        assert!(
+            self.internal_is_dex(sender_id),
+            "Only the exchange can transfer the leverage collateral"
+        );
+        let leverage_swap = self
+            .internal_track_account_storage(account_id, |contract| {
+                contract.leverage_swaps.remove(account_id)
+            })
+            .expect("Leverage swap is not in progress");
+        assert_eq!(
+            &leverage_swap.collateral_token_id, token_id,
+            "Unexpected leverage collateral token"
+        );
+
+        let mut account = self.internal_unwrap_account(account_id);
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        let shares = self.internal_deposit(&mut account, token_id, amount);
+        events::emit::deposit(account_id, amount, shares, token_id);
+        let mut account_asset = account.internal_unwrap_asset(token_id);
+        account_asset.withdraw_shares(shares);
+        account.internal_set_asset(token_id, account_asset);
+        account.increase_collateral(token_id, shares);
+        let mut asset = self.internal_unwrap_asset(token_id);
+        asset.collateral_shares.0 += shares.0;
+        self.internal_set_asset(token_id, asset);
+        events::emit::increase_collateral(account_id, amount, shares, token_id);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(account_id, account);
+    }
+
+    /// Returns the leverage swap in progress for the account, unless it's expired.
+    pub fn internal_get_active_leverage_swap(
+        &self,
+        account_id: &AccountId,
+    ) -> Option<LeverageSwap> {
+        self.leverage_swaps
+            .get(account_id)
+            .filter(|leverage_swap| !leverage_swap.is_expired())
+    }
+
+    /// Returns the adjusted sum of the expected collateral from the leverage swap in progress.
+    /// The expired swap is not counted.
+    pub fn compute_pending_collateral_sum(
+        &self,
+        account_id: &AccountId,
+        prices: &Prices,
+    ) -> BigDecimal {
+        self.internal_get_active_leverage_swap(account_id)
+            .and_then(|leverage_swap| {
+                let price = prices.get_valid(&leverage_swap.collateral_token_id)?;
+                let asset = self.internal_unwrap_asset(&leverage_swap.collateral_token_id);
+                Some(
+                    BigDecimal::from_balance_price(
+                        leverage_swap.min_collateral_amount,
+                        price,
+                        asset.config.extra_decimals,
+                    )
+                    .mul_ratio(asset.config.volatility_ratio),
+                )
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the leverage swap in progress for the given account, if any.
+    pub fn get_leverage_swap(&self, account_id: AccountId) -> Option<LeverageSwap> {
+        self.leverage_swaps.get(&account_id)
+    }
+
+    /// Rolls back the expired leverage swap of the account. The output of the stuck swap is left
+    /// with the contract on the exchange, so the borrowed shares of the swap that are not repaid
+    /// yet are written off and covered by the reserve of the borrowed asset. Anyone can roll back
+    /// the expired swap.
+    /// - Panics if the account doesn't have an expired leverage swap.
+    /// - Panics if the reserve of the borrowed asset is not enough to cover the borrowed amount.
+    pub fn clear_expired_leverage_swap(&mut self, account_id: AccountId) {
+        let leverage_swap = self
+            .leverage_swaps
+            .get(&account_id)
+            .filter(|leverage_swap| leverage_swap.is_expired())
+            .expect("The account doesn't have an expired leverage swap");
+        self.internal_track_account_storage(&account_id, |contract| {
+            contract.leverage_swaps.remove(&account_id)
+        });
+
+        let token_id = leverage_swap.borrow_token_id;
+        let mut account = self.internal_unwrap_account(&account_id);
+        // The borrowed asset might have been repaid while the swap was in progress.
+        let borrowed_shares = account.borrowed.get(&token_id).map_or(0, |shares| shares.0);
+        let shares: Shares = std::cmp::min(leverage_swap.borrowed_shares.0, borrowed_shares).into();
+        let mut amount = 0;
+        if shares.0 > 0 {
+            account.add_affected_farm(FarmId::Borrowed(token_id.clone()));
+            let mut asset = self.internal_unwrap_asset(&token_id);
+            amount = asset.borrowed.shares_to_amount(shares, Rounding::Up);
+            assert!(
+                amount <= asset.reserved,
+                "The reserve is not enough to roll back the leverage swap"
+            );
+            asset.borrowed.withdraw(shares, amount);
+            asset.reserved -= amount;
+            self.internal_set_asset(&token_id, asset);
+            let isolated_token_id = self.internal_get_isolated_collateral(&account);
+            self.internal_decrease_isolated_debt(&isolated_token_id, amount);
+            account.decrease_borrowed(&token_id, shares);
+        }
+        events::emit::leverage_swap_expired(&account_id, amount, &token_id);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+    }
+}
+
+#[ext_contract(ext_leverage)]
+trait ExtLeverage {
+    fn after_leverage_swap(&mut self, account_id: AccountId, amount: U128) -> bool;
+}
+
+trait ExtLeverage {
+    fn after_leverage_swap(&mut self, account_id: AccountId, amount: U128) -> bool;
+}
+
+#[near_bindgen]
+impl ExtLeverage for Contract {
+    /// Repays the borrowed amount refunded by the exchange if the swap has failed.
+    /// Returns whether the swap has succeeded.
+    #[private]
+    fn after_leverage_swap(&mut self, account_id: AccountId, amount: U128) -> bool {
+        let leverage_swap = match self.leverage_swaps.get(&account_id) {
+            Some(leverage_swap) if leverage_swap.borrowed_amount == amount.0 => leverage_swap,
+            // The swap output was already received.
+            _ => return true,
+        };
+        let token_id = leverage_swap.borrow_token_id;
+        let asset = self.internal_unwrap_asset(&token_id);
+        let refunded = transfer_call_refunded_amount(amount.0, asset.config.extra_decimals);
+        if refunded == 0 {
+            return true;
+        }
+
+        self.internal_track_account_storage(&account_id, |contract| {
+            contract.leverage_swaps.remove(&account_id)
+        });
+        let mut account = self.internal_unwrap_account(&account_id);
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        account.add_affected_farm(FarmId::Borrowed(token_id.clone()));
+        self.internal_deposit(&mut account, &token_id, refunded);
+        events::emit::leverage_swap_failed(&account_id, refunded, &token_id);
+        let mut account_asset = account.internal_unwrap_asset(&token_id);
+        let (repaid_shares, repaid_amount) = self.internal_repay(
+            &mut account_asset,
+            &mut account,
+            &AssetAmount {
+                token_id: token_id.clone(),
+                amount: None,
+                max_amount: None,
+            },
+        );
+        events::emit::repay(&account_id, repaid_amount, repaid_shares, &token_id);
+        account.internal_set_asset(&token_id, account_asset);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+        false
+    }
+}
@@ -1,56 +1,200 @@
 mod account;
+mod account_activity;
 mod account_asset;
+mod account_compact;
 mod account_farm;
+mod account_interest;
+mod account_lock;
+mod account_shards;
 mod account_view;
+mod action_validation;
 mod actions;
 mod asset;
+mod asset_accounts;
+mod asset_allowlist;
+mod asset_apy;
+mod asset_change_limits;
 mod asset_config;
+mod asset_delisting;
+mod asset_deprecation;
 mod asset_farm;
+mod asset_history;
+mod asset_listing;
+mod asset_migration;
+mod asset_rate_model;
+mod asset_templates;
 mod asset_view;
+mod aurora;
+mod auto_repay;
 mod big_decimal;
 mod booster_staking;
+mod canary;
+mod collateral_shares_backfill;
 mod config;
+mod dao_adapter;
+mod dex;
+mod dust;
 mod events;
+mod execute_continuation;
+mod extra_decimals_migration;
+mod fee_on_transfer;
+mod flash_loan;
 mod fungible_token;
+#[cfg(feature = "gas-metrics")]
+mod gas_metrics;
+mod guardian;
+mod health_distribution;
+mod intents;
+mod invariants;
+mod isolation;
+mod keeper;
 mod legacy;
+mod leverage;
+mod liquidatable_accounts;
+mod lp_collateral;
+mod lp_farm_rewards;
+mod max_borrow;
+mod multi_token;
+mod multisig;
+mod native_near;
+mod nft_collateral;
+mod on_behalf;
+mod operator;
+mod oracle;
+mod oracle_commitment;
+mod pagination;
+mod partner_hooks;
 mod pool;
 mod price_receiver;
 mod prices;
+mod proposal;
+mod protocol_summary;
+mod protocol_view;
+mod pyth;
+mod ref_twap;
+mod repay_swap;
+mod reserve_staking;
+mod reserve_withdrawal;
+mod reward_per_share;
+mod risk_oracle;
+mod risk_tiers;
+mod roles;
+mod router;
+mod safe_math;
+mod shares_math;
+mod simulation;
+mod state_export;
+#[cfg(feature = "state-import")]
+mod state_import;
+mod state_migration;
 mod storage;
+mod storage_checks;
+mod storage_migration;
+mod storage_sponsorship;
 mod storage_tracker;
+mod sunset;
+mod token_metadata;
+mod transfer_diagnostics;
 mod upgrade;
 mod utils;
+mod withdraw_claim;
+mod withdraw_swap;
+mod withdraw_timelock;
 
 pub use crate::account::*;
+pub use crate::account_activity::*;
 pub use crate::account_asset::*;
+pub use crate::account_compact::*;
 pub use crate::account_farm::*;
+pub use crate::account_interest::*;
+pub use crate::account_shards::*;
 pub use crate::account_view::*;
+pub use crate::action_validation::*;
 pub use crate::actions::*;
 pub use crate::asset::*;
+pub use crate::asset_apy::*;
+pub use crate::asset_change_limits::*;
 pub use crate::asset_config::*;
 pub use crate::asset_farm::*;
+pub use crate::asset_history::*;
+pub use crate::asset_listing::*;
+pub use crate::asset_rate_model::*;
+pub use crate::asset_templates::*;
 pub use crate::asset_view::*;
+pub use crate::aurora::*;
+pub use crate::auto_repay::*;
 pub use crate::big_decimal::*;
 pub use crate::booster_staking::*;
+pub use crate::canary::*;
+pub use crate::collateral_shares_backfill::*;
 pub use crate::config::*;
+pub use crate::dex::*;
+pub use crate::dust::*;
+pub use crate::events::HeavyEventsStats;
+pub use crate::execute_continuation::*;
+pub use crate::extra_decimals_migration::*;
+pub use crate::fee_on_transfer::*;
+pub use crate::flash_loan::*;
 pub use crate::fungible_token::*;
+#[cfg(feature = "gas-metrics")]
+pub use crate::gas_metrics::*;
+pub use crate::health_distribution::*;
+pub use crate::invariants::*;
+pub use crate::keeper::*;
 pub use crate::legacy::*;
+pub use crate::leverage::*;
+pub use crate::liquidatable_accounts::*;
+pub use crate::lp_collateral::*;
+pub use crate::lp_farm_rewards::*;
+pub use crate::multi_token::*;
+pub use crate::multisig::*;
+pub use crate::native_near::*;
+pub use crate::nft_collateral::*;
+pub use crate::oracle::*;
+pub use crate::oracle_commitment::*;
+pub use crate::pagination::*;
+pub use crate::partner_hooks::*;
 pub use crate::pool::*;
 pub use crate::price_receiver::*;
 pub use crate::prices::*;
+pub use crate::proposal::*;
+pub use crate::protocol_summary::*;
+pub use crate::protocol_view::*;
+pub use crate::pyth::*;
+pub use crate::ref_twap::*;
+pub use crate::repay_swap::*;
+pub use crate::reserve_staking::*;
+pub use crate::reserve_withdrawal::*;
+pub use crate::reward_per_share::*;
+pub use crate::risk_oracle::*;
+pub use crate::risk_tiers::*;
+pub use crate::roles::*;
+use crate::safe_math::*;
+pub use crate::shares_math::*;
+pub use crate::state_export::*;
+pub use crate::state_migration::*;
 pub use crate::storage::*;
+pub use crate::storage_checks::*;
+pub use crate::storage_migration::*;
+pub use crate::storage_sponsorship::*;
 use crate::storage_tracker::*;
+pub use crate::sunset::*;
+pub use crate::token_metadata::*;
+pub use crate::transfer_diagnostics::*;
 use crate::utils::*;
+pub use crate::withdraw_claim::*;
+pub use crate::withdraw_swap::*;
+pub use crate::withdraw_timelock::*;
 
 use common::*;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet};
-use near_sdk::json_types::U128;
+use near_sdk::collections::{LazyOption, LookupMap, UnorderedMap, UnorderedSet, Vector};
+use near_sdk::json_types::{Base64VecU8, U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::{
     assert_one_yocto, env, ext_contract, log, near_bindgen, AccountId, Balance, BorshStorageKey,
-    Duration, Gas, PanicOnDefault, Promise, Timestamp,
+    Duration, Gas, PanicOnDefault, Promise, StorageUsage, Timestamp,
 };
 use once_cell::sync::Lazy;
 use std::collections::HashMap;
@@ -68,6 +212,57 @@ enum StorageKey {
     InactiveAssetFarmRewards { farm_id: FarmId },
     AssetIds,
     Config,
+    FlashLoans,
+    LeverageSwaps,
+    AutoRepayOrders,
+    WithdrawClaims,
+    WithdrawTimelocks,
+    WithdrawSwaps,
+    RouterApprovals,
+    RepaySwaps,
+    AssetHistory,
+    AssetSuppliers,
+    AssetSuppliersByIndex { token_index: TokenIndex },
+    AssetBorrowers,
+    AssetBorrowersByIndex { token_index: TokenIndex },
+    Proposals,
+    DelistingAssets,
+    ReserveWithdrawals,
+    AssetAllowlists,
+    AssetAllowlistByIndex { token_index: TokenIndex },
+    MultisigRequests,
+    RiskOracleBounds,
+    DeprecatedAssets,
+    VolatilityRatioChangeWindows,
+    SponsoredStorage,
+    TokenIds,
+    TokenIndices,
+    AccountFarmEntries,
+    MtTokens,
+    MtAssetIds,
+    Dexes,
+    TwapPairs,
+    KeeperTasks,
+    OperatorApprovals,
+    NftCollections,
+    NftCollateral,
+    NftAuctions,
+    Partners,
+    AssetMigrations,
+    PythFeeds,
+    AccountActivities,
+    TokenMetadata,
+    ExtraDecimalsMigrations,
+    ExecuteContinuations,
+    InactiveAssetFarmRewardEntries,
+    AccountLocks,
+    AccountShardEntries,
+    OracleCommitments,
+    StateVersion,
+    Sunset,
+    AssetOracleConfigs,
+    SourcePrices,
+    CollateralSharesBackfills,
 }
 
 #[near_bindgen]
@@ -78,9 +273,134 @@ pub struct Contract {
     pub assets: LookupMap<TokenId, VAsset>,
     pub asset_farms: LookupMap<FarmId, VAssetFarm>,
     pub asset_ids: UnorderedSet<TokenId>,
-    pub config: LazyOption<Config>,
+    pub config: LazyOption<VConfig>,
     /// The last recorded price info from the oracle. It's used for Net TVL farm computation.
     pub last_prices: HashMap<TokenId, Price>,
+    /// Flash loans that are in progress. At most one flash loan per asset can be in progress.
+    pub flash_loans: LookupMap<TokenId, FlashLoan>,
+    /// Leverage swaps that are in progress. At most one leverage swap per account.
+    pub leverage_swaps: LookupMap<AccountId, LeverageSwap>,
+    /// Auto-repay orders of accounts. The storage is paid by the account.
+    pub auto_repay_orders: LookupMap<AccountId, Vec<AutoRepayOrder>>,
+    /// Claimable amounts of failed withdrawals of accounts. The storage is paid by the account.
+    pub withdraw_claims: LookupMap<AccountId, Vec<WithdrawClaim>>,
+    /// Withdraw timelocks of accounts. The storage is paid by the account.
+    pub withdraw_timelocks: LookupMap<AccountId, Vec<WithdrawTimelock>>,
+    /// Withdraw swaps that are in progress. At most one withdraw swap per account.
+    pub withdraw_swaps: LookupMap<AccountId, WithdrawSwap>,
+    /// Routers approved by accounts. The storage is paid by the account.
+    pub router_approvals: LookupMap<AccountId, Vec<AccountId>>,
+    /// Repay swaps that are in progress. At most one repay swap per account.
+    pub repay_swaps: LookupMap<AccountId, RepaySwap>,
+    /// Daily snapshots of assets by (token_id, day % MAX_ASSET_HISTORY_DAYS).
+    pub asset_history: LookupMap<(TokenId, u32), AssetSnapshot>,
+    /// The last day when the snapshot was recorded for every asset.
+    pub last_asset_snapshot_days: HashMap<TokenId, u32>,
+    /// Accounts with non-zero supplied or collateral shares of every asset.
+    pub asset_suppliers: LookupMap<TokenId, UnorderedSet<AccountId>>,
+    /// Accounts with non-zero borrowed shares of every asset.
+    pub asset_borrowers: LookupMap<TokenId, UnorderedSet<AccountId>>,
+    /// Pending proposals of the risk parameter changes by proposal ID.
+    pub proposals: UnorderedMap<u32, Proposal>,
+    /// The ID of the next proposal.
+    pub next_proposal_id: u32,
+    /// The assets that are being delisted.
+    pub delisting_assets: UnorderedSet<TokenId>,
+    /// Announced reserve withdrawals and the withdrawn amounts of the last epoch by asset.
+    pub reserve_withdrawals: LookupMap<TokenId, ReserveWithdrawal>,
+    /// Accounts that can deposit and borrow every permissioned asset.
+    pub asset_allowlists: LookupMap<TokenId, UnorderedSet<AccountId>>,
+    /// Pending multisig requests by request ID.
+    pub multisig_requests: UnorderedMap<u32, MultisigRequest>,
+    /// The ID of the next multisig request.
+    pub next_multisig_request_id: u32,
+    /// The bounds of the risk parameters that the risk oracle can update by asset.
+    pub risk_oracle_bounds: LookupMap<TokenId, RiskOracleBounds>,
+    /// The assets that are deprecated, i.e. in the repay-only mode.
+    pub deprecated_assets: UnorderedSet<TokenId>,
+    /// The current 24 hour windows of the volatility ratio changes by asset.
+    pub volatility_ratio_change_windows: LookupMap<TokenId, VolatilityRatioChangeWindow>,
+    /// The timestamp of the last rates event of every asset.
+    pub last_asset_rates_event_timestamps: HashMap<TokenId, Timestamp>,
+    /// The timestamp of the last `prices_used` event.
+    pub last_prices_used_event_timestamp: Timestamp,
+    /// The changes of the asset balances within the last block where the assets have changed.
+    pub protocol_summary: ProtocolSummary,
+    /// The gas used by the emission of the heavy events.
+    pub heavy_events_stats: HeavyEventsStats,
+    /// The balance of the storage sponsorship pool.
+    pub storage_sponsorship_balance: Balance,
+    /// The number of bytes sponsored to the accounts by account.
+    pub sponsored_storage: LookupMap<AccountId, StorageUsage>,
+    /// The total number of bytes sponsored to the accounts.
+    pub total_sponsored_bytes: StorageUsage,
+    /// The token IDs by the token index used in the account positions.
+    pub token_ids: Vector<TokenId>,
+    /// The token indices by token ID.
+    pub token_indices: LookupMap<TokenId, TokenIndex>,
+    /// The farms of the accounts by account ID and farm ID. The storage is paid by the account.
+    pub account_farms: LookupMap<(AccountId, FarmId), VAccountFarm>,
+    /// The last synced underlying tokens of a unit of the listed pool shares by token ID. It's
+    /// used for the fair prices of the pool shares.
+    pub last_lp_token_infos: HashMap<TokenId, UnitShareTokens>,
+    /// The multi tokens of the assets listed from the multi token contracts by token ID.
+    pub mt_tokens: LookupMap<TokenId, MtToken>,
+    /// The token IDs of the listed multi tokens.
+    pub mt_asset_ids: LookupMap<MtToken, TokenId>,
+    /// The whitelisted exchanges for the swaps besides the `ref_exchange_id` from the config.
+    pub dexes: UnorderedMap<AccountId, DexKind>,
+    /// The Ref Finance pools that are used to cross-check the oracle prices by token ID.
+    pub twap_pairs: UnorderedMap<TokenId, TwapPair>,
+    /// The balance of the maintenance pool that pays the keeper tips.
+    pub keeper_pool_balance: Balance,
+    /// The timestamp of the price data recorded by `keeper_update_prices`.
+    pub keeper_prices_timestamp: Timestamp,
+    /// The index of the next asset to accrue by `keeper_accrue`.
+    pub keeper_accrue_index: u64,
+    /// The index of the next account to check by `keeper_liquidate_worst`.
+    pub keeper_liquidation_index: u64,
+    /// The hashes of the registered Croncat tasks by keeper method.
+    pub keeper_tasks: UnorderedMap<String, Base64VecU8>,
+    /// Operators approved by accounts for `execute_for`. The storage is paid by the account.
+    pub operator_approvals: LookupMap<AccountId, Vec<AccountId>>,
+    /// The whitelisted NFT collections with their floor prices by collection ID.
+    pub nft_collections: UnorderedMap<AccountId, NftCollection>,
+    /// The NFTs in the collateral of accounts. The storage is paid by the account.
+    pub nft_collateral: LookupMap<AccountId, Vec<NftId>>,
+    /// The auctions of the NFTs taken from the collateral of the liquidated accounts.
+    pub nft_auctions: UnorderedMap<NftId, NftAuction>,
+    /// The whitelisted partner contracts by account ID, with whether the position hook is
+    /// registered.
+    pub partners: UnorderedMap<AccountId, bool>,
+    /// The state of the staking of the reserve into the liquid staking token.
+    pub reserve_staking: ReserveStaking,
+    /// The replacements of the deprecated assets by the deprecated token ID.
+    pub asset_migrations: UnorderedMap<TokenId, TokenId>,
+    /// The Pyth price feeds by token ID.
+    pub pyth_feeds: UnorderedMap<TokenId, PythFeed>,
+    /// The activity metrics of the accounts. The storage is paid by the account.
+    pub account_activities: LookupMap<AccountId, AccountActivity>,
+    /// The metadata of the listed tokens, cached from the token contracts.
+    pub token_metadata: LookupMap<TokenId, TokenMetadata>,
+    /// The migrations of the assets to new extra decimals that are in progress.
+    pub extra_decimals_migrations: UnorderedMap<TokenId, ExtraDecimalsMigration>,
+    /// The continuations of the execute bundles that are in progress by account ID. The account
+    /// is locked until its continuation is executed or cancelled.
+    pub execute_continuations: LookupMap<AccountId, ExecuteContinuation>,
+    /// The inactive rewards of the asset farms by farm ID and reward token ID.
+    pub inactive_asset_farm_rewards: LookupMap<(FarmId, TokenId), VAssetFarmReward>,
+    /// The number of pending callbacks of the multi-receipt flows by account ID. The account
+    /// can't execute actions while it's locked.
+    pub account_locks: LookupMap<AccountId, u32>,
+    /// The shards of the sharded accounts by account ID and shard ID.
+    pub account_shards: LookupMap<(AccountId, AccountShardId), AccountShard>,
+    /// The oracle call commitments of the accounts that enabled them.
+    pub oracle_commitments: LookupMap<AccountId, OracleCommitment>,
+    /// The recounts of the total collateral shares of the assets that are in progress.
+    pub collateral_shares_backfills: UnorderedMap<TokenId, CollateralSharesBackfill>,
+    /// Whether the price feeds are paused by a guardian, so the actions that need the prices,
+    /// including the liquidations, are rejected.
+    pub price_feeds_paused: bool,
 }
 
 #[near_bindgen]
@@ -89,14 +409,77 @@ impl Contract {
     #[init]
     pub fn new(config: Config) -> Self {
         config.assert_valid();
+        write_state_version();
         Self {
             accounts: UnorderedMap::new(StorageKey::Accounts),
             storage: LookupMap::new(StorageKey::Storage),
             assets: LookupMap::new(StorageKey::Assets),
             asset_farms: LookupMap::new(StorageKey::AssetFarms),
             asset_ids: UnorderedSet::new(StorageKey::AssetIds),
-            config: LazyOption::new(StorageKey::Config, Some(&config)),
+            config: LazyOption::new(StorageKey::Config, Some(&config.into())),
             last_prices: HashMap::new(),
+            flash_loans: LookupMap::new(StorageKey::FlashLoans),
+            leverage_swaps: LookupMap::new(StorageKey::LeverageSwaps),
+            auto_repay_orders: LookupMap::new(StorageKey::AutoRepayOrders),
+            withdraw_claims: LookupMap::new(StorageKey::WithdrawClaims),
+            withdraw_timelocks: LookupMap::new(StorageKey::WithdrawTimelocks),
+            withdraw_swaps: LookupMap::new(StorageKey::WithdrawSwaps),
+            router_approvals: LookupMap::new(StorageKey::RouterApprovals),
+            repay_swaps: LookupMap::new(StorageKey::RepaySwaps),
+            asset_history: LookupMap::new(StorageKey::AssetHistory),
+            last_asset_snapshot_days: HashMap::new(),
+            asset_suppliers: LookupMap::new(StorageKey::AssetSuppliers),
+            asset_borrowers: LookupMap::new(StorageKey::AssetBorrowers),
+            proposals: UnorderedMap::new(StorageKey::Proposals),
+            next_proposal_id: 0,
+            delisting_assets: UnorderedSet::new(StorageKey::DelistingAssets),
+            reserve_withdrawals: LookupMap::new(StorageKey::ReserveWithdrawals),
+            asset_allowlists: LookupMap::new(StorageKey::AssetAllowlists),
+            multisig_requests: UnorderedMap::new(StorageKey::MultisigRequests),
+            next_multisig_request_id: 0,
+            risk_oracle_bounds: LookupMap::new(StorageKey::RiskOracleBounds),
+            deprecated_assets: UnorderedSet::new(StorageKey::DeprecatedAssets),
+            volatility_ratio_change_windows: LookupMap::new(
+                StorageKey::VolatilityRatioChangeWindows,
+            ),
+            last_asset_rates_event_timestamps: HashMap::new(),
+            last_prices_used_event_timestamp: 0,
+            protocol_summary: ProtocolSummary::default(),
+            heavy_events_stats: HeavyEventsStats::default(),
+            storage_sponsorship_balance: 0,
+            sponsored_storage: LookupMap::new(StorageKey::SponsoredStorage),
+            total_sponsored_bytes: 0,
+            token_ids: Vector::new(StorageKey::TokenIds),
+            token_indices: LookupMap::new(StorageKey::TokenIndices),
+            account_farms: LookupMap::new(StorageKey::AccountFarmEntries),
+            last_lp_token_infos: HashMap::new(),
+            mt_tokens: LookupMap::new(StorageKey::MtTokens),
+            mt_asset_ids: LookupMap::new(StorageKey::MtAssetIds),
+            dexes: UnorderedMap::new(StorageKey::Dexes),
+            twap_pairs: UnorderedMap::new(StorageKey::TwapPairs),
+            keeper_pool_balance: 0,
+            keeper_prices_timestamp: 0,
+            keeper_accrue_index: 0,
+            keeper_liquidation_index: 0,
+            keeper_tasks: UnorderedMap::new(StorageKey::KeeperTasks),
+            operator_approvals: LookupMap::new(StorageKey::OperatorApprovals),
+            nft_collections: UnorderedMap::new(StorageKey::NftCollections),
+            nft_collateral: LookupMap::new(StorageKey::NftCollateral),
+            nft_auctions: UnorderedMap::new(StorageKey::NftAuctions),
+            partners: UnorderedMap::new(StorageKey::Partners),
+            reserve_staking: ReserveStaking::default(),
+            asset_migrations: UnorderedMap::new(StorageKey::AssetMigrations),
+            pyth_feeds: UnorderedMap::new(StorageKey::PythFeeds),
+            account_activities: LookupMap::new(StorageKey::AccountActivities),
+            token_metadata: LookupMap::new(StorageKey::TokenMetadata),
+            extra_decimals_migrations: UnorderedMap::new(StorageKey::ExtraDecimalsMigrations),
+            execute_continuations: LookupMap::new(StorageKey::ExecuteContinuations),
+            inactive_asset_farm_rewards: LookupMap::new(StorageKey::InactiveAssetFarmRewardEntries),
+            account_locks: LookupMap::new(StorageKey::AccountLocks),
+            account_shards: LookupMap::new(StorageKey::AccountShardEntries),
+            oracle_commitments: LookupMap::new(StorageKey::OracleCommitments),
+            collateral_shares_backfills: UnorderedMap::new(StorageKey::CollateralSharesBackfills),
+            price_feeds_paused: false,
         }
     }
 }
@@ -0,0 +1,200 @@
+use crate::*;
+
+/// The borrowed asset of the liquidatable account.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct LiquidatableAssetView {
+    pub token_id: TokenId,
+    #[serde(with = "u128_dec_format")]
+    pub balance: Balance,
+    /// The largest amount of the asset that can be repaid by a single liquidation, see
+    /// `get_liquidatable_accounts`. It's 0 if the liquidations of the asset are paused or the
+    /// liquidation can't improve the health factor.
+    #[serde(with = "u128_dec_format")]
+    pub max_repay_amount: Balance,
+}
+
+/// The account that can be liquidated with its health.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct LiquidatableAccountView {
+    pub account_id: AccountId,
+    /// The health factor as a ratio (multiplied by 10000), i.e. the adjusted collateral sum to the
+    /// adjusted borrowed sum. It's always less than 10000.
+    pub health_factor: u32,
+    pub collateral_sum: BigDecimal,
+    pub borrowed_sum: BigDecimal,
+    /// The discount of the collateral taken by the liquidators.
+    pub max_discount: BigDecimal,
+    pub collateral: Vec<AssetView>,
+    pub borrowed: Vec<LiquidatableAssetView>,
+}
+
+impl Contract {
+    /// Returns the largest amount of the borrowed asset that a liquidation can repay, taking the
+    /// whole discounted value of the repaid amount from the collateral pro rata. The liquidation
+    /// has to leave the account at risk, so the amount is limited by the gap between the adjusted
+    /// sums, and rounded down to the whole units of the token. The amount is 0 if taking the
+    /// collateral for it doesn't improve the health factor.
+    fn internal_compute_max_repay(
+        &self,
+        token_id: &TokenId,
+        balance: Balance,
+        prices: &Prices,
+        (collateral_sum, borrowed_sum): (&BigDecimal, &BigDecimal),
+        (collateral_value, collateral_ratio): (&BigDecimal, &BigDecimal),
+        max_discount: &BigDecimal,
+    ) -> Balance {
+        let asset = self.internal_unwrap_asset(token_id);
+        if asset.config.is_paused(PausableAction::Liquidate) {
+            return 0;
+        }
+        // Repaying the value `x` decreases the adjusted borrowed sum by `x / volatility_ratio`
+        // and the adjusted collateral sum by `x * collateral_ratio / (1 - max_discount)`. The
+        // health factor improves only if the gap shrinks faster than `2 * max_discount` of the
+        // decrease of the borrowed sum.
+        let borrowed_decrease = BigDecimal::one().div_ratio(asset.config.volatility_ratio);
+        let collateral_decrease = *collateral_ratio / (BigDecimal::one() - *max_discount);
+        let min_gap_decrease =
+            (*max_discount * BigDecimal::from(2u32)).div_ratio(asset.config.volatility_ratio);
+        if borrowed_decrease <= collateral_decrease + min_gap_decrease {
+            return 0;
+        }
+        let gap_decrease = borrowed_decrease - collateral_decrease;
+        let price = prices.get_unwrap(token_id);
+        let gap_value = (*borrowed_sum - *collateral_sum) / gap_decrease;
+        let collateral_repay_value = *collateral_value * (BigDecimal::one() - *max_discount);
+        let max_value = if gap_value < collateral_repay_value {
+            gap_value
+        } else {
+            collateral_repay_value
+        };
+
+        let multiplier = 10u128.pow(asset.config.extra_decimals as u32);
+        let unit_value =
+            BigDecimal::from_balance_price(multiplier, price, asset.config.extra_decimals);
+        let amount = (max_value / unit_value).floor_u128() * multiplier;
+        std::cmp::min(amount, balance)
+    }
+
+    /// Returns the view of the account if it can be liquidated with the prices, or `None` if it's
+    /// healthy or the prices don't cover its borrowed assets.
+    fn internal_get_liquidatable_account(
+        &self,
+        account: Account,
+        prices: &Prices,
+    ) -> Option<LiquidatableAccountView> {
+        if account.borrowed.is_empty()
+            || !account
+                .borrowed
+                .keys()
+                .all(|token_id| prices.contains(token_id))
+        {
+            return None;
+        }
+        let (collateral_sum, borrowed_sum) = self.compute_adjusted_sums(&account, prices);
+        if collateral_sum >= borrowed_sum {
+            return None;
+        }
+        let max_discount = self.compute_max_discount(&account, prices);
+        // The unadjusted value of the collateral that can be taken, and its average volatility
+        // ratio.
+        let (collateral_value, adjusted_collateral_value) = account.collateral.iter().fold(
+            (BigDecimal::zero(), BigDecimal::zero()),
+            |(value, adjusted_value), (token_id, shares)| {
+                let price = match prices.get_valid(token_id) {
+                    Some(price) => price,
+                    None => return (value, adjusted_value),
+                };
+                let asset = self.internal_unwrap_asset(token_id);
+                let balance = asset.supplied.shares_to_amount(*shares, Rounding::Down);
+                let asset_value =
+                    BigDecimal::from_balance_price(balance, price, asset.config.extra_decimals);
+                (
+                    value + asset_value,
+                    adjusted_value + asset_value.mul_ratio(asset.config.volatility_ratio),
+                )
+            },
+        );
+        let collateral_ratio = if collateral_value == BigDecimal::zero() {
+            BigDecimal::zero()
+        } else {
+            adjusted_collateral_value / collateral_value
+        };
+
+        let borrowed = account
+            .borrowed
+            .iter()
+            .map(|(token_id, shares)| {
+                let asset = self.internal_unwrap_asset(token_id);
+                let balance = asset.borrowed.shares_to_amount(*shares, Rounding::Up);
+                let max_repay_amount = if collateral_value == BigDecimal::zero() {
+                    0
+                } else {
+                    self.internal_compute_max_repay(
+                        token_id,
+                        balance,
+                        prices,
+                        (&collateral_sum, &borrowed_sum),
+                        (&collateral_value, &collateral_ratio),
+                        &max_discount,
+                    )
+                };
+                LiquidatableAssetView {
+                    token_id: token_id.clone(),
+                    balance,
+                    max_repay_amount,
+                }
+            })
+            .collect();
+        let health_factor =
+            (collateral_sum / borrowed_sum).round_mul_u128(MAX_RATIO as u128) as u32;
+        let collateral = account
+            .collateral
+            .iter()
+            .map(|(token_id, shares)| self.get_asset_view(token_id.clone(), *shares, false))
+            .collect();
+        Some(LiquidatableAccountView {
+            account_id: account.account_id,
+            health_factor,
+            collateral_sum,
+            borrowed_sum,
+            max_discount,
+            collateral,
+            borrowed,
+        })
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns a page of the accounts that can be liquidated with their health and the largest
+    /// amounts of the borrowed assets that a liquidation can repay, starting from the given
+    /// continuation token. The accounts are checked in the order of `get_accounts_page`, and only
+    /// the liquidatable ones are returned, so a page can be empty even if it's not the last page.
+    /// The max repay amounts assume the liquidator takes the whole discounted value of the repaid
+    /// amount from the collateral pro rata. Taking less collateral improves the health factor
+    /// more, so the liquidation of the max amount may fail the check that the account stays at
+    /// risk. If `prices` are not given, the last recorded prices from the oracle are used.
+    pub fn get_liquidatable_accounts(
+        &self,
+        prices: Option<PriceData>,
+        token: Option<String>,
+        limit: Option<u64>,
+    ) -> Page<LiquidatableAccountView> {
+        let prices = prices
+            .map(|data| self.internal_prices_from_data(data))
+            .unwrap_or_else(|| self.internal_last_prices());
+        let page = Page::new(self.accounts.len(), token, limit, |index| {
+            let account = self.internal_get_account_by_index(index);
+            self.internal_get_liquidatable_account(account, &prices)
+        });
+        Page {
+            items: page.items.into_iter().flatten().collect(),
+            total_count: page.total_count,
+            next_token: page.next_token,
+        }
+    }
+}
@@ -0,0 +1,416 @@
+use crate::*;
+use near_sdk::json_types::U128;
+use near_sdk::{is_promise_success, serde_json, PromiseResult};
+use std::convert::TryFrom;
+
+/// The prefix of the token IDs of the Ref Finance pool shares held via the shadow records, e.g.
+/// `shadow_ref_v1-0` for the shares of the pool 0. The shares stay on the exchange locked by the
+/// shadow record, and the contract only keeps their balances.
+pub const SHADOW_V1_TOKEN_PREFIX: &str = "shadow_ref_v1-";
+
+/// The number of the decimals of the computed LP share prices on top of the share decimals.
+const LP_PRICE_DECIMALS: u8 = 8;
+
+const GAS_FOR_MFT_METADATA: Gas = Gas(Gas::ONE_TERA.0 * 5);
+const GAS_FOR_GET_UNIT_LPT_ASSETS: Gas = Gas(Gas::ONE_TERA.0 * 10);
+const GAS_FOR_AFTER_GET_UNIT_LPT_ASSETS: Gas = Gas(Gas::ONE_TERA.0 * 10);
+const GAS_FOR_ON_BURROW_LIQUIDATION: Gas = Gas(Gas::ONE_TERA.0 * 40);
+const GAS_FOR_AFTER_LP_LIQUIDATION: Gas = Gas(Gas::ONE_TERA.0 * 20);
+
+/// The amount of an underlying token of a unit of the pool shares.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct UnitShareToken {
+    pub token_id: TokenId,
+    /// The amount of the token in the token decimals.
+    pub amount: U128,
+}
+
+/// The underlying tokens of a unit of the pool shares, i.e. `10^decimals` shares, as returned by
+/// the exchange.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct UnitShareTokens {
+    /// The decimals of the pool shares.
+    pub decimals: u8,
+    pub tokens: Vec<UnitShareToken>,
+}
+
+impl UnitShareTokens {
+    /// Returns the fair price of the pool shares from the prices of the underlying tokens, or
+    /// `None` if any of the prices is missing. The value of a two-token pool unit is
+    /// `2 * sqrt(value_0 * value_1)`, which doesn't change when the reserves are moved by a swap,
+    /// so the price can't be inflated by manipulating the pool. The value of the other pools is
+    /// the number of tokens times the smallest token value.
+    pub fn fair_price(&self, prices: &Prices) -> Option<Price> {
+        let values = self
+            .tokens
+            .iter()
+            .map(|token| {
+                prices
+                    .get(&token.token_id)
+                    .map(|price| BigDecimal::from_balance_price(token.amount.0, price, 0))
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let unit_value = match values.as_slice() {
+            [a, b] => BigDecimal::from(2u32) * (*a * *b).sqrt(),
+            _ => {
+                let min_value = values
+                    .iter()
+                    .copied()
+                    .reduce(|a, b| if a <= b { a } else { b })?;
+                BigDecimal::from(values.len() as u64) * min_value
+            }
+        };
+        Some(Price {
+            multiplier: unit_value.round_mul_u128(10u128.pow(LP_PRICE_DECIMALS as u32)),
+            decimals: self.decimals + LP_PRICE_DECIMALS,
+        })
+    }
+}
+
+/// Returns the pool ID of the shadow token ID of the pool shares, or `None` if the token is not
+/// the pool shares.
+pub fn parse_pool_id(token_id: &TokenId) -> Option<u64> {
+    token_id
+        .as_str()
+        .strip_prefix(SHADOW_V1_TOKEN_PREFIX)
+        .and_then(|pool_id| pool_id.parse().ok())
+}
+
+/// Returns the token ID of the pool shares with the given shadow ID, i.e. the pool ID.
+fn shadow_token_id(shadow_id: &str) -> TokenId {
+    let pool_id: u64 = shadow_id.parse().expect("Invalid shadow ID");
+    TokenId::try_from(format!("{}{}", SHADOW_V1_TOKEN_PREFIX, pool_id)).unwrap()
+}
+
+impl Contract {
+    pub fn internal_unwrap_ref_exchange_id(&self) -> AccountId {
+        self.internal_config()
+            .ref_exchange_id
+            .expect("The exchange is not set")
+    }
+
+    fn assert_ref_exchange(&self) {
+        assert_eq!(
+            env::predecessor_account_id(),
+            self.internal_unwrap_ref_exchange_id(),
+            "Can only be called by the exchange"
+        );
+    }
+
+    /// Asserts that the pool shares can't be borrowed, because the shares can't leave the
+    /// exchange. The borrowing is either paused or the shares are collateral-only.
+    pub fn internal_assert_lp_asset_config(&self, token_id: &TokenId, asset_config: &AssetConfig) {
+        if parse_pool_id(token_id).is_some() {
+            assert!(
+                asset_config.is_paused(PausableAction::Borrow) || asset_config.collateral_only,
+                "The borrowing of the pool shares has to be paused"
+            );
+        }
+    }
+
+    /// Requests the metadata of the pool shares from the exchange for the listing of the asset.
+    pub fn internal_lp_metadata(&self, pool_id: u64) -> Promise {
+        ext_ref_exchange::mft_metadata(
+            format!(":{}", pool_id),
+            self.internal_unwrap_ref_exchange_id(),
+            NO_DEPOSIT,
+            GAS_FOR_MFT_METADATA,
+        )
+    }
+
+    /// Converts the price data from the oracle, applies the oracle price decimals of the assets
+    /// and adds the fair prices of the pool shares from the last synced underlying tokens.
+    pub fn internal_prices_from_data(&self, data: PriceData) -> Prices {
+        let mut prices: Prices = data.into();
+        self.internal_apply_oracle_price_decimals(&mut prices);
+        self.internal_add_lp_fair_prices(&mut prices);
+        prices
+    }
+
+    /// Adds the fair prices of the pool shares from the last synced underlying tokens.
+    pub fn internal_add_lp_fair_prices(&self, prices: &mut Prices) {
+        for (token_id, unit_share_tokens) in self.last_lp_token_infos.iter() {
+            if let Some(price) = unit_share_tokens.fair_price(prices) {
+                prices.insert(token_id.clone(), price);
+            }
+        }
+    }
+
+    /// Withdraws the pool shares taken by the liquidator and removes the liquidity of the shares
+    /// of the liquidated account on the exchange. The underlying tokens are deposited to the
+    /// liquidator account on the exchange.
+    pub fn internal_unwind_lp_shares(
+        &mut self,
+        account: &mut Account,
+        liquidation_account_id: &AccountId,
+        token_id: &TokenId,
+        shares: Shares,
+        amount: Balance,
+    ) {
+        let pool_id = parse_pool_id(token_id).unwrap();
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        let mut asset = self.internal_unwrap_asset(token_id);
+        let mut account_asset = account.internal_unwrap_asset(token_id);
+        account_asset.withdraw_shares(shares);
+        account.internal_set_asset(token_id, account_asset);
+        asset.supplied.withdraw(shares, amount);
+        let ft_amount = amount / 10u128.pow(asset.config.extra_decimals as u32);
+        self.internal_set_asset(token_id, asset);
+        self.internal_lock_account(liquidation_account_id);
+        on_burrow_liquidation(
+            self.internal_unwrap_ref_exchange_id(),
+            &account.account_id,
+            liquidation_account_id,
+            pool_id.to_string(),
+            ft_amount,
+        )
+        .then(ext_lp_collateral::after_lp_liquidation(
+            account.account_id.clone(),
+            liquidation_account_id.clone(),
+            token_id.clone(),
+            amount.into(),
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_LP_LIQUIDATION,
+        ));
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Deposits the pool shares that the exchange has locked with the shadow record of the
+    /// account, and executes the actions from the message, e.g. to increase the collateral.
+    /// The message is the `TokenReceiverMsg::Execute` message, or empty to only deposit.
+    /// - Panics if the pool shares are not listed or the deposits are paused.
+    /// - Requires to be called by the exchange.
+    pub fn on_cast_shadow(
+        &mut self,
+        account_id: AccountId,
+        shadow_id: String,
+        amount: U128,
+        msg: String,
+    ) {
+        self.assert_ref_exchange();
+        let token_id = shadow_token_id(&shadow_id);
+        let asset = self.internal_unwrap_asset(&token_id);
+        assert!(
+            !asset.config.is_paused(PausableAction::Deposit),
+            "Deposits for this asset are not enabled"
+        );
+        self.internal_assert_allowlisted(&token_id, &account_id);
+        let amount = amount.0 * 10u128.pow(asset.config.extra_decimals as u32);
+        let actions = if msg.is_empty() {
+            vec![]
+        } else {
+            match TokenReceiverMsg::parse(&msg) {
+                TokenReceiverMsg::Execute { actions, .. } => actions,
+                _ => env::panic_str("Unexpected TokenReceiverMsg"),
+            }
+        };
+
+        let mut account = self.internal_unwrap_account(&account_id);
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        let shares = self.internal_deposit(&mut account, &token_id, amount);
+        self.internal_record_supplied_volume(&mut account, &token_id, amount);
+        self.internal_unwrap_asset(&token_id).assert_supply_cap();
+        self.internal_assert_canary_caps(&account);
+        events::emit::deposit(&account_id, amount, shares, &token_id);
+        self.internal_execute_without_prices(&account_id, &mut account, actions);
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Withdraws the pool shares from the supplied balance of the account, so the exchange can
+    /// remove the shadow record and unlock the shares. The collateral has to be decreased first.
+    /// - Panics if the supplied balance is not enough.
+    /// - Requires to be called by the exchange.
+    pub fn on_remove_shadow(
+        &mut self,
+        account_id: AccountId,
+        shadow_id: String,
+        amount: U128,
+        msg: String,
+    ) {
+        self.assert_ref_exchange();
+        assert!(msg.is_empty(), "The message is not supported");
+        let token_id = shadow_token_id(&shadow_id);
+        let asset = self.internal_unwrap_asset(&token_id);
+        let amount = amount.0 * 10u128.pow(asset.config.extra_decimals as u32);
+
+        let mut account = self.internal_unwrap_account(&account_id);
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        let (shares, amount) = self.internal_withdraw(
+            &mut account,
+            &AssetAmount {
+                token_id: token_id.clone(),
+                amount: Some(amount.into()),
+                max_amount: None,
+            },
+        );
+        self.internal_use_withdraw_timelock(&mut account, &token_id, amount);
+        events::emit::withdraw(&account_id, amount, shares, &token_id);
+        events::emit::withdraw_succeeded(&account_id, amount, &token_id);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Syncs the underlying tokens of a unit of the pool shares from the exchange for the given
+    /// listed pool shares, or all of them if not given. The fair prices of the pool shares are
+    /// computed from the synced tokens. Anyone can call it.
+    pub fn sync_ref_exchange_lp_token_infos(&mut self, token_ids: Option<Vec<TokenId>>) -> Promise {
+        let token_ids = token_ids.unwrap_or_else(|| {
+            self.asset_ids
+                .iter()
+                .filter(|token_id| parse_pool_id(token_id).is_some())
+                .collect()
+        });
+        let pool_ids = token_ids
+            .iter()
+            .map(|token_id| {
+                self.internal_unwrap_asset(token_id);
+                parse_pool_id(token_id).expect("The asset is not the pool shares")
+            })
+            .collect();
+        ext_ref_exchange::get_unit_lpt_assets(
+            pool_ids,
+            self.internal_unwrap_ref_exchange_id(),
+            NO_DEPOSIT,
+            GAS_FOR_GET_UNIT_LPT_ASSETS,
+        )
+        .then(ext_lp_collateral::after_get_unit_lpt_assets(
+            token_ids,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_GET_UNIT_LPT_ASSETS,
+        ))
+    }
+
+    /// Returns the last synced underlying tokens of a unit of the listed pool shares.
+    pub fn get_last_lp_token_infos(&self) -> HashMap<TokenId, UnitShareTokens> {
+        self.last_lp_token_infos.clone()
+    }
+}
+
+#[ext_contract(ext_ref_exchange)]
+trait ExtRefExchange {
+    fn mft_metadata(&self, token_id: String) -> FungibleTokenMetadata;
+
+    fn get_unit_lpt_assets(&self, pool_ids: Vec<u64>) -> HashMap<String, UnitShareTokens>;
+
+    fn get_pool_by_ids(&self, pool_ids: Vec<u64>) -> Vec<RefPoolInfo>;
+}
+
+/// Calls `on_burrow_liquidation` of the exchange with the `liquidate_share_amount` of the shadow
+/// shares in the token decimals.
+fn on_burrow_liquidation(
+    ref_exchange_id: AccountId,
+    liquidator_account_id: &AccountId,
+    liquidation_account_id: &AccountId,
+    shadow_id: String,
+    liquidate_share_amount: Balance,
+) -> Promise {
+    Promise::new(ref_exchange_id).function_call(
+        "on_burrow_liquidation".to_string(),
+        serde_json::json!({
+            "liquidator_account_id": liquidator_account_id,
+            "liquidation_account_id": liquidation_account_id,
+            "shadow_id": shadow_id,
+            "liquidate_share_amount": U128(liquidate_share_amount),
+            "min_token_amounts": Vec::<U128>::new(),
+        })
+        .to_string()
+        .into_bytes(),
+        NO_DEPOSIT,
+        GAS_FOR_ON_BURROW_LIQUIDATION,
+    )
+}
+
+#[ext_contract(ext_lp_collateral)]
+trait ExtLpCollateral {
+    fn after_get_unit_lpt_assets(&mut self, token_ids: Vec<TokenId>);
+
+    fn after_lp_liquidation(
+        &mut self,
+        liquidator_account_id: AccountId,
+        liquidation_account_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+    );
+}
+
+pub trait ExtLpCollateral {
+    fn after_get_unit_lpt_assets(&mut self, token_ids: Vec<TokenId>);
+
+    fn after_lp_liquidation(
+        &mut self,
+        liquidator_account_id: AccountId,
+        liquidation_account_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+    );
+}
+
+#[near_bindgen]
+impl ExtLpCollateral for Contract {
+    /// Stores the synced underlying tokens of the pool shares.
+    #[private]
+    fn after_get_unit_lpt_assets(&mut self, token_ids: Vec<TokenId>) {
+        let mut unit_lpt_assets = match env::promise_result(0) {
+            PromiseResult::Successful(value) => {
+                serde_json::from_slice::<HashMap<String, UnitShareTokens>>(&value).ok()
+            }
+            _ => None,
+        }
+        .expect("Failed to get the pool shares info");
+        for token_id in token_ids {
+            let pool_id = parse_pool_id(&token_id).unwrap();
+            let unit_share_tokens = unit_lpt_assets
+                .remove(&pool_id.to_string())
+                .expect("The pool shares info is missing");
+            assert!(
+                unit_share_tokens.tokens.len() >= 2,
+                "The pool should have at least 2 tokens"
+            );
+            self.last_lp_token_infos.insert(token_id, unit_share_tokens);
+        }
+    }
+
+    /// Returns the pool shares to the supplied balance of the liquidated account if the exchange
+    /// has failed to remove the liquidity, so the balance matches the shadow record that is
+    /// still on the exchange.
+    #[private]
+    fn after_lp_liquidation(
+        &mut self,
+        liquidator_account_id: AccountId,
+        liquidation_account_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+    ) {
+        self.internal_unlock_account(&liquidation_account_id);
+        if is_promise_success() {
+            events::emit::lp_shares_unwound(
+                &liquidator_account_id,
+                &liquidation_account_id,
+                amount.0,
+                &token_id,
+            );
+            return;
+        }
+        events::emit::lp_shares_unwind_failed(
+            &liquidator_account_id,
+            &liquidation_account_id,
+            amount.0,
+            &token_id,
+        );
+        if let Some(mut account) = self.internal_get_account(&liquidation_account_id, false) {
+            account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+            let shares = self.internal_deposit(&mut account, &token_id, amount.0);
+            events::emit::deposit(&liquidation_account_id, amount.0, shares, &token_id);
+            self.internal_account_apply_affected_farms(&mut account);
+            self.internal_set_account_in_callback(&liquidation_account_id, account);
+        }
+    }
+}
@@ -0,0 +1,83 @@
+use crate::*;
+use near_sdk::serde_json::json;
+
+const GAS_FOR_CLAIM_LP_FARM_REWARDS: Gas = Gas(Gas::ONE_TERA.0 * 150);
+
+impl Contract {
+    fn internal_unwrap_ref_boost_farm_id(&self) -> AccountId {
+        self.internal_config()
+            .ref_boost_farm_id
+            .expect("The boost farm is not set")
+    }
+
+    /// Deposits the farm rewards of the pool shares held via the shadow records to the supplied
+    /// balance of the account given in the transfer message.
+    pub fn internal_lp_farm_rewards_deposit(
+        &mut self,
+        sender_id: &AccountId,
+        token_id: &TokenId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) {
+        assert_eq!(
+            sender_id,
+            &self.internal_unwrap_ref_boost_farm_id(),
+            "Only the boost farm can deposit the farm rewards"
+        );
+        self.internal_assert_allowlisted(token_id, account_id);
+        let mut account = self.internal_unwrap_account(account_id);
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        let shares = self.internal_deposit(&mut account, token_id, amount);
+        self.internal_unwrap_asset(token_id).assert_supply_cap();
+        self.internal_assert_canary_caps(&account);
+        events::emit::deposit(account_id, amount, shares, token_id);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(account_id, account);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Claims the rewards of the Ref boost farm for the pool shares of the predecessor account that
+    /// are held via the shadow records. The boost farm transfers the claimed reward tokens back
+    /// with `ft_transfer_call` and the `LpFarmRewards` message, so the rewards are deposited to
+    /// the supplied balance of the account. The rewards in the tokens that are not listed are
+    /// refunded and stay claimable on the boost farm.
+    /// - Panics if the boost farm is not set, the asset is not the pool shares or the account
+    ///   doesn't have the pool shares.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn claim_lp_farm_rewards(&mut self, token_id: TokenId) -> Promise {
+        assert_one_yocto();
+        let ref_boost_farm_id = self.internal_unwrap_ref_boost_farm_id();
+        let pool_id = parse_pool_id(&token_id).expect("The asset is not the pool shares");
+        let account_id = env::predecessor_account_id();
+        let account = self.internal_unwrap_account(&account_id);
+        assert!(
+            account.get_supplied_shares(&token_id).0 > 0,
+            "The account doesn't have the pool shares"
+        );
+        let seed_id = format!("{}@{}", self.internal_unwrap_ref_exchange_id(), pool_id);
+        let msg = json!({
+            "LpFarmRewards": {
+                "account_id": account_id,
+            }
+        })
+        .to_string();
+        ext_ref_boost_farm::burrow_claim_rewards(
+            account_id,
+            seed_id,
+            msg,
+            ref_boost_farm_id,
+            NO_DEPOSIT,
+            GAS_FOR_CLAIM_LP_FARM_REWARDS,
+        )
+    }
+}
+
+#[ext_contract(ext_ref_boost_farm)]
+trait ExtRefBoostFarm {
+    /// Claims the rewards of the farmer for the seed and transfers them to the predecessor with
+    /// `ft_transfer_call` using the given message.
+    fn burrow_claim_rewards(&mut self, farmer_id: AccountId, seed_id: String, msg: String);
+}
@@ -0,0 +1,129 @@
+use crate::*;
+use near_sdk::json_types::U128;
+
+impl Contract {
+    /// Returns the largest amount of the asset that the account can borrow while staying healthy
+    /// with the minimum health of the borrows. The amount is limited by the available amount of
+    /// the asset and the maximum number of assets per account, and rounded down to the whole units
+    /// of the token.
+    pub fn internal_compute_max_borrow(
+        &self,
+        account: &Account,
+        token_id: &TokenId,
+        prices: &Prices,
+    ) -> Balance {
+        let asset = self.internal_unwrap_asset(token_id);
+        let is_paused = asset.config.is_paused(PausableAction::Borrow);
+        if is_paused || !asset.config.is_borrowable() || self.flash_loans.contains_key(token_id) {
+            return 0;
+        }
+        if !account.borrowed.contains_key(token_id)
+            && account.collateral.len() + account.borrowed.len()
+                >= self.internal_config().max_num_assets as _
+        {
+            return 0;
+        }
+
+        let (collateral_sum, borrowed_sum) = self.compute_adjusted_sums(account, prices);
+        // The collateral has to cover the borrowed sum with the minimum health of the borrows.
+        let collateral_sum = match self.internal_config().min_borrow_health_bps {
+            Some(min_borrow_health) => collateral_sum.div_ratio(min_borrow_health),
+            None => collateral_sum,
+        };
+        if collateral_sum <= borrowed_sum {
+            return 0;
+        }
+        let borrow_sum = (collateral_sum - borrowed_sum).mul_ratio(asset.config.volatility_ratio);
+
+        let multiplier = 10u128.pow(asset.config.extra_decimals as u32);
+        let unit_sum = BigDecimal::from_balance_price(
+            multiplier,
+            prices.get_unwrap(token_id),
+            asset.config.extra_decimals,
+        );
+        let amount = (borrow_sum / unit_sum).floor_u128() * multiplier;
+        std::cmp::min(amount, asset.available_amount())
+    }
+
+    /// Returns the largest amount of the collateral asset that the account can remove while
+    /// staying healthy with the minimum health of the borrows. Unless the whole collateral can be
+    /// removed, the amount is rounded down to the whole units of the token. The collateral with a
+    /// missing or zero price can't be removed while borrowing.
+    pub fn internal_compute_max_decrease_collateral(
+        &self,
+        account: &Account,
+        token_id: &TokenId,
+        prices: &Prices,
+    ) -> Balance {
+        let collateral_shares = match account.collateral.get(token_id) {
+            Some(shares) => *shares,
+            None => return 0,
+        };
+        let asset = self.internal_unwrap_asset(token_id);
+        let collateral_amount = asset
+            .supplied
+            .shares_to_amount(collateral_shares, Rounding::Down);
+        if account.borrowed.is_empty() {
+            return collateral_amount;
+        }
+        let price = match prices.get_valid(token_id) {
+            Some(price) => price,
+            None => return 0,
+        };
+
+        let (collateral_sum, borrowed_sum) = self.compute_adjusted_sums(account, prices);
+        let borrowed_sum = match self.internal_config().min_borrow_health_bps {
+            Some(min_borrow_health) => borrowed_sum.mul_ratio(min_borrow_health),
+            None => borrowed_sum,
+        };
+        if collateral_sum <= borrowed_sum {
+            return 0;
+        }
+        let decrease_sum = (collateral_sum - borrowed_sum).div_ratio(asset.config.volatility_ratio);
+
+        let multiplier = 10u128.pow(asset.config.extra_decimals as u32);
+        let unit_sum =
+            BigDecimal::from_balance_price(multiplier, price, asset.config.extra_decimals);
+        let amount = (decrease_sum / unit_sum).floor_u128() * multiplier;
+        std::cmp::min(amount, collateral_amount)
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the largest amount of the given asset that the account can borrow.
+    /// If `prices` are not given, the last recorded prices from the oracle are used.
+    pub fn get_max_borrow(
+        &self,
+        account_id: AccountId,
+        token_id: TokenId,
+        prices: Option<PriceData>,
+    ) -> U128 {
+        let account = self
+            .internal_get_account(&account_id, true)
+            .expect("Account is not registered");
+        let prices = prices
+            .map(|data| self.internal_prices_from_data(data))
+            .unwrap_or_else(|| self.internal_last_prices());
+        self.internal_compute_max_borrow(&account, &token_id, &prices)
+            .into()
+    }
+
+    /// Returns the largest amount of the given collateral asset that the account can remove.
+    /// If `prices` are not given, the last recorded prices from the oracle are used.
+    pub fn get_max_decrease_collateral(
+        &self,
+        account_id: AccountId,
+        token_id: TokenId,
+        prices: Option<PriceData>,
+    ) -> U128 {
+        let account = self
+            .internal_get_account(&account_id, true)
+            .expect("Account is not registered");
+        let prices = prices
+            .map(|data| self.internal_prices_from_data(data))
+            .unwrap_or_else(|| self.internal_last_prices());
+        self.internal_compute_max_decrease_collateral(&account, &token_id, &prices)
+            .into()
+    }
+}
@@ -0,0 +1,274 @@
+use crate::*;
+use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
+use near_sdk::json_types::U128;
+use near_sdk::PromiseOrValue;
+
+const GAS_FOR_MT_METADATA: Gas = Gas(Gas::ONE_TERA.0 * 5);
+const GAS_FOR_AFTER_MT_METADATA: Gas = Gas(Gas::ONE_TERA.0 * 10);
+
+/// A token of a multi token contract (NEP-245), e.g. a position token of the Ref v2 exchange.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct MtToken {
+    /// The account ID of the multi token contract.
+    pub contract_id: AccountId,
+    /// The token ID within the multi token contract.
+    pub token_id: String,
+}
+
+/// The part of the base token metadata of a multi token that is used for the listing.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct MtBaseTokenMetadata {
+    pub symbol: Option<String>,
+    pub decimals: Option<String>,
+}
+
+impl Contract {
+    /// Transfers the tokens of the asset in the token decimals to the receiver, using
+    /// `mt_transfer` for the multi token assets and `ft_transfer` otherwise.
+    pub fn internal_token_transfer(
+        &self,
+        receiver_id: &AccountId,
+        token_id: &TokenId,
+        ft_amount: Balance,
+        gas: Gas,
+    ) -> Promise {
+        match self.mt_tokens.get(token_id) {
+            Some(mt_token) => ext_multi_token::mt_transfer(
+                receiver_id.clone(),
+                mt_token.token_id,
+                ft_amount.into(),
+                mt_token.contract_id,
+                ONE_YOCTO,
+                gas,
+            ),
+            None => ext_fungible_token::ft_transfer(
+                receiver_id.clone(),
+                ft_amount.into(),
+                None,
+                token_id.clone(),
+                ONE_YOCTO,
+                gas,
+            ),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Adds an asset for a token of a multi token contract. The asset is added under the given
+    /// token_id after the extra decimals are checked against the decimals from the base token
+    /// metadata, the same way as in `add_asset`.
+    /// - Panics if the asset config is invalid.
+    /// - Panics if an asset with the given token_id or for the given multi token already exists.
+    /// - Panics if the token metadata can't be fetched or the extra decimals don't match.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn add_mt_asset(
+        &mut self,
+        token_id: AccountId,
+        contract_id: AccountId,
+        mt_token_id: String,
+        asset_config: AssetConfig,
+    ) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        asset_config.assert_valid();
+        assert!(
+            parse_pool_id(&token_id).is_none(),
+            "The pool shares can't be listed as a multi token"
+        );
+        assert!(
+            !self.asset_ids.contains(&token_id),
+            "The asset already exists"
+        );
+        let mt_token = MtToken {
+            contract_id,
+            token_id: mt_token_id,
+        };
+        assert!(
+            !self.mt_asset_ids.contains_key(&mt_token),
+            "The multi token is already listed"
+        );
+        ext_multi_token::mt_metadata_base_by_token_id(
+            vec![mt_token.token_id.clone()],
+            mt_token.contract_id.clone(),
+            NO_DEPOSIT,
+            GAS_FOR_MT_METADATA,
+        )
+        .then(ext_multi_token_listing::after_mt_metadata(
+            token_id,
+            mt_token,
+            asset_config,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_MT_METADATA,
+        ))
+    }
+
+    /// Receives the transfer from the multi token contract and deposits the tokens to the sender.
+    /// The message is empty to only deposit, the `TokenReceiverMsg::Execute` message to execute
+    /// the actions after the deposit, or `TokenReceiverMsg::DepositToReserve`. The entire transfer
+    /// is reverted if the actions fail.
+    /// - Panics if any of the tokens is not listed or its deposits are paused.
+    /// - Panics if the tokens are transferred on behalf of another owner.
+    /// - Requires to be called by the multi token contract.
+    pub fn mt_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_ids: Vec<AccountId>,
+        token_ids: Vec<String>,
+        amounts: Vec<U128>,
+        msg: String,
+    ) -> PromiseOrValue<Vec<U128>> {
+        assert_not_self_call();
+        let contract_id = env::predecessor_account_id();
+        assert_eq!(
+            token_ids.len(),
+            amounts.len(),
+            "The number of the tokens and the amounts don't match"
+        );
+        assert!(
+            previous_owner_ids
+                .iter()
+                .all(|owner_id| owner_id == &sender_id),
+            "The tokens have to be transferred by their owner"
+        );
+        let receiver_msg = if msg.is_empty() {
+            None
+        } else {
+            Some(TokenReceiverMsg::parse(&msg))
+        };
+        // The listed token ID, the inner amount and whether the transfer only repays the asset.
+        let asset_amounts: Vec<(TokenId, Balance, bool)> = token_ids
+            .into_iter()
+            .zip(amounts.iter())
+            .map(|(token_id, amount)| {
+                let mt_token = MtToken {
+                    contract_id: contract_id.clone(),
+                    token_id,
+                };
+                let token_id = self
+                    .mt_asset_ids
+                    .get(&mt_token)
+                    .expect("The token is not listed");
+                let asset = self.internal_unwrap_asset(&token_id);
+                let is_repayment = receiver_msg
+                    .as_ref()
+                    .map(|receiver_msg| receiver_msg.is_repayment(&token_id))
+                    .unwrap_or(false);
+                (
+                    token_id,
+                    amount.0 * 10u128.pow(asset.config.extra_decimals as u32),
+                    is_repayment,
+                )
+            })
+            .collect();
+        let unused_amounts = vec![U128(0); amounts.len()];
+
+        let actions = match receiver_msg {
+            None => vec![],
+            Some(TokenReceiverMsg::Execute { actions, .. }) => actions,
+            Some(TokenReceiverMsg::DepositToReserve) => {
+                for (token_id, amount, _) in asset_amounts {
+                    let mut asset = self.internal_unwrap_asset(&token_id);
+                    assert!(
+                        !asset.config.is_paused(PausableAction::Deposit),
+                        "Deposits for this asset are not enabled"
+                    );
+                    asset.reserved += amount;
+                    self.internal_set_asset(&token_id, asset);
+                    events::emit::deposit_to_reserve(&sender_id, amount, &token_id);
+                }
+                return PromiseOrValue::Value(unused_amounts);
+            }
+            Some(_) => env::panic_str("Unexpected TokenReceiverMsg"),
+        };
+
+        let mut account = self.internal_unwrap_account(&sender_id);
+        for (token_id, amount, is_repayment) in asset_amounts {
+            assert!(
+                !self
+                    .internal_unwrap_asset(&token_id)
+                    .config
+                    .is_paused(PausableAction::Deposit)
+                    || is_repayment,
+                "Deposits for this asset are not enabled"
+            );
+            assert!(
+                !self.internal_unwrap_asset(&token_id).config.reserve_only,
+                "The asset can only be deposited to the reserve"
+            );
+            if !is_repayment {
+                self.internal_assert_allowlisted(&token_id, &sender_id);
+            }
+            account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+            let shares = self.internal_deposit(&mut account, &token_id, amount);
+            self.internal_record_supplied_volume(&mut account, &token_id, amount);
+            if !is_repayment {
+                self.internal_unwrap_asset(&token_id).assert_supply_cap();
+                self.internal_assert_canary_caps(&account);
+            }
+            events::emit::deposit(&sender_id, amount, shares, &token_id);
+        }
+        self.internal_execute_without_prices(&sender_id, &mut account, actions);
+        self.internal_set_account(&sender_id, account);
+        PromiseOrValue::Value(unused_amounts)
+    }
+
+    /// Returns the multi token of the asset, or `None` if the asset is not a multi token.
+    pub fn get_mt_token(&self, token_id: AccountId) -> Option<MtToken> {
+        self.mt_tokens.get(&token_id)
+    }
+}
+
+#[ext_contract(ext_multi_token)]
+trait ExtMultiToken {
+    fn mt_transfer(&mut self, receiver_id: AccountId, token_id: String, amount: U128);
+
+    fn mt_metadata_base_by_token_id(&self, token_ids: Vec<String>) -> Vec<MtBaseTokenMetadata>;
+}
+
+#[ext_contract(ext_multi_token_listing)]
+trait ExtMultiTokenListing {
+    fn after_mt_metadata(
+        &mut self,
+        token_id: TokenId,
+        mt_token: MtToken,
+        asset_config: AssetConfig,
+    );
+}
+
+trait ExtMultiTokenListing {
+    fn after_mt_metadata(
+        &mut self,
+        token_id: TokenId,
+        mt_token: MtToken,
+        asset_config: AssetConfig,
+    );
+}
+
+#[near_bindgen]
+impl ExtMultiTokenListing for Contract {
+    /// Adds the asset if the extra decimals match the decimals from the base token metadata, and
+    /// maps the asset to the multi token.
+    #[private]
+    fn after_mt_metadata(
+        &mut self,
+        token_id: TokenId,
+        mt_token: MtToken,
+        asset_config: AssetConfig,
+    ) {
+        let metadata = parse_mt_metadata_result().expect("Failed to get the token metadata");
+        assert!(
+            !self.mt_asset_ids.contains_key(&mt_token),
+            "The multi token is already listed"
+        );
+        self.internal_list_asset(&token_id, asset_config, metadata);
+        self.mt_asset_ids.insert(&mt_token, &token_id);
+        self.mt_tokens.insert(&token_id, &mt_token);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::*;
+use near_sdk::json_types::Base64VecU8;
+
+/// The gas that is kept to complete the execution of the request after scheduling the call.
+const GAS_TO_COMPLETE_MULTISIG_EXECUTE: Gas = Gas(Gas::ONE_TERA.0 * 10);
+
+/// The time after which a request that wasn't executed expires.
+pub const MULTISIG_REQUEST_EXPIRATION_SEC: DurationSec = 7 * 24 * 60 * 60;
+
+/// A call of an owner method of this contract that has to be confirmed by the multisig members.
+/// Once executed, the contract calls the method on itself, so the contract account has to be the
+/// owner for the multisig to manage the contract.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct MultisigRequest {
+    pub request_id: u32,
+    /// The member that has made the request.
+    pub proposer_id: AccountId,
+    pub method_name: String,
+    /// The JSON arguments of the method.
+    pub args: Base64VecU8,
+    /// The members that have confirmed the request.
+    pub confirmations: Vec<AccountId>,
+    /// When the request expires and can no longer be confirmed or executed in nanoseconds.
+    #[serde(with = "u64_dec_format")]
+    pub expiration_timestamp: Timestamp,
+}
+
+impl MultisigRequest {
+    pub fn is_expired(&self) -> bool {
+        env::block_timestamp() >= self.expiration_timestamp
+    }
+}
+
+impl Contract {
+    fn assert_multisig_member(&self) -> AccountId {
+        let account_id = env::predecessor_account_id();
+        assert!(
+            self.internal_config()
+                .multisig_members
+                .contains(&account_id),
+            "Not a multisig member"
+        );
+        account_id
+    }
+
+    fn internal_unwrap_multisig_request(&self, request_id: u32) -> MultisigRequest {
+        self.multisig_requests
+            .get(&request_id)
+            .expect("Multisig request not found")
+    }
+
+    fn assert_multisig_method(&self, method_name: &str) {
+        assert!(
+            self.internal_config()
+                .multisig_methods
+                .iter()
+                .any(|name| name == method_name),
+            "The method can't be called with the multisig"
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Requests a call of the given method of this contract with the given arguments. The request
+    /// is confirmed by the predecessor and expires after `MULTISIG_REQUEST_EXPIRATION_SEC`.
+    /// Returns the ID of the request.
+    /// - Panics if the method is not in the `multisig_methods` of the config.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by a multisig member.
+    #[payable]
+    pub fn multisig_request(&mut self, method_name: String, args: Base64VecU8) -> u32 {
+        assert_one_yocto();
+        let account_id = self.assert_multisig_member();
+        self.assert_multisig_method(&method_name);
+        let request_id = self.next_multisig_request_id;
+        self.next_multisig_request_id += 1;
+        self.multisig_requests.insert(
+            &request_id,
+            &MultisigRequest {
+                request_id,
+                proposer_id: account_id.clone(),
+                method_name,
+                args,
+                confirmations: vec![account_id],
+                expiration_timestamp: env::block_timestamp()
+                    + sec_to_nano(MULTISIG_REQUEST_EXPIRATION_SEC),
+            },
+        );
+        request_id
+    }
+
+    /// Confirms the request by the predecessor.
+    /// - Panics if the request doesn't exist, has expired or is already confirmed by the
+    ///   predecessor.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by a multisig member.
+    #[payable]
+    pub fn multisig_confirm(&mut self, request_id: u32) {
+        assert_one_yocto();
+        let account_id = self.assert_multisig_member();
+        let mut request = self.internal_unwrap_multisig_request(request_id);
+        assert!(!request.is_expired(), "The multisig request has expired");
+        assert!(
+            !request.confirmations.contains(&account_id),
+            "The request is already confirmed by the account"
+        );
+        request.confirmations.push(account_id);
+        self.multisig_requests.insert(&request_id, &request);
+    }
+
+    /// Executes the request once it has enough confirmations from the current multisig members.
+    /// The contract calls the requested method on itself with one yoctoNEAR and the remaining gas.
+    /// - Panics if the request doesn't exist, has expired or doesn't have enough confirmations.
+    /// - Panics if the method is no longer in the `multisig_methods` of the config.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by a multisig member.
+    #[payable]
+    pub fn multisig_execute(&mut self, request_id: u32) -> Promise {
+        assert_one_yocto();
+        self.assert_multisig_member();
+        let config = self.internal_config();
+        let request = self
+            .multisig_requests
+            .remove(&request_id)
+            .expect("Multisig request not found");
+        assert!(!request.is_expired(), "The multisig request has expired");
+        self.assert_multisig_method(&request.method_name);
+        // The confirmations of the removed members are not counted.
+        let num_confirmations = request
+            .confirmations
+            .iter()
+            .filter(|account_id| config.multisig_members.contains(account_id))
+            .count();
+        assert!(
+            config.multisig_threshold > 0
+                && num_confirmations >= config.multisig_threshold as usize,
+            "Not enough confirmations"
+        );
+        // The private callbacks don't accept the attached deposit, so they can't be called.
+        Promise::new(env::current_account_id()).function_call(
+            request.method_name,
+            request.args.into(),
+            ONE_YOCTO,
+            env::prepaid_gas() - env::used_gas() - GAS_TO_COMPLETE_MULTISIG_EXECUTE,
+        )
+    }
+
+    /// Cancels the request. The proposer can cancel the request at any time, and the other
+    /// members can only remove the expired requests.
+    /// - Panics if the request doesn't exist.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by a multisig member.
+    #[payable]
+    pub fn multisig_cancel(&mut self, request_id: u32) {
+        assert_one_yocto();
+        let account_id = self.assert_multisig_member();
+        let request = self
+            .multisig_requests
+            .remove(&request_id)
+            .expect("Multisig request not found");
+        assert!(
+            request.proposer_id == account_id || request.is_expired(),
+            "Only the proposer can cancel the request before it expires"
+        );
+    }
+
+    /// Returns the pending multisig requests.
+    pub fn get_multisig_requests(&self) -> Vec<MultisigRequest> {
+        self.multisig_requests.values().collect()
+    }
+}
@@ -0,0 +1,169 @@
+use crate::*;
+use near_sdk::is_promise_success;
+use near_sdk::json_types::U128;
+
+const GAS_FOR_NEAR_DEPOSIT: Gas = Gas(Gas::ONE_TERA.0 * 10);
+const GAS_FOR_AFTER_NEAR_DEPOSIT: Gas = Gas(Gas::ONE_TERA.0 * 30);
+const GAS_FOR_NEAR_WITHDRAW: Gas = Gas(Gas::ONE_TERA.0 * 10);
+const GAS_FOR_AFTER_NEAR_WITHDRAW: Gas = Gas(Gas::ONE_TERA.0 * 20);
+
+#[ext_contract(ext_wrap_near)]
+trait ExtWrapNear {
+    fn near_deposit(&mut self);
+
+    fn near_withdraw(&mut self, amount: U128);
+}
+
+impl Contract {
+    fn internal_unwrap_wnear_token_id(&self) -> AccountId {
+        self.internal_config()
+            .wnear_token_id
+            .expect("The native NEAR is not enabled")
+    }
+
+    /// Unwraps the withdrawn wNEAR with `near_withdraw` and transfers the native NEAR to the
+    /// account. The amount becomes claimable as wNEAR if the unwrapping fails.
+    pub fn internal_near_withdraw(
+        &mut self,
+        account_id: &AccountId,
+        token_id: &TokenId,
+        amount: Balance,
+    ) -> Promise {
+        assert_eq!(
+            token_id,
+            &self.internal_unwrap_wnear_token_id(),
+            "Only wNEAR can be withdrawn as the native NEAR"
+        );
+        let asset = self.internal_unwrap_asset(token_id);
+        let ft_amount = amount / 10u128.pow(asset.config.extra_decimals as u32);
+        self.internal_lock_account(account_id);
+        ext_wrap_near::near_withdraw(
+            ft_amount.into(),
+            token_id.clone(),
+            ONE_YOCTO,
+            GAS_FOR_NEAR_WITHDRAW,
+        )
+        .then(ext_native_near::after_near_withdraw(
+            account_id.clone(),
+            token_id.clone(),
+            amount.into(),
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_NEAR_WITHDRAW,
+        ))
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Wraps the attached NEAR with `near_deposit` and deposits it to the supplied balance of the
+    /// account as wNEAR. The deposit is checked with the same checks as the wNEAR transfers, but
+    /// it's only credited once the wrapping succeeds, and the account is locked until then. If the
+    /// wrapping fails, the NEAR is refunded.
+    /// - Panics if the account doesn't have enough storage balance for a new supplied position.
+    /// - Panics if the native NEAR is not enabled, the attached deposit is 0 or the deposits of
+    ///   wNEAR are not enabled.
+    /// - Requires the account to be registered.
+    #[payable]
+    pub fn deposit_near(&mut self) -> Promise {
+        assert_not_self_call();
+        let account_id = env::predecessor_account_id();
+        let token_id = self.internal_unwrap_wnear_token_id();
+        let ft_amount = env::attached_deposit();
+        assert!(ft_amount > 0, "The attached deposit can't be 0");
+        let asset = self.internal_unwrap_asset(&token_id);
+        assert!(
+            !asset.config.is_paused(PausableAction::Deposit) && !asset.config.reserve_only,
+            "The deposits of wNEAR are not enabled"
+        );
+        self.internal_assert_allowlisted(&token_id, &account_id);
+        self.internal_assert_account_not_locked(&account_id);
+        let amount = ft_amount * 10u128.pow(asset.config.extra_decimals as u32);
+        if let Some(supply_cap) = asset.config.supply_cap {
+            assert!(
+                asset.supplied.balance + amount <= supply_cap.0,
+                "The supply cap of the asset is exceeded"
+            );
+        }
+        let account = self.internal_unwrap_account(&account_id);
+        // The callback can't fail, so the storage for a new supplied position is checked here.
+        let num_new_positions = u32::from(!account.supplied.contains_key(&token_id));
+        self.internal_assert_storage_for_new_positions(&account, num_new_positions);
+        self.internal_assert_canary_caps_with_pending(&account, Some((&token_id, amount)));
+
+        self.internal_lock_account(&account_id);
+        ext_wrap_near::near_deposit(token_id.clone(), ft_amount, GAS_FOR_NEAR_DEPOSIT).then(
+            ext_native_near::after_near_deposit(
+                account_id,
+                token_id,
+                amount.into(),
+                env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_AFTER_NEAR_DEPOSIT,
+            ),
+        )
+    }
+}
+
+#[ext_contract(ext_native_near)]
+trait ExtNativeNear {
+    fn after_near_deposit(&mut self, account_id: AccountId, token_id: TokenId, amount: U128);
+
+    fn after_near_withdraw(&mut self, account_id: AccountId, token_id: TokenId, amount: U128);
+}
+
+trait ExtNativeNear {
+    fn after_near_deposit(&mut self, account_id: AccountId, token_id: TokenId, amount: U128);
+
+    fn after_near_withdraw(&mut self, account_id: AccountId, token_id: TokenId, amount: U128);
+}
+
+#[near_bindgen]
+impl ExtNativeNear for Contract {
+    /// Unlocks the account and credits the deposit once the wrapping has succeeded, or refunds the
+    /// NEAR if it has failed. The caps were checked when the deposit was made.
+    #[private]
+    fn after_near_deposit(&mut self, account_id: AccountId, token_id: TokenId, amount: U128) {
+        self.internal_unlock_account(&account_id);
+        if !is_promise_success() {
+            let asset = self.internal_unwrap_asset(&token_id);
+            let ft_amount = amount.0 / 10u128.pow(asset.config.extra_decimals as u32);
+            Promise::new(account_id).transfer(ft_amount);
+            return;
+        }
+        let mut account = match self.internal_get_account(&account_id, false) {
+            Some(account) => account,
+            None => {
+                self.internal_add_unregistered_withdraw_claim(&account_id, &token_id, amount.0);
+                return;
+            }
+        };
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        let shares = self.internal_deposit(&mut account, &token_id, amount.0);
+        self.internal_record_supplied_volume(&mut account, &token_id, amount.0);
+        events::emit::deposit(&account_id, amount.0, shares, &token_id);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account_in_callback(&account_id, account);
+    }
+
+    /// Transfers the unwrapped NEAR to the account, or records the amount as claimable wNEAR if
+    /// the unwrapping has failed.
+    #[private]
+    fn after_near_withdraw(&mut self, account_id: AccountId, token_id: TokenId, amount: U128) {
+        self.internal_unlock_account(&account_id);
+        if is_promise_success() {
+            let asset = self.internal_unwrap_asset(&token_id);
+            let ft_amount = amount.0 / 10u128.pow(asset.config.extra_decimals as u32);
+            Promise::new(account_id.clone()).transfer(ft_amount);
+            events::emit::withdraw_succeeded(&account_id, amount.0, &token_id);
+            return;
+        }
+        if let Some(mut account) = self.internal_get_account(&account_id, false) {
+            self.internal_add_withdraw_claim(&mut account, &token_id, amount.0);
+            self.internal_set_account_in_callback(&account_id, account);
+        } else {
+            self.internal_add_unregistered_withdraw_claim(&account_id, &token_id, amount.0);
+        }
+        events::emit::withdraw_failed(&account_id, amount.0, &token_id);
+    }
+}
@@ -0,0 +1,535 @@
+use crate::*;
+use near_sdk::json_types::U128;
+use near_sdk::{is_promise_success, PromiseOrValue};
+
+const GAS_FOR_NFT_TRANSFER: Gas = Gas(Gas::ONE_TERA.0 * 20);
+const GAS_FOR_AFTER_NFT_TRANSFER: Gas = Gas(Gas::ONE_TERA.0 * 20);
+
+/// The minimum increment of the bid over the highest bid of the NFT auction (multiplied by 10000).
+const NFT_AUCTION_MIN_BID_INCREMENT: u32 = 500;
+/// The part of the winning bid of the NFT auction paid to the liquidator that started the auction
+/// (multiplied by 10000).
+const NFT_AUCTION_LIQUIDATOR_REWARD: u32 = 500;
+
+/// A token of a whitelisted NFT collection (NEP-171).
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftId {
+    /// The account ID of the NFT contract.
+    pub collection_id: AccountId,
+    /// The token ID within the NFT contract.
+    pub token_id: String,
+}
+
+/// The owner-configured parameters of a whitelisted NFT collection.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftCollectionConfig {
+    /// The listed asset the floor price is denominated in. The bids of the auctions are made in
+    /// this asset.
+    pub quote_token_id: TokenId,
+    /// The account that reports the floor price of the collection.
+    pub oracle_id: AccountId,
+    /// The part of the floor price value counted as the collateral (multiplied by 10000). E.g.
+    /// 4000 means the haircut is 60%.
+    pub volatility_ratio: u32,
+    /// The floor price older than this is not counted as the collateral.
+    pub max_staleness_sec: DurationSec,
+    /// The duration of the auctions of the NFTs of the liquidated accounts.
+    pub auction_duration_sec: DurationSec,
+}
+
+impl NftCollectionConfig {
+    pub fn assert_valid(&self) {
+        assert!(
+            self.volatility_ratio > 0 && self.volatility_ratio <= MAX_RATIO,
+            "Invalid volatility ratio"
+        );
+        assert!(self.max_staleness_sec > 0, "The max staleness can't be 0");
+        assert!(
+            self.auction_duration_sec > 0,
+            "The auction duration can't be 0"
+        );
+    }
+}
+
+/// A whitelisted NFT collection with the last reported floor price.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftCollection {
+    pub config: NftCollectionConfig,
+    /// The floor price in the quote token decimals.
+    #[serde(with = "u128_dec_format")]
+    pub floor_price: Balance,
+    /// When the floor price was reported, 0 if it was never reported.
+    #[serde(with = "u64_dec_format")]
+    pub floor_price_timestamp: Timestamp,
+}
+
+/// The auction of the NFT from the collateral of a liquidated account. The NFT stays in the
+/// collateral until the auction is settled, and the highest bid is held by the contract until
+/// then.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct NftAuction {
+    /// The liquidated account that receives the proceeds.
+    pub account_id: AccountId,
+    /// The liquidator that started the auction and receives the reward from the winning bid.
+    pub liquidator_id: AccountId,
+    #[serde(with = "u64_dec_format")]
+    pub end_timestamp: Timestamp,
+    pub highest_bidder_id: Option<AccountId>,
+    /// The highest bid in the quote asset with the extra decimals.
+    #[serde(with = "u128_dec_format")]
+    pub highest_bid: Balance,
+}
+
+impl Contract {
+    fn internal_unwrap_nft_collection(&self, collection_id: &AccountId) -> NftCollection {
+        self.nft_collections
+            .get(collection_id)
+            .expect("The NFT collection is not whitelisted")
+    }
+
+    pub fn internal_get_nft_collateral(&self, account_id: &AccountId) -> Vec<NftId> {
+        self.nft_collateral.get(account_id).unwrap_or_default()
+    }
+
+    /// Saves the NFT collateral and records the storage change under the account storage
+    /// tracker.
+    fn internal_set_nft_collateral(&mut self, account: &mut Account, nfts: Vec<NftId>) {
+        account.storage_tracker.start();
+        if nfts.is_empty() {
+            self.nft_collateral.remove(&account.account_id);
+        } else {
+            self.nft_collateral.insert(&account.account_id, &nfts);
+        }
+        account.storage_tracker.stop();
+    }
+
+    fn internal_add_nft_collateral(&mut self, account: &mut Account, nft: NftId) {
+        let mut nfts = self.internal_get_nft_collateral(&account.account_id);
+        nfts.push(nft);
+        self.internal_set_nft_collateral(account, nfts);
+    }
+
+    fn internal_remove_nft_collateral(&mut self, account: &mut Account, nft: &NftId) {
+        let mut nfts = self.internal_get_nft_collateral(&account.account_id);
+        let index = nfts
+            .iter()
+            .position(|id| id == nft)
+            .expect("The NFT is not in the collateral");
+        nfts.remove(index);
+        self.internal_set_nft_collateral(account, nfts);
+    }
+
+    /// Returns the value of the floor price of the collection, or `None` if the floor price is
+    /// stale or the quote asset has no price.
+    fn internal_nft_floor_value(
+        &self,
+        collection: &NftCollection,
+        prices: &Prices,
+    ) -> Option<BigDecimal> {
+        let max_staleness = to_nano(collection.config.max_staleness_sec);
+        if collection.floor_price_timestamp == 0
+            || env::block_timestamp() > collection.floor_price_timestamp + max_staleness
+        {
+            return None;
+        }
+        let price = prices.get(&collection.config.quote_token_id)?;
+        let extra_decimals = self
+            .internal_get_asset(&collection.config.quote_token_id)?
+            .config
+            .extra_decimals;
+        Some(BigDecimal::from_balance_price(
+            collection.floor_price * 10u128.pow(extra_decimals as u32),
+            price,
+            extra_decimals,
+        ))
+    }
+
+    /// Returns the adjusted sum of the NFT collateral of the account. The NFTs of the collections
+    /// with the stale floor price or without the price of the quote asset are not counted.
+    pub fn compute_nft_collateral_sum(
+        &self,
+        account_id: &AccountId,
+        prices: &Prices,
+    ) -> BigDecimal {
+        self.internal_get_nft_collateral(account_id)
+            .iter()
+            .filter_map(|nft| {
+                let collection = self.nft_collections.get(&nft.collection_id)?;
+                self.internal_nft_floor_value(&collection, prices)
+                    .map(|value| value.mul_ratio(collection.config.volatility_ratio))
+            })
+            .fold(BigDecimal::zero(), |sum, value| sum + value)
+    }
+
+    /// Transfers the NFT to the receiver. The NFT is returned to the collateral of the receiver if
+    /// the transfer fails.
+    fn internal_nft_transfer(&mut self, receiver_id: &AccountId, nft: NftId) -> Promise {
+        self.internal_lock_account(receiver_id);
+        ext_nft::nft_transfer(
+            receiver_id.clone(),
+            nft.token_id.clone(),
+            None,
+            None,
+            nft.collection_id.clone(),
+            ONE_YOCTO,
+            GAS_FOR_NFT_TRANSFER,
+        )
+        .then(ext_nft_collateral::after_nft_transfer(
+            receiver_id.clone(),
+            nft,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_NFT_TRANSFER,
+        ))
+    }
+
+    /// Removes the NFT from the collateral of the account and transfers it to the account. The
+    /// caller has to check the health of the account.
+    pub fn internal_withdraw_nft(&mut self, account: &mut Account, nft: NftId) {
+        assert!(
+            self.nft_auctions.get(&nft).is_none(),
+            "The NFT is being auctioned"
+        );
+        self.internal_remove_nft_collateral(account, &nft);
+        self.internal_nft_transfer(&account.account_id, nft);
+    }
+
+    /// Starts the auction of the NFT from the collateral of the liquidatable account. The NFT
+    /// stays in the collateral until the auction is settled, so it's still counted while the
+    /// auction is in progress.
+    pub fn internal_start_nft_auction(
+        &mut self,
+        prices: &Prices,
+        liquidator_id: &AccountId,
+        liquidation_account_id: &AccountId,
+        nft: NftId,
+    ) {
+        let collection = self.internal_unwrap_nft_collection(&nft.collection_id);
+        assert!(
+            self.internal_get_nft_collateral(liquidation_account_id)
+                .contains(&nft),
+            "The NFT is not in the collateral"
+        );
+        assert!(
+            self.nft_auctions.get(&nft).is_none(),
+            "The NFT is already being auctioned"
+        );
+        let liquidation_account = self.internal_unwrap_account(liquidation_account_id);
+        let (collateral_sum, borrowed_sum) =
+            self.compute_adjusted_sums(&liquidation_account, prices);
+        assert!(
+            collateral_sum < borrowed_sum,
+            "The account is not liquidatable"
+        );
+        self.nft_auctions.insert(
+            &nft,
+            &NftAuction {
+                account_id: liquidation_account_id.clone(),
+                liquidator_id: liquidator_id.clone(),
+                end_timestamp: env::block_timestamp()
+                    + to_nano(collection.config.auction_duration_sec),
+                highest_bidder_id: None,
+                highest_bid: 0,
+            },
+        );
+    }
+
+    /// Places the bid of the transferred amount on the NFT auction. The previous highest bid is
+    /// transferred back to its bidder.
+    pub fn internal_nft_bid(
+        &mut self,
+        sender_id: &AccountId,
+        token_id: &TokenId,
+        nft: NftId,
+        amount: Balance,
+    ) {
+        let collection = self.internal_unwrap_nft_collection(&nft.collection_id);
+        assert_eq!(
+            token_id, &collection.config.quote_token_id,
+            "The bid has to be made in the quote asset of the collection"
+        );
+        let mut auction = self
+            .nft_auctions
+            .get(&nft)
+            .expect("The auction doesn't exist");
+        assert!(
+            env::block_timestamp() < auction.end_timestamp,
+            "The auction has ended"
+        );
+        assert_ne!(sender_id, &auction.account_id, "Can't bid on your own NFT");
+        self.internal_unwrap_account(sender_id);
+        let min_bid = if auction.highest_bidder_id.is_some() {
+            auction.highest_bid + ratio(auction.highest_bid, NFT_AUCTION_MIN_BID_INCREMENT)
+        } else {
+            let extra_decimals = self.internal_unwrap_asset(token_id).config.extra_decimals;
+            ratio(
+                collection.floor_price * 10u128.pow(extra_decimals as u32),
+                collection.config.volatility_ratio,
+            )
+        };
+        assert!(amount >= min_bid, "The bid is lower than {}", min_bid);
+        if let Some(previous_bidder_id) = auction.highest_bidder_id.take() {
+            self.internal_ft_transfer(&previous_bidder_id, token_id, auction.highest_bid);
+        }
+        auction.highest_bidder_id = Some(sender_id.clone());
+        auction.highest_bid = amount;
+        self.nft_auctions.insert(&nft, &auction);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Whitelists the NFT collection or updates its config. The last reported floor price is
+    /// kept for the update.
+    /// - Panics if the config is invalid or the quote asset doesn't exist.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn set_nft_collection(&mut self, collection_id: AccountId, config: NftCollectionConfig) {
+        assert_one_yocto();
+        self.assert_owner();
+        config.assert_valid();
+        self.internal_unwrap_asset(&config.quote_token_id);
+        let collection = match self.nft_collections.get(&collection_id) {
+            Some(collection) => NftCollection {
+                config,
+                ..collection
+            },
+            None => NftCollection {
+                config,
+                floor_price: 0,
+                floor_price_timestamp: 0,
+            },
+        };
+        self.nft_collections.insert(&collection_id, &collection);
+    }
+
+    /// Removes the NFT collection from the whitelist. The NFTs of the collection can still be
+    /// withdrawn, but they are no longer counted as the collateral.
+    /// - Panics if an auction of the collection is in progress.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn remove_nft_collection(&mut self, collection_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        assert!(
+            self.nft_auctions
+                .keys()
+                .all(|nft| nft.collection_id != collection_id),
+            "An auction of the collection is in progress"
+        );
+        self.nft_collections
+            .remove(&collection_id)
+            .expect("The NFT collection is not whitelisted");
+    }
+
+    /// Reports the floor price of the collection in the quote token decimals.
+    /// - Requires to be called by the floor price oracle of the collection.
+    pub fn report_nft_floor_price(&mut self, collection_id: AccountId, floor_price: U128) {
+        let mut collection = self.internal_unwrap_nft_collection(&collection_id);
+        assert_eq!(
+            env::predecessor_account_id(),
+            collection.config.oracle_id,
+            "Not the floor price oracle of the collection"
+        );
+        collection.floor_price = floor_price.0;
+        collection.floor_price_timestamp = env::block_timestamp();
+        self.nft_collections.insert(&collection_id, &collection);
+    }
+
+    /// Receives the NFT of a whitelisted collection (NEP-171) and adds it to the collateral of the
+    /// sender. The message should be empty.
+    /// - Panics if the NFT is transferred on behalf of another owner.
+    /// - Requires to be called by the NFT contract of the whitelisted collection.
+    pub fn nft_on_transfer(
+        &mut self,
+        sender_id: AccountId,
+        previous_owner_id: AccountId,
+        token_id: String,
+        msg: String,
+    ) -> PromiseOrValue<bool> {
+        assert_not_self_call();
+        let collection_id = env::predecessor_account_id();
+        self.internal_unwrap_nft_collection(&collection_id);
+        assert_eq!(
+            sender_id, previous_owner_id,
+            "The NFT has to be transferred by its owner"
+        );
+        assert!(msg.is_empty(), "Unexpected message");
+        let mut account = self.internal_unwrap_account(&sender_id);
+        self.internal_add_nft_collateral(
+            &mut account,
+            NftId {
+                collection_id,
+                token_id,
+            },
+        );
+        self.internal_set_account(&sender_id, account);
+        PromiseOrValue::Value(false)
+    }
+
+    /// Settles the ended NFT auction. The NFT is removed from the collateral of the liquidated
+    /// account and transferred to the highest bidder. The liquidator that started the auction
+    /// receives the reward part of the bid to the supplied balance. The rest of the bid repays the
+    /// borrowed quote asset of the liquidated account, and the remainder is added to its
+    /// collateral. Without bids the NFT stays in the collateral of the liquidated account. Anyone
+    /// can call it.
+    /// - Panics if the auction doesn't exist or hasn't ended.
+    pub fn settle_nft_auction(&mut self, collection_id: AccountId, token_id: String) {
+        let nft = NftId {
+            collection_id,
+            token_id,
+        };
+        let auction = self
+            .nft_auctions
+            .get(&nft)
+            .expect("The auction doesn't exist");
+        assert!(
+            env::block_timestamp() >= auction.end_timestamp,
+            "The auction hasn't ended"
+        );
+        self.nft_auctions.remove(&nft);
+        let account_id = auction.account_id;
+        let highest_bidder_id = match auction.highest_bidder_id {
+            Some(highest_bidder_id) => highest_bidder_id,
+            None => return,
+        };
+        let quote_token_id = self
+            .internal_unwrap_nft_collection(&nft.collection_id)
+            .config
+            .quote_token_id;
+        // The account with the NFT collateral can't be unregistered.
+        let mut account = self.internal_unwrap_account(&account_id);
+        self.internal_remove_nft_collateral(&mut account, &nft);
+        self.internal_nft_transfer(&highest_bidder_id, nft);
+
+        let reward = ratio(auction.highest_bid, NFT_AUCTION_LIQUIDATOR_REWARD);
+        let liquidator_id = auction.liquidator_id;
+        match self.internal_get_account(&liquidator_id, false) {
+            Some(mut liquidator_account) => {
+                liquidator_account.add_affected_farm(FarmId::Supplied(quote_token_id.clone()));
+                let shares =
+                    self.internal_deposit(&mut liquidator_account, &quote_token_id, reward);
+                events::emit::deposit(&liquidator_id, reward, shares, &quote_token_id);
+                self.internal_account_apply_affected_farms(&mut liquidator_account);
+                self.internal_set_account(&liquidator_id, liquidator_account);
+            }
+            None => {
+                self.internal_add_unregistered_withdraw_claim(
+                    &liquidator_id,
+                    &quote_token_id,
+                    reward,
+                );
+            }
+        }
+
+        let amount = auction.highest_bid - reward;
+        account.add_affected_farm(FarmId::Supplied(quote_token_id.clone()));
+        let proceeds_shares = self.internal_deposit(&mut account, &quote_token_id, amount);
+        events::emit::deposit(&account_id, amount, proceeds_shares, &quote_token_id);
+        if account.borrowed.contains_key(&quote_token_id) {
+            let mut account_asset = account.internal_unwrap_asset(&quote_token_id);
+            account.add_affected_farm(FarmId::Borrowed(quote_token_id.clone()));
+            let (shares, amount) = self.internal_repay(
+                &mut account_asset,
+                &mut account,
+                &AssetAmount {
+                    token_id: quote_token_id.clone(),
+                    amount: None,
+                    max_amount: None,
+                },
+            );
+            events::emit::repay(&account_id, amount, shares, &quote_token_id);
+            account.internal_set_asset(&quote_token_id, account_asset);
+        }
+        // The remainder of the proceeds replaces the sold NFT in the collateral.
+        let mut account_asset = account.internal_get_asset_or_default(&quote_token_id);
+        let shares: Shares = std::cmp::min(account_asset.shares.0, proceeds_shares.0).into();
+        if shares.0 > 0 {
+            account_asset.withdraw_shares(shares);
+            account.internal_set_asset(&quote_token_id, account_asset);
+            account.increase_collateral(&quote_token_id, shares);
+            let mut asset = self.internal_unwrap_asset(&quote_token_id);
+            asset.collateral_shares.0 += shares.0;
+            let amount = asset.supplied.shares_to_amount(shares, Rounding::Down);
+            self.internal_set_asset(&quote_token_id, asset);
+            events::emit::increase_collateral(&account_id, amount, shares, &quote_token_id);
+        }
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Returns the whitelisted NFT collection, if any.
+    pub fn get_nft_collection(&self, collection_id: AccountId) -> Option<NftCollection> {
+        self.nft_collections.get(&collection_id)
+    }
+
+    /// Returns the NFTs in the collateral of the account.
+    pub fn get_nft_collateral(&self, account_id: AccountId) -> Vec<NftId> {
+        self.internal_get_nft_collateral(&account_id)
+    }
+
+    /// Returns the auction of the NFT, if any.
+    pub fn get_nft_auction(
+        &self,
+        collection_id: AccountId,
+        token_id: String,
+    ) -> Option<NftAuction> {
+        self.nft_auctions.get(&NftId {
+            collection_id,
+            token_id,
+        })
+    }
+}
+
+#[ext_contract(ext_nft)]
+trait ExtNft {
+    fn nft_transfer(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: String,
+        approval_id: Option<u64>,
+        memo: Option<String>,
+    );
+}
+
+#[ext_contract(ext_nft_collateral)]
+trait ExtNftCollateral {
+    fn after_nft_transfer(&mut self, account_id: AccountId, nft: NftId);
+}
+
+trait ExtNftCollateral {
+    fn after_nft_transfer(&mut self, account_id: AccountId, nft: NftId);
+}
+
+#[near_bindgen]
+impl ExtNftCollateral for Contract {
+    /// Returns the NFT to the collateral of the account if the transfer failed.
+    #[private]
+    fn after_nft_transfer(&mut self, account_id: AccountId, nft: NftId) {
+        self.internal_unlock_account(&account_id);
+        if is_promise_success() {
+            return;
+        }
+        match self.internal_get_account(&account_id, false) {
+            Some(mut account) => {
+                self.internal_add_nft_collateral(&mut account, nft);
+                self.internal_set_account_in_callback(&account_id, account);
+            }
+            None => log!(
+                "The NFT {}:{} is kept by the contract",
+                nft.collection_id,
+                nft.token_id
+            ),
+        }
+    }
+}
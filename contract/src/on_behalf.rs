@@ -0,0 +1,78 @@
+use crate::*;
+
+impl Contract {
+    /// Repays the borrowed balance of the account with the tokens transferred by the sender. Only
+    /// the debt of the transferred asset can be repaid, so the health factor of the account can
+    /// only improve. Returns the amount in the token decimals that exceeds the debt, which is
+    /// refunded to the sender.
+    pub fn internal_repay_on_behalf(
+        &mut self,
+        sender_id: &AccountId,
+        token_id: &TokenId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) -> Balance {
+        let mut account = self.internal_unwrap_account(account_id);
+        let borrowed_shares = *account
+            .borrowed
+            .get(token_id)
+            .expect("The account doesn't borrow the asset");
+        let asset = self.internal_unwrap_asset(token_id);
+        let multiplier = 10u128.pow(asset.config.extra_decimals as u32);
+        let debt = asset
+            .borrowed
+            .shares_to_amount(borrowed_shares, Rounding::Up);
+        // The repaid amount is rounded up to the whole units of the token, and the remainder
+        // stays in the supplied balance of the account.
+        let ft_amount = amount / multiplier;
+        let used_ft_amount = std::cmp::min(ft_amount, debt.div_ceil(multiplier));
+        let amount = used_ft_amount * multiplier;
+
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        let shares = self.internal_deposit(&mut account, token_id, amount);
+        self.internal_record_supplied_volume(&mut account, token_id, amount);
+        events::emit::deposit(account_id, amount, shares, token_id);
+        events::emit::repay_on_behalf(sender_id, account_id, amount, token_id);
+        self.internal_execute_without_prices(
+            account_id,
+            &mut account,
+            vec![Action::Repay(AssetAmount {
+                token_id: token_id.clone(),
+                amount: None,
+                max_amount: Some(amount.into()),
+            })],
+        );
+        self.internal_set_account(account_id, account);
+        ft_amount - used_ft_amount
+    }
+
+    /// Deposits the tokens transferred by the sender to the collateral of the account. Only the
+    /// collateral of the transferred asset is increased, so the health factor of the account can
+    /// only improve. The account has to use the asset as collateral already, so the third parties
+    /// can't add new collateral positions to it, e.g. the dust of an unwanted asset that takes
+    /// the storage or changes the isolation mode of the account.
+    pub fn internal_deposit_collateral_for(
+        &mut self,
+        sender_id: &AccountId,
+        token_id: &TokenId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) {
+        let actions = self.internal_deposit_to_collateral_actions(token_id, amount);
+        self.internal_assert_allowlisted(token_id, account_id);
+        let mut account = self.internal_unwrap_account(account_id);
+        assert!(
+            account.collateral.contains_key(token_id),
+            "The account doesn't use the asset as collateral"
+        );
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        let shares = self.internal_deposit(&mut account, token_id, amount);
+        self.internal_record_supplied_volume(&mut account, token_id, amount);
+        self.internal_unwrap_asset(token_id).assert_supply_cap();
+        self.internal_assert_canary_caps(&account);
+        events::emit::deposit(account_id, amount, shares, token_id);
+        events::emit::deposit_collateral_for(sender_id, account_id, amount, token_id);
+        self.internal_execute_without_prices(account_id, &mut account, actions);
+        self.internal_set_account(account_id, account);
+    }
+}
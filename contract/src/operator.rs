@@ -0,0 +1,103 @@
+use crate::*;
+
+impl Contract {
+    pub fn internal_get_approved_operators(&self, account_id: &AccountId) -> Vec<AccountId> {
+        self.operator_approvals.get(account_id).unwrap_or_default()
+    }
+
+    /// Saves the approved operators and records the storage change under the account storage
+    /// tracker.
+    pub fn internal_set_approved_operators(
+        &mut self,
+        account: &mut Account,
+        operators: Vec<AccountId>,
+    ) {
+        account.storage_tracker.start();
+        if operators.is_empty() {
+            self.operator_approvals.remove(&account.account_id);
+        } else {
+            self.operator_approvals
+                .insert(&account.account_id, &operators);
+        }
+        account.storage_tracker.stop();
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Executes the actions on behalf of the given account by the predecessor operator, e.g. a
+    /// relayer that submits the actions signed off-chain by the account. Only the actions that
+    /// can be executed by the routers and don't require prices can be executed.
+    /// - Optional constraints are verified after all actions are executed.
+    /// - Requires one yoctoNEAR.
+    /// - Requires the operator to be approved by the account.
+    #[payable]
+    pub fn execute_for(
+        &mut self,
+        account_id: AccountId,
+        actions: Vec<Action>,
+        constraints: Option<ExecuteConstraints>,
+    ) {
+        assert_one_yocto();
+        assert_not_self_call();
+        let operator_id = env::predecessor_account_id();
+        assert!(
+            self.internal_get_approved_operators(&account_id)
+                .contains(&operator_id),
+            "The operator is not approved by the account"
+        );
+        assert!(
+            actions.iter().all(|action| action.is_router_allowed()),
+            "The action can't be executed by the operator"
+        );
+        let mut account = self.internal_unwrap_account(&account_id);
+        let prices = self.internal_execute_without_prices(&account_id, &mut account, actions);
+        if let Some(constraints) = constraints {
+            self.internal_assert_constraints(&account, &prices, &constraints);
+        }
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Approves the given operator to execute actions on behalf of the predecessor account with
+    /// `execute_for`.
+    /// - Panics if the operator is the account itself.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn approve_operator(&mut self, operator_id: AccountId) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        assert_ne!(
+            account_id, operator_id,
+            "The account can't approve itself as an operator"
+        );
+        let mut account = self.internal_unwrap_account(&account_id);
+        let mut operators = self.internal_get_approved_operators(&account_id);
+        if !operators.contains(&operator_id) {
+            operators.push(operator_id);
+            self.internal_set_approved_operators(&mut account, operators);
+        }
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Revokes the approval of the given operator for the predecessor account.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn revoke_operator(&mut self, operator_id: AccountId) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        let mut operators = self.internal_get_approved_operators(&account_id);
+        let index = operators
+            .iter()
+            .position(|id| id == &operator_id)
+            .expect("The operator is not approved by the account");
+        operators.remove(index);
+        self.internal_set_approved_operators(&mut account, operators);
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Returns the operators approved by the given account.
+    pub fn get_approved_operators(&self, account_id: AccountId) -> Vec<AccountId> {
+        self.internal_get_approved_operators(&account_id)
+    }
+}
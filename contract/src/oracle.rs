@@ -0,0 +1,197 @@
+use crate::*;
+
+/// The source of the prices.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum PriceSource {
+    /// The prices pushed by the price oracle with `oracle_on_call`, or pulled by the keeper.
+    Oracle,
+    /// The prices pulled from Pyth with `execute_with_pyth`.
+    Pyth,
+}
+
+/// The oracle configuration of an asset.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetOracleConfig {
+    /// The sources whose prices of the asset are accepted. The prices of the asset from the
+    /// other sources are ignored.
+    pub sources: Vec<PriceSource>,
+    /// The maximum age of the prices of the asset in seconds. It's stricter than the
+    /// `maximum_staleness_duration_sec` of the config for the volatile assets. Defaults to the
+    /// config.
+    pub max_staleness_sec: Option<DurationSec>,
+    /// The maximum deviation of the price of the asset from the last price of another source that
+    /// is not stale (multiplied by 10000). The borrows and the liquidations are rejected if the
+    /// sources disagree. Not checked if not set.
+    pub max_deviation: Option<u32>,
+}
+
+/// The last price of an asset from a source.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct SourcePrice {
+    pub price: Price,
+    /// The timestamp of the price data.
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+}
+
+/// The oracle configs and the source prices are stored under their own keys, so they don't change
+/// the layout of the contract state.
+fn asset_oracle_configs() -> LookupMap<TokenId, AssetOracleConfig> {
+    LookupMap::new(StorageKey::AssetOracleConfigs)
+}
+
+fn source_prices() -> LookupMap<(TokenId, PriceSource), SourcePrice> {
+    LookupMap::new(StorageKey::SourcePrices)
+}
+
+/// Whether the price deviates from the reference price by more than the maximum deviation. The
+/// prices are compared by the values of the same balance, since the sources may use different
+/// decimals.
+fn price_deviates(price: &Price, reference: &Price, max_deviation: u32) -> bool {
+    let balance = 10u128.pow(24);
+    let value = BigDecimal::from_balance_price(balance, price, 0);
+    let reference_value = BigDecimal::from_balance_price(balance, reference, 0);
+    let deviation = if value > reference_value {
+        value - reference_value
+    } else {
+        reference_value - value
+    };
+    deviation > reference_value.mul_ratio(max_deviation)
+}
+
+impl Contract {
+    /// Applies the oracle configs of the assets to the prices from the source at the timestamp
+    /// of the price data. The prices of the assets that don't accept the source are removed, and
+    /// the rest are checked against the staleness limits of the assets and recorded as the last
+    /// prices of the source. If `check_deviation`, e.g. the actions borrow or liquidate, the
+    /// prices are also checked against the last prices of the other sources.
+    /// - Panics if the price is older than the staleness limit of the asset.
+    /// - Panics if the price deviates from the last price of another source by more than the
+    ///   maximum deviation of the asset.
+    pub fn internal_apply_oracle_sources(
+        &mut self,
+        source: PriceSource,
+        timestamp: Timestamp,
+        prices: &mut Prices,
+        check_deviation: bool,
+    ) {
+        let configs = asset_oracle_configs();
+        let mut source_prices = source_prices();
+        let block_timestamp = env::block_timestamp();
+        let default_max_staleness_sec = self.internal_config().maximum_staleness_duration_sec;
+        for token_id in prices.token_ids() {
+            let config = match configs.get(&token_id) {
+                Some(config) => config,
+                None => continue,
+            };
+            if !config.sources.contains(&source) {
+                prices.remove(&token_id);
+                continue;
+            }
+            let max_staleness = sec_to_nano(
+                config
+                    .max_staleness_sec
+                    .unwrap_or(default_max_staleness_sec),
+            );
+            assert!(
+                block_timestamp - timestamp <= max_staleness,
+                "The price of {} is too stale",
+                token_id
+            );
+            let price = *prices.get_unwrap(&token_id);
+            if let (true, Some(max_deviation)) = (check_deviation, config.max_deviation) {
+                for other_source in config.sources.iter().filter(|s| **s != source) {
+                    let other_price = match source_prices.get(&(token_id.clone(), *other_source)) {
+                        Some(other_price)
+                            if block_timestamp - other_price.timestamp <= max_staleness =>
+                        {
+                            other_price.price
+                        }
+                        _ => continue,
+                    };
+                    assert!(
+                        !price_deviates(&price, &other_price, max_deviation),
+                        "The price of {} deviates between the oracle sources",
+                        token_id
+                    );
+                }
+            }
+            source_prices.insert(&(token_id, source), &SourcePrice { price, timestamp });
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the oracle config of the asset, or removes it if `None`, so the asset accepts the
+    /// prices from all sources with the staleness limit of the config.
+    /// - Panics if the asset doesn't exist, the sources are empty or the max deviation is 0.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn set_asset_oracle_config(
+        &mut self,
+        token_id: TokenId,
+        config: Option<AssetOracleConfig>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_unwrap_asset(&token_id);
+        let mut configs = asset_oracle_configs();
+        if let Some(config) = config {
+            assert!(
+                !config.sources.is_empty(),
+                "The price sources can't be empty"
+            );
+            assert_ne!(
+                config.max_deviation,
+                Some(0),
+                "The max deviation can't be 0"
+            );
+            configs.insert(&token_id, &config);
+        } else {
+            configs.remove(&token_id);
+        }
+    }
+
+    /// Returns the oracle config of the asset, or `None` if it accepts the prices from all
+    /// sources.
+    pub fn get_asset_oracle_config(&self, token_id: TokenId) -> Option<AssetOracleConfig> {
+        asset_oracle_configs().get(&token_id)
+    }
+
+    /// Returns the last price of the asset from the source. Only the prices of the assets with
+    /// the oracle config are recorded.
+    pub fn get_source_price(&self, token_id: TokenId, source: PriceSource) -> Option<SourcePrice> {
+        source_prices().get(&(token_id, source))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn price(multiplier: Balance, decimals: u8) -> Price {
+        Price {
+            multiplier,
+            decimals,
+        }
+    }
+
+    #[test]
+    fn test_price_deviates_across_decimals() {
+        // 10$ for NEAR with 24 decimals, in the decimals of the oracle and of Pyth.
+        let oracle_price = price(100000, 28);
+        let pyth_price = price(1000000000, 32);
+        assert!(!price_deviates(&oracle_price, &pyth_price, 1));
+        assert!(!price_deviates(&price(104000, 28), &pyth_price, 500));
+        assert!(price_deviates(&price(106000, 28), &pyth_price, 500));
+        assert!(price_deviates(&price(94000, 28), &pyth_price, 500));
+    }
+}
@@ -0,0 +1,84 @@
+use crate::*;
+
+/// The oracle call commitment of an account. Once the account enables the commitments, every
+/// oracle call on its behalf has to match the hash of the message committed beforehand.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct OracleCommitment {
+    /// The SHA-256 hash of the `msg` of the next oracle call, if it's committed.
+    pub msg_hash: Option<Vec<u8>>,
+}
+
+impl Contract {
+    /// Verifies the message of the oracle call against the commitment of the account and uses
+    /// the commitment. The accounts that didn't enable the commitments are not checked.
+    pub fn internal_use_oracle_commitment(&mut self, account_id: &AccountId, msg: &str) {
+        let commitment = match self.oracle_commitments.get(account_id) {
+            Some(commitment) => commitment,
+            None => return,
+        };
+        let msg_hash = commitment
+            .msg_hash
+            .expect("The oracle call has to be committed by the account");
+        assert_eq!(
+            msg_hash,
+            env::sha256(msg.as_bytes()),
+            "The oracle call doesn't match the committed message"
+        );
+        self.oracle_commitments
+            .insert(account_id, &OracleCommitment { msg_hash: None });
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Commits the SHA-256 hash of the `msg` of the next oracle call of the predecessor account
+    /// and enables the commitments for the account. Once enabled, the oracle calls on behalf of
+    /// the account are only executed if they match the committed message, so the oracle can't
+    /// forward different actions for the account.
+    /// - Requires one yoctoNEAR.
+    /// - Requires the predecessor account to be registered.
+    #[payable]
+    pub fn commit_oracle_msg(&mut self, msg_hash: Base64VecU8) {
+        assert_one_yocto();
+        assert_eq!(msg_hash.0.len(), 32, "The message hash has to be 32 bytes");
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        account.storage_tracker.start();
+        self.oracle_commitments.insert(
+            &account_id,
+            &OracleCommitment {
+                msg_hash: Some(msg_hash.0),
+            },
+        );
+        account.storage_tracker.stop();
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Disables the oracle call commitments of the predecessor account and removes the pending
+    /// commitment.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn disable_oracle_commitments(&mut self) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        account.storage_tracker.start();
+        self.oracle_commitments.remove(&account_id);
+        account.storage_tracker.stop();
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Returns the hash of the committed message of the next oracle call of the given account.
+    /// Returns `None` if there is no pending commitment.
+    pub fn get_oracle_commitment(&self, account_id: AccountId) -> Option<Base64VecU8> {
+        self.oracle_commitments
+            .get(&account_id)
+            .and_then(|commitment| commitment.msg_hash)
+            .map(Base64VecU8)
+    }
+
+    /// Returns whether the oracle calls of the given account have to be committed.
+    pub fn is_oracle_commitment_enabled(&self, account_id: AccountId) -> bool {
+        self.oracle_commitments.contains_key(&account_id)
+    }
+}
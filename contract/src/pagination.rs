@@ -0,0 +1,70 @@
+use crate::*;
+use std::ops::Range;
+
+/// The gas that a page of a view can use to collect its elements. The RPC nodes limit the gas of
+/// the view calls, so the pages of the heavy views end early once the gas is used, leaving the
+/// rest of the gas for the serialization of the page.
+const GAS_FOR_VIEW_PAGE: Gas = Gas(Gas::ONE_TERA.0 * 100);
+
+/// A page of elements returned by a paged view.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// The total number of elements across all pages.
+    pub total_count: u64,
+    /// The token to pass to get the next page. `None` if this is the last page.
+    pub next_token: Option<String>,
+}
+
+/// Returns the range of indices for the page starting at the given continuation token and the
+/// token for the next page. The token is opaque to the caller.
+pub(crate) fn page_range(
+    total_count: u64,
+    token: Option<String>,
+    limit: Option<u64>,
+) -> (Range<u64>, Option<String>) {
+    let from_index = token
+        .map(|token| token.parse::<u64>().expect("Invalid continuation token"))
+        .unwrap_or(0);
+    let limit = limit.unwrap_or(total_count);
+    let to_index = std::cmp::min(total_count, from_index.saturating_add(limit));
+    let next_token = if to_index < total_count {
+        Some(to_index.to_string())
+    } else {
+        None
+    };
+    (from_index..std::cmp::max(from_index, to_index), next_token)
+}
+
+impl<T> Page<T> {
+    /// Collects the page of elements from the given total count using the continuation token.
+    /// The page size is chosen by the contract: the page ends before the `limit` once the gas for
+    /// the page is used, and the next token continues after the last collected element. Every page
+    /// has at least one element, so the iteration always progresses.
+    pub(crate) fn new<F: FnMut(u64) -> T>(
+        total_count: u64,
+        token: Option<String>,
+        limit: Option<u64>,
+        mut f: F,
+    ) -> Self {
+        let (range, next_token) = page_range(total_count, token, limit);
+        let mut items = vec![];
+        for index in range {
+            if !items.is_empty() && env::used_gas() >= GAS_FOR_VIEW_PAGE {
+                return Self {
+                    items,
+                    total_count,
+                    next_token: Some(index.to_string()),
+                };
+            }
+            items.push(f(index));
+        }
+        Self {
+            items,
+            total_count,
+            next_token,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::*;
+use near_sdk::json_types::U128;
+
+const GAS_FOR_ON_BURROW_POSITION_CHANGE: Gas = Gas(Gas::ONE_TERA.0 * 10);
+
+/// The balances of the partner account after the change, passed to the position hook.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AccountPositionView {
+    pub account_id: AccountId,
+    pub supplied: HashMap<TokenId, U128>,
+    pub collateral: HashMap<TokenId, U128>,
+    pub borrowed: HashMap<TokenId, U128>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct PartnerView {
+    pub partner_id: AccountId,
+    pub is_hook_registered: bool,
+}
+
+fn balances_view(balances: HashMap<TokenId, Balance>) -> HashMap<TokenId, U128> {
+    balances
+        .into_iter()
+        .map(|(token_id, balance)| (token_id, balance.into()))
+        .collect()
+}
+
+impl Contract {
+    /// Calls the position hook of the partner contract with the new balances, if the account is
+    /// a partner with the registered hook. The hook is called without a callback, so its failure
+    /// doesn't affect the actions.
+    pub fn internal_notify_partner(&self, account: &Account) {
+        if self.partners.get(&account.account_id) != Some(true) {
+            return;
+        }
+        let position = AccountPositionView {
+            account_id: account.account_id.clone(),
+            supplied: balances_view(self.compute_account_balances(&account.supplied, false)),
+            collateral: balances_view(self.compute_account_balances(&account.collateral, false)),
+            borrowed: balances_view(self.compute_account_balances(&account.borrowed, true)),
+        };
+        ext_partner::on_burrow_position_change(
+            position,
+            account.account_id.clone(),
+            NO_DEPOSIT,
+            GAS_FOR_ON_BURROW_POSITION_CHANGE,
+        );
+    }
+
+    fn internal_set_partner_hook(&mut self, is_hook_registered: bool) {
+        let partner_id = env::predecessor_account_id();
+        assert!(
+            self.partners.get(&partner_id).is_some(),
+            "Not a whitelisted partner"
+        );
+        self.partners.insert(&partner_id, &is_hook_registered);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Whitelists the partner contract, e.g. a vault built on top of the contract. The partner
+    /// can register the position hook with `register_position_hook` after that.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn add_partner(&mut self, partner_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        if self.partners.get(&partner_id).is_none() {
+            self.partners.insert(&partner_id, &false);
+        }
+    }
+
+    /// Removes the partner contract from the whitelist and unregisters its position hook.
+    /// - Panics if the partner is not whitelisted.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn remove_partner(&mut self, partner_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.partners
+            .remove(&partner_id)
+            .expect("Not a whitelisted partner");
+    }
+
+    /// Registers the position hook of the predecessor partner contract. The contract calls
+    /// `on_burrow_position_change` on the partner with the new balances after every change of the
+    /// partner account.
+    /// - Panics if the predecessor is not a whitelisted partner.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn register_position_hook(&mut self) {
+        assert_one_yocto();
+        self.internal_set_partner_hook(true);
+    }
+
+    /// Unregisters the position hook of the predecessor partner contract.
+    /// - Panics if the predecessor is not a whitelisted partner.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn unregister_position_hook(&mut self) {
+        assert_one_yocto();
+        self.internal_set_partner_hook(false);
+    }
+
+    /// Returns the whitelisted partner contracts.
+    pub fn get_partners(&self) -> Vec<PartnerView> {
+        self.partners
+            .iter()
+            .map(|(partner_id, is_hook_registered)| PartnerView {
+                partner_id,
+                is_hook_registered,
+            })
+            .collect()
+    }
+}
+
+#[ext_contract(ext_partner)]
+trait ExtPartner {
+    /// The position hook of the partner contract.
+    fn on_burrow_position_change(&mut self, position: AccountPositionView);
+}
@@ -5,11 +5,22 @@ use near_sdk::serde_json;
 #[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Serialize))]
 #[serde(crate = "near_sdk::serde")]
 pub enum PriceReceiverMsg {
-    Execute { actions: Vec<Action> },
+    Execute {
+        actions: Vec<Action>,
+        constraints: Option<ExecuteConstraints>,
+    },
+    /// Executes the actions of multiple accounts on behalf of the sender router.
+    RouterExecute {
+        batch: Vec<(AccountId, Vec<Action>)>,
+    },
 }
 
 impl Contract {
     pub fn validate_price_data(&self, data: &PriceData) {
+        assert!(
+            !self.internal_is_price_feeds_paused(),
+            "The price feeds are paused"
+        );
         let config = self.internal_config();
         assert!(
             data.recency_duration_sec <= config.maximum_recency_duration_sec,
@@ -25,23 +36,116 @@ impl Contract {
             "Price data timestamp is too stale"
         );
     }
+
+    /// Emits the price data used for the execution, so the prices of the contested liquidations
+    /// can be audited. The executions with liquidations always emit the event, and the rest at
+    /// most once per the interval from the config.
+    pub fn internal_emit_prices_used(
+        &mut self,
+        account_id: &AccountId,
+        data: &PriceData,
+        has_liquidations: bool,
+    ) {
+        let interval_sec = match self.internal_config().prices_used_event_interval_sec {
+            Some(interval_sec) => interval_sec,
+            None => return,
+        };
+        let timestamp = env::block_timestamp();
+        if !has_liquidations
+            && self.last_prices_used_event_timestamp > 0
+            && timestamp < self.last_prices_used_event_timestamp + sec_to_nano(interval_sec)
+        {
+            return;
+        }
+        events::emit::prices_used(account_id, &env::predecessor_account_id(), data);
+        self.last_prices_used_event_timestamp = timestamp;
+    }
+}
+
+/// Whether the actions borrow, or liquidate or force close any account. The prices of such actions
+/// are checked against the other price sources.
+pub(crate) fn contains_borrows_or_liquidations<'a>(
+    actions: impl IntoIterator<Item = &'a Action>,
+) -> bool {
+    actions.into_iter().any(|action| {
+        matches!(
+            action,
+            Action::Borrow(_)
+                | Action::BorrowAndWithdraw(_)
+                | Action::LeverageUp { .. }
+                | Action::Liquidate { .. }
+                | Action::ForceClose { .. }
+        )
+    })
+}
+
+/// Whether the actions liquidate or force close any account.
+pub(crate) fn contains_liquidations<'a>(actions: impl IntoIterator<Item = &'a Action>) -> bool {
+    actions
+        .into_iter()
+        .any(|action| matches!(action, Action::Liquidate { .. } | Action::ForceClose { .. }))
 }
 
 #[near_bindgen]
 impl OraclePriceReceiver for Contract {
     /// The method will execute a given list of actions in the msg using the prices from the `data`
     /// provided by the oracle on behalf of the sender_id.
+    /// - Panics if the prices deviate from the TWAP of the Ref Finance pools.
+    /// - Panics if the prices are older than the staleness limits of the assets, or the prices of
+    ///   the borrows and the liquidations deviate from the prices of the other sources.
+    /// - Panics if the `sender_id` is not registered, or if the account enabled the oracle call
+    ///   commitments and the `msg` doesn't match the committed one.
+    /// - Panics if the prices don't cover the assets required by the actions.
     /// - Requires to be called by the oracle account ID.
     fn oracle_on_call(&mut self, sender_id: AccountId, data: PriceData, msg: String) {
+        assert_not_self_call();
         assert_eq!(env::predecessor_account_id(), self.get_oracle_account_id());
 
-        let actions = match serde_json::from_str(&msg).expect("Can't parse PriceReceiverMsg") {
-            PriceReceiverMsg::Execute { actions } => actions,
+        let (actions, constraints) = match serde_json::from_str(&msg)
+            .expect("Can't parse PriceReceiverMsg")
+        {
+            PriceReceiverMsg::Execute {
+                actions,
+                constraints,
+            } => (actions, constraints),
+            PriceReceiverMsg::RouterExecute { batch } => {
+                self.validate_price_data(&data);
+                self.internal_emit_prices_used(
+                    &sender_id,
+                    &data,
+                    contains_liquidations(batch.iter().flat_map(|(_, actions)| actions)),
+                );
+                let timestamp = data.timestamp;
+                let mut prices = self.internal_prices_from_data(data);
+                self.internal_apply_oracle_sources(
+                    PriceSource::Oracle,
+                    timestamp,
+                    &mut prices,
+                    contains_borrows_or_liquidations(batch.iter().flat_map(|(_, actions)| actions)),
+                );
+                self.internal_assert_twap_deviation(&prices);
+                self.internal_router_execute(&sender_id, batch, Some(&prices));
+                return;
+            }
         };
 
-        let mut account = self.internal_unwrap_account(&sender_id);
+        let account = self
+            .internal_get_account(&sender_id, false)
+            .expect("The sender of the oracle call is not registered");
+        self.internal_assert_account_not_locked(&sender_id);
+        self.internal_use_oracle_commitment(&sender_id, &msg);
         self.validate_price_data(&data);
-        self.internal_execute(&sender_id, &mut account, actions, data.into());
-        self.internal_set_account(&sender_id, account);
+        self.internal_emit_prices_used(&sender_id, &data, contains_liquidations(&actions));
+        let timestamp = data.timestamp;
+        let mut prices = self.internal_prices_from_data(data);
+        self.internal_apply_oracle_sources(
+            PriceSource::Oracle,
+            timestamp,
+            &mut prices,
+            contains_borrows_or_liquidations(&actions),
+        );
+        self.internal_assert_twap_deviation(&prices);
+        self.internal_assert_prices_cover_actions(&account, &actions, &prices);
+        self.internal_execute_with_continuation(&sender_id, account, actions, prices, constraints);
     }
 }
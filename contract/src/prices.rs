@@ -1,6 +1,8 @@
 use crate::*;
 use std::convert::TryFrom;
 
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
 pub struct Prices {
     prices: HashMap<TokenId, Price>,
 }
@@ -15,6 +17,43 @@ impl Prices {
     pub fn get_unwrap(&self, token_id: &TokenId) -> &Price {
         self.prices.get(token_id).expect("Asset price is missing")
     }
+
+    pub fn get(&self, token_id: &TokenId) -> Option<&Price> {
+        self.prices.get(token_id)
+    }
+
+    /// Returns the price of the token, unless it's missing or zero.
+    pub fn get_valid(&self, token_id: &TokenId) -> Option<&Price> {
+        self.prices
+            .get(token_id)
+            .filter(|price| price.multiplier > 0)
+    }
+
+    /// Panics if the price of the collateral asset that is seized by a liquidation is missing or
+    /// zero. Such collateral is valued at zero, so it can't be seized at a discount.
+    pub fn assert_seizable(&self, token_id: &TokenId) {
+        assert!(
+            self.get_valid(token_id).is_some(),
+            "The price of the seized collateral {} is missing or zero",
+            token_id
+        );
+    }
+
+    pub fn contains(&self, token_id: &TokenId) -> bool {
+        self.prices.contains_key(token_id)
+    }
+
+    pub fn insert(&mut self, token_id: TokenId, price: Price) {
+        self.prices.insert(token_id, price);
+    }
+
+    pub fn remove(&mut self, token_id: &TokenId) {
+        self.prices.remove(token_id);
+    }
+
+    pub fn token_ids(&self) -> Vec<TokenId> {
+        self.prices.keys().cloned().collect()
+    }
 }
 
 impl From<PriceData> for Prices {
@@ -34,6 +73,36 @@ impl From<PriceData> for Prices {
 }
 
 impl Contract {
+    /// Returns the last recorded prices from the oracle.
+    pub fn internal_last_prices(&self) -> Prices {
+        Prices {
+            prices: self.last_prices.clone(),
+        }
+    }
+
+    /// Replaces the decimals of the oracle prices with the oracle price decimals from the configs
+    /// of the assets, for the feeds whose decimals differ from the oracle default.
+    pub fn internal_apply_oracle_price_decimals(&self, prices: &mut Prices) {
+        for (token_id, price) in prices.prices.iter_mut() {
+            if let Some(oracle_price_decimals) = self
+                .internal_get_asset(token_id)
+                .and_then(|asset| asset.config.oracle_price_decimals)
+            {
+                price.decimals = oracle_price_decimals;
+            }
+        }
+    }
+
+    /// Emits the `collateral_price_missing` event for every collateral asset of the account whose
+    /// price is missing or zero, since the health checks value such collateral at zero.
+    pub fn internal_emit_missing_collateral_prices(&self, account: &Account, prices: &Prices) {
+        for token_id in account.collateral.keys() {
+            if prices.get_valid(token_id).is_none() {
+                events::emit::collateral_price_missing(&account.account_id, token_id);
+            }
+        }
+    }
+
     /// Updates last prices in the contract.
     /// The prices will only be stored if the old price for the token is already present or the
     /// asset with this token ID exists.
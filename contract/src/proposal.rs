@@ -0,0 +1,214 @@
+use crate::*;
+
+/// The time after the proposal timelock during which the proposal can be executed. The proposal
+/// expires afterwards, so a stale change can't be executed long after it was announced.
+pub const PROPOSAL_GRACE_PERIOD_SEC: DurationSec = 7 * 24 * 60 * 60;
+
+/// A change of the risk parameters that has to be proposed by the owner and can only be executed
+/// after the proposal timelock.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub enum ProposalKind {
+    /// Replaces the contract config, e.g. to change the oracle or the flash loan fee.
+    UpdateConfig { config: Box<Config> },
+    /// Replaces the asset config, e.g. to change the interest rate or the reserve ratio.
+    UpdateAsset {
+        token_id: TokenId,
+        asset_config: Box<AssetConfig>,
+    },
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Proposal {
+    pub proposal_id: u32,
+    pub kind: ProposalKind,
+    /// When the proposal was made in nanoseconds.
+    #[serde(with = "u64_dec_format")]
+    pub proposed_timestamp: Timestamp,
+    /// When the proposal can be executed in nanoseconds.
+    #[serde(with = "u64_dec_format")]
+    pub executable_timestamp: Timestamp,
+    /// When the proposal expires and can no longer be executed in nanoseconds.
+    #[serde(with = "u64_dec_format")]
+    pub expiration_timestamp: Timestamp,
+}
+
+impl ProposalKind {
+    /// Asserts that the predecessor can propose or cancel the change.
+    fn assert_access(&self, contract: &Contract) {
+        match self {
+            ProposalKind::UpdateConfig { .. } => contract.assert_owner(),
+            ProposalKind::UpdateAsset { .. } => contract.assert_owner_or_role(Role::RiskAdmin),
+        }
+    }
+
+    fn assert_valid(&self, contract: &Contract) {
+        match self {
+            ProposalKind::UpdateConfig { config } => config.assert_valid(),
+            ProposalKind::UpdateAsset {
+                token_id,
+                asset_config,
+            } => {
+                asset_config.assert_valid();
+                contract.internal_unwrap_asset(token_id);
+            }
+        }
+    }
+}
+
+impl Contract {
+    pub fn assert_no_proposal_timelock(&self) {
+        assert_eq!(
+            self.internal_config().proposal_timelock_sec,
+            0,
+            "The change has to be proposed and executed after the timelock"
+        );
+    }
+
+    pub fn internal_update_config(&mut self, config: Config) {
+        config.assert_valid();
+        events::emit::config_updated(&self.internal_config(), &config);
+        self.internal_set_config(config);
+    }
+
+    pub fn internal_update_asset(&mut self, token_id: &TokenId, mut asset_config: AssetConfig) {
+        asset_config.assert_valid();
+        self.internal_assert_lp_asset_config(token_id, &asset_config);
+        self.internal_apply_delisting(token_id, &mut asset_config);
+        self.internal_apply_deprecation(token_id, &mut asset_config);
+        self.internal_apply_sunset(&mut asset_config);
+        self.internal_apply_extra_decimals_migration(token_id, &mut asset_config);
+        self.internal_apply_collateral_shares_backfill(token_id, &mut asset_config);
+        let mut asset = self.internal_unwrap_asset(token_id);
+        assert!(
+            !asset_config.collateral_only || asset.borrowed.balance == 0,
+            "The asset with the borrowed balance can't be collateral-only"
+        );
+        if asset.config.extra_decimals != asset_config.extra_decimals {
+            assert!(
+                asset.borrowed.balance == 0 && asset.supplied.balance == 0 && asset.reserved == 0,
+                "Can't change extra decimals if any of the balances are not 0"
+            );
+            if let Some(metadata) = self.token_metadata.get(token_id) {
+                assert_extra_decimals(metadata.decimals, asset_config.extra_decimals);
+            }
+        }
+        if let Some(metadata) = self.token_metadata.get(token_id) {
+            assert_oracle_price_decimals(metadata.decimals, &asset_config);
+        }
+        self.internal_record_volatility_ratio_change(
+            token_id,
+            asset.config.volatility_ratio,
+            asset_config.volatility_ratio,
+        );
+        events::emit::asset_config_updated(token_id, &asset.config, &asset_config);
+        asset.config = asset_config;
+        self.internal_set_asset(token_id, asset);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Proposes the change that can be executed after the proposal timelock from the config.
+    /// Returns the ID of the proposal.
+    /// - Panics if the proposed config is invalid.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner. The asset config changes can also be
+    ///   proposed and cancelled by a risk admin.
+    #[payable]
+    pub fn propose(&mut self, kind: ProposalKind) -> u32 {
+        assert_one_yocto();
+        kind.assert_access(self);
+        kind.assert_valid(self);
+        let proposal_id = self.next_proposal_id;
+        self.next_proposal_id += 1;
+        let proposed_timestamp = env::block_timestamp();
+        let executable_timestamp =
+            proposed_timestamp + sec_to_nano(self.internal_config().proposal_timelock_sec);
+        let proposal = Proposal {
+            proposal_id,
+            kind,
+            proposed_timestamp,
+            executable_timestamp,
+            expiration_timestamp: executable_timestamp + sec_to_nano(PROPOSAL_GRACE_PERIOD_SEC),
+        };
+        self.proposals.insert(&proposal_id, &proposal);
+        proposal_id
+    }
+
+    /// Executes the proposal after its timelock has passed. Anyone can execute it, so the
+    /// announced change can't be held back once the timelock has passed, unless it's cancelled.
+    /// - Panics if the proposal doesn't exist, is not executable yet or has expired.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn execute_proposal(&mut self, proposal_id: u32) {
+        assert_one_yocto();
+        let proposal = self
+            .proposals
+            .remove(&proposal_id)
+            .expect("Proposal not found");
+        assert!(
+            env::block_timestamp() >= proposal.executable_timestamp,
+            "The proposal timelock has not passed yet"
+        );
+        assert!(
+            env::block_timestamp() < proposal.expiration_timestamp,
+            "The proposal has expired"
+        );
+        match proposal.kind {
+            ProposalKind::UpdateConfig { config } => self.internal_update_config(*config),
+            ProposalKind::UpdateAsset {
+                token_id,
+                asset_config,
+            } => self.internal_update_asset(&token_id, *asset_config),
+        }
+    }
+
+    /// Cancels the proposal before or after its timelock has passed, e.g. if the proposed change
+    /// is no longer wanted or the proposal has expired.
+    /// - Panics if the proposal doesn't exist.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner or a risk admin for the asset config changes.
+    #[payable]
+    pub fn cancel_proposal(&mut self, proposal_id: u32) {
+        assert_one_yocto();
+        let proposal = self
+            .proposals
+            .remove(&proposal_id)
+            .expect("Proposal not found");
+        proposal.kind.assert_access(self);
+    }
+
+    /// Schedules the update of the asset config. It's a shortcut for `propose` with the
+    /// `UpdateAsset` proposal. Returns the ID of the proposal.
+    #[payable]
+    pub fn schedule_asset_config_update(
+        &mut self,
+        token_id: AccountId,
+        asset_config: AssetConfig,
+    ) -> u32 {
+        self.propose(ProposalKind::UpdateAsset {
+            token_id,
+            asset_config: Box::new(asset_config),
+        })
+    }
+
+    /// Executes the scheduled update once its timelock has passed. It's a shortcut for
+    /// `execute_proposal`.
+    #[payable]
+    pub fn execute_scheduled_update(&mut self, proposal_id: u32) {
+        self.execute_proposal(proposal_id);
+    }
+
+    /// Returns the proposal with the given ID if it's pending.
+    pub fn get_proposal(&self, proposal_id: u32) -> Option<Proposal> {
+        self.proposals.get(&proposal_id)
+    }
+
+    /// Returns all pending proposals, i.e. the changes that were proposed, but not executed or
+    /// cancelled yet.
+    pub fn get_pending_proposals(&self) -> Vec<Proposal> {
+        self.proposals.values().collect()
+    }
+}
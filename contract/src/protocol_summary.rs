@@ -0,0 +1,79 @@
+use crate::*;
+use near_sdk::json_types::I128;
+use near_sdk::BlockHeight;
+
+/// The changes of the supplied and the borrowed balances of the assets within one block.
+#[derive(BorshSerialize, BorshDeserialize, Default)]
+pub struct ProtocolSummary {
+    /// The block of the changes.
+    pub block_height: BlockHeight,
+    /// The changes of the supplied and the borrowed balances by asset, including the interest.
+    pub asset_deltas: HashMap<TokenId, (i128, i128)>,
+}
+
+/// The changes of the supplied and the borrowed balances of an asset in the summary event.
+#[derive(Serialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetDeltas {
+    pub token_id: TokenId,
+    pub supplied_delta: I128,
+    pub borrowed_delta: I128,
+}
+
+impl Contract {
+    /// Records the changes of the balances of the asset before it is saved, if the heavy events
+    /// are enabled. The first change in a new block emits the `protocol_summary` event with the
+    /// changes of the previous block where the assets have changed, so the event is emitted at most
+    /// once per block.
+    pub fn internal_record_protocol_deltas(&mut self, token_id: &TokenId, new_asset: &Asset) {
+        self.internal_emit_heavy_events(|contract| {
+            let old_asset: Option<Asset> = contract.assets.get(token_id).map(|o| o.into());
+            let (old_supplied, old_borrowed) = old_asset
+                .map(|asset| (asset.supplied.balance, asset.borrowed.balance))
+                .unwrap_or_default();
+            let supplied_delta = new_asset.supplied.balance as i128 - old_supplied as i128;
+            let borrowed_delta = new_asset.borrowed.balance as i128 - old_borrowed as i128;
+            if supplied_delta != 0 || borrowed_delta != 0 {
+                contract.internal_add_protocol_deltas(token_id, supplied_delta, borrowed_delta);
+            }
+        });
+    }
+
+    fn internal_add_protocol_deltas(
+        &mut self,
+        token_id: &TokenId,
+        supplied_delta: i128,
+        borrowed_delta: i128,
+    ) {
+        let block_height = env::block_height();
+        if self.protocol_summary.block_height != block_height {
+            let summary = std::mem::replace(
+                &mut self.protocol_summary,
+                ProtocolSummary {
+                    block_height,
+                    asset_deltas: HashMap::new(),
+                },
+            );
+            if !summary.asset_deltas.is_empty() {
+                let mut asset_deltas: Vec<AssetDeltas> = summary
+                    .asset_deltas
+                    .into_iter()
+                    .map(|(token_id, (supplied_delta, borrowed_delta))| AssetDeltas {
+                        token_id,
+                        supplied_delta: supplied_delta.into(),
+                        borrowed_delta: borrowed_delta.into(),
+                    })
+                    .collect();
+                asset_deltas.sort_by(|a, b| a.token_id.cmp(&b.token_id));
+                events::emit::protocol_summary(summary.block_height, &asset_deltas);
+            }
+        }
+        let deltas = self
+            .protocol_summary
+            .asset_deltas
+            .entry(token_id.clone())
+            .or_default();
+        deltas.0 += supplied_delta;
+        deltas.1 += borrowed_delta;
+    }
+}
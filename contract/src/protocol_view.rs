@@ -0,0 +1,152 @@
+use crate::*;
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetOverview {
+    pub token_id: TokenId,
+    /// Total supplied including collateral, but excluding reserved.
+    #[serde(with = "u128_dec_format")]
+    pub supplied: Balance,
+    /// Total borrowed.
+    #[serde(with = "u128_dec_format")]
+    pub borrowed: Balance,
+    #[serde(with = "u128_dec_format")]
+    pub reserved: Balance,
+    /// The last recorded price from the oracle, if any.
+    pub price: Option<Price>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ProtocolOverview {
+    pub num_accounts: u32,
+    pub num_assets: u32,
+    /// The total value of the supplied assets using the last recorded prices.
+    /// The assets without the recorded price are not included.
+    pub total_supplied_value: BigDecimal,
+    /// The total value of the borrowed assets using the last recorded prices.
+    pub total_borrowed_value: BigDecimal,
+    /// The total value of the reserved assets using the last recorded prices.
+    pub total_reserved_value: BigDecimal,
+    pub assets: Vec<AssetOverview>,
+}
+
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AssetReserveView {
+    pub token_id: TokenId,
+    /// The amount reserved for the stability.
+    #[serde(with = "u128_dec_format")]
+    pub reserved: Balance,
+    /// The protocol fees that are still in the reserve.
+    #[serde(with = "u128_dec_format")]
+    pub pending_protocol_fees: Balance,
+    /// The total amount of the protocol fees ever collected to the reserve.
+    #[serde(with = "u128_dec_format")]
+    pub collected_protocol_fees: Balance,
+    /// The total amount of the protocol fees ever withdrawn from the reserve.
+    #[serde(with = "u128_dec_format")]
+    pub withdrawn_protocol_fees: Balance,
+}
+
+impl Contract {
+    /// Returns the total value of the borrowed assets using the last recorded prices. The assets
+    /// without a recorded price are not counted.
+    pub fn internal_get_total_borrowed_value(&self) -> BigDecimal {
+        self.asset_ids
+            .iter()
+            .filter_map(|token_id| {
+                let price = self.last_prices.get(&token_id)?;
+                let asset = self.internal_unwrap_asset(&token_id);
+                Some(BigDecimal::from_balance_price(
+                    asset.borrowed.balance,
+                    price,
+                    asset.config.extra_decimals,
+                ))
+            })
+            .fold(BigDecimal::zero(), |total, value| total + value)
+    }
+
+    /// Asserts that the total borrowed value doesn't exceed the global debt ceiling from the config.
+    pub fn assert_max_total_borrowed_value(&self) {
+        if let Some(max_total_borrowed_value) = self.internal_config().max_total_borrowed_value {
+            assert!(
+                self.internal_get_total_borrowed_value()
+                    <= BigDecimal::from(max_total_borrowed_value.0),
+                "The total borrowed value exceeds the protocol debt ceiling of {} USD",
+                max_total_borrowed_value.0
+            );
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the totals of the protocol and the breakdown per asset. The values are computed
+    /// using the last recorded prices from the oracle.
+    pub fn get_protocol_overview(&self) -> ProtocolOverview {
+        let mut total_supplied_value = BigDecimal::zero();
+        let mut total_borrowed_value = BigDecimal::zero();
+        let mut total_reserved_value = BigDecimal::zero();
+        let assets: Vec<AssetOverview> = self
+            .asset_ids
+            .iter()
+            .map(|token_id| {
+                let asset = self.internal_unwrap_asset(&token_id);
+                let price = self.last_prices.get(&token_id).copied();
+                if let Some(price) = &price {
+                    let extra_decimals = asset.config.extra_decimals;
+                    total_supplied_value = total_supplied_value
+                        + BigDecimal::from_balance_price(
+                            asset.supplied.balance,
+                            price,
+                            extra_decimals,
+                        );
+                    total_borrowed_value = total_borrowed_value
+                        + BigDecimal::from_balance_price(
+                            asset.borrowed.balance,
+                            price,
+                            extra_decimals,
+                        );
+                    total_reserved_value = total_reserved_value
+                        + BigDecimal::from_balance_price(asset.reserved, price, extra_decimals);
+                }
+                AssetOverview {
+                    token_id,
+                    supplied: asset.supplied.balance,
+                    borrowed: asset.borrowed.balance,
+                    reserved: asset.reserved,
+                    price,
+                }
+            })
+            .collect();
+        ProtocolOverview {
+            num_accounts: self.accounts.len() as _,
+            num_assets: assets.len() as _,
+            total_supplied_value,
+            total_borrowed_value,
+            total_reserved_value,
+            assets,
+        }
+    }
+
+    /// Returns the reserve balances and the protocol fees of all assets.
+    pub fn get_reserves(&self) -> Vec<AssetReserveView> {
+        self.asset_ids
+            .iter()
+            .map(|token_id| {
+                let asset = self.internal_unwrap_asset(&token_id);
+                AssetReserveView {
+                    token_id,
+                    reserved: asset.reserved,
+                    pending_protocol_fees: asset.get_pending_protocol_fees(),
+                    collected_protocol_fees: asset.collected_protocol_fees,
+                    withdrawn_protocol_fees: asset.withdrawn_protocol_fees,
+                }
+            })
+            .collect()
+    }
+}
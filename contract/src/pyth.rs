@@ -0,0 +1,237 @@
+use crate::*;
+use near_sdk::json_types::I64;
+use near_sdk::{serde_json, PromiseResult};
+
+const GAS_FOR_PYTH_UPDATE_PRICE_FEEDS: Gas = Gas(Gas::ONE_TERA.0 * 80);
+const GAS_FOR_PYTH_LIST_PRICES: Gas = Gas(Gas::ONE_TERA.0 * 10);
+const GAS_FOR_AFTER_PYTH_LIST_PRICES: Gas = Gas(Gas::ONE_TERA.0 * 120);
+
+/// The Pyth price feed of an asset.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct PythFeed {
+    /// The hex-encoded price identifier of the USD price feed on Pyth.
+    pub price_id: String,
+    /// The decimals of the token, since the Pyth price is given for a whole token.
+    pub token_decimals: u8,
+}
+
+/// The price returned by the Pyth contract.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct PythPrice {
+    pub price: I64,
+    pub conf: U64,
+    pub expo: i32,
+    /// The publish time of the price in seconds.
+    pub publish_time: i64,
+}
+
+impl PythFeed {
+    /// Converts the Pyth price of a whole token into the price of the token in its decimals.
+    fn to_price(&self, pyth_price: &PythPrice) -> Price {
+        assert!(pyth_price.price.0 > 0, "The Pyth price is not positive");
+        assert!(pyth_price.expo <= 0, "The Pyth price exponent is positive");
+        Price {
+            multiplier: pyth_price.price.0 as Balance,
+            decimals: self.token_decimals + (-pyth_price.expo) as u8,
+        }
+    }
+}
+
+impl Contract {
+    /// Converts the prices of the Pyth feeds into the price data, so the prices go through the
+    /// same checks as the prices of the oracle calls. The timestamp of the price data is the
+    /// oldest publish time of the prices.
+    fn internal_pyth_price_data(
+        &self,
+        pyth_prices: HashMap<String, Option<PythPrice>>,
+    ) -> PriceData {
+        let mut publish_times = vec![];
+        let prices = self
+            .pyth_feeds
+            .iter()
+            .map(|(token_id, feed)| {
+                let price = pyth_prices
+                    .get(&feed.price_id)
+                    .and_then(|price| price.as_ref())
+                    .map(|pyth_price| {
+                        publish_times.push(pyth_price.publish_time as u64);
+                        feed.to_price(pyth_price)
+                    });
+                AssetOptionalPrice {
+                    asset_id: token_id.to_string(),
+                    price,
+                }
+            })
+            .collect();
+        let min_publish_time = *publish_times.iter().min().expect("Missing Pyth prices");
+        let max_publish_time = *publish_times.iter().max().unwrap();
+        PriceData {
+            timestamp: sec_to_nano(min_publish_time as u32),
+            recency_duration_sec: (max_publish_time - min_publish_time) as DurationSec,
+            prices,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Executes the actions with the prices from Pyth. The attached Pyth price update `data`
+    /// (hex-encoded VAA) is forwarded to the Pyth contract with the attached deposit to pay the
+    /// update fee, and the prices of the feeds are read after the update in the same promise
+    /// chain, so the actions always use the pushed prices.
+    /// - Optional constraints are verified after all actions are executed.
+    /// - Panics if the Pyth oracle is not set in the config.
+    /// - Panics if the actions fail the validation before the prices are requested, e.g. an asset
+    ///   doesn't exist, an action is paused or a balance is not enough.
+    /// - Requires a deposit to cover the Pyth update fee, at least one yoctoNEAR.
+    #[payable]
+    pub fn execute_with_pyth(
+        &mut self,
+        actions: Vec<Action>,
+        constraints: Option<ExecuteConstraints>,
+        data: String,
+    ) -> Promise {
+        assert_not_self_call();
+        assert!(
+            env::attached_deposit() >= ONE_YOCTO,
+            "Requires attached deposit of at least 1 yoctoNEAR"
+        );
+        let pyth_oracle_id = self
+            .internal_config()
+            .pyth_oracle_id
+            .expect("The Pyth oracle is not set");
+        assert!(!self.pyth_feeds.is_empty(), "The Pyth feeds are not set");
+        let account_id = env::predecessor_account_id();
+        let account = self.internal_unwrap_account(&account_id);
+        self.internal_assert_account_not_locked(&account_id);
+        self.internal_prevalidate_actions(&account, &actions);
+        let price_ids = self.pyth_feeds.values().map(|feed| feed.price_id).collect();
+        ext_pyth::update_price_feeds(
+            data,
+            pyth_oracle_id.clone(),
+            env::attached_deposit(),
+            GAS_FOR_PYTH_UPDATE_PRICE_FEEDS,
+        )
+        .then(ext_pyth::list_prices(
+            price_ids,
+            pyth_oracle_id,
+            NO_DEPOSIT,
+            GAS_FOR_PYTH_LIST_PRICES,
+        ))
+        .then(ext_self_pyth::after_pyth_list_prices(
+            account_id,
+            actions,
+            constraints,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_PYTH_LIST_PRICES,
+        ))
+    }
+
+    /// Sets the Pyth price feed of the asset.
+    /// - Panics if the asset doesn't exist or the price identifier is not 32 hex-encoded bytes.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn set_pyth_feed(&mut self, token_id: TokenId, feed: PythFeed) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_unwrap_asset(&token_id);
+        assert!(
+            feed.price_id.len() == 64 && feed.price_id.chars().all(|c| c.is_ascii_hexdigit()),
+            "Invalid Pyth price identifier"
+        );
+        self.pyth_feeds.insert(&token_id, &feed);
+    }
+
+    /// Removes the Pyth price feed of the asset.
+    /// - Panics if the feed is not set.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn remove_pyth_feed(&mut self, token_id: TokenId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.pyth_feeds
+            .remove(&token_id)
+            .expect("The Pyth feed is not set");
+    }
+
+    /// Returns the Pyth price feeds by token ID.
+    pub fn get_pyth_feeds(&self) -> HashMap<TokenId, PythFeed> {
+        self.pyth_feeds.iter().collect()
+    }
+}
+
+#[ext_contract(ext_pyth)]
+trait ExtPyth {
+    #[payable]
+    fn update_price_feeds(&mut self, data: String);
+
+    fn list_prices(&self, price_ids: Vec<String>) -> HashMap<String, Option<PythPrice>>;
+}
+
+#[ext_contract(ext_self_pyth)]
+trait ExtSelfPyth {
+    fn after_pyth_list_prices(
+        &mut self,
+        account_id: AccountId,
+        actions: Vec<Action>,
+        constraints: Option<ExecuteConstraints>,
+    );
+}
+
+pub trait ExtSelfPyth {
+    fn after_pyth_list_prices(
+        &mut self,
+        account_id: AccountId,
+        actions: Vec<Action>,
+        constraints: Option<ExecuteConstraints>,
+    );
+}
+
+#[near_bindgen]
+impl ExtSelfPyth for Contract {
+    /// Executes the actions with the prices read from Pyth after the update. The failed update is
+    /// ignored, since the prices that are too stale are rejected anyway.
+    #[private]
+    fn after_pyth_list_prices(
+        &mut self,
+        account_id: AccountId,
+        actions: Vec<Action>,
+        constraints: Option<ExecuteConstraints>,
+    ) {
+        let pyth_prices = match env::promise_result(0) {
+            PromiseResult::Successful(value) => {
+                serde_json::from_slice::<HashMap<String, Option<PythPrice>>>(&value).ok()
+            }
+            _ => None,
+        }
+        .expect("Failed to get the Pyth prices");
+        let data = self.internal_pyth_price_data(pyth_prices);
+        let timestamp = data.timestamp;
+        let mut account = self.internal_unwrap_account(&account_id);
+        self.validate_price_data(&data);
+        self.internal_emit_prices_used(&account_id, &data, contains_liquidations(&actions));
+        // The Pyth prices already have the decimals of the feeds, so the oracle price decimals of
+        // the assets are not applied.
+        let mut prices: Prices = data.into();
+        self.internal_apply_oracle_sources(
+            PriceSource::Pyth,
+            timestamp,
+            &mut prices,
+            contains_borrows_or_liquidations(&actions),
+        );
+        self.internal_add_lp_fair_prices(&mut prices);
+        self.internal_assert_twap_deviation(&prices);
+        self.internal_execute(&account_id, &mut account, actions, &prices);
+        if let Some(constraints) = constraints {
+            self.internal_assert_constraints(&account, &prices, &constraints);
+        }
+        self.internal_set_account(&account_id, account);
+    }
+}
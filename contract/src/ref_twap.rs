@@ -0,0 +1,293 @@
+use crate::*;
+use near_sdk::json_types::U128;
+use near_sdk::{serde_json, PromiseResult};
+
+const GAS_FOR_GET_POOL_BY_IDS: Gas = Gas(Gas::ONE_TERA.0 * 10);
+const GAS_FOR_AFTER_GET_POOL_BY_IDS: Gas = Gas(Gas::ONE_TERA.0 * 20);
+
+/// The number of the snapshots taken per TWAP window. The snapshots taken sooner than the window
+/// divided by this number after the previous snapshot are skipped.
+const TWAP_SNAPSHOTS_PER_WINDOW: u32 = 12;
+
+/// The part of the pool info of the Ref Finance exchange that is used for the TWAP.
+#[derive(Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RefPoolInfo {
+    pub token_account_ids: Vec<AccountId>,
+    pub amounts: Vec<U128>,
+}
+
+/// The reserves of the pool at the time of the snapshot, in the token decimals.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct TwapSnapshot {
+    pub timestamp: Timestamp,
+    #[serde(with = "u128_dec_format")]
+    pub amount: Balance,
+    #[serde(with = "u128_dec_format")]
+    pub quote_amount: Balance,
+}
+
+impl TwapSnapshot {
+    /// The price of a unit of the asset in the units of the quote token.
+    fn ratio(&self) -> BigDecimal {
+        BigDecimal::from(self.quote_amount) / BigDecimal::from(self.amount)
+    }
+}
+
+/// The Ref Finance pool of the asset and the quote token that is used to cross-check the oracle
+/// price of the asset with the time-weighted average price of the pool.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct TwapPair {
+    /// The ID of the pool on the exchange.
+    pub pool_id: u64,
+    /// The listed asset the asset is priced against in the pool.
+    pub quote_token_id: TokenId,
+    /// The maximum deviation of the oracle price from the TWAP (multiplied by 10000).
+    pub max_deviation: u32,
+    /// The duration of the TWAP window in seconds.
+    pub window_sec: u32,
+    /// The snapshots of the pool reserves in the window, ordered by time. The first snapshot can
+    /// be taken before the start of the window.
+    pub snapshots: Vec<TwapSnapshot>,
+}
+
+impl TwapPair {
+    /// Adds the snapshot of the pool reserves unless the previous snapshot is too recent, and
+    /// removes the snapshots that are out of the window. Returns whether the snapshot is added.
+    fn add_snapshot(&mut self, snapshot: TwapSnapshot) -> bool {
+        let min_interval = sec_to_nano(self.window_sec / TWAP_SNAPSHOTS_PER_WINDOW);
+        if let Some(last) = self.snapshots.last() {
+            if snapshot.timestamp < last.timestamp + min_interval {
+                return false;
+            }
+        }
+        let window_start = snapshot
+            .timestamp
+            .saturating_sub(sec_to_nano(self.window_sec));
+        self.snapshots.push(snapshot);
+        while self.snapshots.len() >= 2 && self.snapshots[1].timestamp <= window_start {
+            self.snapshots.remove(0);
+        }
+        true
+    }
+
+    /// Returns the time-weighted average price of a unit of the asset in the units of the quote
+    /// token over the window ending at the given timestamp, and the last snapshot. Returns `None`
+    /// if there are no snapshots or the last snapshot is out of the window.
+    fn twap(&self, timestamp: Timestamp) -> Option<(BigDecimal, &TwapSnapshot)> {
+        let last = self.snapshots.last()?;
+        let window_start = timestamp.saturating_sub(sec_to_nano(self.window_sec));
+        if last.timestamp < window_start {
+            return None;
+        }
+        let mut weighted_sum = BigDecimal::zero();
+        let mut duration = 0;
+        for (i, snapshot) in self.snapshots.iter().enumerate() {
+            let start = std::cmp::max(snapshot.timestamp, window_start);
+            let end = self
+                .snapshots
+                .get(i + 1)
+                .map(|next| next.timestamp)
+                .unwrap_or(timestamp);
+            if end > start {
+                weighted_sum = weighted_sum + snapshot.ratio() * BigDecimal::from(end - start);
+                duration += end - start;
+            }
+        }
+        if duration == 0 {
+            return Some((last.ratio(), last));
+        }
+        Some((weighted_sum / BigDecimal::from(duration), last))
+    }
+}
+
+impl Contract {
+    /// Asserts that the oracle prices of the assets with the TWAP pairs don't deviate from the
+    /// TWAP of the pools by more than the maximum deviation. It's the circuit breaker of the
+    /// oracle calls. The pairs without the prices or the assets of both tokens, or without the
+    /// recent snapshots are skipped.
+    pub fn internal_assert_twap_deviation(&self, prices: &Prices) {
+        let timestamp = env::block_timestamp();
+        for (token_id, twap_pair) in self.twap_pairs.iter() {
+            let (price, quote_price) =
+                match (prices.get(&token_id), prices.get(&twap_pair.quote_token_id)) {
+                    (Some(price), Some(quote_price)) => (price, quote_price),
+                    _ => continue,
+                };
+            let (twap_ratio, last) = match twap_pair.twap(timestamp) {
+                Some(twap) => twap,
+                None => continue,
+            };
+            let (extra_decimals, quote_extra_decimals) = match (
+                self.internal_get_asset(&token_id),
+                self.internal_get_asset(&twap_pair.quote_token_id),
+            ) {
+                (Some(asset), Some(quote_asset)) => (
+                    asset.config.extra_decimals,
+                    quote_asset.config.extra_decimals,
+                ),
+                _ => continue,
+            };
+            // The values of the same reserve of the asset at the oracle price and at the TWAP.
+            let value = BigDecimal::from_balance_price(
+                last.amount * 10u128.pow(extra_decimals as u32),
+                price,
+                extra_decimals,
+            );
+            let twap_value = BigDecimal::from_balance_price(
+                twap_ratio.round_mul_u128(last.amount) * 10u128.pow(quote_extra_decimals as u32),
+                quote_price,
+                quote_extra_decimals,
+            );
+            let deviation = if value > twap_value {
+                value - twap_value
+            } else {
+                twap_value - value
+            };
+            assert!(
+                deviation <= twap_value.mul_ratio(twap_pair.max_deviation),
+                "The oracle price of {} deviates from the TWAP",
+                token_id
+            );
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the Ref Finance pool of the asset and the quote token that is used to cross-check the
+    /// oracle price of the asset. The snapshots of the previous pool are removed.
+    /// - Panics if the asset or the quote asset doesn't exist.
+    /// - Panics if the max deviation is 0 or the window is shorter than 12 seconds.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn set_twap_pair(
+        &mut self,
+        token_id: AccountId,
+        pool_id: u64,
+        quote_token_id: AccountId,
+        max_deviation: u32,
+        window_sec: u32,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_unwrap_asset(&token_id);
+        self.internal_unwrap_asset(&quote_token_id);
+        assert_ne!(
+            token_id, quote_token_id,
+            "The asset and the quote asset should be different"
+        );
+        assert!(max_deviation > 0, "The max deviation can't be 0");
+        assert!(
+            window_sec >= TWAP_SNAPSHOTS_PER_WINDOW,
+            "The TWAP window is too short"
+        );
+        self.twap_pairs.insert(
+            &token_id,
+            &TwapPair {
+                pool_id,
+                quote_token_id,
+                max_deviation,
+                window_sec,
+                snapshots: vec![],
+            },
+        );
+    }
+
+    /// Removes the TWAP pair of the asset, so its oracle price is not cross-checked.
+    /// - Panics if the asset doesn't have the TWAP pair.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn remove_twap_pair(&mut self, token_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.twap_pairs
+            .remove(&token_id)
+            .expect("The TWAP pair doesn't exist");
+    }
+
+    /// Takes the snapshots of the reserves of the pools of the given TWAP pairs, or all of them
+    /// if not given. The snapshots taken too soon after the previous snapshot are skipped. Anyone
+    /// can call it, it's expected to be called periodically by a keeper.
+    pub fn sync_ref_twap(&mut self, token_ids: Option<Vec<TokenId>>) -> Promise {
+        let token_ids = token_ids.unwrap_or_else(|| self.twap_pairs.keys().collect());
+        let pool_ids = token_ids
+            .iter()
+            .map(|token_id| {
+                self.twap_pairs
+                    .get(token_id)
+                    .expect("The TWAP pair doesn't exist")
+                    .pool_id
+            })
+            .collect();
+        ext_ref_exchange::get_pool_by_ids(
+            pool_ids,
+            self.internal_unwrap_ref_exchange_id(),
+            NO_DEPOSIT,
+            GAS_FOR_GET_POOL_BY_IDS,
+        )
+        .then(ext_ref_twap::after_get_pool_by_ids(
+            token_ids,
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_GET_POOL_BY_IDS,
+        ))
+    }
+
+    /// Returns the TWAP pair of the asset with the snapshots, if any.
+    pub fn get_twap_pair(&self, token_id: AccountId) -> Option<TwapPair> {
+        self.twap_pairs.get(&token_id)
+    }
+}
+
+#[ext_contract(ext_ref_twap)]
+trait ExtRefTwap {
+    fn after_get_pool_by_ids(&mut self, token_ids: Vec<TokenId>);
+}
+
+pub trait ExtRefTwap {
+    fn after_get_pool_by_ids(&mut self, token_ids: Vec<TokenId>);
+}
+
+#[near_bindgen]
+impl ExtRefTwap for Contract {
+    /// Adds the snapshots of the pool reserves to the TWAP pairs.
+    #[private]
+    fn after_get_pool_by_ids(&mut self, token_ids: Vec<TokenId>) {
+        let pools = match env::promise_result(0) {
+            PromiseResult::Successful(value) => {
+                serde_json::from_slice::<Vec<RefPoolInfo>>(&value).ok()
+            }
+            _ => None,
+        }
+        .expect("Failed to get the pools");
+        let timestamp = env::block_timestamp();
+        for (token_id, pool) in token_ids.into_iter().zip(pools) {
+            let mut twap_pair = match self.twap_pairs.get(&token_id) {
+                Some(twap_pair) => twap_pair,
+                None => continue,
+            };
+            let amount_of = |id: &AccountId| {
+                pool.token_account_ids
+                    .iter()
+                    .position(|token_account_id| token_account_id == id)
+                    .map(|index| pool.amounts[index].0)
+                    .expect("The token is not in the pool")
+            };
+            let snapshot = TwapSnapshot {
+                timestamp,
+                amount: amount_of(&token_id),
+                quote_amount: amount_of(&twap_pair.quote_token_id),
+            };
+            if snapshot.amount > 0 && twap_pair.add_snapshot(snapshot) {
+                self.twap_pairs.insert(&token_id, &twap_pair);
+            }
+        }
+    }
+}
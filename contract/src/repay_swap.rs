@@ -0,0 +1,228 @@
+use crate::*;
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
+
+const GAS_FOR_AFTER_REPAY_SWAP: Gas = Gas(Gas::ONE_TERA.0 * 30);
+
+/// A swap of the supplied asset to repay the borrowed asset that is in progress for an account.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RepaySwap {
+    pub supplied_token_id: TokenId,
+    /// The supplied amount that was sent to the exchange.
+    #[serde(with = "u128_dec_format")]
+    pub amount: Balance,
+    pub borrowed_token_id: TokenId,
+    /// When the swap expires in nanoseconds. Anyone can roll back the expired swap with
+    /// `clear_expired_repay_swap`.
+    #[serde(with = "u64_dec_format")]
+    pub expiry_timestamp: Timestamp,
+}
+
+impl Contract {
+    /// Withdraws the supplied asset and swaps it on the exchange for the borrowed
+    /// asset. The output of the swap is supplied and used to repay the borrowed asset once
+    /// received.
+    pub fn internal_repay_from_supplied(
+        &mut self,
+        account: &mut Account,
+        supplied_token: TokenId,
+        amount: Option<U128>,
+        borrowed_token: TokenId,
+        mut route: Vec<SwapAction>,
+        dex_id: Option<AccountId>,
+    ) {
+        let account_id = &account.account_id.clone();
+        let dex = self
+            .internal_get_dex(dex_id)
+            .expect("Swaps are not enabled");
+        assert!(
+            !self.repay_swaps.contains_key(account_id),
+            "Repay swap is in progress"
+        );
+        assert_ne!(
+            supplied_token, borrowed_token,
+            "Supplied and borrowed tokens should be different"
+        );
+        account.internal_unwrap_borrowed(&borrowed_token);
+
+        let (shares, amount) = self.internal_withdraw(
+            account,
+            &AssetAmount {
+                token_id: supplied_token.clone(),
+                amount,
+                max_amount: None,
+            },
+        );
+        events::emit::withdraw(account_id, amount, shares, &supplied_token);
+        let asset = self.internal_unwrap_asset(&supplied_token);
+        let ft_amount = amount / 10u128.pow(asset.config.extra_decimals as u32);
+        assert!(ft_amount > 0, "Repay swap amount can't be 0");
+        let min_amount_out =
+            prepare_swap_route(&mut route, &supplied_token, &borrowed_token, ft_amount);
+
+        account.storage_tracker.start();
+        self.repay_swaps.insert(
+            account_id,
+            &RepaySwap {
+                supplied_token_id: supplied_token.clone(),
+                amount,
+                borrowed_token_id: borrowed_token.clone(),
+                expiry_timestamp: env::block_timestamp() + SWAP_EXPIRY,
+            },
+        );
+        account.storage_tracker.stop();
+        let borrowed_asset = self.internal_unwrap_asset(&borrowed_token);
+        events::emit::repay_swap_started(
+            account_id,
+            amount,
+            &supplied_token,
+            min_amount_out * 10u128.pow(borrowed_asset.config.extra_decimals as u32),
+            &borrowed_token,
+        );
+
+        let client_echo = json!({
+            "RepaySwapOutput": {
+                "account_id": account_id,
+            }
+        })
+        .to_string();
+        dex.swap(route, client_echo)
+            .then(ext_repay_swap::after_repay_swap(
+                account_id.clone(),
+                amount.into(),
+                env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_AFTER_REPAY_SWAP,
+            ));
+    }
+
+    /// Supplies the received output of the repay swap and uses it to repay the borrowed asset.
+    /// The remaining amount stays in the supplied balance.
+    pub fn internal_repay_swap_output(
+        &mut self,
+        sender_id: &AccountId,
+        token_id: &TokenId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) {
+        assert!(
+            self.internal_is_dex(sender_id),
+            "Only the exchange can transfer the repay swap output"
+        );
+        let repay_swap = self
+            .internal_track_account_storage(account_id, |contract| {
+                contract.repay_swaps.remove(account_id)
+            })
+            .expect("Repay swap is not in progress");
+        assert_eq!(
+            &repay_swap.borrowed_token_id, token_id,
+            "Unexpected repay swap output token"
+        );
+
+        let mut account = self.internal_unwrap_account(account_id);
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        let shares = self.internal_deposit(&mut account, token_id, amount);
+        events::emit::deposit(account_id, amount, shares, token_id);
+        // The debt might have been repaid while the swap was in progress.
+        if account.borrowed.contains_key(token_id) {
+            account.add_affected_farm(FarmId::Borrowed(token_id.clone()));
+            let mut account_asset = account.internal_unwrap_asset(token_id);
+            let (repaid_shares, repaid_amount) = self.internal_repay(
+                &mut account_asset,
+                &mut account,
+                &AssetAmount {
+                    token_id: token_id.clone(),
+                    amount: None,
+                    max_amount: None,
+                },
+            );
+            account.internal_set_asset(token_id, account_asset);
+            events::emit::repay(account_id, repaid_amount, repaid_shares, token_id);
+        }
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(account_id, account);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the repay swap in progress for the given account, if any.
+    pub fn get_repay_swap(&self, account_id: AccountId) -> Option<RepaySwap> {
+        self.repay_swaps.get(&account_id)
+    }
+
+    /// Rolls back the expired repay swap of the account. The output of the stuck swap is left with
+    /// the contract on the exchange, so the amount sent to the exchange is returned to the supplied
+    /// balance of the account from the reserve of the supplied asset. Anyone can roll back the
+    /// expired swap.
+    /// - Panics if the account doesn't have an expired repay swap.
+    /// - Panics if the reserve of the supplied asset is not enough to cover the amount.
+    pub fn clear_expired_repay_swap(&mut self, account_id: AccountId) {
+        let repay_swap = self
+            .repay_swaps
+            .get(&account_id)
+            .filter(|repay_swap| env::block_timestamp() >= repay_swap.expiry_timestamp)
+            .expect("The account doesn't have an expired repay swap");
+        self.internal_track_account_storage(&account_id, |contract| {
+            contract.repay_swaps.remove(&account_id)
+        });
+
+        let token_id = repay_swap.supplied_token_id;
+        let amount = repay_swap.amount;
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        assert!(
+            amount <= asset.reserved,
+            "The reserve is not enough to roll back the repay swap"
+        );
+        asset.reserved -= amount;
+        self.internal_set_asset(&token_id, asset);
+        let mut account = self.internal_unwrap_account(&account_id);
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        self.internal_deposit(&mut account, &token_id, amount);
+        events::emit::repay_swap_expired(&account_id, amount, &token_id);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+    }
+}
+
+#[ext_contract(ext_repay_swap)]
+trait ExtRepaySwap {
+    fn after_repay_swap(&mut self, account_id: AccountId, amount: U128) -> bool;
+}
+
+trait ExtRepaySwap {
+    fn after_repay_swap(&mut self, account_id: AccountId, amount: U128) -> bool;
+}
+
+#[near_bindgen]
+impl ExtRepaySwap for Contract {
+    /// Returns the supplied amount refunded by the exchange to the supplied balance if the swap
+    /// has failed. Returns whether the swap has succeeded.
+    #[private]
+    fn after_repay_swap(&mut self, account_id: AccountId, amount: U128) -> bool {
+        let repay_swap = match self.repay_swaps.get(&account_id) {
+            Some(repay_swap) if repay_swap.amount == amount.0 => repay_swap,
+            // The swap output was already received.
+            _ => return true,
+        };
+        let token_id = repay_swap.supplied_token_id;
+        let asset = self.internal_unwrap_asset(&token_id);
+        let refunded = transfer_call_refunded_amount(amount.0, asset.config.extra_decimals);
+        if refunded == 0 {
+            return true;
+        }
+
+        self.internal_track_account_storage(&account_id, |contract| {
+            contract.repay_swaps.remove(&account_id)
+        });
+        let mut account = self.internal_unwrap_account(&account_id);
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        self.internal_deposit(&mut account, &token_id, refunded);
+        events::emit::repay_swap_failed(&account_id, refunded, &token_id);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+        false
+    }
+}
@@ -0,0 +1,298 @@
+use crate::*;
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
+
+const GAS_FOR_AFTER_RESERVE_STAKING_SWAP: Gas = Gas(Gas::ONE_TERA.0 * 20);
+
+/// The config of the staking of a part of the reserve, e.g. of wNEAR, into a liquid staking token.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReserveStakingConfig {
+    /// The asset with the staked reserve, e.g. wNEAR.
+    pub token_id: TokenId,
+    /// The whitelisted liquid staking token, e.g. stNEAR or LiNEAR. It has to be listed as an
+    /// asset to receive it from the exchange, but the staked tokens are not added to its pools.
+    pub staking_token_id: TokenId,
+    /// The maximum part of the reserve that can be staked (multiplied by 10000), counting the
+    /// staked part. E.g. 3000 means 30%.
+    pub max_staked_ratio: u32,
+}
+
+/// The swap of the reserve staking that is in progress.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub enum ReserveStakingSwap {
+    /// The swap of the given reserve amount into the staking token.
+    Stake {
+        #[serde(with = "u128_dec_format")]
+        amount: Balance,
+    },
+    /// The swap of the given staking token amount with the given staked principal back into the
+    /// asset.
+    Unstake {
+        #[serde(with = "u128_dec_format")]
+        staking_amount: Balance,
+        #[serde(with = "u128_dec_format")]
+        principal: Balance,
+    },
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Clone, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReserveStaking {
+    /// The reserve that was swapped into the staking token, i.e. the staked principal, in the inner
+    /// balance of the asset.
+    #[serde(with = "u128_dec_format")]
+    pub staked_amount: Balance,
+    /// The amount of the staking token held by the contract, in the inner balance of the staking
+    /// token asset.
+    #[serde(with = "u128_dec_format")]
+    pub staking_token_balance: Balance,
+    pub swap: Option<ReserveStakingSwap>,
+}
+
+impl Contract {
+    fn internal_unwrap_reserve_staking_config(&self) -> ReserveStakingConfig {
+        self.internal_config()
+            .reserve_staking
+            .expect("The reserve staking is not enabled")
+    }
+
+    fn internal_reserve_staking_swap(
+        &mut self,
+        swap: ReserveStakingSwap,
+        swap_route: Vec<SwapAction>,
+        dex_id: Option<AccountId>,
+        amount: Balance,
+    ) {
+        let dex = self
+            .internal_get_dex(dex_id)
+            .expect("Swaps are not enabled");
+        self.reserve_staking.swap = Some(swap);
+        let client_echo = json!("ReserveStakingOutput").to_string();
+        dex.swap(swap_route, client_echo)
+            .then(ext_reserve_staking::after_reserve_staking_swap(
+                amount.into(),
+                env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_AFTER_RESERVE_STAKING_SWAP,
+            ));
+    }
+
+    /// Receives the output of the reserve staking swap from the exchange. The staking token is
+    /// added to the staked balance, and the unstaked asset is added to the reserve including the
+    /// staking yield.
+    pub fn internal_reserve_staking_output(
+        &mut self,
+        sender_id: &AccountId,
+        token_id: &TokenId,
+        amount: Balance,
+    ) {
+        assert!(
+            self.internal_is_dex(sender_id),
+            "Only the exchange can transfer the reserve staking output"
+        );
+        let config = self.internal_unwrap_reserve_staking_config();
+        let swap = self
+            .reserve_staking
+            .swap
+            .take()
+            .expect("Reserve staking swap is not in progress");
+        match swap {
+            ReserveStakingSwap::Stake { amount: principal } => {
+                assert_eq!(
+                    token_id, &config.staking_token_id,
+                    "Unexpected reserve staking output token"
+                );
+                self.reserve_staking.staked_amount += principal;
+                self.reserve_staking.staking_token_balance += amount;
+            }
+            ReserveStakingSwap::Unstake { principal, .. } => {
+                assert_eq!(
+                    token_id, &config.token_id,
+                    "Unexpected reserve staking output token"
+                );
+                self.reserve_staking.staked_amount -= principal;
+                let mut asset = self.internal_unwrap_asset(token_id);
+                asset.reserved += amount;
+                self.internal_set_asset(token_id, asset);
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Swaps the given amount of the reserve of the asset into the liquid staking token on the
+    /// exchange, so the staking yield accrues to the reserve.
+    /// - Panics if the reserve staking is not enabled, a swap is in progress, or the staked part
+    ///   of the reserve would exceed the `max_staked_ratio`.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn stake_reserve(
+        &mut self,
+        amount: U128,
+        swap_route: Vec<SwapAction>,
+        dex_id: Option<AccountId>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        let config = self.internal_unwrap_reserve_staking_config();
+        assert!(
+            self.reserve_staking.swap.is_none(),
+            "Reserve staking swap is in progress"
+        );
+        let mut asset = self.internal_unwrap_asset(&config.token_id);
+        let amount = amount.0;
+        assert!(amount <= asset.reserved, "Not enough reserve");
+        let staked_amount = self.reserve_staking.staked_amount + amount;
+        assert!(
+            staked_amount
+                <= ratio(
+                    asset.reserved + self.reserve_staking.staked_amount,
+                    config.max_staked_ratio
+                ),
+            "The staked part of the reserve would exceed the limit"
+        );
+        let ft_amount = amount / 10u128.pow(asset.config.extra_decimals as u32);
+        assert!(ft_amount > 0, "The amount can't be 0");
+        let amount = ft_amount * 10u128.pow(asset.config.extra_decimals as u32);
+        let mut swap_route = swap_route;
+        prepare_swap_route(
+            &mut swap_route,
+            &config.token_id,
+            &config.staking_token_id,
+            ft_amount,
+        );
+        asset.reserved -= amount;
+        self.internal_set_asset(&config.token_id, asset);
+        self.internal_reserve_staking_swap(
+            ReserveStakingSwap::Stake { amount },
+            swap_route,
+            dex_id,
+            amount,
+        );
+    }
+
+    /// Swaps the given amount of the staking token (or the entire staked balance if not given)
+    /// back into the asset on the exchange, and adds the output to the reserve. The owner can
+    /// unstake any time. Anyone else can only unstake when the reserve liquidity is needed, i.e.
+    /// the available amount of the asset is below the staked principal, and the minimum amount
+    /// out of the swap has to cover the unstaked principal.
+    /// - Panics if the reserve staking is not enabled or a swap is in progress.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn unstake_reserve(
+        &mut self,
+        staking_amount: Option<U128>,
+        swap_route: Vec<SwapAction>,
+        dex_id: Option<AccountId>,
+    ) {
+        assert_one_yocto();
+        let config = self.internal_unwrap_reserve_staking_config();
+        assert!(
+            self.reserve_staking.swap.is_none(),
+            "Reserve staking swap is in progress"
+        );
+        let staking_token_balance = self.reserve_staking.staking_token_balance;
+        let staking_amount = staking_amount
+            .map(|amount| amount.0)
+            .unwrap_or(staking_token_balance);
+        assert!(
+            staking_amount > 0 && staking_amount <= staking_token_balance,
+            "Invalid staking token amount"
+        );
+        let staking_asset = self.internal_unwrap_asset(&config.staking_token_id);
+        let multiplier = 10u128.pow(staking_asset.config.extra_decimals as u32);
+        let ft_staking_amount = staking_amount / multiplier;
+        assert!(ft_staking_amount > 0, "The amount can't be 0");
+        let staking_amount = ft_staking_amount * multiplier;
+        let principal = u128_ratio(
+            self.reserve_staking.staked_amount,
+            staking_amount,
+            staking_token_balance,
+        );
+        let mut swap_route = swap_route;
+        let min_amount_out = prepare_swap_route(
+            &mut swap_route,
+            &config.staking_token_id,
+            &config.token_id,
+            ft_staking_amount,
+        );
+        if env::predecessor_account_id() != self.internal_config().owner_id {
+            let asset = self.internal_unwrap_asset(&config.token_id);
+            assert!(
+                asset.available_amount() < self.reserve_staking.staked_amount,
+                "The reserve liquidity is not needed"
+            );
+            assert!(
+                min_amount_out * 10u128.pow(asset.config.extra_decimals as u32) >= principal,
+                "The minimum amount out doesn't cover the staked principal"
+            );
+        }
+        self.reserve_staking.staking_token_balance -= staking_amount;
+        self.internal_reserve_staking_swap(
+            ReserveStakingSwap::Unstake {
+                staking_amount,
+                principal,
+            },
+            swap_route,
+            dex_id,
+            staking_amount,
+        );
+    }
+
+    /// Returns the state of the reserve staking.
+    pub fn get_reserve_staking(&self) -> ReserveStaking {
+        self.reserve_staking.clone()
+    }
+}
+
+#[ext_contract(ext_reserve_staking)]
+trait ExtReserveStaking {
+    fn after_reserve_staking_swap(&mut self, amount: U128) -> bool;
+}
+
+trait ExtReserveStaking {
+    fn after_reserve_staking_swap(&mut self, amount: U128) -> bool;
+}
+
+#[near_bindgen]
+impl ExtReserveStaking for Contract {
+    /// Returns the swap input refunded by the exchange to the reserve or to the staked balance if
+    /// the swap has failed. Returns whether the swap has succeeded.
+    #[private]
+    fn after_reserve_staking_swap(&mut self, amount: U128) -> bool {
+        let swap = match self.reserve_staking.swap.clone() {
+            Some(swap) => swap,
+            // The swap output was already received.
+            None => return true,
+        };
+        let config = self.internal_unwrap_reserve_staking_config();
+        let input_token_id = match &swap {
+            ReserveStakingSwap::Stake { .. } => &config.token_id,
+            ReserveStakingSwap::Unstake { .. } => &config.staking_token_id,
+        };
+        let input_asset = self.internal_unwrap_asset(input_token_id);
+        let refunded = transfer_call_refunded_amount(amount.0, input_asset.config.extra_decimals);
+        if refunded == 0 {
+            return true;
+        }
+        self.reserve_staking.swap = None;
+        match swap {
+            ReserveStakingSwap::Stake { .. } => {
+                let mut asset = input_asset;
+                asset.reserved += refunded;
+                self.internal_set_asset(&config.token_id, asset);
+            }
+            ReserveStakingSwap::Unstake { .. } => {
+                self.reserve_staking.staking_token_balance += refunded;
+            }
+        }
+        false
+    }
+}
@@ -0,0 +1,234 @@
+use crate::*;
+use near_sdk::is_promise_success;
+
+const GAS_FOR_RESERVE_WITHDRAWAL: Gas = Gas(Gas::ONE_TERA.0 * 10);
+const GAS_FOR_AFTER_RESERVE_WITHDRAWAL: Gas = Gas(Gas::ONE_TERA.0 * 20);
+
+/// The state of the reserve withdrawals of an asset.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Default)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct ReserveWithdrawal {
+    /// The announced withdrawal that is waiting for the delay.
+    pub announced_withdrawal: Option<AnnouncedReserveWithdrawal>,
+    /// The epoch height of the last withdrawal.
+    #[serde(with = "u64_dec_format")]
+    pub epoch_height: u64,
+    /// The amount withdrawn from the reserve during the epoch of the last withdrawal.
+    #[serde(with = "u128_dec_format")]
+    pub epoch_withdrawn: Balance,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AnnouncedReserveWithdrawal {
+    /// The maximum amount that can be withdrawn.
+    #[serde(with = "u128_dec_format")]
+    pub amount: Balance,
+    pub receiver_id: AccountId,
+    /// When the withdrawal can be executed in nanoseconds.
+    #[serde(with = "u64_dec_format")]
+    pub unlock_timestamp: Timestamp,
+}
+
+impl Contract {
+    /// Asserts that the withdrawal matches the announced withdrawal and its delay has passed.
+    /// Withdrawals don't have to be announced if the delay is 0.
+    fn internal_use_announced_reserve_withdrawal(
+        reserve_withdrawal: &mut ReserveWithdrawal,
+        amount: Balance,
+        receiver_id: &AccountId,
+        delay_sec: DurationSec,
+    ) {
+        if delay_sec == 0 {
+            return;
+        }
+        let announced_withdrawal = reserve_withdrawal
+            .announced_withdrawal
+            .take()
+            .expect("The reserve withdrawal has to be announced");
+        assert!(
+            amount <= announced_withdrawal.amount
+                && receiver_id == &announced_withdrawal.receiver_id,
+            "The reserve withdrawal doesn't match the announced withdrawal"
+        );
+        assert!(
+            env::block_timestamp() >= announced_withdrawal.unlock_timestamp,
+            "The announced reserve withdrawal is not unlocked yet"
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Announces the withdrawal of up to the given amount from the reserve of the asset to the
+    /// receiver. The withdrawal can be executed after the reserve withdrawal delay from the
+    /// config. Replaces the previously announced withdrawal of the asset.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn announce_reserve_withdrawal(
+        &mut self,
+        token_id: AccountId,
+        amount: U128,
+        receiver_id: AccountId,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_unwrap_asset(&token_id);
+        let unlock_timestamp = env::block_timestamp()
+            + sec_to_nano(self.internal_config().reserve_withdrawal_delay_sec);
+        let mut reserve_withdrawal = self.reserve_withdrawals.get(&token_id).unwrap_or_default();
+        reserve_withdrawal.announced_withdrawal = Some(AnnouncedReserveWithdrawal {
+            amount: amount.0,
+            receiver_id: receiver_id.clone(),
+            unlock_timestamp,
+        });
+        self.reserve_withdrawals
+            .insert(&token_id, &reserve_withdrawal);
+        events::emit::reserve_withdrawal_announced(&receiver_id, amount.0, &token_id);
+    }
+
+    /// Cancels the announced reserve withdrawal of the asset.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn cancel_reserve_withdrawal(&mut self, token_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut reserve_withdrawal = self
+            .reserve_withdrawals
+            .get(&token_id)
+            .expect("No announced reserve withdrawal");
+        reserve_withdrawal
+            .announced_withdrawal
+            .take()
+            .expect("No announced reserve withdrawal");
+        self.reserve_withdrawals
+            .insert(&token_id, &reserve_withdrawal);
+    }
+
+    /// Withdraws the given amount from the reserve of the asset and transfers it to the receiver.
+    /// The amount is given in the inner balance of the asset including the extra decimals.
+    /// - Panics if the withdrawal was not announced and the reserve withdrawal delay is not 0.
+    /// - Panics if the amount exceeds the reserve, the available amount of the asset or the
+    ///   remaining reserve withdrawal limit of the current epoch.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn withdraw_reserve(&mut self, token_id: AccountId, amount: U128, receiver_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        let config = self.internal_config();
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        let mut reserve_withdrawal = self.reserve_withdrawals.get(&token_id).unwrap_or_default();
+        Self::internal_use_announced_reserve_withdrawal(
+            &mut reserve_withdrawal,
+            amount.0,
+            &receiver_id,
+            config.reserve_withdrawal_delay_sec,
+        );
+
+        let multiplier = 10u128.pow(asset.config.extra_decimals as u32);
+        let ft_amount = amount.0 / multiplier;
+        let amount = ft_amount * multiplier;
+        assert!(
+            amount > 0 && amount <= asset.reserved && amount <= asset.available_amount(),
+            "Not enough reserved balance"
+        );
+
+        let epoch_height = env::epoch_height();
+        if reserve_withdrawal.epoch_height != epoch_height {
+            reserve_withdrawal.epoch_height = epoch_height;
+            reserve_withdrawal.epoch_withdrawn = 0;
+        }
+        reserve_withdrawal.epoch_withdrawn += amount;
+        assert!(
+            reserve_withdrawal.epoch_withdrawn
+                <= ratio(
+                    asset.reserved + reserve_withdrawal.epoch_withdrawn - amount,
+                    config.max_reserve_withdrawal_ratio_per_epoch,
+                ),
+            "The reserve withdrawal limit of the epoch is exceeded"
+        );
+        self.reserve_withdrawals
+            .insert(&token_id, &reserve_withdrawal);
+
+        let withdrawn_protocol_fees = std::cmp::min(amount, asset.get_pending_protocol_fees());
+        asset.withdraw_reserved(amount);
+        self.internal_set_asset(&token_id, asset);
+        events::emit::reserve_withdrawal_started(&receiver_id, amount, &token_id);
+
+        self.internal_token_transfer(
+            &receiver_id,
+            &token_id,
+            ft_amount,
+            GAS_FOR_RESERVE_WITHDRAWAL,
+        )
+        .then(ext_reserve_withdrawal::after_reserve_withdrawal(
+            receiver_id,
+            token_id,
+            amount.into(),
+            withdrawn_protocol_fees.into(),
+            env::current_account_id(),
+            NO_DEPOSIT,
+            GAS_FOR_AFTER_RESERVE_WITHDRAWAL,
+        ));
+    }
+
+    /// Returns the reserve withdrawal state of the asset, including the announced withdrawal.
+    pub fn get_reserve_withdrawal(&self, token_id: AccountId) -> Option<ReserveWithdrawal> {
+        self.reserve_withdrawals.get(&token_id)
+    }
+}
+
+#[ext_contract(ext_reserve_withdrawal)]
+trait ExtReserveWithdrawal {
+    fn after_reserve_withdrawal(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        withdrawn_protocol_fees: U128,
+    ) -> bool;
+}
+
+trait ExtReserveWithdrawal {
+    fn after_reserve_withdrawal(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        withdrawn_protocol_fees: U128,
+    ) -> bool;
+}
+
+#[near_bindgen]
+impl ExtReserveWithdrawal for Contract {
+    /// Returns the amount back to the reserve and diagnoses the failure if the transfer has failed.
+    #[private]
+    fn after_reserve_withdrawal(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        withdrawn_protocol_fees: U128,
+    ) -> bool {
+        let promise_success = is_promise_success();
+        if !promise_success {
+            let mut asset = self.internal_unwrap_asset(&token_id);
+            asset.reserved += amount.0;
+            asset.withdrawn_protocol_fees -= withdrawn_protocol_fees.0;
+            self.internal_set_asset(&token_id, asset);
+            events::emit::reserve_withdrawal_failed(&receiver_id, amount.0, &token_id);
+            self.internal_diagnose_transfer_failure(
+                &receiver_id,
+                &token_id,
+                amount.0,
+                TransferRecovery::Reserve,
+            );
+        }
+        promise_success
+    }
+}
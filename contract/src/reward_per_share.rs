@@ -0,0 +1,94 @@
+use crate::*;
+use near_sdk::borsh::maybestd::io::Write;
+
+/// The same decimals as the `BigDecimal`.
+const REWARD_PER_SHARE_DIVISOR: u128 = 10u128.pow(27);
+/// The number of 64-bit words of the stored `BigDecimal`.
+const NUM_STORED_WORDS: usize = 6;
+
+/// The accumulated amount of the farm reward per boosted share, a fixed-point number with 27
+/// decimals. The farm accumulation uses the u256 arithmetic instead of the `BigDecimal`, while
+/// it's stored with the same Borsh representation as the `BigDecimal` with the same decimals,
+/// so the existing farms don't have to be migrated.
+/// The rewards per share never exceed the total amount of rewards of a farm multiplied by the
+/// decimals, so they fit into u256 with the product by the shares of any account.
+#[derive(Copy, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct RewardPerShare(pub(crate) U256);
+
+impl RewardPerShare {
+    /// Distributes the rewards over the given boosted shares. The rewards per share round down,
+    /// so the farm never distributes more than it has acquired.
+    pub fn add_rewards(&mut self, rewards: Balance, boosted_shares: Balance) {
+        self.0 +=
+            U256::from(rewards) * U256::from(REWARD_PER_SHARE_DIVISOR) / U256::from(boosted_shares);
+    }
+
+    /// Returns the rewards of the given boosted shares accumulated since the `last` rewards per
+    /// share, rounded half up.
+    pub fn rewards_since(&self, last: &RewardPerShare, boosted_shares: Balance) -> Balance {
+        (((self.0 - last.0) * U256::from(boosted_shares)
+            + U256::from(REWARD_PER_SHARE_DIVISOR / 2))
+            / U256::from(REWARD_PER_SHARE_DIVISOR))
+        .as_u128()
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl std::fmt::Debug for RewardPerShare {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", BigDecimal::from(*self))
+    }
+}
+
+impl BorshSerialize for RewardPerShare {
+    fn serialize<W: Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        let mut words = [0u64; NUM_STORED_WORDS];
+        words[..4].copy_from_slice(&self.0 .0);
+        BorshSerialize::serialize(&words, writer)
+    }
+}
+
+impl BorshDeserialize for RewardPerShare {
+    fn deserialize(buf: &mut &[u8]) -> std::io::Result<Self> {
+        let words: [u64; NUM_STORED_WORDS] = BorshDeserialize::deserialize(buf)?;
+        if words[4..].iter().any(|&word| word != 0) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "The reward per share doesn't fit into u256",
+            ));
+        }
+        let mut low_words = [0u64; 4];
+        low_words.copy_from_slice(&words[..4]);
+        Ok(Self(U256(low_words)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_borsh_matches_big_decimal() {
+        let big_decimal = BigDecimal::from(123456789u128) / BigDecimal::from(7u128);
+        let reward_per_share = RewardPerShare::from(big_decimal);
+        let bytes = big_decimal.try_to_vec().unwrap();
+        assert_eq!(reward_per_share.try_to_vec().unwrap(), bytes);
+        assert!(
+            RewardPerShare::try_from_slice(&bytes).unwrap() == reward_per_share,
+            "The reward per share doesn't round trip"
+        );
+    }
+
+    #[test]
+    fn test_accumulation_matches_big_decimal() {
+        let (rewards, boosted_shares, account_shares) = (10u128.pow(24), 3 * 10u128.pow(18), 7);
+        let big_decimal = BigDecimal::from(rewards) / BigDecimal::from(boosted_shares);
+        let mut reward_per_share = RewardPerShare::default();
+        reward_per_share.add_rewards(rewards, boosted_shares);
+        assert!(reward_per_share == RewardPerShare::from(big_decimal));
+        assert_eq!(
+            reward_per_share.rewards_since(&RewardPerShare::default(), account_shares),
+            big_decimal.round_mul_u128(account_shares)
+        );
+    }
+}
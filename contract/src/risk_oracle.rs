@@ -0,0 +1,155 @@
+use crate::*;
+
+/// The guardrails of the risk parameters of an asset that the risk oracle can update.
+/// The risk oracle can't update the asset without the bounds.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RiskOracleBounds {
+    /// The bounds of the volatility ratio (multiplied by 10000).
+    pub min_volatility_ratio: u32,
+    pub max_volatility_ratio: u32,
+    /// The maximum change of the volatility ratio in one update (multiplied by 10000).
+    /// E.g. 500 means the volatility ratio can change by at most 5% per update.
+    pub max_volatility_ratio_change: u32,
+    /// The bounds of the target utilization (multiplied by 10000).
+    pub min_target_utilization: u32,
+    pub max_target_utilization: u32,
+    /// The bounds of the compounding rate at the target utilization.
+    pub min_target_utilization_rate: LowU128,
+    pub max_target_utilization_rate: LowU128,
+    /// The bounds of the compounding rate at 100% utilization.
+    pub min_max_utilization_rate: LowU128,
+    pub max_max_utilization_rate: LowU128,
+}
+
+impl RiskOracleBounds {
+    pub fn assert_valid(&self) {
+        assert!(
+            self.min_volatility_ratio <= self.max_volatility_ratio
+                && self.min_target_utilization <= self.max_target_utilization
+                && self.min_target_utilization_rate.0 <= self.max_target_utilization_rate.0
+                && self.min_max_utilization_rate.0 <= self.max_max_utilization_rate.0,
+            "The min bound should be no more than the max bound"
+        );
+    }
+
+    /// Asserts that the updated parameters of the asset config are within the bounds.
+    fn assert_within_bounds(&self, old_config: &AssetConfig, new_config: &AssetConfig) {
+        if new_config.volatility_ratio != old_config.volatility_ratio {
+            assert!(
+                (self.min_volatility_ratio..=self.max_volatility_ratio)
+                    .contains(&new_config.volatility_ratio),
+                "The volatility ratio is out of the risk oracle bounds"
+            );
+            let change = std::cmp::max(new_config.volatility_ratio, old_config.volatility_ratio)
+                - std::cmp::min(new_config.volatility_ratio, old_config.volatility_ratio);
+            assert!(
+                change <= self.max_volatility_ratio_change,
+                "The volatility ratio change exceeds the risk oracle bounds"
+            );
+        }
+        if new_config.target_utilization != old_config.target_utilization {
+            assert!(
+                (self.min_target_utilization..=self.max_target_utilization)
+                    .contains(&new_config.target_utilization),
+                "The target utilization is out of the risk oracle bounds"
+            );
+        }
+        if new_config.target_utilization_rate != old_config.target_utilization_rate {
+            assert!(
+                (self.min_target_utilization_rate.0..=self.max_target_utilization_rate.0)
+                    .contains(&new_config.target_utilization_rate.0),
+                "The target utilization rate is out of the risk oracle bounds"
+            );
+        }
+        if new_config.max_utilization_rate != old_config.max_utilization_rate {
+            assert!(
+                (self.min_max_utilization_rate.0..=self.max_max_utilization_rate.0)
+                    .contains(&new_config.max_utilization_rate.0),
+                "The max utilization rate is out of the risk oracle bounds"
+            );
+        }
+    }
+}
+
+impl Contract {
+    pub fn assert_risk_oracle(&self) {
+        assert_eq!(
+            Some(env::predecessor_account_id()),
+            self.internal_config().risk_oracle_account_id,
+            "Not a risk oracle"
+        );
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the bounds of the risk parameters of the asset that the risk oracle can update.
+    /// Removes the bounds if not given, so the risk oracle can't update the asset.
+    /// - Panics if an asset with the given token_id doesn't exist.
+    /// - Panics if the bounds are invalid.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn set_risk_oracle_bounds(
+        &mut self,
+        token_id: AccountId,
+        bounds: Option<RiskOracleBounds>,
+    ) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_unwrap_asset(&token_id);
+        if let Some(bounds) = bounds {
+            bounds.assert_valid();
+            self.risk_oracle_bounds.insert(&token_id, &bounds);
+        } else {
+            self.risk_oracle_bounds.remove(&token_id);
+        }
+    }
+
+    /// Updates the risk parameters of the asset within the bounds set by the owner. The
+    /// parameters that are not given are not changed. The updates don't require the proposals,
+    /// because they are bounded by the owner.
+    /// - Panics if the asset doesn't have the risk oracle bounds.
+    /// - Panics if the updated parameters are out of the bounds or the asset config is invalid.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the risk oracle.
+    #[payable]
+    pub fn update_asset_risk_parameters(
+        &mut self,
+        token_id: AccountId,
+        volatility_ratio: Option<u32>,
+        target_utilization: Option<u32>,
+        target_utilization_rate: Option<LowU128>,
+        max_utilization_rate: Option<LowU128>,
+    ) {
+        assert_one_yocto();
+        self.assert_risk_oracle();
+        let bounds = self
+            .risk_oracle_bounds
+            .get(&token_id)
+            .expect("The asset doesn't have the risk oracle bounds");
+        let old_asset_config = self.internal_unwrap_asset(&token_id).config;
+        let mut asset_config = old_asset_config.clone();
+        if let Some(volatility_ratio) = volatility_ratio {
+            asset_config.volatility_ratio = volatility_ratio;
+        }
+        if let Some(target_utilization) = target_utilization {
+            asset_config.target_utilization = target_utilization;
+        }
+        if let Some(target_utilization_rate) = target_utilization_rate {
+            asset_config.target_utilization_rate = target_utilization_rate;
+        }
+        if let Some(max_utilization_rate) = max_utilization_rate {
+            asset_config.max_utilization_rate = max_utilization_rate;
+        }
+        bounds.assert_within_bounds(&old_asset_config, &asset_config);
+        self.internal_update_asset(&token_id, asset_config);
+    }
+
+    /// Returns the bounds of the risk parameters of the asset that the risk oracle can update.
+    pub fn get_risk_oracle_bounds(&self, token_id: AccountId) -> Option<RiskOracleBounds> {
+        self.risk_oracle_bounds.get(&token_id)
+    }
+}
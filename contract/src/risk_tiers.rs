@@ -0,0 +1,71 @@
+use crate::*;
+
+/// The risk tier of an asset. The risk tier policy of the config limits which tiers of the
+/// borrowed assets can be backed by the collateral of each tier.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum AssetTier {
+    /// The most liquid assets, e.g. the major stablecoins and wNEAR.
+    BlueChip,
+    Mid,
+    /// The volatile assets with thin liquidity.
+    LongTail,
+}
+
+/// The rule of the risk tier policy for the collateral of the given tier.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct RiskTierRule {
+    pub collateral_tier: AssetTier,
+    /// The tiers of the assets that can be borrowed by an account with the collateral of the
+    /// `collateral_tier`.
+    pub borrowable_tiers: Vec<AssetTier>,
+}
+
+impl Config {
+    /// Whether the collateral of the given tier can back the borrowed asset of the given tier.
+    /// The tiers without a rule are not restricted.
+    pub fn is_tier_borrowable(&self, collateral_tier: AssetTier, borrowed_tier: AssetTier) -> bool {
+        self.risk_tier_policy
+            .iter()
+            .find(|rule| rule.collateral_tier == collateral_tier)
+            .map(|rule| rule.borrowable_tiers.contains(&borrowed_tier))
+            .unwrap_or(true)
+    }
+}
+
+impl Contract {
+    /// Asserts that every collateral asset of the account can back every borrowed asset of the
+    /// account under the risk tier policy. The assets without a risk tier are not restricted.
+    pub fn internal_assert_risk_tiers(&self, account: &Account) {
+        let config = self.internal_config();
+        if config.risk_tier_policy.is_empty() || account.borrowed.is_empty() {
+            return;
+        }
+        let tiers = |token_ids: Vec<&TokenId>| -> Vec<(TokenId, AssetTier)> {
+            token_ids
+                .into_iter()
+                .filter_map(|token_id| {
+                    self.internal_unwrap_asset(token_id)
+                        .config
+                        .risk_tier
+                        .map(|tier| (token_id.clone(), tier))
+                })
+                .collect()
+        };
+        let collateral_tiers = tiers(account.collateral.keys().collect());
+        let borrowed_tiers = tiers(account.borrowed.keys().collect());
+        for (collateral_token_id, collateral_tier) in &collateral_tiers {
+            for (borrowed_token_id, borrowed_tier) in &borrowed_tiers {
+                assert!(
+                    config.is_tier_borrowable(*collateral_tier, *borrowed_tier),
+                    "The collateral {} can't back the borrowed {} under the risk tier policy",
+                    collateral_token_id,
+                    borrowed_token_id
+                );
+            }
+        }
+    }
+}
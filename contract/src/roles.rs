@@ -0,0 +1,123 @@
+use crate::*;
+
+/// The roles that the owner can delegate to other accounts. The owner can call every method of
+/// every role.
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Clone,
+    Copy,
+    Hash,
+    Eq,
+    PartialEq,
+    Ord,
+    PartialOrd,
+)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum Role {
+    /// Can update the configs of the existing assets and resume the paused actions.
+    RiskAdmin,
+    /// Can pause the actions of the assets, but can't resume them.
+    EmergencyAdmin,
+    /// Can add and update the asset farm rewards.
+    FarmManager,
+    /// Can claim the protocol fees from the reserves.
+    FeeCollector,
+    /// Can manage the allowlists of the permissioned assets.
+    ComplianceManager,
+}
+
+impl Contract {
+    pub fn internal_has_role(&self, account_id: &AccountId, role: Role) -> bool {
+        self.internal_config()
+            .roles
+            .get(&role)
+            .map(|account_ids| account_ids.contains(account_id))
+            .unwrap_or(false)
+    }
+
+    pub fn assert_owner_or_role(&self, role: Role) {
+        let predecessor_id = env::predecessor_account_id();
+        assert!(
+            predecessor_id == self.internal_config().owner_id
+                || self.internal_has_role(&predecessor_id, role),
+            "Not an owner or an account with the required role"
+        );
+    }
+
+    pub fn internal_grant_role(&mut self, role: Role, account_id: AccountId) {
+        let mut config = self.internal_config();
+        let account_ids = config.roles.entry(role).or_default();
+        assert!(
+            !account_ids.contains(&account_id),
+            "The account already has the role"
+        );
+        events::emit::role_granted(role, &account_id);
+        account_ids.push(account_id);
+        self.internal_set_config(config);
+    }
+
+    pub fn internal_revoke_role(&mut self, role: Role, account_id: &AccountId) {
+        let mut config = self.internal_config();
+        let account_ids = config.roles.entry(role).or_default();
+        let index = account_ids
+            .iter()
+            .position(|id| id == account_id)
+            .expect("The account doesn't have the role");
+        account_ids.remove(index);
+        events::emit::role_revoked(role, account_id);
+        if account_ids.is_empty() {
+            config.roles.remove(&role);
+        }
+        self.internal_set_config(config);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Grants the role to the account.
+    /// - Panics if the account already has the role.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn grant_role(&mut self, role: Role, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_grant_role(role, account_id);
+    }
+
+    /// Revokes the role from the account.
+    /// - Panics if the account doesn't have the role.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn revoke_role(&mut self, role: Role, account_id: AccountId) {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_revoke_role(role, &account_id);
+    }
+
+    /// Returns the accounts that have the given role.
+    pub fn get_role_members(&self, role: Role) -> Vec<AccountId> {
+        self.internal_config()
+            .roles
+            .remove(&role)
+            .unwrap_or_default()
+    }
+
+    /// Returns the roles of the given account.
+    pub fn get_account_roles(&self, account_id: AccountId) -> Vec<Role> {
+        let mut roles: Vec<Role> = self
+            .internal_config()
+            .roles
+            .into_iter()
+            .filter(|(_, account_ids)| account_ids.contains(&account_id))
+            .map(|(role, _)| role)
+            .collect();
+        roles.sort();
+        roles
+    }
+}
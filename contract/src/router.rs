@@ -0,0 +1,130 @@
+use crate::*;
+
+impl Action {
+    /// Whether the action can be executed by a router on behalf of an account.
+    pub fn is_router_allowed(&self) -> bool {
+        matches!(
+            self,
+            Action::Withdraw(_)
+                | Action::IncreaseCollateral(_)
+                | Action::DecreaseCollateral(_)
+                | Action::Borrow(_)
+                | Action::BorrowAndWithdraw(_)
+                | Action::Repay(_)
+                | Action::Net(_)
+        )
+    }
+}
+
+impl Contract {
+    pub fn internal_get_approved_routers(&self, account_id: &AccountId) -> Vec<AccountId> {
+        self.router_approvals.get(account_id).unwrap_or_default()
+    }
+
+    /// Saves the approved routers and records the storage change under the account storage
+    /// tracker.
+    pub fn internal_set_approved_routers(
+        &mut self,
+        account: &mut Account,
+        routers: Vec<AccountId>,
+    ) {
+        account.storage_tracker.start();
+        if routers.is_empty() {
+            self.router_approvals.remove(&account.account_id);
+        } else {
+            self.router_approvals.insert(&account.account_id, &routers);
+        }
+        account.storage_tracker.stop();
+    }
+
+    /// Executes the actions of every account in the batch on behalf of the router. The router has
+    /// to be approved in the config and by every account in the batch.
+    /// If `prices` are not given, only the actions that don't require prices can be executed.
+    pub fn internal_router_execute(
+        &mut self,
+        router_id: &AccountId,
+        batch: Vec<(AccountId, Vec<Action>)>,
+        prices: Option<&Prices>,
+    ) {
+        assert!(
+            self.internal_config().router_ids.contains(router_id),
+            "The router is not approved"
+        );
+        for (account_id, actions) in batch {
+            assert!(
+                self.internal_get_approved_routers(&account_id)
+                    .contains(router_id),
+                "The router is not approved by the account {}",
+                account_id
+            );
+            assert!(
+                actions.iter().all(|action| action.is_router_allowed()),
+                "The action can't be executed by the router"
+            );
+            let mut account = self.internal_unwrap_account(&account_id);
+            match prices {
+                Some(prices) => self.internal_execute(&account_id, &mut account, actions, prices),
+                None => {
+                    self.internal_execute_without_prices(&account_id, &mut account, actions);
+                }
+            }
+            self.internal_set_account(&account_id, account);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Executes the actions of multiple accounts on behalf of the predecessor router account.
+    /// Only the actions that don't require prices can be executed. The actions that require prices
+    /// have to be executed through the oracle call with `PriceReceiverMsg::RouterExecute`.
+    /// - Requires one yoctoNEAR.
+    /// - Requires the router to be approved in the config and by every account in the batch.
+    #[payable]
+    pub fn router_execute(&mut self, batch: Vec<(AccountId, Vec<Action>)>) {
+        assert_one_yocto();
+        assert_not_self_call();
+        self.internal_router_execute(&env::predecessor_account_id(), batch, None);
+    }
+
+    /// Approves the given router to execute actions on behalf of the predecessor account.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn approve_router(&mut self, router_id: AccountId) {
+        assert_one_yocto();
+        assert!(
+            self.internal_config().router_ids.contains(&router_id),
+            "The router is not approved"
+        );
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        let mut routers = self.internal_get_approved_routers(&account_id);
+        if !routers.contains(&router_id) {
+            routers.push(router_id);
+            self.internal_set_approved_routers(&mut account, routers);
+        }
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Revokes the approval of the given router for the predecessor account.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn revoke_router(&mut self, router_id: AccountId) {
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        let mut routers = self.internal_get_approved_routers(&account_id);
+        let index = routers
+            .iter()
+            .position(|id| id == &router_id)
+            .expect("The router is not approved by the account");
+        routers.remove(index);
+        self.internal_set_approved_routers(&mut account, routers);
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Returns the routers approved by the given account.
+    pub fn get_approved_routers(&self, account_id: AccountId) -> Vec<AccountId> {
+        self.internal_get_approved_routers(&account_id)
+    }
+}
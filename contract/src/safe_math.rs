@@ -0,0 +1,71 @@
+use crate::*;
+
+/// Panics with the description of the arithmetic edge case if the contract is built with the
+/// `safe-math-audit` feature. The release build handles the edge case as before, e.g. by
+/// saturating or truncating the value, or with the generic overflow panic. The audit build is used
+/// in testing and canary deployments to surface the latent edge cases.
+#[inline]
+pub(crate) fn audit_assert<F: FnOnce() -> String>(condition: bool, description: F) {
+    #[cfg(feature = "safe-math-audit")]
+    assert!(condition, "Safe math audit: {}", description());
+    #[cfg(not(feature = "safe-math-audit"))]
+    let _ = (condition, description);
+}
+
+/// Converts the 256-bit result of the pool math into `u128`, describing the overflow in the audit
+/// build.
+pub(crate) fn u256_to_u128(value: U256, operation: &str) -> u128 {
+    audit_assert(value.bits() <= 128, || {
+        format!("{} {} overflows u128", operation, value)
+    });
+    value.as_u128()
+}
+
+/// Converts the 384-bit result of the decimal math into `u128`, describing the overflow in the
+/// audit build.
+pub(crate) fn u384_to_u128(value: U384, operation: &str) -> u128 {
+    audit_assert(value.bits() <= 128, || {
+        format!("{} {} overflows u128", operation, value)
+    });
+    value.as_u128()
+}
+
+#[cfg(all(test, feature = "safe-math-audit"))]
+mod tests {
+    use super::*;
+    use near_sdk::json_types::U128;
+
+    #[test]
+    #[should_panic(expected = "Safe math audit: BigDecimal to LowU128")]
+    fn test_audit_low_u128_truncation() {
+        let _ = U128::from(BigDecimal::from(u128::MAX));
+    }
+
+    #[test]
+    #[should_panic(expected = "Safe math audit: f64")]
+    fn test_audit_f64_saturation() {
+        let _ = BigDecimal::from(-1f64);
+    }
+
+    #[test]
+    #[should_panic(expected = "Safe math audit: BigDecimal subtraction")]
+    fn test_audit_sub_underflow() {
+        let _ = BigDecimal::from(1u32) - BigDecimal::from(2u32);
+    }
+
+    #[test]
+    #[should_panic(expected = "Safe math audit: mul_div")]
+    fn test_audit_mul_div_overflow() {
+        mul_div(u128::MAX, u128::MAX, 1, Rounding::Down);
+    }
+
+    #[test]
+    #[should_panic(expected = "Safe math audit: the shares")]
+    fn test_audit_shares_to_amount_clamp() {
+        let pool = Pool {
+            shares: U128(100),
+            balance: 200,
+        };
+        pool.shares_to_amount(U128(300), Rounding::Down);
+    }
+}
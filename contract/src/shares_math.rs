@@ -0,0 +1,331 @@
+use crate::*;
+use near_sdk::json_types::U128;
+
+/// The rounding direction of the conversions between the shares and the balances of a pool.
+/// The conversions always round in favor of the protocol:
+/// - The balances of the supplied shares round down, the balances of the borrowed shares round up.
+/// - The shares taken out of a pool, e.g. to withdraw or to borrow a given amount, round up.
+/// - The shares added to a pool, e.g. to supply or to repay a given amount, round down.
+#[derive(Serialize, Deserialize, Clone, Copy, Eq, PartialEq)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum Rounding {
+    Down,
+    Up,
+}
+
+impl Rounding {
+    /// The rounding of the balance of the shares of the supplied or the borrowed pool.
+    pub fn for_balance(is_borrowed: bool) -> Self {
+        if is_borrowed {
+            Rounding::Up
+        } else {
+            Rounding::Down
+        }
+    }
+
+    pub fn inverse(self) -> Self {
+        match self {
+            Rounding::Down => Rounding::Up,
+            Rounding::Up => Rounding::Down,
+        }
+    }
+}
+
+/// Returns `a * num / denom` rounded in the given direction.
+pub(crate) fn mul_div(a: u128, num: u128, denom: u128, rounding: Rounding) -> u128 {
+    let extra = match rounding {
+        Rounding::Down => U256::zero(),
+        Rounding::Up => U256::from(denom - 1),
+    };
+    u256_to_u128(
+        (U256::from(a) * U256::from(num) + extra) / U256::from(denom),
+        "mul_div",
+    )
+}
+
+impl Pool {
+    pub fn amount_to_shares(&self, amount: Balance, rounding: Rounding) -> Shares {
+        audit_assert(self.balance > 0 || self.shares.0 == 0, || {
+            format!("the pool has {} shares without a balance", self.shares.0)
+        });
+        let shares = if self.balance == 0 {
+            amount
+        } else {
+            mul_div(self.shares.0, amount, self.balance, rounding)
+        };
+        shares.into()
+    }
+
+    pub fn shares_to_amount(&self, shares: Shares, rounding: Rounding) -> Balance {
+        audit_assert(shares.0 <= self.shares.0, || {
+            format!(
+                "the shares {} exceed the pool shares {} and are clamped to the balance",
+                shares.0, self.shares.0
+            )
+        });
+        if shares.0 >= self.balance || shares.0 == self.shares.0 {
+            self.balance
+        } else {
+            assert!(
+                shares.0 < self.shares.0,
+                "Invariant: balance should not be less than the number of shares in the pool"
+            );
+            mul_div(self.balance, shares.0, self.shares.0, rounding)
+        }
+    }
+}
+
+/// Converts the asset amount to the shares of the pool and the amount, limited by the available
+/// shares. The amount is rounded in the inverse direction of the shares, so the shares taken out
+/// of the pool round up while the withdrawn amount rounds down, and the repaid shares round down
+/// while the repaid amount rounds up.
+pub(crate) fn asset_amount_to_shares(
+    pool: &Pool,
+    available_shares: Shares,
+    asset_amount: &AssetAmount,
+    shares_rounding: Rounding,
+) -> (Shares, Balance) {
+    let amount_rounding = shares_rounding.inverse();
+    let (shares, amount) = if let Some(amount) = &asset_amount.amount {
+        (pool.amount_to_shares(amount.0, shares_rounding), amount.0)
+    } else if let Some(max_amount) = &asset_amount.max_amount {
+        let shares = std::cmp::min(
+            available_shares.0,
+            pool.amount_to_shares(max_amount.0, shares_rounding).0,
+        )
+        .into();
+        (
+            shares,
+            std::cmp::min(pool.shares_to_amount(shares, amount_rounding), max_amount.0),
+        )
+    } else {
+        (
+            available_shares,
+            pool.shares_to_amount(available_shares, amount_rounding),
+        )
+    };
+    assert!(shares.0 > 0, "Shares can't be 0");
+    assert!(amount > 0, "Amount can't be 0");
+    (shares, amount)
+}
+
+/// Returns the borrowed shares, the supplied shares and the amount to repay the borrowed asset
+/// from the supplied shares of the account asset. If the supplied shares are not enough, the
+/// repayment is limited to them.
+pub(crate) fn compute_repayment(
+    asset: &Asset,
+    account_asset: &AccountAsset,
+    available_borrowed_shares: Shares,
+    asset_amount: &AssetAmount,
+) -> (Shares, Shares, Balance) {
+    let (mut borrowed_shares, mut amount) = asset_amount_to_shares(
+        &asset.borrowed,
+        available_borrowed_shares,
+        asset_amount,
+        Rounding::Down,
+    );
+
+    let mut supplied_shares = asset.supplied.amount_to_shares(amount, Rounding::Up);
+    if supplied_shares.0 > account_asset.shares.0 {
+        supplied_shares = account_asset.shares;
+        amount = asset
+            .supplied
+            .shares_to_amount(supplied_shares, Rounding::Down);
+        if let Some(min_amount) = &asset_amount.amount {
+            assert!(amount >= min_amount.0, "Not enough supplied balance");
+        }
+        assert!(amount > 0, "Repayment amount can't be 0");
+
+        borrowed_shares = asset.borrowed.amount_to_shares(amount, Rounding::Down);
+        assert!(borrowed_shares.0 > 0, "Shares can't be 0");
+        assert!(borrowed_shares.0 <= available_borrowed_shares.0);
+    }
+    (borrowed_shares, supplied_shares, amount)
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Converts the shares of the supplied pool of the asset, or of the borrowed pool if
+    /// `is_borrow`, to the inner balance with the extra decimals at the current block, using the
+    /// same rounding as the contract. The balance of the supplied shares rounds down and of the
+    /// borrowed shares rounds up, unless `rounding` is given.
+    /// - Panics if the asset doesn't exist.
+    pub fn shares_to_amount(
+        &self,
+        token_id: AccountId,
+        shares: U128,
+        is_borrow: bool,
+        rounding: Option<Rounding>,
+    ) -> U128 {
+        let asset = self.internal_unwrap_asset(&token_id);
+        let pool = if is_borrow {
+            &asset.borrowed
+        } else {
+            &asset.supplied
+        };
+        pool.shares_to_amount(shares, rounding.unwrap_or(Rounding::for_balance(is_borrow)))
+            .into()
+    }
+
+    /// Converts the inner balance with the extra decimals to the shares of the supplied pool of
+    /// the asset, or of the borrowed pool if `is_borrow`, at the current block, using the same
+    /// rounding as the contract. By default, the shares round as for adding the amount to the
+    /// account: down for a deposit and up for a borrow. Pass `rounding` to convert the amount
+    /// taken from the account: `Up` for a withdrawal and `Down` for a repayment.
+    /// - Panics if the asset doesn't exist.
+    pub fn amount_to_shares(
+        &self,
+        token_id: AccountId,
+        amount: U128,
+        is_borrow: bool,
+        rounding: Option<Rounding>,
+    ) -> U128 {
+        let asset = self.internal_unwrap_asset(&token_id);
+        let pool = if is_borrow {
+            &asset.borrowed
+        } else {
+            &asset.supplied
+        };
+        pool.amount_to_shares(
+            amount.0,
+            rounding.unwrap_or(Rounding::for_balance(is_borrow)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use proptest::prelude::*;
+    use rand::Rng;
+
+    fn pool(shares: u128, balance: u128) -> Pool {
+        Pool {
+            shares: shares.into(),
+            balance,
+        }
+    }
+
+    fn random_pool(rng: &mut impl Rng) -> Pool {
+        let shares = rng.gen_range(1..10u128.pow(30));
+        let balance = shares + rng.gen_range(0..10u128.pow(30));
+        pool(shares, balance)
+    }
+
+    #[test]
+    fn test_mul_div_rounding() {
+        assert_eq!(mul_div(10, 1, 3, Rounding::Down), 3);
+        assert_eq!(mul_div(10, 1, 3, Rounding::Up), 4);
+        assert_eq!(mul_div(9, 1, 3, Rounding::Down), 3);
+        assert_eq!(mul_div(9, 1, 3, Rounding::Up), 3);
+    }
+
+    #[test]
+    fn test_empty_pool() {
+        let pool = Pool::new();
+        assert_eq!(pool.amount_to_shares(100, Rounding::Down).0, 100);
+        assert_eq!(pool.amount_to_shares(100, Rounding::Up).0, 100);
+    }
+
+    #[test]
+    fn test_round_trip_doesnt_leak_value() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let pool = random_pool(&mut rng);
+            let amount = rng.gen_range(1..=pool.balance);
+            // Supplying the amount and withdrawing the received shares never returns more.
+            let shares = pool.amount_to_shares(amount, Rounding::Down);
+            assert!(pool.shares_to_amount(shares, Rounding::Down) <= amount);
+            // Borrowing the amount and repaying the owed shares never costs less.
+            let shares = pool.amount_to_shares(amount, Rounding::Up);
+            assert!(pool.shares_to_amount(shares, Rounding::Up) >= amount);
+        }
+    }
+
+    #[test]
+    fn test_rounding_directions_differ_by_at_most_one() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let pool = random_pool(&mut rng);
+            let amount = rng.gen_range(0..=pool.balance);
+            let down = pool.amount_to_shares(amount, Rounding::Down).0;
+            let up = pool.amount_to_shares(amount, Rounding::Up).0;
+            assert!(down <= up && up - down <= 1);
+            let shares = rng.gen_range(0..=pool.shares.0).into();
+            let down = pool.shares_to_amount(shares, Rounding::Down);
+            let up = pool.shares_to_amount(shares, Rounding::Up);
+            assert!(down <= up && up - down <= 1);
+        }
+    }
+
+    #[test]
+    fn test_shares_of_pool_dont_exceed_balance() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..1000 {
+            let pool = random_pool(&mut rng);
+            let shares = rng.gen_range(0..=pool.shares.0).into();
+            assert!(pool.shares_to_amount(shares, Rounding::Up) <= pool.balance);
+            assert_eq!(
+                pool.shares_to_amount(pool.shares, Rounding::Down),
+                pool.balance
+            );
+        }
+    }
+
+    fn any_pool() -> impl Strategy<Value = Pool> {
+        (1..10u128.pow(30), 0..10u128.pow(30))
+            .prop_map(|(shares, extra)| pool(shares, shares + extra))
+    }
+
+    fn any_amount() -> impl Strategy<Value = u128> {
+        0..10u128.pow(30)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_round_trip_doesnt_create_value(pool in any_pool(), amount in any_amount()) {
+            let amount = std::cmp::min(amount, pool.balance);
+            let shares = pool.amount_to_shares(amount, Rounding::Down);
+            prop_assert!(pool.shares_to_amount(shares, Rounding::Down) <= amount);
+            let shares = pool.amount_to_shares(amount, Rounding::Up);
+            prop_assert!(pool.shares_to_amount(shares, Rounding::Up) >= amount);
+        }
+
+        #[test]
+        fn prop_conversions_are_monotonic(
+            pool in any_pool(),
+            a in any_amount(),
+            b in any_amount()
+        ) {
+            let (low, high) = (a.min(b), a.max(b));
+            for rounding in [Rounding::Down, Rounding::Up] {
+                prop_assert!(
+                    pool.amount_to_shares(low, rounding).0
+                        <= pool.amount_to_shares(high, rounding).0
+                );
+                let low = std::cmp::min(low, pool.shares.0).into();
+                let high = std::cmp::min(high, pool.shares.0).into();
+                prop_assert!(
+                    pool.shares_to_amount(low, rounding) <= pool.shares_to_amount(high, rounding)
+                );
+            }
+        }
+
+        #[test]
+        fn prop_supply_doesnt_dilute_holders(
+            pool in any_pool(),
+            amount in any_amount(),
+            held in any_amount()
+        ) {
+            // The shares of a new supply round down, so the existing shares never lose value.
+            let held = std::cmp::min(held, pool.shares.0).into();
+            let shares = pool.amount_to_shares(amount, Rounding::Down);
+            let new_pool = self::pool(pool.shares.0 + shares.0, pool.balance + amount);
+            prop_assert!(
+                new_pool.shares_to_amount(held, Rounding::Down)
+                    >= pool.shares_to_amount(held, Rounding::Down)
+            );
+        }
+    }
+}
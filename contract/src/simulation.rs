@@ -0,0 +1,321 @@
+use crate::*;
+
+impl Action {
+    /// Whether the action can be simulated. The actions that interact with other contracts or
+    /// other accounts can't be simulated.
+    pub fn is_simulation_supported(&self) -> bool {
+        matches!(
+            self,
+            Action::Withdraw(_)
+                | Action::IncreaseCollateral(_)
+                | Action::DecreaseCollateral(_)
+                | Action::Borrow(_)
+                | Action::BorrowAndWithdraw(_)
+                | Action::Repay(_)
+                | Action::Net(_)
+        )
+    }
+}
+
+impl Contract {
+    /// Applies the action to the in-memory copy of the account. The changed assets are only
+    /// cached, so nothing is saved and no promises are created.
+    fn internal_simulate_action(&self, account: &mut Account, prices: &Prices, action: Action) {
+        match action {
+            Action::Withdraw(asset_amount) => {
+                let mut asset = self.internal_unwrap_asset(&asset_amount.token_id);
+                assert!(
+                    !asset.config.is_paused(PausableAction::Withdraw),
+                    "Withdrawals for this asset are not enabled"
+                );
+                self.internal_assert_no_flash_loan(&asset_amount.token_id);
+                let mut account_asset = account.internal_unwrap_asset(&asset_amount.token_id);
+                let (shares, amount) = asset_amount_to_shares(
+                    &asset.supplied,
+                    account_asset.shares,
+                    &asset_amount,
+                    Rounding::Up,
+                );
+                let available_amount = asset.available_amount();
+                assert!(
+                    amount <= available_amount,
+                    "Withdraw error: Exceeded available amount {} of {}",
+                    available_amount,
+                    &asset_amount.token_id
+                );
+                account_asset.withdraw_shares(shares);
+                account.internal_set_asset(&asset_amount.token_id, account_asset);
+                asset.supplied.withdraw(shares, amount);
+                self.internal_cache_asset(&asset_amount.token_id, asset);
+            }
+            Action::IncreaseCollateral(asset_amount) => {
+                let mut asset = self.internal_unwrap_asset(&asset_amount.token_id);
+                assert!(
+                    asset.config.can_use_as_collateral,
+                    "Thi asset can't be used as a collateral"
+                );
+                assert!(
+                    !asset.config.is_paused(PausableAction::IncreaseCollateral),
+                    "Increasing collateral for this asset is not enabled"
+                );
+                self.internal_assert_isolation_collateral(account, &asset_amount.token_id, &asset);
+                let mut account_asset = account.internal_unwrap_asset(&asset_amount.token_id);
+                let (shares, _) = asset_amount_to_shares(
+                    &asset.supplied,
+                    account_asset.shares,
+                    &asset_amount,
+                    Rounding::Up,
+                );
+                account_asset.withdraw_shares(shares);
+                account.internal_set_asset(&asset_amount.token_id, account_asset);
+                account.increase_collateral(&asset_amount.token_id, shares);
+                self.internal_assert_risk_tiers(account);
+                asset.collateral_shares.0 += shares.0;
+                asset.assert_collateral_cap(0);
+                self.internal_cache_asset(&asset_amount.token_id, asset);
+            }
+            Action::DecreaseCollateral(asset_amount) => {
+                self.internal_assert_not_paused(
+                    &asset_amount.token_id,
+                    PausableAction::DecreaseCollateral,
+                );
+                assert!(
+                    account.borrowed.is_empty()
+                        || prices.get_valid(&asset_amount.token_id).is_some(),
+                    "The collateral with a missing or zero price can't be decreased while borrowing"
+                );
+                let mut asset = self.internal_unwrap_asset(&asset_amount.token_id);
+                let mut account_asset =
+                    account.internal_get_asset_or_default(&asset_amount.token_id);
+                let collateral_shares = account.internal_unwrap_collateral(&asset_amount.token_id);
+                let (shares, _) = asset_amount_to_shares(
+                    &asset.supplied,
+                    collateral_shares,
+                    &asset_amount,
+                    Rounding::Up,
+                );
+                account.decrease_collateral(&asset_amount.token_id, shares);
+                asset.decrease_collateral_shares(shares);
+                self.internal_cache_asset(&asset_amount.token_id, asset);
+                account_asset.deposit_shares(shares);
+                account.internal_set_asset(&asset_amount.token_id, account_asset);
+            }
+            Action::Borrow(asset_amount) => {
+                let amount = self.internal_simulate_increase_borrowed(account, &asset_amount);
+                let mut asset = self.internal_unwrap_asset(&asset_amount.token_id);
+                let mut account_asset =
+                    account.internal_get_asset_or_default(&asset_amount.token_id);
+                let supplied_shares = asset.supplied.amount_to_shares(amount, Rounding::Down);
+                asset.supplied.deposit(supplied_shares, amount);
+                self.internal_cache_asset(&asset_amount.token_id, asset);
+                account_asset.deposit_shares(supplied_shares);
+                account.internal_set_asset(&asset_amount.token_id, account_asset);
+            }
+            Action::BorrowAndWithdraw(asset_amount) => {
+                let asset = self.internal_unwrap_asset(&asset_amount.token_id);
+                assert!(
+                    !asset.config.is_paused(PausableAction::Withdraw),
+                    "Withdrawals for this asset are not enabled"
+                );
+                let available_amount = asset.available_amount();
+                let amount = match (asset_amount.amount, asset_amount.max_amount) {
+                    (Some(amount), _) => amount.0,
+                    (None, Some(max_amount)) => std::cmp::min(max_amount.0, available_amount),
+                    (None, None) => available_amount,
+                };
+                let amount = amount - amount % 10u128.pow(asset.config.extra_decimals as u32);
+                self.internal_simulate_increase_borrowed(
+                    account,
+                    &AssetAmount {
+                        token_id: asset_amount.token_id,
+                        amount: Some(amount.into()),
+                        max_amount: None,
+                    },
+                );
+            }
+            Action::Repay(asset_amount) => {
+                self.internal_assert_not_paused(&asset_amount.token_id, PausableAction::Repay);
+                let mut account_asset = account.internal_unwrap_asset(&asset_amount.token_id);
+                self.internal_simulate_repay(&mut account_asset, account, &asset_amount);
+                account.internal_set_asset(&asset_amount.token_id, account_asset);
+            }
+            Action::Net(asset_amount) => {
+                self.internal_assert_not_paused(&asset_amount.token_id, PausableAction::Repay);
+                let token_id = &asset_amount.token_id;
+                let mut account_asset = account.internal_get_asset_or_default(token_id);
+                let collateral_shares = account
+                    .collateral
+                    .get(token_id)
+                    .cloned()
+                    .unwrap_or_else(|| 0.into());
+                if collateral_shares.0 > 0 {
+                    account.decrease_collateral(token_id, collateral_shares);
+                    account_asset.deposit_shares(collateral_shares);
+                }
+                self.internal_simulate_repay(&mut account_asset, account, &asset_amount);
+                let restored_shares: Shares =
+                    std::cmp::min(collateral_shares.0, account_asset.shares.0).into();
+                if restored_shares.0 > 0 {
+                    account_asset.withdraw_shares(restored_shares);
+                    account.increase_collateral(token_id, restored_shares);
+                }
+                let used_collateral_shares: Shares =
+                    (collateral_shares.0 - restored_shares.0).into();
+                if used_collateral_shares.0 > 0 {
+                    let mut asset = self.internal_unwrap_asset(token_id);
+                    asset.decrease_collateral_shares(used_collateral_shares);
+                    self.internal_cache_asset(token_id, asset);
+                }
+                account.internal_set_asset(token_id, account_asset);
+            }
+            _ => env::panic_str("The action can't be simulated"),
+        }
+    }
+
+    /// Increases the borrowed balance of the account and the asset. Returns the borrowed amount.
+    fn internal_simulate_increase_borrowed(
+        &self,
+        account: &mut Account,
+        asset_amount: &AssetAmount,
+    ) -> Balance {
+        let mut asset = self.internal_unwrap_asset(&asset_amount.token_id);
+        assert!(
+            !asset.config.is_paused(PausableAction::Borrow),
+            "Thi asset can't be used borrowed"
+        );
+        assert!(
+            !asset.config.reserve_only,
+            "The reserve-only asset can't be borrowed"
+        );
+        assert!(
+            !asset.config.collateral_only,
+            "The collateral-only asset can't be borrowed"
+        );
+        self.internal_assert_no_flash_loan(&asset_amount.token_id);
+        self.internal_assert_allowlisted(&asset_amount.token_id, &account.account_id);
+
+        let available_amount = asset.available_amount();
+        let max_borrow_shares = asset
+            .borrowed
+            .amount_to_shares(available_amount, Rounding::Down);
+        let (borrowed_shares, amount) = asset_amount_to_shares(
+            &asset.borrowed,
+            max_borrow_shares,
+            asset_amount,
+            Rounding::Up,
+        );
+        assert!(
+            amount <= available_amount,
+            "Borrow error: Exceeded available amount {} of {}",
+            available_amount,
+            &asset_amount.token_id
+        );
+        asset.borrowed.deposit(borrowed_shares, amount);
+        asset.assert_borrow_cap();
+        account.increase_borrowed(&asset_amount.token_id, borrowed_shares);
+        asset.assert_min_borrow_amount(account.internal_unwrap_borrowed(&asset_amount.token_id));
+        self.internal_assert_risk_tiers(account);
+        let borrowable_in_isolation = asset.config.borrowable_in_isolation;
+        self.internal_cache_asset(&asset_amount.token_id, asset);
+
+        if let Some(isolated_token_id) = self.internal_get_isolated_collateral(account) {
+            assert!(
+                borrowable_in_isolation,
+                "The asset can't be borrowed in isolation"
+            );
+            let mut isolated_asset = self.internal_unwrap_asset(&isolated_token_id);
+            isolated_asset.isolated_debt += amount;
+            assert!(
+                isolated_asset.isolated_debt
+                    <= isolated_asset.config.isolation_debt_ceiling.unwrap().0,
+                "The isolation debt ceiling of {} is exceeded",
+                isolated_token_id
+            );
+            self.internal_cache_asset(&isolated_token_id, isolated_asset);
+        }
+        self.assert_max_total_borrowed_value();
+        amount
+    }
+
+    /// Repays the borrowed balance of the account from the supplied shares of the account asset.
+    fn internal_simulate_repay(
+        &self,
+        account_asset: &mut AccountAsset,
+        account: &mut Account,
+        asset_amount: &AssetAmount,
+    ) {
+        let mut asset = self.internal_unwrap_asset(&asset_amount.token_id);
+        let available_borrowed_shares = account.internal_unwrap_borrowed(&asset_amount.token_id);
+        let (borrowed_shares, supplied_shares, amount) = compute_repayment(
+            &asset,
+            account_asset,
+            available_borrowed_shares,
+            asset_amount,
+        );
+        asset.supplied.withdraw(supplied_shares, amount);
+        asset.borrowed.withdraw(borrowed_shares, amount);
+        self.internal_cache_asset(&asset_amount.token_id, asset);
+        if let Some(isolated_token_id) = self.internal_get_isolated_collateral(account) {
+            let mut isolated_asset = self.internal_unwrap_asset(&isolated_token_id);
+            isolated_asset.isolated_debt = isolated_asset.isolated_debt.saturating_sub(amount);
+            self.internal_cache_asset(&isolated_token_id, isolated_asset);
+        }
+        account.decrease_borrowed(&asset_amount.token_id, borrowed_shares);
+        account_asset.withdraw_shares(supplied_shares);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Simulates the execution of the given actions on behalf of the account and returns the
+    /// resulting detailed account view. The actions are applied to in-memory copies of the
+    /// account and the assets, so nothing is saved and no tokens are transferred.
+    /// If `prices` are not given, the last recorded prices from the oracle are used.
+    /// Fails with the same error as the execution of the actions would, except for the
+    /// withdraw timelocks, which are not simulated. The dust positions are not written off and
+    /// the farm rewards are not claimed.
+    pub fn simulate_execute(
+        &self,
+        account_id: AccountId,
+        actions: Vec<Action>,
+        prices: Option<PriceData>,
+    ) -> AccountDetailedView {
+        assert!(
+            actions
+                .iter()
+                .all(|action| action.is_simulation_supported()),
+            "The action can't be simulated"
+        );
+        let prices = prices
+            .map(|data| self.internal_prices_from_data(data))
+            .unwrap_or_else(|| self.internal_last_prices());
+        let need_number_check = actions.iter().any(|action| {
+            matches!(
+                action,
+                Action::IncreaseCollateral(_) | Action::Borrow(_) | Action::BorrowAndWithdraw(_)
+            )
+        });
+        let need_risk_check = actions.iter().any(|action| {
+            matches!(
+                action,
+                Action::DecreaseCollateral(_) | Action::Borrow(_) | Action::BorrowAndWithdraw(_)
+            )
+        });
+        self.internal_assert_num_actions(actions.len());
+        let mut account = self.internal_unwrap_account(&account_id);
+        for action in actions {
+            self.internal_simulate_action(&mut account, &prices, action);
+        }
+        if need_number_check {
+            assert!(
+                account.collateral.len() + account.borrowed.len()
+                    <= self.internal_config().max_num_assets as _
+            );
+        }
+        if need_risk_check {
+            assert!(self.compute_max_discount(&account, &prices) == BigDecimal::zero());
+            self.internal_assert_min_borrow_health(&account, &prices);
+        }
+        self.account_into_detailed_view(account)
+    }
+}
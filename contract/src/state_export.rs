@@ -0,0 +1,101 @@
+use crate::*;
+
+/// The farm exported with its inactive rewards, which are stored separately from the farm.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct FarmExport {
+    pub farm_id: FarmId,
+    pub asset_farm: AssetFarm,
+    pub inactive_rewards: Vec<(TokenId, AssetFarmReward)>,
+}
+
+/// The account exported with the data of its farms and its storage balance, which are stored
+/// separately from the account.
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AccountExport {
+    pub account: Account,
+    pub farms: Vec<(FarmId, AccountFarm)>,
+    pub storage: Storage,
+}
+
+impl Contract {
+    fn internal_export_farm(&self, farm_id: FarmId) -> FarmExport {
+        let asset_farm = self.internal_get_asset_farm(&farm_id).unwrap();
+        // The farm rewards are deposited as assets, so the inactive rewards are found among them.
+        let inactive_rewards = self
+            .asset_ids
+            .iter()
+            .filter(|token_id| !asset_farm.rewards.contains_key(token_id))
+            .filter_map(|token_id| {
+                let reward = self.internal_get_inactive_asset_farm_reward(&farm_id, &token_id)?;
+                Some((token_id, reward))
+            })
+            .collect();
+        FarmExport {
+            farm_id,
+            asset_farm,
+            inactive_rewards,
+        }
+    }
+
+    fn internal_export_account(&self, index: u64) -> AccountExport {
+        let account_id = self.accounts.keys_as_vector().get(index).unwrap();
+        let v_account = self.accounts.values_as_vector().get(index).unwrap();
+        let account = self.internal_vaccount_into_account(&account_id, v_account, true);
+        let farms = account
+            .farm_ids
+            .iter()
+            .filter_map(|farm_id| {
+                let account_farm = self.internal_get_account_farm(&account, farm_id)?;
+                Some((farm_id.clone(), account_farm))
+            })
+            .collect();
+        AccountExport {
+            storage: self.internal_unwrap_storage(&account_id),
+            account,
+            farms,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Exports a page of the assets for a shadow deployment, e.g. to rehearse an upgrade on a
+    /// fork of the mainnet state. Every item is the Borsh-serialized `(TokenId, Asset)`.
+    /// - Requires to be called by the contract owner with a transaction.
+    pub fn export_assets(&self, token: Option<String>, limit: Option<u64>) -> Page<Base64VecU8> {
+        self.assert_owner();
+        let keys = self.asset_ids.as_vector();
+        Page::new(keys.len(), token, limit, |index| {
+            let token_id = keys.get(index).unwrap();
+            let asset = self.internal_unwrap_asset(&token_id);
+            (token_id, asset).try_to_vec().unwrap().into()
+        })
+    }
+
+    /// Exports a page of the farms for a shadow deployment. Every item is the Borsh-serialized
+    /// `FarmExport`.
+    /// - Requires to be called by the contract owner with a transaction.
+    pub fn export_farms(&self, token: Option<String>, limit: Option<u64>) -> Page<Base64VecU8> {
+        self.assert_owner();
+        let farm_ids = self.internal_get_all_farm_ids();
+        Page::new(farm_ids.len() as u64, token, limit, |index| {
+            self.internal_export_farm(farm_ids[index as usize].clone())
+                .try_to_vec()
+                .unwrap()
+                .into()
+        })
+    }
+
+    /// Exports a page of the accounts for a shadow deployment. Every item is the
+    /// Borsh-serialized `AccountExport`.
+    /// - Requires to be called by the contract owner with a transaction.
+    pub fn export_accounts(&self, token: Option<String>, limit: Option<u64>) -> Page<Base64VecU8> {
+        self.assert_owner();
+        Page::new(self.accounts.len(), token, limit, |index| {
+            self.internal_export_account(index)
+                .try_to_vec()
+                .unwrap()
+                .into()
+        })
+    }
+}
@@ -0,0 +1,69 @@
+use crate::*;
+
+/// Deserializes the exported item.
+fn from_export<T: BorshDeserialize>(item: Base64VecU8) -> T {
+    T::try_from_slice(&item.0).expect("Invalid exported item")
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Imports the assets exported with `export_assets`, replacing the existing assets with the
+    /// same token IDs. It's only built with the `state-import` feature for the fork tests and
+    /// the shadow deployments, and must never be deployed to the mainnet.
+    /// - Requires to be called by the contract owner.
+    pub fn import_assets(&mut self, items: Vec<Base64VecU8>) {
+        self.assert_owner();
+        for item in items {
+            let (token_id, asset): (TokenId, Asset) = from_export(item);
+            self.internal_set_asset(&token_id, asset);
+            self.asset_ids.insert(&token_id);
+        }
+    }
+
+    /// Imports the farms exported with `export_farms` together with their inactive rewards.
+    /// - Requires to be called by the contract owner.
+    pub fn import_farms(&mut self, items: Vec<Base64VecU8>) {
+        self.assert_owner();
+        for item in items {
+            let FarmExport {
+                farm_id,
+                asset_farm,
+                inactive_rewards,
+            } = from_export(item);
+            self.internal_set_asset_farm(&farm_id, asset_farm);
+            for (token_id, reward) in inactive_rewards {
+                self.internal_set_inactive_asset_farm_reward(&farm_id, &token_id, reward);
+            }
+        }
+    }
+
+    /// Imports the accounts exported with `export_accounts` together with their farms and
+    /// storage balances. The assets and the farms have to be imported first, since the accounts
+    /// are indexed by them. The storage records keep the exported used bytes, rather than the
+    /// bytes written by the import.
+    /// - Requires to be called by the contract owner.
+    pub fn import_accounts(&mut self, items: Vec<Base64VecU8>) {
+        self.assert_owner();
+        for item in items {
+            let AccountExport {
+                mut account,
+                farms,
+                storage,
+            } = from_export(item);
+            let account_id = account.account_id.clone();
+            self.internal_set_storage(
+                &account_id,
+                Storage {
+                    storage_balance: storage.storage_balance,
+                    used_bytes: 0,
+                    storage_tracker: Default::default(),
+                },
+            );
+            for (farm_id, account_farm) in farms {
+                account.changed_farms.insert(farm_id, Some(account_farm));
+            }
+            self.internal_set_account(&account_id, account);
+            self.internal_set_storage(&account_id, storage);
+        }
+    }
+}
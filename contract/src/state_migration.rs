@@ -0,0 +1,215 @@
+use crate::pagination::page_range;
+use crate::*;
+
+/// The version of the contract state layout. It's increased whenever the layout of the `Contract`
+/// changes, and the previous layout is added to `VContract` with its migration.
+pub const STATE_VERSION: u32 = 2;
+
+/// The state layout of the version 0.6.0, which was released before the state was versioned.
+#[derive(BorshDeserialize, BorshSerialize)]
+pub struct Contract060 {
+    pub accounts: UnorderedMap<AccountId, VAccount>,
+    pub storage: LookupMap<AccountId, VStorage>,
+    pub assets: LookupMap<TokenId, VAsset>,
+    pub asset_farms: LookupMap<FarmId, VAssetFarm>,
+    pub asset_ids: UnorderedSet<TokenId>,
+    pub config: LazyOption<ConfigV0>,
+}
+
+/// The contract state of any known version.
+pub enum VContract {
+    V060(Contract060),
+    /// The state of the version 1 has the same layout, but the config is stored without the
+    /// version.
+    V1(Contract),
+    Current(Contract),
+}
+
+impl VContract {
+    /// Reads the contract state in the layout of its stored version. The state without the
+    /// version was written by 0.6.0.
+    pub fn read() -> Self {
+        match read_state_version() {
+            None => Self::V060(env::state_read().expect("ERR_CONTRACT_IS_NOT_INITIALIZED")),
+            Some(1) => Self::V1(env::state_read().expect("ERR_CONTRACT_IS_NOT_INITIALIZED")),
+            Some(STATE_VERSION) => {
+                Self::Current(env::state_read().expect("ERR_CONTRACT_IS_NOT_INITIALIZED"))
+            }
+            Some(version) => env::panic_str(&format!("Unknown state version {}", version)),
+        }
+    }
+}
+
+impl From<VContract> for Contract {
+    fn from(v: VContract) -> Self {
+        match v {
+            VContract::V060(Contract060 {
+                accounts,
+                storage,
+                assets,
+                asset_farms,
+                asset_ids,
+                config,
+            }) => {
+                let config: Config = config.get().unwrap().into();
+                let mut contract = Contract {
+                    accounts,
+                    storage,
+                    assets,
+                    asset_farms,
+                    asset_ids,
+                    config: LazyOption::new(StorageKey::Config, Some(&config.into())),
+                    last_prices: Default::default(),
+                    flash_loans: LookupMap::new(StorageKey::FlashLoans),
+                    leverage_swaps: LookupMap::new(StorageKey::LeverageSwaps),
+                    auto_repay_orders: LookupMap::new(StorageKey::AutoRepayOrders),
+                    withdraw_claims: LookupMap::new(StorageKey::WithdrawClaims),
+                    withdraw_timelocks: LookupMap::new(StorageKey::WithdrawTimelocks),
+                    withdraw_swaps: LookupMap::new(StorageKey::WithdrawSwaps),
+                    router_approvals: LookupMap::new(StorageKey::RouterApprovals),
+                    repay_swaps: LookupMap::new(StorageKey::RepaySwaps),
+                    asset_history: LookupMap::new(StorageKey::AssetHistory),
+                    last_asset_snapshot_days: HashMap::new(),
+                    asset_suppliers: LookupMap::new(StorageKey::AssetSuppliers),
+                    asset_borrowers: LookupMap::new(StorageKey::AssetBorrowers),
+                    proposals: UnorderedMap::new(StorageKey::Proposals),
+                    next_proposal_id: 0,
+                    delisting_assets: UnorderedSet::new(StorageKey::DelistingAssets),
+                    reserve_withdrawals: LookupMap::new(StorageKey::ReserveWithdrawals),
+                    asset_allowlists: LookupMap::new(StorageKey::AssetAllowlists),
+                    multisig_requests: UnorderedMap::new(StorageKey::MultisigRequests),
+                    next_multisig_request_id: 0,
+                    risk_oracle_bounds: LookupMap::new(StorageKey::RiskOracleBounds),
+                    deprecated_assets: UnorderedSet::new(StorageKey::DeprecatedAssets),
+                    volatility_ratio_change_windows: LookupMap::new(
+                        StorageKey::VolatilityRatioChangeWindows,
+                    ),
+                    last_asset_rates_event_timestamps: HashMap::new(),
+                    last_prices_used_event_timestamp: 0,
+                    protocol_summary: ProtocolSummary::default(),
+                    heavy_events_stats: HeavyEventsStats::default(),
+                    storage_sponsorship_balance: 0,
+                    sponsored_storage: LookupMap::new(StorageKey::SponsoredStorage),
+                    total_sponsored_bytes: 0,
+                    token_ids: Vector::new(StorageKey::TokenIds),
+                    token_indices: LookupMap::new(StorageKey::TokenIndices),
+                    account_farms: LookupMap::new(StorageKey::AccountFarmEntries),
+                    last_lp_token_infos: HashMap::new(),
+                    mt_tokens: LookupMap::new(StorageKey::MtTokens),
+                    mt_asset_ids: LookupMap::new(StorageKey::MtAssetIds),
+                    dexes: UnorderedMap::new(StorageKey::Dexes),
+                    twap_pairs: UnorderedMap::new(StorageKey::TwapPairs),
+                    keeper_pool_balance: 0,
+                    keeper_prices_timestamp: 0,
+                    keeper_accrue_index: 0,
+                    keeper_liquidation_index: 0,
+                    keeper_tasks: UnorderedMap::new(StorageKey::KeeperTasks),
+                    operator_approvals: LookupMap::new(StorageKey::OperatorApprovals),
+                    nft_collections: UnorderedMap::new(StorageKey::NftCollections),
+                    nft_collateral: LookupMap::new(StorageKey::NftCollateral),
+                    nft_auctions: UnorderedMap::new(StorageKey::NftAuctions),
+                    partners: UnorderedMap::new(StorageKey::Partners),
+                    reserve_staking: ReserveStaking::default(),
+                    asset_migrations: UnorderedMap::new(StorageKey::AssetMigrations),
+                    pyth_feeds: UnorderedMap::new(StorageKey::PythFeeds),
+                    account_activities: LookupMap::new(StorageKey::AccountActivities),
+                    token_metadata: LookupMap::new(StorageKey::TokenMetadata),
+                    extra_decimals_migrations: UnorderedMap::new(
+                        StorageKey::ExtraDecimalsMigrations,
+                    ),
+                    execute_continuations: LookupMap::new(StorageKey::ExecuteContinuations),
+                    inactive_asset_farm_rewards: LookupMap::new(
+                        StorageKey::InactiveAssetFarmRewardEntries,
+                    ),
+                    account_locks: LookupMap::new(StorageKey::AccountLocks),
+                    account_shards: LookupMap::new(StorageKey::AccountShardEntries),
+                    oracle_commitments: LookupMap::new(StorageKey::OracleCommitments),
+                    collateral_shares_backfills: UnorderedMap::new(
+                        StorageKey::CollateralSharesBackfills,
+                    ),
+                    price_feeds_paused: false,
+                };
+                // The collateral shares of the assets weren't tracked, so they are recounted
+                // before the assets can be used again.
+                for token_id in contract.asset_ids.to_vec() {
+                    contract.internal_start_collateral_shares_backfill(&token_id);
+                }
+                contract
+            }
+            VContract::V1(mut contract) => {
+                let config = LazyOption::<Config>::new(StorageKey::Config, None)
+                    .get()
+                    .unwrap();
+                contract.internal_set_config(config);
+                contract
+            }
+            VContract::Current(contract) => contract,
+        }
+    }
+}
+
+fn read_state_version() -> Option<u32> {
+    LazyOption::<u32>::new(StorageKey::StateVersion, None).get()
+}
+
+/// Writes the current state version. Called when the state is initialized or migrated.
+pub(crate) fn write_state_version() {
+    LazyOption::new(StorageKey::StateVersion, Some(&STATE_VERSION));
+}
+
+impl Contract {
+    /// Asserts the invariants of the contract state that have to hold across the upgrades: the
+    /// config is valid and the pools of all the assets are consistent. It's checked before the
+    /// upgrade and after the state migration, so a broken state can't be upgraded and a broken
+    /// migration is rolled back.
+    pub(crate) fn internal_assert_state_invariants(&self) {
+        self.internal_config().assert_valid();
+        for token_id in self.asset_ids.iter() {
+            let asset = self.internal_unwrap_asset(&token_id);
+            assert!(
+                asset.supplied.balance + asset.reserved >= asset.borrowed.balance
+                    && asset.supplied.balance >= asset.supplied.shares.0
+                    && asset.borrowed.balance >= asset.borrowed.shares.0,
+                "The pools of the asset {} are inconsistent",
+                token_id
+            );
+        }
+    }
+
+    /// Runs the migration of a page of the accounts, for the per-account structures that are
+    /// migrated lazily after the upgrade. The `migrate_account` function returns whether the
+    /// account was migrated.
+    /// - `token` is the continuation token from the previous page, starting from the first account
+    ///   if not given.
+    /// - `limit` is the maximum number of the accounts to check.
+    pub(crate) fn internal_migrate_accounts_page<F>(
+        &mut self,
+        token: Option<String>,
+        limit: u64,
+        mut migrate_account: F,
+    ) -> StorageMigrationPage
+    where
+        F: FnMut(&mut Self, &AccountId) -> bool,
+    {
+        let (range, next_token) = page_range(self.accounts.len(), token, Some(limit));
+        let account_ids: Vec<AccountId> = range
+            .map(|index| self.accounts.keys_as_vector().get(index).unwrap())
+            .collect();
+        let num_migrated = account_ids
+            .iter()
+            .filter(|account_id| migrate_account(self, account_id))
+            .count() as u64;
+        StorageMigrationPage {
+            num_migrated,
+            next_token,
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the stored version of the contract state layout.
+    pub fn get_state_version(&self) -> u32 {
+        read_state_version().unwrap_or_default()
+    }
+}
@@ -6,7 +6,9 @@ use near_sdk::json_types::U128;
 use near_sdk::StorageUsage;
 
 /// 10000 bytes
-const MIN_STORAGE_BALANCE: Balance = 10000u128 * env::STORAGE_PRICE_PER_BYTE;
+pub(crate) const MIN_STORAGE_BYTES: StorageUsage = 10000;
+pub(crate) const MIN_STORAGE_BALANCE: Balance =
+    MIN_STORAGE_BYTES as Balance * env::STORAGE_PRICE_PER_BYTE;
 
 #[derive(BorshSerialize, BorshDeserialize)]
 pub struct Storage {
@@ -16,6 +18,38 @@ pub struct Storage {
     pub storage_tracker: StorageTracker,
 }
 
+/// The breakdown of the storage used by an account. The sizes of the account parts are the sizes
+/// of their serialized values, the keys and the other records are counted in `other_bytes`. The
+/// positions are counted in the compact encoding that the account is migrated to once it's saved.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageReport {
+    /// The total number of bytes used by the account.
+    pub used_bytes: StorageUsage,
+    pub supplied_bytes: StorageUsage,
+    pub collateral_bytes: StorageUsage,
+    pub borrowed_bytes: StorageUsage,
+    pub farms_bytes: StorageUsage,
+    /// The bytes used by the keys, the storage record, the orders, the claims, the approvals and
+    /// the indexes of the account.
+    pub other_bytes: StorageUsage,
+    /// The total storage balance of the account.
+    #[serde(with = "u128_dec_format")]
+    pub total: Balance,
+    /// The part of the storage balance that is locked to pay for the used bytes, but no less
+    /// than the minimum storage balance.
+    #[serde(with = "u128_dec_format")]
+    pub locked: Balance,
+    /// The part of the storage balance that can be withdrawn.
+    #[serde(with = "u128_dec_format")]
+    pub available: Balance,
+}
+
+fn borsh_size<T: BorshSerialize>(value: &T) -> StorageUsage {
+    value.try_to_vec().unwrap().len() as _
+}
+
 #[derive(BorshSerialize, BorshDeserialize)]
 pub enum VStorage {
     Current(Storage),
@@ -46,10 +80,12 @@ impl Storage {
 
     fn assert_storage_covered(&self) {
         let storage_balance_needed = Balance::from(self.used_bytes) * env::storage_byte_cost();
-        assert!(
-            storage_balance_needed <= self.storage_balance,
-            "Not enough storage balance"
-        );
+        if storage_balance_needed > self.storage_balance {
+            env::panic_str(&format!(
+                "Not enough storage balance, the account needs {} more yoctoNEAR of storage",
+                storage_balance_needed - self.storage_balance
+            ));
+        }
     }
 }
 
@@ -63,12 +99,25 @@ impl Contract {
             .expect("Storage for account is missing")
     }
 
-    pub fn internal_set_storage(&mut self, account_id: &AccountId, mut storage: Storage) {
+    pub fn internal_set_storage(&mut self, account_id: &AccountId, storage: Storage) {
+        self.internal_save_storage(account_id, storage, true);
+    }
+
+    /// Saves the storage and charges the measured storage usage change. If `is_coverage_asserted`
+    /// is false, the added bytes are charged even if the storage balance doesn't cover them.
+    pub(crate) fn internal_save_storage(
+        &mut self,
+        account_id: &AccountId,
+        mut storage: Storage,
+        is_coverage_asserted: bool,
+    ) {
         if storage.storage_tracker.bytes_added >= storage.storage_tracker.bytes_released {
             let extra_bytes_used =
                 storage.storage_tracker.bytes_added - storage.storage_tracker.bytes_released;
             storage.used_bytes += extra_bytes_used;
-            storage.assert_storage_covered();
+            if is_coverage_asserted {
+                storage.assert_storage_covered();
+            }
         } else {
             let bytes_released =
                 storage.storage_tracker.bytes_released - storage.storage_tracker.bytes_added;
@@ -83,6 +132,168 @@ impl Contract {
         self.storage.insert(account_id, &storage.into());
     }
 
+    /// Runs the state changes on behalf of the account outside of the account object, and charges
+    /// or releases the storage balance of the account by the measured storage usage change.
+    /// - Panics if the storage balance doesn't cover the added bytes.
+    pub fn internal_track_account_storage<R, F: FnOnce(&mut Self) -> R>(
+        &mut self,
+        account_id: &AccountId,
+        f: F,
+    ) -> R {
+        let mut storage = self.internal_unwrap_storage(account_id);
+        storage.storage_tracker.start();
+        let res = f(self);
+        storage.storage_tracker.stop();
+        self.internal_set_storage(account_id, storage);
+        res
+    }
+
+    /// Registers a new account with the given storage balance.
+    pub fn internal_register_account(&mut self, account_id: &AccountId, storage_balance: Balance) {
+        let mut storage = Storage::new();
+        storage.storage_balance = storage_balance;
+
+        let mut account = Account::new(account_id);
+        // HACK: Tracking the extra bytes required to store the storage object itself and
+        // recording this under account storage tracker. It'll be accounted when saving the
+        // account below.
+        account.storage_tracker.start();
+        self.internal_set_storage(account_id, storage);
+        account.storage_tracker.stop();
+        // The claims of the transfers that failed after the account was unregistered are saved
+        // again to charge their storage to the account.
+        if let Some(claims) = self.withdraw_claims.remove(account_id) {
+            self.internal_set_withdraw_claims(&mut account, claims);
+        }
+        self.internal_set_account(account_id, account);
+    }
+
+    /// Asserts that the account can be unregistered. The supplied and the collateral assets and
+    /// the withdraw claims are only allowed with `force`, because they are transferred out.
+    fn internal_assert_can_unregister(&self, account: &Account, force: bool) {
+        let account_id = &account.account_id;
+        self.internal_assert_account_not_locked(account_id);
+        assert!(
+            account.borrowed.is_empty(),
+            "The account has borrowed assets"
+        );
+        assert!(
+            account.booster_staking.is_none(),
+            "The account has booster staking"
+        );
+        assert!(
+            self.nft_collateral.get(account_id).is_none(),
+            "The account has NFT collateral"
+        );
+        assert!(
+            self.leverage_swaps.get(account_id).is_none()
+                && self.withdraw_swaps.get(account_id).is_none()
+                && self.repay_swaps.get(account_id).is_none(),
+            "The account has a swap in progress"
+        );
+        // The migration iterates over the accounts by index, which the removal would shift.
+        assert!(
+            self.extra_decimals_migrations.is_empty(),
+            "Can't unregister during the extra decimals migration"
+        );
+        assert!(
+            self.collateral_shares_backfills.is_empty(),
+            "Can't unregister during the collateral shares backfill"
+        );
+        if !force {
+            assert!(
+                account.supplied.is_empty()
+                    && account.collateral.is_empty()
+                    && self.withdraw_claims.get(account_id).is_none(),
+                "The account has supplied or collateral assets or withdraw claims"
+            );
+        }
+    }
+
+    /// Decreases all the collateral, withdraws all the supplied assets and transfers the withdraw
+    /// claims of the account.
+    fn internal_withdraw_all(&mut self, account: &mut Account) {
+        let account_id = account.account_id.clone();
+        let max_amount = |token_id: &TokenId| AssetAmount {
+            token_id: token_id.clone(),
+            amount: None,
+            max_amount: None,
+        };
+        let mut collateral: Vec<_> = account.collateral.keys().cloned().collect();
+        collateral.sort();
+        // The collateral is decreased directly, because the actions can't add the supplied
+        // positions to the account with the storage that is about to be released.
+        for token_id in collateral {
+            self.internal_assert_not_paused(&token_id, PausableAction::DecreaseCollateral);
+            let mut account_asset = account.internal_get_asset_or_default(&token_id);
+            let (shares, amount) = self.internal_decrease_collateral(
+                &mut account_asset,
+                account,
+                &max_amount(&token_id),
+            );
+            account.internal_set_asset(&token_id, account_asset);
+            events::emit::decrease_collateral(&account_id, amount, shares, &token_id);
+        }
+        // Claims the farm rewards to the supplied balance before it's withdrawn.
+        for farm_id in account.get_all_potential_farms() {
+            account.add_affected_farm(farm_id);
+        }
+        self.internal_account_claim_affected_farms(account);
+        let mut supplied: Vec<_> = account.supplied.keys().cloned().collect();
+        supplied.sort();
+        let actions = supplied
+            .iter()
+            .map(|token_id| Action::Withdraw(max_amount(token_id)))
+            .collect();
+        self.internal_execute_without_prices(&account_id, account, actions);
+        assert!(
+            account.supplied.is_empty() && account.collateral.is_empty(),
+            "Failed to withdraw all the assets of the account"
+        );
+
+        for claim in self.internal_get_withdraw_claims(&account_id) {
+            events::emit::withdraw_started(&account_id, claim.amount, &claim.token_id);
+            self.internal_ft_transfer(&account_id, &claim.token_id, claim.amount);
+        }
+        self.withdraw_claims.remove(&account_id);
+    }
+
+    /// Removes the account with all its records and refunds the storage balance. The sponsored
+    /// part of the storage balance returns to the storage sponsorship pool.
+    fn internal_unregister_account(&mut self, mut account: Account) {
+        self.internal_load_account_farm_ids(&mut account);
+        self.internal_save_account_farms(&mut account);
+        // The storage record of the account is removed, so the storage changes are not charged.
+        account.storage_tracker.discard();
+        let account_id = &account.account_id;
+        self.internal_update_asset_accounts(&account);
+        self.auto_repay_orders.remove(account_id);
+        self.withdraw_timelocks.remove(account_id);
+        self.router_approvals.remove(account_id);
+        self.operator_approvals.remove(account_id);
+        self.oracle_commitments.remove(account_id);
+        self.account_activities.remove(account_id);
+        for farm_id in account.farm_ids.iter() {
+            self.account_farms
+                .remove(&(account_id.clone(), farm_id.clone()));
+        }
+        self.accounts.remove(account_id);
+        self.internal_remove_account_shards(account_id);
+        let storage = self.storage.remove(account_id).map(Storage::from).unwrap();
+
+        let sponsored_balance = self.internal_sponsored_storage_balance(account_id);
+        if let Some(sponsored_bytes) = self.sponsored_storage.remove(account_id) {
+            self.total_sponsored_bytes -= sponsored_bytes;
+            self.storage_sponsorship_balance += sponsored_balance;
+        }
+        let refund = storage.storage_balance - sponsored_balance;
+        if refund > 0 {
+            Promise::new(account_id.clone()).transfer(refund);
+        }
+    }
+
+    /// The available storage balance excludes the sponsored storage balance, because it can only
+    /// be reclaimed by the storage sponsorship pool.
     pub fn internal_storage_balance_of(&self, account_id: &AccountId) -> Option<StorageBalance> {
         self.internal_get_storage(account_id)
             .map(|storage| StorageBalance {
@@ -90,8 +301,11 @@ impl Contract {
                 available: U128(
                     storage.storage_balance
                         - std::cmp::max(
-                            Balance::from(storage.used_bytes) * env::storage_byte_cost(),
-                            self.storage_balance_bounds().min.0,
+                            std::cmp::max(
+                                Balance::from(storage.used_bytes) * env::storage_byte_cost(),
+                                self.storage_balance_bounds().min.0,
+                            ),
+                            self.internal_sponsored_storage_balance(account_id),
                         ),
                 ),
             })
@@ -125,25 +339,15 @@ impl StorageManagement for Contract {
                 env::panic_str("The attached deposit is less than the mimimum storage balance");
             }
 
-            let mut storage = Storage::new();
             if registration_only {
                 let refund = amount - min_balance;
                 if refund > 0 {
                     Promise::new(env::predecessor_account_id()).transfer(refund);
                 }
-                storage.storage_balance = min_balance;
+                self.internal_register_account(&account_id, min_balance);
             } else {
-                storage.storage_balance = amount;
+                self.internal_register_account(&account_id, amount);
             }
-
-            let mut account = Account::new(&account_id);
-            // HACK: Tracking the extra bytes required to store the storage object itself and
-            // recording this under account storage tracker. It'll be accounted when saving the
-            // account below.
-            account.storage_tracker.start();
-            self.internal_set_storage(&account_id, storage);
-            account.storage_tracker.stop();
-            self.internal_set_account(&account_id, account);
         }
         self.internal_storage_balance_of(&account_id).unwrap()
     }
@@ -169,10 +373,29 @@ impl StorageManagement for Contract {
         }
     }
 
-    #[allow(unused_variables)]
+    /// Unregisters the predecessor account and refunds its storage balance. Returns `false` if
+    /// the account is not registered. With `force`, the collateral is decreased and the supplied
+    /// assets and the withdraw claims are transferred to the account before it's removed.
+    /// - Panics if the account has borrowed assets, booster staking or a swap in progress.
+    /// - Panics if the account has supplied or collateral assets or withdraw claims, unless
+    ///   `force` is given.
+    /// - Requires one yoctoNEAR.
     #[payable]
     fn storage_unregister(&mut self, force: Option<bool>) -> bool {
-        env::panic_str("The account can't be unregistered");
+        assert_one_yocto();
+        let account_id = env::predecessor_account_id();
+        let mut account = match self.internal_get_account(&account_id, false) {
+            Some(account) => account,
+            None => return false,
+        };
+        let force = force.unwrap_or(false);
+        self.internal_assert_can_unregister(&account, force);
+        if force {
+            self.internal_withdraw_all(&mut account);
+        }
+        self.internal_unregister_account(account);
+        events::emit::account_unregistered(&account_id, force);
+        true
     }
 
     fn storage_balance_bounds(&self) -> StorageBalanceBounds {
@@ -200,4 +423,34 @@ impl Contract {
                 ),
             })
     }
+
+    /// Returns the breakdown of the storage used by the account and its storage balance.
+    pub fn get_storage_report(&self, account_id: AccountId) -> Option<StorageReport> {
+        let storage = self.internal_get_storage(&account_id)?;
+        let storage_balance = self.internal_storage_balance_of(&account_id)?;
+        let account = self.internal_get_account(&account_id, true)?;
+        let supplied_bytes = compact_positions_bytes(&account.supplied);
+        let collateral_bytes = compact_positions_bytes(&account.collateral);
+        let borrowed_bytes = compact_positions_bytes(&account.borrowed);
+        let farms_bytes = account
+            .farm_ids
+            .iter()
+            .filter_map(|farm_id| self.internal_get_account_farm(&account, farm_id))
+            .map(|account_farm| borsh_size(&VAccountFarm::from(account_farm)))
+            .sum();
+        let other_bytes = storage
+            .used_bytes
+            .saturating_sub(supplied_bytes + collateral_bytes + borrowed_bytes + farms_bytes);
+        Some(StorageReport {
+            used_bytes: storage.used_bytes,
+            supplied_bytes,
+            collateral_bytes,
+            borrowed_bytes,
+            farms_bytes,
+            other_bytes,
+            total: storage.storage_balance,
+            locked: storage.storage_balance - storage_balance.available.0,
+            available: storage_balance.available.0,
+        })
+    }
 }
@@ -0,0 +1,188 @@
+use crate::*;
+use near_sdk::json_types::U128;
+use std::collections::HashSet;
+
+/// The estimated number of bytes that a new position adds to the storage of the account,
+/// including the farm of the position and the index of the asset accounts.
+/// A swap in progress is estimated as one position.
+pub const ESTIMATED_BYTES_PER_POSITION: StorageUsage = 400;
+
+/// The estimated storage cost of a planned bundle of actions.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageCostEstimate {
+    /// The number of the new positions that the actions may add to the account.
+    pub num_new_positions: u32,
+    /// The estimated number of the added bytes.
+    pub bytes: StorageUsage,
+    /// The estimated storage cost of the added bytes.
+    #[serde(with = "u128_dec_format")]
+    pub cost: Balance,
+    /// The storage balance the account needs to deposit before the actions.
+    #[serde(with = "u128_dec_format")]
+    pub needed: Balance,
+}
+
+#[derive(PartialEq, Eq, Hash)]
+enum Position {
+    Supplied(TokenId),
+    Collateral(TokenId),
+    Borrowed(TokenId),
+    Swap,
+}
+
+/// Returns the number of the new positions that the actions may add to the account.
+fn count_new_positions(account: &Account, actions: &[Action]) -> u32 {
+    let mut positions = HashSet::new();
+    for action in actions {
+        match action {
+            Action::IncreaseCollateral(asset_amount) => {
+                positions.insert(Position::Collateral(asset_amount.token_id.clone()));
+            }
+            Action::DecreaseCollateral(asset_amount) => {
+                positions.insert(Position::Supplied(asset_amount.token_id.clone()));
+            }
+            Action::Borrow(asset_amount) => {
+                positions.insert(Position::Supplied(asset_amount.token_id.clone()));
+                positions.insert(Position::Borrowed(asset_amount.token_id.clone()));
+            }
+            Action::BorrowAndWithdraw(asset_amount) => {
+                positions.insert(Position::Borrowed(asset_amount.token_id.clone()));
+            }
+            Action::Liquidate { out_assets, .. } => {
+                for asset_amount in out_assets {
+                    positions.insert(Position::Supplied(asset_amount.token_id.clone()));
+                }
+            }
+            Action::LeverageUp {
+                collateral_token,
+                borrow_token,
+                ..
+            } => {
+                positions.insert(Position::Collateral(collateral_token.clone()));
+                positions.insert(Position::Borrowed(borrow_token.clone()));
+                positions.insert(Position::Swap);
+            }
+            Action::WithdrawAndSwap { .. } | Action::RepayFromSupplied { .. } => {
+                positions.insert(Position::Swap);
+            }
+            Action::Withdraw(_)
+            | Action::WithdrawToAurora { .. }
+            | Action::WithdrawNear(_)
+            | Action::Repay(_)
+            | Action::ForceClose { .. }
+            | Action::ExecuteAutoRepayOrder { .. }
+            | Action::WithdrawNft { .. }
+            | Action::StartNftAuction { .. }
+            | Action::MigrateAsset { .. }
+            | Action::Net(_) => {}
+        }
+    }
+    positions
+        .iter()
+        .filter(|position| match position {
+            Position::Supplied(token_id) => !account.supplied.contains_key(token_id),
+            Position::Collateral(token_id) => !account.collateral.contains_key(token_id),
+            Position::Borrowed(token_id) => !account.borrowed.contains_key(token_id),
+            Position::Swap => true,
+        })
+        .count() as u32
+}
+
+impl Contract {
+    /// Returns the storage balance the account needs on top of its current storage balance to
+    /// cover the used bytes, the bytes added by the account object that are not saved yet, and
+    /// the estimated bytes of the given number of new positions.
+    fn internal_storage_needed(
+        &self,
+        account_id: &AccountId,
+        pending_bytes: StorageUsage,
+        num_new_positions: u32,
+    ) -> Balance {
+        let storage = self.internal_unwrap_storage(account_id);
+        let bytes = storage.used_bytes
+            + pending_bytes
+            + StorageUsage::from(num_new_positions) * ESTIMATED_BYTES_PER_POSITION;
+        (Balance::from(bytes) * env::storage_byte_cost()).saturating_sub(storage.storage_balance)
+    }
+
+    /// Asserts that the storage balance of the account is enough for the new positions that the
+    /// actions may add, so the actions fail early with the amount to top up instead of failing at
+    /// the end.
+    pub fn internal_assert_storage_for_actions(&self, account: &Account, actions: &[Action]) {
+        self.internal_assert_storage_for_new_positions(
+            account,
+            count_new_positions(account, actions),
+        );
+    }
+
+    /// Asserts that the storage balance of the account is enough for the given number of new
+    /// positions on top of the bytes that the account object has pending.
+    pub fn internal_assert_storage_for_new_positions(
+        &self,
+        account: &Account,
+        num_new_positions: u32,
+    ) {
+        if num_new_positions == 0 {
+            return;
+        }
+        let tracker = &account.storage_tracker;
+        let pending_bytes = tracker.bytes_added.saturating_sub(tracker.bytes_released);
+        let needed =
+            self.internal_storage_needed(&account.account_id, pending_bytes, num_new_positions);
+        if needed > 0 {
+            env::panic_str(&format!(
+                "The account needs {} more yoctoNEAR of storage",
+                needed
+            ));
+        }
+    }
+
+    pub fn internal_estimate_storage_cost(
+        &self,
+        account_id: &AccountId,
+        actions: &[Action],
+    ) -> Option<StorageCostEstimate> {
+        let account = self.internal_get_account(account_id, true)?;
+        let num_new_positions = count_new_positions(&account, actions);
+        let bytes = StorageUsage::from(num_new_positions) * ESTIMATED_BYTES_PER_POSITION;
+        Some(StorageCostEstimate {
+            num_new_positions,
+            bytes,
+            cost: Balance::from(bytes) * env::storage_byte_cost(),
+            needed: self.internal_storage_needed(account_id, 0, num_new_positions),
+        })
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the storage balance the account needs to deposit to cover the used bytes and the
+    /// estimated bytes of the given number of new positions, e.g. new supplied, collateral or
+    /// borrowed assets. Anyone can top up the storage balance of the account with
+    /// `storage_deposit`. Returns `None` if the account is not registered.
+    pub fn get_storage_needed(
+        &self,
+        account_id: AccountId,
+        num_new_positions: Option<u32>,
+    ) -> Option<U128> {
+        self.internal_get_storage(&account_id)?;
+        Some(U128(self.internal_storage_needed(
+            &account_id,
+            0,
+            num_new_positions.unwrap_or(0),
+        )))
+    }
+
+    /// Estimates the bytes and the storage cost that the given actions would add to the account,
+    /// and the storage balance the account needs to deposit before executing them. Returns `None`
+    /// if the account is not registered.
+    pub fn estimate_storage_cost(
+        &self,
+        account_id: AccountId,
+        actions: Vec<Action>,
+    ) -> Option<StorageCostEstimate> {
+        self.internal_estimate_storage_cost(&account_id, &actions)
+    }
+}
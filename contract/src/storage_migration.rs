@@ -0,0 +1,59 @@
+use crate::*;
+
+/// The result of a page of the account migration.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageMigrationPage {
+    /// The number of the accounts of the page that were migrated.
+    pub num_migrated: u64,
+    /// The token to pass to migrate the next page. `None` if this is the last page.
+    pub next_token: Option<String>,
+}
+
+impl Contract {
+    /// Rewrites the account saved by an older version in the compact form without any other
+    /// changes to the account, removing the legacy entries stored under the prefixes with the
+    /// account ID. The released storage is returned to the available storage balance of the
+    /// account. Returns whether the account was rewritten.
+    fn internal_migrate_account_storage(&mut self, account_id: &AccountId) -> bool {
+        let v_account = match self.accounts.get(account_id) {
+            Some(VAccount::Current(_)) | Some(VAccount::Sharded(_)) | None => return false,
+            Some(v_account) => v_account,
+        };
+        let mut account = self.internal_vaccount_into_account(account_id, v_account, false);
+        let mut storage = self.internal_unwrap_storage(account_id);
+        storage
+            .storage_tracker
+            .consume(&mut account.storage_tracker);
+        self.internal_index_account_tokens(&account);
+        storage.storage_tracker.start();
+        self.internal_save_account_farms(&mut account);
+        let account = self.account_into_compact(account);
+        self.accounts
+            .insert(account_id, &VAccount::Current(account));
+        storage.storage_tracker.stop();
+        self.internal_set_storage(account_id, storage);
+        true
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Rewrites a page of the accounts saved by the older versions in the compact form, so their
+    /// positions and farms are stored under the short prefixes. The accounts are migrated lazily
+    /// on the next operation anyway, this method migrates the idle accounts in the background.
+    /// Anyone can call it, the accounts are not changed otherwise.
+    /// - `token` is the continuation token from the previous page, starting from the first account
+    ///   if not given.
+    /// - `limit` is the maximum number of the accounts to check.
+    pub fn migrate_account_storage(
+        &mut self,
+        token: Option<String>,
+        limit: u64,
+    ) -> StorageMigrationPage {
+        self.internal_migrate_accounts_page(token, limit, |contract, account_id| {
+            contract.internal_migrate_account_storage(account_id)
+        })
+    }
+}
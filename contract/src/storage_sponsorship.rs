@@ -0,0 +1,137 @@
+use crate::*;
+use near_sdk::json_types::U128;
+
+/// The state of the owner-funded pool that pays for the registration of the new accounts.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct StorageSponsorshipView {
+    /// The balance of the pool that is not yet given to the accounts.
+    #[serde(with = "u128_dec_format")]
+    pub balance: Balance,
+    /// The total number of bytes sponsored to the accounts that are not yet reclaimed.
+    pub total_sponsored_bytes: StorageUsage,
+}
+
+impl Contract {
+    /// Returns the part of the storage balance of the account that is sponsored by the pool.
+    pub fn internal_sponsored_storage_balance(&self, account_id: &AccountId) -> Balance {
+        Balance::from(self.sponsored_storage.get(account_id).unwrap_or(0))
+            * env::storage_byte_cost()
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Adds the attached deposit to the storage sponsorship pool.
+    /// - Panics if the attached deposit is 0.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn fund_storage_sponsorship(&mut self) {
+        self.assert_owner();
+        let amount = env::attached_deposit();
+        assert!(amount > 0, "The attached deposit can't be 0");
+        self.storage_sponsorship_balance += amount;
+    }
+
+    /// Withdraws the given amount, or the whole balance if not given, from the storage
+    /// sponsorship pool to the owner.
+    /// - Panics if the amount exceeds the balance of the pool.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn withdraw_storage_sponsorship(&mut self, amount: Option<U128>) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        let amount = amount
+            .map(|a| a.0)
+            .unwrap_or(self.storage_sponsorship_balance);
+        assert!(
+            amount <= self.storage_sponsorship_balance,
+            "The amount exceeds the storage sponsorship balance"
+        );
+        self.storage_sponsorship_balance -= amount;
+        Promise::new(env::predecessor_account_id()).transfer(amount)
+    }
+
+    /// Registers the predecessor account with the storage balance for the
+    /// `max_sponsored_storage_bytes` from the config paid by the storage sponsorship pool. The
+    /// sponsored balance can't be withdrawn by the account, and the account has to deposit its
+    /// own storage balance to use more bytes.
+    /// - Panics if the storage sponsorship is disabled or the pool doesn't have enough balance.
+    /// - Panics if the account is already registered.
+    pub fn sponsored_storage_register(&mut self) {
+        let account_id = env::predecessor_account_id();
+        let sponsored_bytes = self
+            .internal_config()
+            .max_sponsored_storage_bytes
+            .expect("The storage sponsorship is disabled");
+        assert!(
+            self.internal_get_storage(&account_id).is_none(),
+            "The account is already registered"
+        );
+        let amount = Balance::from(sponsored_bytes) * env::storage_byte_cost();
+        assert!(
+            amount <= self.storage_sponsorship_balance,
+            "Not enough storage sponsorship balance"
+        );
+        self.storage_sponsorship_balance -= amount;
+        self.total_sponsored_bytes += sponsored_bytes;
+        self.sponsored_storage.insert(&account_id, &sponsored_bytes);
+        self.internal_register_account(&account_id, amount);
+    }
+
+    /// Returns the sponsored storage balance of the account back to the pool, once the account
+    /// has deposited enough of its own storage balance to cover the used bytes. Partially
+    /// reclaims the sponsored balance if the account has not deposited enough. Returns the
+    /// reclaimed amount.
+    /// - Panics if the account has no sponsored storage.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn reclaim_sponsored_storage(&mut self, account_id: AccountId) -> U128 {
+        assert_one_yocto();
+        self.assert_owner();
+        let sponsored_bytes = self
+            .sponsored_storage
+            .get(&account_id)
+            .expect("The account has no sponsored storage");
+        let mut storage = self.internal_unwrap_storage(&account_id);
+        let locked = std::cmp::max(
+            Balance::from(storage.used_bytes) * env::storage_byte_cost(),
+            MIN_STORAGE_BALANCE,
+        );
+        let reclaimed_bytes = std::cmp::min(
+            storage.storage_balance.saturating_sub(locked) / env::storage_byte_cost(),
+            Balance::from(sponsored_bytes),
+        ) as StorageUsage;
+        let amount = Balance::from(reclaimed_bytes) * env::storage_byte_cost();
+        if amount == 0 {
+            return U128(0);
+        }
+        storage.storage_balance -= amount;
+        self.internal_set_storage(&account_id, storage);
+        if reclaimed_bytes == sponsored_bytes {
+            self.sponsored_storage.remove(&account_id);
+        } else {
+            self.sponsored_storage
+                .insert(&account_id, &(sponsored_bytes - reclaimed_bytes));
+        }
+        self.total_sponsored_bytes -= reclaimed_bytes;
+        self.storage_sponsorship_balance += amount;
+        U128(amount)
+    }
+
+    /// Returns the balance of the storage sponsorship pool and the total sponsored bytes.
+    pub fn get_storage_sponsorship(&self) -> StorageSponsorshipView {
+        StorageSponsorshipView {
+            balance: self.storage_sponsorship_balance,
+            total_sponsored_bytes: self.total_sponsored_bytes,
+        }
+    }
+
+    /// Returns the number of bytes sponsored to the account that are not yet reclaimed.
+    pub fn get_sponsored_storage_bytes(&self, account_id: AccountId) -> StorageUsage {
+        self.sponsored_storage.get(&account_id).unwrap_or(0)
+    }
+}
@@ -45,7 +45,7 @@ impl StorageTracker {
     pub fn consume(&mut self, other: &mut StorageTracker) {
         self.bytes_added += other.bytes_added;
         other.bytes_added = 0;
-        self.bytes_released = other.bytes_released;
+        self.bytes_released += other.bytes_released;
         other.bytes_released = 0;
         assert!(
             other.initial_storage_usage.is_none(),
@@ -53,6 +53,16 @@ impl StorageTracker {
         );
     }
 
+    /// Discards the recorded changes, e.g. when the storage record of the account is removed.
+    pub fn discard(&mut self) {
+        assert!(
+            self.initial_storage_usage.is_none(),
+            "Can't discard storage tracker that is tracking storage"
+        );
+        self.bytes_added = 0;
+        self.bytes_released = 0;
+    }
+
     /// Returns true if no bytes is added or released, and the tracker is not active.
     pub fn is_empty(&self) -> bool {
         self.bytes_added == 0 && self.bytes_released == 0 && self.initial_storage_usage.is_none()
@@ -0,0 +1,117 @@
+use crate::*;
+
+/// The delay between announcing the sunset and executing it, so the accounts have the time to
+/// react before the deposits and the borrows are disabled for good.
+pub const SUNSET_DELAY_SEC: DurationSec = 7 * 24 * 60 * 60;
+
+/// The actions that are paused for every asset once the sunset is executed. Only repayments,
+/// withdrawals and liquidations stay enabled.
+const SUNSET_PAUSED_ACTIONS: [PausableAction; 3] = [
+    PausableAction::Deposit,
+    PausableAction::IncreaseCollateral,
+    PausableAction::Borrow,
+];
+
+/// The sunset of the deployment, i.e. its orderly end of life.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct Sunset {
+    /// The time after which the sunset can be executed.
+    #[serde(with = "u64_dec_format")]
+    pub effective_timestamp: Timestamp,
+    /// Whether the sunset is executed. It can't be reverted once it's executed.
+    pub is_executed: bool,
+}
+
+/// The sunset is stored under its own key, so it doesn't change the layout of the contract state.
+fn sunset_storage() -> LazyOption<Sunset> {
+    LazyOption::new(StorageKey::Sunset, None)
+}
+
+impl Contract {
+    pub fn internal_is_sunset(&self) -> bool {
+        sunset_storage()
+            .get()
+            .is_some_and(|sunset| sunset.is_executed)
+    }
+
+    pub fn assert_not_sunset(&self) {
+        assert!(!self.internal_is_sunset(), "The contract is sunset");
+    }
+
+    /// Pauses the actions that are disabled for all the assets if the sunset is executed.
+    pub fn internal_apply_sunset(&self, asset_config: &mut AssetConfig) {
+        if self.internal_is_sunset() {
+            for action in SUNSET_PAUSED_ACTIONS {
+                asset_config.pause(action);
+            }
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Announces the sunset of the deployment. It can be executed with `execute_sunset` after
+    /// `SUNSET_DELAY_SEC`, and cancelled with `cancel_sunset` until then.
+    /// - Panics if the sunset is already announced.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn announce_sunset(&mut self) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut storage = sunset_storage();
+        assert!(storage.get().is_none(), "The sunset is already announced");
+        let effective_timestamp = env::block_timestamp() + sec_to_nano(SUNSET_DELAY_SEC);
+        storage.set(&Sunset {
+            effective_timestamp,
+            is_executed: false,
+        });
+        events::emit::sunset_announced(effective_timestamp);
+    }
+
+    /// Cancels the announced sunset before it's executed.
+    /// - Panics if the sunset is not announced or is already executed.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn cancel_sunset(&mut self) {
+        assert_one_yocto();
+        self.assert_owner();
+        let mut storage = sunset_storage();
+        let sunset = storage.get().expect("The sunset is not announced");
+        assert!(!sunset.is_executed, "The sunset is already executed");
+        storage.remove();
+        events::emit::sunset_cancelled();
+    }
+
+    /// Executes the announced sunset once the delay has passed. Deposits, new collateral and
+    /// borrowing of every asset are paused for good, and no new farm rewards can be added, while
+    /// repayments, withdrawals and liquidations stay enabled. Anyone can execute it.
+    /// - Panics if the sunset is not announced, is already executed or the delay hasn't passed.
+    pub fn execute_sunset(&mut self) {
+        let mut storage = sunset_storage();
+        let mut sunset = storage.get().expect("The sunset is not announced");
+        assert!(!sunset.is_executed, "The sunset is already executed");
+        assert!(
+            env::block_timestamp() >= sunset.effective_timestamp,
+            "The sunset delay hasn't passed"
+        );
+        sunset.is_executed = true;
+        storage.set(&sunset);
+        for token_id in self.asset_ids.to_vec() {
+            let mut asset = self.internal_unwrap_asset(&token_id);
+            let old_asset_config = asset.config.clone();
+            self.internal_apply_sunset(&mut asset.config);
+            events::emit::asset_config_updated(&token_id, &old_asset_config, &asset.config);
+            self.internal_set_asset(&token_id, asset);
+        }
+        events::emit::sunset_executed();
+    }
+
+    /// Returns the sunset of the deployment, or `None` if it's not announced.
+    pub fn get_sunset(&self) -> Option<Sunset> {
+        sunset_storage().get()
+    }
+}
@@ -0,0 +1,174 @@
+use crate::*;
+use near_contract_standards::fungible_token::metadata::FungibleTokenMetadata;
+use near_sdk::{serde_json, PromiseResult};
+
+const GAS_FOR_TOKEN_METADATA: Gas = Gas(Gas::ONE_TERA.0 * 5);
+const GAS_FOR_AFTER_REFRESH_TOKEN_METADATA: Gas = Gas(Gas::ONE_TERA.0 * 10);
+
+/// The bounds of the decimals of the inner balance, i.e. the token decimals plus the extra
+/// decimals. The interest and the prices are computed with the inner balances, so too few decimals
+/// lose precision and too many can overflow.
+const MIN_INNER_DECIMALS: u8 = 18;
+const MAX_INNER_DECIMALS: u8 = 24;
+
+/// The maximum decimals of the USD price in the oracle price decimals of an asset.
+const MAX_ORACLE_PRICE_DECIMALS: u8 = 18;
+
+/// The metadata of the listed token, cached from the token contract.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub struct TokenMetadata {
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+/// Asserts that the extra decimals are consistent with the token decimals.
+pub fn assert_extra_decimals(decimals: u8, extra_decimals: u8) {
+    let inner_decimals = decimals.saturating_add(extra_decimals);
+    assert!(
+        (MIN_INNER_DECIMALS..=MAX_INNER_DECIMALS).contains(&inner_decimals),
+        "The extra decimals don't match the token decimals of {}",
+        decimals
+    );
+}
+
+/// Asserts that the oracle price decimals of the asset, if set, are consistent with the token
+/// decimals, i.e. include the token decimals and at most `MAX_ORACLE_PRICE_DECIMALS` decimals
+/// of the USD price.
+pub fn assert_oracle_price_decimals(decimals: u8, asset_config: &AssetConfig) {
+    if let Some(oracle_price_decimals) = asset_config.oracle_price_decimals {
+        assert!(
+            (decimals..=decimals.saturating_add(MAX_ORACLE_PRICE_DECIMALS))
+                .contains(&oracle_price_decimals),
+            "The oracle price decimals don't match the token decimals of {}",
+            decimals
+        );
+    }
+}
+
+/// Parses the result of `ft_metadata` of the token or `mft_metadata` of the pool shares.
+pub fn parse_ft_metadata_result() -> Option<TokenMetadata> {
+    match env::promise_result(0) {
+        PromiseResult::Successful(value) => {
+            serde_json::from_slice::<FungibleTokenMetadata>(&value).ok()
+        }
+        _ => None,
+    }
+    .map(|metadata| TokenMetadata {
+        symbol: metadata.symbol,
+        decimals: metadata.decimals,
+    })
+}
+
+/// Parses the result of `mt_metadata_base_by_token_id` of the multi token.
+pub fn parse_mt_metadata_result() -> Option<TokenMetadata> {
+    match env::promise_result(0) {
+        PromiseResult::Successful(value) => {
+            serde_json::from_slice::<Vec<MtBaseTokenMetadata>>(&value).ok()
+        }
+        _ => None,
+    }
+    .and_then(|metadata| metadata.into_iter().next())
+    .and_then(|metadata| {
+        let decimals = metadata.decimals?.parse::<u8>().ok()?;
+        Some(TokenMetadata {
+            symbol: metadata.symbol.unwrap_or_default(),
+            decimals,
+        })
+    })
+}
+
+impl Contract {
+    /// Requests the metadata of the token from the token contract. The metadata of the pool
+    /// shares is requested from the exchange.
+    pub fn internal_token_metadata(&self, token_id: &TokenId) -> Promise {
+        if let Some(pool_id) = parse_pool_id(token_id) {
+            return self.internal_lp_metadata(pool_id);
+        }
+        match self.mt_tokens.get(token_id) {
+            Some(mt_token) => ext_multi_token::mt_metadata_base_by_token_id(
+                vec![mt_token.token_id],
+                mt_token.contract_id,
+                NO_DEPOSIT,
+                GAS_FOR_TOKEN_METADATA,
+            ),
+            None => {
+                ext_ft_metadata::ft_metadata(token_id.clone(), NO_DEPOSIT, GAS_FOR_TOKEN_METADATA)
+            }
+        }
+    }
+
+    /// Caches the metadata of the token after checking it against the extra decimals and the
+    /// oracle price decimals of the asset config.
+    pub fn internal_set_token_metadata(
+        &mut self,
+        token_id: &TokenId,
+        metadata: &TokenMetadata,
+        asset_config: &AssetConfig,
+    ) {
+        assert_extra_decimals(metadata.decimals, asset_config.extra_decimals);
+        assert_oracle_price_decimals(metadata.decimals, asset_config);
+        self.token_metadata.insert(token_id, metadata);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Fetches the metadata of the listed token from the token contract and caches its symbol and
+    /// decimals, e.g. for the assets listed before the metadata was cached.
+    /// - Panics if the asset doesn't exist.
+    /// - Panics in the callback if the metadata can't be fetched or the token decimals don't
+    ///   match the extra decimals of the asset.
+    /// - Requires one yoctoNEAR.
+    /// - Requires to be called by the contract owner.
+    #[payable]
+    pub fn refresh_token_metadata(&mut self, token_id: AccountId) -> Promise {
+        assert_one_yocto();
+        self.assert_owner();
+        self.internal_unwrap_asset(&token_id);
+        self.internal_token_metadata(&token_id).then(
+            ext_token_metadata::after_refresh_token_metadata(
+                token_id,
+                env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_AFTER_REFRESH_TOKEN_METADATA,
+            ),
+        )
+    }
+
+    /// Returns the cached metadata of the listed token.
+    pub fn get_token_metadata(&self, token_id: AccountId) -> Option<TokenMetadata> {
+        self.token_metadata.get(&token_id)
+    }
+}
+
+#[ext_contract(ext_ft_metadata)]
+trait ExtFtMetadata {
+    fn ft_metadata(&self) -> FungibleTokenMetadata;
+}
+
+#[ext_contract(ext_token_metadata)]
+trait ExtTokenMetadata {
+    fn after_refresh_token_metadata(&mut self, token_id: TokenId);
+}
+
+trait ExtTokenMetadata {
+    fn after_refresh_token_metadata(&mut self, token_id: TokenId);
+}
+
+#[near_bindgen]
+impl ExtTokenMetadata for Contract {
+    /// Caches the metadata if the token decimals match the extra decimals of the asset.
+    #[private]
+    fn after_refresh_token_metadata(&mut self, token_id: TokenId) {
+        let metadata = if self.mt_tokens.contains_key(&token_id) {
+            parse_mt_metadata_result()
+        } else {
+            parse_ft_metadata_result()
+        }
+        .expect("Failed to get the token metadata");
+        let asset = self.internal_unwrap_asset(&token_id);
+        self.internal_set_token_metadata(&token_id, &metadata, &asset.config);
+    }
+}
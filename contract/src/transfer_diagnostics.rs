@@ -0,0 +1,118 @@
+use crate::*;
+use near_contract_standards::storage_management::StorageBalance;
+use near_sdk::json_types::U128;
+use near_sdk::{serde_json, PromiseResult};
+
+const GAS_FOR_STORAGE_BALANCE_OF: Gas = Gas(Gas::ONE_TERA.0 * 5);
+const GAS_FOR_AFTER_STORAGE_BALANCE_OF: Gas = Gas(Gas::ONE_TERA.0 * 5);
+
+/// The reason category of a failed token transfer. The failure details of the promise are not
+/// available to the callback, so the reason is diagnosed by querying the storage balance of the
+/// receiver from the token.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum TransferFailureReason {
+    /// The receiver is not registered with the token.
+    ReceiverNotRegistered,
+    /// The token has failed to return the storage balance of the receiver, e.g. the token contract
+    /// is paused entirely or broken.
+    TokenUnavailable,
+    /// The receiver is registered, so the token has rejected the transfer for another reason,
+    /// e.g. the transfers of the token are paused or the transfer ran out of gas.
+    Unknown,
+}
+
+/// How the contract has recovered the amount of a failed token transfer.
+#[derive(Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum TransferRecovery {
+    /// The amount is recorded as a withdraw claim of the account.
+    WithdrawClaim,
+    /// The amount is returned to the reserve of the asset.
+    Reserve,
+}
+
+impl Contract {
+    /// Queries the storage balance of the receiver from the token to diagnose the reason of the
+    /// failed transfer and emits the `transfer_failed` event once the reason is known. Skipped if
+    /// the heavy events are disabled.
+    pub fn internal_diagnose_transfer_failure(
+        &mut self,
+        receiver_id: &AccountId,
+        token_id: &TokenId,
+        amount: Balance,
+        recovery: TransferRecovery,
+    ) {
+        self.internal_emit_heavy_events(|_| {
+            ext_storage_balance::storage_balance_of(
+                receiver_id.clone(),
+                token_id.clone(),
+                NO_DEPOSIT,
+                GAS_FOR_STORAGE_BALANCE_OF,
+            )
+            .then(ext_transfer_diagnostics::after_storage_balance_of(
+                receiver_id.clone(),
+                token_id.clone(),
+                amount.into(),
+                recovery,
+                env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_AFTER_STORAGE_BALANCE_OF,
+            ));
+        });
+    }
+}
+
+#[ext_contract(ext_storage_balance)]
+trait ExtStorageBalance {
+    fn storage_balance_of(&self, account_id: AccountId) -> Option<StorageBalance>;
+}
+
+#[ext_contract(ext_transfer_diagnostics)]
+trait ExtTransferDiagnostics {
+    fn after_storage_balance_of(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        recovery: TransferRecovery,
+    );
+}
+
+trait ExtTransferDiagnostics {
+    fn after_storage_balance_of(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        recovery: TransferRecovery,
+    );
+}
+
+#[near_bindgen]
+impl ExtTransferDiagnostics for Contract {
+    /// Emits the `transfer_failed` event with the reason diagnosed from the storage balance of
+    /// the receiver.
+    #[private]
+    fn after_storage_balance_of(
+        &mut self,
+        receiver_id: AccountId,
+        token_id: TokenId,
+        amount: U128,
+        recovery: TransferRecovery,
+    ) {
+        let reason = match env::promise_result(0) {
+            PromiseResult::Successful(value) => {
+                match serde_json::from_slice::<Option<StorageBalance>>(&value) {
+                    Ok(None) => TransferFailureReason::ReceiverNotRegistered,
+                    Ok(Some(_)) => TransferFailureReason::Unknown,
+                    Err(_) => TransferFailureReason::TokenUnavailable,
+                }
+            }
+            _ => TransferFailureReason::TokenUnavailable,
+        };
+        events::emit::transfer_failed(&receiver_id, amount.0, &token_id, &reason, &recovery);
+    }
+}
@@ -1,46 +1,46 @@
 use crate::*;
 
+/// The versions of the deployed code and of the stored state.
+#[derive(Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct VersionInfo {
+    /// The semver of the code.
+    pub version: String,
+    /// The version of the stored state layout.
+    pub state_version: u32,
+    /// The version of the stored config layout.
+    pub config_version: u32,
+}
+
 #[near_bindgen]
 impl Contract {
     /// A method to migrate a state during the contract upgrade.
-    /// Can only be called after upgrade method.
+    /// Can only be called after upgrade method. The state of any known version is migrated to the
+    /// current version, and the invariants of the migrated state are checked.
     #[private]
     #[init(ignore_state)]
     pub fn migrate_state() -> Self {
-        #[derive(BorshDeserialize, BorshSerialize, PanicOnDefault)]
-        pub struct Contract060 {
-            pub accounts: UnorderedMap<AccountId, VAccount>,
-            pub storage: LookupMap<AccountId, VStorage>,
-            pub assets: LookupMap<TokenId, VAsset>,
-            pub asset_farms: LookupMap<FarmId, VAssetFarm>,
-            pub asset_ids: UnorderedSet<TokenId>,
-            pub config: LazyOption<Config>,
-        }
-
-        let Contract060 {
-            accounts,
-            storage,
-            assets,
-            asset_farms,
-            asset_ids,
-            config,
-        } = env::state_read().unwrap();
-
-        Self {
-            accounts,
-            storage,
-            assets,
-            asset_farms,
-            asset_ids,
-            config,
-            last_prices: Default::default(),
-        }
+        let contract: Self = VContract::read().into();
+        contract.internal_assert_state_invariants();
+        write_state_version();
+        contract
     }
 
     /// Returns semver of this contract.
     pub fn get_version(&self) -> String {
         env!("CARGO_PKG_VERSION").to_string()
     }
+
+    /// Returns the semver of this contract with the versions of the stored state and config, so
+    /// the tooling can verify what is deployed. `get_version` stays for compatibility.
+    pub fn get_version_info(&self) -> VersionInfo {
+        VersionInfo {
+            version: self.get_version(),
+            state_version: self.get_state_version(),
+            config_version: self.config.get().unwrap().version(),
+        }
+    }
 }
 
 mod upgrade {
@@ -60,6 +60,7 @@ mod upgrade {
         env::setup_panic_hook();
         let contract: Contract = env::state_read().expect("ERR_CONTRACT_IS_NOT_INITIALIZED");
         contract.assert_owner();
+        contract.internal_assert_state_invariants();
         let current_account_id = env::current_account_id().as_bytes().to_vec();
         let migrate_method_name = b"migrate_state".to_vec();
         let get_config_method_name = b"get_config".to_vec();
@@ -15,7 +15,10 @@ pub(crate) fn sec_to_nano(sec: u32) -> u64 {
 }
 
 pub(crate) fn u128_ratio(a: u128, num: u128, denom: u128) -> Balance {
-    (U256::from(a) * U256::from(num) / U256::from(denom)).as_u128()
+    u256_to_u128(
+        U256::from(a) * U256::from(num) / U256::from(denom),
+        "u128_ratio",
+    )
 }
 
 pub(crate) fn ratio(balance: Balance, r: u32) -> Balance {
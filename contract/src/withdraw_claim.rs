@@ -0,0 +1,114 @@
+use crate::*;
+
+/// The reason recorded for a failed token transfer. The failure details of the promise are not
+/// available to the callback, e.g. the receiver is not registered or the token is paused.
+const TRANSFER_FAILED_REASON: &str = "The token transfer has failed";
+
+/// A withdrawal that failed to be transferred to the account. The amount is not returned to the
+/// supplied balance, and can be transferred again using `retry_withdraw`.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawClaim {
+    pub token_id: TokenId,
+    /// The total amount of the failed withdrawals of the token.
+    #[serde(with = "u128_dec_format")]
+    pub amount: Balance,
+    /// The reason of the last failure.
+    pub reason: String,
+    /// When the last failure happened in nanoseconds.
+    #[serde(with = "u64_dec_format")]
+    pub timestamp: Timestamp,
+}
+
+impl Contract {
+    pub fn internal_get_withdraw_claims(&self, account_id: &AccountId) -> Vec<WithdrawClaim> {
+        self.withdraw_claims.get(account_id).unwrap_or_default()
+    }
+
+    /// Saves the claims and records the storage change under the account storage tracker.
+    pub fn internal_set_withdraw_claims(
+        &mut self,
+        account: &mut Account,
+        claims: Vec<WithdrawClaim>,
+    ) {
+        account.storage_tracker.start();
+        if claims.is_empty() {
+            self.withdraw_claims.remove(&account.account_id);
+        } else {
+            self.withdraw_claims.insert(&account.account_id, &claims);
+        }
+        account.storage_tracker.stop();
+    }
+
+    /// Records the amount of the failed withdrawal as claimable by the account.
+    pub fn internal_add_withdraw_claim(
+        &mut self,
+        account: &mut Account,
+        token_id: &TokenId,
+        amount: Balance,
+    ) {
+        let mut claims = self.internal_get_withdraw_claims(&account.account_id);
+        add_claim(&mut claims, token_id, amount);
+        self.internal_set_withdraw_claims(account, claims);
+    }
+
+    /// Records the amount of the failed withdrawal as claimable by the account that was
+    /// unregistered while the transfer was in progress. The storage of the claims is charged to
+    /// the account once it registers again.
+    pub fn internal_add_unregistered_withdraw_claim(
+        &mut self,
+        account_id: &AccountId,
+        token_id: &TokenId,
+        amount: Balance,
+    ) {
+        let mut claims = self.internal_get_withdraw_claims(account_id);
+        add_claim(&mut claims, token_id, amount);
+        self.withdraw_claims.insert(account_id, &claims);
+    }
+}
+
+fn add_claim(claims: &mut Vec<WithdrawClaim>, token_id: &TokenId, amount: Balance) {
+    if let Some(claim) = claims.iter_mut().find(|claim| &claim.token_id == token_id) {
+        claim.amount += amount;
+        claim.reason = TRANSFER_FAILED_REASON.to_string();
+        claim.timestamp = env::block_timestamp();
+    } else {
+        claims.push(WithdrawClaim {
+            token_id: token_id.clone(),
+            amount,
+            reason: TRANSFER_FAILED_REASON.to_string(),
+            timestamp: env::block_timestamp(),
+        });
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Transfers the claimable amount of the failed withdrawals of the given token to the
+    /// predecessor account. If the transfer fails again, the amount is claimable again.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn retry_withdraw(&mut self, token_id: TokenId) -> Promise {
+        assert_one_yocto();
+        self.internal_assert_no_extra_decimals_migration(&token_id);
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        let mut claims = self.internal_get_withdraw_claims(&account_id);
+        let index = claims
+            .iter()
+            .position(|claim| claim.token_id == token_id)
+            .expect("No claimable amount for the token");
+        let claim = claims.remove(index);
+        self.internal_set_withdraw_claims(&mut account, claims);
+        self.internal_set_account(&account_id, account);
+
+        events::emit::withdraw_started(&account_id, claim.amount, &token_id);
+        self.internal_ft_transfer(&account_id, &token_id, claim.amount)
+    }
+
+    /// Returns the claimable amounts of the failed withdrawals of the given account.
+    pub fn get_withdraw_claims(&self, account_id: AccountId) -> Vec<WithdrawClaim> {
+        self.internal_get_withdraw_claims(&account_id)
+    }
+}
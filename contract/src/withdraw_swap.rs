@@ -0,0 +1,216 @@
+use crate::*;
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
+
+const GAS_FOR_AFTER_WITHDRAW_SWAP: Gas = Gas(Gas::ONE_TERA.0 * 30);
+
+/// A withdrawal swap that is in progress for an account.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawSwap {
+    pub token_id: TokenId,
+    /// The withdrawn amount that was sent to the exchange.
+    #[serde(with = "u128_dec_format")]
+    pub amount: Balance,
+    pub output_token_id: TokenId,
+    /// The minimum amount of the output token that has to be received to complete the withdrawal.
+    #[serde(with = "u128_dec_format")]
+    pub min_received: Balance,
+    /// When the swap expires in nanoseconds. Anyone can roll back the expired swap with
+    /// `clear_expired_withdraw_swap`.
+    #[serde(with = "u64_dec_format")]
+    pub expiry_timestamp: Timestamp,
+}
+
+impl Contract {
+    /// Withdraws the asset and swaps it on the exchange. The output of the swap is
+    /// transferred to the account once received if it's at least `min_received` (in the output
+    /// token decimals). Otherwise the output is added to the supplied balance of the account.
+    pub fn internal_withdraw_and_swap(
+        &mut self,
+        account: &mut Account,
+        asset_amount: &AssetAmount,
+        mut swap_route: Vec<SwapAction>,
+        min_received: Balance,
+        dex_id: Option<AccountId>,
+    ) {
+        let account_id = &account.account_id.clone();
+        let dex = self
+            .internal_get_dex(dex_id)
+            .expect("Swaps are not enabled");
+        assert!(
+            !self.withdraw_swaps.contains_key(account_id),
+            "Withdraw swap is in progress"
+        );
+        assert!(min_received > 0, "Minimum received amount can't be 0");
+
+        let token_id = &asset_amount.token_id;
+        let output_token_id = swap_route
+            .last()
+            .map(|action| action.token_out.clone())
+            .expect("Swap route can't be empty");
+        let output_asset = self.internal_unwrap_asset(&output_token_id);
+        let min_received = min_received * 10u128.pow(output_asset.config.extra_decimals as u32);
+
+        let (shares, amount) = self.internal_withdraw(account, asset_amount);
+        self.internal_use_withdraw_timelock(account, token_id, amount);
+        events::emit::withdraw(account_id, amount, shares, token_id);
+        let asset = self.internal_unwrap_asset(token_id);
+        let ft_amount = amount / 10u128.pow(asset.config.extra_decimals as u32);
+        assert!(ft_amount > 0, "Withdraw swap amount can't be 0");
+        prepare_swap_route(&mut swap_route, token_id, &output_token_id, ft_amount);
+
+        account.storage_tracker.start();
+        self.withdraw_swaps.insert(
+            account_id,
+            &WithdrawSwap {
+                token_id: token_id.clone(),
+                amount,
+                output_token_id: output_token_id.clone(),
+                min_received,
+                expiry_timestamp: env::block_timestamp() + SWAP_EXPIRY,
+            },
+        );
+        account.storage_tracker.stop();
+        events::emit::withdraw_swap_started(
+            account_id,
+            amount,
+            token_id,
+            min_received,
+            &output_token_id,
+        );
+
+        let client_echo = json!({
+            "WithdrawSwapOutput": {
+                "account_id": account_id,
+            }
+        })
+        .to_string();
+        dex.swap(swap_route, client_echo)
+            .then(ext_withdraw_swap::after_withdraw_swap(
+                account_id.clone(),
+                amount.into(),
+                env::current_account_id(),
+                NO_DEPOSIT,
+                GAS_FOR_AFTER_WITHDRAW_SWAP,
+            ));
+    }
+
+    /// Transfers the received output of the withdrawal swap to the account if it's at least the
+    /// minimum received amount. Otherwise the output is added to the supplied balance.
+    pub fn internal_withdraw_swap_output(
+        &mut self,
+        sender_id: &AccountId,
+        token_id: &TokenId,
+        account_id: &AccountId,
+        amount: Balance,
+    ) {
+        assert!(
+            self.internal_is_dex(sender_id),
+            "Only the exchange can transfer the withdraw swap output"
+        );
+        let withdraw_swap = self
+            .internal_track_account_storage(account_id, |contract| {
+                contract.withdraw_swaps.remove(account_id)
+            })
+            .expect("Withdraw swap is not in progress");
+        assert_eq!(
+            &withdraw_swap.output_token_id, token_id,
+            "Unexpected withdraw swap output token"
+        );
+
+        if amount >= withdraw_swap.min_received {
+            self.internal_ft_transfer(account_id, token_id, amount);
+            events::emit::withdraw_started(account_id, amount, token_id);
+        } else {
+            let mut account = self.internal_unwrap_account(account_id);
+            account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+            self.internal_deposit(&mut account, token_id, amount);
+            events::emit::withdraw_swap_failed(account_id, amount, token_id);
+            self.internal_account_apply_affected_farms(&mut account);
+            self.internal_set_account(account_id, account);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the withdrawal swap in progress for the given account, if any.
+    pub fn get_withdraw_swap(&self, account_id: AccountId) -> Option<WithdrawSwap> {
+        self.withdraw_swaps.get(&account_id)
+    }
+
+    /// Rolls back the expired withdrawal swap of the account. The output of the stuck swap is left
+    /// with the contract on the exchange, so the withdrawn amount is returned to the supplied
+    /// balance of the account from the reserve of the asset. Anyone can roll back the expired
+    /// swap.
+    /// - Panics if the account doesn't have an expired withdraw swap.
+    /// - Panics if the reserve of the asset is not enough to cover the withdrawn amount.
+    pub fn clear_expired_withdraw_swap(&mut self, account_id: AccountId) {
+        let withdraw_swap = self
+            .withdraw_swaps
+            .get(&account_id)
+            .filter(|withdraw_swap| env::block_timestamp() >= withdraw_swap.expiry_timestamp)
+            .expect("The account doesn't have an expired withdraw swap");
+        self.internal_track_account_storage(&account_id, |contract| {
+            contract.withdraw_swaps.remove(&account_id)
+        });
+
+        let token_id = withdraw_swap.token_id;
+        let amount = withdraw_swap.amount;
+        let mut asset = self.internal_unwrap_asset(&token_id);
+        assert!(
+            amount <= asset.reserved,
+            "The reserve is not enough to roll back the withdraw swap"
+        );
+        asset.reserved -= amount;
+        self.internal_set_asset(&token_id, asset);
+        let mut account = self.internal_unwrap_account(&account_id);
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        self.internal_deposit(&mut account, &token_id, amount);
+        events::emit::withdraw_swap_expired(&account_id, amount, &token_id);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+    }
+}
+
+#[ext_contract(ext_withdraw_swap)]
+trait ExtWithdrawSwap {
+    fn after_withdraw_swap(&mut self, account_id: AccountId, amount: U128) -> bool;
+}
+
+trait ExtWithdrawSwap {
+    fn after_withdraw_swap(&mut self, account_id: AccountId, amount: U128) -> bool;
+}
+
+#[near_bindgen]
+impl ExtWithdrawSwap for Contract {
+    /// Returns the withdrawn amount refunded by the exchange to the supplied balance if the swap
+    /// has failed. Returns whether the swap has succeeded.
+    #[private]
+    fn after_withdraw_swap(&mut self, account_id: AccountId, amount: U128) -> bool {
+        let withdraw_swap = match self.withdraw_swaps.get(&account_id) {
+            Some(withdraw_swap) if withdraw_swap.amount == amount.0 => withdraw_swap,
+            // The swap output was already received.
+            _ => return true,
+        };
+        let token_id = withdraw_swap.token_id;
+        let asset = self.internal_unwrap_asset(&token_id);
+        let refunded = transfer_call_refunded_amount(amount.0, asset.config.extra_decimals);
+        if refunded == 0 {
+            return true;
+        }
+
+        self.internal_track_account_storage(&account_id, |contract| {
+            contract.withdraw_swaps.remove(&account_id)
+        });
+        let mut account = self.internal_unwrap_account(&account_id);
+        account.add_affected_farm(FarmId::Supplied(token_id.clone()));
+        self.internal_deposit(&mut account, &token_id, refunded);
+        events::emit::withdraw_swap_failed(&account_id, refunded, &token_id);
+        self.internal_account_apply_affected_farms(&mut account);
+        self.internal_set_account(&account_id, account);
+        false
+    }
+}
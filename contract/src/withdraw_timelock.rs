@@ -0,0 +1,258 @@
+use crate::*;
+use near_sdk::json_types::U64;
+
+const NANOS_PER_DAY: Duration = 24 * 60 * 60 * 10u64.pow(9);
+
+/// Protects the account from instant withdrawals of an asset above the threshold within 24 hours.
+/// Such withdrawals have to be announced first and can only be executed after the delay.
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct WithdrawTimelock {
+    pub token_id: TokenId,
+    /// Withdrawals above this amount in total within the 24 hour window have to be announced.
+    #[serde(with = "u128_dec_format")]
+    pub threshold: Balance,
+    /// The delay between the announcement and the withdrawal in seconds.
+    pub delay_sec: DurationSec,
+    /// The announced withdrawal that is waiting for the delay.
+    pub announced_withdraw: Option<AnnouncedWithdraw>,
+    /// When the timelock can be removed in nanoseconds, if the removal was requested.
+    pub removal_timestamp: Option<U64>,
+    /// When the current 24 hour window of the withdrawals has started in nanoseconds.
+    #[serde(with = "u64_dec_format")]
+    pub window_start_timestamp: Timestamp,
+    /// The amount withdrawn without the announcement within the current window.
+    #[serde(with = "u128_dec_format")]
+    pub window_withdrawn: Balance,
+}
+
+impl WithdrawTimelock {
+    /// Returns the amount withdrawn without the announcement within the window at the given
+    /// timestamp.
+    fn withdrawn_in_window(&self, timestamp: Timestamp) -> Balance {
+        if timestamp < self.window_start_timestamp + NANOS_PER_DAY {
+            self.window_withdrawn
+        } else {
+            0
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Debug, Deserialize))]
+#[serde(crate = "near_sdk::serde")]
+pub struct AnnouncedWithdraw {
+    /// The maximum amount that can be withdrawn.
+    #[serde(with = "u128_dec_format")]
+    pub amount: Balance,
+    /// When the withdrawal can be executed in nanoseconds.
+    #[serde(with = "u64_dec_format")]
+    pub unlock_timestamp: Timestamp,
+}
+
+impl Contract {
+    pub fn internal_get_withdraw_timelocks(&self, account_id: &AccountId) -> Vec<WithdrawTimelock> {
+        self.withdraw_timelocks.get(account_id).unwrap_or_default()
+    }
+
+    /// Saves the timelocks and records the storage change under the account storage tracker.
+    pub fn internal_set_withdraw_timelocks(
+        &mut self,
+        account: &mut Account,
+        timelocks: Vec<WithdrawTimelock>,
+    ) {
+        account.storage_tracker.start();
+        if timelocks.is_empty() {
+            self.withdraw_timelocks.remove(&account.account_id);
+        } else {
+            self.withdraw_timelocks
+                .insert(&account.account_id, &timelocks);
+        }
+        account.storage_tracker.stop();
+    }
+
+    /// Asserts that the given amount of the asset can be transferred out of the account.
+    /// The amount is added to the amount withdrawn within the 24 hour window. If the total is
+    /// above the timelock threshold, the announced withdrawal is used instead.
+    pub fn internal_use_withdraw_timelock(
+        &mut self,
+        account: &mut Account,
+        token_id: &TokenId,
+        amount: Balance,
+    ) {
+        let mut timelocks = self.internal_get_withdraw_timelocks(&account.account_id);
+        let timelock = match timelocks
+            .iter_mut()
+            .find(|timelock| &timelock.token_id == token_id)
+        {
+            Some(timelock) => timelock,
+            None => return,
+        };
+        let timestamp = env::block_timestamp();
+        let window_withdrawn = timelock.withdrawn_in_window(timestamp);
+        if window_withdrawn == 0 {
+            timelock.window_start_timestamp = timestamp;
+        }
+        if window_withdrawn + amount <= timelock.threshold {
+            timelock.window_withdrawn = window_withdrawn + amount;
+            self.internal_set_withdraw_timelocks(account, timelocks);
+            return;
+        }
+        let announced_withdraw = timelock
+            .announced_withdraw
+            .take()
+            .expect("The withdrawal above the timelock threshold has to be announced");
+        assert!(
+            amount <= announced_withdraw.amount,
+            "The withdrawal amount exceeds the announced amount"
+        );
+        assert!(
+            env::block_timestamp() >= announced_withdraw.unlock_timestamp,
+            "The announced withdrawal is not unlocked yet"
+        );
+        self.internal_set_withdraw_timelocks(account, timelocks);
+    }
+
+    /// Asserts that the given amount of the asset together with the amount withdrawn within the
+    /// 24 hour window is not above the timelock threshold.
+    pub fn internal_assert_below_withdraw_timelock(
+        &self,
+        account_id: &AccountId,
+        token_id: &TokenId,
+        amount: Balance,
+    ) {
+        if let Some(timelock) = self
+            .internal_get_withdraw_timelocks(account_id)
+            .into_iter()
+            .find(|timelock| &timelock.token_id == token_id)
+        {
+            let window_withdrawn = timelock.withdrawn_in_window(env::block_timestamp());
+            assert!(
+                window_withdrawn + amount <= timelock.threshold,
+                "The amount exceeds the withdraw timelock threshold"
+            );
+        }
+    }
+
+    fn internal_update_withdraw_timelock<F>(&mut self, token_id: &TokenId, f: F)
+    where
+        F: FnOnce(&mut Vec<WithdrawTimelock>, usize),
+    {
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        let mut timelocks = self.internal_get_withdraw_timelocks(&account_id);
+        let index = timelocks
+            .iter()
+            .position(|timelock| &timelock.token_id == token_id)
+            .expect("Withdraw timelock not found");
+        f(&mut timelocks, index);
+        self.internal_set_withdraw_timelocks(&mut account, timelocks);
+        self.internal_set_account(&account_id, account);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Sets the withdraw timelock for the given asset of the predecessor account.
+    /// An existing timelock can only be made stricter, i.e. with a lower threshold and a longer
+    /// delay. It also cancels the requested removal of the timelock.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn set_withdraw_timelock(
+        &mut self,
+        token_id: TokenId,
+        threshold: U128,
+        delay_sec: DurationSec,
+    ) {
+        assert_one_yocto();
+        self.internal_unwrap_asset(&token_id);
+        self.internal_assert_no_extra_decimals_migration(&token_id);
+        let account_id = env::predecessor_account_id();
+        let mut account = self.internal_unwrap_account(&account_id);
+        let mut timelocks = self.internal_get_withdraw_timelocks(&account_id);
+        if let Some(timelock) = timelocks
+            .iter_mut()
+            .find(|timelock| timelock.token_id == token_id)
+        {
+            assert!(
+                threshold.0 <= timelock.threshold && delay_sec >= timelock.delay_sec,
+                "The withdraw timelock can only be made stricter"
+            );
+            timelock.threshold = threshold.0;
+            timelock.delay_sec = delay_sec;
+            timelock.removal_timestamp = None;
+        } else {
+            timelocks.push(WithdrawTimelock {
+                token_id,
+                threshold: threshold.0,
+                delay_sec,
+                announced_withdraw: None,
+                removal_timestamp: None,
+                window_start_timestamp: 0,
+                window_withdrawn: 0,
+            });
+        }
+        self.internal_set_withdraw_timelocks(&mut account, timelocks);
+        self.internal_set_account(&account_id, account);
+    }
+
+    /// Removes the withdraw timelock for the given asset of the predecessor account.
+    /// The first call requests the removal, and the timelock can be removed by calling it again
+    /// after the delay of the timelock.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn remove_withdraw_timelock(&mut self, token_id: TokenId) {
+        assert_one_yocto();
+        self.internal_update_withdraw_timelock(&token_id, |timelocks, index| {
+            let timelock = &mut timelocks[index];
+            match timelock.removal_timestamp {
+                Some(removal_timestamp) => {
+                    assert!(
+                        env::block_timestamp() >= removal_timestamp.0,
+                        "The withdraw timelock can't be removed yet"
+                    );
+                    timelocks.remove(index);
+                }
+                None => {
+                    timelock.removal_timestamp =
+                        Some((env::block_timestamp() + sec_to_nano(timelock.delay_sec)).into());
+                }
+            }
+        });
+    }
+
+    /// Announces the withdrawal of up to the given amount of the asset above the timelock
+    /// threshold. Replaces the previously announced withdrawal.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn announce_withdraw(&mut self, token_id: TokenId, amount: U128) {
+        assert_one_yocto();
+        self.internal_assert_no_extra_decimals_migration(&token_id);
+        self.internal_update_withdraw_timelock(&token_id, |timelocks, index| {
+            let timelock = &mut timelocks[index];
+            timelock.announced_withdraw = Some(AnnouncedWithdraw {
+                amount: amount.0,
+                unlock_timestamp: env::block_timestamp() + sec_to_nano(timelock.delay_sec),
+            });
+        });
+    }
+
+    /// Cancels the announced withdrawal of the asset.
+    /// - Requires one yoctoNEAR.
+    #[payable]
+    pub fn cancel_announced_withdraw(&mut self, token_id: TokenId) {
+        assert_one_yocto();
+        self.internal_update_withdraw_timelock(&token_id, |timelocks, index| {
+            timelocks[index]
+                .announced_withdraw
+                .take()
+                .expect("No announced withdrawal");
+        });
+    }
+
+    /// Returns the withdraw timelocks of the given account.
+    pub fn get_withdraw_timelocks(&self, account_id: AccountId) -> Vec<WithdrawTimelock> {
+        self.internal_get_withdraw_timelocks(&account_id)
+    }
+}
@@ -1,18 +1,122 @@
 use common::*;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::{assert_one_yocto, env, ext_contract, near_bindgen, AccountId, Gas, Promise};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{
+    assert_one_yocto, env, ext_contract, near_bindgen, AccountId, Gas, Promise, Timestamp,
+};
+use std::collections::HashMap;
 
 const GAS_FOR_PROMISE: Gas = Gas(Gas::ONE_TERA.0 * 10);
+/// The gas of the starved callbacks, which is not enough to execute any actions.
+const GAS_FOR_STARVED_CALLBACK: Gas = Gas(Gas::ONE_TERA.0 * 2);
 
 #[ext_contract(ext_price_receiver)]
 pub trait ExtPriceReceiver {
     fn oracle_on_call(&mut self, sender_id: AccountId, data: PriceData, msg: String);
 }
 
+#[ext_contract(ext_self)]
+pub trait ExtSelf {
+    fn on_delay(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        price_data: PriceData,
+        msg: String,
+        blocks: u32,
+    ) -> Promise;
+}
+
+/// The failure injected into the oracle calls until it's cleared.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Copy)]
+#[serde(crate = "near_sdk::serde")]
+pub enum OracleFailure {
+    /// The oracle call panics before calling the receiver.
+    Panic,
+    /// The callback of the receiver is delayed by the number of blocks, by passing the call
+    /// through a chain of self-calls.
+    Delay { blocks: u32 },
+    /// The callback of the receiver is made with too little gas to be executed.
+    StarveCallback,
+}
+
+/// The price of an asset to record, which is `age_sec` seconds older than the current block.
+/// The recorded price is removed if `price` is `None`.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RecordedPrice {
+    pub asset_id: AssetId,
+    pub price: Option<Price>,
+    pub age_sec: Option<DurationSec>,
+}
+
+/// A single call of the batch.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OracleCall {
+    pub receiver_id: AccountId,
+    pub price_data: PriceData,
+    pub msg: String,
+}
+
 #[near_bindgen]
 #[derive(BorshDeserialize, BorshSerialize, Default)]
-pub struct Contract {}
+pub struct Contract {
+    /// The recorded prices with their timestamps by asset ID.
+    prices: HashMap<AssetId, (Price, Timestamp)>,
+    failure: Option<OracleFailure>,
+}
+
+impl Contract {
+    /// Calls the receiver, unless the injected failure makes the call panic, delays or starves
+    /// the callback.
+    fn internal_call(
+        &self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        price_data: PriceData,
+        msg: String,
+        gas: Gas,
+    ) -> Promise {
+        match self.failure {
+            Some(OracleFailure::Panic) => env::panic_str("The oracle call failed"),
+            Some(OracleFailure::Delay { blocks }) if blocks > 0 => ext_self::on_delay(
+                sender_id,
+                receiver_id,
+                price_data,
+                msg,
+                blocks - 1,
+                env::current_account_id(),
+                NO_DEPOSIT,
+                gas,
+            ),
+            Some(OracleFailure::StarveCallback) => ext_price_receiver::oracle_on_call(
+                sender_id,
+                price_data,
+                msg,
+                receiver_id,
+                NO_DEPOSIT,
+                GAS_FOR_STARVED_CALLBACK,
+            ),
+            _ => ext_price_receiver::oracle_on_call(
+                sender_id,
+                price_data,
+                msg,
+                receiver_id,
+                NO_DEPOSIT,
+                gas,
+            ),
+        }
+    }
+
+    /// Returns the remaining gas for the promises of the call.
+    fn remaining_gas() -> Gas {
+        let remaining_gas = env::prepaid_gas() - env::used_gas();
+        assert!(remaining_gas >= GAS_FOR_PROMISE);
+        remaining_gas - GAS_FOR_PROMISE
+    }
+}
 
 #[near_bindgen]
 impl Contract {
@@ -25,16 +129,140 @@ impl Contract {
     ) -> Promise {
         assert_one_yocto();
         let sender_id = env::predecessor_account_id();
-        let remaining_gas = env::prepaid_gas() - env::used_gas();
-        assert!(remaining_gas >= GAS_FOR_PROMISE);
-
-        ext_price_receiver::oracle_on_call(
+        self.internal_call(
             sender_id,
+            receiver_id,
             price_data,
             msg,
+            Self::remaining_gas(),
+        )
+    }
+
+    /// Calls the receiver with the recorded prices of the assets, like the price oracle.
+    #[payable]
+    pub fn oracle_call_recorded(
+        &mut self,
+        receiver_id: AccountId,
+        asset_ids: Option<Vec<AssetId>>,
+        msg: String,
+    ) -> Promise {
+        assert_one_yocto();
+        let sender_id = env::predecessor_account_id();
+        let price_data = self.get_price_data(asset_ids);
+        self.internal_call(
+            sender_id,
             receiver_id,
-            NO_DEPOSIT,
-            remaining_gas - GAS_FOR_PROMISE,
+            price_data,
+            msg,
+            Self::remaining_gas(),
         )
     }
+
+    /// Makes multiple calls in the same transaction, splitting the remaining gas between them.
+    #[payable]
+    pub fn oracle_batch_call(&mut self, calls: Vec<OracleCall>) -> Promise {
+        assert_one_yocto();
+        assert!(!calls.is_empty(), "The batch is empty");
+        let sender_id = env::predecessor_account_id();
+        let gas = Gas(Self::remaining_gas().0 / calls.len() as u64);
+        calls
+            .into_iter()
+            .map(|call| {
+                self.internal_call(
+                    sender_id.clone(),
+                    call.receiver_id,
+                    call.price_data,
+                    call.msg,
+                    gas,
+                )
+            })
+            .reduce(|batch, promise| batch.and(promise))
+            .unwrap()
+    }
+
+    #[private]
+    pub fn on_delay(
+        &mut self,
+        sender_id: AccountId,
+        receiver_id: AccountId,
+        price_data: PriceData,
+        msg: String,
+        blocks: u32,
+    ) -> Promise {
+        let gas = Self::remaining_gas();
+        if blocks > 0 {
+            ext_self::on_delay(
+                sender_id,
+                receiver_id,
+                price_data,
+                msg,
+                blocks - 1,
+                env::current_account_id(),
+                NO_DEPOSIT,
+                gas,
+            )
+        } else {
+            ext_price_receiver::oracle_on_call(
+                sender_id,
+                price_data,
+                msg,
+                receiver_id,
+                NO_DEPOSIT,
+                gas,
+            )
+        }
+    }
+
+    /// Records the prices of the assets with their own timestamps, or removes them.
+    pub fn set_prices(&mut self, prices: Vec<RecordedPrice>) {
+        let timestamp = env::block_timestamp();
+        for RecordedPrice {
+            asset_id,
+            price,
+            age_sec,
+        } in prices
+        {
+            if let Some(price) = price {
+                let age = to_nano(age_sec.unwrap_or(0));
+                self.prices.insert(asset_id, (price, timestamp - age));
+            } else {
+                self.prices.remove(&asset_id);
+            }
+        }
+    }
+
+    /// Injects the failure into the following oracle calls, or clears it if `None`.
+    pub fn set_failure(&mut self, failure: Option<OracleFailure>) {
+        self.failure = failure;
+    }
+
+    /// Returns the recorded prices of the assets, or of all recorded assets if `None`. The assets
+    /// without the recorded price have no price. The timestamp of the data is the one of the
+    /// oldest price, and the recency duration is the time between the oldest and the newest
+    /// prices.
+    pub fn get_price_data(&self, asset_ids: Option<Vec<AssetId>>) -> PriceData {
+        let asset_ids = asset_ids.unwrap_or_else(|| self.prices.keys().cloned().collect());
+        let prices: Vec<_> = asset_ids
+            .into_iter()
+            .map(|asset_id| AssetOptionalPrice {
+                price: self.prices.get(&asset_id).map(|(price, _)| *price),
+                asset_id,
+            })
+            .collect();
+        let timestamps: Vec<Timestamp> = prices
+            .iter()
+            .filter_map(|price| self.prices.get(&price.asset_id).map(|(_, ts)| *ts))
+            .collect();
+        let oldest = timestamps
+            .iter()
+            .copied()
+            .min()
+            .unwrap_or_else(env::block_timestamp);
+        let newest = timestamps.iter().copied().max().unwrap_or(oldest);
+        PriceData {
+            timestamp: oldest,
+            recency_duration_sec: ((newest - oldest) / 10u64.pow(9)) as DurationSec,
+            prices,
+        }
+    }
 }
@@ -0,0 +1,76 @@
+mod setup;
+
+use crate::setup::*;
+use contract::AccountActivityView;
+use near_sdk_sim::UserAccount;
+
+fn get_account_activity(e: &Env, user: &UserAccount) -> Option<AccountActivityView> {
+    e.near
+        .view_method_call(e.contract.contract.get_account_activity(user.account_id()))
+        .unwrap_json()
+}
+
+#[test]
+fn test_account_activity() {
+    let (e, tokens, users) = basic_setup();
+
+    // The activity is not recorded until the tracking is enabled.
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(100, 18), "")
+        .assert_success();
+    assert!(get_account_activity(&e, &users.alice).is_none());
+
+    let mut config = e.get_config();
+    config.activity_tracking_enabled = true;
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(50, 18), "")
+        .assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    let activity = get_account_activity(&e, &users.alice).unwrap();
+    assert_eq!(
+        activity.supplied_volume[&tokens.ndai.account_id()].0,
+        d(50, 18)
+    );
+    assert_eq!(
+        activity.supplied_volume[&tokens.wnear.account_id()].0,
+        d(100, 24)
+    );
+    assert_eq!(activity.borrowed_duration_sec, 0);
+
+    e.borrow(
+        &users.alice,
+        &tokens.nusdc,
+        price_data(&tokens, Some(100000), None),
+        d(10, 18),
+    )
+    .assert_success();
+    e.skip_time(ONE_DAY_SEC * 2);
+
+    // The current borrowing is counted.
+    let activity = get_account_activity(&e, &users.alice).unwrap();
+    assert_eq!(activity.borrowed_days, 2);
+
+    // The borrowing is recorded on the interaction with the account.
+    e.deposit_with_actions(
+        &users.alice,
+        &tokens.nusdc,
+        d(20, 6),
+        vec![Action::Repay(AssetAmount {
+            token_id: tokens.nusdc.account_id(),
+            amount: None,
+            max_amount: None,
+        })],
+        None,
+    )
+    .assert_success();
+    e.skip_time(ONE_DAY_SEC);
+    let activity = get_account_activity(&e, &users.alice).unwrap();
+    assert_eq!(activity.borrowed_days, 2);
+    assert_eq!(
+        activity.supplied_volume[&tokens.nusdc.account_id()].0,
+        d(20, 18)
+    );
+}
@@ -0,0 +1,131 @@
+mod setup;
+
+use crate::setup::*;
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
+use near_sdk_sim::transaction::ExecutionStatus;
+use near_sdk_sim::ExecutionResult;
+
+fn get_account_lock(e: &Env, user: &UserAccount) -> u32 {
+    e.near
+        .view_method_call(e.contract.contract.get_account_lock(user.account_id()))
+        .unwrap_json()
+}
+
+#[test]
+fn test_account_unlocked_after_withdraw() {
+    let (e, tokens, users) = basic_setup();
+
+    let amount = d(100, 18);
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, amount, "")
+        .assert_success();
+    assert_eq!(get_account_lock(&e, &users.alice), 0);
+
+    e.withdraw(&users.alice, &tokens.ndai, d(40, 18))
+        .assert_success();
+    // The lock is released by the transfer callback.
+    assert_eq!(get_account_lock(&e, &users.alice), 0);
+    assert_eq!(e.ft_balance_of(&users.alice, &tokens.ndai), d(40, 18));
+
+    // The failed transfer releases the lock as well.
+    users
+        .alice
+        .call(
+            tokens.ndai.account_id(),
+            "storage_unregister",
+            &json!({ "force": true }).to_string().into_bytes(),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+    e.withdraw(&users.alice, &tokens.ndai, d(30, 18))
+        .assert_success();
+    assert_eq!(get_account_lock(&e, &users.alice), 0);
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.withdraw_claims[0].amount, d(30, 18));
+}
+
+#[test]
+fn test_account_unlocked_after_flash_loan() {
+    let (e, tokens, users) = basic_setup();
+
+    e.contract_ft_transfer_call(&tokens.nusdc, &users.alice, d(1000, 6), "")
+        .assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+
+    // Bob doesn't have a contract, so the loan is repaid from the escrowed supplied balance and
+    // the lock is released by the callback.
+    e.flash_loan(&users.alice, &tokens.nusdc, d(300, 18), &users.bob, "")
+        .assert_success();
+    assert_eq!(get_account_lock(&e, &users.alice), 0);
+    e.withdraw(&users.alice, &tokens.ndai, d(100, 18))
+        .assert_success();
+}
+
+#[test]
+fn test_contract_cant_call_own_entry_points() {
+    let (e, tokens, users) = basic_setup();
+
+    let assert_self_call_failure = |res: ExecutionResult| match res.status() {
+        ExecutionStatus::Failure(err) => assert!(err
+            .to_string()
+            .contains("The contract can't call its own entry points")),
+        _ => panic!("The self call should fail"),
+    };
+    let contract = &e.contract.user_account;
+    assert_self_call_failure(
+        contract.call(
+            contract.account_id(),
+            "ft_on_transfer",
+            &json!({
+                "sender_id": users.alice.account_id(),
+                "amount": U128(d(100, 18)),
+                "msg": "",
+            })
+            .to_string()
+            .into_bytes(),
+            DEFAULT_GAS.0,
+            0,
+        ),
+    );
+    assert_self_call_failure(
+        contract.call(
+            contract.account_id(),
+            "oracle_on_call",
+            &json!({
+                "sender_id": users.alice.account_id(),
+                "data": price_data(&tokens, None, None),
+                "msg": "",
+            })
+            .to_string()
+            .into_bytes(),
+            DEFAULT_GAS.0,
+            0,
+        ),
+    );
+    assert_self_call_failure(contract.function_call(
+        e.contract.contract.execute(vec![], None),
+        DEFAULT_GAS.0,
+        1,
+    ));
+    assert_self_call_failure(contract.function_call(
+        e.contract.contract.router_execute(vec![]),
+        DEFAULT_GAS.0,
+        1,
+    ));
+    assert_self_call_failure(contract.function_call(
+        e.contract
+            .contract
+            .execute_for(users.alice.account_id(), vec![], None),
+        DEFAULT_GAS.0,
+        1,
+    ));
+}
@@ -0,0 +1,137 @@
+mod setup;
+
+use crate::setup::*;
+use contract::FarmId;
+
+/// Supplies all the assets and borrows 4 of them with all the farms, so the account has 14
+/// positions and 11 farms and gets sharded.
+fn sharded_setup() -> (Env, Tokens, Users) {
+    let (e, tokens, users) = basic_setup();
+
+    let collateral_tokens = [&tokens.ndai, &tokens.nusdc, &tokens.nusdt, &tokens.wnear];
+    let mut farm_ids = vec![FarmId::NetTvl];
+    for token in [
+        &tokens.ndai,
+        &tokens.nusdc,
+        &tokens.nusdt,
+        &tokens.wnear,
+        &tokens.neth,
+        &e.booster_token,
+    ] {
+        farm_ids.push(FarmId::Supplied(token.account_id()));
+    }
+    for token in collateral_tokens {
+        farm_ids.push(FarmId::Borrowed(token.account_id()));
+    }
+    for farm_id in farm_ids {
+        e.add_farm(farm_id, &e.booster_token, d(10, 18), d(100, 18), d(100, 18));
+    }
+
+    storage_deposit(
+        &users.alice,
+        &e.contract.account_id(),
+        &users.alice.account_id(),
+        d(1, 24),
+    );
+    e.supply_to_collateral(&users.alice, &tokens.ndai, d(10000, 18))
+        .assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.nusdc, d(10000, 6))
+        .assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.nusdt, d(10000, 6))
+        .assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(1000, 24))
+        .assert_success();
+    e.contract_ft_transfer_call(&tokens.neth, &users.alice, d(100, 18), "")
+        .assert_success();
+    e.contract_ft_transfer_call(&e.booster_token, &users.alice, d(100, 18), "")
+        .assert_success();
+
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(100, 18),
+    )
+    .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.nusdc,
+        price_data(&tokens, Some(100000), None),
+        d(100, 6),
+    )
+    .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.nusdt,
+        price_data(&tokens, Some(100000), None),
+        d(100, 6),
+    )
+    .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.wnear,
+        price_data(&tokens, Some(100000), None),
+        d(10, 24),
+    )
+    .assert_success();
+
+    (e, tokens, users)
+}
+
+#[test]
+fn test_sharded_account_view() {
+    let (e, tokens, users) = sharded_setup();
+
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.supplied.len(), 6);
+    assert_eq!(account.collateral.len(), 4);
+    assert_eq!(account.borrowed.len(), 4);
+    assert_eq!(account.farms.len(), 11);
+    assert_eq!(
+        find_asset(&account.supplied, &tokens.neth.account_id()).balance,
+        d(100, 18)
+    );
+}
+
+#[test]
+fn test_sharded_account_leave_farm() {
+    let (e, tokens, users) = sharded_setup();
+
+    e.withdraw(&users.alice, &tokens.neth, d(40, 18))
+        .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(
+        find_asset(&account.supplied, &tokens.neth.account_id()).balance,
+        d(60, 18)
+    );
+    assert_eq!(account.farms.len(), 11);
+
+    // Withdrawing the whole supplied balance leaves the farm of the asset.
+    e.withdraw(&users.alice, &tokens.neth, d(60, 18))
+        .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.supplied.len(), 5);
+    assert_eq!(account.farms.len(), 10);
+    assert!(account
+        .farms
+        .iter()
+        .all(|farm| farm.farm_id != FarmId::Supplied(tokens.neth.account_id())));
+    assert_eq!(e.ft_balance_of(&users.alice, &tokens.neth), d(1000000, 18));
+}
+
+#[test]
+fn test_sharded_account_claim_all() {
+    let (e, _tokens, users) = sharded_setup();
+
+    e.skip_time(ONE_DAY_SEC);
+    e.account_farm_claim_all(&users.alice).assert_success();
+
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.farms.len(), 11);
+    assert!(account
+        .farms
+        .iter()
+        .flat_map(|farm| farm.rewards.iter())
+        .all(|reward| reward.unclaimed_amount == 0));
+    assert!(find_asset(&account.supplied, &e.booster_token.account_id()).balance > d(100, 18));
+}
@@ -0,0 +1,159 @@
+mod setup;
+
+use crate::setup::*;
+use contract::FarmId;
+use near_sdk_sim::ExecutionResult;
+
+/// Alice has 100 wNEAR of collateral, which is 600$ adjusted at 10$. Bob supplies nDAI.
+fn ordering_setup() -> (Env, Tokens, Users) {
+    let (e, tokens, users) = basic_setup();
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(2000, 18), "")
+        .assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+
+    (e, tokens, users)
+}
+
+fn execute_with_prices(
+    e: &Env,
+    tokens: &Tokens,
+    user: &UserAccount,
+    actions: Vec<Action>,
+) -> ExecutionResult {
+    e.oracle_call(
+        user,
+        price_data(tokens, Some(100000), None),
+        PriceReceiverMsg::Execute {
+            actions,
+            constraints: None,
+        },
+    )
+}
+
+#[test]
+fn test_borrow_then_repay_is_checked_at_the_end() {
+    let (e, tokens, users) = ordering_setup();
+
+    // The final borrowed 700 nDAI is 736.8$ adjusted.
+    let res = execute_with_prices(
+        &e,
+        &tokens,
+        &users.alice,
+        vec![
+            Action::Borrow(asset_amount(&tokens.ndai, d(1000, 18))),
+            Action::Repay(asset_amount(&tokens.ndai, d(300, 18))),
+        ],
+    );
+    assert!(!res.is_ok());
+    assert!(e.get_account(&users.alice).borrowed.is_empty());
+
+    // The final borrowed 500 nDAI is 526.3$ adjusted.
+    execute_with_prices(
+        &e,
+        &tokens,
+        &users.alice,
+        vec![
+            Action::Borrow(asset_amount(&tokens.ndai, d(1000, 18))),
+            Action::Repay(asset_amount(&tokens.ndai, d(500, 18))),
+        ],
+    )
+    .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.borrowed[0].balance, d(500, 18));
+    e.assert_invariants(&tokens);
+}
+
+#[test]
+fn test_decrease_then_increase_collateral_is_checked_at_the_end() {
+    let (e, tokens, users) = ordering_setup();
+
+    // The final collateral of 70 wNEAR is 420$ adjusted, and the borrowed 400 nDAI is 421$.
+    let res = execute_with_prices(
+        &e,
+        &tokens,
+        &users.alice,
+        vec![
+            Action::DecreaseCollateral(asset_amount(&tokens.wnear, d(50, 24))),
+            Action::Borrow(asset_amount(&tokens.ndai, d(400, 18))),
+            Action::IncreaseCollateral(asset_amount(&tokens.wnear, d(20, 24))),
+        ],
+    );
+    assert!(!res.is_ok());
+
+    // The intermediate state is unhealthy, but the final one is not.
+    execute_with_prices(
+        &e,
+        &tokens,
+        &users.alice,
+        vec![
+            Action::DecreaseCollateral(asset_amount(&tokens.wnear, d(50, 24))),
+            Action::Borrow(asset_amount(&tokens.ndai, d(400, 18))),
+            Action::IncreaseCollateral(asset_amount(&tokens.wnear, d(50, 24))),
+        ],
+    )
+    .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.collateral[0].balance, d(100, 24));
+    assert_eq!(account.borrowed[0].balance, d(400, 18));
+}
+
+#[test]
+fn test_withdraw_of_borrowed_is_checked_at_the_end() {
+    let (e, tokens, users) = ordering_setup();
+
+    let balance = e.ft_balance_of(&users.alice, &tokens.ndai);
+    let res = execute_with_prices(
+        &e,
+        &tokens,
+        &users.alice,
+        vec![
+            Action::Borrow(asset_amount(&tokens.ndai, d(600, 18))),
+            Action::Withdraw(asset_amount(&tokens.ndai, d(600, 18))),
+        ],
+    );
+    assert!(!res.is_ok());
+    assert_eq!(e.ft_balance_of(&users.alice, &tokens.ndai), balance);
+}
+
+#[test]
+fn test_claimed_rewards_are_covered_by_the_check() {
+    let (e, tokens, users) = ordering_setup();
+
+    e.add_farm(
+        FarmId::Supplied(tokens.wnear.account_id()),
+        &e.booster_token,
+        d(100, 18),
+        d(100, 18),
+        d(1000, 18),
+    );
+    // Joins the farm.
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(1, 24))
+        .assert_success();
+    e.skip_time(ONE_DAY_SEC);
+
+    // The rewards claimed by the bundle don't make the account healthy.
+    let res = execute_with_prices(
+        &e,
+        &tokens,
+        &users.alice,
+        vec![Action::Borrow(asset_amount(&tokens.ndai, d(600, 18)))],
+    );
+    assert!(!res.is_ok());
+    let account = e.get_account(&users.alice);
+    assert!(account.borrowed.is_empty());
+    assert!(account.farms[0].rewards[0].unclaimed_amount > 0);
+
+    execute_with_prices(
+        &e,
+        &tokens,
+        &users.alice,
+        vec![Action::Borrow(asset_amount(&tokens.ndai, d(500, 18)))],
+    )
+    .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.borrowed[0].balance, d(500, 18));
+    assert!(find_asset(&account.supplied, &e.booster_token.account_id()).balance > 0);
+    e.assert_invariants(&tokens);
+}
@@ -0,0 +1,196 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::ActionsValidationView;
+use near_sdk::serde_json::json;
+use near_sdk::{AccountId, Gas};
+use near_sdk_sim::ExecutionResult;
+
+fn execute(e: &Env, user: &UserAccount, actions: Vec<Action>) -> ExecutionResult {
+    user.function_call(
+        e.contract.contract.execute(actions, None),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+fn validate_actions(
+    e: &Env,
+    actions_json: String,
+    account_id: Option<AccountId>,
+) -> Result<ActionsValidationView, String> {
+    let res = e.near.view_method_call(
+        e.contract
+            .contract
+            .validate_actions(actions_json, account_id),
+    );
+    if res.is_ok() {
+        Ok(res.unwrap_json())
+    } else {
+        Err(res.unwrap_err().to_string())
+    }
+}
+
+#[test]
+fn test_validate_actions() {
+    let (e, tokens, users) = basic_setup();
+
+    let actions_json = json!([
+        {"IncreaseCollateral": {"token_id": tokens.wnear.account_id()}},
+        {"Borrow": {"token_id": tokens.ndai.account_id(), "amount": "1000"}},
+        {"Withdraw": {"token_id": tokens.ndai.account_id()}},
+    ])
+    .to_string();
+    let view = validate_actions(&e, actions_json, Some(users.alice.account_id())).unwrap();
+    assert_eq!(view.actions.len(), 3);
+    assert!(view.requires_prices);
+    match &view.actions[1] {
+        Action::Borrow(asset_amount) => {
+            assert_eq!(asset_amount.token_id, tokens.ndai.account_id());
+            assert_eq!(asset_amount.amount.unwrap().0, 1000);
+            assert!(asset_amount.max_amount.is_none());
+        }
+        _ => panic!("Unexpected action"),
+    }
+    // The execution, the oracle call, two actions and one transfer.
+    assert_eq!(view.gas.0, Gas::ONE_TERA.0 * (20 + 30 + 10 + 10 + 50));
+    // The collateral of wNEAR, and the supplied and borrowed nDAI.
+    assert_eq!(view.storage.unwrap().num_new_positions, 3);
+
+    let view = validate_actions(
+        &e,
+        json!([{"Repay": {"token_id": tokens.ndai.account_id()}}]).to_string(),
+        None,
+    )
+    .unwrap();
+    assert!(!view.requires_prices);
+    assert!(view.storage.is_none());
+}
+
+#[test]
+fn test_validate_invalid_actions() {
+    let (e, tokens, _users) = basic_setup();
+
+    let err = validate_actions(&e, "[{\"Lend\": {}}]".to_string(), None).unwrap_err();
+    assert!(err.contains("Can't parse the actions"), "{}", err);
+
+    let err = validate_actions(&e, "[]".to_string(), None).unwrap_err();
+    assert!(err.contains("The actions can't be empty"), "{}", err);
+
+    let err = validate_actions(
+        &e,
+        json!([{"Withdraw": {"token_id": "unknown.near"}}]).to_string(),
+        None,
+    )
+    .unwrap_err();
+    assert!(err.contains("Asset not found"), "{}", err);
+
+    assert!(validate_actions(
+        &e,
+        json!([{"Withdraw": {"token_id": tokens.nusdc.account_id()}}]).to_string(),
+        None,
+    )
+    .is_ok());
+}
+
+#[test]
+fn test_execute_prevalidation() {
+    let (e, tokens, users) = basic_setup();
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(100, 18), "")
+        .assert_success();
+
+    assert_failure(
+        execute(&e, &users.alice, vec![]),
+        "The actions can't be empty",
+    );
+    assert_failure(
+        execute(
+            &e,
+            &users.alice,
+            vec![Action::Withdraw(asset_amount(&tokens.ndai, d(200, 18)))],
+        ),
+        "Not enough supplied balance",
+    );
+    assert_failure(
+        execute(
+            &e,
+            &users.alice,
+            vec![Action::DecreaseCollateral(asset_amount(
+                &tokens.ndai,
+                d(10, 18),
+            ))],
+        ),
+        "Not enough collateral balance",
+    );
+    assert_failure(
+        execute(
+            &e,
+            &users.alice,
+            vec![Action::Repay(asset_amount(&tokens.ndai, d(10, 18)))],
+        ),
+        "Borrowed asset not found",
+    );
+
+    // The balances of the assets touched by the earlier actions are checked on the execution.
+    execute(
+        &e,
+        &users.alice,
+        vec![
+            Action::IncreaseCollateral(asset_amount(&tokens.ndai, d(50, 18))),
+            Action::DecreaseCollateral(asset_amount(&tokens.ndai, d(50, 18))),
+        ],
+    )
+    .assert_success();
+    let account = e.get_account(&users.alice);
+    assert!(account.collateral.is_empty());
+    assert_balances(
+        &account.supplied,
+        &[av(tokens.ndai.account_id(), d(100, 18))],
+    );
+}
+
+#[test]
+fn test_max_num_actions() {
+    let (e, tokens, users) = basic_setup();
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(100, 18), "")
+        .assert_success();
+
+    let mut config = e.get_config();
+    config.max_num_actions = Some(2);
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+
+    let withdraw = || Action::Withdraw(asset_amount(&tokens.ndai, d(10, 18)));
+    assert_failure(
+        execute(&e, &users.alice, vec![withdraw(), withdraw(), withdraw()]),
+        "The number of actions 3 exceeds the maximum of 2 per call",
+    );
+    // The bundle is rejected before any action is executed.
+    assert_balances(
+        &e.get_account(&users.alice).supplied,
+        &[av(tokens.ndai.account_id(), d(100, 18))],
+    );
+
+    let err = validate_actions(
+        &e,
+        json!([
+            {"Withdraw": {"token_id": tokens.ndai.account_id()}},
+            {"Withdraw": {"token_id": tokens.ndai.account_id()}},
+            {"Withdraw": {"token_id": tokens.ndai.account_id()}},
+        ])
+        .to_string(),
+        None,
+    )
+    .unwrap_err();
+    assert!(err.contains("exceeds the maximum"), "{}", err);
+
+    execute(&e, &users.alice, vec![withdraw(), withdraw()]).assert_success();
+    assert_balances(
+        &e.get_account(&users.alice).supplied,
+        &[av(tokens.ndai.account_id(), d(80, 18))],
+    );
+}
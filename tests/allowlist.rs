@@ -0,0 +1,119 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::Role;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+fn add_to_asset_allowlist(
+    e: &Env,
+    caller: &UserAccount,
+    token: &UserAccount,
+    user: &UserAccount,
+) -> ExecutionResult {
+    caller.function_call(
+        e.contract
+            .contract
+            .add_to_asset_allowlist(token.account_id(), vec![user.account_id()]),
+        DEFAULT_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+fn is_allowlisted(e: &Env, token: &UserAccount, user: &UserAccount) -> bool {
+    e.near
+        .view_method_call(
+            e.contract
+                .contract
+                .is_allowlisted(token.account_id(), user.account_id()),
+        )
+        .unwrap_json()
+}
+
+#[test]
+fn test_asset_allowlist() {
+    let (e, tokens, users) = basic_setup();
+
+    let mut asset_config = e.get_asset(&tokens.ndai).config;
+    asset_config.permissioned = true;
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .update_asset(tokens.ndai.account_id(), asset_config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .grant_role(Role::ComplianceManager, users.charlie.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    // The deposit of a non-allowlisted account is refunded.
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "");
+    assert_eq!(e.get_asset(&tokens.ndai).supplied.balance, 0);
+
+    assert_failure(
+        add_to_asset_allowlist(&e, &users.bob, &tokens.ndai, &users.bob),
+        "Not an owner or an account with the required role",
+    );
+    add_to_asset_allowlist(&e, &users.charlie, &tokens.ndai, &users.bob).assert_success();
+    assert!(is_allowlisted(&e, &tokens.ndai, &users.bob));
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "")
+        .assert_success();
+    assert_eq!(e.get_asset(&tokens.ndai).supplied.balance, d(100, 18));
+
+    // Other assets stay permissionless.
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    assert_failure(
+        e.borrow(
+            &users.alice,
+            &tokens.ndai,
+            price_data(&tokens, Some(100000), None),
+            d(50, 18),
+        ),
+        "The account is not in the allowlist of the asset",
+    );
+    add_to_asset_allowlist(&e, &users.charlie, &tokens.ndai, &users.alice).assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(50, 18),
+    )
+    .assert_success();
+
+    // The removed accounts can still repay.
+    users
+        .charlie
+        .function_call(
+            e.contract.contract.remove_from_asset_allowlist(
+                tokens.ndai.account_id(),
+                vec![users.alice.account_id()],
+            ),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    assert!(!is_allowlisted(&e, &tokens.ndai, &users.alice));
+    e.deposit_with_actions(
+        &users.alice,
+        &tokens.ndai,
+        d(60, 18),
+        vec![Action::Repay(AssetAmount {
+            token_id: tokens.ndai.account_id(),
+            amount: None,
+            max_amount: None,
+        })],
+        None,
+    )
+    .assert_success();
+    assert!(e.get_account(&users.alice).borrowed.is_empty());
+}
@@ -0,0 +1,141 @@
+mod setup;
+
+use crate::setup::*;
+
+use contract::LiquidationConfig;
+use near_sdk_sim::transaction::ExecutionStatus;
+
+#[test]
+fn test_update_asset_granular() {
+    let (e, tokens, _users) = basic_setup();
+
+    let old_config = e.get_asset(&tokens.ndai).config;
+    e.owner
+        .function_call(
+            e.contract.contract.update_asset_rates(
+                tokens.ndai.account_id(),
+                Some(2000),
+                None,
+                None,
+                None,
+                None,
+            ),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+    e.owner
+        .function_call(
+            e.contract.contract.update_asset_limits(
+                tokens.ndai.account_id(),
+                Some(9000),
+                None,
+                None,
+                None,
+                None,
+            ),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .update_asset_flags(tokens.ndai.account_id(), false),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+
+    let config = e.get_asset(&tokens.ndai).config;
+    assert_eq!(config.reserve_ratio, 2000);
+    assert_eq!(config.volatility_ratio, 9000);
+    assert!(!config.can_use_as_collateral);
+    assert_eq!(config.target_utilization, old_config.target_utilization);
+    assert_eq!(
+        config.max_utilization_rate.0,
+        old_config.max_utilization_rate.0
+    );
+    assert_eq!(config.net_tvl_multiplier, old_config.net_tvl_multiplier);
+
+    assert!(!e
+        .owner
+        .function_call(
+            e.contract.contract.update_asset_limits(
+                tokens.ndai.account_id(),
+                Some(10000),
+                None,
+                None,
+                None,
+                None,
+            ),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .is_ok());
+    assert_eq!(e.get_asset(&tokens.ndai).config.volatility_ratio, 9000);
+}
+
+#[test]
+fn test_liquidation_config() {
+    let (e, tokens, _users) = basic_setup();
+
+    let config: LiquidationConfig = e
+        .near
+        .view_method_call(e.contract.contract.get_liquidation_config())
+        .unwrap_json();
+    assert!(config.force_closing_enabled);
+    assert_eq!(config.assets.len(), 6);
+    let wnear = config
+        .assets
+        .iter()
+        .find(|asset| asset.token_id == tokens.wnear.account_id())
+        .unwrap();
+    assert_eq!(
+        wnear.volatility_ratio,
+        e.get_asset(&tokens.wnear).config.volatility_ratio
+    );
+    assert!(wnear.can_use_as_collateral);
+    assert!(!wnear.liquidation_paused);
+}
+
+#[test]
+fn test_volatility_ratio_change_limit() {
+    let (e, tokens, _users) = basic_setup();
+
+    let mut config = e.get_config();
+    config.max_volatility_ratio_change_per_day = Some(1000);
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+
+    let update_volatility_ratio = |volatility_ratio: u32| {
+        e.owner.function_call(
+            e.contract.contract.update_asset_limits(
+                tokens.wnear.account_id(),
+                Some(volatility_ratio),
+                None,
+                None,
+                None,
+                None,
+            ),
+            DEFAULT_GAS.0,
+            1,
+        )
+    };
+
+    // The volatility ratio of wNEAR is 60%.
+    update_volatility_ratio(5500).assert_success();
+    let res = update_volatility_ratio(4900);
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with the volatility ratio change limit error"),
+    };
+    assert!(err.contains("The volatility ratio can change by at most 1000 within 24 hours"));
+    update_volatility_ratio(5000).assert_success();
+
+    e.skip_time(ONE_DAY_SEC);
+    update_volatility_ratio(4000).assert_success();
+    assert_eq!(e.get_asset(&tokens.wnear).config.volatility_ratio, 4000);
+}
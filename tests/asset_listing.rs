@@ -0,0 +1,285 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::TokenMetadata;
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::{self, json};
+use near_sdk::AccountId;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+use std::collections::HashMap;
+
+fn apply_asset_listing(
+    e: &Env,
+    caller: &UserAccount,
+    token_id: AccountId,
+    asset_config: AssetConfig,
+) -> ExecutionResult {
+    caller.function_call(
+        e.contract
+            .contract
+            .apply_asset_listing(token_id, asset_config),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_apply_asset_listing() {
+    let (e, tokens, users) = basic_setup();
+    let token_id = init_token(&e, &a("new_token.near"), 6).account_id();
+
+    let mut asset_config = e.get_asset(&tokens.nusdc).config;
+    assert_failure(
+        apply_asset_listing(&e, &users.alice, token_id.clone(), asset_config.clone()),
+        "Not an owner",
+    );
+
+    asset_config.volatility_ratio = 9900;
+    assert_failure(
+        apply_asset_listing(&e, &e.owner, token_id.clone(), asset_config.clone()),
+        "The volatility ratio is out of bounds",
+    );
+    asset_config.volatility_ratio = 9000;
+    asset_config.target_utilization = 9900;
+    assert_failure(
+        apply_asset_listing(&e, &e.owner, token_id.clone(), asset_config.clone()),
+        "The target utilization is out of bounds",
+    );
+    asset_config.target_utilization = 8000;
+    // The rate for ~3000% APR.
+    asset_config.max_utilization_rate = 1000000000108888423124061250u128.into();
+    assert_failure(
+        apply_asset_listing(&e, &e.owner, token_id.clone(), asset_config.clone()),
+        "The max utilization rate is out of bounds",
+    );
+    asset_config.max_utilization_rate = 1000000000039724853136740579u128.into();
+    asset_config.extra_decimals = 0;
+    assert_failure(
+        apply_asset_listing(&e, &e.owner, token_id.clone(), asset_config.clone()),
+        "The extra decimals don't match the token decimals of 6",
+    );
+    asset_config.extra_decimals = 12;
+
+    apply_asset_listing(&e, &e.owner, token_id.clone(), asset_config.clone()).assert_success();
+    let asset: AssetDetailedView = e
+        .near
+        .view_method_call(e.contract.contract.get_asset_detailed(token_id.clone()))
+        .unwrap_json();
+    assert_eq!(asset.config.volatility_ratio, 9000);
+
+    // The existing asset is updated.
+    asset_config.reserve_ratio = 3000;
+    apply_asset_listing(&e, &e.owner, token_id.clone(), asset_config).assert_success();
+    let asset: AssetDetailedView = e
+        .near
+        .view_method_call(e.contract.contract.get_asset_detailed(token_id))
+        .unwrap_json();
+    assert_eq!(asset.config.reserve_ratio, 3000);
+}
+
+#[test]
+fn test_add_asset_validation() {
+    let (e, tokens, _users) = basic_setup();
+    let token_id = init_token(&e, &a("new_token.near"), 6).account_id();
+
+    let mut asset_config = e.get_asset(&tokens.nusdc).config;
+    asset_config.extra_decimals = 0;
+    assert_failure(
+        e.owner.function_call(
+            e.contract
+                .contract
+                .add_asset(token_id.clone(), asset_config.clone()),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The extra decimals don't match the token decimals of 6",
+    );
+    asset_config.extra_decimals = 12;
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .add_asset(token_id.clone(), asset_config.clone()),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    asset_config.max_utilization_rate = asset_config.target_utilization_rate;
+    asset_config.target_utilization_rate = 1000000000039724853136740579u128.into();
+    assert_failure(
+        e.owner.function_call(
+            e.contract.contract.update_asset(token_id, asset_config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The max utilization rate should be no less than the target utilization rate",
+    );
+}
+
+#[test]
+fn test_token_metadata() {
+    let (e, tokens, users) = basic_setup();
+    let token_id = init_token(&e, &a("new_token.near"), 6).account_id();
+
+    let get_token_metadata = |token_id: AccountId| -> Option<TokenMetadata> {
+        e.near
+            .view_method_call(e.contract.contract.get_token_metadata(token_id))
+            .unwrap_json()
+    };
+    let metadata = get_token_metadata(tokens.nusdc.account_id()).unwrap();
+    assert_eq!(metadata.decimals, 6);
+
+    let mut asset_config = e.get_asset(&tokens.nusdc).config;
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .add_asset(token_id.clone(), asset_config.clone()),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    let metadata = get_token_metadata(token_id.clone()).unwrap();
+    assert_eq!(metadata.symbol, "new_token.near");
+    assert_eq!(metadata.decimals, 6);
+
+    // The extra decimals can be changed without balances, but have to match the token decimals.
+    asset_config.extra_decimals = 0;
+    assert_failure(
+        e.owner.function_call(
+            e.contract
+                .contract
+                .update_asset(token_id.clone(), asset_config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The extra decimals don't match the token decimals of 6",
+    );
+
+    assert_failure(
+        users.alice.function_call(
+            e.contract.contract.refresh_token_metadata(token_id.clone()),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+    e.owner
+        .function_call(
+            e.contract.contract.refresh_token_metadata(token_id),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+}
+
+#[test]
+fn test_oracle_price_decimals() {
+    let (e, tokens, users) = basic_setup();
+
+    let update_asset = |asset_config: AssetConfig| {
+        e.owner.function_call(
+            e.contract
+                .contract
+                .update_asset(tokens.ndai.account_id(), asset_config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+    };
+    let get_max_borrow = || -> u128 {
+        let max_borrow: U128 = e
+            .near
+            .view_method_call(e.contract.contract.get_max_borrow(
+                users.alice.account_id(),
+                tokens.ndai.account_id(),
+                Some(price_data(&tokens, Some(100000), None)),
+            ))
+            .unwrap_json();
+        max_borrow.0
+    };
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    let max_borrow = get_max_borrow();
+    assert!(max_borrow > 0);
+
+    // The oracle price decimals of DAI have to include its 18 decimals.
+    let mut asset_config = e.get_asset(&tokens.ndai).config;
+    asset_config.oracle_price_decimals = Some(17);
+    assert_failure(
+        update_asset(asset_config.clone()),
+        "The oracle price decimals don't match the token decimals of 18",
+    );
+    asset_config.oracle_price_decimals = Some(37);
+    assert_failure(
+        update_asset(asset_config.clone()),
+        "The oracle price decimals don't match the token decimals of 18",
+    );
+
+    // The oracle gives the price of DAI with 22 decimals, so the override makes DAI 10 times
+    // cheaper.
+    asset_config.oracle_price_decimals = Some(23);
+    update_asset(asset_config).assert_success();
+    almost_eq(get_max_borrow(), max_borrow * 10, 15);
+}
+
+#[test]
+fn test_add_asset_from_template() {
+    let (e, _tokens, users) = basic_setup();
+    let token = init_token(&e, &a("new_token.near"), 6);
+
+    let add_asset_from_template =
+        |caller: &UserAccount, template_name: &str, overrides: serde_json::Value| {
+            caller.function_call(
+                e.contract.contract.add_asset_from_template(
+                    token.account_id(),
+                    template_name.to_string(),
+                    Some(serde_json::from_value(overrides).unwrap()),
+                ),
+                MAX_GAS.0,
+                ONE_YOCTO,
+            )
+        };
+
+    assert_failure(
+        add_asset_from_template(&users.alice, "stablecoin", json!({})),
+        "Not an owner",
+    );
+    assert_failure(
+        add_asset_from_template(&e.owner, "meme", json!({})),
+        "Unknown asset template",
+    );
+    assert_failure(
+        add_asset_from_template(&e.owner, "stablecoin", json!({"decimals": 6})),
+        "Unknown asset config parameter decimals",
+    );
+    assert_failure(
+        add_asset_from_template(&e.owner, "stablecoin", json!({"volatility_ratio": 9900})),
+        "The volatility ratio is out of bounds",
+    );
+    assert_failure(
+        add_asset_from_template(&e.owner, "stablecoin", json!({})),
+        "The extra decimals don't match the token decimals of 6",
+    );
+
+    add_asset_from_template(
+        &e.owner,
+        "stablecoin",
+        json!({"extra_decimals": 12, "supply_cap": d(1000000, 18).to_string()}),
+    )
+    .assert_success();
+
+    let templates: HashMap<String, AssetConfig> = e
+        .near
+        .view_method_call(e.contract.contract.get_asset_templates())
+        .unwrap_json();
+    let template = &templates["stablecoin"];
+    let config = e.get_asset(&token).config;
+    assert_eq!(config.extra_decimals, 12);
+    assert_eq!(config.supply_cap.unwrap().0, d(1000000, 18));
+    assert_eq!(config.volatility_ratio, template.volatility_ratio);
+    assert_eq!(config.reserve_ratio, template.reserve_ratio);
+    assert_eq!(config.risk_tier, template.risk_tier);
+}
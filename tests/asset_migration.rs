@@ -0,0 +1,185 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use near_sdk::AccountId;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+use std::collections::HashMap;
+
+fn deprecate_asset(e: &Env, token: &UserAccount) {
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .deprecate_asset(token.account_id(), false),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+}
+
+fn set_asset_migration(e: &Env, token: &UserAccount, new_token: &UserAccount) -> ExecutionResult {
+    e.owner.function_call(
+        e.contract
+            .contract
+            .set_asset_migration(token.account_id(), new_token.account_id()),
+        DEFAULT_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+fn migrate_asset(e: &Env, user: &UserAccount, token: &UserAccount) -> ExecutionResult {
+    user.function_call(
+        e.contract.contract.execute(
+            vec![Action::MigrateAsset {
+                token_id: token.account_id(),
+            }],
+            None,
+        ),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_set_asset_migration() {
+    let (e, tokens, users) = basic_setup();
+
+    assert_failure(
+        set_asset_migration(&e, &tokens.nusdt, &tokens.nusdc),
+        "The asset has to be deprecated",
+    );
+    deprecate_asset(&e, &tokens.nusdt);
+    assert_failure(
+        set_asset_migration(&e, &tokens.nusdt, &tokens.nusdt),
+        "The assets have to be different",
+    );
+    assert_failure(
+        users.alice.function_call(
+            e.contract
+                .contract
+                .set_asset_migration(tokens.nusdt.account_id(), tokens.nusdc.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+    set_asset_migration(&e, &tokens.nusdt, &tokens.nusdc).assert_success();
+    let migrations: HashMap<AccountId, AccountId> = e
+        .near
+        .view_method_call(e.contract.contract.get_asset_migrations())
+        .unwrap_json();
+    assert_eq!(
+        migrations.get(&tokens.nusdt.account_id()),
+        Some(&tokens.nusdc.account_id())
+    );
+
+    // The migration is removed with the deprecation.
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .undeprecate_asset(tokens.nusdt.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    let migrations: HashMap<AccountId, AccountId> = e
+        .near
+        .view_method_call(e.contract.contract.get_asset_migrations())
+        .unwrap_json();
+    assert!(migrations.is_empty());
+}
+
+#[test]
+fn test_migrate_supplied_and_collateral() {
+    let (e, tokens, users) = basic_setup();
+
+    let amount = d(100, 18);
+    e.contract_ft_transfer_call(&tokens.nusdt, &users.alice, d(100, 6), "")
+        .assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.nusdt, d(100, 6))
+        .assert_success();
+
+    deprecate_asset(&e, &tokens.nusdt);
+    assert_failure(
+        migrate_asset(&e, &users.alice, &tokens.nusdt),
+        "The asset migration is not set",
+    );
+    set_asset_migration(&e, &tokens.nusdt, &tokens.nusdc).assert_success();
+    assert_failure(
+        migrate_asset(&e, &users.bob, &tokens.nusdt),
+        "The account has no balance of the asset",
+    );
+
+    let nusdt_reserved = e.get_asset(&tokens.nusdt).reserved;
+    let nusdc_reserved = e.get_asset(&tokens.nusdc).reserved;
+    migrate_asset(&e, &users.alice, &tokens.nusdt).assert_success();
+
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.supplied.len(), 1);
+    assert_eq!(account.supplied[0].token_id, tokens.nusdc.account_id());
+    assert_eq!(account.supplied[0].balance, amount);
+    assert_eq!(account.collateral.len(), 1);
+    assert_eq!(account.collateral[0].token_id, tokens.nusdc.account_id());
+    assert_eq!(account.collateral[0].balance, amount);
+
+    // The token balances don't change, so the reserves take the other side of the conversion.
+    assert_eq!(
+        e.get_asset(&tokens.nusdt).reserved,
+        nusdt_reserved + 2 * amount
+    );
+    assert_eq!(
+        e.get_asset(&tokens.nusdc).reserved,
+        nusdc_reserved - 2 * amount
+    );
+}
+
+#[test]
+fn test_migrate_borrowed() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.nusdt,
+        price_data(&tokens, Some(100000), None),
+        d(50, 18),
+    )
+    .assert_success();
+
+    deprecate_asset(&e, &tokens.nusdt);
+    set_asset_migration(&e, &tokens.nusdt, &tokens.nusdc).assert_success();
+
+    // The account has borrowed assets, so the risk check requires prices.
+    assert!(!migrate_asset(&e, &users.alice, &tokens.nusdt).is_ok());
+
+    let nusdc_reserved = e.get_asset(&tokens.nusdc).reserved;
+    e.oracle_call(
+        &users.alice,
+        price_data(&tokens, Some(100000), None),
+        PriceReceiverMsg::Execute {
+            actions: vec![Action::MigrateAsset {
+                token_id: tokens.nusdt.account_id(),
+            }],
+            constraints: None,
+        },
+    )
+    .assert_success();
+
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.borrowed.len(), 1);
+    assert_eq!(account.borrowed[0].token_id, tokens.nusdc.account_id());
+    let borrowed = account.borrowed[0].balance;
+    assert!(borrowed >= d(50, 18) && borrowed < d(501, 17));
+    // The borrowed nUSDT stays supplied, only the debt is converted.
+    assert_eq!(account.supplied.len(), 1);
+    assert_eq!(account.supplied[0].token_id, tokens.nusdt.account_id());
+    assert_eq!(account.collateral[0].token_id, tokens.wnear.account_id());
+
+    assert_eq!(e.get_asset(&tokens.nusdt).borrowed.balance, 0);
+    // The reserve of nUSDC lends the converted debt.
+    let reserved = e.get_asset(&tokens.nusdc).reserved;
+    assert!(reserved >= nusdc_reserved + borrowed && reserved < nusdc_reserved + d(501, 17));
+}
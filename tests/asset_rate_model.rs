@@ -0,0 +1,90 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::AssetRateModel;
+use near_sdk::json_types::U128;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+fn set_asset_rate_model(
+    e: &Env,
+    user: &UserAccount,
+    token: &UserAccount,
+    rate_model: AssetRateModel,
+) -> ExecutionResult {
+    user.function_call(
+        e.contract
+            .contract
+            .set_asset_rate_model(token.account_id(), rate_model),
+        DEFAULT_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_set_asset_rate_model() {
+    let (e, tokens, users) = basic_setup();
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "")
+        .assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(50, 18),
+    )
+    .assert_success();
+
+    let old_config = e.get_asset(&tokens.ndai).config;
+    let rate_model = AssetRateModel {
+        target_utilization: 5000,
+        target_utilization_rate: old_config.max_utilization_rate,
+        max_utilization_rate: old_config.max_utilization_rate,
+    };
+
+    assert_failure(
+        set_asset_rate_model(&e, &users.alice, &tokens.ndai, rate_model.clone()),
+        "Not an owner",
+    );
+    assert_failure(
+        set_asset_rate_model(
+            &e,
+            &e.owner,
+            &tokens.ndai,
+            AssetRateModel {
+                max_utilization_rate: U128(old_config.target_utilization_rate.0 - 1),
+                ..rate_model.clone()
+            },
+        ),
+        "The max utilization rate should be no less than the target utilization rate",
+    );
+
+    e.skip_time(30 * 24 * 60 * 60);
+    // The interest up to the switch is compounded with the old rate model.
+    let asset = e.get_asset(&tokens.ndai);
+    set_asset_rate_model(&e, &e.owner, &tokens.ndai, rate_model.clone()).assert_success();
+    let new_asset = e.get_asset(&tokens.ndai);
+    almost_eq(new_asset.borrowed.balance, asset.borrowed.balance, 9);
+    assert!(new_asset.borrow_apr > asset.borrow_apr);
+
+    let config = new_asset.config;
+    assert_eq!(config.target_utilization, 5000);
+    assert_eq!(
+        config.target_utilization_rate.0,
+        old_config.max_utilization_rate.0
+    );
+    assert_eq!(config.reserve_ratio, old_config.reserve_ratio);
+    assert_eq!(config.volatility_ratio, old_config.volatility_ratio);
+
+    let view: AssetRateModel = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_asset_rate_model(tokens.ndai.account_id()),
+        )
+        .unwrap_json();
+    assert_eq!(view.target_utilization, 5000);
+}
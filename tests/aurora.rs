@@ -0,0 +1,96 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use near_sdk::serde_json::json;
+use near_sdk::Balance;
+use near_sdk_sim::{to_yocto, ExecutionResult, UserAccount};
+
+const RECIPIENT: &str = "0x0123456789ABCDEF0123456789abcdef01234567";
+
+fn aurora_deposit_msg(user: &UserAccount) -> String {
+    json!({
+        "AuroraDeposit": {
+            "account_id": user.account_id(),
+        }
+    })
+    .to_string()
+}
+
+fn withdraw_to_aurora(
+    e: &Env,
+    user: &UserAccount,
+    token: &UserAccount,
+    amount: Balance,
+    recipient: &str,
+) -> ExecutionResult {
+    user.function_call(
+        e.contract.contract.execute(
+            vec![Action::WithdrawToAurora {
+                asset_amount: asset_amount(token, amount),
+                recipient: recipient.to_string(),
+            }],
+            None,
+        ),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_aurora_deposit() {
+    let (e, tokens, users) = basic_setup();
+
+    let aurora = e.near.create_user(a(AURORA_ENGINE_ID), to_yocto("100"));
+    e.mint_tokens(&tokens, &aurora);
+
+    let amount = d(100, 18);
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &aurora,
+        amount,
+        &aurora_deposit_msg(&users.alice),
+    )
+    .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.supplied[0].token_id, tokens.ndai.account_id());
+    assert_eq!(account.supplied[0].balance, amount);
+}
+
+#[test]
+fn test_aurora_deposit_requires_engine() {
+    let (e, tokens, users) = basic_setup();
+
+    let balance = e.ft_balance_of(&users.alice, &tokens.ndai);
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &users.alice,
+        d(100, 18),
+        &aurora_deposit_msg(&users.bob),
+    )
+    .assert_success();
+    // The deposit is rejected and refunded.
+    assert_eq!(e.ft_balance_of(&users.alice, &tokens.ndai), balance);
+    assert!(e.get_account(&users.bob).supplied.is_empty());
+}
+
+#[test]
+fn test_withdraw_to_aurora_refunded() {
+    let (e, tokens, users) = basic_setup();
+
+    let amount = d(100, 18);
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, amount, "")
+        .assert_success();
+
+    assert_failure(
+        withdraw_to_aurora(&e, &users.alice, &tokens.ndai, amount, "0x1234"),
+        "Invalid Aurora address",
+    );
+
+    // The Aurora engine is not registered with the token, so the transfer is refunded.
+    withdraw_to_aurora(&e, &users.alice, &tokens.ndai, amount, RECIPIENT).assert_success();
+    let account = e.get_account(&users.alice);
+    assert!(account.supplied.is_empty());
+    assert_eq!(account.withdraw_claims.len(), 1);
+    assert_eq!(account.withdraw_claims[0].amount, amount);
+}
@@ -0,0 +1,98 @@
+mod setup;
+
+use crate::setup::*;
+use near_sdk_sim::transaction::ExecutionStatus;
+
+/// Alice puts 100 NEAR as collateral and borrows 200 DAI, which stays in her supplied balance.
+/// The adjusted collateral is 600$ and the adjusted borrowed is ~210.5$, so the health is ~2.85.
+fn auto_repay_setup() -> (Env, Tokens, Users) {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+
+    (e, tokens, users)
+}
+
+#[test]
+fn test_auto_repay_order() {
+    let (e, tokens, users) = auto_repay_setup();
+
+    e.add_auto_repay_order(&users.alice, &tokens.ndai, 20000, d(100, 18), d(1, 18))
+        .assert_success();
+    e.add_auto_repay_order(&users.alice, &tokens.ndai, 30000, d(100, 18), d(1, 18))
+        .assert_success();
+
+    let orders = e.get_auto_repay_orders(&users.alice);
+    assert_eq!(orders.len(), 2);
+    assert_eq!(orders[0].order_id, 0);
+    assert_eq!(orders[1].order_id, 1);
+
+    let res = e.execute_auto_repay_order(
+        &users.bob,
+        &users.alice,
+        price_data(&tokens, Some(100000), None),
+        0,
+    );
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with the health factor above the trigger"),
+    };
+    assert!(err.contains("The health factor is not below the trigger of the order"));
+
+    e.execute_auto_repay_order(
+        &users.bob,
+        &users.alice,
+        price_data(&tokens, Some(100000), None),
+        1,
+    )
+    .assert_success();
+
+    let orders = e.get_auto_repay_orders(&users.alice);
+    assert_eq!(orders.len(), 1);
+    assert_eq!(orders[0].order_id, 0);
+
+    let account = e.get_account(&users.alice);
+    almost_eq(account.supplied[0].balance, d(99, 18), 18);
+    almost_eq(account.borrowed[0].balance, d(100, 18), 18);
+
+    let account = e.get_account(&users.bob);
+    almost_eq(account.supplied[0].balance, d(1, 18), 18);
+    assert_eq!(account.supplied[0].token_id, tokens.ndai.account_id());
+}
+
+#[test]
+fn test_remove_auto_repay_order() {
+    let (e, tokens, users) = auto_repay_setup();
+
+    e.add_auto_repay_order(&users.alice, &tokens.ndai, 30000, d(100, 18), 0)
+        .assert_success();
+    users
+        .alice
+        .function_call(
+            e.contract.contract.remove_auto_repay_order(0),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+    assert!(e.get_auto_repay_orders(&users.alice).is_empty());
+
+    let res = e.execute_auto_repay_order(
+        &users.bob,
+        &users.alice,
+        price_data(&tokens, Some(100000), None),
+        0,
+    );
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with missing order"),
+    };
+    assert!(err.contains("Auto-repay order not found"));
+}
@@ -2,7 +2,9 @@ mod setup;
 
 use crate::setup::*;
 
-use contract::{BigDecimal, MS_PER_YEAR};
+use contract::{AssetDetailedView, BigDecimal, MS_PER_YEAR};
+use near_sdk::json_types::{U128, U64};
+use near_sdk_sim::transaction::ExecutionStatus;
 
 const SEC_PER_YEAR: u32 = (MS_PER_YEAR / 1000) as u32;
 
@@ -100,6 +102,68 @@ fn test_borrow() {
     assert!(account.borrowed[0].apr > BigDecimal::zero());
 }
 
+#[test]
+fn test_borrow_with_constraints() {
+    let (e, tokens, users) = basic_setup();
+
+    let supply_amount = d(100, 24);
+    e.supply_to_collateral(&users.alice, &tokens.wnear, supply_amount)
+        .assert_success();
+
+    // 100 NEAR at 10$ with 60% volatility ratio -> 600$ adjusted collateral.
+    // 200 DAI with 95% volatility ratio -> ~210.5$ adjusted borrowed. Health factor is ~2.85.
+    let borrow_amount = d(200, 18);
+    let res = e.borrow_with_constraints(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        borrow_amount,
+        ExecuteConstraints {
+            min_health_after: Some(30000),
+            deadline_ts: None,
+        },
+    );
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with health factor error"),
+    };
+    assert!(err.contains("The health factor is below the minimum"));
+
+    let res = e.borrow_with_constraints(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        borrow_amount,
+        ExecuteConstraints {
+            min_health_after: None,
+            deadline_ts: Some(U64(1)),
+        },
+    );
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with deadline error"),
+    };
+    assert!(err.contains("The deadline for the actions has passed"));
+
+    let account = e.get_account(&users.alice);
+    assert!(account.borrowed.is_empty());
+
+    e.borrow_with_constraints(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        borrow_amount,
+        ExecuteConstraints {
+            min_health_after: Some(25000),
+            deadline_ts: Some(U64(u64::MAX)),
+        },
+    )
+    .assert_success();
+
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.borrowed[0].balance, borrow_amount);
+}
+
 #[test]
 fn test_borrow_and_withdraw() {
     let (e, tokens, users) = basic_setup();
@@ -122,12 +186,136 @@ fn test_borrow_and_withdraw() {
     assert!(asset.borrow_apr > BigDecimal::zero());
     assert_eq!(asset.supplied.balance, 0);
     assert_eq!(asset.supply_apr, BigDecimal::zero());
+    assert_eq!(asset.available_amount, asset.reserved - borrow_amount);
+    assert_eq!(
+        asset.utilization,
+        BigDecimal::from(borrow_amount).div_u128(asset.reserved)
+    );
+
+    let asset_detailed: AssetDetailedView = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_asset_detailed(tokens.ndai.account_id()),
+        )
+        .unwrap_json();
+    assert_eq!(asset_detailed.available_amount, asset.available_amount);
 
     let account = e.get_account(&users.alice);
     assert!(account.supplied.is_empty());
     assert_eq!(account.borrowed[0].balance, borrow_amount);
     assert_eq!(account.borrowed[0].token_id, tokens.ndai.account_id());
     assert!(account.borrowed[0].apr > BigDecimal::zero());
+    e.assert_invariants(&tokens);
+}
+
+#[test]
+fn test_borrow_to_wallet() {
+    let (e, tokens, users) = basic_setup();
+
+    let supply_amount = d(100, 24);
+    e.supply_to_collateral(&users.alice, &tokens.wnear, supply_amount)
+        .assert_success();
+
+    let wallet_balance = e.ft_balance_of(&users.alice, &tokens.ndai);
+
+    let borrow_amount = d(200, 18);
+    e.borrow_to_wallet(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        borrow_amount,
+    )
+    .assert_success();
+
+    assert_eq!(
+        e.ft_balance_of(&users.alice, &tokens.ndai),
+        wallet_balance + borrow_amount
+    );
+
+    let asset = e.get_asset(&tokens.ndai);
+    assert_eq!(asset.borrowed.balance, borrow_amount);
+    assert_eq!(asset.supplied.balance, 0);
+
+    let account = e.get_account(&users.alice);
+    assert!(account.supplied.is_empty());
+    assert_eq!(account.borrowed[0].balance, borrow_amount);
+    assert_eq!(account.borrowed[0].token_id, tokens.ndai.account_id());
+}
+
+#[test]
+fn test_borrow_to_wallet_rounds_down_extra_decimals() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+
+    let wallet_balance = e.ft_balance_of(&users.alice, &tokens.nusdc);
+
+    // USDC has 12 extra decimals, so the dust below 1e-6 USDC can't be transferred and is not
+    // borrowed.
+    e.borrow_to_wallet(
+        &users.alice,
+        &tokens.nusdc,
+        price_data(&tokens, Some(100000), None),
+        d(100, 18) + 123456,
+    )
+    .assert_success();
+    assert_eq!(
+        e.ft_balance_of(&users.alice, &tokens.nusdc),
+        wallet_balance + d(100, 6)
+    );
+
+    let account = e.get_account(&users.alice);
+    assert!(account.supplied.is_empty());
+    assert_eq!(account.borrowed[0].balance, d(100, 18));
+    assert_eq!(e.get_asset(&tokens.nusdc).borrowed.balance, d(100, 18));
+}
+
+#[test]
+fn test_execute_without_prices() {
+    let (e, tokens, users) = basic_setup();
+
+    let supply_amount = d(100, 24);
+    e.supply_to_collateral(&users.alice, &tokens.wnear, supply_amount)
+        .assert_success();
+
+    let res = users.alice.function_call(
+        e.contract.contract.execute(
+            vec![Action::Borrow(asset_amount(&tokens.ndai, d(200, 18)))],
+            None,
+        ),
+        MAX_GAS.0,
+        1,
+    );
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with prices required"),
+    };
+    assert!(
+        err.contains("The actions require prices and have to be executed through the oracle call")
+    );
+
+    // Without borrowed assets, the collateral can be decreased without prices.
+    users
+        .alice
+        .function_call(
+            e.contract.contract.execute(
+                vec![Action::DecreaseCollateral(asset_amount(
+                    &tokens.wnear,
+                    supply_amount,
+                ))],
+                None,
+            ),
+            MAX_GAS.0,
+            1,
+        )
+        .assert_success();
+
+    let account = e.get_account(&users.alice);
+    assert!(account.collateral.is_empty());
+    assert_eq!(account.supplied[0].balance, supply_amount);
 }
 
 #[test]
@@ -165,3 +353,78 @@ fn test_interest() {
     );
     assert_eq!(account.borrowed[0].token_id, tokens.ndai.account_id());
 }
+
+#[test]
+fn test_interest_long_dormancy() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(10000, 24))
+        .assert_success();
+    let borrow_amount = d(8000, 18);
+    e.borrow_and_withdraw(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        borrow_amount,
+    )
+    .assert_success();
+    let asset = e.get_asset(&tokens.ndai);
+    let total_supplied = asset.supplied.balance + asset.reserved;
+
+    // The asset is not touched for 10 years, so the gap is compounded in chunks with the same
+    // rate.
+    e.skip_time(10 * SEC_PER_YEAR);
+
+    let asset = e.get_asset(&tokens.ndai);
+    let expected_borrow_amount = borrow_amount as f64 * 1.08f64.powi(10);
+    assert_relative_eq!(
+        asset.borrowed.balance as f64,
+        expected_borrow_amount,
+        max_relative = 1e-9
+    );
+    assert_eq!(
+        asset.supplied.balance + asset.reserved - total_supplied,
+        asset.borrowed.balance - borrow_amount
+    );
+    e.assert_invariants(&tokens);
+}
+
+#[test]
+fn test_accrued_interest() {
+    let (e, tokens, users) = basic_setup();
+
+    let supply_amount = d(10000, 24);
+    e.supply_to_collateral(&users.alice, &tokens.wnear, supply_amount)
+        .assert_success();
+
+    let borrow_amount = d(8000, 18);
+    e.borrow_and_withdraw(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        borrow_amount,
+    )
+    .assert_success();
+
+    let account = e.get_account(&users.alice);
+    assert!(account.last_action_timestamp.is_some());
+    assert_eq!(account.borrowed[0].accrued_interest, Some(U128(0)));
+    assert_eq!(account.collateral[0].accrued_interest, Some(U128(0)));
+
+    e.skip_time(SEC_PER_YEAR);
+
+    let account = e.get_account(&users.alice);
+    let accrued_interest = account.borrowed[0].accrued_interest.unwrap().0;
+    assert_eq!(
+        accrued_interest,
+        account.borrowed[0].balance - borrow_amount
+    );
+    assert_relative_eq!(accrued_interest as f64, (borrow_amount * 8 / 100) as f64);
+
+    // Only the changed positions record their principals again.
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(1, 18), "")
+        .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.supplied[0].accrued_interest, Some(U128(0)));
+    assert!(account.borrowed[0].accrued_interest.unwrap().0 >= accrued_interest);
+}
@@ -0,0 +1,338 @@
+mod setup;
+
+use crate::setup::*;
+
+use near_sdk::json_types::U128;
+use near_sdk_sim::transaction::ExecutionStatus;
+
+#[test]
+fn test_max_borrow() {
+    let (e, tokens, users) = basic_setup();
+
+    let supply_amount = d(100, 24);
+    e.supply_to_collateral(&users.alice, &tokens.wnear, supply_amount)
+        .assert_success();
+
+    let get_max_borrow = || {
+        let max_borrow: U128 = e
+            .near
+            .view_method_call(e.contract.contract.get_max_borrow(
+                users.alice.account_id(),
+                tokens.ndai.account_id(),
+                Some(price_data(&tokens, Some(100000), None)),
+            ))
+            .unwrap_json();
+        max_borrow.0
+    };
+
+    // 100 NEAR at 10$ with 60% volatility ratio, borrowing DAI at 1$ with 95% volatility ratio.
+    let max_borrow = get_max_borrow();
+    assert_eq!(max_borrow, d(570, 18));
+
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        max_borrow,
+    )
+    .assert_success();
+    assert_eq!(get_max_borrow(), 0);
+}
+
+#[test]
+fn test_min_borrow_health() {
+    let (e, tokens, users) = basic_setup();
+
+    let mut config = e.get_config();
+    config.min_borrow_health_bps = Some(10500);
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+
+    // 600$ of adjusted collateral covers 570 DAI at 100%, but only ~542.86 DAI at 105%.
+    let max_borrow: U128 = e
+        .near
+        .view_method_call(e.contract.contract.get_max_borrow(
+            users.alice.account_id(),
+            tokens.ndai.account_id(),
+            Some(price_data(&tokens, Some(100000), None)),
+        ))
+        .unwrap_json();
+    assert!(max_borrow.0 > d(542, 18) && max_borrow.0 < d(543, 18));
+
+    let res = e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(560, 18),
+    );
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with the minimum borrow health error"),
+    };
+    assert!(err.contains("The health factor is below the minimum for the borrows"));
+
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(540, 18),
+    )
+    .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.borrowed[0].balance, d(540, 18));
+}
+
+#[test]
+fn test_max_decrease_collateral() {
+    let (e, tokens, users) = basic_setup();
+
+    let supply_amount = d(100, 24);
+    e.supply_to_collateral(&users.alice, &tokens.wnear, supply_amount)
+        .assert_success();
+
+    let get_max_decrease_collateral = || {
+        let max_decrease: U128 = e
+            .near
+            .view_method_call(e.contract.contract.get_max_decrease_collateral(
+                users.alice.account_id(),
+                tokens.wnear.account_id(),
+                Some(price_data(&tokens, Some(100000), None)),
+            ))
+            .unwrap_json();
+        max_decrease.0
+    };
+
+    // Without borrowed assets, the whole collateral can be removed.
+    assert_eq!(get_max_decrease_collateral(), supply_amount);
+
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(285, 18),
+    )
+    .assert_success();
+
+    // 300$ of the adjusted borrowed value requires 50 NEAR at 10$ with 60% volatility ratio.
+    let max_decrease = get_max_decrease_collateral();
+    assert_eq!(max_decrease, d(50, 24));
+
+    e.oracle_call(
+        &users.alice,
+        price_data(&tokens, Some(100000), None),
+        PriceReceiverMsg::Execute {
+            actions: vec![Action::DecreaseCollateral(asset_amount(
+                &tokens.wnear,
+                max_decrease,
+            ))],
+            constraints: None,
+        },
+    )
+    .assert_success();
+    assert_eq!(get_max_decrease_collateral(), 0);
+}
+
+#[test]
+fn test_supply_and_borrow_caps() {
+    let (e, tokens, users) = basic_setup();
+
+    e.owner
+        .function_call(
+            e.contract.contract.update_asset_limits(
+                tokens.ndai.account_id(),
+                None,
+                None,
+                Some(U128(d(150, 18))),
+                Some(U128(d(100, 18))),
+                None,
+            ),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "")
+        .assert_success();
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "");
+    let asset = e.get_asset(&tokens.ndai);
+    assert_eq!(asset.supplied.balance, d(100, 18));
+    assert_eq!(asset.remaining_supply_capacity, Some(U128(d(50, 18))));
+    assert_eq!(asset.remaining_borrow_capacity, Some(U128(d(100, 18))));
+    assert_eq!(asset.remaining_collateral_capacity, None);
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(150, 18),
+    );
+    assert_eq!(e.get_asset(&tokens.ndai).borrowed.balance, 0);
+
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(50, 18),
+    )
+    .assert_success();
+    let asset = e.get_asset(&tokens.ndai);
+    assert_eq!(asset.borrowed.balance, d(50, 18));
+    assert_eq!(asset.remaining_borrow_capacity, Some(U128(d(50, 18))));
+}
+
+#[test]
+fn test_min_borrow_amount() {
+    let (e, tokens, users) = basic_setup();
+
+    let mut asset_config = e.get_asset(&tokens.ndai).config;
+    asset_config.min_borrow_amount = Some(U128(d(10, 18)));
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .update_asset(tokens.ndai.account_id(), asset_config),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(1, 18),
+    );
+    assert_eq!(e.get_asset(&tokens.ndai).borrowed.balance, 0);
+
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(10, 18),
+    )
+    .assert_success();
+    assert_eq!(e.get_asset(&tokens.ndai).borrowed.balance, d(10, 18));
+
+    // The minimum applies to the borrowed balance of the account, so it can be topped up.
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(1, 18),
+    )
+    .assert_success();
+    let borrowed = e.get_asset(&tokens.ndai).borrowed.balance;
+    assert!(borrowed >= d(11, 18) && borrowed < d(1101, 16));
+}
+
+#[test]
+fn test_dust_threshold() {
+    let (e, tokens, users) = basic_setup();
+
+    let mut asset_config = e.get_asset(&tokens.ndai).config;
+    asset_config.dust_threshold = Some(U128(d(1, 18)));
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .update_asset(tokens.ndai.account_id(), asset_config),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+
+    // A new position below the threshold is not written off.
+    e.contract_ft_transfer_call(&tokens.ndai, &users.charlie, d(5, 17), "")
+        .assert_success();
+    let account = e.get_account(&users.charlie);
+    assert_eq!(account.supplied[0].balance, d(5, 17));
+
+    // The residual supplied balance goes to the reserve.
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "")
+        .assert_success();
+    let reserved = e.get_asset(&tokens.ndai).reserved;
+    e.withdraw(&users.bob, &tokens.ndai, d(995, 17))
+        .assert_success();
+    let account = e.get_account(&users.bob);
+    assert!(account.supplied.is_empty());
+    assert_eq!(e.get_asset(&tokens.ndai).reserved, reserved + d(5, 17));
+    let logs = get_logs(&e.near.borrow_runtime());
+    assert!(logs
+        .iter()
+        .any(|log| log.contains("\"event\":\"dust_written_off\"")));
+
+    // The residual borrowed balance is covered by the reserve.
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(10, 18),
+    )
+    .assert_success();
+    users
+        .alice
+        .function_call(
+            e.contract.contract.execute(
+                vec![Action::Repay(asset_amount(&tokens.ndai, d(95, 17)))],
+                None,
+            ),
+            MAX_GAS.0,
+            1,
+        )
+        .assert_success();
+    let account = e.get_account(&users.alice);
+    assert!(account.borrowed.is_empty());
+    assert!(account.supplied.is_empty());
+    assert_eq!(e.get_asset(&tokens.ndai).borrowed.balance, 0);
+    e.assert_invariants(&tokens);
+}
+
+#[test]
+fn test_max_total_borrowed_value() {
+    let (e, tokens, users) = basic_setup();
+
+    let mut config = e.get_config();
+    config.max_total_borrowed_value = Some(U128(100));
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(60, 18),
+    )
+    .assert_success();
+
+    e.supply_to_collateral(&users.bob, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.bob,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(60, 18),
+    );
+    assert!(e.get_account(&users.bob).borrowed.is_empty());
+
+    e.borrow(
+        &users.bob,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(30, 18),
+    )
+    .assert_success();
+    assert!(!e.get_account(&users.bob).borrowed.is_empty());
+}
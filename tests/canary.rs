@@ -0,0 +1,108 @@
+mod setup;
+
+use crate::setup::*;
+use contract::{CanaryConfig, CanaryStage};
+use near_sdk::json_types::U128;
+use near_sdk_sim::transaction::ExecutionStatus;
+
+fn get_canary_stage(e: &Env) -> Option<CanaryStage> {
+    e.near
+        .view_method_call(e.contract.contract.get_canary_stage())
+        .unwrap_json()
+}
+
+#[test]
+fn test_canary_caps() {
+    let (e, tokens, users) = basic_setup();
+
+    let mut config = e.get_config();
+    config.canary = Some(CanaryConfig {
+        stage: 1,
+        max_total_supplied_value: U128(5000),
+        max_account_supplied_value: U128(2000),
+    });
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+
+    // 100 NEAR of collateral is 1000$ once the prices are recorded by the borrow.
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(10, 18),
+    )
+    .assert_success();
+
+    // 1000 DAI more would bring the account to 2010$.
+    let ndai_balance = e.ft_balance_of(&users.alice, &tokens.ndai);
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(1000, 18), "")
+        .assert_success();
+    assert_eq!(e.ft_balance_of(&users.alice, &tokens.ndai), ndai_balance);
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(900, 18), "")
+        .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(
+        find_asset(&account.supplied, &tokens.ndai.account_id()).balance,
+        d(910, 18)
+    );
+
+    // 4000 USDC would bring the total supplied value to 5910$.
+    let nusdc_balance = e.ft_balance_of(&users.bob, &tokens.nusdc);
+    e.contract_ft_transfer_call(&tokens.nusdc, &users.bob, d(4000, 6), "")
+        .assert_success();
+    assert_eq!(e.ft_balance_of(&users.bob, &tokens.nusdc), nusdc_balance);
+
+    let res = e.owner.function_call(
+        e.contract
+            .contract
+            .raise_canary_caps(U128(4000), U128(5000)),
+        DEFAULT_GAS.0,
+        1,
+    );
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail"),
+    };
+    assert!(err.contains("The canary caps can only be raised"));
+
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .raise_canary_caps(U128(10000), U128(5000)),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+    let stage = get_canary_stage(&e).unwrap();
+    assert_eq!(stage.stage, 2);
+    assert_eq!(stage.max_total_supplied_value.0, 10000);
+    assert_eq!(stage.max_account_supplied_value.0, 5000);
+
+    e.contract_ft_transfer_call(&tokens.nusdc, &users.bob, d(4000, 6), "")
+        .assert_success();
+    assert_eq!(
+        e.ft_balance_of(&users.bob, &tokens.nusdc),
+        nusdc_balance - d(4000, 6)
+    );
+
+    e.owner
+        .function_call(
+            e.contract.contract.end_canary_deployment(),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+    assert!(get_canary_stage(&e).is_none());
+
+    e.contract_ft_transfer_call(&tokens.nusdc, &users.bob, d(10000, 6), "")
+        .assert_success();
+    assert_eq!(
+        e.ft_balance_of(&users.bob, &tokens.nusdc),
+        nusdc_balance - d(14000, 6)
+    );
+}
@@ -0,0 +1,64 @@
+mod setup;
+
+use crate::setup::*;
+
+use near_sdk::json_types::U128;
+
+#[test]
+fn test_collateral_cap() {
+    let (e, tokens, users) = basic_setup();
+
+    e.owner
+        .function_call(
+            e.contract.contract.update_asset_limits(
+                tokens.ndai.account_id(),
+                None,
+                None,
+                None,
+                None,
+                Some(U128(d(150, 18))),
+            ),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+
+    // The asset can be supplied above the collateral cap.
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(200, 18), "")
+        .assert_success();
+    assert_eq!(e.get_asset(&tokens.ndai).supplied.balance, d(200, 18));
+
+    let execute = |action: Action| {
+        users.bob.function_call(
+            e.contract.contract.execute(vec![action], None),
+            MAX_GAS.0,
+            1,
+        )
+    };
+    execute(Action::IncreaseCollateral(asset_amount(
+        &tokens.ndai,
+        d(100, 18),
+    )))
+    .assert_success();
+    assert!(!execute(Action::IncreaseCollateral(asset_amount(
+        &tokens.ndai,
+        d(100, 18),
+    )))
+    .is_ok());
+    assert_eq!(e.get_account(&users.bob).collateral[0].balance, d(100, 18));
+
+    execute(Action::IncreaseCollateral(asset_amount(
+        &tokens.ndai,
+        d(50, 18),
+    )))
+    .assert_success();
+    execute(Action::DecreaseCollateral(asset_amount(
+        &tokens.ndai,
+        d(50, 18),
+    )))
+    .assert_success();
+
+    let asset = e.get_asset(&tokens.ndai);
+    assert_eq!(asset.collateral_shares.0, d(100, 18));
+    assert_eq!(e.get_account(&users.bob).collateral[0].balance, d(100, 18));
+}
@@ -0,0 +1,60 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::BigDecimal;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+fn update_asset(e: &Env, token: &UserAccount, asset_config: AssetConfig) -> ExecutionResult {
+    e.owner.function_call(
+        e.contract
+            .contract
+            .update_asset(token.account_id(), asset_config),
+        DEFAULT_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_collateral_only_asset() {
+    let (e, tokens, users) = basic_setup();
+
+    let mut asset_config = e.get_asset(&tokens.ndai).config;
+    asset_config.collateral_only = true;
+    asset_config.can_use_as_collateral = false;
+    assert_failure(
+        update_asset(&e, &tokens.ndai, asset_config.clone()),
+        "The collateral-only asset has to be usable as collateral",
+    );
+    asset_config.can_use_as_collateral = true;
+    update_asset(&e, &tokens.ndai, asset_config).assert_success();
+
+    e.supply_to_collateral(&users.bob, &tokens.wnear, d(100, 24))
+        .assert_success();
+    assert_failure(
+        e.borrow(
+            &users.bob,
+            &tokens.ndai,
+            price_data(&tokens, Some(100000), None),
+            d(10, 18),
+        ),
+        "The collateral-only asset can't be borrowed",
+    );
+
+    // The collateral-only asset backs the borrows of other assets.
+    e.supply_to_collateral(&users.alice, &tokens.ndai, d(100, 18))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.wnear,
+        price_data(&tokens, Some(100000), None),
+        d(1, 24),
+    )
+    .assert_success();
+    assert_eq!(e.get_asset(&tokens.wnear).borrowed.balance, d(1, 24));
+
+    let asset = e.get_asset(&tokens.ndai);
+    assert_eq!(asset.borrowed.balance, 0);
+    assert_eq!(asset.borrow_apr, BigDecimal::zero());
+    assert_eq!(asset.supply_apr, BigDecimal::zero());
+}
@@ -0,0 +1,60 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+
+#[test]
+fn test_collateral_shares_backfill() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.supply_to_collateral(&users.bob, &tokens.wnear, d(50, 24))
+        .assert_success();
+    let collateral_shares = e.get_asset(&tokens.wnear).collateral_shares;
+    assert_eq!(collateral_shares.0, d(150, 24));
+
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .start_collateral_shares_backfill(tokens.wnear.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    // The asset is paused until the backfill is completed.
+    e.supply_to_collateral(&users.charlie, &tokens.wnear, d(10, 24));
+    assert_eq!(e.get_asset(&tokens.wnear).collateral_shares, collateral_shares);
+
+    let is_completed: bool = users
+        .charlie
+        .function_call(
+            e.contract
+                .contract
+                .backfill_collateral_shares(tokens.wnear.account_id(), 1),
+            DEFAULT_GAS.0,
+            0,
+        )
+        .unwrap_json();
+    assert!(!is_completed);
+    let is_completed: bool = users
+        .charlie
+        .function_call(
+            e.contract
+                .contract
+                .backfill_collateral_shares(tokens.wnear.account_id(), 100),
+            DEFAULT_GAS.0,
+            0,
+        )
+        .unwrap_json();
+    assert!(is_completed);
+
+    let asset = e.get_asset(&tokens.wnear);
+    assert_eq!(asset.collateral_shares, collateral_shares);
+    assert_eq!(asset.config.paused_actions, 0);
+    e.supply_to_collateral(&users.charlie, &tokens.wnear, d(10, 24))
+        .assert_success();
+    assert_eq!(e.get_asset(&tokens.wnear).collateral_shares.0, d(160, 24));
+}
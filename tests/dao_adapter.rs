@@ -0,0 +1,83 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use near_sdk::json_types::U128;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+fn repay_and_withdraw(
+    e: &Env,
+    user: &UserAccount,
+    repay_token: &UserAccount,
+    withdraw_token: &UserAccount,
+    amount: Option<u128>,
+) -> ExecutionResult {
+    user.function_call(
+        e.contract.contract.repay_and_withdraw(
+            repay_token.account_id(),
+            withdraw_token.account_id(),
+            amount.map(U128),
+        ),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_deposit_to_collateral() {
+    let (e, tokens, users) = basic_setup();
+
+    let amount = d(1000, 18);
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &users.alice,
+        amount,
+        "\"DepositToCollateral\"",
+    )
+    .assert_success();
+    let account = e.get_account(&users.alice);
+    assert!(account.supplied.is_empty());
+    assert_eq!(account.collateral[0].token_id, tokens.ndai.account_id());
+    assert_eq!(account.collateral[0].balance, amount);
+}
+
+#[test]
+fn test_repay_and_withdraw() {
+    let (e, tokens, users) = basic_setup();
+
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &users.alice,
+        d(1000, 18),
+        "\"DepositToCollateral\"",
+    )
+    .assert_success();
+    assert_failure(
+        repay_and_withdraw(&e, &users.alice, &tokens.ndai, &tokens.ndai, None),
+        "The account doesn't borrow the repay asset",
+    );
+
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, None, None),
+        d(100, 18),
+    )
+    .assert_success();
+    // Covers the interest accrued on the borrowed balance.
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(10, 18), "")
+        .assert_success();
+
+    assert_failure(
+        repay_and_withdraw(&e, &users.alice, &tokens.ndai, &tokens.ndai, Some(0)),
+        "The amount can't be 0",
+    );
+
+    let balance = e.ft_balance_of(&users.alice, &tokens.ndai);
+    repay_and_withdraw(&e, &users.alice, &tokens.ndai, &tokens.ndai, None).assert_success();
+    let account = e.get_account(&users.alice);
+    assert!(account.borrowed.is_empty());
+    assert!(account.collateral.is_empty());
+    assert!(account.supplied.is_empty());
+    assert!(e.ft_balance_of(&users.alice, &tokens.ndai) > balance + d(1009, 18));
+}
@@ -0,0 +1,122 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use near_sdk::AccountId;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+fn all_of(token: &UserAccount) -> AssetAmount {
+    AssetAmount {
+        token_id: token.account_id(),
+        amount: None,
+        max_amount: None,
+    }
+}
+
+fn withdraw_all(e: &Env, user: &UserAccount, token: &UserAccount) -> ExecutionResult {
+    user.function_call(
+        e.contract
+            .contract
+            .execute(vec![Action::Withdraw(all_of(token))], None),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+fn get_delisting_assets(e: &Env) -> Vec<AccountId> {
+    e.near
+        .view_method_call(e.contract.contract.get_delisting_assets())
+        .unwrap_json()
+}
+
+#[test]
+fn test_asset_delisting() {
+    let (e, tokens, users) = basic_setup();
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "")
+        .assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(50, 18),
+    )
+    .assert_success();
+
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .start_asset_delisting(tokens.ndai.account_id(), None, None),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    assert_eq!(get_delisting_assets(&e), vec![tokens.ndai.account_id()]);
+
+    // The delisting pauses can't be resumed.
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .update_asset_paused_actions(tokens.ndai.account_id(), vec![]),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    let config = e.get_asset(&tokens.ndai).config;
+    assert_eq!(
+        config.get_paused_actions(),
+        vec![
+            PausableAction::Deposit,
+            PausableAction::IncreaseCollateral,
+            PausableAction::Borrow
+        ]
+    );
+
+    let supplied_balance = e.get_asset(&tokens.ndai).supplied.balance;
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(10, 18), "");
+    assert_eq!(e.get_asset(&tokens.ndai).supplied.balance, supplied_balance);
+
+    assert_failure(
+        e.owner.function_call(
+            e.contract.contract.remove_asset(tokens.ndai.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The asset still has suppliers or borrowers",
+    );
+
+    // Repayments are accepted while the deposits are paused.
+    e.deposit_with_actions(
+        &users.alice,
+        &tokens.ndai,
+        d(60, 18),
+        vec![Action::Repay(all_of(&tokens.ndai))],
+        None,
+    )
+    .assert_success();
+    assert!(e.get_account(&users.alice).borrowed.is_empty());
+
+    withdraw_all(&e, &users.alice, &tokens.ndai).assert_success();
+    withdraw_all(&e, &users.bob, &tokens.ndai).assert_success();
+
+    let owner_balance = e.ft_balance_of(&e.owner, &tokens.ndai);
+    e.owner
+        .function_call(
+            e.contract.contract.remove_asset(tokens.ndai.account_id()),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    let asset: Option<AssetDetailedView> = e
+        .near
+        .view_method_call(e.contract.contract.get_asset(tokens.ndai.account_id()))
+        .unwrap_json();
+    assert!(asset.is_none());
+    assert!(get_delisting_assets(&e).is_empty());
+    assert!(e.ft_balance_of(&e.owner, &tokens.ndai) >= owner_balance + d(10000, 18));
+}
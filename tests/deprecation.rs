@@ -0,0 +1,103 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use near_sdk_sim::UserAccount;
+
+fn all_of(token: &UserAccount) -> AssetAmount {
+    AssetAmount {
+        token_id: token.account_id(),
+        amount: None,
+        max_amount: None,
+    }
+}
+
+#[test]
+fn test_asset_deprecation() {
+    let (e, tokens, users) = basic_setup();
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "")
+        .assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(50, 18),
+    )
+    .assert_success();
+
+    assert_failure(
+        users.bob.function_call(
+            e.contract
+                .contract
+                .deprecate_asset(tokens.ndai.account_id(), true),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .deprecate_asset(tokens.ndai.account_id(), true),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    let asset = e.get_asset(&tokens.ndai);
+    assert!(asset.config.frozen_rate.is_some());
+    assert_eq!(
+        asset.config.get_paused_actions(),
+        vec![
+            PausableAction::Deposit,
+            PausableAction::IncreaseCollateral,
+            PausableAction::Borrow
+        ]
+    );
+
+    // The borrow rate doesn't follow the utilization.
+    let borrow_apr = asset.borrow_apr;
+    e.deposit_with_actions(
+        &users.alice,
+        &tokens.ndai,
+        d(20, 18),
+        vec![Action::Repay(all_of(&tokens.ndai))],
+        None,
+    )
+    .assert_success();
+    assert_eq!(e.get_asset(&tokens.ndai).borrow_apr, borrow_apr);
+
+    assert_failure(
+        e.borrow(
+            &users.alice,
+            &tokens.ndai,
+            price_data(&tokens, Some(100000), None),
+            d(10, 18),
+        ),
+        "can't be used borrowed",
+    );
+
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .undeprecate_asset(tokens.ndai.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    let asset = e.get_asset(&tokens.ndai);
+    assert!(asset.config.frozen_rate.is_none());
+    assert!(asset.config.get_paused_actions().is_empty());
+    assert_ne!(asset.borrow_apr, borrow_apr);
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(10, 18),
+    )
+    .assert_success();
+}
@@ -0,0 +1,84 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::{Dex, DexKind};
+use near_sdk::json_types::U128;
+
+const DEX_ID: &str = "dex.near";
+
+fn get_dexes(e: &Env) -> Vec<Dex> {
+    e.near
+        .view_method_call(e.contract.contract.get_dexes())
+        .unwrap_json()
+}
+
+#[test]
+fn test_add_and_remove_dex() {
+    let (e, _tokens, users) = basic_setup();
+
+    let dexes = get_dexes(&e);
+    assert_eq!(dexes.len(), 1);
+    assert_eq!(dexes[0].dex_id, a(REF_EXCHANGE_ID));
+
+    assert_failure(
+        users.alice.function_call(
+            e.contract.contract.add_dex(a(DEX_ID), DexKind::RefFinance),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+    e.owner
+        .function_call(
+            e.contract.contract.add_dex(a(DEX_ID), DexKind::RefFinance),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    let dexes = get_dexes(&e);
+    assert_eq!(dexes.len(), 2);
+    assert_eq!(dexes[1].dex_id, a(DEX_ID));
+    assert_eq!(dexes[1].kind, DexKind::RefFinance);
+
+    e.owner
+        .function_call(
+            e.contract.contract.remove_dex(a(DEX_ID)),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    assert_eq!(get_dexes(&e).len(), 1);
+}
+
+#[test]
+fn test_swap_requires_whitelisted_dex() {
+    let (e, tokens, users) = basic_setup();
+
+    let amount = d(100, 18);
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, amount, "")
+        .assert_success();
+
+    assert_failure(
+        users.alice.function_call(
+            e.contract.contract.execute(
+                vec![Action::WithdrawAndSwap {
+                    asset_amount: asset_amount(&tokens.ndai, amount),
+                    swap_route: vec![SwapAction {
+                        pool_id: 0,
+                        token_in: tokens.ndai.account_id(),
+                        amount_in: None,
+                        token_out: tokens.wnear.account_id(),
+                        min_amount_out: U128(d(9, 24)),
+                    }],
+                    min_received: U128(d(10, 24)),
+                    dex_id: Some(a(DEX_ID)),
+                }],
+                None,
+            ),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The exchange is not whitelisted",
+    );
+}
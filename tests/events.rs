@@ -1,7 +1,9 @@
 mod setup;
 
 use crate::setup::*;
+use contract::HeavyEventsStats;
 use near_sdk::serde_json;
+use near_sdk_sim::UserAccount;
 
 #[test]
 fn test_deposit_event() {
@@ -18,6 +20,7 @@ fn test_deposit_event() {
     let value: serde_json::Value =
         serde_json::from_str(&event[EVENT_JSON.len()..]).expect("Failed to parse the event");
     assert_eq!(value["standard"].as_str().unwrap(), "burrow");
+    assert_eq!(value["version"].as_str().unwrap(), "1.18.0");
     assert_eq!(value["event"].as_str().unwrap(), "deposit");
     assert_eq!(
         value["data"][0]["account_id"].as_str().unwrap(),
@@ -31,4 +34,293 @@ fn test_deposit_event() {
         value["data"][0]["token_id"].as_str().unwrap(),
         tokens.wnear.account_id().as_str()
     );
+    let account = e.get_account(&users.alice);
+    assert_eq!(
+        value["data"][0]["shares"].as_str().unwrap(),
+        account.supplied[0].shares.0.to_string()
+    );
+}
+
+fn find_event(e: &Env, event: &str) -> serde_json::Value {
+    get_logs(&e.near.borrow_runtime())
+        .iter()
+        .filter(|log| log.starts_with(EVENT_JSON))
+        .map(|log| {
+            serde_json::from_str::<serde_json::Value>(&log[EVENT_JSON.len()..])
+                .expect("Failed to parse the event")
+        })
+        .find(|value| value["event"].as_str().unwrap() == event)
+        .expect("Event not found")
+}
+
+#[test]
+fn test_deposit_refunded_event() {
+    let (e, tokens, users) = basic_setup();
+
+    e.owner
+        .function_call(
+            e.contract.contract.update_asset_paused_actions(
+                tokens.ndai.account_id(),
+                vec![PausableAction::Deposit],
+            ),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+
+    let balance = e.ft_balance_of(&users.bob, &tokens.ndai);
+    // The transfer doesn't fail, but the full amount is refunded.
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "")
+        .assert_success();
+    let value = find_event(&e, "deposit_refunded");
+    assert_eq!(
+        value["data"][0]["account_id"].as_str().unwrap(),
+        users.bob.account_id().as_str()
+    );
+    assert_eq!(
+        value["data"][0]["amount"].as_str().unwrap(),
+        d(100, 18).to_string()
+    );
+    assert_eq!(e.ft_balance_of(&users.bob, &tokens.ndai), balance);
+    assert_eq!(e.get_asset(&tokens.ndai).supplied.balance, 0);
+}
+
+#[test]
+fn test_admin_events() {
+    let (e, tokens, _users) = basic_setup();
+
+    let old_reserve_ratio = e.get_asset(&tokens.ndai).config.reserve_ratio;
+    e.owner
+        .function_call(
+            e.contract.contract.update_asset_rates(
+                tokens.ndai.account_id(),
+                Some(2000),
+                None,
+                None,
+                None,
+                None,
+            ),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+    let value = find_event(&e, "asset_config_updated");
+    let data = &value["data"][0];
+    assert_eq!(
+        data["token_id"].as_str().unwrap(),
+        tokens.ndai.account_id().as_str()
+    );
+    assert_eq!(
+        data["old_asset_config"]["reserve_ratio"].as_u64().unwrap(),
+        old_reserve_ratio as u64
+    );
+    assert_eq!(
+        data["new_asset_config"]["reserve_ratio"].as_u64().unwrap(),
+        2000
+    );
+
+    let mut config = e.get_config();
+    config.oracle_account_id = a("new_oracle.near");
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+    let value = find_event(&e, "config_updated");
+    let data = &value["data"][0];
+    assert_eq!(
+        data["old_config"]["oracle_account_id"].as_str().unwrap(),
+        ORACLE_ID
+    );
+    assert_eq!(
+        data["new_config"]["oracle_account_id"].as_str().unwrap(),
+        "new_oracle.near"
+    );
+}
+
+fn find_rates_event(e: &Env, token: &UserAccount) -> Option<serde_json::Value> {
+    get_logs(&e.near.borrow_runtime())
+        .iter()
+        .filter(|log| log.starts_with(EVENT_JSON))
+        .map(|log| {
+            serde_json::from_str::<serde_json::Value>(&log[EVENT_JSON.len()..])
+                .expect("Failed to parse the event")
+        })
+        .find(|value| {
+            value["event"].as_str().unwrap() == "asset_rates_updated"
+                && value["data"][0]["token_id"].as_str().unwrap() == token.account_id().as_str()
+        })
+}
+
+#[test]
+fn test_asset_rates_event() {
+    let (e, tokens, users) = basic_setup();
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "")
+        .assert_success();
+    assert!(find_rates_event(&e, &tokens.ndai).is_none());
+
+    let mut config = e.get_config();
+    config.asset_rates_event_interval_sec = Some(3600);
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "")
+        .assert_success();
+    let value = find_rates_event(&e, &tokens.ndai).expect("Event not found");
+    assert_eq!(value["data"][0]["utilization"].as_str().unwrap(), "0.0");
+
+    // The rates events are throttled.
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "")
+        .assert_success();
+    assert!(find_rates_event(&e, &tokens.ndai).is_none());
+
+    e.skip_time(3600);
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "")
+        .assert_success();
+    assert!(find_rates_event(&e, &tokens.ndai).is_some());
+}
+
+#[test]
+fn test_account_health_event() {
+    let (e, tokens, users) = basic_setup();
+
+    let supply_amount = d(100, 24);
+    e.supply_to_collateral(&users.alice, &tokens.wnear, supply_amount)
+        .assert_success();
+
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+
+    // 100 NEAR at 10$ with 60% volatility ratio -> 600$ adjusted collateral.
+    // 200 DAI with 95% volatility ratio -> ~210.5$ adjusted borrowed.
+    let data = &find_event(&e, "account_health")["data"][0];
+    assert_eq!(
+        data["account_id"].as_str().unwrap(),
+        users.alice.account_id().as_str()
+    );
+    let collateral_sum: f64 = data["collateral_sum"].as_str().unwrap().parse().unwrap();
+    let borrowed_sum: f64 = data["borrowed_sum"].as_str().unwrap().parse().unwrap();
+    assert!((collateral_sum - 600.0).abs() < 1e-6);
+    assert!((borrowed_sum - 200.0 / 0.95).abs() < 1e-6);
+}
+
+fn has_event(e: &Env, event: &str) -> bool {
+    get_logs(&e.near.borrow_runtime())
+        .iter()
+        .any(|log| log.contains(&format!("\"event\":\"{}\"", event)))
+}
+
+#[test]
+fn test_prices_used_event() {
+    let (e, tokens, users) = basic_setup();
+
+    let mut config = e.get_config();
+    config.prices_used_event_interval_sec = Some(3600);
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(100, 18),
+    )
+    .assert_success();
+    let data = &find_event(&e, "prices_used")["data"][0];
+    assert_eq!(
+        data["account_id"].as_str().unwrap(),
+        users.alice.account_id().as_str()
+    );
+    assert_eq!(data["oracle_id"].as_str().unwrap(), ORACLE_ID);
+    assert!(!data["price_data"]["prices"].as_array().unwrap().is_empty());
+
+    // The events of the executions without liquidations are throttled.
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(100, 18),
+    )
+    .assert_success();
+    assert!(!has_event(&e, "prices_used"));
+
+    e.skip_time(3600);
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(100, 18),
+    )
+    .assert_success();
+    assert!(has_event(&e, "prices_used"));
+}
+
+#[test]
+fn test_protocol_summary_event() {
+    let (e, tokens, users) = basic_setup();
+
+    let amount = d(100, 18);
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, amount, "")
+        .assert_success();
+
+    // The first change in the next block emits the summary of the previous block.
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, amount, "")
+        .assert_success();
+    let data = &find_event(&e, "protocol_summary")["data"][0];
+    let asset_deltas = data["asset_deltas"].as_array().unwrap();
+    assert_eq!(asset_deltas.len(), 1);
+    assert_eq!(
+        asset_deltas[0]["token_id"].as_str().unwrap(),
+        tokens.ndai.account_id().as_str()
+    );
+    assert_eq!(
+        asset_deltas[0]["supplied_delta"].as_str().unwrap(),
+        amount.to_string()
+    );
+    assert_eq!(asset_deltas[0]["borrowed_delta"].as_str().unwrap(), "0");
+}
+
+fn get_heavy_events_stats(e: &Env) -> HeavyEventsStats {
+    e.near
+        .view_method_call(e.contract.contract.get_heavy_events_stats())
+        .unwrap_json()
+}
+
+#[test]
+fn test_heavy_events_flag() {
+    let (e, tokens, users) = basic_setup();
+
+    let mut config = e.get_config();
+    config.heavy_events_enabled = false;
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+    let stats = get_heavy_events_stats(&e);
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "")
+        .assert_success();
+    assert!(!has_event(&e, "account_health"));
+    let new_stats = get_heavy_events_stats(&e);
+    assert_eq!(new_stats.num_emissions, stats.num_emissions);
+    assert_eq!(new_stats.gas_used, stats.gas_used);
+
+    let mut config = e.get_config();
+    config.heavy_events_enabled = true;
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "")
+        .assert_success();
+    assert!(has_event(&e, "account_health"));
+    let new_stats = get_heavy_events_stats(&e);
+    assert!(new_stats.num_emissions > stats.num_emissions);
+    assert!(new_stats.gas_used > stats.gas_used);
 }
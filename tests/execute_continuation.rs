@@ -0,0 +1,58 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::ExecuteContinuation;
+
+#[test]
+fn test_execute_bundle_within_gas() {
+    let (e, tokens, users) = basic_setup();
+
+    let amount = d(100, 18);
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, amount, "")
+        .assert_success();
+
+    let mut actions = vec![];
+    for _ in 0..5 {
+        actions.push(Action::IncreaseCollateral(asset_amount(
+            &tokens.ndai,
+            d(10, 18),
+        )));
+        actions.push(Action::DecreaseCollateral(asset_amount(
+            &tokens.ndai,
+            d(5, 18),
+        )));
+    }
+    users
+        .alice
+        .function_call(
+            e.contract.contract.execute(actions, None),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    let account = e.get_account(&users.alice);
+    assert_balances(
+        &account.collateral,
+        &[av(tokens.ndai.account_id(), d(25, 18))],
+    );
+    let continuation: Option<ExecuteContinuation> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_execute_continuation(users.alice.account_id()),
+        )
+        .unwrap_json();
+    assert!(continuation.is_none());
+
+    assert_failure(
+        users.alice.function_call(
+            e.contract.contract.cancel_execute_continuation(),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The execute continuation of the account is not in progress",
+    );
+}
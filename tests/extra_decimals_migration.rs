@@ -0,0 +1,116 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::ExtraDecimalsMigration;
+use near_sdk::AccountId;
+use std::collections::HashMap;
+
+fn get_extra_decimals_migrations(e: &Env) -> HashMap<AccountId, ExtraDecimalsMigration> {
+    e.near
+        .view_method_call(e.contract.contract.get_extra_decimals_migrations())
+        .unwrap_json()
+}
+
+#[test]
+fn test_extra_decimals_migration() {
+    let (e, tokens, users) = basic_setup();
+
+    e.contract_ft_transfer_call(&tokens.nusdc, &users.alice, d(100, 6), "")
+        .assert_success();
+    e.set_withdraw_timelock(&users.alice, &tokens.nusdc, d(50, 18), 3600)
+        .assert_success();
+    let asset = e.get_asset(&tokens.nusdc);
+
+    assert_failure(
+        users.alice.function_call(
+            e.contract
+                .contract
+                .start_extra_decimals_migration(tokens.nusdc.account_id(), 13),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+    assert_failure(
+        e.owner.function_call(
+            e.contract
+                .contract
+                .start_extra_decimals_migration(tokens.nusdc.account_id(), 12),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The extra decimals are not changed",
+    );
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .start_extra_decimals_migration(tokens.nusdc.account_id(), 13),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    // The pool balances are rescaled, while the shares are kept.
+    let migrated_asset = e.get_asset(&tokens.nusdc);
+    assert_eq!(migrated_asset.config.extra_decimals, 13);
+    assert_eq!(migrated_asset.supplied.shares, asset.supplied.shares);
+    assert_eq!(migrated_asset.supplied.balance, asset.supplied.balance * 10);
+    assert_eq!(migrated_asset.reserved, asset.reserved * 10);
+    let account = e.get_account(&users.alice);
+    assert_eq!(
+        find_asset(&account.supplied, &tokens.nusdc.account_id()).balance,
+        d(100, 19)
+    );
+
+    // The asset is paused until the migration is completed.
+    e.contract_ft_transfer_call(&tokens.nusdc, &users.alice, d(100, 6), "");
+    assert_eq!(
+        e.get_asset(&tokens.nusdc).supplied.balance,
+        migrated_asset.supplied.balance
+    );
+    assert_failure(
+        e.announce_withdraw(&users.alice, &tokens.nusdc, d(100, 18)),
+        "The extra decimals migration of the asset is in progress",
+    );
+    assert_eq!(get_extra_decimals_migrations(&e).len(), 1);
+
+    let completed: bool = users
+        .bob
+        .function_call(
+            e.contract
+                .contract
+                .migrate_extra_decimals(tokens.nusdc.account_id(), 1),
+            MAX_GAS.0,
+            0,
+        )
+        .unwrap_json();
+    assert!(!completed);
+    let completed: bool = users
+        .bob
+        .function_call(
+            e.contract
+                .contract
+                .migrate_extra_decimals(tokens.nusdc.account_id(), 100),
+            MAX_GAS.0,
+            0,
+        )
+        .unwrap_json();
+    assert!(completed);
+
+    assert!(get_extra_decimals_migrations(&e).is_empty());
+    assert_eq!(e.get_asset(&tokens.nusdc).config.paused_actions, 0);
+    assert_eq!(
+        e.get_withdraw_timelocks(&users.alice)[0].threshold,
+        d(50, 19)
+    );
+
+    e.contract_ft_transfer_call(&tokens.nusdc, &users.alice, d(100, 6), "")
+        .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(
+        find_asset(&account.supplied, &tokens.nusdc.account_id()).balance,
+        d(200, 19)
+    );
+}
@@ -2,8 +2,21 @@ mod setup;
 
 use crate::setup::*;
 use common::ONE_YOCTO;
-use contract::FarmId;
+use contract::{AssetApyView, BigDecimal, FarmId};
 use near_sdk::json_types::U128;
+use near_sdk::serde_json;
+
+fn find_event(e: &Env, event: &str) -> serde_json::Value {
+    get_logs(&e.near.borrow_runtime())
+        .iter()
+        .filter(|log| log.starts_with(EVENT_JSON))
+        .map(|log| {
+            serde_json::from_str::<serde_json::Value>(&log[EVENT_JSON.len()..])
+                .expect("Failed to parse the event")
+        })
+        .find(|value| value["event"].as_str().unwrap() == event)
+        .expect("Event not found")
+}
 
 #[test]
 fn test_farm_supplied() {
@@ -95,6 +108,20 @@ fn test_farm_supplied() {
     assert_eq!(account.farms[0].rewards[0].unclaimed_amount, farmed_amount);
 
     e.account_farm_claim_all(&users.alice).assert_success();
+    let data = &find_event(&e, "farm_reward_claimed")["data"][0];
+    assert_eq!(
+        data["account_id"].as_str().unwrap(),
+        users.alice.account_id().as_str()
+    );
+    assert_eq!(
+        data["farm_id"]["Supplied"].as_str().unwrap(),
+        tokens.ndai.account_id().as_str()
+    );
+    assert_eq!(
+        data["token_id"].as_str().unwrap(),
+        e.booster_token.account_id().as_str()
+    );
+    assert_eq!(data["amount"].as_str().unwrap(), farmed_amount.to_string());
 
     let asset = e.get_asset(&e.booster_token);
     assert_eq!(asset.supplied.balance, farmed_amount);
@@ -200,6 +227,11 @@ fn test_farm_supplied() {
     );
 
     e.account_farm_claim_all(&users.alice).assert_success();
+    let data = &find_event(&e, "asset_farm_reward_exhausted")["data"][0];
+    assert_eq!(
+        data["reward_token_id"].as_str().unwrap(),
+        e.booster_token.account_id().as_str()
+    );
 
     let asset = e.get_asset(&e.booster_token);
     assert_eq!(asset.supplied.balance, total_reward);
@@ -828,7 +860,7 @@ fn test_farm_net_tvl_complex() {
         price_data(&tokens, Some(100000), None),
         charlie_borrow_amount,
     )
-        .assert_success();
+    .assert_success();
 
     let account = e.get_account(&users.alice);
     assert_eq!(account.farms.len(), 1);
@@ -906,7 +938,7 @@ fn test_farm_net_tvl_complex() {
     almost_eq(
         account.farms[0].rewards[0].unclaimed_amount,
         farmed_amount * 90 / 150,
-        18
+        18,
     );
 
     let bobs_farmed_amount = farmed_amount * 30 / 150;
@@ -925,7 +957,11 @@ fn test_farm_net_tvl_complex() {
         &[av(tokens.ndai.account_id(), bobs_farmed_amount)],
     );
     // 30$ usdc + 60$ ndai from farming rewards.
-    almost_eq(account.farms[0].rewards[0].boosted_shares, d(30 + 60 , 18), 13);
+    almost_eq(
+        account.farms[0].rewards[0].boosted_shares,
+        d(30 + 60, 18),
+        13,
+    );
     assert_eq!(account.farms[0].rewards[0].unclaimed_amount, 0);
 
     let asset_farm = e.get_asset_farm(farm_id.clone());
@@ -952,7 +988,11 @@ fn test_farm_net_tvl_complex() {
         &[av(tokens.ndai.account_id(), charlie_farmed_amount)],
     );
     // 30$ usdt + 60$ ndai from farming rewards.
-    almost_eq(account.farms[0].rewards[0].boosted_shares, d(30 + 60 , 18), 13);
+    almost_eq(
+        account.farms[0].rewards[0].boosted_shares,
+        d(30 + 60, 18),
+        13,
+    );
     assert_eq!(account.farms[0].rewards[0].unclaimed_amount, 0);
 
     let asset_farm = e.get_asset_farm(farm_id.clone());
@@ -1193,11 +1233,26 @@ fn test_farm_net_tvl_multipliers() {
                     max_utilization_rate: U128(1000000000039724853136740579),
                     volatility_ratio: 6000,
                     extra_decimals: 0,
-                    can_deposit: true,
-                    can_withdraw: true,
+                    paused_actions: 0,
                     can_use_as_collateral: true,
-                    can_borrow: true,
                     net_tvl_multiplier: 8000,
+                    supply_cap: None,
+                    borrow_cap: None,
+                    isolation_debt_ceiling: None,
+                    borrowable_in_isolation: false,
+                    permissioned: false,
+                    frozen_rate: None,
+                    fee_on_transfer: false,
+                    min_borrow_amount: None,
+                    risk_tier: None,
+                    can_flash_loan: true,
+                    flash_loan_fee: None,
+                    protocol_fee_ratio: 0,
+                    collateral_cap: None,
+                    reserve_only: false,
+                    oracle_price_decimals: None,
+                    collateral_only: false,
+                    dust_threshold: None,
                 },
             ),
             DEFAULT_GAS.0,
@@ -1231,3 +1286,212 @@ fn test_farm_net_tvl_multipliers() {
     // 100 - 4 * 10 * 0.8 + 10 * 10 * 0.8
     assert_eq!(account.farms[0].rewards[0].boosted_shares, d(148, 18));
 }
+
+#[test]
+fn test_farms_paged() {
+    let (e, tokens, _users) = basic_setup();
+
+    let reward_per_day = d(100, 18);
+    let total_reward = d(3000, 18);
+
+    let supplied_farm_id = FarmId::Supplied(tokens.ndai.account_id());
+    e.add_farm(
+        supplied_farm_id.clone(),
+        &e.booster_token,
+        reward_per_day,
+        d(100, 18),
+        total_reward,
+    );
+    e.add_farm(
+        FarmId::NetTvl,
+        &e.booster_token,
+        reward_per_day,
+        d(100, 18),
+        total_reward,
+    );
+
+    let farm_ids: Vec<FarmId> = e
+        .near
+        .view_method_call(e.contract.contract.get_all_farm_ids())
+        .unwrap_json();
+    assert_eq!(farm_ids, vec![supplied_farm_id.clone(), FarmId::NetTvl]);
+
+    let get_asset_farms_paged = |from_index: u64, limit: u64| -> Vec<FarmId> {
+        let farms: Vec<(FarmId, near_sdk::serde_json::Value)> = e
+            .near
+            .view_method_call(
+                e.contract
+                    .contract
+                    .get_asset_farms_paged(Some(from_index), Some(limit)),
+            )
+            .unwrap_json();
+        farms.into_iter().map(|(farm_id, _)| farm_id).collect()
+    };
+    assert_eq!(get_asset_farms_paged(0, 1), vec![supplied_farm_id]);
+    assert_eq!(get_asset_farms_paged(1, 1), vec![FarmId::NetTvl]);
+    assert!(get_asset_farms_paged(2, 1).is_empty());
+}
+
+#[test]
+fn test_asset_apy() {
+    let (e, tokens, users) = basic_setup();
+
+    e.add_farm(
+        FarmId::Supplied(tokens.ndai.account_id()),
+        &tokens.ndai,
+        d(10, 18),
+        d(100, 18),
+        d(1000, 18),
+    );
+    e.add_farm(
+        FarmId::Borrowed(tokens.ndai.account_id()),
+        &tokens.ndai,
+        d(1, 18),
+        d(100, 18),
+        d(1000, 18),
+    );
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(3650, 18), "")
+        .assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(10000, 24))
+        .assert_success();
+    e.borrow_and_withdraw(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(365, 18),
+    )
+    .assert_success();
+
+    let apy: AssetApyView = e
+        .near
+        .view_method_call(e.contract.contract.get_asset_apy(tokens.ndai.account_id()))
+        .unwrap_json();
+    // 10 DAI per day for 3650 DAI supplied and 1 DAI per day for 365 DAI borrowed.
+    assert!((apy.supply_farm_apr.f64() - 1.0).abs() < 1e-3);
+    assert!((apy.borrow_farm_apr.f64() - 1.0).abs() < 1e-3);
+    assert!(apy.supply_apr > BigDecimal::zero());
+    assert_eq!(apy.net_supply_apy, apy.supply_apr + apy.supply_farm_apr);
+    assert_eq!(apy.net_borrow_apy, BigDecimal::zero());
+
+    let apys: Vec<AssetApyView> = e
+        .near
+        .view_method_call(e.contract.contract.get_assets_apy())
+        .unwrap_json();
+    let wnear_apy = apys
+        .into_iter()
+        .find(|apy| apy.token_id == tokens.wnear.account_id())
+        .unwrap();
+    assert_eq!(wnear_apy.supply_farm_apr, BigDecimal::zero());
+    assert_eq!(wnear_apy.net_borrow_apy, wnear_apy.borrow_apr);
+}
+
+#[test]
+fn test_farms_limit_and_exit_farm() {
+    let (e, tokens, users) = basic_setup();
+
+    let ndai_farm_id = FarmId::Supplied(tokens.ndai.account_id());
+    let wnear_farm_id = FarmId::Supplied(tokens.wnear.account_id());
+    for farm_id in vec![ndai_farm_id.clone(), wnear_farm_id.clone()] {
+        e.add_farm(
+            farm_id,
+            &e.booster_token,
+            d(100, 18),
+            d(100, 18),
+            d(3000, 18),
+        );
+    }
+
+    let mut config = e.get_config();
+    config.max_num_farms_per_account = Some(1);
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(100, 18), "")
+        .assert_success();
+    e.contract_ft_transfer_call(&tokens.wnear, &users.alice, d(100, 24), "")
+        .assert_success();
+
+    // The account has reached the limit, so it doesn't join the wNEAR farm.
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.farms.len(), 1);
+    assert_eq!(account.farms[0].farm_id, ndai_farm_id);
+    let booster_reward = e.get_asset(&tokens.wnear).farms[0]
+        .rewards
+        .get(&e.booster_token.account_id())
+        .cloned()
+        .unwrap();
+    assert_eq!(booster_reward.boosted_shares, 0);
+
+    users
+        .alice
+        .function_call(
+            e.contract.contract.exit_farm(ndai_farm_id.clone()),
+            MAX_GAS.0,
+            1,
+        )
+        .assert_success();
+    let data = &find_event(&e, "farm_exited")["data"][0];
+    assert_eq!(
+        data["account_id"].as_str().unwrap(),
+        users.alice.account_id().as_str()
+    );
+
+    let account = e.get_account(&users.alice);
+    assert!(account.farms.is_empty());
+    let booster_reward = e.get_asset(&tokens.ndai).farms[0]
+        .rewards
+        .get(&e.booster_token.account_id())
+        .cloned()
+        .unwrap();
+    assert_eq!(booster_reward.boosted_shares, 0);
+
+    e.contract_ft_transfer_call(&tokens.wnear, &users.alice, d(100, 24), "")
+        .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.farms.len(), 1);
+    assert_eq!(account.farms[0].farm_id, wnear_farm_id);
+}
+
+#[test]
+fn test_farm_settled_lazily() {
+    let (e, tokens, users) = basic_setup();
+
+    let reward_per_day = d(100, 18);
+    let total_reward = d(3000, 18);
+
+    let farm_id = FarmId::Supplied(tokens.ndai.account_id());
+    e.add_farm(
+        farm_id.clone(),
+        &e.booster_token,
+        reward_per_day,
+        d(100, 18),
+        total_reward,
+    );
+
+    let amount = d(100, 18);
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, amount, "")
+        .assert_success();
+
+    e.skip_time(ONE_DAY_SEC * 3);
+    let farmed_amount = reward_per_day * 3;
+
+    // The deposit of another asset doesn't change the shares of the farm, so the rewards of the
+    // farm are not claimed.
+    e.contract_ft_transfer_call(&tokens.wnear, &users.alice, d(1, 24), "")
+        .assert_success();
+
+    let asset = e.get_asset(&e.booster_token);
+    assert_eq!(asset.supplied.balance, 0);
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.farms[0].farm_id, farm_id);
+    assert_eq!(account.farms[0].rewards[0].unclaimed_amount, farmed_amount);
+
+    e.account_farm_claim_all(&users.alice).assert_success();
+
+    let asset = e.get_asset(&e.booster_token);
+    assert_eq!(asset.supplied.balance, farmed_amount);
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.farms[0].rewards[0].unclaimed_amount, 0);
+}
@@ -0,0 +1,50 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+
+fn set_fee_on_transfer(e: &Env, tokens: &Tokens) {
+    let mut asset_config = e.get_asset(&tokens.ndai).config;
+    asset_config.fee_on_transfer = true;
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .update_asset(tokens.ndai.account_id(), asset_config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+}
+
+#[test]
+fn test_fee_on_transfer_deposit() {
+    let (e, tokens, users) = basic_setup();
+    set_fee_on_transfer(&e, &tokens);
+
+    let amount = d(100, 18);
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, amount, "")
+        .assert_success();
+    // The token transfers the full amount, so the measured balance difference is the amount.
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.supplied[0].token_id, tokens.ndai.account_id());
+    assert_eq!(account.supplied[0].balance, amount);
+}
+
+#[test]
+fn test_fee_on_transfer_deposit_with_message() {
+    let (e, tokens, users) = basic_setup();
+    set_fee_on_transfer(&e, &tokens);
+
+    let balance = e.ft_balance_of(&users.alice, &tokens.ndai);
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &users.alice,
+        d(100, 18),
+        "\"DepositToCollateral\"",
+    )
+    .assert_success();
+    // The deposit is rejected and refunded.
+    assert_eq!(e.ft_balance_of(&users.alice, &tokens.ndai), balance);
+    assert!(e.get_account(&users.alice).collateral.is_empty());
+}
@@ -0,0 +1,242 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::FlashLoan;
+use near_sdk_sim::transaction::ExecutionStatus;
+
+const FLASH_LOAN_FEE: u32 = 9;
+
+/// Alice supplies 1000 USDC to back the flash loans, puts 100 NEAR as collateral and borrows 200
+/// DAI.
+fn flash_loan_setup() -> (Env, Tokens, Users) {
+    let (e, tokens, users) = basic_setup();
+
+    e.contract_ft_transfer_call(&tokens.nusdc, &users.alice, d(1000, 6), "")
+        .assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+
+    (e, tokens, users)
+}
+
+#[test]
+fn test_flash_loan_failed_transfer_without_fee() {
+    let (e, tokens, users) = flash_loan_setup();
+
+    let reserved = e.get_asset(&tokens.nusdc).reserved;
+
+    // Dude isn't registered with the token, so the transfer fails, the loan isn't taken and the
+    // fee is not charged.
+    e.flash_loan(&users.alice, &tokens.nusdc, d(300, 18), &users.dude, "")
+        .assert_success();
+    let logs = get_logs(&e.near.borrow_runtime());
+    assert!(!logs
+        .iter()
+        .any(|log| log.contains("\"event\":\"flash_loan_shortfall\"")));
+
+    let flash_loan: Option<FlashLoan> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_flash_loan(tokens.nusdc.account_id()),
+        )
+        .unwrap_json();
+    assert!(flash_loan.is_none());
+
+    // The escrowed shares are returned in full.
+    let account = e.get_account(&users.alice);
+    assert_eq!(
+        find_asset(&account.supplied, &tokens.nusdc.account_id()).balance,
+        d(1000, 18)
+    );
+    assert!(!account
+        .borrowed
+        .iter()
+        .any(|a| a.token_id == tokens.nusdc.account_id()));
+
+    let asset = e.get_asset(&tokens.nusdc);
+    assert_eq!(asset.reserved, reserved);
+    assert_eq!(asset.borrowed.balance, 0);
+    assert_eq!(asset.flash_loan_volume, 0);
+    assert_eq!(asset.flash_loan_fees, 0);
+}
+
+#[test]
+fn test_flash_loan_not_repaid() {
+    let (e, tokens, users) = flash_loan_setup();
+
+    let reserved = e.get_asset(&tokens.nusdc).reserved;
+    let bob_balance = e.ft_balance_of(&users.bob, &tokens.nusdc);
+
+    // Bob doesn't have a contract, so the receiver call fails after the loan is transferred, and
+    // nothing is repaid.
+    let amount = d(300, 18);
+    e.flash_loan(&users.alice, &tokens.nusdc, amount, &users.bob, "")
+        .assert_success();
+    assert_eq!(
+        e.ft_balance_of(&users.bob, &tokens.nusdc),
+        bob_balance + d(300, 6)
+    );
+    // The loan and the fee are repaid from the escrowed shares as the shortfall.
+    let logs = get_logs(&e.near.borrow_runtime());
+    assert!(logs
+        .iter()
+        .any(|log| log.contains("\"event\":\"flash_loan_shortfall\"")));
+
+    let fee = amount * u128::from(FLASH_LOAN_FEE) / 10000;
+
+    let flash_loan: Option<FlashLoan> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_flash_loan(tokens.nusdc.account_id()),
+        )
+        .unwrap_json();
+    assert!(flash_loan.is_none());
+
+    // The shortfall never becomes a debt. Alice gets back the suppliers' part of the fee, since
+    // she is the only supplier.
+    let reserved_fee = fee * 2500 / 10000;
+    let account = e.get_account(&users.alice);
+    assert!(!account
+        .borrowed
+        .iter()
+        .any(|a| a.token_id == tokens.nusdc.account_id()));
+    let supplied = find_asset(&account.supplied, &tokens.nusdc.account_id()).balance;
+    assert!(supplied + amount + reserved_fee <= d(1000, 18));
+    assert!(supplied + amount + reserved_fee + 1 >= d(1000, 18));
+
+    let asset = e.get_asset(&tokens.nusdc);
+    assert_eq!(asset.reserved, reserved + reserved_fee);
+    assert_eq!(asset.borrowed.balance, 0);
+    assert_eq!(asset.flash_loan_volume, amount);
+}
+
+#[test]
+fn test_flash_loan_not_enough_supplied() {
+    let (e, tokens, users) = flash_loan_setup();
+
+    // Bob has no supplied USDC to back the loan.
+    let res = e.flash_loan(&users.bob, &tokens.nusdc, d(100, 18), &users.bob, "");
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with not enough supplied balance"),
+    };
+    assert!(err.contains("Not enough supplied balance to cover the flash loan"));
+
+    // The collateral doesn't back the loan.
+    e.supply_to_collateral(&users.bob, &tokens.nusdc, d(1000, 6))
+        .assert_success();
+    let res = e.flash_loan(&users.bob, &tokens.nusdc, d(100, 18), &users.bob, "");
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with not enough supplied balance"),
+    };
+    assert!(err.contains("Not enough supplied balance to cover the flash loan"));
+
+    // The loan and the fee have to be covered by Alice's 1000 USDC.
+    let res = e.flash_loan(&users.alice, &tokens.nusdc, d(300, 18), &users.bob, "");
+    res.assert_success();
+    let res = e.flash_loan(&users.alice, &tokens.nusdc, d(700, 18), &users.bob, "");
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with not enough supplied balance"),
+    };
+    assert!(err.contains("Not enough supplied balance to cover the flash loan"));
+}
+
+#[test]
+fn test_flash_loan_exceeds_available_amount() {
+    let (e, tokens, users) = flash_loan_setup();
+
+    let res = e.flash_loan(&users.alice, &tokens.nusdc, d(20000, 18), &users.bob, "");
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with exceeded available amount"),
+    };
+    assert!(err.contains("Flash loan error: Exceeded available amount"));
+}
+
+#[test]
+fn test_flash_loan_asset_config() {
+    let (e, tokens, users) = flash_loan_setup();
+
+    let mut asset_config = e.get_asset(&tokens.nusdc).config;
+    asset_config.flash_loan_fee = Some(30);
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .update_asset(tokens.nusdc.account_id(), asset_config.clone()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    let amount = d(300, 18);
+    e.flash_loan(&users.alice, &tokens.nusdc, amount, &users.bob, "")
+        .assert_success();
+
+    let fee = amount * 30 / 10000;
+    let asset = e.get_asset(&tokens.nusdc);
+    assert_eq!(asset.flash_loan_volume, amount);
+    assert_eq!(asset.flash_loan_fees, fee);
+    assert_eq!(asset.borrowed.balance, 0);
+
+    asset_config.can_flash_loan = false;
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .update_asset(tokens.nusdc.account_id(), asset_config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    let res = e.flash_loan(&users.alice, &tokens.nusdc, amount, &users.bob, "");
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with disabled flash loans"),
+    };
+    assert!(err.contains("This asset can't be used for flash loans"));
+}
+
+#[test]
+fn test_flash_loan_fee_split() {
+    let (e, tokens, users) = flash_loan_setup();
+
+    e.contract_ft_transfer_call(&tokens.nusdc, &users.bob, d(1000, 6), "")
+        .assert_success();
+    let asset = e.get_asset(&tokens.nusdc);
+    let (reserved, supplied) = (asset.reserved, asset.supplied.balance);
+
+    let amount = d(300, 18);
+    e.flash_loan(&users.alice, &tokens.nusdc, amount, &users.bob, "")
+        .assert_success();
+
+    // 25% of the fee goes to the reserve and the rest to the suppliers. The loan and the fee are
+    // then taken from Alice's escrowed shares as the shortfall.
+    let fee = amount * u128::from(FLASH_LOAN_FEE) / 10000;
+    let reserved_fee = fee * 2500 / 10000;
+    let asset = e.get_asset(&tokens.nusdc);
+    assert_eq!(asset.flash_loan_fees, fee);
+    assert_eq!(asset.reserved - reserved, reserved_fee);
+    assert_eq!(
+        supplied + (fee - reserved_fee) - (amount + fee),
+        asset.supplied.balance
+    );
+
+    // Bob's supplied balance earns his part of the suppliers' fee.
+    let account = e.get_account(&users.bob);
+    assert!(find_asset(&account.supplied, &tokens.nusdc.account_id()).balance > d(1000, 18));
+}
@@ -0,0 +1,109 @@
+#![cfg(feature = "state-import")]
+
+mod setup;
+
+use crate::setup::*;
+use contract::Page;
+use near_sdk::AccountId;
+
+/// Forks the state of one deployment into another and rehearses a risk change and an upgrade
+/// against the imported positions.
+#[test]
+fn test_fork_state() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+
+    let state = ForkState::export(&e);
+    let fork = Env::init();
+    fork.import_state(&state);
+
+    let account = e.get_account(&users.alice);
+    let fork_account = fork.get_account(&users.alice);
+    assert_balances(&fork_account.collateral, &account.collateral);
+    assert_balances(&fork_account.borrowed, &account.borrowed);
+    let (asset, fork_asset) = (e.get_asset(&tokens.ndai), fork.get_asset(&tokens.ndai));
+    assert_eq!(fork_asset.borrowed.balance, asset.borrowed.balance);
+    assert_eq!(fork_asset.borrowed.shares, asset.borrowed.shares);
+
+    // The imported accounts are added to the indexes of the suppliers and the borrowers by the
+    // backfill.
+    let get_borrowers = || -> Vec<AccountId> {
+        let page: Page<AccountId> = fork
+            .near
+            .view_method_call(fork.contract.contract.get_asset_borrowers(
+                tokens.ndai.account_id(),
+                None,
+                None,
+            ))
+            .unwrap_json();
+        page.items
+    };
+    assert!(get_borrowers().is_empty());
+    let num_accounts: u64 = fork
+        .owner
+        .function_call(
+            fork.contract.contract.backfill_asset_accounts(0, 100),
+            MAX_GAS.0,
+            0,
+        )
+        .unwrap_json();
+    assert_eq!(num_accounts, state.accounts.len() as u64);
+    assert_eq!(get_borrowers(), vec![users.alice.account_id()]);
+
+    // 100 NEAR at 10$ adjusted to 600$ against 200 DAI adjusted to 210.5$.
+    let prices = || Some(price_data(&tokens, Some(100000), None));
+    let buckets = fork.get_health_distribution(prices());
+    assert_eq!(buckets[6].num_accounts, 1);
+
+    // Lowering the volatility ratio of NEAR to 20% would make Alice liquidatable.
+    let mut asset_config = fork.get_asset(&tokens.wnear).config;
+    asset_config.volatility_ratio = 2000;
+    fork.owner
+        .function_call(
+            fork.contract
+                .contract
+                .update_asset(tokens.wnear.account_id(), asset_config),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+    let buckets = fork.get_health_distribution(prices());
+    assert_eq!(buckets[0].num_accounts, 1);
+    assert!(buckets[0].min_health.is_none());
+    // The source deployment is not affected.
+    assert_eq!(e.get_health_distribution(prices())[6].num_accounts, 1);
+
+    fork.deploy_contract_by_owner(burrowland_wasm_bytes())
+        .assert_success();
+    assert_eq!(fork.get_health_distribution(prices())[0].num_accounts, 1);
+    fork.assert_invariants(&tokens);
+}
+
+/// Rehearses the upgrade on the mainnet state exported to the directory in
+/// `BURROW_FORK_STATE_DIR`. Skipped if it's not set.
+#[test]
+fn test_fork_mainnet_state() {
+    let state = match ForkState::load_from_env() {
+        Some(state) => state,
+        None => return,
+    };
+    let fork = Env::init();
+    fork.import_state(&state);
+    let buckets = fork.get_health_distribution(state.prices.clone());
+
+    fork.deploy_contract_by_owner(burrowland_wasm_bytes())
+        .assert_success();
+    let upgraded_buckets = fork.get_health_distribution(state.prices.clone());
+    for (bucket, upgraded_bucket) in buckets.iter().zip(upgraded_buckets.iter()) {
+        assert_eq!(bucket.num_accounts, upgraded_bucket.num_accounts);
+    }
+}
@@ -0,0 +1,91 @@
+mod setup;
+
+use crate::setup::*;
+use contract::FarmId;
+
+/// Checks the gas burnt by the canonical operations against the budgets in
+/// `tests/gas_budgets.json`.
+#[test]
+fn test_gas_budgets() {
+    let (e, tokens, users) = basic_setup();
+
+    for token in [&tokens.ndai, &tokens.nusdc, &tokens.neth] {
+        e.add_farm(
+            FarmId::Supplied(token.account_id()),
+            &e.booster_token,
+            d(100, 18),
+            d(100, 18),
+            d(3000, 18),
+        );
+    }
+
+    assert_gas_budget(
+        "deposit",
+        &e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), ""),
+    );
+    e.skip_time(ONE_DAY_SEC);
+    assert_gas_budget("claim_1_farm", &e.account_farm_claim_all(&users.bob));
+
+    e.contract_ft_transfer_call(&tokens.nusdc, &users.bob, d(100, 6), "")
+        .assert_success();
+    e.contract_ft_transfer_call(&tokens.neth, &users.bob, d(1, 18), "")
+        .assert_success();
+    e.skip_time(ONE_DAY_SEC);
+    assert_gas_budget("claim_3_farms", &e.account_farm_claim_all(&users.bob));
+
+    assert_gas_budget(
+        "supply_to_collateral",
+        &e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24)),
+    );
+    assert_gas_budget(
+        "borrow",
+        &e.borrow(
+            &users.alice,
+            &tokens.ndai,
+            price_data(&tokens, Some(100000), None),
+            d(200, 18),
+        ),
+    );
+    assert_gas_budget(
+        "borrow_and_withdraw",
+        &e.borrow_and_withdraw(
+            &users.alice,
+            &tokens.ndai,
+            price_data(&tokens, Some(100000), None),
+            d(100, 18),
+        ),
+    );
+    assert_gas_budget(
+        "repay",
+        &e.deposit_with_actions(
+            &users.alice,
+            &tokens.ndai,
+            d(50, 18),
+            vec![Action::Repay(asset_amount(&tokens.ndai, d(50, 18)))],
+            None,
+        ),
+    );
+
+    // Charlie borrows 50 NEAR at 10$ against 1000 USDC, and Bob liquidates him at 12$.
+    e.supply_to_collateral(&users.charlie, &tokens.nusdc, d(1000, 6))
+        .assert_success();
+    e.borrow_and_withdraw(
+        &users.charlie,
+        &tokens.wnear,
+        price_data(&tokens, Some(100000), None),
+        d(50, 24),
+    )
+    .assert_success();
+    e.contract_ft_transfer_call(&tokens.wnear, &users.bob, d(100, 24), "")
+        .assert_success();
+    assert_gas_budget(
+        "liquidate",
+        &e.liquidate(
+            &users.bob,
+            &users.charlie,
+            price_data(&tokens, Some(120000), None),
+            vec![asset_amount(&tokens.wnear, d(49, 23))],
+            vec![asset_amount(&tokens.nusdc, d(60, 18))],
+        ),
+    );
+}
@@ -0,0 +1,51 @@
+#![cfg(feature = "gas-metrics")]
+
+mod setup;
+
+use crate::setup::*;
+use contract::GAS_METRICS_LOG_PREFIX;
+use near_sdk::serde_json;
+
+/// Returns the action names and the gas used from the gas metrics logs of the last call.
+fn gas_metrics(e: &Env) -> Vec<(String, u64)> {
+    get_logs(&e.near.borrow_runtime())
+        .iter()
+        .filter_map(|log| log.strip_prefix(GAS_METRICS_LOG_PREFIX))
+        .map(|log| {
+            let value = serde_json::from_str::<serde_json::Value>(log)
+                .expect("Failed to parse the gas metrics");
+            (
+                value["action"].as_str().unwrap().to_string(),
+                value["gas_used"].as_str().unwrap().parse().unwrap(),
+            )
+        })
+        .collect()
+}
+
+#[test]
+fn test_gas_metrics_of_actions() {
+    let (e, tokens, users) = basic_setup();
+
+    let supply_amount = d(100, 24);
+    e.supply_to_collateral(&users.alice, &tokens.wnear, supply_amount)
+        .assert_success();
+    let metrics = gas_metrics(&e);
+    assert_eq!(metrics.len(), 1);
+    assert_eq!(metrics[0].0, "IncreaseCollateral");
+    assert!(metrics[0].1 > 0);
+
+    let borrow_amount = d(200, 18);
+    e.borrow_and_withdraw(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        borrow_amount,
+    )
+    .assert_success();
+    let metrics = gas_metrics(&e);
+    assert_eq!(metrics.len(), 1);
+    assert_eq!(metrics[0].0, "BorrowAndWithdraw");
+    for (action, gas_used) in metrics {
+        println!("{}: {} TGas", action, gas_used as f64 / 1e12);
+    }
+}
@@ -0,0 +1,145 @@
+mod setup;
+
+use crate::setup::*;
+use contract::OperationalState;
+
+fn get_operational_state(e: &Env) -> OperationalState {
+    e.near
+        .view_method_call(e.contract.contract.get_operational_state())
+        .unwrap_json()
+}
+
+#[test]
+fn test_guardian_pause() {
+    let (e, tokens, users) = basic_setup();
+
+    assert_failure(
+        e.pause(&users.bob, None, None),
+        "Not an owner or an account with the required role",
+    );
+
+    e.add_guardian(&users.bob).assert_success();
+    assert_eq!(
+        get_operational_state(&e).guardians,
+        vec![users.bob.account_id()]
+    );
+    e.pause(
+        &users.bob,
+        Some(vec![tokens.ndai.account_id()]),
+        Some(vec![PausableAction::Borrow]),
+    )
+    .assert_success();
+
+    let config = e.get_asset(&tokens.ndai).config;
+    assert_eq!(config.get_paused_actions(), vec![PausableAction::Borrow]);
+    assert_eq!(e.get_asset(&tokens.wnear).config.paused_actions, 0);
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    assert_failure(
+        e.borrow(
+            &users.alice,
+            &tokens.ndai,
+            price_data(&tokens, Some(100000), None),
+            d(200, 18),
+        ),
+        "can't be used borrowed",
+    );
+
+    e.pause(&users.bob, None, Some(vec![PausableAction::Deposit]))
+        .assert_success();
+    let config = e.get_asset(&tokens.wnear).config;
+    assert!(config.is_paused(PausableAction::Deposit));
+    assert!(!config.is_paused(PausableAction::Withdraw));
+    assert!(e
+        .get_asset(&tokens.ndai)
+        .config
+        .is_paused(PausableAction::Deposit));
+}
+
+#[test]
+fn test_guardian_cant_resume() {
+    let (e, tokens, users) = basic_setup();
+    e.add_guardian(&users.bob).assert_success();
+    e.pause(&users.bob, Some(vec![tokens.ndai.account_id()]), None)
+        .assert_success();
+
+    let mut config = e.get_asset(&tokens.ndai).config;
+    assert_eq!(config.get_paused_actions(), PausableAction::all());
+    config.paused_actions = 0;
+    assert_failure(
+        users.bob.function_call(
+            e.contract
+                .contract
+                .update_asset(tokens.ndai.account_id(), config),
+            DEFAULT_GAS.0,
+            1,
+        ),
+        "Not an owner",
+    );
+    assert_failure(
+        users.bob.function_call(
+            e.contract
+                .contract
+                .update_asset_paused_actions(tokens.ndai.account_id(), vec![]),
+            DEFAULT_GAS.0,
+            1,
+        ),
+        "Not an owner",
+    );
+    assert_failure(
+        users.bob.function_call(
+            e.contract.contract.add_guardian(users.charlie.account_id()),
+            DEFAULT_GAS.0,
+            1,
+        ),
+        "Not an owner",
+    );
+}
+
+#[test]
+fn test_guardian_pause_price_feeds() {
+    let (e, tokens, users) = basic_setup();
+    e.add_guardian(&users.bob).assert_success();
+
+    let price_feeds_paused = || get_operational_state(&e).price_feeds_paused;
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    users
+        .bob
+        .function_call(e.contract.contract.pause_price_feeds(), DEFAULT_GAS.0, 1)
+        .assert_success();
+    assert!(price_feeds_paused());
+
+    assert_failure(
+        e.borrow(
+            &users.alice,
+            &tokens.ndai,
+            price_data(&tokens, Some(100000), None),
+            d(200, 18),
+        ),
+        "The price feeds are paused",
+    );
+    // The actions that don't need the prices are still executed.
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(10, 18), "")
+        .assert_success();
+
+    assert_failure(
+        users
+            .bob
+            .function_call(e.contract.contract.resume_price_feeds(), DEFAULT_GAS.0, 1),
+        "Not an owner",
+    );
+    e.owner
+        .function_call(e.contract.contract.resume_price_feeds(), DEFAULT_GAS.0, 1)
+        .assert_success();
+    assert!(!price_feeds_paused());
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+}
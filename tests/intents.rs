@@ -0,0 +1,76 @@
+mod setup;
+
+use crate::setup::*;
+use near_sdk::serde_json::json;
+use near_sdk_sim::{to_yocto, UserAccount};
+
+const INTENTS_CONTRACT_ID: &str = "intents.near";
+
+fn enable_intents(e: &Env) {
+    let mut config = e.get_config();
+    config.intents_contract_ids = vec![a(INTENTS_CONTRACT_ID)];
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+}
+
+fn intent_deposit_msg(user: &UserAccount, token: &UserAccount, action: &str) -> String {
+    json!({
+        "IntentDeposit": {
+            "account_id": user.account_id(),
+            "actions": [{action: {"token_id": token.account_id()}}],
+        }
+    })
+    .to_string()
+}
+
+#[test]
+fn test_intent_deposit() {
+    let (e, tokens, users) = basic_setup();
+    enable_intents(&e);
+
+    let intents = e.near.create_user(a(INTENTS_CONTRACT_ID), to_yocto("100"));
+    e.mint_tokens(&tokens, &intents);
+
+    let amount = d(100, 18);
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &intents,
+        amount,
+        &intent_deposit_msg(&users.alice, &tokens.ndai, "IncreaseCollateral"),
+    )
+    .assert_success();
+    let account = e.get_account(&users.alice);
+    assert!(account.supplied.is_empty());
+    assert_eq!(account.collateral[0].token_id, tokens.ndai.account_id());
+    assert_eq!(account.collateral[0].balance, amount);
+
+    // The withdrawals can't follow the intent deposit, so the deposit is refunded.
+    let balance = e.ft_balance_of(&intents, &tokens.ndai);
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &intents,
+        amount,
+        &intent_deposit_msg(&users.alice, &tokens.ndai, "Withdraw"),
+    )
+    .assert_success();
+    assert_eq!(e.ft_balance_of(&intents, &tokens.ndai), balance);
+}
+
+#[test]
+fn test_intent_deposit_requires_settlement_contract() {
+    let (e, tokens, users) = basic_setup();
+    enable_intents(&e);
+
+    let balance = e.ft_balance_of(&users.alice, &tokens.ndai);
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &users.alice,
+        d(100, 18),
+        &intent_deposit_msg(&users.bob, &tokens.ndai, "IncreaseCollateral"),
+    )
+    .assert_success();
+    // The deposit is rejected and refunded.
+    assert_eq!(e.ft_balance_of(&users.alice, &tokens.ndai), balance);
+    assert!(e.get_account(&users.bob).collateral.is_empty());
+}
@@ -0,0 +1,103 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use near_sdk::json_types::U128;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+fn update_asset_config(e: &Env, token: &UserAccount, f: impl FnOnce(&mut AssetConfig)) {
+    let mut asset_config = e.get_asset(token).config;
+    f(&mut asset_config);
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .update_asset(token.account_id(), asset_config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+}
+
+fn increase_collateral(e: &Env, user: &UserAccount, token: &UserAccount) -> ExecutionResult {
+    user.function_call(
+        e.contract.contract.execute(
+            vec![Action::IncreaseCollateral(AssetAmount {
+                token_id: token.account_id(),
+                amount: None,
+                max_amount: None,
+            })],
+            None,
+        ),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_isolation_mode() {
+    let (e, tokens, users) = basic_setup();
+
+    update_asset_config(&e, &tokens.wnear, |asset_config| {
+        asset_config.isolation_debt_ceiling = Some(U128(d(100, 18)));
+    });
+    update_asset_config(&e, &tokens.ndai, |asset_config| {
+        asset_config.borrowable_in_isolation = true;
+    });
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(50, 18),
+    )
+    .assert_success();
+    assert_eq!(e.get_asset(&tokens.wnear).isolated_debt, d(50, 18));
+
+    assert_failure(
+        e.borrow(
+            &users.alice,
+            &tokens.neth,
+            price_data(&tokens, Some(100000), Some(100000)),
+            d(1, 18),
+        ),
+        "The asset can't be borrowed in isolation",
+    );
+    assert_failure(
+        e.borrow(
+            &users.alice,
+            &tokens.ndai,
+            price_data(&tokens, Some(100000), None),
+            d(60, 18),
+        ),
+        "The isolation debt ceiling of wrap.near is exceeded",
+    );
+    e.contract_ft_transfer_call(&tokens.neth, &users.alice, d(1, 18), "")
+        .assert_success();
+    assert_failure(
+        increase_collateral(&e, &users.alice, &tokens.neth),
+        "The isolated asset can't be combined with other collateral assets",
+    );
+
+    e.deposit_with_actions(
+        &users.alice,
+        &tokens.ndai,
+        d(20, 18),
+        vec![Action::Repay(asset_amount(&tokens.ndai, d(20, 18)))],
+        None,
+    )
+    .assert_success();
+    assert_eq!(e.get_asset(&tokens.wnear).isolated_debt, d(30, 18));
+
+    // Accounts with other collateral can't add the isolated asset.
+    e.supply_to_collateral(&users.bob, &tokens.neth, d(1, 18))
+        .assert_success();
+    e.contract_ft_transfer_call(&tokens.wnear, &users.bob, d(1, 24), "")
+        .assert_success();
+    assert_failure(
+        increase_collateral(&e, &users.bob, &tokens.wnear),
+        "The isolated asset can't be combined with other collateral assets",
+    );
+}
@@ -0,0 +1,188 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::KeeperStateView;
+use near_sdk::json_types::U128;
+use near_sdk_sim::to_yocto;
+
+fn get_keeper_state(e: &Env) -> KeeperStateView {
+    e.near
+        .view_method_call(e.contract.contract.get_keeper_state())
+        .unwrap_json()
+}
+
+#[test]
+fn test_keeper_pool() {
+    let (e, _tokens, users) = basic_setup();
+
+    assert_failure(
+        users
+            .alice
+            .function_call(e.contract.contract.fund_keeper_pool(), DEFAULT_GAS.0, 0),
+        "The attached deposit can't be 0",
+    );
+    users
+        .alice
+        .function_call(
+            e.contract.contract.fund_keeper_pool(),
+            DEFAULT_GAS.0,
+            to_yocto("1"),
+        )
+        .assert_success();
+    assert_eq!(get_keeper_state(&e).pool_balance, to_yocto("1"));
+
+    assert_failure(
+        users.alice.function_call(
+            e.contract.contract.withdraw_keeper_pool(None),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+    assert_failure(
+        e.owner.function_call(
+            e.contract
+                .contract
+                .withdraw_keeper_pool(Some(U128(to_yocto("2")))),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The amount exceeds the maintenance pool balance",
+    );
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .withdraw_keeper_pool(Some(U128(to_yocto("0.4")))),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    assert_eq!(get_keeper_state(&e).pool_balance, to_yocto("0.6"));
+}
+
+#[test]
+fn test_keeper_accrue() {
+    let (e, _tokens, users) = basic_setup();
+
+    users
+        .alice
+        .function_call(
+            e.contract.contract.fund_keeper_pool(),
+            DEFAULT_GAS.0,
+            to_yocto("1"),
+        )
+        .assert_success();
+
+    // The assets were updated recently, so there is nothing to accrue and no tip is paid.
+    let num_accrued: u32 = users
+        .bob
+        .function_call(e.contract.contract.keeper_accrue(), MAX_GAS.0, 0)
+        .unwrap_json();
+    assert_eq!(num_accrued, 0);
+    assert_eq!(get_keeper_state(&e).pool_balance, to_yocto("1"));
+
+    e.skip_time(ONE_DAY_SEC);
+
+    let bob_balance = users.bob.account().unwrap().amount;
+    let num_accrued: u32 = users
+        .bob
+        .function_call(e.contract.contract.keeper_accrue(), MAX_GAS.0, 0)
+        .unwrap_json();
+    assert!(num_accrued > 0);
+    assert_eq!(
+        get_keeper_state(&e).pool_balance,
+        to_yocto("1") - KEEPER_TIP
+    );
+    // The tip exceeds the gas spent by the keeper.
+    assert!(users.bob.account().unwrap().amount > bob_balance);
+
+    // The repeated call is a no-op.
+    let num_accrued: u32 = users
+        .bob
+        .function_call(e.contract.contract.keeper_accrue(), MAX_GAS.0, 0)
+        .unwrap_json();
+    assert_eq!(num_accrued, 0);
+    assert_eq!(
+        get_keeper_state(&e).pool_balance,
+        to_yocto("1") - KEEPER_TIP
+    );
+}
+
+#[test]
+fn test_keeper_liquidate_worst_stale_prices() {
+    let (e, _tokens, users) = basic_setup();
+
+    assert_failure(
+        users
+            .bob
+            .function_call(e.contract.contract.keeper_liquidate_worst(), MAX_GAS.0, 0),
+        "The keeper prices are stale",
+    );
+
+    let mut config = e.get_config();
+    config.force_closing_enabled = false;
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+    assert_failure(
+        users
+            .bob
+            .function_call(e.contract.contract.keeper_liquidate_worst(), MAX_GAS.0, 0),
+        "The force closing is not enabled",
+    );
+}
+
+#[test]
+fn test_register_keeper_task() {
+    let (e, _tokens, users) = basic_setup();
+
+    assert_failure(
+        e.owner.function_call(
+            e.contract
+                .contract
+                .register_keeper_task("keeper_accrue".to_string(), "0 0 * * * *".to_string()),
+            MAX_GAS.0,
+            to_yocto("1"),
+        ),
+        "The Croncat manager is not set",
+    );
+
+    let mut config = e.get_config();
+    config.croncat_manager_id = Some(a("manager.croncat.near"));
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+
+    assert_failure(
+        users.alice.function_call(
+            e.contract
+                .contract
+                .register_keeper_task("keeper_accrue".to_string(), "0 0 * * * *".to_string()),
+            MAX_GAS.0,
+            to_yocto("1"),
+        ),
+        "Not an owner",
+    );
+    assert_failure(
+        e.owner.function_call(
+            e.contract
+                .contract
+                .register_keeper_task("execute".to_string(), "0 0 * * * *".to_string()),
+            MAX_GAS.0,
+            to_yocto("1"),
+        ),
+        "Not a keeper method",
+    );
+    assert_failure(
+        e.owner.function_call(
+            e.contract
+                .contract
+                .unregister_keeper_task("keeper_accrue".to_string()),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The keeper task is not registered",
+    );
+}
@@ -0,0 +1,113 @@
+mod setup;
+
+use crate::setup::*;
+use contract::LeverageSwap;
+use near_sdk::json_types::U128;
+use near_sdk::Balance;
+use near_sdk_sim::transaction::ExecutionStatus;
+
+fn swap_route(tokens: &Tokens, min_amount_out: Balance) -> Vec<SwapAction> {
+    vec![SwapAction {
+        pool_id: 0,
+        token_in: tokens.ndai.account_id(),
+        amount_in: None,
+        token_out: tokens.wnear.account_id(),
+        min_amount_out: U128(min_amount_out),
+    }]
+}
+
+#[test]
+fn test_leverage_up_failed_swap_is_repaid() {
+    let (e, tokens, users) = basic_setup();
+
+    let supply_amount = d(100, 24);
+    e.supply_to_collateral(&users.alice, &tokens.wnear, supply_amount)
+        .assert_success();
+
+    // 100 NEAR at 10$ with 1.5x leverage requires to borrow 500 DAI.
+    let res = e.leverage_up(
+        &users.alice,
+        price_data(&tokens, Some(100000), None),
+        &tokens.wnear,
+        &tokens.ndai,
+        15000,
+        swap_route(&tokens, d(49, 24)),
+    );
+    res.assert_success();
+    let logs = get_logs(&e.near.borrow_runtime());
+    assert!(logs
+        .iter()
+        .any(|log| log.contains("\"event\":\"leverage_swap_started\"")
+            && log.contains("\"amount\":\"500000000000000000000\"")));
+    // The exchange doesn't exist, so the swap fails and the borrowed amount is repaid.
+    assert!(logs
+        .iter()
+        .any(|log| log.contains("\"event\":\"leverage_swap_failed\"")));
+
+    let leverage_swap: Option<LeverageSwap> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_leverage_swap(users.alice.account_id()),
+        )
+        .unwrap_json();
+    assert!(leverage_swap.is_none());
+
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.collateral[0].balance, supply_amount);
+    // Some interest might have accrued in between.
+    assert!(account
+        .borrowed
+        .iter()
+        .all(|asset| asset.balance < d(1, 12)));
+
+    // There is no leverage swap to roll back.
+    assert_failure(
+        users.bob.function_call(
+            e.contract
+                .contract
+                .clear_expired_leverage_swap(users.alice.account_id()),
+            DEFAULT_GAS.0,
+            0,
+        ),
+        "The account doesn't have an expired leverage swap",
+    );
+}
+
+#[test]
+fn test_leverage_up_not_enough_min_amount_out() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+
+    // 100 NEAR at 10$ with 3x leverage requires to borrow 2000 DAI.
+    // Expecting only 10 NEAR out of the swap is not enough to cover the borrowed amount.
+    let res = e.leverage_up(
+        &users.alice,
+        price_data(&tokens, Some(100000), None),
+        &tokens.wnear,
+        &tokens.ndai,
+        30000,
+        swap_route(&tokens, d(10, 24)),
+    );
+    assert!(!res.is_ok());
+
+    let res = e.leverage_up(
+        &users.alice,
+        price_data(&tokens, Some(100000), None),
+        &tokens.ndai,
+        &tokens.wnear,
+        20000,
+        swap_route(&tokens, d(10, 24)),
+    );
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with collateral not found"),
+    };
+    assert!(err.contains("Collateral asset not found"));
+
+    let account = e.get_account(&users.alice);
+    assert!(account.borrowed.is_empty());
+}
@@ -1,7 +1,7 @@
 mod setup;
 
 use crate::setup::*;
-use contract::BigDecimal;
+use contract::{BigDecimal, LiquidatableAccountView, Page};
 use near_sdk::serde_json;
 use near_sdk_sim::transaction::ExecutionStatus;
 
@@ -225,6 +225,21 @@ fn test_liquidation_decrease_health_factor() {
         "111.0"
     );
     assert_eq!(value["data"][0]["repaid_sum"].as_str().unwrap(), "108.8");
+    let repaid_assets = value["data"][0]["repaid_assets"].as_array().unwrap();
+    assert_eq!(repaid_assets.len(), 2);
+    assert_eq!(
+        repaid_assets[1]["token_id"].as_str().unwrap(),
+        tokens.nusdt.account_id().as_str()
+    );
+    assert_eq!(
+        repaid_assets[1]["amount"].as_str().unwrap(),
+        usdt_amount_in.to_string()
+    );
+    let collateral_assets = value["data"][0]["collateral_assets"].as_array().unwrap();
+    assert_eq!(
+        collateral_assets[0]["amount"].as_str().unwrap(),
+        usdc_amount_out.to_string()
+    );
 
     let account = e.get_account(&users.alice);
     assert!(account.supplied.is_empty());
@@ -346,3 +361,139 @@ fn test_force_close() {
     let asset = e.get_asset(&tokens.wnear);
     assert_eq!(asset.reserved, wnear_reserve - borrow_amount);
 }
+
+/// Alice's wNEAR collateral is valued at zero while the oracle omits its price, and Bob can only
+/// liquidate her by taking the collateral with a price.
+#[test]
+fn test_liquidation_missing_collateral_price() {
+    let (e, tokens, users) = basic_setup();
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(2000, 18), "")
+        .assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.nusdc, d(1000, 6))
+        .assert_success();
+
+    // The borrow is allowed with the wNEAR collateral valued at zero, and the event is emitted.
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, None, None),
+        d(100, 18),
+    )
+    .assert_success();
+    let logs = get_logs(&e.near.borrow_runtime());
+    assert!(logs
+        .iter()
+        .any(|log| log.contains("\"event\":\"collateral_price_missing\"")
+            && log.contains(tokens.wnear.account_id().as_str())));
+
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(900, 18),
+    )
+    .assert_success();
+
+    // The collateral without a price can't be decreased while borrowing.
+    let res = e.oracle_call(
+        &users.alice,
+        price_data(&tokens, None, None),
+        PriceReceiverMsg::Execute {
+            actions: vec![Action::DecreaseCollateral(asset_amount(
+                &tokens.wnear,
+                d(1, 24),
+            ))],
+            constraints: None,
+        },
+    );
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail"),
+    };
+    assert!(err.contains("can't be decreased while borrowing"));
+
+    // Without the wNEAR price, the adjusted collateral of 950$ is below the adjusted borrowed sum
+    // of 1052.6$, but the wNEAR collateral can't be seized.
+    let res = e.liquidate(
+        &users.bob,
+        &users.alice,
+        price_data(&tokens, None, None),
+        vec![asset_amount(&tokens.ndai, d(100, 18))],
+        vec![asset_amount(&tokens.wnear, d(10, 24))],
+    );
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail"),
+    };
+    assert!(err.contains("The price of the seized collateral"));
+
+    e.liquidate(
+        &users.bob,
+        &users.alice,
+        price_data(&tokens, None, None),
+        vec![asset_amount(&tokens.ndai, d(100, 18))],
+        vec![asset_amount(&tokens.nusdc, d(98, 18))],
+    )
+    .assert_success();
+
+    let account = e.get_account(&users.alice);
+    assert_balances(
+        &account.collateral,
+        &[
+            av(tokens.wnear.account_id(), d(100, 24)),
+            av(tokens.nusdc.account_id(), d(902, 18)),
+        ],
+    );
+}
+
+#[test]
+fn test_get_liquidatable_accounts() {
+    let (e, tokens, users) = basic_setup();
+
+    let get_liquidatable_accounts = |wnear_mul| -> Page<LiquidatableAccountView> {
+        e.near
+            .view_method_call(e.contract.contract.get_liquidatable_accounts(
+                Some(price_data(&tokens, Some(wnear_mul), None)),
+                None,
+                None,
+            ))
+            .unwrap_json()
+    };
+
+    e.supply_to_collateral(&users.alice, &tokens.nusdc, d(1000, 6))
+        .assert_success();
+    e.borrow_and_withdraw(
+        &users.alice,
+        &tokens.wnear,
+        price_data(&tokens, Some(100000), None),
+        d(50, 24),
+    )
+    .assert_success();
+    e.contract_ft_transfer_call(&tokens.wnear, &users.bob, d(100, 24), "")
+        .assert_success();
+
+    assert!(get_liquidatable_accounts(100000).items.is_empty());
+
+    // The adjusted collateral sum is 950$ and the adjusted borrowed sum is 1000$ at 12$.
+    let page = get_liquidatable_accounts(120000);
+    assert_eq!(page.items.len(), 1);
+    let account = &page.items[0];
+    assert_eq!(account.account_id, users.alice.account_id());
+    assert_eq!(account.health_factor, 9500);
+    assert_eq!(account.collateral[0].balance, d(1000, 18));
+    // The gap of 50$ closes after repaying 72.2$, so the max repay is rounded down to 6 NEAR.
+    assert_eq!(account.borrowed[0].token_id, tokens.wnear.account_id());
+    assert_eq!(account.borrowed[0].max_repay_amount, d(6, 24));
+
+    e.liquidate(
+        &users.bob,
+        &users.alice,
+        price_data(&tokens, Some(120000), None),
+        vec![asset_amount(&tokens.wnear, d(6, 24))],
+        vec![asset_amount(&tokens.nusdc, d(738, 17))],
+    )
+    .assert_success();
+}
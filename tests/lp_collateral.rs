@@ -0,0 +1,71 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use near_sdk::json_types::U128;
+
+const LP_TOKEN_ID: &str = "shadow_ref_v1-0";
+
+#[test]
+fn test_cast_shadow_requires_exchange() {
+    let (e, _tokens, users) = basic_setup();
+
+    assert_failure(
+        users.alice.function_call(
+            e.contract.contract.on_cast_shadow(
+                users.alice.account_id(),
+                "0".to_string(),
+                U128(d(1, 24)),
+                "".to_string(),
+            ),
+            MAX_GAS.0,
+            0,
+        ),
+        "Can only be called by the exchange",
+    );
+    assert_failure(
+        users.alice.function_call(
+            e.contract.contract.on_remove_shadow(
+                users.alice.account_id(),
+                "0".to_string(),
+                U128(d(1, 24)),
+                "".to_string(),
+            ),
+            MAX_GAS.0,
+            0,
+        ),
+        "Can only be called by the exchange",
+    );
+}
+
+#[test]
+fn test_lp_asset_requires_paused_borrow() {
+    let (e, tokens, _users) = basic_setup();
+
+    let mut asset_config = e.get_asset(&tokens.neth).config;
+    asset_config.paused_actions = 0;
+    assert_failure(
+        e.owner.function_call(
+            e.contract.contract.add_asset(a(LP_TOKEN_ID), asset_config),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The borrowing of the pool shares has to be paused",
+    );
+}
+
+#[test]
+fn test_sync_lp_token_infos_requires_lp_asset() {
+    let (e, tokens, users) = basic_setup();
+
+    assert_failure(
+        users.alice.function_call(
+            e.contract
+                .contract
+                .sync_ref_exchange_lp_token_infos(Some(vec![tokens.neth.account_id()])),
+            MAX_GAS.0,
+            0,
+        ),
+        "The asset is not the pool shares",
+    );
+}
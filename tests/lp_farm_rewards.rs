@@ -0,0 +1,88 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use near_sdk::serde_json::json;
+use near_sdk_sim::{to_yocto, UserAccount};
+
+const REF_BOOST_FARM_ID: &str = "boostfarm.near";
+
+fn set_ref_boost_farm(e: &Env) {
+    let mut config = e.get_config();
+    config.ref_boost_farm_id = Some(a(REF_BOOST_FARM_ID));
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+}
+
+fn lp_farm_rewards_msg(user: &UserAccount) -> String {
+    json!({
+        "LpFarmRewards": {
+            "account_id": user.account_id(),
+        }
+    })
+    .to_string()
+}
+
+#[test]
+fn test_claim_lp_farm_rewards_validation() {
+    let (e, tokens, users) = basic_setup();
+
+    let claim = |e: &Env, token_id: &str| {
+        users.alice.function_call(
+            e.contract.contract.claim_lp_farm_rewards(a(token_id)),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+    };
+    assert_failure(claim(&e, "shadow_ref_v1-0"), "The boost farm is not set");
+
+    set_ref_boost_farm(&e);
+    assert_failure(
+        claim(&e, tokens.ndai.account_id().as_str()),
+        "The asset is not the pool shares",
+    );
+    assert_failure(
+        claim(&e, "shadow_ref_v1-0"),
+        "The account doesn't have the pool shares",
+    );
+}
+
+#[test]
+fn test_lp_farm_rewards_deposit() {
+    let (e, tokens, users) = basic_setup();
+    set_ref_boost_farm(&e);
+
+    let boost_farm = e.near.create_user(a(REF_BOOST_FARM_ID), to_yocto("100"));
+    e.mint_tokens(&tokens, &boost_farm);
+
+    let amount = d(10, 18);
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &boost_farm,
+        amount,
+        &lp_farm_rewards_msg(&users.alice),
+    )
+    .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.supplied[0].token_id, tokens.ndai.account_id());
+    assert_eq!(account.supplied[0].balance, amount);
+}
+
+#[test]
+fn test_lp_farm_rewards_deposit_requires_boost_farm() {
+    let (e, tokens, users) = basic_setup();
+    set_ref_boost_farm(&e);
+
+    let balance = e.ft_balance_of(&users.alice, &tokens.ndai);
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &users.alice,
+        d(10, 18),
+        &lp_farm_rewards_msg(&users.bob),
+    )
+    .assert_success();
+    // The deposit is rejected and refunded.
+    assert_eq!(e.ft_balance_of(&users.alice, &tokens.ndai), balance);
+    assert!(e.get_account(&users.bob).supplied.is_empty());
+}
@@ -0,0 +1,81 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::MtToken;
+use near_sdk::json_types::U128;
+
+const MT_CONTRACT_ID: &str = "mt.near";
+
+#[test]
+fn test_add_mt_asset_validation() {
+    let (e, tokens, users) = basic_setup();
+
+    let asset_config = e.get_asset(&tokens.ndai).config;
+    assert_failure(
+        users.alice.function_call(
+            e.contract.contract.add_mt_asset(
+                a("mt_asset.near"),
+                a(MT_CONTRACT_ID),
+                "1".to_string(),
+                asset_config.clone(),
+            ),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+    assert_failure(
+        e.owner.function_call(
+            e.contract.contract.add_mt_asset(
+                tokens.ndai.account_id(),
+                a(MT_CONTRACT_ID),
+                "1".to_string(),
+                asset_config,
+            ),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The asset already exists",
+    );
+
+    let mt_token: Option<MtToken> = e
+        .near
+        .view_method_call(e.contract.contract.get_mt_token(tokens.ndai.account_id()))
+        .unwrap_json();
+    assert!(mt_token.is_none());
+}
+
+#[test]
+fn test_mt_on_transfer_requires_listed_token() {
+    let (e, _tokens, users) = basic_setup();
+
+    assert_failure(
+        users.alice.function_call(
+            e.contract.contract.mt_on_transfer(
+                users.alice.account_id(),
+                vec![users.alice.account_id()],
+                vec!["1".to_string()],
+                vec![U128(d(1, 18))],
+                "".to_string(),
+            ),
+            MAX_GAS.0,
+            0,
+        ),
+        "The token is not listed",
+    );
+    assert_failure(
+        users.alice.function_call(
+            e.contract.contract.mt_on_transfer(
+                users.alice.account_id(),
+                vec![users.bob.account_id()],
+                vec!["1".to_string()],
+                vec![U128(d(1, 18))],
+                "".to_string(),
+            ),
+            MAX_GAS.0,
+            0,
+        ),
+        "The tokens have to be transferred by their owner",
+    );
+}
@@ -0,0 +1,170 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::MULTISIG_REQUEST_EXPIRATION_SEC;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde_json::json;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+fn multisig_request(
+    e: &Env,
+    user: &UserAccount,
+    method_name: &str,
+    args: near_sdk::serde_json::Value,
+) -> ExecutionResult {
+    user.function_call(
+        e.contract.contract.multisig_request(
+            method_name.to_string(),
+            Base64VecU8::from(args.to_string().into_bytes()),
+        ),
+        DEFAULT_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+fn multisig_confirm(e: &Env, user: &UserAccount, request_id: u32) -> ExecutionResult {
+    user.function_call(
+        e.contract.contract.multisig_confirm(request_id),
+        DEFAULT_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+fn multisig_execute(e: &Env, user: &UserAccount, request_id: u32) -> ExecutionResult {
+    user.function_call(
+        e.contract.contract.multisig_execute(request_id),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_multisig_owner() {
+    let (e, tokens, users) = basic_setup();
+
+    let mut config = e.get_config();
+    config.owner_id = e.contract.account_id();
+    config.multisig_members = vec![
+        users.alice.account_id(),
+        users.bob.account_id(),
+        users.charlie.account_id(),
+    ];
+    config.multisig_threshold = 2;
+    config.multisig_methods = vec!["update_asset_flags".to_string()];
+    e.owner
+        .function_call(
+            e.contract.contract.update_config(config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    // The previous owner can't call the owner methods anymore.
+    assert_failure(
+        e.owner.function_call(
+            e.contract
+                .contract
+                .update_asset_flags(tokens.ndai.account_id(), false),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+
+    let args = json!({
+        "token_id": tokens.ndai.account_id(),
+        "can_use_as_collateral": false,
+    });
+    assert_failure(
+        multisig_request(&e, &users.dude, "update_asset_flags", args.clone()),
+        "Not a multisig member",
+    );
+    assert_failure(
+        multisig_request(
+            &e,
+            &users.alice,
+            "update_config",
+            json!({ "config": e.get_config() }),
+        ),
+        "The method can't be called with the multisig",
+    );
+    let request_id: u32 =
+        multisig_request(&e, &users.alice, "update_asset_flags", args).unwrap_json();
+
+    assert_failure(
+        multisig_confirm(&e, &users.alice, request_id),
+        "The request is already confirmed by the account",
+    );
+    assert_failure(
+        multisig_execute(&e, &users.alice, request_id),
+        "Not enough confirmations",
+    );
+
+    multisig_confirm(&e, &users.bob, request_id).assert_success();
+    multisig_execute(&e, &users.charlie, request_id).assert_success();
+    assert!(!e.get_asset(&tokens.ndai).config.can_use_as_collateral);
+
+    assert_failure(
+        multisig_confirm(&e, &users.bob, request_id),
+        "Multisig request not found",
+    );
+}
+
+#[test]
+fn test_multisig_cancel_and_expiration() {
+    let (e, tokens, users) = basic_setup();
+
+    let mut config = e.get_config();
+    config.owner_id = e.contract.account_id();
+    config.multisig_members = vec![
+        users.alice.account_id(),
+        users.bob.account_id(),
+        users.charlie.account_id(),
+    ];
+    config.multisig_threshold = 2;
+    config.multisig_methods = vec!["update_asset_flags".to_string()];
+    e.owner
+        .function_call(
+            e.contract.contract.update_config(config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    let args = json!({
+        "token_id": tokens.ndai.account_id(),
+        "can_use_as_collateral": false,
+    });
+    let multisig_cancel = |user: &UserAccount, request_id: u32| {
+        user.function_call(
+            e.contract.contract.multisig_cancel(request_id),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+    };
+
+    // Only the proposer can cancel the pending request.
+    let request_id: u32 =
+        multisig_request(&e, &users.alice, "update_asset_flags", args.clone()).unwrap_json();
+    assert_failure(
+        multisig_cancel(&users.bob, request_id),
+        "Only the proposer can cancel the request before it expires",
+    );
+    multisig_cancel(&users.alice, request_id).assert_success();
+
+    // The expired request can't be confirmed or executed, and any member can remove it.
+    let request_id: u32 =
+        multisig_request(&e, &users.alice, "update_asset_flags", args).unwrap_json();
+    e.skip_time(MULTISIG_REQUEST_EXPIRATION_SEC);
+    assert_failure(
+        multisig_confirm(&e, &users.bob, request_id),
+        "The multisig request has expired",
+    );
+    assert_failure(
+        multisig_execute(&e, &users.bob, request_id),
+        "The multisig request has expired",
+    );
+    multisig_cancel(&users.bob, request_id).assert_success();
+    assert!(e.get_asset(&tokens.ndai).config.can_use_as_collateral);
+}
@@ -0,0 +1,103 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use near_sdk::Balance;
+use near_sdk_sim::{to_yocto, ExecutionResult, UserAccount};
+
+/// Enables the native NEAR with the test wNEAR token, which doesn't implement the wrapping, so
+/// every wrapping and unwrapping fails.
+fn enable_native_near(e: &Env, tokens: &Tokens) {
+    let mut config = e.get_config();
+    config.wnear_token_id = Some(tokens.wnear.account_id());
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+}
+
+fn deposit_near(e: &Env, user: &UserAccount, amount: Balance) -> ExecutionResult {
+    user.function_call(e.contract.contract.deposit_near(), MAX_GAS.0, amount)
+}
+
+fn withdraw_near(
+    e: &Env,
+    user: &UserAccount,
+    token: &UserAccount,
+    amount: Balance,
+) -> ExecutionResult {
+    user.function_call(
+        e.contract.contract.execute(
+            vec![Action::WithdrawNear(asset_amount(token, amount))],
+            None,
+        ),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_native_near_not_enabled() {
+    let (e, tokens, users) = basic_setup();
+
+    assert_failure(
+        deposit_near(&e, &users.alice, to_yocto("10")),
+        "The native NEAR is not enabled",
+    );
+    e.contract_ft_transfer_call(&tokens.wnear, &users.alice, d(10, 24), "")
+        .assert_success();
+    assert_failure(
+        withdraw_near(&e, &users.alice, &tokens.wnear, d(10, 24)),
+        "The native NEAR is not enabled",
+    );
+}
+
+#[test]
+fn test_deposit_near_refund() {
+    let (e, tokens, users) = basic_setup();
+    enable_native_near(&e, &tokens);
+
+    let near_balance = users.alice.account().unwrap().amount;
+    deposit_near(&e, &users.alice, to_yocto("10")).assert_success();
+
+    // The wrapping fails, so the deposit is not credited and the NEAR is refunded.
+    let account = e.get_account(&users.alice);
+    assert!(account.supplied.is_empty());
+    assert!(near_balance - users.alice.account().unwrap().amount < to_yocto("1"));
+
+    // The account is unlocked.
+    e.contract_ft_transfer_call(&tokens.wnear, &users.alice, d(10, 24), "")
+        .assert_success();
+    e.withdraw(&users.alice, &tokens.wnear, d(5, 24))
+        .assert_success();
+}
+
+#[test]
+fn test_withdraw_near_rollback() {
+    let (e, tokens, users) = basic_setup();
+    enable_native_near(&e, &tokens);
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(100, 18), "")
+        .assert_success();
+    assert_failure(
+        withdraw_near(&e, &users.alice, &tokens.ndai, d(100, 18)),
+        "Only wNEAR can be withdrawn as the native NEAR",
+    );
+
+    let amount = d(10, 24);
+    e.contract_ft_transfer_call(&tokens.wnear, &users.alice, amount, "")
+        .assert_success();
+    withdraw_near(&e, &users.alice, &tokens.wnear, amount).assert_success();
+
+    // The unwrapping fails, so the amount becomes claimable as wNEAR.
+    let account = e.get_account(&users.alice);
+    assert!(account
+        .supplied
+        .iter()
+        .all(|asset| asset.token_id != tokens.wnear.account_id()));
+    assert_eq!(account.withdraw_claims.len(), 1);
+    assert_eq!(
+        account.withdraw_claims[0].token_id,
+        tokens.wnear.account_id()
+    );
+    assert_eq!(account.withdraw_claims[0].amount, amount);
+}
@@ -0,0 +1,74 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+
+#[test]
+fn test_net_supplied_and_collateral() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+    users
+        .alice
+        .function_call(
+            e.contract.contract.execute(
+                vec![Action::IncreaseCollateral(asset_amount(
+                    &tokens.ndai,
+                    d(50, 18),
+                ))],
+                None,
+            ),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    // The 150 DAI of the supplied balance are used first and the rest is taken from collateral.
+    users
+        .alice
+        .function_call(
+            e.contract.contract.execute(
+                vec![Action::Net(asset_amount(&tokens.ndai, d(180, 18)))],
+                None,
+            ),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    let account = e.get_account(&users.alice);
+    assert!(account
+        .supplied
+        .iter()
+        .all(|asset| asset.token_id != tokens.ndai.account_id()));
+    almost_eq(
+        find_asset(&account.collateral, &tokens.ndai.account_id()).balance,
+        d(20, 18),
+        15,
+    );
+    almost_eq(
+        find_asset(&account.borrowed, &tokens.ndai.account_id()).balance,
+        d(20, 18),
+        15,
+    );
+
+    assert_failure(
+        users.bob.function_call(
+            e.contract.contract.execute(
+                vec![Action::Net(asset_amount(&tokens.ndai, d(1, 18)))],
+                None,
+            ),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Borrowed asset not found",
+    );
+}
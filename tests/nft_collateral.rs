@@ -0,0 +1,309 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::{NftAuction, NftCollectionConfig, NftId, PriceReceiverMsg};
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
+use near_sdk_sim::{to_yocto, ExecutionResult, UserAccount};
+
+const NFT_TOKEN_ID: &str = "1";
+
+/// The NFT contract and the floor price oracle are plain accounts, so the NFT transfers from the
+/// contract fail and the NFTs are returned to the collateral of the receivers.
+fn nft_setup() -> (Env, Tokens, Users, UserAccount, UserAccount) {
+    let (e, tokens, users) = basic_setup();
+    let collection = e.near.create_user(a("nft.near"), to_yocto("100"));
+    let floor_oracle = e.near.create_user(a("floor-oracle.near"), to_yocto("100"));
+    e.owner
+        .function_call(
+            e.contract.contract.set_nft_collection(
+                collection.account_id(),
+                NftCollectionConfig {
+                    quote_token_id: tokens.ndai.account_id(),
+                    oracle_id: floor_oracle.account_id(),
+                    volatility_ratio: 5000,
+                    max_staleness_sec: ONE_DAY_SEC,
+                    auction_duration_sec: ONE_DAY_SEC,
+                },
+            ),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    report_floor_price(&e, &floor_oracle, &collection, d(1000, 18)).assert_success();
+    (e, tokens, users, collection, floor_oracle)
+}
+
+fn report_floor_price(
+    e: &Env,
+    floor_oracle: &UserAccount,
+    collection: &UserAccount,
+    floor_price: u128,
+) -> ExecutionResult {
+    floor_oracle.function_call(
+        e.contract
+            .contract
+            .report_nft_floor_price(collection.account_id(), U128(floor_price)),
+        DEFAULT_GAS.0,
+        0,
+    )
+}
+
+fn nft_deposit(e: &Env, collection: &UserAccount, user: &UserAccount) -> ExecutionResult {
+    collection.function_call(
+        e.contract.contract.nft_on_transfer(
+            user.account_id(),
+            user.account_id(),
+            NFT_TOKEN_ID.to_string(),
+            "".to_string(),
+        ),
+        MAX_GAS.0,
+        0,
+    )
+}
+
+fn nft_id(collection: &UserAccount) -> NftId {
+    NftId {
+        collection_id: collection.account_id(),
+        token_id: NFT_TOKEN_ID.to_string(),
+    }
+}
+
+fn get_nft_collateral(e: &Env, user: &UserAccount) -> Vec<NftId> {
+    e.near
+        .view_method_call(e.contract.contract.get_nft_collateral(user.account_id()))
+        .unwrap_json()
+}
+
+fn nft_bid_msg(collection: &UserAccount) -> String {
+    json!({
+        "NftBid": {
+            "collection_id": collection.account_id(),
+            "token_id": NFT_TOKEN_ID,
+        }
+    })
+    .to_string()
+}
+
+#[test]
+fn test_nft_deposit() {
+    let (e, _tokens, users, collection, floor_oracle) = nft_setup();
+
+    assert_failure(
+        nft_deposit(&e, &floor_oracle, &users.alice),
+        "The NFT collection is not whitelisted",
+    );
+    assert_failure(
+        collection.function_call(
+            e.contract.contract.nft_on_transfer(
+                users.bob.account_id(),
+                users.alice.account_id(),
+                NFT_TOKEN_ID.to_string(),
+                "".to_string(),
+            ),
+            MAX_GAS.0,
+            0,
+        ),
+        "The NFT has to be transferred by its owner",
+    );
+    assert_failure(
+        report_floor_price(&e, &users.alice, &collection, d(1, 18)),
+        "Not the floor price oracle of the collection",
+    );
+
+    nft_deposit(&e, &collection, &users.alice).assert_success();
+    assert_eq!(
+        get_nft_collateral(&e, &users.alice),
+        vec![nft_id(&collection)]
+    );
+}
+
+#[test]
+fn test_nft_borrow_and_withdraw() {
+    let (e, tokens, users, collection, _floor_oracle) = nft_setup();
+    nft_deposit(&e, &collection, &users.alice).assert_success();
+
+    // The floor price of 1000 DAI with the 50% haircut covers up to 475 DAI of the borrowed DAI.
+    assert_failure(
+        e.borrow(
+            &users.alice,
+            &tokens.ndai,
+            price_data(&tokens, None, None),
+            d(500, 18),
+        ),
+        "compute_max_discount",
+    );
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, None, None),
+        d(400, 18),
+    )
+    .assert_success();
+
+    let withdraw_nft = |e: &Env| {
+        e.oracle_call(
+            &users.alice,
+            price_data(&tokens, None, None),
+            PriceReceiverMsg::Execute {
+                actions: vec![Action::WithdrawNft {
+                    collection_id: collection.account_id(),
+                    token_id: NFT_TOKEN_ID.to_string(),
+                }],
+                constraints: None,
+            },
+        )
+    };
+    assert_failure(withdraw_nft(&e), "compute_max_discount");
+
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &users.alice,
+        d(500, 18),
+        &json!({
+            "Execute": {
+                "actions": [{"Repay": {"token_id": tokens.ndai.account_id()}}]
+            }
+        })
+        .to_string(),
+    )
+    .assert_success();
+    withdraw_nft(&e).assert_success();
+    // The transfer to the plain account fails, so the NFT is returned to the collateral.
+    assert_eq!(
+        get_nft_collateral(&e, &users.alice),
+        vec![nft_id(&collection)]
+    );
+}
+
+#[test]
+fn test_nft_auction() {
+    let (e, tokens, users, collection, floor_oracle) = nft_setup();
+    nft_deposit(&e, &collection, &users.alice).assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, None, None),
+        d(400, 18),
+    )
+    .assert_success();
+
+    let start_auction = |e: &Env| {
+        e.oracle_call(
+            &users.bob,
+            price_data(&tokens, None, None),
+            PriceReceiverMsg::Execute {
+                actions: vec![Action::StartNftAuction {
+                    account_id: users.alice.account_id(),
+                    collection_id: collection.account_id(),
+                    token_id: NFT_TOKEN_ID.to_string(),
+                }],
+                constraints: None,
+            },
+        )
+    };
+    assert_failure(start_auction(&e), "The account is not liquidatable");
+
+    report_floor_price(&e, &floor_oracle, &collection, d(500, 18)).assert_success();
+    start_auction(&e).assert_success();
+    // The NFT stays in the collateral until the auction is settled.
+    assert_eq!(
+        get_nft_collateral(&e, &users.alice),
+        vec![nft_id(&collection)]
+    );
+    assert_failure(start_auction(&e), "The NFT is already being auctioned");
+
+    // The first bid has to cover the floor price with the haircut, i.e. 250 DAI.
+    let bob_balance = e.ft_balance_of(&users.bob, &tokens.ndai);
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &users.bob,
+        d(200, 18),
+        &nft_bid_msg(&collection),
+    )
+    .assert_success();
+    assert_eq!(e.ft_balance_of(&users.bob, &tokens.ndai), bob_balance);
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &users.bob,
+        d(300, 18),
+        &nft_bid_msg(&collection),
+    )
+    .assert_success();
+    assert_eq!(
+        e.ft_balance_of(&users.bob, &tokens.ndai),
+        bob_balance - d(300, 18)
+    );
+
+    // The next bid has to exceed the highest bid by 5%.
+    let charlie_balance = e.ft_balance_of(&users.charlie, &tokens.ndai);
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &users.charlie,
+        d(310, 18),
+        &nft_bid_msg(&collection),
+    )
+    .assert_success();
+    assert_eq!(
+        e.ft_balance_of(&users.charlie, &tokens.ndai),
+        charlie_balance
+    );
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &users.charlie,
+        d(320, 18),
+        &nft_bid_msg(&collection),
+    )
+    .assert_success();
+    // The outbid amount is transferred back.
+    assert_eq!(e.ft_balance_of(&users.bob, &tokens.ndai), bob_balance);
+    let auction: Option<NftAuction> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_nft_auction(collection.account_id(), NFT_TOKEN_ID.to_string()),
+        )
+        .unwrap_json();
+    let auction = auction.unwrap();
+    assert_eq!(auction.highest_bidder_id, Some(users.charlie.account_id()));
+    assert_eq!(auction.highest_bid, d(320, 18));
+
+    let settle = |e: &Env| {
+        users.bob.function_call(
+            e.contract
+                .contract
+                .settle_nft_auction(collection.account_id(), NFT_TOKEN_ID.to_string()),
+            MAX_GAS.0,
+            0,
+        )
+    };
+    assert_failure(settle(&e), "The auction hasn't ended");
+    e.skip_time(ONE_DAY_SEC);
+    let bob_supplied = find_asset(
+        &e.get_account(&users.bob).supplied,
+        &tokens.ndai.account_id(),
+    )
+    .balance;
+    settle(&e).assert_success();
+    assert!(get_nft_collateral(&e, &users.alice).is_empty());
+
+    // The liquidator receives 5% of the winning bid.
+    let bob_supplied = find_asset(
+        &e.get_account(&users.bob).supplied,
+        &tokens.ndai.account_id(),
+    )
+    .balance
+        - bob_supplied;
+    assert!(bob_supplied >= d(16, 18) && bob_supplied < d(17, 18));
+    // The rest of the proceeds repays the borrowed DAI of the liquidated account.
+    let account = e.get_account(&users.alice);
+    assert!(account.borrowed[0].balance > d(96, 18));
+    assert!(account.borrowed[0].balance < d(97, 18));
+    // The transfer to the plain account fails, so the NFT is added to the winner's collateral.
+    assert_eq!(
+        get_nft_collateral(&e, &users.charlie),
+        vec![nft_id(&collection)]
+    );
+}
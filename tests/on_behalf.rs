@@ -0,0 +1,117 @@
+mod setup;
+
+use crate::setup::*;
+use near_sdk::serde_json::json;
+use near_sdk_sim::{to_yocto, UserAccount};
+
+fn repay_on_behalf_msg(user: &UserAccount) -> String {
+    json!({
+        "RepayOnBehalf": {
+            "account_id": user.account_id(),
+        }
+    })
+    .to_string()
+}
+
+fn deposit_collateral_for_msg(user: &UserAccount) -> String {
+    json!({
+        "DepositCollateralFor": {
+            "account_id": user.account_id(),
+        }
+    })
+    .to_string()
+}
+
+#[test]
+fn test_repay_on_behalf() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.ndai, d(1000, 18))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.wnear,
+        price_data(&tokens, Some(100000), None),
+        d(10, 24),
+    )
+    .assert_success();
+
+    // The amount that exceeds the debt is refunded to the sender.
+    let bob_balance = e.ft_balance_of(&users.bob, &tokens.wnear);
+    e.contract_ft_transfer_call(
+        &tokens.wnear,
+        &users.bob,
+        d(20, 24),
+        &repay_on_behalf_msg(&users.alice),
+    )
+    .assert_success();
+    let repaid = bob_balance - e.ft_balance_of(&users.bob, &tokens.wnear);
+    assert!(repaid >= d(10, 24) && repaid < d(11, 24));
+
+    let account = e.get_account(&users.alice);
+    assert!(account.borrowed.is_empty());
+    assert_eq!(account.collateral[0].token_id, tokens.ndai.account_id());
+    assert_eq!(account.collateral[0].balance, d(1000, 18));
+    assert!(e.get_account(&users.bob).supplied.is_empty());
+
+    // The account doesn't borrow the asset, so the transfer is refunded.
+    let charlie_balance = e.ft_balance_of(&users.charlie, &tokens.ndai);
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &users.charlie,
+        d(100, 18),
+        &repay_on_behalf_msg(&users.alice),
+    );
+    assert_eq!(
+        e.ft_balance_of(&users.charlie, &tokens.ndai),
+        charlie_balance
+    );
+}
+
+#[test]
+fn test_deposit_collateral_for() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.ndai, d(1000, 18))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.wnear,
+        price_data(&tokens, Some(100000), None),
+        d(10, 24),
+    )
+    .assert_success();
+
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &users.bob,
+        d(100, 18),
+        &deposit_collateral_for_msg(&users.alice),
+    )
+    .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.collateral[0].balance, d(1100, 18));
+    assert!(e.get_account(&users.bob).supplied.is_empty());
+
+    // The target account has to use the asset as collateral already.
+    let bob_balance = e.ft_balance_of(&users.bob, &tokens.nusdc);
+    e.contract_ft_transfer_call(
+        &tokens.nusdc,
+        &users.bob,
+        d(100, 6),
+        &deposit_collateral_for_msg(&users.alice),
+    );
+    assert_eq!(e.ft_balance_of(&users.bob, &tokens.nusdc), bob_balance);
+    assert_eq!(e.get_account(&users.alice).collateral.len(), 1);
+
+    // The target account has to be registered.
+    let dave = e.near.create_user(a("dave.near"), to_yocto("100"));
+    let bob_balance = e.ft_balance_of(&users.bob, &tokens.ndai);
+    e.contract_ft_transfer_call(
+        &tokens.ndai,
+        &users.bob,
+        d(100, 18),
+        &deposit_collateral_for_msg(&dave),
+    );
+    assert_eq!(e.ft_balance_of(&users.bob, &tokens.ndai), bob_balance);
+}
@@ -0,0 +1,93 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use near_sdk::AccountId;
+use near_sdk_sim::{to_yocto, ExecutionResult, UserAccount};
+
+fn execute_for(
+    e: &Env,
+    operator: &UserAccount,
+    user: &UserAccount,
+    actions: Vec<Action>,
+) -> ExecutionResult {
+    operator.function_call(
+        e.contract
+            .contract
+            .execute_for(user.account_id(), actions, None),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_execute_for() {
+    let (e, tokens, users) = basic_setup();
+    let relayer = e.near.create_user(a("relayer.near"), to_yocto("100"));
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+
+    let decrease_collateral = || {
+        vec![Action::DecreaseCollateral(asset_amount(
+            &tokens.wnear,
+            d(10, 24),
+        ))]
+    };
+    assert_failure(
+        execute_for(&e, &relayer, &users.alice, decrease_collateral()),
+        "The operator is not approved by the account",
+    );
+
+    users
+        .alice
+        .function_call(
+            e.contract.contract.approve_operator(relayer.account_id()),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    let operators: Vec<AccountId> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_approved_operators(users.alice.account_id()),
+        )
+        .unwrap_json();
+    assert_eq!(operators, vec![relayer.account_id()]);
+
+    execute_for(&e, &relayer, &users.alice, decrease_collateral()).assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.supplied[0].balance, d(10, 24));
+    assert_eq!(account.collateral[0].balance, d(90, 24));
+
+    assert_failure(
+        execute_for(
+            &e,
+            &relayer,
+            &users.alice,
+            vec![Action::ForceClose {
+                account_id: users.bob.account_id(),
+            }],
+        ),
+        "The action can't be executed by the operator",
+    );
+    // The operator approved by one account can't act on behalf of another.
+    assert_failure(
+        execute_for(&e, &relayer, &users.bob, decrease_collateral()),
+        "The operator is not approved by the account",
+    );
+
+    users
+        .alice
+        .function_call(
+            e.contract.contract.revoke_operator(relayer.account_id()),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    assert_failure(
+        execute_for(&e, &relayer, &users.alice, decrease_collateral()),
+        "The operator is not approved by the account",
+    );
+}
@@ -0,0 +1,146 @@
+mod setup;
+
+use crate::setup::*;
+use near_sdk::env;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde_json;
+use near_sdk_sim::transaction::ExecutionStatus;
+use near_sdk_sim::ExecutionResult;
+
+fn borrow_msg(tokens: &Tokens, amount: Balance) -> PriceReceiverMsg {
+    PriceReceiverMsg::Execute {
+        actions: vec![Action::Borrow(asset_amount(&tokens.ndai, amount))],
+        constraints: None,
+    }
+}
+
+fn commit_oracle_msg(e: &Env, user: &UserAccount, msg: &PriceReceiverMsg) -> ExecutionResult {
+    let msg_hash = env::sha256(serde_json::to_string(msg).unwrap().as_bytes());
+    user.function_call(
+        e.contract.contract.commit_oracle_msg(Base64VecU8(msg_hash)),
+        DEFAULT_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+fn failure(res: ExecutionResult) -> String {
+    match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail"),
+    }
+}
+
+#[test]
+fn test_oracle_commitment() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    assert!(!e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .is_oracle_commitment_enabled(users.alice.account_id())
+        )
+        .unwrap_json::<bool>());
+
+    commit_oracle_msg(&e, &users.alice, &borrow_msg(&tokens, d(100, 18))).assert_success();
+    assert!(e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .is_oracle_commitment_enabled(users.alice.account_id())
+        )
+        .unwrap_json::<bool>());
+
+    // The oracle call with different actions is rejected.
+    let res = e.oracle_call(
+        &users.alice,
+        price_data(&tokens, Some(100000), None),
+        borrow_msg(&tokens, d(200, 18)),
+    );
+    assert!(failure(res).contains("The oracle call doesn't match the committed message"));
+
+    e.oracle_call(
+        &users.alice,
+        price_data(&tokens, Some(100000), None),
+        borrow_msg(&tokens, d(100, 18)),
+    )
+    .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.borrowed[0].balance, d(100, 18));
+
+    // The commitment is used, so the next call has to be committed again.
+    let res = e.oracle_call(
+        &users.alice,
+        price_data(&tokens, Some(100000), None),
+        borrow_msg(&tokens, d(100, 18)),
+    );
+    assert!(failure(res).contains("The oracle call has to be committed by the account"));
+
+    users
+        .alice
+        .function_call(
+            e.contract.contract.disable_oracle_commitments(),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    e.oracle_call(
+        &users.alice,
+        price_data(&tokens, Some(100000), None),
+        borrow_msg(&tokens, d(100, 18)),
+    )
+    .assert_success();
+}
+
+#[test]
+fn test_oracle_call_unregistered_sender() {
+    let (e, tokens, users) = basic_setup();
+
+    let res = e.oracle_call(
+        &users.eve,
+        price_data(&tokens, Some(100000), None),
+        borrow_msg(&tokens, d(100, 18)),
+    );
+    assert!(failure(res).contains("The sender of the oracle call is not registered"));
+}
+
+#[test]
+fn test_oracle_call_prices_cover_actions() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.ndai, d(1000, 18))
+        .assert_success();
+
+    // The borrowed asset has no price.
+    let res = e.borrow(
+        &users.alice,
+        &tokens.wnear,
+        price_data(&tokens, None, None),
+        d(1, 24),
+    );
+    assert!(failure(res).contains("The prices don't cover the asset"));
+
+    e.borrow(
+        &users.alice,
+        &tokens.wnear,
+        price_data(&tokens, Some(100000), None),
+        d(1, 24),
+    )
+    .assert_success();
+
+    // The borrowed assets of the account need prices as well.
+    let res = e.borrow(
+        &users.alice,
+        &tokens.nusdc,
+        price_data(&tokens, None, None),
+        d(1, 18),
+    );
+    assert!(failure(res).contains(&format!(
+        "The prices don't cover the asset {}",
+        tokens.wnear.account_id()
+    )));
+}
@@ -0,0 +1,122 @@
+mod setup;
+
+use crate::setup::*;
+use contract::{AssetOracleConfig, PriceSource, SourcePrice};
+use near_sdk_sim::transaction::ExecutionStatus;
+use near_sdk_sim::ExecutionResult;
+
+fn failure(res: ExecutionResult) -> String {
+    match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail"),
+    }
+}
+
+fn set_asset_oracle_config(
+    e: &Env,
+    tokens: &Tokens,
+    config: Option<AssetOracleConfig>,
+) -> ExecutionResult {
+    e.owner.function_call(
+        e.contract
+            .contract
+            .set_asset_oracle_config(tokens.wnear.account_id(), config),
+        DEFAULT_GAS.0,
+        1,
+    )
+}
+
+#[test]
+fn test_oracle_sources() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.ndai, d(1000, 18))
+        .assert_success();
+
+    let err = failure(set_asset_oracle_config(
+        &e,
+        &tokens,
+        Some(AssetOracleConfig {
+            sources: vec![],
+            max_staleness_sec: None,
+            max_deviation: None,
+        }),
+    ));
+    assert!(err.contains("The price sources can't be empty"));
+
+    // The prices of the oracle are ignored for the asset that is only priced by Pyth.
+    set_asset_oracle_config(
+        &e,
+        &tokens,
+        Some(AssetOracleConfig {
+            sources: vec![PriceSource::Pyth],
+            max_staleness_sec: None,
+            max_deviation: None,
+        }),
+    )
+    .assert_success();
+    let err = failure(e.borrow(
+        &users.alice,
+        &tokens.wnear,
+        price_data(&tokens, Some(100000), None),
+        d(10, 24),
+    ));
+    assert!(err.contains("The prices don't cover the asset"));
+
+    set_asset_oracle_config(
+        &e,
+        &tokens,
+        Some(AssetOracleConfig {
+            sources: vec![PriceSource::Oracle, PriceSource::Pyth],
+            max_staleness_sec: Some(60),
+            max_deviation: Some(500),
+        }),
+    )
+    .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.wnear,
+        price_data(&tokens, Some(100000), None),
+        d(10, 24),
+    )
+    .assert_success();
+    let source_price: Option<SourcePrice> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_source_price(tokens.wnear.account_id(), PriceSource::Oracle),
+        )
+        .unwrap_json();
+    assert_eq!(source_price.unwrap().price.multiplier, 100000);
+    let source_price: Option<SourcePrice> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_source_price(tokens.wnear.account_id(), PriceSource::Pyth),
+        )
+        .unwrap_json();
+    assert!(source_price.is_none());
+
+    let err = failure(
+        users.alice.function_call(
+            e.contract
+                .contract
+                .set_asset_oracle_config(tokens.wnear.account_id(), None),
+            DEFAULT_GAS.0,
+            1,
+        ),
+    );
+    assert!(err.contains("Not an owner"));
+    set_asset_oracle_config(&e, &tokens, None).assert_success();
+    let config: Option<AssetOracleConfig> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_asset_oracle_config(tokens.wnear.account_id()),
+        )
+        .unwrap_json();
+    assert!(config.is_none());
+}
@@ -0,0 +1,89 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::PartnerView;
+
+fn get_partners(e: &Env) -> Vec<PartnerView> {
+    e.near
+        .view_method_call(e.contract.contract.get_partners())
+        .unwrap_json()
+}
+
+#[test]
+fn test_partner_whitelist() {
+    let (e, _tokens, users) = basic_setup();
+
+    assert_failure(
+        users.alice.function_call(
+            e.contract.contract.register_position_hook(),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not a whitelisted partner",
+    );
+    assert_failure(
+        users.bob.function_call(
+            e.contract.contract.add_partner(users.alice.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+    e.owner
+        .function_call(
+            e.contract.contract.add_partner(users.alice.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    users
+        .alice
+        .function_call(
+            e.contract.contract.register_position_hook(),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    let partners = get_partners(&e);
+    assert_eq!(partners.len(), 1);
+    assert_eq!(partners[0].partner_id, users.alice.account_id());
+    assert!(partners[0].is_hook_registered);
+
+    e.owner
+        .function_call(
+            e.contract.contract.remove_partner(users.alice.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    assert!(get_partners(&e).is_empty());
+}
+
+#[test]
+fn test_partner_hook_failure_is_ignored() {
+    let (e, tokens, users) = basic_setup();
+
+    e.owner
+        .function_call(
+            e.contract.contract.add_partner(users.alice.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    users
+        .alice
+        .function_call(
+            e.contract.contract.register_position_hook(),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    // The partner account has no contract, so the hook fails without affecting the deposit.
+    let amount = d(100, 18);
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, amount, "")
+        .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.supplied[0].balance, amount);
+}
@@ -0,0 +1,175 @@
+mod setup;
+
+use crate::setup::*;
+use common::{DurationSec, ONE_YOCTO};
+use contract::{OperationalState, PROPOSAL_GRACE_PERIOD_SEC};
+
+fn set_proposal_timelock(e: &Env, proposal_timelock_sec: DurationSec) {
+    let mut config = e.get_config();
+    config.proposal_timelock_sec = proposal_timelock_sec;
+    e.owner
+        .function_call(
+            e.contract.contract.update_config(config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+}
+
+#[test]
+fn test_proposal_timelock() {
+    let (e, tokens, users) = basic_setup();
+    set_proposal_timelock(&e, ONE_DAY_SEC);
+
+    let mut asset_config = e.get_asset(&tokens.ndai).config;
+    asset_config.reserve_ratio = 5000;
+    assert_failure(
+        e.owner.function_call(
+            e.contract
+                .contract
+                .update_asset(tokens.ndai.account_id(), asset_config.clone()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The change has to be proposed",
+    );
+
+    e.propose(ProposalKind::UpdateAsset {
+        token_id: tokens.ndai.account_id(),
+        asset_config: Box::new(asset_config),
+    })
+    .assert_success();
+    let proposals = e.get_pending_proposals();
+    assert_eq!(proposals.len(), 1);
+    let state: OperationalState = e
+        .near
+        .view_method_call(e.contract.contract.get_operational_state())
+        .unwrap_json();
+    assert_eq!(state.pending_proposal_ids, vec![0]);
+    assert_eq!(proposals[0].proposal_id, 0);
+    assert_eq!(
+        proposals[0].executable_timestamp,
+        proposals[0].proposed_timestamp + to_nano(ONE_DAY_SEC)
+    );
+
+    assert_failure(
+        e.execute_proposal(0),
+        "The proposal timelock has not passed",
+    );
+    e.skip_time(ONE_DAY_SEC);
+    // Anyone can execute the proposal after the timelock.
+    users
+        .bob
+        .function_call(
+            e.contract.contract.execute_proposal(0),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    assert_eq!(e.get_asset(&tokens.ndai).config.reserve_ratio, 5000);
+    assert!(e.get_pending_proposals().is_empty());
+    assert_failure(e.execute_proposal(0), "Proposal not found");
+}
+
+#[test]
+fn test_cancel_proposal() {
+    let (e, _tokens, users) = basic_setup();
+    set_proposal_timelock(&e, ONE_DAY_SEC);
+
+    let mut config = e.get_config();
+    config.oracle_account_id = users.bob.account_id();
+    e.propose(ProposalKind::UpdateConfig {
+        config: Box::new(config),
+    })
+    .assert_success();
+
+    assert_failure(
+        users.bob.function_call(
+            e.contract.contract.cancel_proposal(0),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+    e.owner
+        .function_call(
+            e.contract.contract.cancel_proposal(0),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    assert!(e.get_pending_proposals().is_empty());
+
+    e.skip_time(ONE_DAY_SEC);
+    assert_failure(e.execute_proposal(0), "Proposal not found");
+    assert_eq!(e.get_config().oracle_account_id, a(ORACLE_ID));
+}
+
+#[test]
+fn test_cancel_proposal_after_timelock() {
+    let (e, _tokens, users) = basic_setup();
+    set_proposal_timelock(&e, ONE_DAY_SEC);
+
+    let mut config = e.get_config();
+    config.oracle_account_id = users.bob.account_id();
+    e.propose(ProposalKind::UpdateConfig {
+        config: Box::new(config),
+    })
+    .assert_success();
+
+    e.skip_time(ONE_DAY_SEC);
+    e.owner
+        .function_call(
+            e.contract.contract.cancel_proposal(0),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    assert_failure(e.execute_proposal(0), "Proposal not found");
+    assert_eq!(e.get_config().oracle_account_id, a(ORACLE_ID));
+}
+
+#[test]
+fn test_proposal_expiration() {
+    let (e, tokens, _users) = basic_setup();
+    set_proposal_timelock(&e, ONE_DAY_SEC);
+
+    let mut asset_config = e.get_asset(&tokens.ndai).config;
+    asset_config.reserve_ratio = 5000;
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .schedule_asset_config_update(tokens.ndai.account_id(), asset_config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    let proposals = e.get_pending_proposals();
+    assert_eq!(
+        proposals[0].expiration_timestamp,
+        proposals[0].executable_timestamp + to_nano(PROPOSAL_GRACE_PERIOD_SEC)
+    );
+
+    e.skip_time(ONE_DAY_SEC + PROPOSAL_GRACE_PERIOD_SEC);
+    assert_failure(
+        e.owner.function_call(
+            e.contract.contract.execute_scheduled_update(0),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The proposal has expired",
+    );
+    assert_ne!(e.get_asset(&tokens.ndai).config.reserve_ratio, 5000);
+
+    // The expired proposal can still be cancelled to clean it up.
+    e.owner
+        .function_call(
+            e.contract.contract.cancel_proposal(0),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    assert!(e.get_pending_proposals().is_empty());
+}
@@ -0,0 +1,128 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::PythFeed;
+use near_sdk::AccountId;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+use std::collections::HashMap;
+
+const WNEAR_PRICE_ID: &str = "c415de8d2eba7db216527dff4b60e8f3a5311c740dadb233e13e12547e226750";
+
+fn wnear_feed() -> PythFeed {
+    PythFeed {
+        price_id: WNEAR_PRICE_ID.to_string(),
+        token_decimals: 24,
+    }
+}
+
+fn execute_with_pyth(e: &Env, user: &UserAccount) -> ExecutionResult {
+    user.function_call(
+        e.contract
+            .contract
+            .execute_with_pyth(vec![], None, "504e4155".to_string()),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_pyth_feeds() {
+    let (e, tokens, users) = basic_setup();
+
+    assert_failure(
+        users.alice.function_call(
+            e.contract
+                .contract
+                .set_pyth_feed(tokens.wnear.account_id(), wnear_feed()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+    assert_failure(
+        e.owner.function_call(
+            e.contract.contract.set_pyth_feed(
+                tokens.wnear.account_id(),
+                PythFeed {
+                    price_id: "0x1234".to_string(),
+                    token_decimals: 24,
+                },
+            ),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Invalid Pyth price identifier",
+    );
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .set_pyth_feed(tokens.wnear.account_id(), wnear_feed()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    let feeds: HashMap<AccountId, PythFeed> = e
+        .near
+        .view_method_call(e.contract.contract.get_pyth_feeds())
+        .unwrap_json();
+    assert_eq!(
+        feeds.get(&tokens.wnear.account_id()).unwrap().price_id,
+        WNEAR_PRICE_ID
+    );
+
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .remove_pyth_feed(tokens.wnear.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    assert_failure(
+        e.owner.function_call(
+            e.contract
+                .contract
+                .remove_pyth_feed(tokens.wnear.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The Pyth feed is not set",
+    );
+}
+
+#[test]
+fn test_execute_with_pyth_not_enabled() {
+    let (e, tokens, users) = basic_setup();
+
+    assert_failure(
+        execute_with_pyth(&e, &users.alice),
+        "The Pyth oracle is not set",
+    );
+
+    let mut config = e.get_config();
+    config.pyth_oracle_id = Some(a("pyth-oracle.near"));
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+    assert_failure(
+        execute_with_pyth(&e, &users.alice),
+        "The Pyth feeds are not set",
+    );
+
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .set_pyth_feed(tokens.wnear.account_id(), wnear_feed()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    assert_failure(
+        execute_with_pyth(&e, &users.dude),
+        "Account is not registered",
+    );
+}
@@ -0,0 +1,85 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::TwapPair;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+fn set_twap_pair(
+    e: &Env,
+    user: &UserAccount,
+    token: &UserAccount,
+    quote_token: &UserAccount,
+    max_deviation: u32,
+) -> ExecutionResult {
+    user.function_call(
+        e.contract.contract.set_twap_pair(
+            token.account_id(),
+            0,
+            quote_token.account_id(),
+            max_deviation,
+            3600,
+        ),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_set_twap_pair() {
+    let (e, tokens, users) = basic_setup();
+
+    assert_failure(
+        set_twap_pair(&e, &users.alice, &tokens.wnear, &tokens.ndai, 500),
+        "Not an owner",
+    );
+    assert_failure(
+        set_twap_pair(&e, &e.owner, &tokens.wnear, &tokens.wnear, 500),
+        "The asset and the quote asset should be different",
+    );
+    assert_failure(
+        set_twap_pair(&e, &e.owner, &tokens.wnear, &tokens.ndai, 0),
+        "The max deviation can't be 0",
+    );
+    set_twap_pair(&e, &e.owner, &tokens.wnear, &tokens.ndai, 500).assert_success();
+
+    let twap_pair: Option<TwapPair> = e
+        .near
+        .view_method_call(e.contract.contract.get_twap_pair(tokens.wnear.account_id()))
+        .unwrap_json();
+    let twap_pair = twap_pair.unwrap();
+    assert_eq!(twap_pair.quote_token_id, tokens.ndai.account_id());
+    assert_eq!(twap_pair.max_deviation, 500);
+    assert!(twap_pair.snapshots.is_empty());
+
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .remove_twap_pair(tokens.wnear.account_id()),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    let twap_pair: Option<TwapPair> = e
+        .near
+        .view_method_call(e.contract.contract.get_twap_pair(tokens.wnear.account_id()))
+        .unwrap_json();
+    assert!(twap_pair.is_none());
+}
+
+#[test]
+fn test_twap_check_skipped_without_snapshots() {
+    let (e, tokens, users) = basic_setup();
+
+    set_twap_pair(&e, &e.owner, &tokens.wnear, &tokens.ndai, 500).assert_success();
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+}
@@ -0,0 +1,105 @@
+mod setup;
+
+use crate::setup::*;
+use contract::RepaySwap;
+use near_sdk::json_types::U128;
+use near_sdk::Balance;
+use near_sdk_sim::transaction::ExecutionStatus;
+
+fn swap_route(tokens: &Tokens, min_amount_out: Balance) -> Vec<SwapAction> {
+    vec![SwapAction {
+        pool_id: 0,
+        token_in: tokens.neth.account_id(),
+        amount_in: None,
+        token_out: tokens.ndai.account_id(),
+        min_amount_out: U128(min_amount_out),
+    }]
+}
+
+#[test]
+fn test_repay_from_supplied_failed_swap_is_returned() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+    let supply_amount = d(1, 18);
+    e.contract_ft_transfer_call(&tokens.neth, &users.alice, supply_amount, "")
+        .assert_success();
+
+    e.repay_from_supplied(
+        &users.alice,
+        &tokens.neth,
+        supply_amount,
+        &tokens.ndai,
+        swap_route(&tokens, d(100, 18)),
+    )
+    .assert_success();
+    let logs = get_logs(&e.near.borrow_runtime());
+    assert!(logs
+        .iter()
+        .any(|log| log.contains("\"event\":\"repay_swap_started\"")
+            && log.contains("\"min_amount_out\":\"100000000000000000000\"")));
+    // The exchange doesn't exist, so the swap fails and the amount is returned.
+    assert!(logs
+        .iter()
+        .any(|log| log.contains("\"event\":\"repay_swap_failed\"")));
+
+    let repay_swap: Option<RepaySwap> = e
+        .near
+        .view_method_call(e.contract.contract.get_repay_swap(users.alice.account_id()))
+        .unwrap_json();
+    assert!(repay_swap.is_none());
+
+    let account = e.get_account(&users.alice);
+    let supplied = account
+        .supplied
+        .iter()
+        .find(|asset| asset.token_id == tokens.neth.account_id())
+        .unwrap();
+    assert_eq!(supplied.balance, supply_amount);
+    assert!(account.borrowed[0].balance >= d(200, 18));
+
+    // There is no repay swap to roll back.
+    assert_failure(
+        users.bob.function_call(
+            e.contract
+                .contract
+                .clear_expired_repay_swap(users.alice.account_id()),
+            DEFAULT_GAS.0,
+            0,
+        ),
+        "The account doesn't have an expired repay swap",
+    );
+}
+
+#[test]
+fn test_repay_from_supplied_without_borrowed() {
+    let (e, tokens, users) = basic_setup();
+
+    let supply_amount = d(1, 18);
+    e.contract_ft_transfer_call(&tokens.neth, &users.alice, supply_amount, "")
+        .assert_success();
+
+    let res = e.repay_from_supplied(
+        &users.alice,
+        &tokens.neth,
+        supply_amount,
+        &tokens.ndai,
+        swap_route(&tokens, d(100, 18)),
+    );
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail without borrowed asset"),
+    };
+    assert!(err.contains("Borrowed asset not found"));
+
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.supplied[0].balance, supply_amount);
+}
@@ -0,0 +1,59 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::BigDecimal;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+fn update_asset(e: &Env, token: &UserAccount, asset_config: AssetConfig) -> ExecutionResult {
+    e.owner.function_call(
+        e.contract
+            .contract
+            .update_asset(token.account_id(), asset_config),
+        DEFAULT_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_reserve_only_asset() {
+    let (e, tokens, users) = basic_setup();
+
+    let mut asset_config = e.get_asset(&tokens.ndai).config;
+    asset_config.reserve_only = true;
+    assert_failure(
+        update_asset(&e, &tokens.ndai, asset_config.clone()),
+        "The reserve-only asset can't be used as collateral",
+    );
+    asset_config.can_use_as_collateral = false;
+    update_asset(&e, &tokens.ndai, asset_config).assert_success();
+
+    // The deposits of the accounts are refunded.
+    let balance = e.ft_balance_of(&users.bob, &tokens.ndai);
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), "")
+        .assert_success();
+    assert_eq!(e.ft_balance_of(&users.bob, &tokens.ndai), balance);
+    assert_eq!(e.get_asset(&tokens.ndai).supplied.balance, 0);
+
+    let reserved = e.get_asset(&tokens.ndai).reserved;
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), DEPOSIT_TO_RESERVE)
+        .assert_success();
+    assert_eq!(e.get_asset(&tokens.ndai).reserved, reserved + d(100, 18));
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    assert_failure(
+        e.borrow(
+            &users.alice,
+            &tokens.ndai,
+            price_data(&tokens, Some(100000), None),
+            d(10, 18),
+        ),
+        "The reserve-only asset can't be borrowed",
+    );
+
+    let asset = e.get_asset(&tokens.ndai);
+    assert_eq!(asset.borrowed.balance, 0);
+    assert_eq!(asset.borrow_apr, BigDecimal::zero());
+    assert_eq!(asset.supply_apr, BigDecimal::zero());
+}
@@ -0,0 +1,103 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::{ReserveStaking, ReserveStakingConfig};
+use near_sdk::json_types::U128;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+/// The nETH stands for the liquid staking token.
+fn enable_reserve_staking(e: &Env, tokens: &Tokens) {
+    let mut config = e.get_config();
+    config.reserve_staking = Some(ReserveStakingConfig {
+        token_id: tokens.wnear.account_id(),
+        staking_token_id: tokens.neth.account_id(),
+        max_staked_ratio: 3000,
+    });
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+}
+
+fn stake_reserve(e: &Env, tokens: &Tokens, user: &UserAccount, amount: u128) -> ExecutionResult {
+    user.function_call(
+        e.contract.contract.stake_reserve(
+            U128(amount),
+            vec![SwapAction {
+                pool_id: 0,
+                token_in: tokens.wnear.account_id(),
+                amount_in: None,
+                token_out: tokens.neth.account_id(),
+                min_amount_out: U128(d(1, 18)),
+            }],
+            None,
+        ),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+fn get_reserve_staking(e: &Env) -> ReserveStaking {
+    e.near
+        .view_method_call(e.contract.contract.get_reserve_staking())
+        .unwrap_json()
+}
+
+#[test]
+fn test_stake_reserve_validation() {
+    let (e, tokens, users) = basic_setup();
+
+    assert_failure(
+        stake_reserve(&e, &tokens, &e.owner, d(1000, 24)),
+        "The reserve staking is not enabled",
+    );
+    enable_reserve_staking(&e, &tokens);
+    assert_failure(
+        stake_reserve(&e, &tokens, &users.alice, d(1000, 24)),
+        "Not an owner",
+    );
+    assert_failure(
+        stake_reserve(&e, &tokens, &e.owner, d(4000, 24)),
+        "The staked part of the reserve would exceed the limit",
+    );
+    assert_failure(
+        users.alice.function_call(
+            e.contract.contract.unstake_reserve(None, vec![], None),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Invalid staking token amount",
+    );
+}
+
+#[test]
+fn test_stake_reserve_refunded() {
+    let (e, tokens, _users) = basic_setup();
+    enable_reserve_staking(&e, &tokens);
+
+    // The exchange is not deployed, so the swap input is refunded to the reserve.
+    let reserved = e.get_asset(&tokens.wnear).reserved;
+    stake_reserve(&e, &tokens, &e.owner, d(1000, 24)).assert_success();
+    assert_eq!(e.get_asset(&tokens.wnear).reserved, reserved);
+    let reserve_staking = get_reserve_staking(&e);
+    assert_eq!(reserve_staking.staked_amount, 0);
+    assert!(reserve_staking.swap.is_none());
+}
+
+#[test]
+fn test_reserve_staking_output_requires_exchange() {
+    let (e, tokens, users) = basic_setup();
+    enable_reserve_staking(&e, &tokens);
+
+    let balance = e.ft_balance_of(&users.alice, &tokens.neth);
+    e.contract_ft_transfer_call(
+        &tokens.neth,
+        &users.alice,
+        d(1, 18),
+        "\"ReserveStakingOutput\"",
+    )
+    .assert_success();
+    // The transfer is rejected and refunded.
+    assert_eq!(e.ft_balance_of(&users.alice, &tokens.neth), balance);
+    assert_eq!(get_reserve_staking(&e).staking_token_balance, 0);
+}
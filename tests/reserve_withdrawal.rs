@@ -0,0 +1,105 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use near_sdk::json_types::U128;
+use near_sdk::Balance;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+fn announce_reserve_withdrawal(
+    e: &Env,
+    token: &UserAccount,
+    amount: Balance,
+    receiver: &UserAccount,
+) -> ExecutionResult {
+    e.owner.function_call(
+        e.contract.contract.announce_reserve_withdrawal(
+            token.account_id(),
+            U128(amount),
+            receiver.account_id(),
+        ),
+        DEFAULT_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+fn withdraw_reserve(
+    e: &Env,
+    token: &UserAccount,
+    amount: Balance,
+    receiver: &UserAccount,
+) -> ExecutionResult {
+    e.owner.function_call(
+        e.contract.contract.withdraw_reserve(
+            token.account_id(),
+            U128(amount),
+            receiver.account_id(),
+        ),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_withdraw_reserve() {
+    let (e, tokens, users) = basic_setup();
+
+    let mut config = e.get_config();
+    config.reserve_withdrawal_delay_sec = ONE_DAY_SEC;
+    config.max_reserve_withdrawal_ratio_per_epoch = 1000;
+    e.owner
+        .function_call(
+            e.contract.contract.update_config(config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    assert_failure(
+        withdraw_reserve(&e, &tokens.ndai, d(500, 18), &users.charlie),
+        "The reserve withdrawal has to be announced",
+    );
+    assert_failure(
+        users.charlie.function_call(
+            e.contract.contract.announce_reserve_withdrawal(
+                tokens.ndai.account_id(),
+                U128(d(500, 18)),
+                users.charlie.account_id(),
+            ),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+
+    announce_reserve_withdrawal(&e, &tokens.ndai, d(1100, 18), &users.charlie).assert_success();
+    assert_failure(
+        withdraw_reserve(&e, &tokens.ndai, d(1100, 18), &users.charlie),
+        "The announced reserve withdrawal is not unlocked yet",
+    );
+
+    e.skip_time(ONE_DAY_SEC);
+    // Only 10% of the reserve can be withdrawn in one epoch.
+    assert_failure(
+        withdraw_reserve(&e, &tokens.ndai, d(1100, 18), &users.charlie),
+        "The reserve withdrawal limit of the epoch is exceeded",
+    );
+    assert_failure(
+        withdraw_reserve(&e, &tokens.ndai, d(500, 18), &users.bob),
+        "The reserve withdrawal doesn't match the announced withdrawal",
+    );
+
+    let balance = e.ft_balance_of(&users.charlie, &tokens.ndai);
+    withdraw_reserve(&e, &tokens.ndai, d(500, 18), &users.charlie).assert_success();
+    assert_eq!(
+        e.ft_balance_of(&users.charlie, &tokens.ndai),
+        balance + d(500, 18)
+    );
+    assert_eq!(e.get_asset(&tokens.ndai).reserved, d(9500, 18));
+
+    // The announced withdrawal can only be used once.
+    assert_failure(
+        withdraw_reserve(&e, &tokens.ndai, d(500, 18), &users.charlie),
+        "The reserve withdrawal has to be announced",
+    );
+}
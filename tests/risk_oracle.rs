@@ -0,0 +1,110 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::RiskOracleBounds;
+use near_sdk::json_types::U128;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+fn update_volatility_ratio(
+    e: &Env,
+    user: &UserAccount,
+    token: &UserAccount,
+    volatility_ratio: u32,
+) -> ExecutionResult {
+    user.function_call(
+        e.contract.contract.update_asset_risk_parameters(
+            token.account_id(),
+            Some(volatility_ratio),
+            None,
+            None,
+            None,
+        ),
+        DEFAULT_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_risk_oracle_updates() {
+    let (e, tokens, users) = basic_setup();
+
+    let mut config = e.get_config();
+    config.risk_oracle_account_id = Some(users.charlie.account_id());
+    e.owner
+        .function_call(
+            e.contract.contract.update_config(config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    // The volatility ratio of nDAI is 95%.
+    assert_failure(
+        update_volatility_ratio(&e, &users.charlie, &tokens.ndai, 9000),
+        "The asset doesn't have the risk oracle bounds",
+    );
+
+    let asset_config = e.get_asset(&tokens.ndai).config;
+    let bounds = RiskOracleBounds {
+        min_volatility_ratio: 8000,
+        max_volatility_ratio: 9500,
+        max_volatility_ratio_change: 500,
+        min_target_utilization: asset_config.target_utilization,
+        max_target_utilization: asset_config.target_utilization,
+        min_target_utilization_rate: asset_config.target_utilization_rate,
+        max_target_utilization_rate: asset_config.target_utilization_rate,
+        min_max_utilization_rate: asset_config.max_utilization_rate,
+        max_max_utilization_rate: U128(asset_config.max_utilization_rate.0 * 2),
+    };
+    assert_failure(
+        users.charlie.function_call(
+            e.contract
+                .contract
+                .set_risk_oracle_bounds(tokens.ndai.account_id(), Some(bounds.clone())),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .set_risk_oracle_bounds(tokens.ndai.account_id(), Some(bounds)),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    assert_failure(
+        update_volatility_ratio(&e, &users.bob, &tokens.ndai, 9000),
+        "Not a risk oracle",
+    );
+    assert_failure(
+        update_volatility_ratio(&e, &users.charlie, &tokens.ndai, 8500),
+        "The volatility ratio change exceeds the risk oracle bounds",
+    );
+    update_volatility_ratio(&e, &users.charlie, &tokens.ndai, 9000).assert_success();
+    assert_eq!(e.get_asset(&tokens.ndai).config.volatility_ratio, 9000);
+    update_volatility_ratio(&e, &users.charlie, &tokens.ndai, 8500).assert_success();
+    assert_failure(
+        update_volatility_ratio(&e, &users.charlie, &tokens.ndai, 7900),
+        "The volatility ratio is out of the risk oracle bounds",
+    );
+
+    assert_failure(
+        users.charlie.function_call(
+            e.contract.contract.update_asset_risk_parameters(
+                tokens.ndai.account_id(),
+                None,
+                Some(9000),
+                None,
+                None,
+            ),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "The target utilization is out of the risk oracle bounds",
+    );
+}
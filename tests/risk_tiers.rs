@@ -0,0 +1,101 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::{AssetTier, RiskTierRule};
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+fn set_risk_tier(e: &Env, token: &UserAccount, risk_tier: AssetTier) {
+    let mut asset_config = e.get_asset(token).config;
+    asset_config.risk_tier = Some(risk_tier);
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .update_asset(token.account_id(), asset_config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+}
+
+fn increase_collateral(e: &Env, user: &UserAccount, token: &UserAccount) -> ExecutionResult {
+    user.function_call(
+        e.contract.contract.execute(
+            vec![Action::IncreaseCollateral(AssetAmount {
+                token_id: token.account_id(),
+                amount: None,
+                max_amount: None,
+            })],
+            None,
+        ),
+        MAX_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_risk_tier_policy() {
+    let (e, tokens, users) = basic_setup();
+
+    set_risk_tier(&e, &tokens.wnear, AssetTier::LongTail);
+    set_risk_tier(&e, &tokens.neth, AssetTier::Mid);
+    set_risk_tier(&e, &tokens.ndai, AssetTier::BlueChip);
+
+    let rule = RiskTierRule {
+        collateral_tier: AssetTier::LongTail,
+        borrowable_tiers: vec![AssetTier::LongTail, AssetTier::Mid],
+    };
+    let mut config = e.get_config();
+    config.risk_tier_policy = vec![rule.clone(), rule];
+    assert_failure(
+        e.owner
+            .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1),
+        "Duplicate risk tier policy rule",
+    );
+    let mut config = e.get_config();
+    config.risk_tier_policy = vec![RiskTierRule {
+        collateral_tier: AssetTier::LongTail,
+        borrowable_tiers: vec![AssetTier::LongTail, AssetTier::Mid],
+    }];
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    assert_failure(
+        e.borrow(
+            &users.alice,
+            &tokens.ndai,
+            price_data(&tokens, Some(100000), None),
+            d(50, 18),
+        ),
+        "The collateral wrap.near can't back the borrowed dai.near under the risk tier policy",
+    );
+    e.borrow(
+        &users.alice,
+        &tokens.neth,
+        price_data(&tokens, Some(100000), Some(100000)),
+        d(1, 18),
+    )
+    .assert_success();
+
+    // The mid tier collateral is not restricted, but the long-tail collateral can't be added
+    // to the account that borrows a blue-chip asset.
+    e.supply_to_collateral(&users.bob, &tokens.neth, d(1, 18))
+        .assert_success();
+    e.borrow(
+        &users.bob,
+        &tokens.ndai,
+        price_data(&tokens, None, Some(100000)),
+        d(50, 18),
+    )
+    .assert_success();
+    e.contract_ft_transfer_call(&tokens.wnear, &users.bob, d(1, 24), "")
+        .assert_success();
+    assert_failure(
+        increase_collateral(&e, &users.bob, &tokens.wnear),
+        "The collateral wrap.near can't back the borrowed dai.near under the risk tier policy",
+    );
+}
@@ -0,0 +1,177 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::{AssetReserveView, FarmId, Role};
+use near_sdk::json_types::U128;
+use near_sdk::AccountId;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+fn grant_role(e: &Env, role: Role, user: &UserAccount) -> ExecutionResult {
+    e.owner.function_call(
+        e.contract.contract.grant_role(role, user.account_id()),
+        DEFAULT_GAS.0,
+        ONE_YOCTO,
+    )
+}
+
+#[test]
+fn test_grant_and_revoke_roles() {
+    let (e, _tokens, users) = basic_setup();
+
+    assert_failure(
+        users.bob.function_call(
+            e.contract
+                .contract
+                .grant_role(Role::RiskAdmin, users.bob.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+
+    grant_role(&e, Role::RiskAdmin, &users.bob).assert_success();
+    grant_role(&e, Role::FarmManager, &users.bob).assert_success();
+    assert_failure(
+        grant_role(&e, Role::FarmManager, &users.bob),
+        "The account already has the role",
+    );
+    grant_role(&e, Role::EmergencyAdmin, &users.charlie).assert_success();
+
+    let roles: Vec<Role> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_account_roles(users.bob.account_id()),
+        )
+        .unwrap_json();
+    assert_eq!(roles, vec![Role::RiskAdmin, Role::FarmManager]);
+    let guardians: Vec<AccountId> = e
+        .near
+        .view_method_call(e.contract.contract.get_guardians())
+        .unwrap_json();
+    assert_eq!(guardians, vec![users.charlie.account_id()]);
+
+    e.owner
+        .function_call(
+            e.contract
+                .contract
+                .revoke_role(Role::RiskAdmin, users.bob.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+    let risk_admins: Vec<AccountId> = e
+        .near
+        .view_method_call(e.contract.contract.get_role_members(Role::RiskAdmin))
+        .unwrap_json();
+    assert!(risk_admins.is_empty());
+}
+
+#[test]
+fn test_farm_manager() {
+    let (e, tokens, users) = basic_setup();
+    grant_role(&e, Role::FarmManager, &users.bob).assert_success();
+
+    users
+        .bob
+        .function_call(
+            e.contract.contract.add_asset_farm_reward(
+                FarmId::Supplied(tokens.ndai.account_id()),
+                e.booster_token.account_id(),
+                U128(d(100, 18)),
+                U128(d(100, 18)),
+                U128(d(3000, 18)),
+            ),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    // The farm manager can't change the assets.
+    let asset_config = e.get_asset(&tokens.ndai).config;
+    assert_failure(
+        users.bob.function_call(
+            e.contract
+                .contract
+                .add_asset(users.bob.account_id(), asset_config),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner",
+    );
+    assert_failure(
+        users.bob.function_call(
+            e.contract
+                .contract
+                .update_asset_flags(tokens.ndai.account_id(), false),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        ),
+        "Not an owner or an account with the required role",
+    );
+}
+
+#[test]
+fn test_fee_collector() {
+    let (e, tokens, users) = basic_setup();
+
+    e.owner
+        .function_call(
+            e.contract.contract.update_asset_rates(
+                tokens.ndai.account_id(),
+                None,
+                None,
+                None,
+                None,
+                Some(2500),
+            ),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .assert_success();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(10000, 24))
+        .assert_success();
+    e.borrow_and_withdraw(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(1000, 18),
+    )
+    .assert_success();
+    e.skip_time(ONE_DAY_SEC * 30);
+
+    let claim_protocol_fees = |user: &UserAccount| -> ExecutionResult {
+        user.function_call(
+            e.contract
+                .contract
+                .claim_protocol_fees(tokens.ndai.account_id(), None),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+    };
+    assert_failure(
+        claim_protocol_fees(&users.charlie),
+        "Not an owner or an account with the required role",
+    );
+
+    grant_role(&e, Role::FeeCollector, &users.charlie).assert_success();
+    claim_protocol_fees(&users.charlie).assert_success();
+
+    let reserves: Vec<AssetReserveView> = e
+        .near
+        .view_method_call(e.contract.contract.get_reserves())
+        .unwrap_json();
+    let reserve = reserves
+        .into_iter()
+        .find(|reserve| reserve.token_id == tokens.ndai.account_id())
+        .unwrap();
+    assert!(reserve.withdrawn_protocol_fees > 0);
+    assert_eq!(reserve.pending_protocol_fees, 0);
+
+    let account = e.get_account(&users.charlie);
+    assert_eq!(account.supplied[0].token_id, tokens.ndai.account_id());
+    assert_eq!(account.supplied[0].balance, reserve.withdrawn_protocol_fees);
+}
@@ -0,0 +1,116 @@
+mod setup;
+
+use crate::setup::*;
+use near_sdk_sim::transaction::ExecutionStatus;
+use near_sdk_sim::{to_yocto, UserAccount};
+
+fn router_setup() -> (Env, Tokens, Users, UserAccount) {
+    let (e, tokens, users) = basic_setup();
+    let router = e.near.create_user(a(ROUTER_ID), to_yocto("100"));
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.supply_to_collateral(&users.bob, &tokens.wnear, d(50, 24))
+        .assert_success();
+
+    (e, tokens, users, router)
+}
+
+#[test]
+fn test_router_execute() {
+    let (e, tokens, users, router) = router_setup();
+    e.approve_router(&users.alice).assert_success();
+    e.approve_router(&users.bob).assert_success();
+
+    e.router_execute(
+        &router,
+        vec![
+            (
+                users.alice.account_id(),
+                vec![Action::DecreaseCollateral(asset_amount(
+                    &tokens.wnear,
+                    d(10, 24),
+                ))],
+            ),
+            (
+                users.bob.account_id(),
+                vec![Action::DecreaseCollateral(asset_amount(
+                    &tokens.wnear,
+                    d(20, 24),
+                ))],
+            ),
+        ],
+    )
+    .assert_success();
+
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.supplied[0].balance, d(10, 24));
+    assert_eq!(account.collateral[0].balance, d(90, 24));
+    let account = e.get_account(&users.bob);
+    assert_eq!(account.supplied[0].balance, d(20, 24));
+    assert_eq!(account.collateral[0].balance, d(30, 24));
+}
+
+#[test]
+fn test_router_execute_with_prices() {
+    let (e, tokens, users, router) = router_setup();
+    e.approve_router(&users.alice).assert_success();
+    e.approve_router(&users.bob).assert_success();
+
+    e.router_execute_with_prices(
+        &router,
+        price_data(&tokens, Some(100000), None),
+        vec![
+            (
+                users.alice.account_id(),
+                vec![Action::Borrow(asset_amount(&tokens.ndai, d(200, 18)))],
+            ),
+            (
+                users.bob.account_id(),
+                vec![Action::Borrow(asset_amount(&tokens.ndai, d(100, 18)))],
+            ),
+        ],
+    )
+    .assert_success();
+
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.borrowed[0].balance, d(200, 18));
+    let account = e.get_account(&users.bob);
+    assert_eq!(account.borrowed[0].balance, d(100, 18));
+}
+
+#[test]
+fn test_router_not_approved_by_account() {
+    let (e, tokens, users, router) = router_setup();
+    e.approve_router(&users.alice).assert_success();
+
+    let res = e.router_execute(
+        &router,
+        vec![
+            (
+                users.alice.account_id(),
+                vec![Action::DecreaseCollateral(asset_amount(
+                    &tokens.wnear,
+                    d(10, 24),
+                ))],
+            ),
+            (
+                users.bob.account_id(),
+                vec![Action::DecreaseCollateral(asset_amount(
+                    &tokens.wnear,
+                    d(20, 24),
+                ))],
+            ),
+        ],
+    );
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with the router approval error"),
+    };
+    assert!(err.contains("The router is not approved by the account"));
+
+    // The entire batch is reverted.
+    let account = e.get_account(&users.alice);
+    assert!(account.supplied.is_empty());
+    assert_eq!(account.collateral[0].balance, d(100, 24));
+}
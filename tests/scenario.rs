@@ -0,0 +1,60 @@
+mod setup;
+
+use crate::setup::*;
+
+/// Alice and Charlie borrow NEAR against USDC. NEAR goes up and Bob liquidates both of them, then
+/// NEAR goes up again and Bob liquidates Alice once more.
+#[test]
+fn test_cascading_liquidations() {
+    let (e, tokens, users) = basic_setup();
+
+    Scenario::new(&e, &tokens)
+        .supply_collateral(&users.alice, &tokens.nusdc, d(1000, 6))
+        .borrow_and_withdraw(&users.alice, &tokens.wnear, d(50, 24))
+        .supply_collateral(&users.charlie, &tokens.nusdc, d(1000, 6))
+        .borrow_and_withdraw(&users.charlie, &tokens.wnear, d(50, 24))
+        .deposit(&users.bob, &tokens.wnear, d(100, 24))
+        .expect_failure("The liquidation account is not at risk")
+        .liquidate(
+            &users.bob,
+            &users.alice,
+            &[(&tokens.wnear, d(49, 23))],
+            &[(&tokens.nusdc, d(60, 18))],
+        )
+        // Assuming 2% discount for 4.9 NEAR at 12$.
+        .set_wnear_price(120000)
+        .liquidate(
+            &users.bob,
+            &users.alice,
+            &[(&tokens.wnear, d(49, 23))],
+            &[(&tokens.nusdc, d(60, 18))],
+        )
+        .liquidate(
+            &users.bob,
+            &users.charlie,
+            &[(&tokens.wnear, d(49, 23))],
+            &[(&tokens.nusdc, d(60, 18))],
+        )
+        .assert_borrowed(&users.charlie, &tokens.wnear, d(451, 23))
+        .assert_collateral(&users.charlie, &tokens.nusdc, d(940, 18))
+        // Taking too much collateral for the repaid debt is rejected.
+        .expect_failure("Not enough balances repaid")
+        .liquidate(
+            &users.bob,
+            &users.alice,
+            &[(&tokens.wnear, d(49, 23))],
+            &[(&tokens.nusdc, d(100, 18))],
+        )
+        .set_wnear_price(130000)
+        .liquidate(
+            &users.bob,
+            &users.alice,
+            &[(&tokens.wnear, d(49, 23))],
+            &[(&tokens.nusdc, d(65, 18))],
+        )
+        .assert_borrowed(&users.alice, &tokens.wnear, d(402, 23))
+        .assert_collateral(&users.alice, &tokens.nusdc, d(875, 18))
+        .assert_supplied(&users.bob, &tokens.wnear, d(853, 23))
+        .assert_supplied(&users.bob, &tokens.nusdc, d(185, 18))
+        .assert_invariants();
+}
@@ -0,0 +1,160 @@
+use super::*;
+use contract::{HealthBucket, Page};
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::serde::de::DeserializeOwned;
+use near_sdk::PendingContractTx;
+use std::path::Path;
+
+/// The number of the exported items per page and per import call.
+const FORK_BATCH_SIZE: usize = 20;
+
+/// The environment variable with the directory of the exported mainnet state for the fork tests.
+pub const FORK_STATE_DIR_ENV: &str = "BURROW_FORK_STATE_DIR";
+
+/// The state exported with `export_assets`, `export_farms` and `export_accounts`, where every
+/// item is the base64-encoded Borsh of the exported value, and the prices to value it.
+pub struct ForkState {
+    pub assets: Vec<Base64VecU8>,
+    pub farms: Vec<Base64VecU8>,
+    pub accounts: Vec<Base64VecU8>,
+    pub prices: Option<PriceData>,
+}
+
+fn read_json<T: DeserializeOwned>(path: &Path) -> T {
+    let data = std::fs::read_to_string(path)
+        .unwrap_or_else(|err| panic!("Failed to read {}: {}", path.display(), err));
+    serde_json::from_str(&data)
+        .unwrap_or_else(|err| panic!("Failed to parse {}: {}", path.display(), err))
+}
+
+/// Returns the items of all pages of the export.
+fn export_pages(
+    e: &Env,
+    export: impl Fn(Option<String>, Option<u64>) -> PendingContractTx,
+) -> Vec<Base64VecU8> {
+    let mut items = vec![];
+    let mut token = None;
+    loop {
+        let page: Page<Base64VecU8> = e
+            .owner
+            .function_call(export(token, Some(FORK_BATCH_SIZE as u64)), MAX_GAS.0, 0)
+            .unwrap_json();
+        items.extend(page.items);
+        token = match page.next_token {
+            Some(next_token) => Some(next_token),
+            None => return items,
+        };
+    }
+}
+
+impl ForkState {
+    /// Exports the whole state of the deployment page by page.
+    pub fn export(e: &Env) -> Self {
+        Self {
+            assets: export_pages(e, |token, limit| {
+                e.contract.contract.export_assets(token, limit)
+            }),
+            farms: export_pages(e, |token, limit| {
+                e.contract.contract.export_farms(token, limit)
+            }),
+            accounts: export_pages(e, |token, limit| {
+                e.contract.contract.export_accounts(token, limit)
+            }),
+            prices: None,
+        }
+    }
+
+    /// Loads the exported state from the directory. The `assets.json`, `farms.json` and
+    /// `accounts.json` files contain the JSON arrays of the items of all pages of the exports,
+    /// and the optional `prices.json` contains the price data of the assets.
+    pub fn load(dir: &Path) -> Self {
+        let prices_path = dir.join("prices.json");
+        Self {
+            assets: read_json(&dir.join("assets.json")),
+            farms: read_json(&dir.join("farms.json")),
+            accounts: read_json(&dir.join("accounts.json")),
+            prices: if prices_path.exists() {
+                Some(read_json(&prices_path))
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Loads the exported mainnet state from the directory in `BURROW_FORK_STATE_DIR`, or returns
+    /// `None` if it's not set.
+    pub fn load_from_env() -> Option<Self> {
+        std::env::var(FORK_STATE_DIR_ENV)
+            .ok()
+            .map(|dir| Self::load(Path::new(&dir)))
+    }
+}
+
+impl Env {
+    /// Imports the exported state into the deployment in batches, the assets and the farms first,
+    /// since the accounts are indexed by them. The deployment has to be built with the
+    /// `state-import` feature.
+    pub fn import_state(&self, state: &ForkState) {
+        for items in state.assets.chunks(FORK_BATCH_SIZE) {
+            self.owner
+                .function_call(
+                    self.contract.contract.import_assets(items.to_vec()),
+                    MAX_GAS.0,
+                    0,
+                )
+                .assert_success();
+        }
+        for items in state.farms.chunks(FORK_BATCH_SIZE) {
+            self.owner
+                .function_call(
+                    self.contract.contract.import_farms(items.to_vec()),
+                    MAX_GAS.0,
+                    0,
+                )
+                .assert_success();
+        }
+        for items in state.accounts.chunks(FORK_BATCH_SIZE) {
+            self.owner
+                .function_call(
+                    self.contract.contract.import_accounts(items.to_vec()),
+                    MAX_GAS.0,
+                    0,
+                )
+                .assert_success();
+        }
+    }
+
+    /// Returns the health distribution of the accounts at the given prices, to compare the
+    /// positions before and after a rehearsed change. The distribution is requested page by page
+    /// and the buckets of the pages are added up.
+    pub fn get_health_distribution(&self, prices: Option<PriceData>) -> Vec<HealthBucket> {
+        let num_accounts: u32 = self
+            .near
+            .view_method_call(self.contract.contract.get_num_accounts())
+            .unwrap_json();
+        let mut buckets: Vec<HealthBucket> = vec![];
+        let mut from_index = 0;
+        loop {
+            let page: Vec<HealthBucket> = self
+                .near
+                .view_method_call(self.contract.contract.get_health_distribution(
+                    prices.clone(),
+                    Some(from_index),
+                    Some(FORK_BATCH_SIZE as u64),
+                ))
+                .unwrap_json();
+            if buckets.is_empty() {
+                buckets = page;
+            } else {
+                for (bucket, page_bucket) in buckets.iter_mut().zip(page) {
+                    bucket.num_accounts += page_bucket.num_accounts;
+                    bucket.total_debt = bucket.total_debt + page_bucket.total_debt;
+                }
+            }
+            from_index += FORK_BATCH_SIZE as u64;
+            if from_index >= num_accounts as u64 {
+                return buckets;
+            }
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use super::*;
+use std::collections::HashMap;
+
+/// The checked-in gas budgets of the canonical operations in TGas.
+const GAS_BUDGETS_JSON: &str = include_str!("../gas_budgets.json");
+
+/// Returns the gas burnt by the transaction and all its receipts, including the calls of the
+/// token and the oracle contracts.
+pub fn total_gas_burnt(res: &ExecutionResult) -> Gas {
+    let receipts_gas_burnt: u64 = res
+        .promise_results()
+        .iter()
+        .flatten()
+        .map(|outcome| outcome.gas_burnt().0)
+        .sum();
+    Gas(res.gas_burnt().0 + receipts_gas_burnt)
+}
+
+/// Asserts that the operation succeeded and burnt no more gas than its budget in
+/// `tests/gas_budgets.json`, so the gas regressions fail the local runs.
+pub fn assert_gas_budget(operation: &str, res: &ExecutionResult) {
+    res.assert_success();
+    let budgets: HashMap<String, u64> =
+        serde_json::from_str(GAS_BUDGETS_JSON).expect("Invalid gas budgets");
+    let budget = budgets
+        .get(operation)
+        .unwrap_or_else(|| panic!("Missing the gas budget of {}", operation));
+    let gas_burnt = total_gas_burnt(res);
+    let tgas_burnt = gas_burnt.0 as f64 / Gas::ONE_TERA.0 as f64;
+    println!("{}: {:.2} TGas of {} TGas", operation, tgas_burnt, budget);
+    assert!(
+        gas_burnt.0 <= budget * Gas::ONE_TERA.0,
+        "The gas burnt by {} is {:.2} TGas, over the budget of {} TGas",
+        operation,
+        tgas_burnt,
+        budget
+    );
+}
@@ -7,17 +7,30 @@ use near_sdk::json_types::U128;
 use near_sdk::serde_json::json;
 use near_sdk::{env, serde_json, AccountId, Balance, Gas, Timestamp};
 use near_sdk_sim::runtime::GenesisConfig;
+use near_sdk_sim::transaction::ExecutionStatus;
 use near_sdk_sim::{
-    deploy, init_simulator, to_yocto, ContractAccount, ExecutionResult, UserAccount,
+    deploy, init_simulator, to_yocto, ContractAccount, ExecutionResult, UserAccount, ViewResult,
 };
 
 pub use contract::{
-    AccountDetailedView, Action, AssetAmount, AssetConfig, AssetDetailedView, Config,
-    ContractContract as BurrowlandContract, PriceReceiverMsg, TokenReceiverMsg,
+    AccountDetailedView, Action, AssetAmount, AssetConfig, AssetDetailedView, AssetInvariants,
+    AutoRepayOrder, Config, ContractContract as BurrowlandContract, ExecuteConstraints,
+    OnActionsFailure, PausableAction, PriceReceiverMsg, Proposal, ProposalKind, SwapAction,
+    TokenReceiverMsg, WithdrawTimelock,
 };
 use contract::{AssetFarmView, AssetView, FarmId};
 use near_sdk_sim::runtime::RuntimeStandalone;
 use test_oracle::ContractContract as OracleContract;
+pub use test_oracle::{OracleCall, OracleFailure, RecordedPrice};
+
+#[cfg(feature = "state-import")]
+mod fork;
+mod gas;
+mod scenario;
+#[cfg(feature = "state-import")]
+pub use fork::*;
+pub use gas::*;
+pub use scenario::*;
 
 near_sdk_sim::lazy_static_include::lazy_static_include_bytes! {
     BURROWLAND_WASM_BYTES => "res/burrowland.wasm",
@@ -50,6 +63,12 @@ pub const ORACLE_ID: &str = "oracle.near";
 pub const BURROWLAND_ID: &str = "burrowland.near";
 pub const BOOSTER_TOKEN_ID: &str = "token.burrowland.near";
 pub const OWNER_ID: &str = "owner.near";
+/// The exchange account is not deployed, so all leverage swaps fail and get refunded.
+pub const REF_EXCHANGE_ID: &str = "ref-finance.near";
+pub const ROUTER_ID: &str = "router.near";
+pub const AURORA_ENGINE_ID: &str = "aurora.near";
+/// The tip paid from the maintenance pool for every keeper call that did the work.
+pub const KEEPER_TIP: Balance = 10u128.pow(22);
 
 pub const DEFAULT_GAS: Gas = Gas(Gas::ONE_TERA.0 * 15);
 pub const MAX_GAS: Gas = Gas(Gas::ONE_TERA.0 * 300);
@@ -166,6 +185,37 @@ impl Env {
                     maximum_staking_duration_sec: 31536000,
                     x_booster_multiplier_at_maximum_staking_duration: 40000,
                     force_closing_enabled: true,
+                    flash_loan_fee: 9,
+                    ref_exchange_id: Some(a(REF_EXCHANGE_ID)),
+                    router_ids: vec![a(ROUTER_ID)],
+                    roles: Default::default(),
+                    proposal_timelock_sec: 0,
+                    max_total_borrowed_value: None,
+                    reserve_withdrawal_delay_sec: 0,
+                    max_reserve_withdrawal_ratio_per_epoch: 10000,
+                    multisig_members: vec![],
+                    multisig_threshold: 0,
+                    multisig_methods: vec![],
+                    risk_oracle_account_id: None,
+                    max_volatility_ratio_change_per_day: None,
+                    asset_rates_event_interval_sec: None,
+                    prices_used_event_interval_sec: None,
+                    heavy_events_enabled: true,
+                    max_sponsored_storage_bytes: None,
+                    max_num_farms_per_account: None,
+                    aurora_engine_id: Some(a(AURORA_ENGINE_ID)),
+                    keeper_tip: Some(U128(KEEPER_TIP)),
+                    croncat_manager_id: None,
+                    ref_boost_farm_id: None,
+                    reserve_staking: None,
+                    intents_contract_ids: vec![],
+                    pyth_oracle_id: None,
+                    activity_tracking_enabled: false,
+                    risk_tier_policy: vec![],
+                    max_num_actions: None,
+                    min_borrow_health_bps: None,
+                    canary: None,
+                    wnear_token_id: None,
                 }
             )
         );
@@ -242,14 +292,29 @@ impl Env {
                         max_utilization_rate: U128(1000000000039724853136740579),
                         volatility_ratio: 2000,
                         extra_decimals: 0,
-                        can_deposit: true,
-                        can_withdraw: true,
+                        paused_actions: PausableAction::Borrow.mask(),
                         can_use_as_collateral: false,
-                        can_borrow: false,
                         net_tvl_multiplier: 10000,
+                        supply_cap: None,
+                        borrow_cap: None,
+                        isolation_debt_ceiling: None,
+                        borrowable_in_isolation: false,
+                        permissioned: false,
+                        frozen_rate: None,
+                        fee_on_transfer: false,
+                        min_borrow_amount: None,
+                        risk_tier: None,
+                        can_flash_loan: true,
+                        flash_loan_fee: None,
+                        protocol_fee_ratio: 0,
+                        collateral_cap: None,
+                        reserve_only: false,
+                        oracle_price_decimals: None,
+                        collateral_only: false,
+                        dust_threshold: None,
                     },
                 ),
-                DEFAULT_GAS.0,
+                MAX_GAS.0,
                 ONE_YOCTO,
             )
             .assert_success();
@@ -265,14 +330,29 @@ impl Env {
                         max_utilization_rate: U128(1000000000039724853136740579),
                         volatility_ratio: 6000,
                         extra_decimals: 0,
-                        can_deposit: true,
-                        can_withdraw: true,
+                        paused_actions: 0,
                         can_use_as_collateral: true,
-                        can_borrow: true,
                         net_tvl_multiplier: 10000,
+                        supply_cap: None,
+                        borrow_cap: None,
+                        isolation_debt_ceiling: None,
+                        borrowable_in_isolation: false,
+                        permissioned: false,
+                        frozen_rate: None,
+                        fee_on_transfer: false,
+                        min_borrow_amount: None,
+                        risk_tier: None,
+                        can_flash_loan: true,
+                        flash_loan_fee: None,
+                        protocol_fee_ratio: 0,
+                        collateral_cap: None,
+                        reserve_only: false,
+                        oracle_price_decimals: None,
+                        collateral_only: false,
+                        dust_threshold: None,
                     },
                 ),
-                DEFAULT_GAS.0,
+                MAX_GAS.0,
                 ONE_YOCTO,
             )
             .assert_success();
@@ -288,14 +368,29 @@ impl Env {
                         max_utilization_rate: U128(1000000000039724853136740579),
                         volatility_ratio: 9500,
                         extra_decimals: 0,
-                        can_deposit: true,
-                        can_withdraw: true,
+                        paused_actions: 0,
                         can_use_as_collateral: true,
-                        can_borrow: true,
                         net_tvl_multiplier: 10000,
+                        supply_cap: None,
+                        borrow_cap: None,
+                        isolation_debt_ceiling: None,
+                        borrowable_in_isolation: false,
+                        permissioned: false,
+                        frozen_rate: None,
+                        fee_on_transfer: false,
+                        min_borrow_amount: None,
+                        risk_tier: None,
+                        can_flash_loan: true,
+                        flash_loan_fee: None,
+                        protocol_fee_ratio: 0,
+                        collateral_cap: None,
+                        reserve_only: false,
+                        oracle_price_decimals: None,
+                        collateral_only: false,
+                        dust_threshold: None,
                     },
                 ),
-                DEFAULT_GAS.0,
+                MAX_GAS.0,
                 ONE_YOCTO,
             )
             .assert_success();
@@ -311,14 +406,29 @@ impl Env {
                         max_utilization_rate: U128(1000000000039724853136740579),
                         volatility_ratio: 9500,
                         extra_decimals: 12,
-                        can_deposit: true,
-                        can_withdraw: true,
+                        paused_actions: 0,
                         can_use_as_collateral: true,
-                        can_borrow: true,
                         net_tvl_multiplier: 10000,
+                        supply_cap: None,
+                        borrow_cap: None,
+                        isolation_debt_ceiling: None,
+                        borrowable_in_isolation: false,
+                        permissioned: false,
+                        frozen_rate: None,
+                        fee_on_transfer: false,
+                        min_borrow_amount: None,
+                        risk_tier: None,
+                        can_flash_loan: true,
+                        flash_loan_fee: None,
+                        protocol_fee_ratio: 0,
+                        collateral_cap: None,
+                        reserve_only: false,
+                        oracle_price_decimals: None,
+                        collateral_only: false,
+                        dust_threshold: None,
                     },
                 ),
-                DEFAULT_GAS.0,
+                MAX_GAS.0,
                 ONE_YOCTO,
             )
             .assert_success();
@@ -334,14 +444,29 @@ impl Env {
                         max_utilization_rate: U128(1000000000039724853136740579),
                         volatility_ratio: 9500,
                         extra_decimals: 12,
-                        can_deposit: true,
-                        can_withdraw: true,
+                        paused_actions: 0,
                         can_use_as_collateral: true,
-                        can_borrow: true,
                         net_tvl_multiplier: 10000,
+                        supply_cap: None,
+                        borrow_cap: None,
+                        isolation_debt_ceiling: None,
+                        borrowable_in_isolation: false,
+                        permissioned: false,
+                        frozen_rate: None,
+                        fee_on_transfer: false,
+                        min_borrow_amount: None,
+                        risk_tier: None,
+                        can_flash_loan: true,
+                        flash_loan_fee: None,
+                        protocol_fee_ratio: 0,
+                        collateral_cap: None,
+                        reserve_only: false,
+                        oracle_price_decimals: None,
+                        collateral_only: false,
+                        dust_threshold: None,
                     },
                 ),
-                DEFAULT_GAS.0,
+                MAX_GAS.0,
                 ONE_YOCTO,
             )
             .assert_success();
@@ -357,14 +482,29 @@ impl Env {
                         max_utilization_rate: U128(1000000000039724853136740579),
                         volatility_ratio: 6000,
                         extra_decimals: 0,
-                        can_deposit: true,
-                        can_withdraw: true,
+                        paused_actions: 0,
                         can_use_as_collateral: true,
-                        can_borrow: true,
                         net_tvl_multiplier: 10000,
+                        supply_cap: None,
+                        borrow_cap: None,
+                        isolation_debt_ceiling: None,
+                        borrowable_in_isolation: false,
+                        permissioned: false,
+                        frozen_rate: None,
+                        fee_on_transfer: false,
+                        min_borrow_amount: None,
+                        risk_tier: None,
+                        can_flash_loan: true,
+                        flash_loan_fee: None,
+                        protocol_fee_ratio: 0,
+                        collateral_cap: None,
+                        reserve_only: false,
+                        oracle_price_decimals: None,
+                        collateral_only: false,
+                        dust_threshold: None,
                     },
                 ),
-                DEFAULT_GAS.0,
+                MAX_GAS.0,
                 ONE_YOCTO,
             )
             .assert_success();
@@ -453,6 +593,29 @@ impl Env {
         asset.unwrap()
     }
 
+    /// Asserts that the invariants of all the assets hold.
+    pub fn assert_invariants(&self, tokens: &Tokens) {
+        let token_ids = vec![
+            tokens.wnear.account_id(),
+            tokens.neth.account_id(),
+            tokens.ndai.account_id(),
+            tokens.nusdt.account_id(),
+            tokens.nusdc.account_id(),
+            self.booster_token.account_id(),
+        ];
+        let invariants: Vec<AssetInvariants> = self
+            .near
+            .view_method_call(self.contract.contract.assert_invariants(token_ids))
+            .unwrap_json();
+        for asset_invariants in invariants {
+            assert!(
+                asset_invariants.is_valid,
+                "The invariants are broken: {:?}",
+                asset_invariants
+            );
+        }
+    }
+
     pub fn get_asset_farm(&self, farm_id: FarmId) -> AssetFarmView {
         let asset_farm: Option<serde_json::value::Value> = self
             .near
@@ -468,11 +631,37 @@ impl Env {
     pub fn get_account(&self, user: &UserAccount) -> AccountDetailedView {
         let account: Option<AccountDetailedView> = self
             .near
-            .view_method_call(self.contract.contract.get_account(user.account_id()))
+            .view_method_call(self.contract.contract.get_account(user.account_id(), None))
             .unwrap_json();
         account.unwrap()
     }
 
+    pub fn get_account_with_values(&self, user: &UserAccount) -> AccountDetailedView {
+        let account: Option<AccountDetailedView> = self
+            .near
+            .view_method_call(
+                self.contract
+                    .contract
+                    .get_account(user.account_id(), Some(true)),
+            )
+            .unwrap_json();
+        account.unwrap()
+    }
+
+    pub fn simulate_execute(
+        &self,
+        user: &UserAccount,
+        actions: Vec<Action>,
+        price_data: Option<PriceData>,
+    ) -> ViewResult {
+        self.near
+            .view_method_call(self.contract.contract.simulate_execute(
+                user.account_id(),
+                actions,
+                price_data,
+            ))
+    }
+
     pub fn storage_balance_of(&self, user: &UserAccount) -> Option<StorageBalance> {
         self.near
             .view_method_call(self.contract.contract.storage_balance_of(user.account_id()))
@@ -489,6 +678,147 @@ impl Env {
             .unwrap_json()
     }
 
+    pub fn ft_balance_of(&self, user: &UserAccount, token: &UserAccount) -> Balance {
+        let balance: U128 = self
+            .near
+            .view(
+                token.account_id(),
+                "ft_balance_of",
+                &json!({ "account_id": user.account_id() })
+                    .to_string()
+                    .into_bytes(),
+            )
+            .unwrap_json();
+        balance.0
+    }
+
+    pub fn withdraw(
+        &self,
+        user: &UserAccount,
+        token: &UserAccount,
+        amount: Balance,
+    ) -> ExecutionResult {
+        user.function_call(
+            self.contract
+                .contract
+                .execute(vec![Action::Withdraw(asset_amount(token, amount))], None),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn withdraw_and_swap(
+        &self,
+        user: &UserAccount,
+        token: &UserAccount,
+        amount: Balance,
+        swap_route: Vec<SwapAction>,
+        min_received: Balance,
+    ) -> ExecutionResult {
+        user.function_call(
+            self.contract.contract.execute(
+                vec![Action::WithdrawAndSwap {
+                    asset_amount: asset_amount(token, amount),
+                    swap_route,
+                    min_received: min_received.into(),
+                    dex_id: None,
+                }],
+                None,
+            ),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn repay_from_supplied(
+        &self,
+        user: &UserAccount,
+        supplied_token: &UserAccount,
+        amount: Balance,
+        borrowed_token: &UserAccount,
+        route: Vec<SwapAction>,
+    ) -> ExecutionResult {
+        user.function_call(
+            self.contract.contract.execute(
+                vec![Action::RepayFromSupplied {
+                    supplied_token: supplied_token.account_id(),
+                    amount: Some(amount.into()),
+                    borrowed_token: borrowed_token.account_id(),
+                    route,
+                    dex_id: None,
+                }],
+                None,
+            ),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn retry_withdraw(&self, user: &UserAccount, token: &UserAccount) -> ExecutionResult {
+        user.function_call(
+            self.contract.contract.retry_withdraw(token.account_id()),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn set_withdraw_timelock(
+        &self,
+        user: &UserAccount,
+        token: &UserAccount,
+        threshold: Balance,
+        delay_sec: DurationSec,
+    ) -> ExecutionResult {
+        user.function_call(
+            self.contract.contract.set_withdraw_timelock(
+                token.account_id(),
+                U128::from(threshold),
+                delay_sec,
+            ),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn remove_withdraw_timelock(
+        &self,
+        user: &UserAccount,
+        token: &UserAccount,
+    ) -> ExecutionResult {
+        user.function_call(
+            self.contract
+                .contract
+                .remove_withdraw_timelock(token.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn announce_withdraw(
+        &self,
+        user: &UserAccount,
+        token: &UserAccount,
+        amount: Balance,
+    ) -> ExecutionResult {
+        user.function_call(
+            self.contract
+                .contract
+                .announce_withdraw(token.account_id(), U128::from(amount)),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn get_withdraw_timelocks(&self, user: &UserAccount) -> Vec<WithdrawTimelock> {
+        self.near
+            .view_method_call(
+                self.contract
+                    .contract
+                    .get_withdraw_timelocks(user.account_id()),
+            )
+            .unwrap_json()
+    }
+
     pub fn supply_to_collateral(
         &self,
         user: &UserAccount,
@@ -505,6 +835,27 @@ impl Env {
                     amount: None,
                     max_amount: None,
                 })],
+                on_failure: None,
+            })
+            .unwrap(),
+        )
+    }
+
+    pub fn deposit_with_actions(
+        &self,
+        user: &UserAccount,
+        token: &UserAccount,
+        amount: Balance,
+        actions: Vec<Action>,
+        on_failure: Option<OnActionsFailure>,
+    ) -> ExecutionResult {
+        self.contract_ft_transfer_call(
+            &token,
+            &user,
+            amount,
+            &serde_json::to_string(&TokenReceiverMsg::Execute {
+                actions,
+                on_failure,
             })
             .unwrap(),
         )
@@ -527,6 +878,139 @@ impl Env {
         )
     }
 
+    /// Calls the contract with the prices recorded in the test oracle for the assets.
+    pub fn oracle_call_recorded(
+        &self,
+        user: &UserAccount,
+        asset_ids: Option<Vec<String>>,
+        msg: PriceReceiverMsg,
+    ) -> ExecutionResult {
+        user.function_call(
+            self.oracle.contract.oracle_call_recorded(
+                self.contract.user_account.account_id(),
+                asset_ids,
+                serde_json::to_string(&msg).unwrap(),
+            ),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    /// Makes multiple oracle calls to the contract in one transaction.
+    pub fn oracle_batch_call(
+        &self,
+        user: &UserAccount,
+        calls: Vec<(PriceData, PriceReceiverMsg)>,
+    ) -> ExecutionResult {
+        let calls = calls
+            .into_iter()
+            .map(|(price_data, msg)| OracleCall {
+                receiver_id: self.contract.user_account.account_id(),
+                price_data,
+                msg: serde_json::to_string(&msg).unwrap(),
+            })
+            .collect();
+        user.function_call(
+            self.oracle.contract.oracle_batch_call(calls),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn set_oracle_prices(&self, prices: Vec<RecordedPrice>) {
+        self.owner
+            .function_call(self.oracle.contract.set_prices(prices), DEFAULT_GAS.0, 0)
+            .assert_success();
+    }
+
+    pub fn set_oracle_failure(&self, failure: Option<OracleFailure>) {
+        self.owner
+            .function_call(self.oracle.contract.set_failure(failure), DEFAULT_GAS.0, 0)
+            .assert_success();
+    }
+
+    pub fn add_guardian(&self, guardian: &UserAccount) -> ExecutionResult {
+        self.owner.function_call(
+            self.contract.contract.add_guardian(guardian.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn pause(
+        &self,
+        user: &UserAccount,
+        token_ids: Option<Vec<AccountId>>,
+        actions: Option<Vec<PausableAction>>,
+    ) -> ExecutionResult {
+        user.function_call(
+            self.contract.contract.pause(token_ids, actions),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn propose(&self, kind: ProposalKind) -> ExecutionResult {
+        self.owner.function_call(
+            self.contract.contract.propose(kind),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn execute_proposal(&self, proposal_id: u32) -> ExecutionResult {
+        self.owner.function_call(
+            self.contract.contract.execute_proposal(proposal_id),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn get_pending_proposals(&self) -> Vec<Proposal> {
+        self.near
+            .view_method_call(self.contract.contract.get_pending_proposals())
+            .unwrap_json()
+    }
+
+    pub fn get_config(&self) -> Config {
+        self.near
+            .view_method_call(self.contract.contract.get_config())
+            .unwrap_json()
+    }
+
+    pub fn approve_router(&self, user: &UserAccount) -> ExecutionResult {
+        user.function_call(
+            self.contract.contract.approve_router(a(ROUTER_ID)),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn router_execute(
+        &self,
+        router: &UserAccount,
+        batch: Vec<(AccountId, Vec<Action>)>,
+    ) -> ExecutionResult {
+        router.function_call(
+            self.contract.contract.router_execute(batch),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn router_execute_with_prices(
+        &self,
+        router: &UserAccount,
+        price_data: PriceData,
+        batch: Vec<(AccountId, Vec<Action>)>,
+    ) -> ExecutionResult {
+        self.oracle_call(
+            router,
+            price_data,
+            PriceReceiverMsg::RouterExecute { batch },
+        )
+    }
+
     pub fn borrow(
         &self,
         user: &UserAccount,
@@ -539,6 +1023,25 @@ impl Env {
             price_data,
             PriceReceiverMsg::Execute {
                 actions: vec![Action::Borrow(asset_amount(token, amount))],
+                constraints: None,
+            },
+        )
+    }
+
+    pub fn borrow_with_constraints(
+        &self,
+        user: &UserAccount,
+        token: &UserAccount,
+        price_data: PriceData,
+        amount: Balance,
+        constraints: ExecuteConstraints,
+    ) -> ExecutionResult {
+        self.oracle_call(
+            &user,
+            price_data,
+            PriceReceiverMsg::Execute {
+                actions: vec![Action::Borrow(asset_amount(token, amount))],
+                constraints: Some(constraints),
             },
         )
     }
@@ -558,6 +1061,24 @@ impl Env {
                     Action::Borrow(asset_amount(token, amount)),
                     Action::Withdraw(asset_amount(token, amount)),
                 ],
+                constraints: None,
+            },
+        )
+    }
+
+    pub fn borrow_to_wallet(
+        &self,
+        user: &UserAccount,
+        token: &UserAccount,
+        price_data: PriceData,
+        amount: Balance,
+    ) -> ExecutionResult {
+        self.oracle_call(
+            &user,
+            price_data,
+            PriceReceiverMsg::Execute {
+                actions: vec![Action::BorrowAndWithdraw(asset_amount(token, amount))],
+                constraints: None,
             },
         )
     }
@@ -579,6 +1100,7 @@ impl Env {
                     in_assets,
                     out_assets,
                 }],
+                constraints: None,
             },
         )
     }
@@ -596,6 +1118,7 @@ impl Env {
                 actions: vec![Action::ForceClose {
                     account_id: liquidation_user.account_id(),
                 }],
+                constraints: None,
             },
         )
     }
@@ -604,6 +1127,101 @@ impl Env {
         self.near.borrow_runtime_mut().cur_block.block_timestamp += to_nano(seconds);
     }
 
+    pub fn leverage_up(
+        &self,
+        user: &UserAccount,
+        price_data: PriceData,
+        collateral_token: &UserAccount,
+        borrow_token: &UserAccount,
+        target_leverage: u32,
+        swap_route: Vec<SwapAction>,
+    ) -> ExecutionResult {
+        self.oracle_call(
+            &user,
+            price_data,
+            PriceReceiverMsg::Execute {
+                actions: vec![Action::LeverageUp {
+                    collateral_token: collateral_token.account_id(),
+                    borrow_token: borrow_token.account_id(),
+                    target_leverage,
+                    swap_route,
+                    dex_id: None,
+                }],
+                constraints: None,
+            },
+        )
+    }
+
+    pub fn add_auto_repay_order(
+        &self,
+        user: &UserAccount,
+        token: &UserAccount,
+        trigger_health: u32,
+        max_amount: Balance,
+        tip: Balance,
+    ) -> ExecutionResult {
+        user.function_call(
+            self.contract.contract.add_auto_repay_order(
+                token.account_id(),
+                trigger_health,
+                U128::from(max_amount),
+                U128::from(tip),
+            ),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
+    pub fn execute_auto_repay_order(
+        &self,
+        user: &UserAccount,
+        order_user: &UserAccount,
+        price_data: PriceData,
+        order_id: u32,
+    ) -> ExecutionResult {
+        self.oracle_call(
+            &user,
+            price_data,
+            PriceReceiverMsg::Execute {
+                actions: vec![Action::ExecuteAutoRepayOrder {
+                    account_id: order_user.account_id(),
+                    order_id,
+                }],
+                constraints: None,
+            },
+        )
+    }
+
+    pub fn get_auto_repay_orders(&self, user: &UserAccount) -> Vec<AutoRepayOrder> {
+        self.near
+            .view_method_call(
+                self.contract
+                    .contract
+                    .get_auto_repay_orders(user.account_id()),
+            )
+            .unwrap_json()
+    }
+
+    pub fn flash_loan(
+        &self,
+        user: &UserAccount,
+        token: &UserAccount,
+        amount: Balance,
+        receiver: &UserAccount,
+        msg: &str,
+    ) -> ExecutionResult {
+        user.function_call(
+            self.contract.contract.flash_loan(
+                token.account_id(),
+                U128::from(amount),
+                receiver.account_id(),
+                msg.to_string(),
+            ),
+            MAX_GAS.0,
+            ONE_YOCTO,
+        )
+    }
+
     pub fn account_stake_booster(
         &self,
         user: &UserAccount,
@@ -863,6 +1481,8 @@ pub fn av(token_id: AccountId, balance: Balance) -> AssetView {
         balance,
         shares: U128(0),
         apr: Default::default(),
+        value: None,
+        accrued_interest: None,
     }
 }
 
@@ -876,4 +1496,12 @@ pub fn almost_eq(a: u128, b: u128, prec: u32) {
         "{}",
         format!("Expected {} to eq {}, with precision {}", a, b, prec)
     );
-}
\ No newline at end of file
+}
+
+pub fn assert_failure(res: ExecutionResult, message: &str) {
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail with {}", message),
+    };
+    assert!(err.contains(message), "{}", err);
+}
@@ -0,0 +1,266 @@
+use super::*;
+use near_sdk_sim::transaction::ExecutionStatus;
+
+/// The default price of NEAR, 10$.
+pub const SCENARIO_WNEAR_PRICE: Balance = 100000;
+
+/// A sequence of the protocol steps of multiple users with the assertions of the state between
+/// them. Every step is executed right away and has to succeed, unless it's preceded by
+/// `expect_failure`. The steps that call the oracle use the current prices of the scenario, which
+/// are changed with `set_wnear_price` and `set_neth_price`. The amounts are in the decimals of the
+/// token, and the asserted balances are the inner balances with the extra decimals, like in the
+/// account views.
+///
+/// ```ignore
+/// Scenario::new(&e, &tokens)
+///     .supply_collateral(&users.alice, &tokens.nusdc, d(1000, 6))
+///     .borrow(&users.alice, &tokens.wnear, d(50, 24))
+///     .set_wnear_price(120000)
+///     .liquidate(
+///         &users.bob,
+///         &users.alice,
+///         &[(&tokens.wnear, d(49, 23))],
+///         &[(&tokens.nusdc, d(60, 18))],
+///     )
+///     .assert_borrowed(&users.alice, &tokens.wnear, d(451, 23));
+/// ```
+pub struct Scenario<'a> {
+    pub e: &'a Env,
+    pub tokens: &'a Tokens,
+    wnear_price: Option<Balance>,
+    neth_price: Option<Balance>,
+    expected_failure: Option<String>,
+    step: usize,
+}
+
+impl<'a> Scenario<'a> {
+    pub fn new(e: &'a Env, tokens: &'a Tokens) -> Self {
+        Self {
+            e,
+            tokens,
+            wnear_price: Some(SCENARIO_WNEAR_PRICE),
+            neth_price: None,
+            expected_failure: None,
+            step: 0,
+        }
+    }
+
+    pub fn price_data(&self) -> PriceData {
+        price_data(self.tokens, self.wnear_price, self.neth_price)
+    }
+
+    /// Checks the outcome of the step against the expected failure, if any.
+    fn check(mut self, name: &str, res: ExecutionResult) -> Self {
+        self.step += 1;
+        let expected_failure = self.expected_failure.take();
+        match (res.status(), expected_failure) {
+            (ExecutionStatus::Failure(err), Some(expected)) => {
+                let err = err.to_string();
+                assert!(
+                    err.contains(&expected),
+                    "Step {} ({}) failed with {:?} instead of {:?}",
+                    self.step,
+                    name,
+                    err,
+                    expected
+                );
+            }
+            (ExecutionStatus::Failure(err), None) => {
+                panic!("Step {} ({}) failed: {}", self.step, name, err)
+            }
+            (_, Some(expected)) => {
+                panic!(
+                    "Step {} ({}) succeeded, but it should fail with {:?}",
+                    self.step, name, expected
+                )
+            }
+            (_, None) => {}
+        }
+        self
+    }
+
+    /// Expects the next step to fail with the error containing the given message. The deposits
+    /// are refunded instead of failing, so their outcome is asserted with the balances instead.
+    pub fn expect_failure(mut self, message: &str) -> Self {
+        self.expected_failure = Some(message.to_string());
+        self
+    }
+
+    pub fn set_wnear_price(mut self, multiplier: Balance) -> Self {
+        self.wnear_price = Some(multiplier);
+        self
+    }
+
+    pub fn set_neth_price(mut self, multiplier: Balance) -> Self {
+        self.neth_price = Some(multiplier);
+        self
+    }
+
+    pub fn skip_days(self, days: u32) -> Self {
+        self.e.skip_time(days * ONE_DAY_SEC);
+        self
+    }
+
+    pub fn deposit(self, user: &UserAccount, token: &UserAccount, amount: Balance) -> Self {
+        let res = self.e.contract_ft_transfer_call(token, user, amount, "");
+        self.check("deposit", res)
+    }
+
+    pub fn supply_collateral(
+        self,
+        user: &UserAccount,
+        token: &UserAccount,
+        amount: Balance,
+    ) -> Self {
+        let res = self.e.supply_to_collateral(user, token, amount);
+        self.check("supply_collateral", res)
+    }
+
+    /// Deposits the amount and repays the debt of the token with the supplied balance.
+    pub fn repay(self, user: &UserAccount, token: &UserAccount, amount: Balance) -> Self {
+        let res = self.e.deposit_with_actions(
+            user,
+            token,
+            amount,
+            vec![Action::Repay(AssetAmount {
+                token_id: token.account_id(),
+                amount: None,
+                max_amount: None,
+            })],
+            None,
+        );
+        self.check("repay", res)
+    }
+
+    pub fn borrow(self, user: &UserAccount, token: &UserAccount, amount: Balance) -> Self {
+        let res = self.e.borrow(user, token, self.price_data(), amount);
+        self.check("borrow", res)
+    }
+
+    pub fn borrow_and_withdraw(
+        self,
+        user: &UserAccount,
+        token: &UserAccount,
+        amount: Balance,
+    ) -> Self {
+        let res = self
+            .e
+            .borrow_and_withdraw(user, token, self.price_data(), amount);
+        self.check("borrow_and_withdraw", res)
+    }
+
+    /// Liquidates the account by repaying the inner amounts of the borrowed assets and taking the
+    /// inner amounts of the collateral assets.
+    pub fn liquidate(
+        self,
+        liquidator: &UserAccount,
+        account: &UserAccount,
+        in_assets: &[(&UserAccount, Balance)],
+        out_assets: &[(&UserAccount, Balance)],
+    ) -> Self {
+        let to_asset_amounts = |assets: &[(&UserAccount, Balance)]| {
+            assets
+                .iter()
+                .map(|(token, amount)| asset_amount(token, *amount))
+                .collect()
+        };
+        let res = self.e.liquidate(
+            liquidator,
+            account,
+            self.price_data(),
+            to_asset_amounts(in_assets),
+            to_asset_amounts(out_assets),
+        );
+        self.check("liquidate", res)
+    }
+
+    pub fn force_close(self, liquidator: &UserAccount, account: &UserAccount) -> Self {
+        let res = self.e.force_close(liquidator, account, self.price_data());
+        self.check("force_close", res)
+    }
+
+    pub fn assert_supplied(
+        self,
+        user: &UserAccount,
+        token: &UserAccount,
+        balance: Balance,
+    ) -> Self {
+        let account = self.e.get_account(user);
+        assert_eq!(
+            balance_of(&account.supplied, token),
+            balance,
+            "The supplied balance after step {}",
+            self.step
+        );
+        self
+    }
+
+    pub fn assert_collateral(
+        self,
+        user: &UserAccount,
+        token: &UserAccount,
+        balance: Balance,
+    ) -> Self {
+        let account = self.e.get_account(user);
+        assert_eq!(
+            balance_of(&account.collateral, token),
+            balance,
+            "The collateral balance after step {}",
+            self.step
+        );
+        self
+    }
+
+    pub fn assert_borrowed(
+        self,
+        user: &UserAccount,
+        token: &UserAccount,
+        balance: Balance,
+    ) -> Self {
+        let account = self.e.get_account(user);
+        assert_eq!(
+            balance_of(&account.borrowed, token),
+            balance,
+            "The borrowed balance after step {}",
+            self.step
+        );
+        self
+    }
+
+    /// Asserts the borrowed balance with the accrued interest up to the given precision.
+    pub fn assert_borrowed_almost(
+        self,
+        user: &UserAccount,
+        token: &UserAccount,
+        balance: Balance,
+        prec: u32,
+    ) -> Self {
+        let account = self.e.get_account(user);
+        almost_eq(balance_of(&account.borrowed, token), balance, prec);
+        self
+    }
+
+    pub fn assert_no_debt(self, user: &UserAccount) -> Self {
+        let account = self.e.get_account(user);
+        assert!(
+            account.borrowed.is_empty(),
+            "The account has the debt after step {}: {:?}",
+            self.step,
+            account.borrowed
+        );
+        self
+    }
+
+    pub fn assert_invariants(self) -> Self {
+        self.e.assert_invariants(self.tokens);
+        self
+    }
+}
+
+/// Returns the balance of the token in the assets, or 0 if it's missing.
+fn balance_of(assets: &[AssetView], token: &UserAccount) -> Balance {
+    assets
+        .iter()
+        .find(|asset| asset.token_id == token.account_id())
+        .map_or(0, |asset| asset.balance)
+}
@@ -0,0 +1,79 @@
+mod setup;
+
+use crate::setup::*;
+use contract::{AccountExport, Asset, FarmExport, FarmId, Page};
+use near_sdk::borsh::BorshDeserialize;
+use near_sdk::json_types::Base64VecU8;
+use near_sdk::AccountId;
+use near_sdk_sim::transaction::ExecutionStatus;
+
+#[test]
+fn test_state_export() {
+    let (e, tokens, users) = basic_setup();
+
+    let farm_id = FarmId::Supplied(tokens.ndai.account_id());
+    e.add_farm(
+        farm_id.clone(),
+        &e.booster_token,
+        d(100, 18),
+        d(100, 18),
+        d(3000, 18),
+    );
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(100, 18), "")
+        .assert_success();
+
+    let page: Page<Base64VecU8> = e
+        .owner
+        .function_call(
+            e.contract.contract.export_accounts(None, None),
+            MAX_GAS.0,
+            0,
+        )
+        .unwrap_json();
+    assert!(page.next_token.is_none());
+    assert_eq!(page.items.len() as u64, page.total_count);
+    let alice = page
+        .items
+        .iter()
+        .map(|item| AccountExport::try_from_slice(&item.0).unwrap())
+        .find(|export| export.account.account_id == users.alice.account_id())
+        .unwrap();
+    assert_eq!(alice.account.supplied.len(), 1);
+    assert_eq!(alice.farms.len(), 1);
+    assert_eq!(alice.farms[0].0, farm_id);
+    assert!(alice.storage.storage_balance > 0);
+
+    let page: Page<Base64VecU8> = e
+        .owner
+        .function_call(e.contract.contract.export_farms(None, None), MAX_GAS.0, 0)
+        .unwrap_json();
+    assert_eq!(page.items.len(), 1);
+    let farm = FarmExport::try_from_slice(&page.items[0].0).unwrap();
+    assert_eq!(farm.farm_id, farm_id);
+    assert_eq!(farm.asset_farm.rewards.len(), 1);
+    assert!(farm.inactive_rewards.is_empty());
+
+    let page: Page<Base64VecU8> = e
+        .owner
+        .function_call(
+            e.contract.contract.export_assets(None, Some(2)),
+            MAX_GAS.0,
+            0,
+        )
+        .unwrap_json();
+    assert_eq!(page.items.len(), 2);
+    assert!(page.next_token.is_some());
+    let (_token_id, _asset) = <(AccountId, Asset)>::try_from_slice(&page.items[0].0).unwrap();
+
+    // The exports are only available to the owner.
+    let res = users.alice.function_call(
+        e.contract.contract.export_accounts(None, None),
+        MAX_GAS.0,
+        0,
+    );
+    let err = match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail"),
+    };
+    assert!(err.contains("Not an owner"));
+}
@@ -0,0 +1,199 @@
+mod setup;
+
+use crate::setup::*;
+
+use contract::{StorageCostEstimate, StorageReport, ESTIMATED_BYTES_PER_POSITION};
+use near_contract_standards::storage_management::StorageBalance;
+use near_sdk::env;
+use near_sdk::json_types::U128;
+use near_sdk_sim::UserAccount;
+
+#[test]
+fn test_storage_released() {
+    let (e, tokens, users) = basic_setup();
+
+    storage_deposit(
+        &users.alice,
+        &e.contract.account_id(),
+        &users.alice.account_id(),
+        d(1, 23),
+    );
+    let initial_storage_balance = e.debug_storage_balance_of(&users.alice).unwrap();
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(100, 18), "")
+        .assert_success();
+    e.contract_ft_transfer_call(&tokens.wnear, &users.alice, d(100, 24), "")
+        .assert_success();
+    let storage_balance = e.debug_storage_balance_of(&users.alice).unwrap();
+    assert!(storage_balance.available.0 < initial_storage_balance.available.0);
+
+    // Closing the positions releases their bytes back to the available storage balance.
+    e.withdraw(&users.alice, &tokens.ndai, d(100, 18))
+        .assert_success();
+    e.withdraw(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    let storage_balance = e.debug_storage_balance_of(&users.alice).unwrap();
+    assert_eq!(
+        storage_balance.available.0,
+        initial_storage_balance.available.0
+    );
+
+    let available = e.storage_balance_of(&users.alice).unwrap().available.0;
+    let res =
+        users
+            .alice
+            .function_call(e.contract.contract.storage_withdraw(None), DEFAULT_GAS.0, 1);
+    res.assert_success();
+    let storage_balance: StorageBalance = res.unwrap_json();
+    assert_eq!(storage_balance.available.0, 0);
+    assert_eq!(
+        storage_balance.total.0,
+        initial_storage_balance.total.0 - available
+    );
+}
+
+#[test]
+fn test_storage_report() {
+    let (e, tokens, users) = basic_setup();
+
+    let get_storage_report = |user: &UserAccount| -> Option<StorageReport> {
+        e.near
+            .view_method_call(e.contract.contract.get_storage_report(user.account_id()))
+            .unwrap_json()
+    };
+
+    let report = get_storage_report(&users.alice).unwrap();
+    assert_eq!(report.borrowed_bytes, 4);
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+
+    let new_report = get_storage_report(&users.alice).unwrap();
+    assert!(new_report.borrowed_bytes > report.borrowed_bytes);
+    assert!(new_report.collateral_bytes > report.collateral_bytes);
+    assert!(new_report.used_bytes > report.used_bytes);
+    // One position takes the token index, the shares and the principal.
+    assert_eq!(new_report.borrowed_bytes, 4 + 36);
+    assert_eq!(new_report.collateral_bytes, 4 + 36);
+    assert_eq!(
+        new_report.used_bytes,
+        new_report.supplied_bytes
+            + new_report.collateral_bytes
+            + new_report.borrowed_bytes
+            + new_report.farms_bytes
+            + new_report.other_bytes
+    );
+    let storage_balance = e.storage_balance_of(&users.alice).unwrap();
+    assert_eq!(new_report.total, storage_balance.total.0);
+    assert_eq!(new_report.available, storage_balance.available.0);
+    assert_eq!(new_report.locked + new_report.available, new_report.total);
+
+    assert!(get_storage_report(&e.near).is_none());
+}
+
+#[test]
+fn test_storage_needed() {
+    let (e, _tokens, users) = basic_setup();
+
+    let get_storage_needed = |user: &UserAccount, num_new_positions| -> Option<U128> {
+        e.near
+            .view_method_call(
+                e.contract
+                    .contract
+                    .get_storage_needed(user.account_id(), num_new_positions),
+            )
+            .unwrap_json()
+    };
+
+    assert_eq!(get_storage_needed(&users.alice, None), Some(U128(0)));
+
+    let storage_balance = e.debug_storage_balance_of(&users.alice).unwrap();
+    let used = storage_balance.total.0 - storage_balance.available.0;
+    let num_new_positions = 100;
+    assert_eq!(
+        get_storage_needed(&users.alice, Some(num_new_positions)),
+        Some(U128(
+            used + u128::from(num_new_positions)
+                * u128::from(ESTIMATED_BYTES_PER_POSITION)
+                * env::STORAGE_PRICE_PER_BYTE
+                - storage_balance.total.0
+        ))
+    );
+
+    // Anyone can top up the storage balance of the account.
+    let needed = get_storage_needed(&users.alice, Some(num_new_positions))
+        .unwrap()
+        .0;
+    storage_deposit(
+        &users.bob,
+        &e.contract.account_id(),
+        &users.alice.account_id(),
+        needed,
+    );
+    assert_eq!(
+        get_storage_needed(&users.alice, Some(num_new_positions)),
+        Some(U128(0))
+    );
+
+    assert!(get_storage_needed(&e.near, None).is_none());
+}
+
+#[test]
+fn test_estimate_storage_cost() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+
+    let estimate_storage_cost = |user: &UserAccount, actions| -> Option<StorageCostEstimate> {
+        e.near
+            .view_method_call(
+                e.contract
+                    .contract
+                    .estimate_storage_cost(user.account_id(), actions),
+            )
+            .unwrap_json()
+    };
+
+    // The existing collateral doesn't add a position, the borrow adds the supplied and the
+    // borrowed positions.
+    let estimate = estimate_storage_cost(
+        &users.alice,
+        vec![
+            Action::IncreaseCollateral(asset_amount(&tokens.wnear, d(1, 24))),
+            Action::Borrow(asset_amount(&tokens.ndai, d(10, 18))),
+        ],
+    )
+    .unwrap();
+    assert_eq!(estimate.num_new_positions, 2);
+    assert_eq!(estimate.bytes, 2 * ESTIMATED_BYTES_PER_POSITION);
+    assert_eq!(
+        estimate.cost,
+        u128::from(estimate.bytes) * env::STORAGE_PRICE_PER_BYTE
+    );
+
+    let storage_balance = e.debug_storage_balance_of(&users.alice).unwrap();
+    let used = storage_balance.total.0 - storage_balance.available.0;
+    assert_eq!(
+        estimate.needed,
+        (used + estimate.cost).saturating_sub(storage_balance.total.0)
+    );
+
+    let estimate = estimate_storage_cost(
+        &users.alice,
+        vec![Action::Withdraw(asset_amount(&tokens.wnear, d(1, 24)))],
+    )
+    .unwrap();
+    assert_eq!(estimate.num_new_positions, 0);
+    assert_eq!(estimate.cost, 0);
+    assert_eq!(estimate.needed, 0);
+
+    assert!(estimate_storage_cost(&e.near, vec![]).is_none());
+}
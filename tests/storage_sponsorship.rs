@@ -0,0 +1,110 @@
+mod setup;
+
+use crate::setup::*;
+use common::ONE_YOCTO;
+use contract::StorageSponsorshipView;
+use near_sdk::json_types::U128;
+use near_sdk_sim::to_yocto;
+
+fn get_storage_sponsorship(e: &Env) -> StorageSponsorshipView {
+    e.near
+        .view_method_call(e.contract.contract.get_storage_sponsorship())
+        .unwrap_json()
+}
+
+#[test]
+fn test_sponsored_storage_register() {
+    let (e, _tokens, users) = basic_setup();
+
+    assert_failure(
+        users.alice.function_call(
+            e.contract.contract.fund_storage_sponsorship(),
+            DEFAULT_GAS.0,
+            to_yocto("1"),
+        ),
+        "Not an owner",
+    );
+    e.owner
+        .function_call(
+            e.contract.contract.fund_storage_sponsorship(),
+            DEFAULT_GAS.0,
+            to_yocto("1"),
+        )
+        .assert_success();
+
+    let frank = e.near.create_user(a("frank.near"), to_yocto("100"));
+    assert_failure(
+        frank.function_call(
+            e.contract.contract.sponsored_storage_register(),
+            DEFAULT_GAS.0,
+            0,
+        ),
+        "The storage sponsorship is disabled",
+    );
+
+    let mut config = e.get_config();
+    config.max_sponsored_storage_bytes = Some(10000);
+    e.owner
+        .function_call(e.contract.contract.update_config(config), DEFAULT_GAS.0, 1)
+        .assert_success();
+
+    frank
+        .function_call(
+            e.contract.contract.sponsored_storage_register(),
+            DEFAULT_GAS.0,
+            0,
+        )
+        .assert_success();
+    let storage_balance = e.storage_balance_of(&frank).unwrap();
+    assert_eq!(storage_balance.total.0, to_yocto("0.1"));
+    // The sponsored storage balance can't be withdrawn.
+    assert_eq!(storage_balance.available.0, 0);
+    let sponsorship = get_storage_sponsorship(&e);
+    assert_eq!(sponsorship.balance, to_yocto("0.9"));
+    assert_eq!(sponsorship.total_sponsored_bytes, 10000);
+
+    assert_failure(
+        frank.function_call(
+            e.contract.contract.sponsored_storage_register(),
+            DEFAULT_GAS.0,
+            0,
+        ),
+        "The account is already registered",
+    );
+
+    // Nothing is reclaimed until the account deposits its own storage balance.
+    let reclaimed: U128 = e
+        .owner
+        .function_call(
+            e.contract
+                .contract
+                .reclaim_sponsored_storage(frank.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .unwrap_json();
+    assert_eq!(reclaimed.0, 0);
+
+    frank
+        .function_call(
+            e.contract.contract.storage_deposit(None, None),
+            DEFAULT_GAS.0,
+            to_yocto("1"),
+        )
+        .assert_success();
+    let reclaimed: U128 = e
+        .owner
+        .function_call(
+            e.contract
+                .contract
+                .reclaim_sponsored_storage(frank.account_id()),
+            DEFAULT_GAS.0,
+            ONE_YOCTO,
+        )
+        .unwrap_json();
+    assert_eq!(reclaimed.0, to_yocto("0.1"));
+    let sponsorship = get_storage_sponsorship(&e);
+    assert_eq!(sponsorship.balance, to_yocto("1"));
+    assert_eq!(sponsorship.total_sponsored_bytes, 0);
+    assert_eq!(e.storage_balance_of(&frank).unwrap().total.0, to_yocto("1"));
+}
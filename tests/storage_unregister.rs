@@ -0,0 +1,90 @@
+mod setup;
+
+use crate::setup::*;
+use near_sdk_sim::{ExecutionResult, UserAccount};
+
+fn storage_unregister(e: &Env, user: &UserAccount, force: bool) -> ExecutionResult {
+    user.function_call(
+        e.contract.contract.storage_unregister(Some(force)),
+        MAX_GAS.0,
+        1,
+    )
+}
+
+#[test]
+fn test_storage_unregister() {
+    let (e, tokens, users) = basic_setup();
+
+    storage_unregister(&e, &users.alice, false).assert_success();
+    assert!(e.storage_balance_of(&users.alice).is_none());
+    let account: Option<AccountDetailedView> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_account(users.alice.account_id(), None),
+        )
+        .unwrap_json();
+    assert!(account.is_none());
+
+    // The account is not registered anymore.
+    let res = storage_unregister(&e, &users.alice, false);
+    res.assert_success();
+    let unregistered: bool = res.unwrap_json();
+    assert!(!unregistered);
+
+    // The account can register again.
+    storage_deposit(
+        &users.alice,
+        &e.contract.account_id(),
+        &users.alice.account_id(),
+        d(1, 23),
+    );
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(100, 18), "")
+        .assert_success();
+    assert_eq!(e.get_account(&users.alice).supplied.len(), 1);
+}
+
+#[test]
+fn test_storage_unregister_force() {
+    let (e, tokens, users) = basic_setup();
+
+    let wnear_balance = e.ft_balance_of(&users.alice, &tokens.wnear);
+    let ndai_balance = e.ft_balance_of(&users.alice, &tokens.ndai);
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(100, 18), "")
+        .assert_success();
+
+    assert_failure(
+        storage_unregister(&e, &users.alice, false),
+        "The account has supplied or collateral assets or withdraw claims",
+    );
+
+    storage_unregister(&e, &users.alice, true).assert_success();
+    assert!(e.storage_balance_of(&users.alice).is_none());
+    assert_eq!(e.ft_balance_of(&users.alice, &tokens.wnear), wnear_balance);
+    assert_eq!(e.ft_balance_of(&users.alice, &tokens.ndai), ndai_balance);
+    assert_eq!(e.get_asset(&tokens.ndai).supplied.balance, 0);
+}
+
+#[test]
+fn test_storage_unregister_with_debt() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+
+    assert_failure(
+        storage_unregister(&e, &users.alice, true),
+        "The account has borrowed assets",
+    );
+    assert!(e.storage_balance_of(&users.alice).is_some());
+}
@@ -0,0 +1,101 @@
+mod setup;
+
+use crate::setup::*;
+use contract::{FarmId, Sunset, SUNSET_DELAY_SEC};
+use near_sdk::json_types::U128;
+use near_sdk_sim::transaction::ExecutionStatus;
+use near_sdk_sim::ExecutionResult;
+
+fn failure(res: ExecutionResult) -> String {
+    match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail"),
+    }
+}
+
+fn get_sunset(e: &Env) -> Option<Sunset> {
+    e.near
+        .view_method_call(e.contract.contract.get_sunset())
+        .unwrap_json()
+}
+
+#[test]
+fn test_sunset() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(100, 18),
+    )
+    .assert_success();
+
+    e.owner
+        .function_call(e.contract.contract.announce_sunset(), DEFAULT_GAS.0, 1)
+        .assert_success();
+    assert!(!get_sunset(&e).unwrap().is_executed);
+
+    let err = failure(users.bob.function_call(
+        e.contract.contract.execute_sunset(),
+        DEFAULT_GAS.0,
+        0,
+    ));
+    assert!(err.contains("The sunset delay hasn't passed"));
+
+    e.skip_time(SUNSET_DELAY_SEC);
+    users
+        .bob
+        .function_call(e.contract.contract.execute_sunset(), MAX_GAS.0, 0)
+        .assert_success();
+    assert!(get_sunset(&e).unwrap().is_executed);
+
+    // The executed sunset can't be reverted.
+    let err = failure(
+        e.owner
+            .function_call(e.contract.contract.cancel_sunset(), DEFAULT_GAS.0, 1),
+    );
+    assert!(err.contains("The sunset is already executed"));
+
+    // The deposits are refunded.
+    let nusdc_balance = e.ft_balance_of(&users.bob, &tokens.nusdc);
+    e.contract_ft_transfer_call(&tokens.nusdc, &users.bob, d(100, 6), "")
+        .assert_success();
+    assert_eq!(e.ft_balance_of(&users.bob, &tokens.nusdc), nusdc_balance);
+
+    let err = failure(e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(100, 18),
+    ));
+    assert!(err.contains("Thi asset can't be used borrowed"));
+
+    // The repayments are still accepted.
+    e.deposit_with_actions(
+        &users.alice,
+        &tokens.ndai,
+        d(50, 18),
+        vec![Action::Repay(asset_amount(&tokens.ndai, d(50, 18)))],
+        None,
+    )
+    .assert_success();
+    let account = e.get_account(&users.alice);
+    let borrowed = find_asset(&account.borrowed, &tokens.ndai.account_id()).balance;
+    assert!(borrowed < d(51, 18));
+
+    let err = failure(e.owner.function_call(
+        e.contract.contract.add_asset_farm_reward(
+            FarmId::Supplied(tokens.ndai.account_id()),
+            e.booster_token.account_id(),
+            U128(d(100, 18)),
+            U128(d(100, 18)),
+            U128(d(100, 18)),
+        ),
+        DEFAULT_GAS.0,
+        1,
+    ));
+    assert!(err.contains("The contract is sunset"));
+}
@@ -0,0 +1,157 @@
+mod setup;
+
+use crate::setup::*;
+use near_sdk_sim::transaction::ExecutionStatus;
+use near_sdk_sim::ExecutionResult;
+
+fn failure(res: ExecutionResult) -> String {
+    match res.status() {
+        ExecutionStatus::Failure(e) => e.to_string(),
+        _ => panic!("Should fail"),
+    }
+}
+
+/// Records the prices of the assets in the test oracle, with the price of wNEAR `wnear_age_sec`
+/// seconds old.
+fn record_prices(e: &Env, tokens: &Tokens, wnear_age_sec: Option<u32>) {
+    let wnear_id = tokens.wnear.account_id().to_string();
+    e.set_oracle_prices(
+        price_data(tokens, Some(100000), None)
+            .prices
+            .into_iter()
+            .map(|price| RecordedPrice {
+                age_sec: if price.asset_id == wnear_id {
+                    wnear_age_sec
+                } else {
+                    None
+                },
+                asset_id: price.asset_id,
+                price: price.price,
+            })
+            .collect(),
+    );
+}
+
+fn borrow_msg(token: &UserAccount, amount: Balance) -> PriceReceiverMsg {
+    PriceReceiverMsg::Execute {
+        actions: vec![Action::Borrow(asset_amount(token, amount))],
+        constraints: None,
+    }
+}
+
+fn borrowed(e: &Env, user: &UserAccount, token: &UserAccount) -> Balance {
+    e.get_account(user)
+        .borrowed
+        .iter()
+        .find(|asset| asset.token_id == token.account_id())
+        .map_or(0, |asset| asset.balance)
+}
+
+#[test]
+fn test_oracle_per_asset_timestamps() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.ndai, d(1000, 18))
+        .assert_success();
+
+    // The price data is as old as the oldest price.
+    record_prices(&e, &tokens, Some(30));
+    let err =
+        failure(e.oracle_call_recorded(&users.alice, None, borrow_msg(&tokens.wnear, d(10, 24))));
+    assert!(err.contains("Price data timestamp is too stale"));
+
+    record_prices(&e, &tokens, Some(10));
+    e.oracle_call_recorded(&users.alice, None, borrow_msg(&tokens.wnear, d(10, 24)))
+        .assert_success();
+    assert_eq!(borrowed(&e, &users.alice, &tokens.wnear), d(10, 24));
+}
+
+#[test]
+fn test_oracle_partial_prices() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.ndai, d(1000, 18))
+        .assert_success();
+    record_prices(&e, &tokens, None);
+    e.set_oracle_prices(vec![RecordedPrice {
+        asset_id: tokens.wnear.account_id().to_string(),
+        price: None,
+        age_sec: None,
+    }]);
+
+    let err = failure(e.oracle_call_recorded(
+        &users.alice,
+        Some(vec![
+            tokens.ndai.account_id().to_string(),
+            tokens.wnear.account_id().to_string(),
+        ]),
+        borrow_msg(&tokens.wnear, d(10, 24)),
+    ));
+    assert!(err.contains("The prices don't cover the asset"));
+
+    // The assets without the prices are not required to borrow the others.
+    e.oracle_call_recorded(
+        &users.alice,
+        Some(vec![
+            tokens.ndai.account_id().to_string(),
+            tokens.wnear.account_id().to_string(),
+        ]),
+        borrow_msg(&tokens.ndai, d(10, 18)),
+    )
+    .assert_success();
+    assert_eq!(borrowed(&e, &users.alice, &tokens.ndai), d(10, 18));
+}
+
+#[test]
+fn test_oracle_failure_injection() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.ndai, d(1000, 18))
+        .assert_success();
+    let prices = || price_data(&tokens, Some(100000), None);
+
+    e.set_oracle_failure(Some(OracleFailure::Panic));
+    let err = failure(e.borrow(&users.alice, &tokens.wnear, prices(), d(10, 24)));
+    assert!(err.contains("The oracle call failed"));
+
+    // The callback without enough gas fails without changing the account.
+    e.set_oracle_failure(Some(OracleFailure::StarveCallback));
+    let err = failure(e.borrow(&users.alice, &tokens.wnear, prices(), d(10, 24)));
+    assert!(err.contains("Exceeded the prepaid gas"));
+    assert_eq!(borrowed(&e, &users.alice, &tokens.wnear), 0);
+
+    e.set_oracle_failure(Some(OracleFailure::Delay { blocks: 3 }));
+    e.borrow(&users.alice, &tokens.wnear, prices(), d(10, 24))
+        .assert_success();
+    assert_eq!(borrowed(&e, &users.alice, &tokens.wnear), d(10, 24));
+
+    e.set_oracle_failure(None);
+    e.borrow(&users.alice, &tokens.wnear, prices(), d(10, 24))
+        .assert_success();
+    almost_eq(borrowed(&e, &users.alice, &tokens.wnear), d(20, 24), 18);
+}
+
+#[test]
+fn test_oracle_batch_call() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.ndai, d(1000, 18))
+        .assert_success();
+
+    e.oracle_batch_call(
+        &users.alice,
+        vec![
+            (
+                price_data(&tokens, Some(100000), None),
+                borrow_msg(&tokens.wnear, d(10, 24)),
+            ),
+            (
+                price_data(&tokens, Some(100000), None),
+                borrow_msg(&tokens.ndai, d(10, 18)),
+            ),
+        ],
+    )
+    .assert_success();
+    assert_eq!(borrowed(&e, &users.alice, &tokens.wnear), d(10, 24));
+    assert_eq!(borrowed(&e, &users.alice, &tokens.ndai), d(10, 18));
+}
@@ -0,0 +1,97 @@
+mod setup;
+
+use crate::setup::*;
+
+use near_sdk::serde_json::json;
+use near_sdk_sim::transaction::ExecutionStatus;
+
+/// The deposit is followed by a withdrawal of the asset that Alice doesn't have.
+fn deposit_with_failed_actions(on_failure: OnActionsFailure) -> (Env, Tokens, Users) {
+    let (e, tokens, users) = basic_setup();
+
+    let amount = d(100, 18);
+    e.deposit_with_actions(
+        &users.alice,
+        &tokens.ndai,
+        amount,
+        vec![
+            Action::IncreaseCollateral(asset_amount(&tokens.ndai, amount)),
+            Action::Withdraw(asset_amount(&tokens.neth, d(1, 18))),
+        ],
+        Some(on_failure),
+    )
+    .assert_success();
+
+    (e, tokens, users)
+}
+
+#[test]
+fn test_deposit_kept_on_failed_actions() {
+    let (e, tokens, users) = deposit_with_failed_actions(OnActionsFailure::KeepDeposit);
+
+    let account = e.get_account(&users.alice);
+    assert!(account.collateral.is_empty());
+    assert_eq!(account.supplied[0].token_id, tokens.ndai.account_id());
+    assert_eq!(account.supplied[0].balance, d(100, 18));
+    assert_eq!(
+        e.ft_balance_of(&users.alice, &tokens.ndai),
+        d(1000000 - 100, 18)
+    );
+}
+
+#[test]
+fn test_deposit_refunded_on_failed_actions() {
+    let (e, tokens, users) = deposit_with_failed_actions(OnActionsFailure::RefundDeposit);
+
+    let account = e.get_account(&users.alice);
+    assert!(account.collateral.is_empty());
+    assert!(account.supplied.is_empty());
+    assert_eq!(e.ft_balance_of(&users.alice, &tokens.ndai), d(1000000, 18));
+}
+
+#[test]
+fn test_versioned_msg() {
+    let (e, tokens, users) = basic_setup();
+
+    let amount = d(100, 18);
+    let msg = json!({
+        "v": 2,
+        "memo": "unknown fields are ignored",
+        "Execute": {
+            "actions": [{"IncreaseCollateral": {"token_id": tokens.ndai.account_id()}}],
+            "memo": "unknown fields are ignored",
+        },
+    });
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, amount, &msg.to_string())
+        .assert_success();
+
+    let account = e.get_account(&users.alice);
+    assert!(account.supplied.is_empty());
+    assert_eq!(account.collateral[0].token_id, tokens.ndai.account_id());
+    assert_eq!(account.collateral[0].balance, amount);
+}
+
+#[test]
+fn test_unsupported_msg_version() {
+    let (e, tokens, users) = basic_setup();
+
+    let msg = json!({
+        "v": 3,
+        "Execute": {"actions": []},
+    });
+    let res = e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(100, 18), &msg.to_string());
+    // The transfer succeeds with the refund, so the error is in the `ft_on_transfer` receipt.
+    let err = res
+        .promise_results()
+        .into_iter()
+        .flatten()
+        .find_map(|r| match r.status() {
+            ExecutionStatus::Failure(e) => Some(e.to_string()),
+            _ => None,
+        })
+        .expect("Should fail with unsupported version");
+    assert!(err.contains("Unsupported TokenReceiverMsg version"));
+
+    assert!(e.get_account(&users.alice).supplied.is_empty());
+    assert_eq!(e.ft_balance_of(&users.alice, &tokens.ndai), d(1000000, 18));
+}
@@ -1,7 +1,11 @@
 mod setup;
 
 use crate::setup::*;
-use near_sdk::serde_json;
+use contract::{
+    CollateralSharesBackfill, StorageMigrationPage, VersionInfo, CONFIG_VERSION, STATE_VERSION,
+};
+use near_sdk::{serde_json, AccountId};
+use std::collections::HashMap;
 
 const PREVIOUS_VERSION: &'static str = "0.6.0";
 const LATEST_VERSION: &'static str = "0.7.0";
@@ -16,6 +20,44 @@ fn test_version() {
         .unwrap_json();
 
     assert_eq!(version, LATEST_VERSION);
+
+    let state_version: u32 = e
+        .near
+        .view_method_call(e.contract.contract.get_state_version())
+        .unwrap_json();
+    assert_eq!(state_version, STATE_VERSION);
+
+    let version_info: VersionInfo = e
+        .near
+        .view_method_call(e.contract.contract.get_version_info())
+        .unwrap_json();
+    assert_eq!(version_info.version, LATEST_VERSION);
+    assert_eq!(version_info.state_version, STATE_VERSION);
+    assert_eq!(version_info.config_version, CONFIG_VERSION);
+}
+
+#[test]
+fn test_upgrade_to_same_state_version() {
+    let (e, tokens, users) = basic_setup();
+
+    let amount = d(100, 24);
+    e.contract_ft_transfer_call(&tokens.wnear, &users.alice, amount, "")
+        .assert_success();
+
+    // The current state is migrated as is.
+    e.deploy_contract_by_owner(burrowland_wasm_bytes())
+        .assert_success();
+
+    let state_version: u32 = e
+        .near
+        .view_method_call(e.contract.contract.get_state_version())
+        .unwrap_json();
+    assert_eq!(state_version, STATE_VERSION);
+    let account = e.get_account(&users.alice);
+    assert_eq!(
+        find_asset(&account.supplied, &tokens.wnear.account_id()).balance,
+        amount
+    );
 }
 
 #[test]
@@ -121,8 +163,43 @@ fn test_upgrade_by_owner() {
 
     assert_eq!(version, LATEST_VERSION);
 
+    let state_version: u32 = e
+        .near
+        .view_method_call(e.contract.contract.get_state_version())
+        .unwrap_json();
+    assert_eq!(state_version, STATE_VERSION);
+
+    // The legacy config is migrated to the current layout.
+    let version_info: VersionInfo = e
+        .near
+        .view_method_call(e.contract.contract.get_version_info())
+        .unwrap_json();
+    assert_eq!(version_info.config_version, CONFIG_VERSION);
+
+    // The collateral shares of the migrated assets are recounted before the assets can be used.
+    let backfills: HashMap<AccountId, CollateralSharesBackfill> = e
+        .near
+        .view_method_call(e.contract.contract.get_collateral_shares_backfills())
+        .unwrap_json();
+    assert!(backfills.contains_key(&tokens.wnear.account_id()));
+    for token_id in backfills.into_keys() {
+        let is_completed: bool = users
+            .bob
+            .function_call(
+                e.contract
+                    .contract
+                    .backfill_collateral_shares(token_id, 100),
+                DEFAULT_GAS.0,
+                0,
+            )
+            .unwrap_json();
+        assert!(is_completed);
+    }
+
     let asset = e.get_asset(&tokens.wnear);
     assert_eq!(asset.supplied.balance, amount);
+    assert_eq!(asset.collateral_shares.0, 0);
+    assert_eq!(asset.config.paused_actions, 0);
     assert_eq!(asset.config.net_tvl_multiplier, 10000);
 
     let account = e.get_account(&users.alice);
@@ -176,3 +253,68 @@ fn test_degrade_fails() {
 
     assert_eq!(version, "0.4.0");
 }
+
+#[test]
+fn test_migrate_account_storage() {
+    let (e, tokens, users) = basic_setup_with_contract(burrowland_previous_wasm_bytes());
+
+    let amount = d(100, 24);
+    e.contract_ft_transfer_call(&tokens.wnear, &users.alice, amount, "")
+        .assert_success();
+
+    e.deploy_contract_by_owner(burrowland_wasm_bytes())
+        .assert_success();
+
+    let before_storage_balance = e.debug_storage_balance_of(&users.alice).unwrap();
+    let num_accounts: u32 = e
+        .near
+        .view_method_call(e.contract.contract.get_num_accounts())
+        .unwrap_json();
+
+    // Anyone can migrate the accounts page by page.
+    let mut num_migrated = 0;
+    let mut token = None;
+    loop {
+        let page: StorageMigrationPage = users
+            .bob
+            .function_call(
+                e.contract.contract.migrate_account_storage(token, 2),
+                MAX_GAS.0,
+                0,
+            )
+            .unwrap_json();
+        num_migrated += page.num_migrated;
+        token = page.next_token;
+        if token.is_none() {
+            break;
+        }
+    }
+    assert_eq!(num_migrated, u64::from(num_accounts));
+
+    let after_storage_balance = e.debug_storage_balance_of(&users.alice).unwrap();
+    assert!(before_storage_balance.available.0 < after_storage_balance.available.0);
+    let account = e.get_account(&users.alice);
+    assert_eq!(
+        find_asset(&account.supplied, &tokens.wnear.account_id()).balance,
+        amount
+    );
+
+    // The migrated accounts are skipped.
+    let page: StorageMigrationPage = users
+        .bob
+        .function_call(
+            e.contract.contract.migrate_account_storage(None, 100),
+            MAX_GAS.0,
+            0,
+        )
+        .unwrap_json();
+    assert_eq!(page.num_migrated, 0);
+    assert!(page.next_token.is_none());
+    assert_eq!(
+        e.debug_storage_balance_of(&users.alice)
+            .unwrap()
+            .available
+            .0,
+        after_storage_balance.available.0
+    );
+}
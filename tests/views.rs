@@ -0,0 +1,659 @@
+mod setup;
+
+use crate::setup::*;
+
+use contract::{
+    Account, Asset, AssetDetailedView, AssetReserveView, AssetSnapshotView, BigDecimal, FarmId,
+    HealthBucket, OperationalState, Page, ProtocolOverview, Rounding, MS_PER_YEAR,
+};
+use near_sdk::borsh::BorshDeserialize;
+use near_sdk::json_types::{Base64VecU8, U128};
+use near_sdk::AccountId;
+use near_sdk_sim::UserAccount;
+
+const SEC_PER_YEAR: u32 = (MS_PER_YEAR / 1000) as u32;
+
+#[test]
+fn test_protocol_overview() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    let borrow_amount = d(200, 18);
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        borrow_amount,
+    )
+    .assert_success();
+
+    let overview: ProtocolOverview = e
+        .near
+        .view_method_call(e.contract.contract.get_protocol_overview())
+        .unwrap_json();
+    let num_accounts: u32 = e
+        .near
+        .view_method_call(e.contract.contract.get_num_accounts())
+        .unwrap_json();
+    assert_eq!(overview.num_accounts, num_accounts);
+    assert_eq!(overview.num_assets as usize, overview.assets.len());
+
+    let ndai = overview
+        .assets
+        .iter()
+        .find(|asset| asset.token_id == tokens.ndai.account_id())
+        .unwrap();
+    assert_eq!(ndai.borrowed, borrow_amount);
+    assert_eq!(ndai.supplied, borrow_amount);
+    assert!(ndai.price.is_some());
+    // 200 DAI at 1$.
+    assert_eq!(overview.total_borrowed_value, BigDecimal::from(200u128));
+    // The price of nETH is not recorded.
+    let neth = overview
+        .assets
+        .iter()
+        .find(|asset| asset.token_id == tokens.neth.account_id())
+        .unwrap();
+    assert!(neth.price.is_none());
+}
+
+#[test]
+fn test_reserves() {
+    let (e, tokens, users) = basic_setup();
+
+    e.owner
+        .function_call(
+            e.contract.contract.update_asset_rates(
+                tokens.ndai.account_id(),
+                None,
+                None,
+                None,
+                None,
+                Some(2500),
+            ),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(10000, 24))
+        .assert_success();
+    e.borrow_and_withdraw(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(1000, 18),
+    )
+    .assert_success();
+
+    e.skip_time(SEC_PER_YEAR);
+
+    let get_ndai_reserve = || -> AssetReserveView {
+        let reserves: Vec<AssetReserveView> = e
+            .near
+            .view_method_call(e.contract.contract.get_reserves())
+            .unwrap_json();
+        reserves
+            .into_iter()
+            .find(|reserve| reserve.token_id == tokens.ndai.account_id())
+            .unwrap()
+    };
+
+    // All the interest goes to the reserve, since there are no suppliers, but only the protocol
+    // fee ratio of it is counted as the protocol fees.
+    let reserve = get_ndai_reserve();
+    assert!(reserve.collected_protocol_fees > 0);
+    let interest = reserve.reserved - d(10000, 18);
+    almost_eq(reserve.collected_protocol_fees, interest / 4, 18);
+    assert_eq!(
+        reserve.pending_protocol_fees,
+        reserve.collected_protocol_fees
+    );
+    assert_eq!(reserve.withdrawn_protocol_fees, 0);
+
+    // Funding the farm rewards from the reserve withdraws the pending fees first.
+    e.add_farm(
+        FarmId::Supplied(tokens.ndai.account_id()),
+        &tokens.ndai,
+        d(1, 18),
+        d(100, 18),
+        d(1000, 18),
+    );
+    let reserve = get_ndai_reserve();
+    assert_eq!(reserve.pending_protocol_fees, 0);
+    assert_eq!(
+        reserve.withdrawn_protocol_fees,
+        reserve.collected_protocol_fees
+    );
+}
+
+#[test]
+fn test_account_values() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+
+    let account = e.get_account(&users.alice);
+    assert!(account.values.is_none());
+    assert!(account.collateral[0].value.is_none());
+
+    let account = e.get_account_with_values(&users.alice);
+    let values = account.values.unwrap();
+    // 100 NEAR at 10$.
+    assert_eq!(values.collateral, BigDecimal::from(1000u128));
+    assert_eq!(account.collateral[0].value, Some(values.collateral));
+    assert_eq!(
+        find_asset(&account.supplied, &tokens.ndai.account_id()).value,
+        Some(values.supplied)
+    );
+    assert_eq!(
+        find_asset(&account.borrowed, &tokens.ndai.account_id()).value,
+        Some(values.borrowed)
+    );
+    assert!(values.borrowed >= BigDecimal::from(200u128));
+}
+
+#[test]
+fn test_health_distribution() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+    e.supply_to_collateral(&users.bob, &tokens.wnear, d(100, 24))
+        .assert_success();
+
+    let get_distribution = |prices| -> Vec<HealthBucket> {
+        e.near
+            .view_method_call(
+                e.contract
+                    .contract
+                    .get_health_distribution(prices, None, None),
+            )
+            .unwrap_json()
+    };
+
+    // 100 NEAR at 10$ adjusted to 600$ against 200 DAI adjusted to 210.5$.
+    let buckets = get_distribution(Some(price_data(&tokens, Some(100000), None)));
+    assert_eq!(buckets.len(), 7);
+    assert!(buckets[0].min_health.is_none());
+    assert!(buckets[6].max_health.is_none());
+    assert_eq!(buckets[6].min_health, Some(20000));
+    assert_eq!(buckets.iter().map(|b| b.num_accounts).sum::<u32>(), 1);
+    assert_eq!(buckets[6].num_accounts, 1);
+    assert!(buckets[6].total_debt >= BigDecimal::from(200u128));
+
+    // 100 NEAR at 2.5$ adjusted to 150$.
+    let buckets = get_distribution(Some(price_data(&tokens, Some(25000), None)));
+    assert_eq!(buckets[0].num_accounts, 1);
+    assert_eq!(buckets[6].num_accounts, 0);
+}
+
+#[test]
+fn test_asset_suppliers_and_borrowers() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+    e.contract_ft_transfer_call(&tokens.neth, &users.bob, d(10, 18), "")
+        .assert_success();
+
+    let get_suppliers = |token: &UserAccount| -> Vec<AccountId> {
+        let page: Page<AccountId> = e
+            .near
+            .view_method_call(e.contract.contract.get_asset_suppliers(
+                token.account_id(),
+                None,
+                None,
+            ))
+            .unwrap_json();
+        page.items
+    };
+    let get_borrowers = |token: &UserAccount| -> Vec<AccountId> {
+        let page: Page<AccountId> = e
+            .near
+            .view_method_call(e.contract.contract.get_asset_borrowers(
+                token.account_id(),
+                None,
+                None,
+            ))
+            .unwrap_json();
+        page.items
+    };
+
+    assert_eq!(get_suppliers(&tokens.wnear), vec![users.alice.account_id()]);
+    assert_eq!(get_suppliers(&tokens.ndai), vec![users.alice.account_id()]);
+    assert_eq!(get_borrowers(&tokens.ndai), vec![users.alice.account_id()]);
+    assert_eq!(get_suppliers(&tokens.neth), vec![users.bob.account_id()]);
+    assert!(get_borrowers(&tokens.wnear).is_empty());
+
+    e.withdraw(&users.bob, &tokens.neth, d(10, 18))
+        .assert_success();
+    assert!(get_suppliers(&tokens.neth).is_empty());
+    e.assert_invariants(&tokens);
+}
+
+#[test]
+fn test_invariants() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(300, 18), "")
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+    e.skip_time(ONE_DAY_SEC);
+
+    let invariants: Vec<AssetInvariants> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .assert_invariants(vec![tokens.ndai.account_id()]),
+        )
+        .unwrap_json();
+    let asset = e.get_asset(&tokens.ndai);
+    assert_eq!(invariants.len(), 1);
+    assert!(invariants[0].is_valid);
+    assert!(invariants[0].is_fully_sampled);
+    assert_eq!(
+        invariants[0].sampled_supplied_shares,
+        asset.supplied.shares.0
+    );
+    assert_eq!(
+        invariants[0].sampled_borrowed_shares,
+        asset.borrowed.shares.0
+    );
+    e.assert_invariants(&tokens);
+}
+
+#[test]
+fn test_paged_views() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+
+    let num_accounts: u32 = e
+        .near
+        .view_method_call(e.contract.contract.get_num_accounts())
+        .unwrap_json();
+    let mut token = None;
+    let mut account_ids = vec![];
+    loop {
+        let page: Page<near_sdk::serde_json::Value> = e
+            .near
+            .view_method_call(e.contract.contract.get_accounts_page(token, Some(2)))
+            .unwrap_json();
+        assert_eq!(page.total_count, num_accounts as u64);
+        assert!(page.items.len() <= 2);
+        account_ids.extend(
+            page.items
+                .iter()
+                .map(|account| account["account_id"].as_str().unwrap().to_string()),
+        );
+        token = page.next_token;
+        if token.is_none() {
+            break;
+        }
+    }
+    assert_eq!(account_ids.len(), num_accounts as usize);
+    assert!(account_ids.contains(&users.alice.account_id().to_string()));
+
+    let page: Page<AssetDetailedView> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_assets_page_detailed(Some("4".to_string()), Some(4)),
+        )
+        .unwrap_json();
+    assert_eq!(page.total_count, 6);
+    assert_eq!(page.items.len(), 2);
+    assert!(page.next_token.is_none());
+
+    // The detailed accounts are streamed until the next token is `null`.
+    let mut token = None;
+    let mut accounts = vec![];
+    loop {
+        let page: Page<AccountDetailedView> = e
+            .near
+            .view_method_call(e.contract.contract.get_accounts_page_detailed(
+                token,
+                None,
+                Some(true),
+            ))
+            .unwrap_json();
+        assert!(!page.items.is_empty());
+        accounts.extend(page.items);
+        token = page.next_token;
+        if token.is_none() {
+            break;
+        }
+    }
+    assert_eq!(accounts.len(), num_accounts as usize);
+    let alice = accounts
+        .iter()
+        .find(|account| account.account_id == users.alice.account_id())
+        .unwrap();
+    assert_balances(
+        &alice.collateral,
+        &[av(tokens.wnear.account_id(), d(100, 24))],
+    );
+}
+
+#[test]
+fn test_raw_views() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+
+    let raw: Base64VecU8 = e
+        .near
+        .view_method_call(e.contract.contract.get_accounts_raw(None, None))
+        .unwrap_json();
+    let accounts: Vec<Account> = BorshDeserialize::try_from_slice(&raw.0).unwrap();
+    let num_accounts: u32 = e
+        .near
+        .view_method_call(e.contract.contract.get_num_accounts())
+        .unwrap_json();
+    assert_eq!(accounts.len(), num_accounts as usize);
+    let alice = accounts
+        .iter()
+        .find(|account| account.account_id == users.alice.account_id())
+        .unwrap();
+    assert!(alice.collateral.contains_key(&tokens.wnear.account_id()));
+
+    let raw: Base64VecU8 = e
+        .near
+        .view_method_call(e.contract.contract.get_accounts_raw(Some(1), Some(1)))
+        .unwrap_json();
+    let accounts: Vec<Account> = BorshDeserialize::try_from_slice(&raw.0).unwrap();
+    assert_eq!(accounts.len(), 1);
+
+    let raw: Base64VecU8 = e
+        .near
+        .view_method_call(e.contract.contract.get_assets_raw())
+        .unwrap_json();
+    let assets: Vec<(AccountId, Asset)> = BorshDeserialize::try_from_slice(&raw.0).unwrap();
+    assert_eq!(assets.len(), 6);
+    let (_, wnear) = assets
+        .iter()
+        .find(|(token_id, _)| token_id == &tokens.wnear.account_id())
+        .unwrap();
+    assert_eq!(wnear.supplied.balance, d(100, 24));
+}
+
+#[test]
+fn test_asset_history() {
+    let (e, tokens, users) = basic_setup();
+
+    let supply_amount = d(10000, 24);
+    e.supply_to_collateral(&users.alice, &tokens.wnear, supply_amount)
+        .assert_success();
+
+    e.skip_time(24 * 60 * 60);
+
+    let borrow_amount = d(200, 18);
+    e.borrow_and_withdraw(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        borrow_amount,
+    )
+    .assert_success();
+
+    // The snapshots are recorded when the asset was added and on the first borrow of the next day.
+    let history: Vec<AssetSnapshotView> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_asset_history(tokens.ndai.account_id(), 7),
+        )
+        .unwrap_json();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].borrowed, 0);
+    assert_eq!(history[1].borrowed, borrow_amount);
+    assert!(history[0].timestamp < history[1].timestamp);
+
+    e.skip_time(24 * 60 * 60);
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(1, 18),
+    )
+    .assert_success();
+
+    let history: Vec<AssetSnapshotView> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_asset_history(tokens.ndai.account_id(), 1),
+        )
+        .unwrap_json();
+    assert_eq!(history.len(), 1);
+    assert!(history[0].borrowed >= borrow_amount);
+    assert!(history[0].borrow_apr > BigDecimal::zero());
+}
+
+#[test]
+fn test_simulate_execute() {
+    let (e, tokens, users) = basic_setup();
+
+    let supply_amount = d(10000, 24);
+    e.supply_to_collateral(&users.alice, &tokens.wnear, supply_amount)
+        .assert_success();
+
+    let borrow_amount = d(200, 18);
+    let account: AccountDetailedView = e
+        .simulate_execute(
+            &users.alice,
+            vec![
+                Action::Borrow(asset_amount(&tokens.ndai, borrow_amount)),
+                Action::Withdraw(asset_amount(&tokens.ndai, d(50, 18))),
+            ],
+            Some(price_data(&tokens, Some(100000), None)),
+        )
+        .unwrap_json();
+    assert_eq!(account.borrowed.len(), 1);
+    assert_eq!(account.borrowed[0].balance, borrow_amount);
+    let ndai = account
+        .supplied
+        .iter()
+        .find(|asset| asset.token_id == tokens.ndai.account_id())
+        .unwrap();
+    assert_eq!(ndai.balance, d(150, 18));
+
+    // Nothing is saved.
+    let account = e.get_account(&users.alice);
+    assert!(account.borrowed.is_empty());
+    let asset = e.get_asset(&tokens.ndai);
+    assert_eq!(asset.borrowed.balance, 0);
+
+    // The account is not healthy without the collateral.
+    assert!(e
+        .simulate_execute(
+            &users.alice,
+            vec![
+                Action::Borrow(asset_amount(&tokens.ndai, borrow_amount)),
+                Action::DecreaseCollateral(asset_amount(&tokens.wnear, supply_amount)),
+            ],
+            Some(price_data(&tokens, Some(100000), None)),
+        )
+        .is_err());
+}
+
+#[test]
+fn test_shares_conversion() {
+    let (e, tokens, users) = basic_setup();
+
+    e.supply_to_collateral(&users.alice, &tokens.wnear, d(100, 24))
+        .assert_success();
+    e.borrow(
+        &users.alice,
+        &tokens.ndai,
+        price_data(&tokens, Some(100000), None),
+        d(200, 18),
+    )
+    .assert_success();
+    e.skip_time(ONE_DAY_SEC * 30);
+
+    let shares_to_amount = |shares: U128, is_borrow: bool, rounding: Option<Rounding>| -> U128 {
+        e.near
+            .view_method_call(e.contract.contract.shares_to_amount(
+                tokens.ndai.account_id(),
+                shares,
+                is_borrow,
+                rounding,
+            ))
+            .unwrap_json()
+    };
+    let amount_to_shares = |amount: U128, is_borrow: bool, rounding: Option<Rounding>| -> U128 {
+        e.near
+            .view_method_call(e.contract.contract.amount_to_shares(
+                tokens.ndai.account_id(),
+                amount,
+                is_borrow,
+                rounding,
+            ))
+            .unwrap_json()
+    };
+
+    let account = e.get_account(&users.alice);
+    let borrowed = find_asset(&account.borrowed, &tokens.ndai.account_id());
+    assert!(borrowed.balance > d(200, 18));
+    assert_eq!(
+        shares_to_amount(borrowed.shares, true, None).0,
+        borrowed.balance
+    );
+    let supplied = find_asset(&account.supplied, &tokens.ndai.account_id());
+    assert_eq!(
+        shares_to_amount(supplied.shares, false, None).0,
+        supplied.balance
+    );
+
+    // The borrowed shares of the amount round up by default and down for a repayment.
+    let amount = U128(d(100, 18) + 1);
+    let up = amount_to_shares(amount, true, None).0;
+    let down = amount_to_shares(amount, true, Some(Rounding::Down)).0;
+    assert!(up > 0 && up - down <= 1);
+    assert!(shares_to_amount(U128(up), true, None).0 >= amount.0);
+}
+
+#[test]
+fn test_operational_state() {
+    let (e, tokens, _users) = basic_setup();
+
+    let get_operational_state = || -> OperationalState {
+        e.near
+            .view_method_call(e.contract.contract.get_operational_state())
+            .unwrap_json()
+    };
+
+    let state = get_operational_state();
+    assert_eq!(state.owner_id, e.owner.account_id());
+    assert_eq!(state.assets.len(), 6);
+    assert!(state.assets.iter().all(|asset| {
+        !asset.paused_actions.contains(&PausableAction::Deposit) && !asset.flash_loan_in_progress
+    }));
+
+    e.owner
+        .function_call(
+            e.contract.contract.update_asset_paused_actions(
+                tokens.ndai.account_id(),
+                vec![PausableAction::Borrow, PausableAction::Liquidate],
+            ),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+    let asset_config = e.get_asset(&tokens.ndai).config;
+    assert_eq!(
+        asset_config.paused_actions,
+        PausableAction::Borrow.mask() | PausableAction::Liquidate.mask()
+    );
+
+    let state = get_operational_state();
+    let ndai = state
+        .assets
+        .iter()
+        .find(|asset| asset.token_id == tokens.ndai.account_id())
+        .unwrap();
+    assert_eq!(
+        ndai.paused_actions,
+        vec![PausableAction::Borrow, PausableAction::Liquidate]
+    );
+}
+
+#[test]
+fn test_get_accounts_detailed() {
+    let (e, tokens, users) = basic_setup();
+
+    let amount = d(100, 18);
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, amount, "")
+        .assert_success();
+
+    let unknown_account_id: AccountId = "unknown.near".parse().unwrap();
+    let accounts: Vec<Option<AccountDetailedView>> = e
+        .near
+        .view_method_call(e.contract.contract.get_accounts_detailed(
+            vec![
+                users.alice.account_id(),
+                unknown_account_id.clone(),
+                users.bob.account_id(),
+            ],
+            None,
+        ))
+        .unwrap_json();
+    assert_eq!(accounts.len(), 3);
+    let alice = accounts[0].as_ref().unwrap();
+    assert_eq!(alice.account_id, users.alice.account_id());
+    assert_balances(&alice.supplied, &[av(tokens.ndai.account_id(), amount)]);
+    assert!(accounts[1].is_none());
+    assert_eq!(
+        accounts[2].as_ref().unwrap().account_id,
+        users.bob.account_id()
+    );
+
+    let result = e.near.view_method_call(
+        e.contract
+            .contract
+            .get_accounts_detailed(vec![unknown_account_id; 101], None),
+    );
+    assert!(result
+        .unwrap_err()
+        .to_string()
+        .contains("Too many accounts requested"));
+}
@@ -0,0 +1,77 @@
+mod setup;
+
+use crate::setup::*;
+use near_sdk::serde_json;
+use near_sdk::serde_json::json;
+
+fn find_event(e: &Env, event: &str) -> serde_json::Value {
+    get_logs(&e.near.borrow_runtime())
+        .iter()
+        .filter(|log| log.starts_with(EVENT_JSON))
+        .map(|log| {
+            serde_json::from_str::<serde_json::Value>(&log[EVENT_JSON.len()..])
+                .expect("Failed to parse the event")
+        })
+        .find(|value| value["event"].as_str().unwrap() == event)
+        .expect("Event not found")
+}
+
+#[test]
+fn test_failed_withdraw_claim() {
+    let (e, tokens, users) = basic_setup();
+
+    let amount = d(100, 18);
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, amount, "")
+        .assert_success();
+
+    // Alice is no longer registered with the token, so the transfer fails.
+    users
+        .alice
+        .call(
+            tokens.ndai.account_id(),
+            "storage_unregister",
+            &json!({ "force": true }).to_string().into_bytes(),
+            DEFAULT_GAS.0,
+            1,
+        )
+        .assert_success();
+
+    e.withdraw(&users.alice, &tokens.ndai, amount)
+        .assert_success();
+    let data = &find_event(&e, "transfer_failed")["data"][0];
+    assert_eq!(
+        data["account_id"].as_str().unwrap(),
+        users.alice.account_id().as_str()
+    );
+    assert_eq!(data["amount"].as_str().unwrap(), amount.to_string());
+    assert_eq!(data["reason"].as_str().unwrap(), "ReceiverNotRegistered");
+    assert_eq!(data["recovery"].as_str().unwrap(), "WithdrawClaim");
+
+    let account = e.get_account(&users.alice);
+    assert!(account.supplied.is_empty());
+    assert_eq!(account.withdraw_claims.len(), 1);
+    assert_eq!(
+        account.withdraw_claims[0].token_id,
+        tokens.ndai.account_id()
+    );
+    assert_eq!(account.withdraw_claims[0].amount, amount);
+
+    // The retry fails again and the amount stays claimable.
+    e.retry_withdraw(&users.alice, &tokens.ndai)
+        .assert_success();
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.withdraw_claims.len(), 1);
+    assert_eq!(account.withdraw_claims[0].amount, amount);
+
+    ft_storage_deposit(
+        &users.alice,
+        &tokens.ndai.account_id(),
+        &users.alice.account_id(),
+    );
+    e.retry_withdraw(&users.alice, &tokens.ndai)
+        .assert_success();
+
+    let account = e.get_account(&users.alice);
+    assert!(account.withdraw_claims.is_empty());
+    assert_eq!(e.ft_balance_of(&users.alice, &tokens.ndai), amount);
+}
@@ -0,0 +1,94 @@
+mod setup;
+
+use crate::setup::*;
+use contract::WithdrawSwap;
+use near_sdk::json_types::U128;
+use near_sdk::serde_json::json;
+use near_sdk_sim::transaction::ExecutionStatus;
+
+#[test]
+fn test_withdraw_and_swap_failed_swap_is_returned() {
+    let (e, tokens, users) = basic_setup();
+
+    let amount = d(100, 18);
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, amount, "")
+        .assert_success();
+
+    let swap_route = vec![SwapAction {
+        pool_id: 0,
+        token_in: tokens.ndai.account_id(),
+        amount_in: None,
+        token_out: tokens.wnear.account_id(),
+        min_amount_out: U128(d(9, 24)),
+    }];
+    let storage_balance = e.debug_storage_balance_of(&users.alice).unwrap();
+    let res = e.withdraw_and_swap(&users.alice, &tokens.ndai, amount, swap_route, d(10, 24));
+    res.assert_success();
+    let logs = get_logs(&e.near.borrow_runtime());
+    assert!(logs
+        .iter()
+        .any(|log| log.contains("\"event\":\"withdraw_swap_started\"")
+            && log.contains("\"min_received\":\"10000000000000000000000000\"")));
+    // The exchange doesn't exist, so the swap fails and the amount is returned.
+    assert!(logs
+        .iter()
+        .any(|log| log.contains("\"event\":\"withdraw_swap_failed\"")));
+
+    let withdraw_swap: Option<WithdrawSwap> = e
+        .near
+        .view_method_call(
+            e.contract
+                .contract
+                .get_withdraw_swap(users.alice.account_id()),
+        )
+        .unwrap_json();
+    assert!(withdraw_swap.is_none());
+    // The bytes of the withdraw swap are charged and then released from the storage balance.
+    assert_eq!(
+        e.debug_storage_balance_of(&users.alice).unwrap().available,
+        storage_balance.available
+    );
+
+    let account = e.get_account(&users.alice);
+    assert_eq!(account.supplied[0].token_id, tokens.ndai.account_id());
+    assert_eq!(account.supplied[0].balance, amount);
+    assert_eq!(
+        e.ft_balance_of(&users.alice, &tokens.ndai),
+        d(1000000, 18) - amount
+    );
+
+    // There is no withdraw swap to roll back.
+    assert_failure(
+        users.bob.function_call(
+            e.contract
+                .contract
+                .clear_expired_withdraw_swap(users.alice.account_id()),
+            DEFAULT_GAS.0,
+            0,
+        ),
+        "The account doesn't have an expired withdraw swap",
+    );
+}
+
+#[test]
+fn test_withdraw_swap_output_only_from_exchange() {
+    let (e, tokens, users) = basic_setup();
+
+    let msg = json!({
+        "WithdrawSwapOutput": {
+            "account_id": users.alice.account_id(),
+        }
+    });
+    let res = e.contract_ft_transfer_call(&tokens.ndai, &users.bob, d(100, 18), &msg.to_string());
+    let err = res
+        .promise_results()
+        .into_iter()
+        .flatten()
+        .find_map(|r| match r.status() {
+            ExecutionStatus::Failure(e) => Some(e.to_string()),
+            _ => None,
+        })
+        .expect("Should fail with the exchange error");
+    assert!(err.contains("Only the exchange can transfer the withdraw swap output"));
+    assert_eq!(e.ft_balance_of(&users.bob, &tokens.ndai), d(1000000, 18));
+}
@@ -0,0 +1,94 @@
+mod setup;
+
+use crate::setup::*;
+
+/// Alice supplies 100 DAI and protects withdrawals above 10 DAI with a 1 day delay.
+fn timelock_setup() -> (Env, Tokens, Users) {
+    let (e, tokens, users) = basic_setup();
+
+    e.contract_ft_transfer_call(&tokens.ndai, &users.alice, d(100, 18), "")
+        .assert_success();
+    e.set_withdraw_timelock(&users.alice, &tokens.ndai, d(10, 18), ONE_DAY_SEC)
+        .assert_success();
+
+    (e, tokens, users)
+}
+
+#[test]
+fn test_withdraw_timelock() {
+    let (e, tokens, users) = timelock_setup();
+
+    e.withdraw(&users.alice, &tokens.ndai, d(5, 18))
+        .assert_success();
+
+    assert_failure(
+        e.withdraw(&users.alice, &tokens.ndai, d(50, 18)),
+        "The withdrawal above the timelock threshold has to be announced",
+    );
+
+    e.announce_withdraw(&users.alice, &tokens.ndai, d(50, 18))
+        .assert_success();
+    assert_failure(
+        e.withdraw(&users.alice, &tokens.ndai, d(50, 18)),
+        "The announced withdrawal is not unlocked yet",
+    );
+
+    e.skip_time(ONE_DAY_SEC);
+    assert_failure(
+        e.withdraw(&users.alice, &tokens.ndai, d(60, 18)),
+        "The withdrawal amount exceeds the announced amount",
+    );
+    e.withdraw(&users.alice, &tokens.ndai, d(50, 18))
+        .assert_success();
+
+    let timelocks = e.get_withdraw_timelocks(&users.alice);
+    assert!(timelocks[0].announced_withdraw.is_none());
+    let account = e.get_account(&users.alice);
+    almost_eq(account.supplied[0].balance, d(45, 18), 18);
+}
+
+#[test]
+fn test_withdraw_timelock_window() {
+    let (e, tokens, users) = timelock_setup();
+
+    // The withdrawals are summed up within the 24 hour window.
+    e.withdraw(&users.alice, &tokens.ndai, d(6, 18))
+        .assert_success();
+    e.withdraw(&users.alice, &tokens.ndai, d(4, 18))
+        .assert_success();
+    assert_failure(
+        e.withdraw(&users.alice, &tokens.ndai, d(1, 18)),
+        "The withdrawal above the timelock threshold has to be announced",
+    );
+
+    e.skip_time(ONE_DAY_SEC);
+    e.withdraw(&users.alice, &tokens.ndai, d(6, 18))
+        .assert_success();
+    let timelocks = e.get_withdraw_timelocks(&users.alice);
+    assert_eq!(timelocks[0].window_withdrawn, d(6, 18));
+}
+
+#[test]
+fn test_remove_withdraw_timelock() {
+    let (e, tokens, users) = timelock_setup();
+
+    assert_failure(
+        e.set_withdraw_timelock(&users.alice, &tokens.ndai, d(20, 18), ONE_DAY_SEC),
+        "The withdraw timelock can only be made stricter",
+    );
+
+    e.remove_withdraw_timelock(&users.alice, &tokens.ndai)
+        .assert_success();
+    assert_failure(
+        e.remove_withdraw_timelock(&users.alice, &tokens.ndai),
+        "The withdraw timelock can't be removed yet",
+    );
+
+    e.skip_time(ONE_DAY_SEC);
+    e.remove_withdraw_timelock(&users.alice, &tokens.ndai)
+        .assert_success();
+    assert!(e.get_withdraw_timelocks(&users.alice).is_empty());
+
+    e.withdraw(&users.alice, &tokens.ndai, d(50, 18))
+        .assert_success();
+}